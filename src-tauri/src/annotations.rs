@@ -0,0 +1,217 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::clips::{self, now_millis, present};
+use crate::errors::AppError;
+
+/// Highlight colors the reader can show
+pub const ANNOTATION_COLORS: &[&str] = &["yellow", "green", "blue", "pink", "purple"];
+const DEFAULT_COLOR: &str = "yellow";
+const MAX_SEARCH_RESULTS: u32 = 200;
+
+/// A highlight or note on a clip. `start`/`end` are character offsets into the
+/// clip's plain text; `quote` keeps the highlighted text so the annotation survives
+/// edits that shift the offsets.
+#[derive(Debug, Serialize, Clone)]
+pub struct Annotation {
+    pub id: i64,
+    pub clip_id: i64,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub quote: Option<String>,
+    pub note: Option<String>,
+    pub color: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewAnnotation {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub quote: Option<String>,
+    pub note: Option<String>,
+    pub color: Option<String>,
+}
+
+/// Partial update for `update_annotation`. Absent fields are left untouched; an
+/// explicit `null` clears the note.
+#[derive(Debug, Deserialize, Default)]
+pub struct AnnotationUpdate {
+    #[serde(default, deserialize_with = "present")]
+    pub note: Option<Option<String>>,
+    pub color: Option<String>,
+}
+
+/// A full-text match on an annotation's quote or note
+#[derive(Debug, Serialize)]
+pub struct AnnotationSearchHit {
+    pub annotation: Annotation,
+    pub clip_title: String,
+    /// Matching excerpt with matches wrapped in `<mark>`
+    pub snippet: String,
+}
+
+const ANNOTATION_COLUMNS: &str = "a.id, a.clip_id, a.start_offset, a.end_offset, a.quote, a.note, a.color, a.created_at, a.updated_at";
+
+impl Annotation {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Annotation {
+            id: row.get(0)?,
+            clip_id: row.get(1)?,
+            start: row.get(2)?,
+            end: row.get(3)?,
+            quote: row.get(4)?,
+            note: row.get(5)?,
+            color: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }
+}
+
+fn non_blank(text: Option<String>) -> Option<String> {
+    text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+fn validate_color(color: &str) -> Result<(), AppError> {
+    if !ANNOTATION_COLORS.contains(&color) {
+        return Err(AppError::validation(format!(
+            "Invalid highlight color '{}'. Must be one of: {}",
+            color,
+            ANNOTATION_COLORS.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+fn get_annotation(conn: &Connection, id: i64) -> Result<Option<Annotation>, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM annotations a WHERE a.id = ?1", ANNOTATION_COLUMNS),
+        params![id],
+        Annotation::from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read annotation: {}", e)))
+}
+
+/// A clip's annotations in reading order; notes without a range come last
+pub fn list_annotations(conn: &Connection, clip_id: i64) -> Result<Vec<Annotation>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM annotations a WHERE a.clip_id = ?1
+             ORDER BY a.start_offset IS NULL, a.start_offset, a.id",
+            ANNOTATION_COLUMNS
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let annotations = stmt
+        .query_map(params![clip_id], Annotation::from_row)
+        .map_err(|e| AppError::database(format!("Failed to list annotations: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read annotation: {}", e)))?;
+    Ok(annotations)
+}
+
+/// Private clips can't be annotated; their annotations are kept encrypted with them
+pub fn create_annotation(conn: &Connection, clip_id: i64, annotation: NewAnnotation) -> Result<Annotation, AppError> {
+    let clip =
+        clips::get_clip(conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+    if clip.private {
+        return Err(AppError::validation(format!("Clip {} is private; make it a normal clip to annotate it", clip_id)));
+    }
+    let quote = non_blank(annotation.quote);
+    let note = non_blank(annotation.note);
+    if quote.is_none() && note.is_none() {
+        return Err(AppError::validation("An annotation needs a highlighted quote or a note"));
+    }
+    match (annotation.start, annotation.end) {
+        (Some(start), Some(end)) if start < 0 || end < start => {
+            return Err(AppError::validation("Annotation range must satisfy 0 <= start <= end"));
+        }
+        (Some(_), None) | (None, Some(_)) => {
+            return Err(AppError::validation("Annotation range needs both start and end"));
+        }
+        _ => {}
+    }
+    let color = annotation.color.unwrap_or_else(|| DEFAULT_COLOR.to_string());
+    validate_color(&color)?;
+
+    let now = now_millis();
+    conn.execute(
+        "INSERT INTO annotations (clip_id, start_offset, end_offset, quote, note, color, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+        params![clip_id, annotation.start, annotation.end, quote, note, color, now],
+    )
+    .map_err(|e| AppError::database(format!("Failed to create annotation: {}", e)))?;
+
+    let id = conn.last_insert_rowid();
+    get_annotation(conn, id)?.ok_or_else(|| AppError::database(format!("Annotation {} vanished after insert", id)))
+}
+
+/// Change an annotation's note or color; the highlighted range is fixed once made
+pub fn update_annotation(conn: &Connection, id: i64, changes: AnnotationUpdate) -> Result<Annotation, AppError> {
+    let existing = get_annotation(conn, id)?.ok_or_else(|| AppError::not_found(format!("Annotation {} not found", id)))?;
+    let note = match changes.note {
+        Some(note) => non_blank(note),
+        None => existing.note,
+    };
+    if existing.quote.is_none() && note.is_none() {
+        return Err(AppError::validation("An annotation needs a highlighted quote or a note"));
+    }
+    let color = changes.color.unwrap_or(existing.color);
+    validate_color(&color)?;
+
+    conn.execute(
+        "UPDATE annotations SET note = ?1, color = ?2, updated_at = ?3 WHERE id = ?4",
+        params![note, color, now_millis(), id],
+    )
+    .map_err(|e| AppError::database(format!("Failed to update annotation: {}", e)))?;
+    get_annotation(conn, id)?.ok_or_else(|| AppError::not_found(format!("Annotation {} not found", id)))
+}
+
+pub fn delete_annotation(conn: &Connection, id: i64) -> Result<(), AppError> {
+    let deleted = conn
+        .execute("DELETE FROM annotations WHERE id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to delete annotation: {}", e)))?;
+    if deleted == 0 {
+        return Err(AppError::not_found(format!("Annotation {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Ranked full-text search over the quotes and notes of every annotation, except those
+/// of private clips
+pub fn search_annotations(conn: &Connection, query: &str, limit: u32) -> Result<Vec<AnnotationSearchHit>, AppError> {
+    let Some(match_query) = clips::fts_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    // Notes are the user's own words, so they outrank the quoted text
+    let sql = format!(
+        "SELECT {}, c.title,
+                snippet(annotations_fts, -1, '<mark>', '</mark>', '…', 24),
+                bm25(annotations_fts, 1.0, 3.0) AS score
+         FROM annotations_fts
+         JOIN annotations a ON a.id = annotations_fts.rowid
+         JOIN clips c ON c.id = a.clip_id
+         WHERE annotations_fts MATCH ?1 AND c.deleted_at IS NULL AND c.private = 0
+         ORDER BY score
+         LIMIT ?2",
+        ANNOTATION_COLUMNS
+    );
+    let column_count = ANNOTATION_COLUMNS.split(", ").count();
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| AppError::database(format!("Failed to prepare search: {}", e)))?;
+    let hits = stmt
+        .query_map(params![match_query, limit.clamp(1, MAX_SEARCH_RESULTS)], |row| {
+            Ok(AnnotationSearchHit {
+                annotation: Annotation::from_row(row)?,
+                clip_title: row.get(column_count)?,
+                snippet: row.get(column_count + 1)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to search annotations: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read search result: {}", e)))?;
+    Ok(hits)
+}
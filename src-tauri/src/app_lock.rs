@@ -0,0 +1,268 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::warn;
+
+use crate::errors::AppError;
+use crate::secrets::SecretsManager;
+use crate::settings::SettingsManager;
+
+/// Secret holding the Argon2 hash of the app lock passphrase
+pub const APP_LOCK_SECRET: &str = "app_lock_passphrase";
+/// How often the idle check runs, so the frontend hears about the lock promptly
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Serialize)]
+pub struct AppLockStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    pub idle_minutes: Option<u32>,
+    /// Whether `unlock_app` without a passphrase can ask the OS to authenticate the user
+    pub biometric_available: bool,
+}
+
+/// Gate for commands that return clip content or secrets, managed as Tauri state.
+/// Commands call `check`, which also counts as activity.
+pub struct AppLock {
+    state: Mutex<LockState>,
+}
+
+struct LockState {
+    locked: bool,
+    /// Whether `app-locked` went out since the app last locked
+    announced: bool,
+    /// None when the lock is off
+    idle_timeout: Option<Duration>,
+    last_activity: Instant,
+}
+
+impl AppLock {
+    /// With the lock on, the app starts out locked
+    pub fn new(idle_timeout: Option<Duration>) -> Self {
+        Self {
+            state: Mutex::new(LockState {
+                locked: idle_timeout.is_some(),
+                announced: idle_timeout.is_some(),
+                idle_timeout,
+                last_activity: Instant::now(),
+            }),
+        }
+    }
+
+    /// Fail with `AppError::Locked` if the app is locked or has been idle too long;
+    /// otherwise record activity
+    pub fn check(&self) -> Result<(), AppError> {
+        let mut state = self.state.lock().unwrap();
+        state.lock_if_idle();
+        if state.locked {
+            return Err(AppError::locked("LOS is locked; unlock it to continue"));
+        }
+        state.last_activity = Instant::now();
+        Ok(())
+    }
+
+    /// Record user activity in the frontend without fetching anything
+    pub fn touch(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.lock_if_idle();
+        if !state.locked {
+            state.last_activity = Instant::now();
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.lock_if_idle();
+        state.locked
+    }
+
+    /// Lock right away, if the lock is on
+    pub fn lock(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.idle_timeout.is_some() {
+            state.locked = true;
+        }
+    }
+
+    /// True once per lock, for whoever then emits `app-locked`
+    fn take_unannounced(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.lock_if_idle();
+        let unannounced = state.locked && !state.announced;
+        state.announced |= unannounced;
+        unannounced
+    }
+
+    /// Applied from settings; turning the lock off also unlocks
+    pub fn set_idle_timeout(&self, idle_timeout: Option<Duration>) {
+        let mut state = self.state.lock().unwrap();
+        if idle_timeout.is_none() {
+            state.locked = false;
+            state.announced = false;
+        }
+        state.idle_timeout = idle_timeout;
+        state.last_activity = Instant::now();
+    }
+
+    fn unlock(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.locked = false;
+        state.announced = false;
+        state.last_activity = Instant::now();
+    }
+}
+
+impl LockState {
+    fn lock_if_idle(&mut self) {
+        if self.idle_timeout.is_some_and(|idle| self.last_activity.elapsed() >= idle) {
+            self.locked = true;
+        }
+    }
+}
+
+pub fn status(app_handle: &AppHandle) -> AppLockStatus {
+    let idle_minutes = app_handle.state::<SettingsManager>().get().app_lock_idle_minutes.filter(|m| *m > 0);
+    AppLockStatus {
+        enabled: idle_minutes.is_some(),
+        locked: app_handle.state::<AppLock>().is_locked(),
+        idle_minutes,
+        biometric_available: biometric_available(),
+    }
+}
+
+/// Turn the lock on with `idle_minutes` (keeping the current passphrase unless a new one
+/// is given) or off with 0. Changing it needs the app to be unlocked.
+pub async fn configure(app_handle: &AppHandle, passphrase: Option<String>, idle_minutes: u32) -> Result<(), AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let secrets = app_handle.state::<SecretsManager>();
+    let settings = app_handle.state::<SettingsManager>();
+
+    if idle_minutes == 0 {
+        settings.update(|s| s.app_lock_idle_minutes = None)?;
+        if secrets.has_secret(APP_LOCK_SECRET).await? {
+            secrets.remove_secret(APP_LOCK_SECRET).await?;
+        }
+        return Ok(());
+    }
+
+    match passphrase {
+        Some(passphrase) if !passphrase.is_empty() => {
+            let hash = tauri::async_runtime::spawn_blocking(move || hash_passphrase(&passphrase))
+                .await
+                .map_err(|e| AppError::internal(format!("Failed to hash passphrase: {}", e)))??;
+            secrets.store_secret(APP_LOCK_SECRET.to_string(), hash).await?;
+        }
+        Some(_) => return Err(AppError::validation("Passphrase must not be empty")),
+        None if !secrets.has_secret(APP_LOCK_SECRET).await? => {
+            return Err(AppError::validation("Set a passphrase to turn the app lock on"));
+        }
+        None => {}
+    }
+    settings.update(|s| s.app_lock_idle_minutes = Some(idle_minutes))?;
+    Ok(())
+}
+
+/// Unlock with the passphrase, or without one by asking the OS to authenticate the user
+pub async fn unlock(app_handle: &AppHandle, passphrase: Option<String>) -> Result<(), AppError> {
+    let lock = app_handle.state::<AppLock>();
+    if !lock.is_locked() {
+        return Ok(());
+    }
+
+    let verified = match passphrase {
+        Some(passphrase) => {
+            let hash = app_handle.state::<SecretsManager>().get_secret(APP_LOCK_SECRET, "unlock_app").await?;
+            // Argon2 is deliberately slow; keep it off the async workers
+            tauri::async_runtime::spawn_blocking(move || verify_passphrase(&passphrase, &hash))
+                .await
+                .map_err(|e| AppError::internal(format!("Failed to check passphrase: {}", e)))??
+        }
+        None => {
+            tauri::async_runtime::spawn_blocking(authenticate_with_os)
+                .await
+                .map_err(|e| AppError::internal(format!("OS authentication failed: {}", e)))??
+        }
+    };
+    if !verified {
+        return Err(AppError::auth("Couldn't unlock LOS: authentication failed"));
+    }
+
+    lock.unlock();
+    app_handle
+        .emit("app-unlocked", ())
+        .map_err(|e| AppError::internal(format!("Failed to emit app-unlocked event: {}", e)))?;
+    Ok(())
+}
+
+/// Lock now and tell the frontend
+pub fn lock_now(app_handle: &AppHandle) -> Result<(), AppError> {
+    let lock = app_handle.state::<AppLock>();
+    lock.lock();
+    if lock.take_unannounced() {
+        app_handle
+            .emit("app-locked", ())
+            .map_err(|e| AppError::internal(format!("Failed to emit app-locked event: {}", e)))?;
+    }
+    Ok(())
+}
+
+fn hash_passphrase(passphrase: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::internal(format!("Failed to hash passphrase: {}", e)))
+}
+
+fn verify_passphrase(passphrase: &str, hash: &str) -> Result<bool, AppError> {
+    let hash =
+        PasswordHash::new(hash).map_err(|e| AppError::internal(format!("Corrupt app lock passphrase hash: {}", e)))?;
+    Ok(Argon2::default().verify_password(passphrase.as_bytes(), &hash).is_ok())
+}
+
+/// polkit asks the user to authenticate through the desktop's agent, which can use a
+/// fingerprint reader when PAM is set up for one
+#[cfg(target_os = "linux")]
+fn biometric_available() -> bool {
+    std::process::Command::new("pkcheck").arg("--version").output().is_ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn biometric_available() -> bool {
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn authenticate_with_os() -> Result<bool, AppError> {
+    let status = std::process::Command::new("pkcheck")
+        .args(["--action-id", "org.freedesktop.policykit.exec", "--allow-user-interaction", "--process"])
+        .arg(std::process::id().to_string())
+        .status()
+        .map_err(|e| AppError::internal(format!("Failed to run pkcheck: {}", e)))?;
+    Ok(status.success())
+}
+
+/// Touch ID and Windows Hello need native APIs LOS doesn't bind yet; use the passphrase
+#[cfg(not(target_os = "linux"))]
+fn authenticate_with_os() -> Result<bool, AppError> {
+    Err(AppError::validation("OS authentication isn't available here; unlock with the passphrase"))
+}
+
+/// Lock the app once it has been idle too long, even if no command notices, so the
+/// frontend can cover the window
+pub fn start_idle_check(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+            if app_handle.state::<AppLock>().take_unannounced() {
+                if let Err(e) = app_handle.emit("app-locked", ()) {
+                    warn!("Failed to emit app-locked event: {}", e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,358 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::Serialize;
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::clips::{self, now_millis};
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::http;
+
+/// Per-resource cap; larger images and stylesheets are left as remote links
+const MAX_RESOURCE_BYTES: usize = 10 * 1024 * 1024;
+/// Stop inlining after this many resources so huge pages still archive in reasonable time
+const MAX_RESOURCES: usize = 200;
+
+#[derive(Debug, Serialize)]
+pub struct ClipArchive {
+    pub clip_id: i64,
+    /// Absolute path of the self-contained HTML file
+    pub path: PathBuf,
+    pub size: u64,
+    /// Milliseconds since the epoch
+    pub archived_at: i64,
+    /// Images and stylesheets embedded into the file
+    pub inlined_resources: usize,
+}
+
+/// End of the tag starting before `from`: the byte after its closing `>`, skipping quoted values
+fn tag_end(html: &str, from: usize) -> Option<usize> {
+    let mut quote = None;
+    for (i, b) in html.bytes().enumerate().skip(from) {
+        match (quote, b) {
+            (None, b'"' | b'\'') => quote = Some(b),
+            (Some(q), b) if b == q => quote = None,
+            (None, b'>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Byte ranges of every `<name ...>` start tag, matched case-insensitively
+fn start_tags(html: &str, name: &str) -> Vec<Range<usize>> {
+    // ASCII lowercasing keeps byte offsets identical to the original
+    let lower = html.to_ascii_lowercase();
+    let needle = format!("<{}", name);
+    let mut ranges = Vec::new();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(&needle) {
+        let start = from + pos;
+        let after = start + needle.len();
+        // `<img` must not be the start of some longer tag name like `<image`
+        match lower.as_bytes().get(after) {
+            Some(b) if b.is_ascii_whitespace() || *b == b'/' || *b == b'>' => {}
+            _ => {
+                from = after;
+                continue;
+            }
+        }
+        let Some(end) = tag_end(&lower, after) else { break };
+        ranges.push(start..end);
+        from = end;
+    }
+    ranges
+}
+
+/// Attributes of a start tag as (lowercased name, value) pairs
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    let inner = tag.trim_start_matches('<').trim_end_matches('>');
+    let mut chars = inner.char_indices().peekable();
+    // Skip the tag name
+    while let Some((_, c)) = chars.peek() {
+        if c.is_whitespace() || *c == '/' {
+            break;
+        }
+        chars.next();
+    }
+
+    let mut attrs = Vec::new();
+    loop {
+        while matches!(chars.peek(), Some((_, c)) if c.is_whitespace() || *c == '/') {
+            chars.next();
+        }
+        let Some(&(name_start, _)) = chars.peek() else { break };
+        let mut name_end = inner.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() || c == '=' || c == '/' {
+                name_end = i;
+                break;
+            }
+            chars.next();
+        }
+        let name = inner[name_start..name_end].to_ascii_lowercase();
+        while matches!(chars.peek(), Some((_, c)) if c.is_whitespace()) {
+            chars.next();
+        }
+
+        let mut value = String::new();
+        if matches!(chars.peek(), Some((_, '='))) {
+            chars.next();
+            while matches!(chars.peek(), Some((_, c)) if c.is_whitespace()) {
+                chars.next();
+            }
+            match chars.peek().map(|&(_, c)| c) {
+                Some(q @ ('"' | '\'')) => {
+                    chars.next();
+                    for (_, c) in chars.by_ref() {
+                        if c == q {
+                            break;
+                        }
+                        value.push(c);
+                    }
+                }
+                _ => {
+                    while let Some(&(_, c)) = chars.peek() {
+                        if c.is_whitespace() {
+                            break;
+                        }
+                        value.push(c);
+                        chars.next();
+                    }
+                }
+            }
+        }
+        if !name.is_empty() {
+            attrs.push((name, decode_entities(&value)));
+        }
+    }
+    attrs
+}
+
+/// The handful of entities that show up in URLs
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+}
+
+fn escape_attr(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+fn attr<'a>(attrs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attrs.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+}
+
+fn render_tag(name: &str, attrs: &[(String, String)]) -> String {
+    let mut tag = format!("<{}", name);
+    for (key, value) in attrs {
+        tag.push_str(&format!(" {}=\"{}\"", key, escape_attr(value)));
+    }
+    tag.push('>');
+    tag
+}
+
+/// Drop `<script>` elements; the archive is meant to be read, not run
+fn strip_scripts(html: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let mut out = String::with_capacity(html.len());
+    let mut copied = 0;
+    for tag in start_tags(html, "script") {
+        if tag.start < copied {
+            continue;
+        }
+        let end = lower[tag.end..]
+            .find("</script")
+            .and_then(|close| tag_end(&lower, tag.end + close))
+            .unwrap_or(html.len());
+        out.push_str(&html[copied..tag.start]);
+        copied = end;
+    }
+    out.push_str(&html[copied..]);
+    out
+}
+
+/// Replace `ranges` of `html` (sorted, non-overlapping) with new text
+fn splice(html: &str, mut replacements: Vec<(Range<usize>, String)>) -> String {
+    replacements.sort_by_key(|(range, _)| range.start);
+    let mut out = String::with_capacity(html.len());
+    let mut copied = 0;
+    for (range, text) in replacements {
+        out.push_str(&html[copied..range.start]);
+        out.push_str(&text);
+        copied = range.end;
+    }
+    out.push_str(&html[copied..]);
+    out
+}
+
+/// Point relative `url(...)` references in a stylesheet at absolute URLs so fonts and
+/// background images still resolve once the CSS is inlined
+fn absolutize_css_urls(css: &str, base: &reqwest::Url) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(pos) = rest.find("url(") {
+        out.push_str(&rest[..pos + 4]);
+        rest = &rest[pos + 4..];
+        let Some(close) = rest.find(')') else { break };
+        let raw = rest[..close].trim().trim_matches(|c| c == '"' || c == '\'');
+        match base.join(raw) {
+            Ok(url) if !raw.starts_with("data:") => out.push_str(&format!("\"{}\"", url)),
+            _ => out.push_str(&rest[..close]),
+        }
+        rest = &rest[close..];
+    }
+    out.push_str(rest);
+    out
+}
+
+async fn fetch_resource(client: &reqwest::Client, url: &reqwest::Url) -> Option<(Vec<u8>, Option<String>)> {
+    let response = client.get(url.clone()).send().await.ok()?;
+    if !response.status().is_success() || response.content_length().unwrap_or(0) as usize > MAX_RESOURCE_BYTES {
+        return None;
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let bytes = response.bytes().await.ok()?;
+    if bytes.len() > MAX_RESOURCE_BYTES {
+        return None;
+    }
+    Some((bytes.to_vec(), content_type))
+}
+
+/// Turn a fetched page into a single self-contained HTML document: scripts removed,
+/// stylesheets and images inlined. Returns the document and how many resources were inlined.
+async fn build_archive(html: &str, page_url: &reqwest::Url) -> (String, usize) {
+    let client = http::client();
+    let html = strip_scripts(html);
+
+    // Resolve against an existing <base href> if the page declares one
+    let base = start_tags(&html, "base")
+        .first()
+        .and_then(|range| attr(&attributes(&html[range.clone()]), "href").map(str::to_string))
+        .and_then(|href| page_url.join(&href).ok())
+        .unwrap_or_else(|| page_url.clone());
+
+    let mut replacements = Vec::new();
+    let mut inlined = 0;
+
+    for range in start_tags(&html, "link") {
+        if inlined >= MAX_RESOURCES {
+            break;
+        }
+        let attrs = attributes(&html[range.clone()]);
+        let is_stylesheet = attr(&attrs, "rel")
+            .map(|rel| rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("stylesheet")))
+            .unwrap_or(false);
+        let Some(href) = attr(&attrs, "href").filter(|_| is_stylesheet) else { continue };
+        let Ok(url) = base.join(href) else { continue };
+        if let Some((bytes, _)) = fetch_resource(&client, &url).await {
+            let css = absolutize_css_urls(&String::from_utf8_lossy(&bytes), &url);
+            let media = attr(&attrs, "media")
+                .map(|media| format!(" media=\"{}\"", escape_attr(media)))
+                .unwrap_or_default();
+            // A literal </style> inside the CSS would end the element early
+            let css = css.replace("</style", "<\\/style");
+            replacements.push((range, format!("<style{}>\n{}\n</style>", media, css)));
+            inlined += 1;
+        }
+    }
+
+    for range in start_tags(&html, "img") {
+        if inlined >= MAX_RESOURCES {
+            break;
+        }
+        let mut attrs = attributes(&html[range.clone()]);
+        let Some(src) = attr(&attrs, "src").filter(|src| !src.starts_with("data:")) else { continue };
+        let Ok(url) = base.join(src) else { continue };
+        if let Some((bytes, content_type)) = fetch_resource(&client, &url).await {
+            let mime = content_type
+                .filter(|mime| mime.starts_with("image/"))
+                .unwrap_or_else(|| "image/*".to_string());
+            let data_uri = format!("data:{};base64,{}", mime, BASE64.encode(&bytes));
+            // srcset would make the browser go back to the network for other sizes
+            attrs.retain(|(name, _)| name != "src" && name != "srcset" && name != "loading");
+            attrs.push(("src".to_string(), data_uri));
+            replacements.push((range, render_tag("img", &attrs)));
+            inlined += 1;
+        }
+    }
+
+    let mut document = splice(&html, replacements);
+
+    // Anything not inlined (links, fonts, iframes) still resolves against the original page
+    let header = format!(
+        "<meta charset=\"utf-8\"><base href=\"{}\"><!-- Archived from {} -->",
+        escape_attr(base.as_str()),
+        escape_attr(page_url.as_str())
+    );
+    match start_tags(&document, "head").first() {
+        Some(head) => document.insert_str(head.end, &header),
+        None => document.insert_str(0, &header),
+    }
+
+    (document, inlined)
+}
+
+/// Fetch the clip's URL and save a self-contained snapshot under the archives dir
+pub async fn archive_clip(app_handle: &AppHandle, clip_id: i64) -> Result<ClipArchive, AppError> {
+    let clip = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?
+    };
+    let url = clip.url.ok_or_else(|| AppError::validation(format!("Clip {} has no URL to archive", clip_id)))?;
+    let page_url = reqwest::Url::parse(&url).map_err(|e| AppError::validation(format!("Invalid URL: {}", e)))?;
+
+    let response = http::client()
+        .get(page_url.clone())
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to fetch page: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("Failed to fetch page: HTTP {}", response.status())));
+    }
+    // Redirects may have moved us; relative links resolve against where we ended up
+    let final_url = response.url().clone();
+    let html = response
+        .text()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to read page: {}", e)))?;
+
+    let (document, inlined_resources) = build_archive(&html, &final_url).await;
+
+    let archived_at = now_millis();
+    let archives_dir = app_handle.state::<AppConfig>().archives_dir();
+    let file_name = format!("{}-{}.html", clip_id, archived_at);
+    let path = archives_dir.join(&file_name);
+    let tmp = archives_dir.join(format!(".{}.part", file_name));
+    fs::write(&tmp, document.as_bytes()).map_err(|e| AppError::internal(format!("Failed to write archive: {}", e)))?;
+    fs::rename(&tmp, &path).map_err(|e| AppError::internal(format!("Failed to write archive: {}", e)))?;
+
+    let previous = {
+        let db = app_handle.state::<Database>();
+        clips::set_archive(&db.conn()?, clip_id, &file_name, archived_at)?
+    };
+    // Only the latest snapshot is kept
+    if let Some(previous) = previous.filter(|previous| *previous != file_name) {
+        let _ = fs::remove_file(archives_dir.join(previous));
+    }
+
+    Ok(ClipArchive {
+        clip_id,
+        path,
+        size: document.len() as u64,
+        archived_at,
+        inlined_resources,
+    })
+}
@@ -0,0 +1,121 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::embeddings;
+use crate::extract::plain_text;
+use crate::llm;
+use crate::providers::{LlmError, LlmMessage};
+
+/// Characters of each retrieved clip included in the prompt
+const MAX_SOURCE_CHARS: usize = 3_000;
+const ANSWER_MAX_TOKENS: u32 = 800;
+
+/// A clip offered to the model as context, numbered as it was cited
+#[derive(Debug, Serialize)]
+pub struct AskSource {
+    /// The `[n]` marker used for this clip in the answer
+    pub index: usize,
+    pub clip_id: i64,
+    pub title: String,
+    pub url: Option<String>,
+    pub score: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AskAnswer {
+    pub answer: String,
+    /// Every clip retrieved for the question, in prompt order
+    pub sources: Vec<AskSource>,
+    /// Clips the answer actually cites, in order of first citation
+    pub cited_clip_ids: Vec<i64>,
+}
+
+/// `[n]` markers in the answer, mapped back to clip ids
+fn cited_ids(answer: &str, sources: &[AskSource]) -> Vec<i64> {
+    let mut cited = Vec::new();
+    for piece in answer.split('[').skip(1) {
+        let Some(number) = piece.split(']').next() else { continue };
+        for part in number.split(',') {
+            let clip_id = part
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| sources.iter().find(|source| source.index == n))
+                .map(|source| source.clip_id);
+            if let Some(clip_id) = clip_id {
+                if !cited.contains(&clip_id) {
+                    cited.push(clip_id);
+                }
+            }
+        }
+    }
+    cited
+}
+
+/// Answer `question` from the `k` most relevant clips, citing them as `[n]`
+pub async fn ask_clips(app_handle: &AppHandle, question: &str, k: usize) -> Result<AskAnswer, LlmError> {
+    let question = question.trim();
+    if question.is_empty() {
+        return Err("Question must not be empty".into());
+    }
+
+    let hits = embeddings::semantic_search(app_handle, question, k).await?;
+    if hits.is_empty() {
+        return Ok(AskAnswer {
+            answer: "None of your clips are indexed yet, so there is nothing to answer from.".to_string(),
+            sources: Vec::new(),
+            cited_clip_ids: Vec::new(),
+        });
+    }
+
+    let mut context = String::new();
+    let mut sources = Vec::with_capacity(hits.len());
+    for (i, hit) in hits.into_iter().enumerate() {
+        let index = i + 1;
+        let clip = hit.clip;
+        let body = clip
+            .summary
+            .clone()
+            .or_else(|| clip.content.as_deref().map(plain_text))
+            .or_else(|| clip.description.clone())
+            .unwrap_or_default();
+        let body: String = body.chars().take(MAX_SOURCE_CHARS).collect();
+        context.push_str(&format!(
+            "[{}] {}{}\n{}\n\n",
+            index,
+            clip.title,
+            clip.url.as_deref().map(|url| format!(" ({})", url)).unwrap_or_default(),
+            body
+        ));
+        sources.push(AskSource {
+            index,
+            clip_id: clip.id,
+            title: clip.title,
+            url: clip.url,
+            score: hit.score,
+        });
+    }
+
+    let messages = vec![
+        LlmMessage {
+            role: "system".to_string(),
+            content: "Answer the user's question using only the numbered clips from their library. \
+                      Cite the clips you rely on with their number in square brackets, like [1] or [2, 3]. \
+                      If the clips don't contain the answer, say so."
+                .to_string(),
+        },
+        LlmMessage {
+            role: "user".to_string(),
+            content: format!("Clips:\n\n{}Question: {}", context, question),
+        },
+    ];
+    let response = llm::complete_with_default(app_handle, "ask_clips", messages, Some(ANSWER_MAX_TOKENS)).await?;
+    let answer = response.content.trim().to_string();
+    let cited_clip_ids = cited_ids(&answer, &sources);
+
+    Ok(AskAnswer {
+        answer,
+        sources,
+        cited_clip_ids,
+    })
+}
@@ -0,0 +1,339 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::{AppHandle, Manager};
+
+use crate::clips::{self, now_millis, ClipData, ClipInsert, ClipUpdate};
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::http;
+use crate::jobs::{JobKind, JobQueue};
+use crate::providers::{send_json, LlmError};
+use crate::secrets::SecretsManager;
+use crate::settings::SettingsManager;
+
+/// Refuse to store anything larger than this
+const MAX_AUDIO_BYTES: usize = 200 * 1024 * 1024;
+/// The OpenAI transcription endpoint rejects larger uploads
+const MAX_WHISPER_API_BYTES: usize = 25 * 1024 * 1024;
+const DEFAULT_WHISPER_MODEL: &str = "whisper-1";
+const OPENAI_API_KEY_SECRET: &str = "openai_api_key";
+
+/// Audio formats we accept, by file extension
+const AUDIO_TYPES: &[(&str, &str)] = &[
+    ("mp3", "audio/mpeg"),
+    ("mpga", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("mp4", "audio/mp4"),
+    ("wav", "audio/wav"),
+    ("ogg", "audio/ogg"),
+    ("oga", "audio/ogg"),
+    ("webm", "audio/webm"),
+    ("flac", "audio/flac"),
+];
+
+/// Speech-to-text backend for audio clips
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TranscriptionEngine {
+    /// OpenAI's `whisper-1` transcription API, with the `openai_api_key` secret
+    #[default]
+    OpenAi,
+    /// A local whisper.cpp build (`whisper-cli`) and a ggml model file
+    WhisperCpp { binary: PathBuf, model: PathBuf },
+}
+
+impl TranscriptionEngine {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let TranscriptionEngine::WhisperCpp { binary, model } = self {
+            if !binary.is_absolute() || !model.is_absolute() {
+                return Err(AppError::validation("The whisper.cpp binary and model must be absolute paths"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An audio clip's file as stored on disk
+#[derive(Debug, Serialize)]
+pub struct ClipAudio {
+    pub clip_id: i64,
+    /// Absolute path of the local copy, for `convertFileSrc` in the UI
+    pub path: PathBuf,
+    /// Hex SHA-256 of the file
+    pub hash: String,
+    pub size: u64,
+    pub media_type: String,
+    /// Milliseconds since the epoch of the last transcription, if any
+    pub transcribed_at: Option<i64>,
+}
+
+/// Result of transcribing an audio clip
+#[derive(Debug, Serialize)]
+pub struct AudioTranscript {
+    pub clip_id: i64,
+    pub word_count: usize,
+}
+
+fn media_type_for(extension: &str) -> Option<&'static str> {
+    let extension = extension.to_lowercase();
+    AUDIO_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, media_type)| *media_type)
+}
+
+/// Read the audio from an http(s) URL or a local path. Returns the bytes, the URL
+/// (for remote files) and the file name.
+async fn load(source: &str) -> Result<(Vec<u8>, Option<String>, String), AppError> {
+    let source = source.trim();
+    if let Ok(url) = reqwest::Url::parse(source) {
+        if matches!(url.scheme(), "http" | "https") {
+            let name = url
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .filter(|name| !name.is_empty())
+                .unwrap_or("audio")
+                .to_string();
+            let response = http::client()
+                .get(url.clone())
+                .send()
+                .await
+                .map_err(|e| AppError::network(format!("Failed to download audio: {}", e)))?;
+            if !response.status().is_success() {
+                return Err(AppError::network(format!("Failed to download audio: HTTP {}", response.status())));
+            }
+            if response.content_length().is_some_and(|len| len as usize > MAX_AUDIO_BYTES) {
+                return Err(AppError::validation("Audio file is too large to store"));
+            }
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|e| AppError::network(format!("Failed to download audio: {}", e)))?;
+            if bytes.len() > MAX_AUDIO_BYTES {
+                return Err(AppError::validation("Audio file is too large to store"));
+            }
+            return Ok((bytes.to_vec(), Some(url.to_string()), name));
+        }
+    }
+
+    let path = PathBuf::from(source);
+    let size = fs::metadata(&path)
+        .map_err(|e| AppError::validation(format!("Failed to read {}: {}", path.display(), e)))?
+        .len();
+    if size as usize > MAX_AUDIO_BYTES {
+        return Err(AppError::validation("Audio file is too large to store"));
+    }
+    let bytes = fs::read(&path).map_err(|e| AppError::internal(format!("Failed to read {}: {}", path.display(), e)))?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "audio".to_string());
+    Ok((bytes, None, name))
+}
+
+/// Write the audio into `media_dir`, named by content hash. Returns the file name and hash.
+fn store_file(bytes: &[u8], extension: &str, media_dir: &Path) -> Result<(String, String), AppError> {
+    let hash: String = Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    let file_name = format!("{}.{}", hash, extension);
+    let path = media_dir.join(&file_name);
+    if !path.exists() {
+        let tmp = media_dir.join(format!(".{}.part", file_name));
+        let mut file = fs::File::create(&tmp).map_err(|e| AppError::internal(format!("Failed to write audio: {}", e)))?;
+        file.write_all(bytes).map_err(|e| AppError::internal(format!("Failed to write audio: {}", e)))?;
+        fs::rename(&tmp, &path).map_err(|e| AppError::internal(format!("Failed to write audio: {}", e)))?;
+    }
+    Ok((file_name, hash))
+}
+
+fn read_clip_audio(conn: &Connection, media_dir: &Path, clip_id: i64) -> Result<Option<ClipAudio>, AppError> {
+    conn.query_row(
+        "SELECT file_name, file_hash, size, media_type, transcribed_at FROM clip_audio WHERE clip_id = ?1",
+        params![clip_id],
+        |row| {
+            Ok(ClipAudio {
+                clip_id,
+                path: media_dir.join(row.get::<_, String>(0)?),
+                hash: row.get(1)?,
+                size: row.get::<_, i64>(2)? as u64,
+                media_type: row.get(3)?,
+                transcribed_at: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read audio details: {}", e)))
+}
+
+/// Clip an audio file (a voice memo, a podcast episode) from a path or http(s) URL:
+/// keep a copy in the media dir and queue its transcription. The transcript becomes
+/// the clip's content once the job finishes.
+pub async fn clip_audio(app_handle: &AppHandle, source: &str) -> Result<ClipInsert, AppError> {
+    let (bytes, url, name) = load(source).await?;
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name.as_str(), ""));
+    let extension = extension.to_lowercase();
+    let media_type = media_type_for(&extension).ok_or_else(|| {
+        let supported: Vec<&str> = AUDIO_TYPES.iter().map(|(ext, _)| *ext).collect();
+        AppError::validation(format!("Unsupported audio format; use one of {}", supported.join(", ")))
+    })?;
+    let title = if stem.trim().is_empty() { "Audio" } else { stem }.to_string();
+
+    let media_dir = app_handle.state::<AppConfig>().media_dir();
+    let size = bytes.len() as u64;
+    let (stored_name, hash) = {
+        let extension = extension.clone();
+        tauri::async_runtime::spawn_blocking(move || store_file(&bytes, &extension, &media_dir))
+            .await
+            .map_err(|e| AppError::internal(format!("Failed to store audio: {}", e)))??
+    };
+
+    let clip_data = ClipData {
+        r#type: "audio".to_string(),
+        title,
+        url,
+        content: None,
+        image_url: None,
+        description: None,
+        author: None,
+        timestamp: now_millis() as u64,
+        original_url: None,
+        language: None,
+    };
+    let inserted = crate::ingest_clip(app_handle, clip_data)?;
+    let clip_id = inserted.clip.id;
+    {
+        let db = app_handle.state::<Database>();
+        db.conn()?
+            .execute(
+                "INSERT INTO clip_audio (clip_id, file_name, file_hash, size, media_type) VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (clip_id) DO UPDATE SET
+                    file_name = excluded.file_name, file_hash = excluded.file_hash, size = excluded.size,
+                    media_type = excluded.media_type",
+                params![clip_id, stored_name, hash, size as i64, media_type],
+            )
+            .map_err(|e| AppError::database(format!("Failed to store audio details: {}", e)))?;
+    }
+    app_handle
+        .state::<JobQueue>()
+        .submit(app_handle, JobKind::TranscribeAudio { clip_id })?;
+    Ok(inserted)
+}
+
+/// The stored file of an audio clip
+pub fn get_clip_audio(app_handle: &AppHandle, clip_id: i64) -> Result<ClipAudio, AppError> {
+    let media_dir = app_handle.state::<AppConfig>().media_dir();
+    let db = app_handle.state::<Database>();
+    read_clip_audio(&db.conn()?, &media_dir, clip_id)?
+        .ok_or_else(|| AppError::not_found(format!("Clip {} has no audio file", clip_id)))
+}
+
+async fn transcribe_with_openai(app_handle: &AppHandle, audio: &ClipAudio) -> Result<String, LlmError> {
+    if audio.size as usize > MAX_WHISPER_API_BYTES {
+        return Err("Audio is larger than the 25 MB the OpenAI API accepts; use whisper.cpp for long recordings".into());
+    }
+    let api_key = app_handle
+        .state::<SecretsManager>()
+        .get_secret(OPENAI_API_KEY_SECRET, "transcribe_audio")
+        .await
+        .map_err(|e| LlmError::Auth { message: e.to_string() })?;
+    let bytes = fs::read(&audio.path).map_err(|e| format!("Failed to read audio: {}", e))?;
+    let file_name = audio
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let part = reqwest::multipart::Part::bytes(bytes)
+        .file_name(file_name)
+        .mime_str(&audio.media_type)
+        .map_err(|e| format!("Invalid audio type: {}", e))?;
+    let form = reqwest::multipart::Form::new()
+        .text("model", DEFAULT_WHISPER_MODEL)
+        .part("file", part);
+
+    let response_json = send_json(
+        http::client()
+            .post("https://api.openai.com/v1/audio/transcriptions")
+            .header("Authorization", format!("Bearer {}", api_key))
+            .multipart(form),
+    )
+    .await?;
+    Ok(response_json["text"].as_str().ok_or("No text in transcription response")?.to_string())
+}
+
+/// Run whisper.cpp with timestamps and progress output off, so stdout is the transcript
+fn transcribe_with_whisper_cpp(binary: &Path, model: &Path, audio: &Path) -> Result<String, AppError> {
+    let output = Command::new(binary)
+        .arg("-m")
+        .arg(model)
+        .arg("-f")
+        .arg(audio)
+        .args(["--no-timestamps", "--no-prints"])
+        .output()
+        .map_err(|e| AppError::internal(format!("Failed to run {}: {}", binary.display(), e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::internal(format!("whisper.cpp failed: {}", stderr.trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Transcribe an audio clip with the configured engine and store the transcript as
+/// its content, then redo tags and embeddings with the new text
+pub async fn transcribe_clip(app_handle: &AppHandle, clip_id: i64) -> Result<AudioTranscript, LlmError> {
+    let audio = get_clip_audio(app_handle, clip_id)?;
+    let engine = app_handle.state::<SettingsManager>().get().transcription_engine;
+    let transcript = match engine {
+        TranscriptionEngine::OpenAi => transcribe_with_openai(app_handle, &audio).await?,
+        TranscriptionEngine::WhisperCpp { binary, model } => {
+            let path = audio.path.clone();
+            tauri::async_runtime::spawn_blocking(move || transcribe_with_whisper_cpp(&binary, &model, &path))
+                .await
+                .map_err(|e| format!("Transcription failed: {}", e))??
+        }
+    };
+    let transcript = transcript.split_whitespace().collect::<Vec<_>>().join(" ");
+    if transcript.is_empty() {
+        return Err(format!("No speech found in clip {}", clip_id).into());
+    }
+
+    let updated = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let updated = clips::update_clip(
+            &conn,
+            clip_id,
+            ClipUpdate {
+                content: Some(Some(transcript.clone())),
+                ..Default::default()
+            },
+        )?;
+        conn.execute(
+            "UPDATE clip_audio SET transcribed_at = ?1 WHERE clip_id = ?2",
+            params![now_millis(), clip_id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to update audio details: {}", e)))?;
+        updated
+    };
+    events::emit(app_handle, "clip-updated", &updated)
+        .map_err(|e| format!("Failed to emit clip event: {}", e))?;
+
+    let settings = app_handle.state::<SettingsManager>().get();
+    let queue = app_handle.state::<JobQueue>();
+    if settings.auto_tag {
+        queue.submit(app_handle, JobKind::AutoTagClip { clip_id })?;
+    }
+    if settings.embedding_model.is_some() {
+        queue.submit(app_handle, JobKind::EmbedClips { clip_ids: vec![clip_id] })?;
+    }
+
+    Ok(AudioTranscript {
+        clip_id,
+        word_count: transcript.split_whitespace().count(),
+    })
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::clips;
+use crate::db::Database;
+use crate::events;
+use crate::llm;
+use crate::providers::{LlmError, LlmMessage};
+use crate::tags;
+
+/// Content beyond this is cut off; the start of an article is enough to classify it
+const MAX_PROMPT_CHARS: usize = 6_000;
+/// Existing tags offered to the model so it reuses them instead of inventing synonyms
+const MAX_KNOWN_TAGS: usize = 100;
+const MAX_CATEGORY_LEN: usize = 64;
+
+/// Payload of the `clip-auto-tagged` event
+#[derive(Debug, Serialize, Clone)]
+pub struct ClipAutoTagged {
+    pub clip_id: i64,
+    pub tags: Vec<String>,
+    pub category: Option<String>,
+}
+
+/// Shape we ask the model to reply with
+#[derive(Debug, Deserialize)]
+struct Suggestion {
+    #[serde(default)]
+    tags: Vec<String>,
+    category: Option<String>,
+}
+
+/// Ask the default model for 3-5 tags and a category, and store them as suggestions
+/// (`source = auto`) for the user to accept or reject
+pub async fn auto_tag_clip(app_handle: &AppHandle, clip_id: i64) -> Result<ClipAutoTagged, LlmError> {
+    let (clip, known_tags) = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let clip = clips::get_clip(&conn, clip_id)?.ok_or_else(|| format!("Clip {} not found", clip_id))?;
+        let mut known = tags::list_tags(&conn)?;
+        known.sort_by(|a, b| b.clip_count.cmp(&a.clip_count));
+        let known: Vec<String> = known.into_iter().take(MAX_KNOWN_TAGS).map(|tag| tag.name).collect();
+        (clip, known)
+    };
+
+    let body: String = clip
+        .summary
+        .as_deref()
+        .or(clip.content.as_deref())
+        .or(clip.description.as_deref())
+        .unwrap_or_default()
+        .chars()
+        .take(MAX_PROMPT_CHARS)
+        .collect();
+    let prompt = format!(
+        "Title: {}\nURL: {}\nType: {}\n\n{}\n\nExisting tags (reuse when they fit): {}",
+        clip.title,
+        clip.url.as_deref().unwrap_or("-"),
+        clip.r#type,
+        body,
+        if known_tags.is_empty() { "none".to_string() } else { known_tags.join(", ") }
+    );
+    let messages = vec![
+        LlmMessage {
+            role: "system".to_string(),
+            content: "You organize a personal library of saved web clips. Propose 3-5 short lowercase tags \
+                      and one broad category for the clip. Reply with JSON only: \
+                      {\"tags\": [\"...\"], \"category\": \"...\"}"
+                .to_string(),
+        },
+        LlmMessage {
+            role: "user".to_string(),
+            content: prompt,
+        },
+    ];
+    let response = llm::complete_with_default(app_handle, "auto_tag", messages, Some(200)).await?;
+    let suggestion: Suggestion = llm::extract_json(&response.content, "tag suggestion")?;
+
+    let proposed: Vec<String> = suggestion
+        .tags
+        .iter()
+        .filter_map(|tag| tags::normalize_tag(tag).ok())
+        .take(5)
+        .collect();
+    let category = suggestion
+        .category
+        .map(|c| c.trim().chars().take(MAX_CATEGORY_LEN).collect::<String>())
+        .filter(|c| !c.is_empty());
+
+    {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        for tag in &proposed {
+            tags::add_auto_tag(&conn, clip_id, tag)?;
+        }
+        // Don't overwrite a category the user picked or already accepted
+        if clip.category_source.as_deref() != Some("user") {
+            if let Some(category) = &category {
+                clips::set_category(&conn, clip_id, Some(category), "auto")?;
+            }
+        }
+    }
+
+    let event = ClipAutoTagged {
+        clip_id,
+        tags: proposed,
+        category,
+    };
+    events::emit(app_handle, "clip-auto-tagged", &event)
+        .map_err(|e| format!("Failed to emit auto-tag event: {}", e))?;
+    crate::emit_tags_changed(app_handle, &app_handle.state::<Database>(), clip_id)?;
+
+    Ok(event)
+}
@@ -0,0 +1,194 @@
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{error, info};
+
+use crate::clips::now_millis;
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::migrations;
+use crate::settings::SettingsManager;
+
+/// Backups kept when the user hasn't chosen a number
+pub const DEFAULT_BACKUPS_TO_KEEP: usize = 7;
+/// How often the scheduler checks whether a backup is due
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Pages copied per backup step; other connections can write between steps
+const BACKUP_PAGES_PER_STEP: std::os::raw::c_int = 256;
+const BACKUP_PREFIX: &str = "clips-";
+const BACKUP_EXTENSION: &str = "db";
+
+#[derive(Debug, Serialize)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Milliseconds since the epoch
+    pub created_at: i64,
+}
+
+/// Start the background task that takes a backup whenever the newest one is older
+/// than `backup_interval_hours`. Settings are re-read on every check, so schedule
+/// changes apply without a restart.
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let interval_hours = app_handle.state::<SettingsManager>().get().backup_interval_hours;
+            if let Some(interval_hours) = interval_hours.filter(|hours| *hours > 0) {
+                let due = match list_backups(&app_handle) {
+                    Ok(backups) => !backups
+                        .first()
+                        .is_some_and(|newest| now_millis() - newest.created_at < interval_hours as i64 * 3_600_000),
+                    Err(e) => {
+                        error!("{}", e);
+                        false
+                    }
+                };
+                if due {
+                    if let Err(e) = backup_now(&app_handle).await {
+                        error!("Scheduled backup failed: {}", e);
+                    }
+                }
+            }
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Snapshot clips.db into the backups dir with SQLite's online backup API, verify
+/// the copy, then delete the oldest backups beyond the configured number to keep
+pub async fn backup_now(app_handle: &AppHandle) -> Result<BackupInfo, AppError> {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let backups_dir = app_handle.state::<AppConfig>().backups_dir();
+        let keep = app_handle.state::<SettingsManager>().get().backups_to_keep();
+        let db = app_handle.state::<Database>();
+
+        let info = write_backup(&db, &backups_dir)?;
+        rotate(&backups_dir, keep)?;
+        Ok(info)
+    })
+    .await
+    .map_err(|e| AppError::internal(format!("Backup failed: {}", e)))?
+}
+
+/// Replace the live database with the backup at `path`. The backup is verified
+/// first and the current database is itself backed up before being overwritten.
+/// An encrypted backup must use the database's current key.
+pub async fn restore_backup(app_handle: &AppHandle, path: PathBuf) -> Result<BackupInfo, AppError> {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let backups_dir = app_handle.state::<AppConfig>().backups_dir();
+        let db = app_handle.state::<Database>();
+        let source = db.open_file(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        check_integrity(&source)?;
+
+        let safety = write_backup(&db, &backups_dir)?;
+        info!("Backed up current database to {} before restoring", safety.path.display());
+
+        // On the writer, so no other write lands halfway through the copy
+        db.write(move |conn| {
+            copy_database(&source, conn).map_err(|e| AppError::database(format!("Failed to restore backup: {}", e)))?;
+            // Backups from older versions need the newer schema
+            migrations::run(conn)?;
+            check_integrity(conn)
+        })?;
+
+        events::emit(&app_handle, "database-restored", &())
+            .map_err(|e| AppError::internal(format!("Failed to emit restore event: {}", e)))?;
+        backup_info(&path)
+    })
+    .await
+    .map_err(|e| AppError::internal(format!("Restore failed: {}", e)))?
+}
+
+/// Backups in the backups dir, newest first
+pub fn list_backups(app_handle: &AppHandle) -> Result<Vec<BackupInfo>, AppError> {
+    backups_in(&app_handle.state::<AppConfig>().backups_dir())
+}
+
+fn write_backup(db: &Database, backups_dir: &Path) -> Result<BackupInfo, AppError> {
+    fs::create_dir_all(backups_dir).map_err(|e| AppError::internal(format!("Failed to create {}: {}", backups_dir.display(), e)))?;
+    let created_at = now_millis();
+    let path = backups_dir.join(format!("{}{}.{}", BACKUP_PREFIX, created_at, BACKUP_EXTENSION));
+    // Write under a temporary name so a failed or corrupt backup is never listed
+    let partial = path.with_extension("partial");
+
+    // Opened through the database so an encrypted database gets an encrypted backup
+    let verified = db.open_file(&partial, OpenFlags::default()).and_then(|mut backup| {
+        copy_database(&db.conn()?, &mut backup).map_err(|e| AppError::database(format!("Failed to back up database: {}", e)))?;
+        check_integrity(&backup)
+    });
+    if let Err(e) = verified {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    fs::rename(&partial, &path).map_err(|e| AppError::internal(format!("Failed to save backup {}: {}", path.display(), e)))?;
+    backup_info(&path)
+}
+
+/// Copy every page of `source` into `dest` with SQLite's online backup API
+fn copy_database(source: &Connection, dest: &mut Connection) -> rusqlite::Result<()> {
+    Backup::new(source, dest)?.run_to_completion(BACKUP_PAGES_PER_STEP, Duration::ZERO, None)
+}
+
+/// Fail unless `PRAGMA integrity_check` reports `ok`
+fn check_integrity(conn: &Connection) -> Result<(), AppError> {
+    let result: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to check database integrity: {}", e)))?;
+    if result != "ok" {
+        return Err(AppError::database(format!("Database integrity check failed: {}", result)));
+    }
+    Ok(())
+}
+
+/// Delete all but the newest `keep` backups
+fn rotate(backups_dir: &Path, keep: usize) -> Result<(), AppError> {
+    for old in backups_in(backups_dir)?.into_iter().skip(keep.max(1)) {
+        fs::remove_file(&old.path).map_err(|e| AppError::internal(format!("Failed to remove old backup {}: {}", old.path.display(), e)))?;
+    }
+    Ok(())
+}
+
+fn backups_in(backups_dir: &Path) -> Result<Vec<BackupInfo>, AppError> {
+    if !backups_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(backups_dir).map_err(|e| AppError::internal(format!("Failed to read {}: {}", backups_dir.display(), e)))?;
+
+    let mut backups: Vec<BackupInfo> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == BACKUP_EXTENSION))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(BACKUP_PREFIX))
+        })
+        .filter_map(|path| backup_info(&path).ok())
+        .collect();
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(backups)
+}
+
+/// Details of a backup file; `created_at` comes from the `clips-<millis>.db` name
+fn backup_info(path: &Path) -> Result<BackupInfo, AppError> {
+    let metadata = fs::metadata(path).map_err(|e| AppError::internal(format!("Failed to read {}: {}", path.display(), e)))?;
+    let created_at = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.strip_prefix(BACKUP_PREFIX))
+        .and_then(|millis| millis.parse().ok())
+        .unwrap_or(0);
+    Ok(BackupInfo {
+        path: path.to_path_buf(),
+        size: metadata.len(),
+        created_at,
+    })
+}
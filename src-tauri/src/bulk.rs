@@ -0,0 +1,160 @@
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::clips::{self, now_millis, READ_STATES};
+use crate::collections;
+use crate::errors::AppError;
+use crate::tags;
+
+/// Upper bound on one multi-select action
+const MAX_BULK_CLIPS: usize = 10_000;
+/// Ids per `IN (...)` list, well under SQLite's bound parameter limit
+const CHUNK_SIZE: usize = 500;
+
+/// Changes `bulk_update_clips` makes to every selected clip; absent fields are left alone
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct BulkClipChanges {
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+    /// Move into this collection; an explicit `null` takes the clips out of their collection
+    #[serde(deserialize_with = "clips::present", skip_serializing_if = "Option::is_none")]
+    pub collection_id: Option<Option<i64>>,
+    /// One of `READ_STATES`
+    pub read_state: Option<String>,
+}
+
+impl BulkClipChanges {
+    fn is_empty(&self) -> bool {
+        self.add_tags.is_empty()
+            && self.remove_tags.is_empty()
+            && self.collection_id.is_none()
+            && self.read_state.is_none()
+    }
+}
+
+/// Result of a bulk action, also the payload of the `clips-updated` and `clips-deleted` events
+#[derive(Debug, Serialize)]
+pub struct BulkResult {
+    /// Clips the action applied to
+    pub ids: Vec<i64>,
+    /// Requested ids that don't exist or are in the trash
+    pub missing: Vec<i64>,
+    /// What changed, for `clips-updated`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<BulkClipChanges>,
+}
+
+fn check_ids(ids: &[i64]) -> Result<(), AppError> {
+    if ids.is_empty() {
+        return Err(AppError::validation("Select at least one clip"));
+    }
+    if ids.len() > MAX_BULK_CLIPS {
+        return Err(AppError::validation(format!("At most {} clips can be changed at once", MAX_BULK_CLIPS)));
+    }
+    Ok(())
+}
+
+/// Split `ids` into those of live clips and the rest, keeping the caller's order
+fn live_ids(conn: &Connection, ids: &[i64]) -> Result<(Vec<i64>, Vec<i64>), AppError> {
+    let mut live = HashSet::new();
+    for chunk in ids.chunks(CHUNK_SIZE) {
+        let sql = format!(
+            "SELECT id FROM clips WHERE deleted_at IS NULL AND id IN ({})",
+            vec!["?"; chunk.len()].join(", ")
+        );
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+        let found = stmt
+            .query_map(params_from_iter(chunk.iter()), |row| row.get::<_, i64>(0))
+            .map_err(|e| AppError::database(format!("Failed to read clips: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+        live.extend(found);
+    }
+    let mut seen = HashSet::new();
+    Ok(ids.iter().copied().filter(|id| seen.insert(*id)).partition(|id| live.contains(id)))
+}
+
+/// Apply `changes` to every clip in `ids` in one transaction: either all of them change
+/// or none do. Ids of missing or trashed clips are skipped and reported.
+pub fn bulk_update_clips(conn: &mut Connection, ids: &[i64], changes: BulkClipChanges) -> Result<BulkResult, AppError> {
+    check_ids(ids)?;
+    if changes.is_empty() {
+        return Err(AppError::validation("Choose at least one change to apply"));
+    }
+    if let Some(state) = &changes.read_state {
+        if !READ_STATES.contains(&state.as_str()) {
+            return Err(AppError::validation(format!(
+                "Invalid read state '{}'. Must be one of: {}",
+                state,
+                READ_STATES.join(", ")
+            )));
+        }
+    }
+    let add_tags = changes.add_tags.iter().map(|tag| tags::normalize_tag(tag)).collect::<Result<Vec<_>, _>>()?;
+    let remove_tags = changes.remove_tags.iter().map(|tag| tags::normalize_tag(tag)).collect::<Result<Vec<_>, _>>()?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+    let (live, missing) = live_ids(&tx, ids)?;
+
+    for id in &live {
+        for tag in &add_tags {
+            tags::add_tag_to_clip(&tx, *id, tag)?;
+        }
+    }
+    for tag in &remove_tags {
+        for chunk in live.chunks(CHUNK_SIZE) {
+            tags::remove_tag_from_clips(&tx, chunk, tag)?;
+        }
+    }
+    if let Some(collection_id) = changes.collection_id {
+        for chunk in live.chunks(CHUNK_SIZE) {
+            collections::move_clips(&tx, chunk, collection_id)?;
+        }
+    }
+    if let Some(state) = &changes.read_state {
+        for id in &live {
+            clips::set_read_state(&tx, *id, state)?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| AppError::database(format!("Failed to commit bulk update: {}", e)))?;
+    Ok(BulkResult {
+        ids: live,
+        missing,
+        changes: Some(changes),
+    })
+}
+
+/// Move every clip in `ids` to the trash in one transaction
+pub fn bulk_delete_clips(conn: &mut Connection, ids: &[i64]) -> Result<BulkResult, AppError> {
+    check_ids(ids)?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+    let (live, missing) = live_ids(&tx, ids)?;
+
+    let now = now_millis();
+    for chunk in live.chunks(CHUNK_SIZE) {
+        let sql = format!(
+            "UPDATE clips SET deleted_at = ? WHERE deleted_at IS NULL AND id IN ({})",
+            vec!["?"; chunk.len()].join(", ")
+        );
+        tx.execute(&sql, params_from_iter(std::iter::once(now).chain(chunk.iter().copied())))
+            .map_err(|e| AppError::database(format!("Failed to delete clips: {}", e)))?;
+    }
+
+    tx.commit()
+        .map_err(|e| AppError::database(format!("Failed to commit bulk delete: {}", e)))?;
+    Ok(BulkResult {
+        ids: live,
+        missing,
+        changes: None,
+    })
+}
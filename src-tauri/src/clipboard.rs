@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tracing::{error, warn};
+
+use crate::clips::{domain_of, now_millis, ClipData};
+use crate::errors::AppError;
+use crate::hotkey::note_from_text;
+use crate::settings::SettingsManager;
+
+/// How often the clipboard is checked for new contents
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What the monitor does with clipboard contents that pass the rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardAction {
+    /// Emit `clipboard-clip-suggested` and let the user decide
+    #[default]
+    Prompt,
+    /// Save it straight away
+    AutoSave,
+}
+
+/// Clipboard monitor configuration, stored in settings
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ClipboardMonitorSettings {
+    pub enabled: bool,
+    pub action: ClipboardAction,
+    /// Only pick up URLs on these domains (and their subdomains); empty allows any
+    pub allowed_domains: Vec<String>,
+    /// Ignore copied text shorter than this many characters; URLs are exempt
+    pub min_text_length: usize,
+}
+
+impl Default for ClipboardMonitorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            action: ClipboardAction::default(),
+            allowed_domains: Vec::new(),
+            min_text_length: 200,
+        }
+    }
+}
+
+/// Background clipboard poller, managed as Tauri state
+pub struct ClipboardMonitor {
+    enabled: Arc<AtomicBool>,
+    /// Temporarily suspended (tray "Pause clipping") without changing the setting
+    paused: Arc<AtomicBool>,
+}
+
+impl ClipboardMonitor {
+    /// Start polling; the loop idles while the monitor is disabled in settings
+    pub fn start(app_handle: AppHandle) -> Self {
+        let enabled = Arc::new(AtomicBool::new(
+            app_handle.state::<SettingsManager>().get().clipboard_monitor.enabled,
+        ));
+        let paused = Arc::new(AtomicBool::new(false));
+        let (enabled_flag, paused_flag) = (enabled.clone(), paused.clone());
+        tauri::async_runtime::spawn(async move { poll(app_handle, enabled_flag, paused_flag).await });
+        Self { enabled, paused }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn set_enabled(&self, app_handle: &AppHandle, enabled: bool) -> Result<(), AppError> {
+        app_handle
+            .state::<SettingsManager>()
+            .update(|s| s.clipboard_monitor.enabled = enabled)?;
+        self.enabled.store(enabled, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+async fn poll(app_handle: AppHandle, enabled: Arc<AtomicBool>, paused: Arc<AtomicBool>) {
+    // Whatever is on the clipboard when monitoring starts was copied before it; skip it
+    let mut last_seen: Option<String> = None;
+    let mut was_enabled = false;
+
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        // Copies made while paused are skipped too, not picked up on resume
+        if !enabled.load(Ordering::Relaxed) || paused.load(Ordering::Relaxed) {
+            was_enabled = false;
+            continue;
+        }
+
+        let Ok(text) = app_handle.clipboard().read_text() else {
+            continue;
+        };
+        if !was_enabled {
+            was_enabled = true;
+            last_seen = Some(text);
+            continue;
+        }
+        if last_seen.as_deref() == Some(text.as_str()) {
+            continue;
+        }
+        last_seen = Some(text.clone());
+
+        let settings = app_handle.state::<SettingsManager>().get().clipboard_monitor;
+        let Some(clip_data) = clip_from_text(&text, &settings) else {
+            continue;
+        };
+        match settings.action {
+            ClipboardAction::Prompt => {
+                if let Err(e) = app_handle.emit("clipboard-clip-suggested", &clip_data) {
+                    warn!("Failed to emit clipboard suggestion: {}", e);
+                }
+            }
+            ClipboardAction::AutoSave => {
+                if let Err(e) = crate::ingest_clip(&app_handle, clip_data) {
+                    error!("Failed to save clipboard clip: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Turn copied text into a clip if it passes the rules: a lone URL on an allowed
+/// domain becomes a `url` clip, a long enough block of text a `note`
+fn clip_from_text(text: &str, settings: &ClipboardMonitorSettings) -> Option<ClipData> {
+    let text = text.trim();
+    let is_url = !text.contains(char::is_whitespace) && (text.starts_with("http://") || text.starts_with("https://"));
+
+    if !is_url {
+        if text.chars().count() < settings.min_text_length.max(1) {
+            return None;
+        }
+        return Some(note_from_text(text));
+    }
+
+    let domain = domain_of(text)?;
+    let allowed = settings.allowed_domains.is_empty()
+        || settings.allowed_domains.iter().any(|allowed| {
+            let allowed = allowed.trim().to_lowercase();
+            let allowed = allowed.strip_prefix("www.").unwrap_or(&allowed);
+            domain == allowed || domain.ends_with(&format!(".{}", allowed))
+        });
+    if !allowed {
+        return None;
+    }
+
+    Some(ClipData {
+        r#type: "url".to_string(),
+        title: text.to_string(),
+        url: Some(text.to_string()),
+        content: None,
+        image_url: None,
+        description: None,
+        author: None,
+        timestamp: now_millis() as u64,
+        original_url: None,
+        language: None,
+    })
+}
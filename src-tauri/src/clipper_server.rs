@@ -0,0 +1,225 @@
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+use tokio::sync::oneshot;
+use tracing::{error, info, warn};
+
+use crate::clips::ClipData;
+use crate::errors::AppError;
+use crate::pairing::{self, PairingConfirm, PairingRequest, SignedClip};
+
+type ClipHandler = Box<dyn Fn(ClipData) -> Result<i64, AppError> + Send + Sync>;
+
+/// Connection details the browser clipper needs to reach the app. A clipper pairs
+/// through `/pair` before it can submit clips.
+#[derive(Debug, Serialize, Clone)]
+pub struct ClipperEndpoint {
+    pub port: u16,
+    pub http_url: String,
+    pub ws_url: String,
+}
+
+/// Reply sent for every submitted clip, over HTTP or WebSocket
+#[derive(Debug, Serialize)]
+struct Ack {
+    ok: bool,
+    /// Id of the stored clip
+    id: Option<i64>,
+    error: Option<String>,
+}
+
+impl Ack {
+    fn ok(id: i64) -> Self {
+        Self {
+            ok: true,
+            id: Some(id),
+            error: None,
+        }
+    }
+
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            id: None,
+            error: Some(message.into()),
+        }
+    }
+}
+
+struct ServerState {
+    app_handle: AppHandle,
+    on_clip: ClipHandler,
+}
+
+impl ServerState {
+    /// Verify the signature and hand the clip to `on_clip`
+    fn submit(&self, signed: SignedClip) -> (StatusCode, Ack) {
+        let clip = match pairing::verify(&self.app_handle, &signed, true) {
+            Ok(clip) => clip,
+            Err(e @ AppError::Auth { .. }) => {
+                warn!("Rejected clip from clipper {}: {}", signed.client_id, e);
+                return (StatusCode::UNAUTHORIZED, Ack::error(e.to_string()));
+            }
+            Err(e) => return (error_status(&e), Ack::error(e.to_string())),
+        };
+        // Invalid clips are refused outright; the clipper shows the reason
+        match (self.on_clip)(clip) {
+            Ok(id) => (StatusCode::OK, Ack::ok(id)),
+            Err(e) => (error_status(&e), Ack::error(e.to_string())),
+        }
+    }
+
+    /// `submit` on the blocking pool, since verifying and storing a clip wait on the
+    /// database writer
+    async fn submit_blocking(self: Arc<Self>, signed: SignedClip) -> (StatusCode, Ack) {
+        tauri::async_runtime::spawn_blocking(move || self.submit(signed)).await.unwrap_or_else(|e| {
+            let message = format!("Clip submission failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Ack::error(message))
+        })
+    }
+}
+
+/// Localhost HTTP + WebSocket server the browser clipper submits clips to
+pub struct ClipperServer {
+    endpoint: ClipperEndpoint,
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl ClipperServer {
+    /// Bind to a random localhost port and start serving. `on_clip` is called
+    /// for every clip signed by a paired clipper.
+    pub fn start<F>(app_handle: AppHandle, on_clip: F) -> Result<Self, AppError>
+    where
+        F: Fn(ClipData) -> Result<i64, AppError> + Send + Sync + 'static,
+    {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0))
+            .map_err(|e| AppError::internal(format!("Failed to bind clipper server: {}", e)))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| AppError::internal(format!("Failed to configure clipper server: {}", e)))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| AppError::internal(format!("Failed to read clipper server address: {}", e)))?
+            .port();
+
+        let state = Arc::new(ServerState {
+            app_handle,
+            on_clip: Box::new(on_clip),
+        });
+
+        let router = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .route("/pair", post(post_pair))
+            .route("/pair/confirm", post(post_pair_confirm))
+            .route("/clips", post(post_clip))
+            .route("/ws", get(ws_upgrade))
+            .with_state(state);
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        tauri::async_runtime::spawn(async move {
+            let listener = match tokio::net::TcpListener::from_std(listener) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    error!("Failed to start clipper server: {}", e);
+                    return;
+                }
+            };
+            let result = axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    let _ = shutdown_rx.await;
+                })
+                .await;
+            if let Err(e) = result {
+                error!("Clipper server stopped with error: {}", e);
+            }
+        });
+
+        info!("LOS Clipper server listening on 127.0.0.1:{}", port);
+
+        Ok(Self {
+            endpoint: ClipperEndpoint {
+                port,
+                http_url: format!("http://127.0.0.1:{}", port),
+                ws_url: format!("ws://127.0.0.1:{}/ws", port),
+            },
+            shutdown: Mutex::new(Some(shutdown_tx)),
+        })
+    }
+
+    pub fn endpoint(&self) -> ClipperEndpoint {
+        self.endpoint.clone()
+    }
+
+    /// Stop accepting connections and let in-flight requests finish
+    pub fn shutdown(&self) {
+        if let Some(tx) = self.shutdown.lock().unwrap().take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+fn error_status(error: &AppError) -> StatusCode {
+    match error {
+        AppError::Auth { .. } => StatusCode::UNAUTHORIZED,
+        AppError::Validation { .. } => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// `POST /pair` with `{ "name": ... }`; once the user approves it the app shows a code
+/// to enter in the clipper, which then confirms with it
+async fn post_pair(State(state): State<Arc<ServerState>>, Json(request): Json<PairingRequest>) -> Response {
+    match pairing::request_pairing(&state.app_handle, request) {
+        Ok(started) => Json(started).into_response(),
+        Err(e) => (error_status(&e), Json(Ack::error(e.to_string()))).into_response(),
+    }
+}
+
+/// `POST /pair/confirm` with `{ "pairing_id": ..., "code": ... }`; answers with the
+/// clipper's id and the token it signs submissions with
+async fn post_pair_confirm(State(state): State<Arc<ServerState>>, Json(confirm): Json<PairingConfirm>) -> Response {
+    // Saving the clipper waits on the database writer
+    let app_handle = state.app_handle.clone();
+    let confirmed = tauri::async_runtime::spawn_blocking(move || pairing::confirm_pairing(&app_handle, confirm))
+        .await
+        .unwrap_or_else(|e| Err(AppError::internal(format!("Pairing failed: {}", e))));
+    match confirmed {
+        Ok(credentials) => Json(credentials).into_response(),
+        Err(e) => (error_status(&e), Json(Ack::error(e.to_string()))).into_response(),
+    }
+}
+
+/// `POST /clips` with a SignedClip JSON body
+async fn post_clip(State(state): State<Arc<ServerState>>, Json(signed): Json<SignedClip>) -> Response {
+    let (status, ack) = state.submit_blocking(signed).await;
+    (status, Json(ack)).into_response()
+}
+
+/// `GET /ws`; each text frame is a SignedClip JSON payload and is answered with an ack
+async fn ws_upgrade(State(state): State<Arc<ServerState>>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| handle_socket(socket, state))
+}
+
+async fn handle_socket(mut socket: WebSocket, state: Arc<ServerState>) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let ack = match message {
+            Message::Text(text) => match serde_json::from_str::<SignedClip>(&text) {
+                Ok(signed) => state.clone().submit_blocking(signed).await.1,
+                Err(e) => Ack::error(format!("Invalid clip payload: {}", e)),
+            },
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let reply = serde_json::to_string(&ack).unwrap_or_default();
+        if socket.send(Message::Text(reply)).await.is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,1329 @@
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::code;
+use crate::errors::AppError;
+use crate::extract::PageMetadata;
+use crate::revisions;
+use crate::smart_collections;
+
+/// Clip types the app knows how to render
+pub const CLIP_TYPES: &[&str] = &["article", "image", "url", "note", "pdf", "video", "audio", "digest", "code"];
+/// Read-later states: `unread` -> `reading` -> `archived`
+pub const READ_STATES: &[&str] = &["unread", "reading", "archived"];
+
+const CLIP_COLUMNS: &str =
+    "id, type, title, url, content, image_url, description, author, timestamp, created_at, updated_at, domain, collection_id, summary, category, category_source, image_path, image_hash, archive_path, archived_at, normalized_url, content_hash, times_clipped, read_state, reading_progress, finished_at, deleted_at, original_url, site_name, published_at, preview_image_url, favicon_path, private, language, status_id, status_position";
+
+/// `CLIP_COLUMNS` qualified with a table alias, for queries that join other tables
+fn clip_columns(alias: &str) -> String {
+    CLIP_COLUMNS
+        .split(", ")
+        .map(|column| format!("{}.{}", alias, column))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Large text columns a listing can leave out; they come back as `null`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipField {
+    Content,
+    Summary,
+    Description,
+}
+
+impl ClipField {
+    fn column(self) -> &'static str {
+        match self {
+            ClipField::Content => "content",
+            ClipField::Summary => "summary",
+            ClipField::Description => "description",
+        }
+    }
+}
+
+/// `CLIP_COLUMNS` with each of `exclude` selected as `NULL`, so `SqliteClip::from_row` still applies
+fn masked_columns(exclude: &[ClipField]) -> String {
+    CLIP_COLUMNS
+        .split(", ")
+        .map(|column| {
+            if exclude.iter().any(|field| field.column() == column) {
+                format!("NULL AS {}", column)
+            } else {
+                column.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Page size used by `query_clips` when the caller doesn't pass one
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipData {
+    pub r#type: String, // article, image, url, note
+    pub title: String,
+    pub url: Option<String>,
+    pub content: Option<String>,
+    pub image_url: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub timestamp: u64,
+    /// The URL as clipped, when `url` was canonicalized from it (see `canonicalize`)
+    #[serde(default)]
+    pub original_url: Option<String>,
+    /// Programming language of a code clip; detected when not given (see `code`)
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SqliteClip {
+    pub id: i64,
+    pub r#type: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub content: Option<String>,
+    pub image_url: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub timestamp: i64,
+    pub created_at: String,
+    /// Milliseconds since the epoch; doubles as the optimistic concurrency token
+    pub updated_at: i64,
+    /// Host of `url` without a leading `www.`
+    pub domain: Option<String>,
+    pub collection_id: Option<i64>,
+    /// LLM-generated summary, filled in by `summarize_clip`
+    pub summary: Option<String>,
+    pub category: Option<String>,
+    /// `auto` while an LLM-proposed category awaits review, `user` once accepted or set by hand
+    pub category_source: Option<String>,
+    /// File name of the downloaded image inside the media dir
+    pub image_path: Option<String>,
+    /// Hex SHA-256 of the downloaded image
+    pub image_hash: Option<String>,
+    /// File name of the latest HTML snapshot inside the archives dir
+    pub archive_path: Option<String>,
+    /// When the snapshot was taken, in milliseconds since the epoch
+    pub archived_at: Option<i64>,
+    /// `url` reduced to a comparable form (see `normalize_url`), for duplicate detection
+    pub normalized_url: Option<String>,
+    /// Hex SHA-256 of the clip's normalized text, for duplicate detection
+    pub content_hash: Option<String>,
+    /// How many times this page has been clipped; repeats are merged into one clip
+    pub times_clipped: u32,
+    /// One of `READ_STATES`
+    pub read_state: String,
+    /// Fraction of the clip read, 0.0 to 1.0
+    pub reading_progress: f64,
+    /// When the clip was last archived, in milliseconds since the epoch
+    pub finished_at: Option<i64>,
+    /// When the clip was moved to the trash, in milliseconds since the epoch
+    pub deleted_at: Option<i64>,
+    /// The URL as clipped, before tracking parameters were stripped and redirects followed
+    pub original_url: Option<String>,
+    /// From the page's OpenGraph tags, filled in by the site metadata job
+    pub site_name: Option<String>,
+    /// Publication date as the page states it
+    pub published_at: Option<String>,
+    /// The page's OpenGraph or Twitter-card image
+    pub preview_image_url: Option<String>,
+    /// File name of the site's icon inside the favicons dir
+    pub favicon_path: Option<String>,
+    /// Content, description and summary are encrypted (see `private`) and read back as `null`
+    /// unless private clips are unlocked
+    pub private: bool,
+    /// Programming language of a code clip, e.g. `rust`
+    pub language: Option<String>,
+    /// Board column the clip is in (see `statuses`)
+    pub status_id: Option<i64>,
+    /// Order within its status, lowest first
+    pub status_position: Option<i64>,
+}
+
+impl SqliteClip {
+    /// Map a row selected with `CLIP_COLUMNS`
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(SqliteClip {
+            id: row.get(0)?,
+            r#type: row.get(1)?,
+            title: row.get(2)?,
+            url: row.get(3)?,
+            content: row.get(4)?,
+            image_url: row.get(5)?,
+            description: row.get(6)?,
+            author: row.get(7)?,
+            timestamp: row.get(8)?,
+            created_at: row.get(9)?,
+            updated_at: row.get::<_, Option<i64>>(10)?.unwrap_or(0),
+            domain: row.get(11)?,
+            collection_id: row.get(12)?,
+            summary: row.get(13)?,
+            category: row.get(14)?,
+            category_source: row.get(15)?,
+            image_path: row.get(16)?,
+            image_hash: row.get(17)?,
+            archive_path: row.get(18)?,
+            archived_at: row.get(19)?,
+            normalized_url: row.get(20)?,
+            content_hash: row.get(21)?,
+            times_clipped: row.get(22)?,
+            read_state: row.get(23)?,
+            reading_progress: row.get(24)?,
+            finished_at: row.get(25)?,
+            deleted_at: row.get(26)?,
+            original_url: row.get(27)?,
+            site_name: row.get(28)?,
+            published_at: row.get(29)?,
+            preview_image_url: row.get(30)?,
+            favicon_path: row.get(31)?,
+            private: row.get(32)?,
+            language: row.get(33)?,
+            status_id: row.get(34)?,
+            status_position: row.get(35)?,
+        })
+    }
+}
+
+/// Partial update for `update_clip`. Absent fields are left untouched; for the
+/// optional columns an explicit `null` clears the value.
+#[derive(Debug, Deserialize, Default)]
+pub struct ClipUpdate {
+    pub r#type: Option<String>,
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub content: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub image_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub author: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub language: Option<Option<String>>,
+    /// The `updated_at` the caller last saw; the update is rejected if the clip changed since
+    pub expected_updated_at: Option<i64>,
+}
+
+/// Distinguishes a field set to `null` (`Some(None)`) from a missing one (`None`)
+pub fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Host part of a URL, lowercased and without `www.`, used for domain filters
+pub fn domain_of(url: &str) -> Option<String> {
+    let host = reqwest::Url::parse(url).ok()?.host_str()?.to_lowercase();
+    Some(host.strip_prefix("www.").map(str::to_string).unwrap_or(host))
+}
+
+/// Query parameters that identify a campaign or click rather than the page
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "ref_src", "_hsenc", "_hsmi",
+];
+/// Hosts that only redirect elsewhere; clips of these are resolved to the real page
+pub const SHORTENER_HOSTS: &[&str] = &[
+    "t.co", "bit.ly", "buff.ly", "ow.ly", "tinyurl.com", "goo.gl", "lnkd.in", "dlvr.it", "trib.al", "is.gd", "rb.gy",
+];
+/// Shorter text is too generic to treat identical content as the same clip
+const MIN_HASHED_TEXT_LEN: usize = 100;
+
+/// A URL reduced so that trivially different links to one page compare equal: scheme,
+/// `www.`, fragment, tracking parameters and trailing slash are dropped and the
+/// remaining query parameters sorted. Non-web URLs are kept as they are.
+pub fn normalize_url(url: &str) -> Option<String> {
+    let mut parsed = reqwest::Url::parse(url.trim()).ok()?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Some(parsed.to_string());
+    }
+
+    let host = parsed.host_str()?.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    let mut pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    pairs.sort();
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    let port = parsed.port().map(|port| format!(":{}", port)).unwrap_or_default();
+    let path = parsed.path().trim_end_matches('/');
+    let query = parsed.query().map(|query| format!("?{}", query)).unwrap_or_default();
+    Some(format!("{}{}{}{}", host, port, path, query))
+}
+
+/// `url` without tracking parameters, keeping everything else (including the order of
+/// the other parameters) as it was. None for non-web URLs.
+pub fn strip_tracking_params(url: &str) -> Option<String> {
+    let mut parsed = reqwest::Url::parse(url.trim()).ok()?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return None;
+    }
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if pairs.len() == parsed.query_pairs().count() {
+        return Some(url.trim().to_string());
+    }
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    Some(parsed.to_string())
+}
+
+/// Whether `url` points at a link shortener from `SHORTENER_HOSTS`
+pub fn is_short_link(url: &str) -> bool {
+    domain_of(url).is_some_and(|host| SHORTENER_HOSTS.contains(&host.as_str()))
+}
+
+/// Replace the clip's URL with its canonical form, keeping what was clipped in
+/// `original_url`. Redirects are resolved later, by the `resolve_url` job.
+pub fn canonicalize(mut clip: ClipData) -> ClipData {
+    if let Some(url) = clip.url.take() {
+        match strip_tracking_params(&url) {
+            Some(canonical) if canonical != url.trim() => {
+                clip.original_url.get_or_insert(url);
+                clip.url = Some(canonical);
+            }
+            _ => clip.url = Some(url),
+        }
+    }
+    clip
+}
+
+/// Point a clip at the page its short link redirects to. The first URL it had is
+/// kept as `original_url`.
+pub fn set_resolved_url(conn: &Connection, id: i64, url: &str) -> Result<SqliteClip, AppError> {
+    let changed = conn
+        .execute(
+            "UPDATE clips
+             SET original_url = COALESCE(original_url, url), url = ?1, domain = ?2, normalized_url = ?3,
+                 updated_at = MAX(?4, COALESCE(updated_at, 0) + 1)
+             WHERE id = ?5",
+            params![url, domain_of(url), normalize_url(url), now_millis(), id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to update clip URL: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Clip {} not found", id)));
+    }
+    get_clip(conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))
+}
+
+/// Hash of the clip's visible text, case- and whitespace-insensitive
+pub fn content_hash(content: Option<&str>) -> Option<String> {
+    let text = crate::extract::plain_text(content?).to_lowercase();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.len() < MIN_HASHED_TEXT_LEN {
+        return None;
+    }
+    Some(Sha256::digest(text.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+pub(crate) fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+/// Reject clips the frontend wouldn't be able to display
+pub fn validate(clip: &ClipData) -> Result<(), AppError> {
+    if !CLIP_TYPES.contains(&clip.r#type.as_str()) {
+        return Err(AppError::validation(format!("Unknown clip type '{}'", clip.r#type)));
+    }
+    if clip.title.trim().is_empty() {
+        return Err(AppError::validation("Clip title must not be empty"));
+    }
+    let has = |field: &Option<String>| field.as_deref().map(|v| !v.trim().is_empty()).unwrap_or(false);
+    match clip.r#type.as_str() {
+        "url" | "article" | "video" if !has(&clip.url) => Err(AppError::validation(format!("A clip of type '{}' needs a url", clip.r#type))),
+        "image" if !has(&clip.image_url) => Err(AppError::validation("An image clip needs an image_url")),
+        "note" if !has(&clip.content) => Err(AppError::validation("A note clip needs content")),
+        "code" if !has(&clip.content) => Err(AppError::validation("A code clip needs content")),
+        _ => Ok(()),
+    }
+}
+
+/// Validate and insert a clip, returning the stored row
+pub fn insert_clip(conn: &Connection, clip: &ClipData) -> Result<SqliteClip, AppError> {
+    validate(clip)?;
+
+    conn.execute(
+        "INSERT INTO clips (type, title, url, content, image_url, description, author, timestamp, updated_at, domain,
+                            normalized_url, content_hash, original_url, language)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            clip.r#type,
+            clip.title.trim(),
+            clip.url,
+            clip.content,
+            clip.image_url,
+            clip.description,
+            clip.author,
+            clip.timestamp as i64,
+            now_millis(),
+            clip.url.as_deref().and_then(domain_of),
+            clip.url.as_deref().and_then(normalize_url),
+            content_hash(clip.content.as_deref()),
+            clip.original_url,
+            clip.language,
+        ],
+    )
+    .map_err(|e| AppError::database(format!("Failed to insert clip: {}", e)))?;
+
+    let id = conn.last_insert_rowid();
+    get_clip(conn, id)?.ok_or_else(|| AppError::database(format!("Clip {} vanished after insert", id)))
+}
+
+/// What to do when an incoming clip duplicates an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Fold it into the existing clip: newer timestamp, `times_clipped` + 1, blanks filled in
+    #[default]
+    Merge,
+    /// Store it as a separate clip anyway
+    Keep,
+}
+
+/// Result of `insert_or_merge`
+#[derive(Debug, Serialize)]
+pub struct ClipInsert {
+    /// The new clip, or the existing one it was merged into
+    pub clip: SqliteClip,
+    /// Id of the existing clip this one duplicates, if any
+    pub duplicate_of: Option<i64>,
+    pub merged: bool,
+}
+
+/// Most recent clip outside the trash with the same normalized URL or content hash.
+/// Private clips are never matched, so a repeat can't write plaintext back into one.
+pub fn find_duplicate(conn: &Connection, clip: &ClipData) -> Result<Option<i64>, AppError> {
+    let normalized_url = clip.url.as_deref().and_then(normalize_url);
+    let hash = content_hash(clip.content.as_deref());
+    if normalized_url.is_none() && hash.is_none() {
+        return Ok(None);
+    }
+    conn.query_row(
+        "SELECT id FROM clips WHERE (normalized_url = ?1 OR content_hash = ?2) AND deleted_at IS NULL AND private = 0
+         ORDER BY timestamp DESC, id DESC LIMIT 1",
+        params![normalized_url, hash],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to check for duplicates: {}", e)))
+}
+
+/// Insert a clip, handling repeats of an existing clip according to `policy`
+pub fn insert_or_merge(conn: &Connection, clip: &ClipData, policy: DuplicatePolicy) -> Result<ClipInsert, AppError> {
+    validate(clip)?;
+    let duplicate_of = find_duplicate(conn, clip)?;
+
+    match (duplicate_of, policy) {
+        (Some(id), DuplicatePolicy::Merge) => {
+            let existing = get_clip(conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))?;
+            conn.execute(
+                "UPDATE clips
+                 SET timestamp = MAX(timestamp, ?1), times_clipped = times_clipped + 1, updated_at = ?2,
+                     content = COALESCE(content, ?3), image_url = COALESCE(image_url, ?4),
+                     description = COALESCE(description, ?5), author = COALESCE(author, ?6),
+                     content_hash = COALESCE(content_hash, ?7)
+                 WHERE id = ?8",
+                params![
+                    clip.timestamp as i64,
+                    now_millis().max(existing.updated_at + 1),
+                    clip.content,
+                    clip.image_url,
+                    clip.description,
+                    clip.author,
+                    content_hash(clip.content.as_deref()),
+                    id,
+                ],
+            )
+            .map_err(|e| AppError::database(format!("Failed to merge duplicate clip: {}", e)))?;
+            Ok(ClipInsert {
+                clip: get_clip(conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))?,
+                duplicate_of,
+                merged: true,
+            })
+        }
+        _ => Ok(ClipInsert {
+            clip: insert_clip(conn, clip)?,
+            duplicate_of,
+            merged: false,
+        }),
+    }
+}
+
+/// Clips that share a normalized URL or content hash
+#[derive(Debug, Serialize)]
+pub struct DuplicateGroup {
+    /// `url` or `content`
+    pub matched_on: String,
+    pub key: String,
+    /// Newest first; the first is the natural one to keep
+    pub clips: Vec<SqliteClip>,
+}
+
+/// Every set of existing clips that duplicate each other, for cleaning up old data
+pub fn find_duplicate_clips(conn: &Connection) -> Result<Vec<DuplicateGroup>, AppError> {
+    let mut groups = Vec::new();
+    for (matched_on, column) in [("url", "normalized_url"), ("content", "content_hash")] {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {column} FROM clips WHERE {column} IS NOT NULL AND deleted_at IS NULL
+                 GROUP BY {column} HAVING COUNT(*) > 1 ORDER BY MAX(timestamp) DESC",
+                column = column
+            ))
+            .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+        let keys = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| AppError::database(format!("Failed to find duplicates: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::database(format!("Failed to find duplicates: {}", e)))?;
+
+        let mut clips_stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM clips WHERE {} = ?1 AND deleted_at IS NULL ORDER BY timestamp DESC, id DESC",
+                CLIP_COLUMNS, column
+            ))
+            .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+        for key in keys {
+            let clips = clips_stmt
+                .query_map(params![key], SqliteClip::from_row)
+                .map_err(|e| AppError::database(format!("Failed to read duplicates: {}", e)))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+            groups.push(DuplicateGroup {
+                matched_on: matched_on.to_string(),
+                key,
+                clips,
+            });
+        }
+    }
+    Ok(groups)
+}
+
+pub fn get_clip(conn: &Connection, id: i64) -> Result<Option<SqliteClip>, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM clips WHERE id = ?1", CLIP_COLUMNS),
+        params![id],
+        SqliteClip::from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))
+}
+
+/// All clips outside the trash, newest first
+pub fn get_all_clips(conn: &Connection) -> Result<Vec<SqliteClip>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM clips WHERE deleted_at IS NULL ORDER BY timestamp DESC",
+            CLIP_COLUMNS
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+
+    let clips = stmt
+        .query_map([], SqliteClip::from_row)
+        .map_err(|e| AppError::database(format!("Failed to execute query: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+
+    Ok(clips)
+}
+
+/// Apply `changes` to a clip. Fails with a conflict if `expected_updated_at` is
+/// given and no longer matches. A change to the title or content keeps the previous
+/// version in `clip_revisions`.
+pub fn update_clip(conn: &Connection, id: i64, changes: ClipUpdate) -> Result<SqliteClip, AppError> {
+    let existing = get_clip(conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))?;
+    if let Some(expected) = changes.expected_updated_at {
+        if expected != existing.updated_at {
+            return Err(AppError::validation(format!("Clip {} was modified elsewhere; reload and try again", id)));
+        }
+    }
+    if existing.private && (changes.content.is_some() || changes.description.is_some()) {
+        return Err(AppError::validation(format!("Clip {} is private; make it a normal clip to edit its content", id)));
+    }
+    let previous = existing.clone();
+
+    let merged = ClipData {
+        r#type: changes.r#type.unwrap_or(existing.r#type),
+        title: changes.title.unwrap_or(existing.title),
+        url: changes.url.unwrap_or(existing.url),
+        content: changes.content.unwrap_or(existing.content),
+        image_url: changes.image_url.unwrap_or(existing.image_url),
+        description: changes.description.unwrap_or(existing.description),
+        author: changes.author.unwrap_or(existing.author),
+        timestamp: existing.timestamp as u64,
+        original_url: existing.original_url,
+        language: changes.language.unwrap_or(existing.language),
+    };
+    let language = match merged.r#type.as_str() {
+        "code" => merged.language.as_deref().and_then(code::normalize_language).or_else(|| {
+            // A private clip's content is encrypted, so there's nothing to detect from
+            merged.content.as_deref().filter(|_| !existing.private).and_then(code::detect_language)
+        }),
+        _ => None,
+    };
+    // A private note's content is encrypted, so only the title can be checked
+    if !existing.private {
+        validate(&merged)?;
+    } else if merged.title.trim().is_empty() {
+        return Err(AppError::validation("Clip title must not be empty"));
+    }
+
+    // Keep the token strictly increasing even if the clock goes backwards
+    let updated_at = now_millis().max(existing.updated_at + 1);
+    let changed = conn
+        .execute(
+            "UPDATE clips
+             SET type = ?1, title = ?2, url = ?3, content = ?4, image_url = ?5,
+                 description = ?6, author = ?7, updated_at = ?8, domain = ?9,
+                 image_path = CASE WHEN image_url IS ?5 THEN image_path END,
+                 image_hash = CASE WHEN image_url IS ?5 THEN image_hash END,
+                 normalized_url = ?12, content_hash = ?13, language = ?14
+             WHERE id = ?10 AND COALESCE(updated_at, 0) = ?11",
+            params![
+                merged.r#type,
+                merged.title.trim(),
+                merged.url,
+                merged.content,
+                merged.image_url,
+                merged.description,
+                merged.author,
+                updated_at,
+                merged.url.as_deref().and_then(domain_of),
+                id,
+                existing.updated_at,
+                merged.url.as_deref().and_then(normalize_url),
+                content_hash(merged.content.as_deref()),
+                language,
+            ],
+        )
+        .map_err(|e| AppError::database(format!("Failed to update clip: {}", e)))?;
+
+    if changed == 0 {
+        return Err(AppError::validation(format!("Clip {} was modified elsewhere; reload and try again", id)));
+    }
+    if merged.title.trim() != previous.title || merged.content != previous.content {
+        revisions::record(conn, &previous)?;
+    }
+    get_clip(conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))
+}
+
+/// Store a generated summary. Doesn't bump `updated_at`, so it never conflicts with user edits.
+pub fn set_summary(conn: &Connection, id: i64, summary: &str) -> Result<(), AppError> {
+    let changed = conn
+        .execute("UPDATE clips SET summary = ?1 WHERE id = ?2", params![summary, id])
+        .map_err(|e| AppError::database(format!("Failed to store summary: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Clip {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Set or clear a clip's category; `source` is `user` or `auto`
+pub fn set_category(conn: &Connection, id: i64, category: Option<&str>, source: &str) -> Result<(), AppError> {
+    let changed = conn
+        .execute(
+            "UPDATE clips SET category = ?1, category_source = ?2 WHERE id = ?3",
+            params![category, category.map(|_| source), id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to set category: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Clip {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Record (or with None, forget) the local copy of a clip's image
+pub fn set_image_file(conn: &Connection, id: i64, file: Option<(&str, &str)>) -> Result<(), AppError> {
+    let (path, hash) = file.unzip();
+    let changed = conn
+        .execute(
+            "UPDATE clips SET image_path = ?1, image_hash = ?2 WHERE id = ?3",
+            params![path, hash, id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to store image path: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Clip {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Record the site details fetched for a clip. Like the image columns this isn't an
+/// edit, so `updated_at` stays as it is.
+pub fn set_site_metadata(
+    conn: &Connection,
+    id: i64,
+    metadata: &PageMetadata,
+    favicon_path: Option<&str>,
+) -> Result<(), AppError> {
+    let changed = conn
+        .execute(
+            "UPDATE clips SET site_name = ?1, published_at = ?2, preview_image_url = ?3, favicon_path = ?4 WHERE id = ?5",
+            params![metadata.site_name, metadata.published_at, metadata.image, favicon_path, id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to store site metadata: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Clip {} not found", id)));
+    }
+    Ok(())
+}
+
+/// A favicon already downloaded for another clip from `domain`
+pub fn cached_favicon(conn: &Connection, domain: &str) -> Result<Option<String>, AppError> {
+    conn.query_row(
+        "SELECT favicon_path FROM clips WHERE domain = ?1 AND favicon_path IS NOT NULL LIMIT 1",
+        params![domain],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read favicon: {}", e)))
+}
+
+/// Record a new page snapshot, returning the file name of the one it replaces
+pub fn set_archive(conn: &Connection, id: i64, file_name: &str, archived_at: i64) -> Result<Option<String>, AppError> {
+    let previous = get_clip(conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))?.archive_path;
+    conn.execute(
+        "UPDATE clips SET archive_path = ?1, archived_at = ?2 WHERE id = ?3",
+        params![file_name, archived_at, id],
+    )
+    .map_err(|e| AppError::database(format!("Failed to record archive: {}", e)))?;
+    Ok(previous)
+}
+
+/// Move a clip between read-later states. Archiving records `finished_at`; moving
+/// back out clears it. Like summaries, this doesn't bump `updated_at`.
+pub fn set_read_state(conn: &Connection, id: i64, state: &str) -> Result<SqliteClip, AppError> {
+    if !READ_STATES.contains(&state) {
+        return Err(AppError::validation(format!("Invalid read state '{}'. Must be one of: {}", state, READ_STATES.join(", "))));
+    }
+    let changed = conn
+        .execute(
+            "UPDATE clips SET read_state = ?1,
+                finished_at = CASE WHEN ?1 = 'archived' THEN COALESCE(finished_at, ?2) END
+             WHERE id = ?3",
+            params![state, now_millis(), id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to set read state: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Clip {} not found", id)));
+    }
+    get_clip(conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))
+}
+
+/// Record how far into a clip the reader is. Starting an unread clip moves it to `reading`.
+pub fn set_reading_progress(conn: &Connection, id: i64, progress: f64) -> Result<SqliteClip, AppError> {
+    if !(0.0..=1.0).contains(&progress) {
+        return Err(AppError::validation("Reading progress must be between 0 and 1"));
+    }
+    let changed = conn
+        .execute(
+            "UPDATE clips SET reading_progress = ?1,
+                read_state = CASE WHEN read_state = 'unread' AND ?1 > 0 THEN 'reading' ELSE read_state END
+             WHERE id = ?2",
+            params![progress, id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to set reading progress: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Clip {} not found", id)));
+    }
+    get_clip(conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))
+}
+
+/// Average reading speed used to estimate how long the backlog takes to read
+pub(crate) const WORDS_PER_MINUTE: f64 = 230.0;
+/// Average characters per English word including the following space
+pub(crate) const CHARS_PER_WORD: f64 = 6.0;
+pub(crate) const WEEK_MILLIS: i64 = 7 * 24 * 3_600_000;
+
+#[derive(Debug, Serialize)]
+pub struct ReadingStats {
+    pub unread: u32,
+    pub reading: u32,
+    pub archived: u32,
+    /// Rough time to read every unread and in-progress clip, in minutes
+    pub backlog_minutes: u32,
+    /// Clips archived per week, oldest week first
+    pub finished_per_week: Vec<WeeklyCount>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeeklyCount {
+    /// Monday 00:00 UTC starting the week, in milliseconds since the epoch
+    pub week_start: i64,
+    pub count: u32,
+}
+
+/// Start of the UTC week (Monday) containing `millis`. The epoch fell on a Thursday.
+pub(crate) fn week_start(millis: i64) -> i64 {
+    const DAY: i64 = 24 * 3_600_000;
+    let days = millis.div_euclid(DAY);
+    (days - (days + 3).rem_euclid(7)) * DAY
+}
+
+/// Backlog size and the number of clips finished in each of the last `weeks` weeks
+pub fn reading_stats(conn: &Connection, weeks: u32) -> Result<ReadingStats, AppError> {
+    let mut stats = ReadingStats {
+        unread: 0,
+        reading: 0,
+        archived: 0,
+        backlog_minutes: 0,
+        finished_per_week: Vec::new(),
+    };
+    let mut stmt = conn
+        .prepare(
+            "SELECT read_state, COUNT(*), COALESCE(SUM(LENGTH(content)), 0) FROM clips
+             WHERE deleted_at IS NULL GROUP BY read_state",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?, row.get::<_, i64>(2)?)))
+        .map_err(|e| AppError::database(format!("Failed to count clips: {}", e)))?;
+    let mut backlog_chars = 0;
+    for row in rows {
+        let (state, count, chars) = row.map_err(|e| AppError::database(format!("Failed to count clips: {}", e)))?;
+        match state.as_str() {
+            "unread" => stats.unread = count,
+            "reading" => stats.reading = count,
+            "archived" => stats.archived = count,
+            _ => continue,
+        }
+        if state != "archived" {
+            backlog_chars += chars;
+        }
+    }
+    stats.backlog_minutes = (backlog_chars as f64 / CHARS_PER_WORD / WORDS_PER_MINUTE).ceil() as u32;
+
+    let current_week = week_start(now_millis());
+    let first_week = current_week - (weeks.max(1) as i64 - 1) * WEEK_MILLIS;
+    stats.finished_per_week = (0..weeks.max(1) as i64)
+        .map(|i| WeeklyCount {
+            week_start: first_week + i * WEEK_MILLIS,
+            count: 0,
+        })
+        .collect();
+    let mut stmt = conn
+        .prepare("SELECT finished_at FROM clips WHERE read_state = 'archived' AND finished_at >= ?1 AND deleted_at IS NULL")
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let finished = stmt
+        .query_map(params![first_week], |row| row.get::<_, i64>(0))
+        .map_err(|e| AppError::database(format!("Failed to read finished clips: {}", e)))?;
+    for finished_at in finished {
+        let finished_at = finished_at.map_err(|e| AppError::database(format!("Failed to read finished clips: {}", e)))?;
+        let index = ((week_start(finished_at) - first_week) / WEEK_MILLIS) as usize;
+        if let Some(week) = stats.finished_per_week.get_mut(index) {
+            week.count += 1;
+        }
+    }
+    Ok(stats)
+}
+
+/// Ids of clips with content but no summary yet, newest first
+pub fn unsummarized_clip_ids(conn: &Connection, limit: u32) -> Result<Vec<i64>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id FROM clips
+             WHERE summary IS NULL AND COALESCE(content, '') != '' AND deleted_at IS NULL
+             ORDER BY timestamp DESC, id DESC LIMIT ?1",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let ids = stmt
+        .query_map(params![limit], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to execute query: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+    Ok(ids)
+}
+
+/// Move a clip to the trash. It drops out of lists, search and duplicate checks
+/// until restored, and is deleted for good when the trash is emptied.
+pub fn delete_clip(conn: &Connection, id: i64) -> Result<(), AppError> {
+    let deleted = conn
+        .execute(
+            "UPDATE clips SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![now_millis(), id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to delete clip: {}", e)))?;
+    if deleted == 0 {
+        return Err(AppError::not_found(format!("Clip {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Take a clip back out of the trash. A clip tombstoned by a merge stops redirecting.
+pub fn restore_clip(conn: &Connection, id: i64) -> Result<SqliteClip, AppError> {
+    let restored = conn
+        .execute(
+            "UPDATE clips SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to restore clip: {}", e)))?;
+    if restored == 0 {
+        return Err(AppError::not_found(format!("Clip {} is not in the trash", id)));
+    }
+    conn.execute("DELETE FROM clip_redirects WHERE from_id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to restore clip: {}", e)))?;
+    get_clip(conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))
+}
+
+/// Clips in the trash, most recently deleted first
+pub fn list_trash(conn: &Connection) -> Result<Vec<SqliteClip>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM clips WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, id DESC",
+            CLIP_COLUMNS
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let clips = stmt
+        .query_map([], SqliteClip::from_row)
+        .map_err(|e| AppError::database(format!("Failed to list trash: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+    Ok(clips)
+}
+
+/// Permanently delete trashed clips, only those trashed before `before` if given.
+/// Returns each purged clip's archive snapshot file name, if it had one.
+pub fn purge_trash(conn: &Connection, before: Option<i64>) -> Result<Vec<Option<String>>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "DELETE FROM clips WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR deleted_at < ?1)
+             RETURNING archive_path",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let archives = stmt
+        .query_map(params![before], |row| row.get::<_, Option<String>>(0))
+        .map_err(|e| AppError::database(format!("Failed to empty trash: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to empty trash: {}", e)))?;
+    Ok(archives)
+}
+
+/// Filters for `query_clips`; all present filters must match. Smart collections store one.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ClipFilter {
+    /// Any of these clip types
+    pub types: Option<Vec<String>>,
+    /// Inclusive lower bound on the clip `timestamp`
+    pub since: Option<i64>,
+    /// Exclusive upper bound on the clip `timestamp`
+    pub until: Option<i64>,
+    /// Clipped in the last this many days, counted from when the filter runs
+    pub within_days: Option<u32>,
+    /// Full-text search terms over title, content and description, as in the search box
+    pub text: Option<String>,
+    /// Case-insensitive substring match on author
+    pub author: Option<String>,
+    /// Matches the domain and its subdomains, e.g. `nytimes.com`
+    pub domain: Option<String>,
+    /// Clips must carry all of these tags
+    pub tags: Option<Vec<String>>,
+    /// Clips in this collection or any of its subcollections
+    pub collection_id: Option<i64>,
+    /// Any of these read-later states
+    pub read_states: Option<Vec<String>>,
+    /// Only private clips, or only the others
+    pub private: Option<bool>,
+    /// Any of these statuses
+    pub status_ids: Option<Vec<i64>>,
+    /// Only clips with a status, or only those without one
+    pub has_status: Option<bool>,
+}
+
+impl ClipFilter {
+    /// Render the filter as a SQL condition plus its positional parameters
+    fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut conditions = vec!["deleted_at IS NULL".to_string()];
+        let mut values = Vec::new();
+
+        if let Some(types) = self.types.as_ref().filter(|t| !t.is_empty()) {
+            conditions.push(format!("type IN ({})", vec!["?"; types.len()].join(", ")));
+            values.extend(types.iter().map(|t| Value::Text(t.clone())));
+        }
+        if let Some(read_states) = self.read_states.as_ref().filter(|s| !s.is_empty()) {
+            conditions.push(format!("read_state IN ({})", vec!["?"; read_states.len()].join(", ")));
+            values.extend(read_states.iter().map(|s| Value::Text(s.clone())));
+        }
+        if let Some(private) = self.private {
+            conditions.push("private = ?".to_string());
+            values.push(Value::Integer(private as i64));
+        }
+        if let Some(status_ids) = self.status_ids.as_ref().filter(|s| !s.is_empty()) {
+            conditions.push(format!("status_id IN ({})", vec!["?"; status_ids.len()].join(", ")));
+            values.extend(status_ids.iter().map(|id| Value::Integer(*id)));
+        }
+        if let Some(has_status) = self.has_status {
+            conditions.push(if has_status { "status_id IS NOT NULL" } else { "status_id IS NULL" }.to_string());
+        }
+        if let Some(since) = self.since {
+            conditions.push("timestamp >= ?".to_string());
+            values.push(Value::Integer(since));
+        }
+        if let Some(until) = self.until {
+            conditions.push("timestamp < ?".to_string());
+            values.push(Value::Integer(until));
+        }
+        if let Some(days) = self.within_days {
+            conditions.push("timestamp >= ?".to_string());
+            values.push(Value::Integer(now_millis() - days as i64 * 86_400_000));
+        }
+        if let Some(match_query) = self.text.as_deref().and_then(fts_query) {
+            conditions.push("clips.id IN (SELECT rowid FROM clips_fts WHERE clips_fts MATCH ?)".to_string());
+            values.push(Value::Text(match_query));
+        }
+        if let Some(author) = &self.author {
+            conditions.push("author LIKE ? ESCAPE '\\'".to_string());
+            values.push(Value::Text(format!("%{}%", escape_like(author))));
+        }
+        if let Some(domain) = &self.domain {
+            let domain = domain.trim().to_lowercase();
+            let domain = domain.strip_prefix("www.").unwrap_or(&domain).to_string();
+            conditions.push("(domain = ? OR domain LIKE ? ESCAPE '\\')".to_string());
+            values.push(Value::Text(domain.clone()));
+            values.push(Value::Text(format!("%.{}", escape_like(&domain))));
+        }
+
+        for tag in self.tags.iter().flatten() {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM clip_tags ct JOIN tags t ON t.id = ct.tag_id
+                         WHERE ct.clip_id = clips.id AND t.name = ?)"
+                    .to_string(),
+            );
+            values.push(Value::Text(tag.trim().to_string()));
+        }
+
+        if let Some(collection_id) = self.collection_id {
+            conditions.push(
+                "collection_id IN (
+                    WITH RECURSIVE subtree(id) AS (
+                        SELECT ? UNION ALL
+                        SELECT c.id FROM collections c JOIN subtree ON c.parent_id = subtree.id
+                    )
+                    SELECT id FROM subtree
+                )"
+                .to_string(),
+            );
+            values.push(Value::Integer(collection_id));
+        }
+
+        (conditions.join(" AND "), values)
+    }
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Position to resume a keyset-paginated query from
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipCursor {
+    pub timestamp: i64,
+    pub id: i64,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct ClipQuery {
+    #[serde(flatten)]
+    pub filter: ClipFilter,
+    pub limit: Option<u32>,
+    /// Offset pagination; ignored when `after` is given
+    pub offset: Option<u32>,
+    /// Keyset pagination: continue after this clip (use `next_cursor` from the previous page)
+    pub after: Option<ClipCursor>,
+    /// Only clips in this smart collection, on top of the other filters
+    pub smart_collection_id: Option<i64>,
+    /// Fields to leave out of each clip, e.g. `content` for a list view; fetch the full
+    /// clip with `get_clip` when it's opened
+    pub exclude_fields: Vec<ClipField>,
+    /// Order clips by their place in their status, as a board column shows them, instead
+    /// of newest first. Pages by `offset`; `after` and `next_cursor` don't apply.
+    pub board_order: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClipPage {
+    pub clips: Vec<SqliteClip>,
+    /// Number of clips matching the filter, across all pages
+    pub total: u32,
+    /// Cursor for the next page, or None if this is the last one
+    pub next_cursor: Option<ClipCursor>,
+    /// Matching clips per collection (ignoring the collection filter) for the sidebar
+    pub collection_counts: Vec<CollectionCount>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CollectionCount {
+    /// None counts unfiled clips
+    pub collection_id: Option<i64>,
+    pub count: u32,
+}
+
+/// Number of clips matching `filter`
+pub fn count_clips(conn: &Connection, filter: &ClipFilter) -> Result<u32, AppError> {
+    let (where_sql, values) = filter.to_sql();
+    count_where(conn, &where_sql, &values)
+}
+
+fn count_where(conn: &Connection, where_sql: &str, values: &[Value]) -> Result<u32, AppError> {
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM clips WHERE {}", where_sql),
+        params_from_iter(values.iter()),
+        |row| row.get(0),
+    )
+    .map_err(|e| AppError::database(format!("Failed to count clips: {}", e)))
+}
+
+/// Call `f` with every clip matching `filter`, newest first, reading one row at a
+/// time so the whole library never has to fit in memory
+pub fn for_each_clip<F>(conn: &Connection, filter: &ClipFilter, mut f: F) -> Result<(), AppError>
+where
+    F: FnMut(SqliteClip) -> Result<(), AppError>,
+{
+    let (where_sql, values) = filter.to_sql();
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM clips WHERE {} ORDER BY timestamp DESC, id DESC",
+            CLIP_COLUMNS, where_sql
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let rows = stmt
+        .query_map(params_from_iter(values.iter()), SqliteClip::from_row)
+        .map_err(|e| AppError::database(format!("Failed to execute query: {}", e)))?;
+    for clip in rows {
+        f(clip.map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?)?;
+    }
+    Ok(())
+}
+
+/// `filter` as SQL, narrowed to the smart collection `smart_collection_id` if given
+fn scoped_sql(
+    conn: &Connection,
+    filter: &ClipFilter,
+    smart_collection_id: Option<i64>,
+) -> Result<(String, Vec<Value>), AppError> {
+    let (mut where_sql, mut values) = filter.to_sql();
+    if let Some(id) = smart_collection_id {
+        let (smart_sql, smart_values) = smart_collections::get_smart_collection(conn, id)?.filter.to_sql();
+        where_sql = format!("{} AND {}", where_sql, smart_sql);
+        values.extend(smart_values);
+    }
+    Ok((where_sql, values))
+}
+
+/// One page of clips matching the query, newest first
+pub fn query_clips(conn: &Connection, query: &ClipQuery) -> Result<ClipPage, AppError> {
+    let (where_sql, mut values) = scoped_sql(conn, &query.filter, query.smart_collection_id)?;
+    let total = count_where(conn, &where_sql, &values)?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let mut sql = format!("SELECT {} FROM clips WHERE {}", masked_columns(&query.exclude_fields), where_sql);
+    if let Some(cursor) = query.after.as_ref().filter(|_| !query.board_order) {
+        sql.push_str(" AND (timestamp < ? OR (timestamp = ? AND id < ?))");
+        values.extend([
+            Value::Integer(cursor.timestamp),
+            Value::Integer(cursor.timestamp),
+            Value::Integer(cursor.id),
+        ]);
+    }
+    if query.board_order {
+        sql.push_str(" ORDER BY status_position IS NULL, status_position, id LIMIT ?");
+    } else {
+        sql.push_str(" ORDER BY timestamp DESC, id DESC LIMIT ?");
+    }
+    values.push(Value::Integer(limit as i64));
+    if query.after.is_none() || query.board_order {
+        sql.push_str(" OFFSET ?");
+        values.push(Value::Integer(query.offset.unwrap_or(0) as i64));
+    }
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let clips = stmt
+        .query_map(params_from_iter(values.iter()), SqliteClip::from_row)
+        .map_err(|e| AppError::database(format!("Failed to execute query: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+
+    let next_cursor = if clips.len() as u32 == limit && !query.board_order {
+        clips.last().map(|clip| ClipCursor {
+            timestamp: clip.timestamp,
+            id: clip.id,
+        })
+    } else {
+        None
+    };
+
+    let facet_filter = ClipFilter {
+        collection_id: None,
+        ..query.filter.clone()
+    };
+    let (facet_sql, facet_values) = scoped_sql(conn, &facet_filter, query.smart_collection_id)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT collection_id, COUNT(*) FROM clips WHERE {} GROUP BY collection_id",
+            facet_sql
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let collection_counts = stmt
+        .query_map(params_from_iter(facet_values.iter()), |row| {
+            Ok(CollectionCount {
+                collection_id: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to count clips per collection: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to count clips per collection: {}", e)))?;
+
+    Ok(ClipPage {
+        clips,
+        total,
+        next_cursor,
+        collection_counts,
+    })
+}
+
+/// A full-text search match with highlighted excerpts: HTML-escaped text with matches wrapped in `<mark>`
+#[derive(Debug, Serialize)]
+pub struct ClipSearchHit {
+    pub clip: SqliteClip,
+    pub title_highlighted: String,
+    pub snippet: String,
+    /// bm25 score; lower is a better match
+    pub rank: f64,
+}
+
+/// Wrapped around matches by FTS `highlight` and `snippet`, then turned into `<mark>`
+/// tags by `mark_matches`
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+/// Turn free text from the search box into a safe FTS5 query: every word is
+/// quoted (so punctuation can't break the syntax) and the last word is a prefix
+/// match so results update while typing
+pub fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", terms.join(" ")))
+}
+
+/// Ranked full-text search over clip title, content and description
+pub fn search_clips(conn: &Connection, query: &str, limit: u32) -> Result<Vec<ClipSearchHit>, AppError> {
+    match fts_query(query) {
+        Some(match_query) => search_matching(conn, &match_query, false, None, limit),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Code clips matching `query`, only those in `language` if given. With an empty query
+/// the newest code clips are listed, so a language can be browsed on its own.
+pub fn search_code_clips(
+    conn: &Connection,
+    query: &str,
+    language: Option<&str>,
+    limit: u32,
+) -> Result<Vec<ClipSearchHit>, AppError> {
+    if let Some(match_query) = fts_query(query) {
+        return search_matching(conn, &match_query, true, language, limit);
+    }
+
+    let sql = format!(
+        "SELECT {}, c.title, NULL, 0.0
+         FROM clips c
+         WHERE c.type = 'code' AND c.deleted_at IS NULL AND (?1 IS NULL OR c.language = ?1)
+         ORDER BY c.timestamp DESC
+         LIMIT ?2",
+        clip_columns("c")
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| AppError::database(format!("Failed to prepare search: {}", e)))?;
+    let hits = stmt
+        .query_map(params![language, limit.clamp(1, MAX_PAGE_SIZE)], search_hit_from_row)
+        .map_err(|e| AppError::database(format!("Failed to search clips: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read search result: {}", e)))?;
+    Ok(hits)
+}
+
+/// Map a row of `CLIP_COLUMNS` followed by the highlighted title, snippet and rank
+fn search_hit_from_row(row: &Row) -> rusqlite::Result<ClipSearchHit> {
+    let column_count = CLIP_COLUMNS.split(", ").count();
+    Ok(ClipSearchHit {
+        clip: SqliteClip::from_row(row)?,
+        title_highlighted: mark_matches(&row.get::<_, String>(column_count)?),
+        snippet: mark_matches(&row.get::<_, Option<String>>(column_count + 1)?.unwrap_or_default()),
+        rank: row.get(column_count + 2)?,
+    })
+}
+
+/// Swap FTS match markers for `<mark>` tags. The text is escaped first: titles, code
+/// and Markdown aren't HTML-cleaned, so any markup in them would otherwise go live.
+fn mark_matches(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
+
+/// FTS search for `match_query`, limited to code clips (in `language`, if given) when `code_only`
+fn search_matching(
+    conn: &Connection,
+    match_query: &str,
+    code_only: bool,
+    language: Option<&str>,
+    limit: u32,
+) -> Result<Vec<ClipSearchHit>, AppError> {
+    // Title matches weigh most, then description, then body text
+    let sql = format!(
+        "SELECT {},
+                highlight(clips_fts, 0, char(2), char(3)),
+                snippet(clips_fts, -1, char(2), char(3), '…', 24),
+                bm25(clips_fts, 10.0, 1.0, 3.0) AS score
+         FROM clips_fts
+         JOIN clips c ON c.id = clips_fts.rowid
+         WHERE clips_fts MATCH ?1 AND c.deleted_at IS NULL
+           AND (?3 = 0 OR c.type = 'code') AND (?4 IS NULL OR c.language = ?4)
+         ORDER BY score
+         LIMIT ?2",
+        clip_columns("c")
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| AppError::database(format!("Failed to prepare search: {}", e)))?;
+    let hits = stmt
+        .query_map(params![match_query, limit.clamp(1, MAX_PAGE_SIZE), code_only, language], search_hit_from_row)
+        .map_err(|e| AppError::database(format!("Failed to search clips: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read search result: {}", e)))?;
+
+    Ok(hits)
+}
@@ -0,0 +1,173 @@
+use rusqlite::{params, Connection};
+use tauri::{AppHandle, Manager};
+
+use crate::clips::{self, ClipData, SqliteClip};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::llm;
+use crate::providers::{LlmError, LlmMessage};
+
+/// Snippets beyond this are cut off; the start is plenty to recognize a language
+const MAX_PROMPT_CHARS: usize = 4_000;
+const MAX_LANGUAGE_LEN: usize = 32;
+/// Distinct signature hits needed before `detect_language` commits to a guess
+const MIN_SIGNATURE_HITS: usize = 2;
+
+/// Substrings typical of each language. Earlier entries win ties, so languages whose
+/// snippets also look like another's (TypeScript and JavaScript, C++ and C) come first.
+const SIGNATURES: &[(&str, &[&str])] = &[
+    (
+        "rust",
+        &["fn ", "let mut ", "impl ", "pub fn ", "use std::", "&self", "-> Result<", "#[derive(", "println!(", "Some("],
+    ),
+    ("python", &["def ", "import ", "self.", "elif ", "None", "True", "print(", "__init__", "\"\"\"", "    return "]),
+    (
+        "typescript",
+        &[": string", ": number", ": boolean", "interface ", "export type ", "readonly ", "as const", "<T>"],
+    ),
+    (
+        "javascript",
+        &["function ", "const ", "=> ", "console.log(", "require(", "document.", "===", "module.exports", "async "],
+    ),
+    ("go", &["package ", "func ", ":= ", "fmt.", "go func", "chan ", "defer ", "err != nil", "[]string"]),
+    ("kotlin", &["fun ", "val ", "println(", "data class ", "?.", "companion object", "when ("]),
+    ("java", &["public class ", "public static void", "System.out.", "import java.", "@Override", "private final "]),
+    ("csharp", &["using System", "namespace ", "Console.Write", "async Task", "{ get; set; }", "public void "]),
+    ("cpp", &["#include <", "std::", "cout <<", "template <", "template<", "nullptr", "namespace ", "::"]),
+    ("c", &["#include <", "int main(", "printf(", "malloc(", "sizeof(", "NULL", "->", "void "]),
+    ("swift", &["func ", "import Foundation", "import SwiftUI", "import UIKit", "guard ", "let ", "var ", "-> "]),
+    ("ruby", &["def ", "\nend", "puts ", "require '", "attr_accessor", "do |", ".each ", "@"]),
+    ("php", &["<?php", "$this->", "echo ", "function ", "=> ", "->", "$"]),
+    ("shell", &["#!/bin/", "echo ", "\nfi", "then\n", "$(", "export ", "sudo ", "| grep", "\ndone"]),
+    ("sql", &["SELECT ", "FROM ", "WHERE ", "INSERT INTO", "CREATE TABLE", "JOIN ", "GROUP BY", "ORDER BY"]),
+    ("html", &["<!DOCTYPE", "<html", "<div", "<span", "</", "class=\"", "<a href"]),
+    ("css", &["color:", "margin:", "padding:", "display:", "px;", "@media", "font-", "}\n"]),
+    ("yaml", &["---\n", "apiVersion:", "  - name:", "steps:", "services:", "image:", "version:"]),
+];
+
+/// Common spellings of language names, mapped to the ones `SIGNATURES` uses
+const ALIASES: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("py", "python"),
+    ("python3", "python"),
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("node", "javascript"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("golang", "go"),
+    ("kt", "kotlin"),
+    ("c#", "csharp"),
+    ("cs", "csharp"),
+    ("c++", "cpp"),
+    ("cc", "cpp"),
+    ("rb", "ruby"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("yml", "yaml"),
+    ("htm", "html"),
+];
+
+/// Lowercase a language name and map common aliases (`js`, `c++`, `bash`) to one
+/// spelling, so searching by language finds every snippet in it
+pub fn normalize_language(name: &str) -> Option<String> {
+    let name = name.trim().to_lowercase();
+    let name = ALIASES.iter().find(|(alias, _)| *alias == name).map_or(name.as_str(), |(_, language)| *language);
+    let valid = !name.is_empty()
+        && name.len() <= MAX_LANGUAGE_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '#' | '-' | '_'));
+    valid.then(|| name.to_string())
+}
+
+/// Guess the language of a snippet from its shebang or the language's typical syntax.
+/// None when nothing stands out; the `DetectCodeLanguage` job then asks the LLM.
+pub fn detect_language(code: &str) -> Option<String> {
+    let trimmed = code.trim_start();
+    if let Some(shebang) = trimmed.strip_prefix("#!").and_then(|rest| rest.lines().next()) {
+        let interpreter = shebang.split_whitespace().last().unwrap_or_default();
+        let interpreter = interpreter.rsplit('/').next().unwrap_or_default();
+        if let Some(language) = normalize_language(interpreter.trim_end_matches(char::is_numeric)) {
+            return Some(language);
+        }
+    }
+    if trimmed.starts_with("<?php") {
+        return Some("php".to_string());
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some("json".to_string());
+    }
+
+    let mut best: Option<(&str, usize)> = None;
+    for (language, signatures) in SIGNATURES {
+        let hits = signatures.iter().filter(|signature| code.contains(*signature)).count();
+        if hits >= MIN_SIGNATURE_HITS && !matches!(best, Some((_, most)) if most >= hits) {
+            best = Some((*language, hits));
+        }
+    }
+    best.map(|(language, _)| language.to_string())
+}
+
+/// Settle the language of a new code clip: the one it came with if it names one,
+/// otherwise a guess. Other clips have none.
+pub fn classify(mut clip_data: ClipData) -> ClipData {
+    clip_data.language = if clip_data.r#type == "code" {
+        clip_data
+            .language
+            .as_deref()
+            .and_then(normalize_language)
+            .or_else(|| clip_data.content.as_deref().and_then(detect_language))
+    } else {
+        None
+    };
+    clip_data
+}
+
+fn set_language(conn: &Connection, id: i64, language: &str) -> Result<(), AppError> {
+    let changed = conn
+        .execute("UPDATE clips SET language = ?1 WHERE id = ?2 AND type = 'code'", params![language, id])
+        .map_err(|e| AppError::database(format!("Failed to store language: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Code clip {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Ask the default model which language a code clip is in, for snippets the heuristics
+/// couldn't place. Like summaries, this doesn't bump `updated_at`.
+pub async fn detect_language_with_llm(app_handle: &AppHandle, clip_id: i64) -> Result<SqliteClip, LlmError> {
+    let clip = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::get_clip(&conn, clip_id)?.ok_or_else(|| format!("Clip {} not found", clip_id))?
+    };
+    if clip.r#type != "code" || clip.language.is_some() || clip.private {
+        return Ok(clip);
+    }
+
+    let snippet: String = clip.content.as_deref().unwrap_or_default().chars().take(MAX_PROMPT_CHARS).collect();
+    let messages = vec![
+        LlmMessage {
+            role: "system".to_string(),
+            content: "Name the programming or markup language of the code snippet. Reply with the \
+                      language name only, in lowercase, e.g. rust, python, javascript, sql."
+                .to_string(),
+        },
+        LlmMessage {
+            role: "user".to_string(),
+            content: format!("Title: {}\n\n{}", clip.title, snippet),
+        },
+    ];
+    let response = llm::complete_with_default(app_handle, "detect_language", messages, Some(10)).await?;
+    let reply = response.content.trim().trim_matches(|c: char| c == '`' || c == '.' || c == '"');
+    let language = normalize_language(reply).ok_or_else(|| format!("Unusable language from model: {}", reply))?;
+
+    let db = app_handle.state::<Database>();
+    db.write(move |conn| set_language(conn, clip_id, &language))?;
+    let clip = clips::get_clip(&db.conn()?, clip_id)?.ok_or_else(|| format!("Clip {} not found", clip_id))?;
+    events::emit(app_handle, "clip-updated", &clip).map_err(|e| format!("Failed to emit clip event: {}", e))?;
+    Ok(clip)
+}
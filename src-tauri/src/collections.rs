@@ -0,0 +1,136 @@
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::errors::AppError;
+
+const MAX_NAME_LEN: usize = 128;
+
+/// A folder of clips; collections nest via `parent_id`
+#[derive(Debug, Serialize, Clone)]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    /// Clips directly in this collection (not counting subcollections)
+    pub clip_count: u32,
+}
+
+fn normalize_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::validation("Collection name must not be empty"));
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::validation(format!("Collection name must be at most {} characters", MAX_NAME_LEN)));
+    }
+    Ok(name.to_string())
+}
+
+fn get_collection(conn: &Connection, id: i64) -> Result<Option<Collection>, AppError> {
+    conn.query_row(
+        "SELECT c.id, c.name, c.parent_id,
+                (SELECT COUNT(*) FROM clips WHERE collection_id = c.id AND deleted_at IS NULL)
+         FROM collections c WHERE c.id = ?1",
+        params![id],
+        |row| {
+            Ok(Collection {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                parent_id: row.get(2)?,
+                clip_count: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read collection: {}", e)))
+}
+
+pub fn create_collection(conn: &Connection, name: &str, parent_id: Option<i64>) -> Result<Collection, AppError> {
+    let name = normalize_name(name)?;
+    if let Some(parent_id) = parent_id {
+        if get_collection(conn, parent_id)?.is_none() {
+            return Err(AppError::not_found(format!("Collection {} not found", parent_id)));
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO collections (name, parent_id) VALUES (?1, ?2)",
+        params![name, parent_id],
+    )
+    .map_err(|e| AppError::database(format!("Failed to create collection: {}", e)))?;
+
+    let id = conn.last_insert_rowid();
+    get_collection(conn, id)?.ok_or_else(|| AppError::database(format!("Collection {} vanished after insert", id)))
+}
+
+pub fn rename_collection(conn: &Connection, id: i64, name: &str) -> Result<Collection, AppError> {
+    let name = normalize_name(name)?;
+    let changed = conn
+        .execute("UPDATE collections SET name = ?1 WHERE id = ?2", params![name, id])
+        .map_err(|e| AppError::database(format!("Failed to rename collection: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Collection {} not found", id)));
+    }
+    get_collection(conn, id)?.ok_or_else(|| AppError::not_found(format!("Collection {} not found", id)))
+}
+
+/// Delete a collection and its subcollections. Their clips are kept but become unfiled.
+pub fn delete_collection(conn: &Connection, id: i64) -> Result<(), AppError> {
+    let deleted = conn
+        .execute("DELETE FROM collections WHERE id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to delete collection: {}", e)))?;
+    if deleted == 0 {
+        return Err(AppError::not_found(format!("Collection {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Move clips into a collection, or out of any collection when `collection_id` is None
+pub fn move_clips(conn: &Connection, clip_ids: &[i64], collection_id: Option<i64>) -> Result<usize, AppError> {
+    if clip_ids.is_empty() {
+        return Ok(0);
+    }
+    if let Some(collection_id) = collection_id {
+        if get_collection(conn, collection_id)?.is_none() {
+            return Err(AppError::not_found(format!("Collection {} not found", collection_id)));
+        }
+    }
+
+    let sql = format!(
+        "UPDATE clips SET collection_id = ? WHERE id IN ({})",
+        vec!["?"; clip_ids.len()].join(", ")
+    );
+    let values = std::iter::once(collection_id).chain(clip_ids.iter().map(|id| Some(*id)));
+    conn.execute(&sql, params_from_iter(values))
+        .map_err(|e| AppError::database(format!("Failed to move clips: {}", e)))
+}
+
+/// Every collection with its clip count, parents before children
+pub fn list_collections(conn: &Connection) -> Result<Vec<Collection>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "WITH RECURSIVE tree(id, depth) AS (
+                SELECT id, 0 FROM collections WHERE parent_id IS NULL
+                UNION ALL
+                SELECT c.id, tree.depth + 1 FROM collections c JOIN tree ON c.parent_id = tree.id
+            )
+            SELECT c.id, c.name, c.parent_id,
+                   (SELECT COUNT(*) FROM clips WHERE collection_id = c.id AND deleted_at IS NULL)
+            FROM tree JOIN collections c ON c.id = tree.id
+            ORDER BY tree.depth, c.name COLLATE NOCASE",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let collections = stmt
+        .query_map([], |row| {
+            Ok(Collection {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                parent_id: row.get(2)?,
+                clip_count: row.get(3)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to list collections: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read collection: {}", e)))?;
+    Ok(collections)
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use crate::errors::AppError;
+
+/// Resolved on-disk locations for everything the active profile stores. Clones share
+/// the data dir, which `set_data_dir` moves when another profile is switched to.
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    data_dir: Arc<RwLock<PathBuf>>,
+}
+
+impl AppConfig {
+    /// Locations inside `data_dir`, resolved for the active profile by `profiles`
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self {
+            data_dir: Arc::new(RwLock::new(data_dir)),
+        }
+    }
+
+    pub fn data_dir(&self) -> PathBuf {
+        self.data_dir.read().unwrap().clone()
+    }
+
+    pub fn set_data_dir(&self, data_dir: PathBuf) {
+        *self.data_dir.write().unwrap() = data_dir;
+    }
+
+    /// Create the directory structure if this is the first run
+    pub fn ensure_dirs(&self) -> Result<(), AppError> {
+        for dir in [self.data_dir(), self.clips_dir(), self.media_dir(), self.archives_dir(), self.favicons_dir(), self.backups_dir(), self.logs_dir()] {
+            fs::create_dir_all(&dir).map_err(|e| AppError::internal(format!("Failed to create {}: {}", dir.display(), e)))?;
+        }
+        Ok(())
+    }
+
+    pub fn clips_db_path(&self) -> PathBuf {
+        self.data_dir().join("clips.db")
+    }
+
+    /// Drop folder the browser extension writes clip JSON files into
+    pub fn clips_dir(&self) -> PathBuf {
+        self.data_dir().join("clips")
+    }
+
+    /// Local copies of clipped images, named by content hash
+    pub fn media_dir(&self) -> PathBuf {
+        self.data_dir().join("media")
+    }
+
+    /// Self-contained HTML snapshots of clipped pages
+    pub fn archives_dir(&self) -> PathBuf {
+        self.data_dir().join("archives")
+    }
+
+    /// Site icons, one per domain, shared by every clip from that site
+    pub fn favicons_dir(&self) -> PathBuf {
+        self.data_dir().join("favicons")
+    }
+
+    /// Verified snapshots of clips.db, rotated by the backup scheduler
+    pub fn backups_dir(&self) -> PathBuf {
+        self.data_dir().join("backups")
+    }
+
+    /// Daily-rotated application logs
+    pub fn logs_dir(&self) -> PathBuf {
+        self.data_dir().join("logs")
+    }
+
+    pub fn secrets_path(&self) -> PathBuf {
+        self.data_dir().join("secrets.enc")
+    }
+}
@@ -0,0 +1,113 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::errors::AppError;
+use crate::http;
+use crate::jobs::JobQueue;
+use crate::settings::Settings;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Any answer from one of these means the internet is reachable. Plain HTTPS rather than
+/// a raw socket, so proxies from the network settings are honored.
+const PROBE_URLS: &[&str] = &["https://www.gstatic.com/generate_204", "https://cloudflare.com/cdn-cgi/trace"];
+
+/// Whether the last probe reached the internet; assumed until the first probe says otherwise
+static REACHABLE: AtomicBool = AtomicBool::new(true);
+/// The user switched to offline mode in settings
+static OFFLINE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Payload of `get_connectivity` and the `connectivity-changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectivityStatus {
+    /// Network features are available: the internet is reachable and offline mode is off
+    pub online: bool,
+    pub offline_mode: bool,
+    /// What the last probe found, regardless of offline mode
+    pub reachable: bool,
+}
+
+pub fn status() -> ConnectivityStatus {
+    ConnectivityStatus {
+        online: is_online(),
+        offline_mode: OFFLINE_MODE.load(Ordering::Relaxed),
+        reachable: REACHABLE.load(Ordering::Relaxed),
+    }
+}
+
+pub fn is_online() -> bool {
+    !OFFLINE_MODE.load(Ordering::Relaxed) && REACHABLE.load(Ordering::Relaxed)
+}
+
+/// Fail fast with an `offline` error instead of waiting on a request that can't succeed
+pub fn ensure_online() -> Result<(), AppError> {
+    if OFFLINE_MODE.load(Ordering::Relaxed) {
+        return Err(AppError::offline("Offline mode is on; turn it off in settings to use network features"));
+    }
+    if !REACHABLE.load(Ordering::Relaxed) {
+        return Err(AppError::offline("No internet connection"));
+    }
+    Ok(())
+}
+
+/// Hosts on this machine, which stay usable offline (Ollama, a local OpenAI-compatible server)
+pub fn is_local_host(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+fn changed(app_handle: &AppHandle) {
+    let status = status();
+    info!(
+        "Network features are now {}",
+        if status.online { "available" } else { "unavailable" }
+    );
+    if let Err(e) = app_handle.emit("connectivity-changed", &status) {
+        warn!("Failed to emit connectivity event: {}", e);
+    }
+    // Jobs that waited for the network can run now
+    if status.online {
+        if let Some(queue) = app_handle.try_state::<JobQueue>() {
+            queue.poke();
+        }
+    }
+}
+
+/// Follow the `offline_mode` setting; called at startup and whenever settings change
+pub fn apply_settings(app_handle: &AppHandle, settings: &Settings) {
+    if OFFLINE_MODE.swap(settings.offline_mode, Ordering::Relaxed) != settings.offline_mode {
+        changed(app_handle);
+    }
+}
+
+async fn probe() -> bool {
+    let client = http::client();
+    for url in PROBE_URLS {
+        if client.head(*url).timeout(PROBE_TIMEOUT).send().await.is_ok() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Probe the internet every `CHECK_INTERVAL` and emit `connectivity-changed` when it
+/// comes or goes. Nothing is probed while offline mode is on.
+pub fn start_monitor(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if !OFFLINE_MODE.load(Ordering::Relaxed) {
+                let reachable = probe().await;
+                if REACHABLE.swap(reachable, Ordering::Relaxed) != reachable {
+                    changed(&app_handle);
+                }
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
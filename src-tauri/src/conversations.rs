@@ -0,0 +1,246 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::clips::now_millis;
+use crate::errors::AppError;
+use crate::providers::{LlmMessage, LlmUsage};
+
+const MESSAGE_ROLES: &[&str] = &["system", "user", "assistant"];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Conversation {
+    pub id: i64,
+    pub title: String,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// Sent ahead of the history on every call
+    pub system_prompt: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Milliseconds since the epoch
+    pub created_at: i64,
+    /// Time of the latest message, in milliseconds since the epoch
+    pub updated_at: i64,
+    pub message_count: u32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Message {
+    pub id: i64,
+    pub conversation_id: i64,
+    pub role: String,
+    pub content: String,
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub created_at: i64,
+}
+
+/// Model settings stored with a conversation; `None` leaves the choice to the caller
+/// or the provider's default
+#[derive(Debug, Deserialize, Default)]
+pub struct ConversationSettings {
+    pub system_prompt: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+/// A conversation with its messages, oldest first
+#[derive(Debug, Serialize)]
+pub struct ConversationDetail {
+    pub conversation: Conversation,
+    pub messages: Vec<Message>,
+}
+
+const CONVERSATION_SELECT: &str = "SELECT c.id, c.title, c.provider, c.model, c.system_prompt, c.temperature, c.max_tokens,
+        c.created_at, c.updated_at,
+        (SELECT COUNT(*) FROM messages WHERE conversation_id = c.id)
+    FROM conversations c";
+
+fn conversation_from_row(row: &rusqlite::Row) -> rusqlite::Result<Conversation> {
+    Ok(Conversation {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        provider: row.get(2)?,
+        model: row.get(3)?,
+        system_prompt: row.get(4)?,
+        temperature: row.get(5)?,
+        max_tokens: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+        message_count: row.get(9)?,
+    })
+}
+
+pub fn create_conversation(
+    conn: &Connection,
+    title: Option<&str>,
+    provider: Option<&str>,
+    model: Option<&str>,
+) -> Result<Conversation, AppError> {
+    let title = title.map(str::trim).filter(|t| !t.is_empty()).unwrap_or("New conversation");
+    let now = now_millis();
+    conn.execute(
+        "INSERT INTO conversations (title, provider, model, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+        params![title, provider, model, now],
+    )
+    .map_err(|e| AppError::database(format!("Failed to create conversation: {}", e)))?;
+
+    let id = conn.last_insert_rowid();
+    get_conversation_row(conn, id)?.ok_or_else(|| AppError::database(format!("Conversation {} vanished after insert", id)))
+}
+
+pub fn get_conversation_row(conn: &Connection, id: i64) -> Result<Option<Conversation>, AppError> {
+    conn.query_row(
+        &format!("{} WHERE c.id = ?1", CONVERSATION_SELECT),
+        params![id],
+        conversation_from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read conversation: {}", e)))
+}
+
+/// Replace the conversation's model settings; later calls with its id pick them up
+pub fn update_conversation_settings(
+    conn: &Connection,
+    id: i64,
+    settings: &ConversationSettings,
+) -> Result<Conversation, AppError> {
+    if let Some(temperature) = settings.temperature {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(AppError::validation("Temperature must be between 0 and 2"));
+        }
+    }
+    if settings.max_tokens == Some(0) {
+        return Err(AppError::validation("max_tokens must be at least 1"));
+    }
+    let system_prompt = settings.system_prompt.as_deref().map(str::trim).filter(|p| !p.is_empty());
+    let changed = conn
+        .execute(
+            "UPDATE conversations SET system_prompt = ?1, provider = ?2, model = ?3, temperature = ?4, max_tokens = ?5
+             WHERE id = ?6",
+            params![
+                system_prompt,
+                settings.provider,
+                settings.model,
+                settings.temperature,
+                settings.max_tokens,
+                id,
+            ],
+        )
+        .map_err(|e| AppError::database(format!("Failed to update conversation: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Conversation {} not found", id)));
+    }
+    get_conversation_row(conn, id)?.ok_or_else(|| AppError::not_found(format!("Conversation {} not found", id)))
+}
+
+/// Append a message and bump the conversation's `updated_at`
+pub fn append_message(
+    conn: &Connection,
+    conversation_id: i64,
+    role: &str,
+    content: &str,
+    usage: Option<&LlmUsage>,
+) -> Result<Message, AppError> {
+    if !MESSAGE_ROLES.contains(&role) {
+        return Err(AppError::validation(format!("Invalid message role '{}'. Must be one of: {}", role, MESSAGE_ROLES.join(", "))));
+    }
+    if get_conversation_row(conn, conversation_id)?.is_none() {
+        return Err(AppError::not_found(format!("Conversation {} not found", conversation_id)));
+    }
+
+    let now = now_millis();
+    conn.execute(
+        "INSERT INTO messages (conversation_id, role, content, input_tokens, output_tokens, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            conversation_id,
+            role,
+            content,
+            usage.map(|u| u.input_tokens),
+            usage.map(|u| u.output_tokens),
+            now,
+        ],
+    )
+    .map_err(|e| AppError::database(format!("Failed to append message: {}", e)))?;
+    let id = conn.last_insert_rowid();
+
+    conn.execute(
+        "UPDATE conversations SET updated_at = ?1 WHERE id = ?2",
+        params![now, conversation_id],
+    )
+    .map_err(|e| AppError::database(format!("Failed to update conversation: {}", e)))?;
+
+    Ok(Message {
+        id,
+        conversation_id,
+        role: role.to_string(),
+        content: content.to_string(),
+        input_tokens: usage.map(|u| u.input_tokens),
+        output_tokens: usage.map(|u| u.output_tokens),
+        created_at: now,
+    })
+}
+
+/// All conversations, most recently active first
+pub fn list_conversations(conn: &Connection) -> Result<Vec<Conversation>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY c.updated_at DESC, c.id DESC", CONVERSATION_SELECT))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let conversations = stmt
+        .query_map([], conversation_from_row)
+        .map_err(|e| AppError::database(format!("Failed to list conversations: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read conversation: {}", e)))?;
+    Ok(conversations)
+}
+
+pub fn get_conversation(conn: &Connection, id: i64) -> Result<Option<ConversationDetail>, AppError> {
+    let conversation = match get_conversation_row(conn, id)? {
+        Some(conversation) => conversation,
+        None => return Ok(None),
+    };
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, conversation_id, role, content, input_tokens, output_tokens, created_at
+             FROM messages WHERE conversation_id = ?1 ORDER BY id",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let messages = stmt
+        .query_map(params![id], |row| {
+            Ok(Message {
+                id: row.get(0)?,
+                conversation_id: row.get(1)?,
+                role: row.get(2)?,
+                content: row.get(3)?,
+                input_tokens: row.get(4)?,
+                output_tokens: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to read messages: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read message: {}", e)))?;
+
+    Ok(Some(ConversationDetail { conversation, messages }))
+}
+
+/// The conversation's messages in the shape the LLM providers expect, led by its
+/// system prompt if it has one
+pub fn history(conn: &Connection, id: i64) -> Result<Vec<LlmMessage>, AppError> {
+    let detail = get_conversation(conn, id)?.ok_or_else(|| AppError::not_found(format!("Conversation {} not found", id)))?;
+    let system = detail.conversation.system_prompt.map(|content| LlmMessage {
+        role: "system".to_string(),
+        content,
+    });
+    Ok(system
+        .into_iter()
+        .chain(detail.messages.into_iter().map(|message| LlmMessage {
+            role: message.role,
+            content: message.content,
+        }))
+        .collect())
+}
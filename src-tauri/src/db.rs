@@ -0,0 +1,411 @@
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Connection, OpenFlags};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+use tracing::error;
+
+use crate::errors::AppError;
+use crate::migrations;
+
+pub type DbConnection = PooledConnection<SqliteConnectionManager>;
+
+/// Name of the secret holding the SQLCipher key when the database is encrypted
+pub const DATABASE_KEY_SECRET: &str = "database_key";
+
+/// How long a connection waits on another connection's write lock before failing with
+/// "database is locked"
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A write run by the writer thread, handed a connection or the error checking one out
+type WriteJob = Box<dyn FnOnce(Result<&mut Connection, AppError>) + Send>;
+
+/// Pooled SQLite connections to clips.db, managed as Tauri state. The file is in WAL
+/// mode so reads never wait on a write. Writes from the clipper, the drop folder
+/// watcher and the UI go through `write` or `write_async`, which run them one at a time
+/// on a single writer thread; other writers rely on `BUSY_TIMEOUT`.
+pub struct Database {
+    /// Swapped out wholesale when the database is encrypted, decrypted or re-keyed, or
+    /// another profile's database is opened
+    inner: Arc<RwLock<Inner>>,
+    writer: mpsc::Sender<WriteJob>,
+}
+
+struct Inner {
+    path: PathBuf,
+    /// None only while `convert` swaps the file underneath
+    pool: Option<Pool<SqliteConnectionManager>>,
+    /// SQLCipher key; None for a plaintext database
+    key: Option<String>,
+}
+
+impl Database {
+    /// Open (or create) the database and bring its schema up to date. `key` opens
+    /// an SQLCipher-encrypted database.
+    pub fn open(path: &Path, key: Option<String>) -> Result<Self, AppError> {
+        let pool = build_pool(path, key.clone())?;
+
+        let mut conn = pool.get().map_err(|e| AppError::database(format!("Failed to open database: {}", e)))?;
+        migrations::run(&mut conn)?;
+
+        let inner = Arc::new(RwLock::new(Inner {
+            path: path.to_path_buf(),
+            pool: Some(pool),
+            key,
+        }));
+        let (writer, jobs) = mpsc::channel();
+        let writer_inner = inner.clone();
+        thread::Builder::new()
+            .name("db-writer".into())
+            .spawn(move || run_writer(&writer_inner, jobs))
+            .map_err(|e| AppError::internal(format!("Failed to start database writer: {}", e)))?;
+
+        Ok(Self { inner, writer })
+    }
+
+    /// Close the current database and open (or create) the one at `path` in its place,
+    /// bringing its schema up to date. Queued writes run against whichever database is
+    /// open when their turn comes. On error the current database stays open.
+    pub fn reopen(&self, path: &Path, key: Option<String>) -> Result<(), AppError> {
+        let pool = build_pool(path, key.clone())?;
+        let mut conn = pool.get().map_err(|e| AppError::database(format!("Failed to open database: {}", e)))?;
+        migrations::run(&mut conn)?;
+        drop(conn);
+
+        let mut inner = self.inner.write().map_err(|_| AppError::internal("Database lock poisoned"))?;
+        *inner = Inner {
+            path: path.to_path_buf(),
+            pool: Some(pool),
+            key,
+        };
+        Ok(())
+    }
+
+    /// Check out a connection from the pool
+    pub fn conn(&self) -> Result<DbConnection, AppError> {
+        let inner = self.inner.read().map_err(|_| AppError::internal("Database lock poisoned"))?;
+        inner.get()
+    }
+
+    /// Run `write` on the writer thread and wait for its result. Writes queue up rather
+    /// than contend for the lock. `write` must not call `write` itself, which would wait
+    /// on its own turn forever. Async code should use `write_async`; when this is reached
+    /// from a runtime worker anyway, the worker hands its other tasks off while it waits.
+    pub fn write<T, F>(&self, write: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, AppError> + Send + 'static,
+    {
+        let (reply, result) = mpsc::sync_channel(1);
+        let job: WriteJob = Box::new(move |conn| {
+            // The caller only goes away if its thread panicked
+            let _ = reply.send(conn.and_then(write));
+        });
+        self.writer
+            .send(job)
+            .map_err(|_| AppError::internal("The database writer has stopped"))?;
+        tokio::task::block_in_place(|| result.recv()).map_err(|_| AppError::internal("A database write panicked"))?
+    }
+
+    /// `write` for async code: waits for the writer without holding up a runtime thread
+    pub async fn write_async<T, F>(&self, write: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, AppError> + Send + 'static,
+    {
+        let (reply, result) = tokio::sync::oneshot::channel();
+        let job: WriteJob = Box::new(move |conn| {
+            // The caller only goes away if its task was cancelled
+            let _ = reply.send(conn.and_then(write));
+        });
+        self.writer
+            .send(job)
+            .map_err(|_| AppError::internal("The database writer has stopped"))?;
+        result
+            .await
+            .map_err(|_| AppError::internal("A database write panicked"))?
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.inner.read().map(|inner| inner.key.is_some()).unwrap_or(false)
+    }
+
+    /// Open another database file, such as a backup, with the same key as clips.db
+    pub fn open_file(&self, path: &Path, flags: OpenFlags) -> Result<Connection, AppError> {
+        let key = self.inner.read().map_err(|_| AppError::internal("Database lock poisoned"))?.key.clone();
+        let conn =
+            Connection::open_with_flags(path, flags).map_err(|e| AppError::database(format!("Failed to open {}: {}", path.display(), e)))?;
+        if let Some(key) = key {
+            apply_key(&conn, &key).map_err(|e| AppError::database(format!("Failed to unlock {}: {}", path.display(), e)))?;
+        }
+        Ok(conn)
+    }
+
+    /// Rewrite clips.db in place encrypted with `key`, or as plaintext when `key` is
+    /// None. The copy is made with `sqlcipher_export` into a sibling file that then
+    /// replaces the original, and the pool is reopened on it.
+    pub fn convert(&self, key: Option<String>) -> Result<(), AppError> {
+        let mut inner = self.inner.write().map_err(|_| AppError::internal("Database lock poisoned"))?;
+        let path = inner.path.clone();
+        let converted = path.with_extension("converting");
+        let _ = fs::remove_file(&converted);
+
+        export(&inner.get()?, &converted, key.as_deref().unwrap_or(""))?;
+
+        // Close the old connections before replacing the file underneath them. Their
+        // write-ahead log belongs to the old file and must not be replayed into the new one.
+        inner.pool = None;
+        for suffix in ["-wal", "-shm"] {
+            let mut sidecar = path.clone().into_os_string();
+            sidecar.push(suffix);
+            let _ = fs::remove_file(sidecar);
+        }
+        if let Err(e) = fs::rename(&converted, &path) {
+            let _ = fs::remove_file(&converted);
+            inner.pool = Some(build_pool(&path, inner.key.clone())?);
+            return Err(AppError::internal(format!("Failed to replace database: {}", e)));
+        }
+        inner.pool = Some(build_pool(&path, key.clone())?);
+        inner.key = key;
+        Ok(())
+    }
+
+    /// Change the key of an encrypted database (`PRAGMA rekey`)
+    pub fn rekey(&self, key: String) -> Result<(), AppError> {
+        let mut inner = self.inner.write().map_err(|_| AppError::internal("Database lock poisoned"))?;
+        if inner.key.is_none() {
+            return Err(AppError::validation("The database is not encrypted"));
+        }
+
+        inner
+            .get()?
+            .pragma_update(None, "rekey", &key)
+            .map_err(|e| AppError::database(format!("Failed to re-key database: {}", e)))?;
+
+        // Pooled connections were unlocked with the old key
+        inner.pool = Some(build_pool(&inner.path, Some(key.clone()))?);
+        inner.key = Some(key);
+        Ok(())
+    }
+}
+
+impl Inner {
+    fn get(&self) -> Result<DbConnection, AppError> {
+        self.pool
+            .as_ref()
+            .ok_or_else(|| AppError::database("Database is unavailable"))?
+            .get()
+            .map_err(|e| AppError::database(format!("Failed to get database connection: {}", e)))
+    }
+}
+
+fn build_pool(path: &Path, key: Option<String>) -> Result<Pool<SqliteConnectionManager>, AppError> {
+    let manager = SqliteConnectionManager::file(path).with_init(move |conn| {
+        // The key has to be set before anything else touches the file
+        if let Some(key) = &key {
+            apply_key(conn, key)?;
+        }
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")
+    });
+    Pool::builder()
+        .max_size(8)
+        .build(manager)
+        .map_err(|e| AppError::database(format!("Failed to open database: {}", e)))
+}
+
+/// Run queued writes in order until the `Database` is dropped. Each gets a connection
+/// under the read lock, so `convert` and `rekey` wait for a write in progress.
+fn run_writer(inner: &RwLock<Inner>, jobs: mpsc::Receiver<WriteJob>) {
+    for job in jobs {
+        let Ok(inner) = inner.read() else {
+            error!("Database lock poisoned; stopping the writer");
+            return;
+        };
+        match inner.get() {
+            Ok(mut conn) => job(Ok(&mut *conn)),
+            Err(e) => job(Err(e)),
+        }
+    }
+}
+
+fn apply_key(conn: &Connection, key: &str) -> rusqlite::Result<()> {
+    conn.pragma_update(None, "key", key)?;
+    // A wrong key only surfaces on the first read
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+}
+
+/// Copy the whole database into `dest` with `key` (empty for plaintext)
+fn export(conn: &Connection, dest: &Path, key: &str) -> Result<(), AppError> {
+    let dest = dest.to_str().ok_or_else(|| AppError::internal("Database path is not valid UTF-8"))?;
+    conn.execute("ATTACH DATABASE ?1 AS converted KEY ?2", rusqlite::params![dest, key])
+        .map_err(|e| AppError::database(format!("Failed to create converted database: {}", e)))?;
+
+    let result = conn
+        .query_row("SELECT sqlcipher_export('converted')", [], |_| Ok(()))
+        .and_then(|_| {
+            // sqlcipher_export doesn't carry over the schema version migrations rely on
+            let version: i64 = conn.query_row("PRAGMA main.user_version", [], |row| row.get(0))?;
+            conn.execute_batch(&format!("PRAGMA converted.user_version = {};", version))
+        })
+        .map_err(|e| AppError::database(format!("Failed to convert database: {}", e)));
+
+    conn.execute_batch("DETACH DATABASE converted;")
+        .map_err(|e| AppError::database(format!("Failed to detach converted database: {}", e)))?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clips::{self, ClipData, ClipUpdate};
+
+    const THREADS: usize = 8;
+    const WRITES_PER_THREAD: usize = 25;
+
+    /// A fresh clips.db in the temp dir, removed with its WAL files when dropped
+    struct TestDb {
+        db: Database,
+        dir: PathBuf,
+    }
+
+    impl TestDb {
+        fn open(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("los-db-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            let db = Database::open(&dir.join("clips.db"), None).unwrap();
+            Self { db, dir }
+        }
+    }
+
+    impl Drop for TestDb {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn note(title: String) -> ClipData {
+        ClipData {
+            r#type: "note".to_string(),
+            content: Some(format!("Content of {}", title)),
+            title,
+            url: None,
+            image_url: None,
+            description: None,
+            author: None,
+            timestamp: 1_700_000_000_000,
+            original_url: None,
+            language: None,
+        }
+    }
+
+    fn clip_count(db: &Database) -> i64 {
+        db.conn()
+            .unwrap()
+            .query_row("SELECT count(*) FROM clips", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn connections_use_wal_and_busy_timeout() {
+        let test = TestDb::open("pragmas");
+        let conn = test.db.conn().unwrap();
+        let journal_mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(journal_mode, "wal");
+        let busy_timeout: i64 = conn.query_row("PRAGMA busy_timeout", [], |row| row.get(0)).unwrap();
+        assert_eq!(busy_timeout, BUSY_TIMEOUT.as_millis() as i64);
+    }
+
+    #[test]
+    fn concurrent_inserts_all_land() {
+        let test = TestDb::open("inserts");
+        thread::scope(|scope| {
+            for t in 0..THREADS {
+                let db = &test.db;
+                scope.spawn(move || {
+                    for i in 0..WRITES_PER_THREAD {
+                        let clip = note(format!("Note {} from thread {}", i, t));
+                        db.write(move |conn| clips::insert_clip(conn, &clip)).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(clip_count(&test.db), (THREADS * WRITES_PER_THREAD) as i64);
+    }
+
+    #[test]
+    fn concurrent_updates_of_one_clip_are_serialized() {
+        let test = TestDb::open("updates");
+        let id = test.db.write(|conn| clips::insert_clip(conn, &note("Shared".to_string()))).unwrap().id;
+        thread::scope(|scope| {
+            for t in 0..THREADS {
+                let db = &test.db;
+                scope.spawn(move || {
+                    for i in 0..WRITES_PER_THREAD {
+                        let changes = ClipUpdate {
+                            title: Some(format!("Edit {} from thread {}", i, t)),
+                            ..Default::default()
+                        };
+                        db.write(move |conn| clips::update_clip(conn, id, changes)).unwrap();
+                    }
+                });
+            }
+        });
+        let clip = clips::get_clip(&test.db.conn().unwrap(), id).unwrap().unwrap();
+        assert!(clip.title.starts_with("Edit "));
+    }
+
+    #[test]
+    fn optimistic_updates_conflict_instead_of_locking() {
+        let test = TestDb::open("conflicts");
+        let clip = test.db.write(|conn| clips::insert_clip(conn, &note("Contended".to_string()))).unwrap();
+        // Every thread saw the same version; exactly one edit can win
+        let results = thread::scope(|scope| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let db = &test.db;
+                    let (id, expected) = (clip.id, clip.updated_at);
+                    scope.spawn(move || {
+                        let changes = ClipUpdate {
+                            title: Some(format!("Edit from thread {}", t)),
+                            expected_updated_at: Some(expected),
+                            ..Default::default()
+                        };
+                        db.write(move |conn| clips::update_clip(conn, id, changes))
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Vec<_>>()
+        });
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        for result in results.iter().filter_map(|result| result.as_ref().err()) {
+            assert!(matches!(result, AppError::Validation { .. }), "unexpected error: {}", result);
+        }
+    }
+
+    #[test]
+    fn pooled_writes_wait_for_the_writer() {
+        let test = TestDb::open("mixed");
+        thread::scope(|scope| {
+            for t in 0..THREADS {
+                let db = &test.db;
+                scope.spawn(move || {
+                    for i in 0..WRITES_PER_THREAD {
+                        let clip = note(format!("Note {} from thread {}", i, t));
+                        // Half the threads bypass the writer, as background jobs do
+                        if t % 2 == 0 {
+                            db.write(move |conn| clips::insert_clip(conn, &clip)).unwrap();
+                        } else {
+                            clips::insert_clip(&db.conn().unwrap(), &clip).unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(clip_count(&test.db), (THREADS * WRITES_PER_THREAD) as i64);
+    }
+}
@@ -0,0 +1,222 @@
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::error;
+
+use crate::clips::{now_millis, ClipData};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract::plain_text;
+use crate::jobs::{JobKind, JobQueue};
+use crate::llm;
+use crate::notifications::{self, NotificationKind};
+use crate::providers::{LlmError, LlmMessage};
+use crate::settings::SettingsManager;
+
+/// Clips covered by one digest; a busy day's remainder is only counted
+const MAX_DIGEST_CLIPS: u32 = 30;
+/// Unread clips offered instead when nothing was saved in the last day
+const MAX_BACKLOG_CLIPS: u32 = 10;
+/// Text of each clip sent to the model when it has no summary
+const MAX_EXCERPT_CHARS: usize = 600;
+const DIGEST_MAX_TOKENS: u32 = 1_500;
+const DAY_MILLIS: i64 = 24 * 3_600_000;
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// A clip going into the digest
+struct DigestItem {
+    id: i64,
+    title: String,
+    url: Option<String>,
+    text: String,
+}
+
+/// Shape we ask the model to reply with
+#[derive(Debug, Deserialize)]
+struct DigestReply {
+    overview: String,
+    #[serde(default)]
+    items: Vec<DigestLine>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DigestLine {
+    id: i64,
+    line: String,
+}
+
+fn digest_items(conn: &Connection, sql: &str, values: impl rusqlite::Params) -> Result<Vec<DigestItem>, AppError> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let items = stmt
+        .query_map(values, |row| {
+            let summary: Option<String> = row.get(3)?;
+            let description: Option<String> = row.get(4)?;
+            let content: Option<String> = row.get(5)?;
+            let text = summary
+                .or(description)
+                .or(content.map(|content| plain_text(&content)))
+                .unwrap_or_default()
+                .chars()
+                .take(MAX_EXCERPT_CHARS)
+                .collect();
+            Ok(DigestItem {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                url: row.get(2)?,
+                text,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to read clips: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read clips: {}", e)))?;
+    Ok(items)
+}
+
+/// Clips saved in the last 24 hours, or the oldest unread ones when there are none.
+/// The flag says whether the backlog was used.
+fn gather(conn: &Connection) -> Result<(Vec<DigestItem>, bool), AppError> {
+    let recent = digest_items(
+        conn,
+        "SELECT id, title, url, summary, description, content FROM clips
+         WHERE timestamp >= ?1 AND type != 'digest' AND deleted_at IS NULL
+         ORDER BY timestamp DESC LIMIT ?2",
+        params![now_millis() - DAY_MILLIS, MAX_DIGEST_CLIPS],
+    )?;
+    if !recent.is_empty() {
+        return Ok((recent, false));
+    }
+    let backlog = digest_items(
+        conn,
+        "SELECT id, title, url, summary, description, content FROM clips
+         WHERE read_state = 'unread' AND type != 'digest' AND deleted_at IS NULL
+         ORDER BY timestamp LIMIT ?1",
+        params![MAX_BACKLOG_CLIPS],
+    )?;
+    Ok((backlog, true))
+}
+
+/// Today's date in local time, as SQLite sees it
+fn local_date(conn: &Connection) -> Result<String, AppError> {
+    conn.query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to read the date: {}", e)))
+}
+
+/// Compile the last day's clips (or the unread backlog) into a digest clip: an
+/// overview written by the default model, then a one-line note per clip. Returns the
+/// digest clip's id.
+pub async fn generate_digest(app_handle: &AppHandle) -> Result<i64, LlmError> {
+    let (items, from_backlog, date) = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let (items, from_backlog) = gather(&conn)?;
+        (items, from_backlog, local_date(&conn)?)
+    };
+    if items.is_empty() {
+        return Err("Nothing to put in a digest: no new or unread clips".into());
+    }
+
+    let listing: Vec<String> = items
+        .iter()
+        .map(|item| format!("id {}: {}\n{}", item.id, item.title, item.text))
+        .collect();
+    let intro = if from_backlog {
+        "These clips are waiting in the user's reading list."
+    } else {
+        "The user saved these clips in the last day."
+    };
+    let messages = vec![
+        LlmMessage {
+            role: "system".to_string(),
+            content: "You write a short daily digest of saved web clips. Reply with only a JSON object \
+                      like {\"overview\": \"...\", \"items\": [{\"id\": 1, \"line\": \"...\"}]}: an overview \
+                      of two to four sentences tying the clips together, and one line per clip saying \
+                      why it's worth reading."
+                .to_string(),
+        },
+        LlmMessage {
+            role: "user".to_string(),
+            content: format!("{}\n\n{}", intro, listing.join("\n\n")),
+        },
+    ];
+    let reply = llm::complete_with_default(app_handle, "digest", messages, Some(DIGEST_MAX_TOKENS)).await?;
+    let digest: DigestReply = llm::extract_json(&reply.content, "digest")?;
+
+    let lines: Vec<String> = items
+        .iter()
+        .map(|item| {
+            let note = digest
+                .items
+                .iter()
+                .find(|line| line.id == item.id)
+                .map(|line| format!(" — {}", line.line.trim()))
+                .unwrap_or_default();
+            match &item.url {
+                Some(url) => format!("- [{}]({}){}", item.title, url, note),
+                None => format!("- {} (clip {}){}", item.title, item.id, note),
+            }
+        })
+        .collect();
+    let heading = if from_backlog { "From your reading list" } else { "Saved today" };
+    let title = format!("Digest for {}", date);
+    let digest_clip = ClipData {
+        r#type: "digest".to_string(),
+        title: title.clone(),
+        url: None,
+        content: Some(format!("{}\n\n## {}\n\n{}", digest.overview.trim(), heading, lines.join("\n"))),
+        image_url: None,
+        description: Some(digest.overview.trim().to_string()),
+        author: None,
+        timestamp: now_millis() as u64,
+        original_url: None,
+        language: None,
+    };
+    let clip_id = crate::ingest_clip(app_handle, digest_clip)?.clip.id;
+
+    if app_handle.state::<SettingsManager>().get().digest_notification {
+        notifications::notify(app_handle, NotificationKind::Digest, &title, digest.overview.trim());
+    }
+    Ok(clip_id)
+}
+
+/// Whether it's past `hour` local time and today's digest hasn't been made yet
+fn digest_due(conn: &Connection, hour: u8) -> Result<bool, AppError> {
+    conn.query_row(
+        "SELECT CAST(strftime('%H', 'now', 'localtime') AS INTEGER) >= ?1
+            AND NOT EXISTS(SELECT 1 FROM clips WHERE type = 'digest' AND deleted_at IS NULL
+                           AND date(timestamp / 1000, 'unixepoch', 'localtime') = date('now', 'localtime'))",
+        params![hour],
+        |row| row.get(0),
+    )
+    .map_err(|e| AppError::database(format!("Failed to check the digest schedule: {}", e)))
+}
+
+/// Queue a digest once a day at the `digest_hour` setting, while it's set
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // A digest that keeps failing is tried once per day, not every check
+        let mut queued_on: Option<String> = None;
+        loop {
+            if let Some(hour) = app_handle.state::<SettingsManager>().get().digest_hour {
+                let due = {
+                    let db = app_handle.state::<Database>();
+                    db.conn().and_then(|conn| Ok((digest_due(&conn, hour)?, local_date(&conn)?)))
+                };
+                match due {
+                    Ok((true, today)) if queued_on.as_deref() != Some(today.as_str()) => {
+                        let queue = app_handle.state::<JobQueue>();
+                        match queue.submit(&app_handle, JobKind::GenerateDigest) {
+                            Ok(_) => queued_on = Some(today),
+                            Err(e) => error!("Failed to queue the daily digest: {}", e),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("{}", e),
+                }
+            }
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,385 @@
+use mailparse::{DispositionType, MailHeaderMap, ParsedMail};
+use native_tls::TlsConnector;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tracing::{error, info, warn};
+
+use crate::clips::{now_millis, ClipData};
+use crate::config::AppConfig;
+use crate::connectivity;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract;
+use crate::secrets::SecretsManager;
+use crate::settings::SettingsManager;
+
+/// Secret holding the IMAP password, or an app password for Gmail and similar
+pub const IMAP_PASSWORD_SECRET: &str = "imap_password";
+const DEFAULT_IMAP_PORT: u16 = 993;
+const DEFAULT_FOLDER: &str = "LOS";
+const DEFAULT_POLL_MINUTES: u32 = 15;
+/// Larger attachments are left in the mailbox
+const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+/// Messages clipped per check; the rest wait for the next one
+const MAX_MESSAGES_PER_CHECK: usize = 50;
+/// Subfolder of the media dir holding email attachments
+const ATTACHMENTS_DIR: &str = "attachments";
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Set while a check runs so the scheduler and `check_email_now` don't clip a message twice
+static CHECKING: AtomicBool = AtomicBool::new(false);
+
+/// Mailbox watched for emails to clip, set in `Settings::email_inbox`. The password
+/// is the `IMAP_PASSWORD_SECRET` secret.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmailInbox {
+    pub host: String,
+    /// Defaults to `DEFAULT_IMAP_PORT` (IMAP over TLS)
+    pub port: Option<u16>,
+    pub username: String,
+    /// Folder or Gmail label to watch; defaults to `DEFAULT_FOLDER`
+    pub folder: Option<String>,
+    /// How often to check for new mail; defaults to `DEFAULT_POLL_MINUTES`
+    pub poll_minutes: Option<u32>,
+}
+
+impl EmailInbox {
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(DEFAULT_IMAP_PORT)
+    }
+
+    pub fn folder(&self) -> &str {
+        self.folder.as_deref().unwrap_or(DEFAULT_FOLDER)
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        let minutes = self.poll_minutes.unwrap_or(DEFAULT_POLL_MINUTES);
+        Duration::from_secs(minutes as u64 * 60)
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.host.trim().is_empty() {
+            return Err(AppError::validation("Enter the IMAP server of the email inbox"));
+        }
+        if self.username.trim().is_empty() {
+            return Err(AppError::validation("Enter the user name of the email inbox"));
+        }
+        if self.folder.as_ref().is_some_and(|folder| folder.trim().is_empty()) {
+            return Err(AppError::validation("The email folder can't be empty"));
+        }
+        if self.poll_minutes == Some(0) {
+            return Err(AppError::validation("Email can be checked at most once a minute"));
+        }
+        Ok(())
+    }
+}
+
+/// A file that came attached to an emailed clip
+#[derive(Debug, Serialize)]
+pub struct ClipAttachment {
+    pub clip_id: i64,
+    /// Name the sender gave the file
+    pub file_name: String,
+    /// Absolute path of the stored file
+    pub path: PathBuf,
+    pub hash: String,
+    pub size: u64,
+    pub media_type: String,
+}
+
+/// Result of one check of the inbox
+#[derive(Debug, Default, Serialize)]
+pub struct EmailCheck {
+    /// Clips created or merged from new messages
+    pub clipped: Vec<i64>,
+    /// Messages with nothing to clip or rejected by a rule; marked read all the same
+    pub skipped: u32,
+    /// Messages left unread to be tried again next time
+    pub failed: u32,
+}
+
+struct Attachment {
+    file_name: String,
+    media_type: String,
+    bytes: Vec<u8>,
+}
+
+/// The parts of a message that make up a clip
+#[derive(Default)]
+struct EmailBody {
+    html: Option<String>,
+    text: Option<String>,
+    attachments: Vec<Attachment>,
+}
+
+/// Walk the MIME tree for the first HTML and plain-text bodies and any attachments.
+/// Forwarded messages sent as attachments are opened up like the outer one.
+fn collect_parts(part: &ParsedMail, body: &mut EmailBody) -> Result<(), mailparse::MailParseError> {
+    if !part.subparts.is_empty() {
+        for subpart in &part.subparts {
+            collect_parts(subpart, body)?;
+        }
+        return Ok(());
+    }
+
+    let media_type = part.ctype.mimetype.to_lowercase();
+    if media_type == "message/rfc822" {
+        let raw = part.get_body_raw()?;
+        return collect_parts(&mailparse::parse_mail(&raw)?, body);
+    }
+    let disposition = part.get_content_disposition();
+    if matches!(disposition.disposition, DispositionType::Attachment) {
+        let file_name = disposition
+            .params
+            .get("filename")
+            .or_else(|| part.ctype.params.get("name"))
+            .cloned()
+            .unwrap_or_else(|| "attachment".to_string());
+        body.attachments.push(Attachment {
+            file_name,
+            media_type,
+            bytes: part.get_body_raw()?,
+        });
+    } else if media_type == "text/html" && body.html.is_none() {
+        body.html = Some(part.get_body()?);
+    } else if media_type == "text/plain" && body.text.is_none() {
+        body.text = Some(part.get_body()?);
+    }
+    Ok(())
+}
+
+/// The display name of a From header, or the address when there is none
+fn sender_name(from: &str) -> String {
+    match from.split_once('<') {
+        Some((name, address)) => {
+            let name = name.trim().trim_matches('"').trim();
+            if name.is_empty() {
+                address.trim_end_matches('>').trim().to_string()
+            } else {
+                name.to_string()
+            }
+        }
+        None => from.trim().to_string(),
+    }
+}
+
+/// Write an attachment into `dir`, named by content hash. Returns the stored file name and hash.
+fn store_attachment(attachment: &Attachment, dir: &Path) -> Result<(String, String), AppError> {
+    let hash: String = Sha256::digest(&attachment.bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    let extension = Path::new(&attachment.file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(str::to_lowercase)
+        .unwrap_or_else(|| "bin".to_string());
+    let stored_name = format!("{}.{}", hash, extension);
+    let path = dir.join(&stored_name);
+    if !path.exists() {
+        let tmp = dir.join(format!(".{}.part", stored_name));
+        fs::write(&tmp, &attachment.bytes).map_err(|e| AppError::internal(format!("Failed to write attachment: {}", e)))?;
+        fs::rename(&tmp, &path).map_err(|e| AppError::internal(format!("Failed to write attachment: {}", e)))?;
+    }
+    Ok((stored_name, hash))
+}
+
+/// Turn one raw message into an article clip, storing its attachments in the media dir.
+/// Returns the clip id.
+fn clip_message(app_handle: &AppHandle, raw: &[u8]) -> Result<i64, AppError> {
+    let mail = mailparse::parse_mail(raw).map_err(|e| AppError::validation(format!("Unreadable email: {}", e)))?;
+    let mut body = EmailBody::default();
+    collect_parts(&mail, &mut body).map_err(|e| AppError::validation(format!("Unreadable email: {}", e)))?;
+
+    // Newsletters are mostly HTML; their plain-text part is often a stub
+    let content = body
+        .html
+        .as_deref()
+        .map(extract::html_text)
+        .filter(|text| !text.trim().is_empty())
+        .or(body.text.map(|text| text.trim().to_string()))
+        .filter(|text| !text.is_empty());
+    if content.is_none() && body.attachments.is_empty() {
+        return Err(AppError::validation("Email has no text or attachments"));
+    }
+
+    let subject = mail.headers.get_first_value("Subject").map(|s| s.trim().to_string());
+    let timestamp = mail
+        .headers
+        .get_first_value("Date")
+        .and_then(|date| mailparse::dateparse(&date).ok())
+        .map(|seconds| seconds * 1000)
+        .unwrap_or_else(now_millis);
+    let clip_data = ClipData {
+        r#type: "article".to_string(),
+        title: subject.filter(|s| !s.is_empty()).unwrap_or_else(|| "(no subject)".to_string()),
+        url: None,
+        content,
+        image_url: None,
+        description: None,
+        author: mail.headers.get_first_value("From").map(|from| sender_name(&from)),
+        timestamp: timestamp as u64,
+        original_url: None,
+        language: None,
+    };
+    let clip_id = crate::ingest_clip(app_handle, clip_data)?.clip.id;
+
+    let dir = app_handle.state::<AppConfig>().media_dir().join(ATTACHMENTS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| AppError::internal(format!("Failed to create attachments folder: {}", e)))?;
+    let db = app_handle.state::<Database>();
+    let conn = db.conn()?;
+    for attachment in &body.attachments {
+        if attachment.bytes.len() > MAX_ATTACHMENT_BYTES {
+            warn!("Skipping attachment {} of clip {}: larger than 25 MB", attachment.file_name, clip_id);
+            continue;
+        }
+        let (stored_name, hash) = store_attachment(attachment, &dir)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO clip_attachments (clip_id, file_name, stored_name, file_hash, size, media_type)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![clip_id, attachment.file_name, stored_name, hash, attachment.bytes.len() as i64, attachment.media_type],
+        )
+        .map_err(|e| AppError::database(format!("Failed to store attachment details: {}", e)))?;
+    }
+    Ok(clip_id)
+}
+
+/// Clip every unread message in `folder`, marking each read once it's clipped
+fn clip_unread<T: Read + Write>(
+    app_handle: &AppHandle,
+    session: &mut imap::Session<T>,
+    folder: &str,
+) -> Result<EmailCheck, AppError> {
+    session
+        .select(folder)
+        .map_err(|e| AppError::not_found(format!("Failed to open mail folder '{}': {}", folder, e)))?;
+    let mut uids: Vec<u32> = session
+        .uid_search("UNSEEN")
+        .map_err(|e| AppError::network(format!("Failed to search mail: {}", e)))?
+        .into_iter()
+        .collect();
+    uids.sort_unstable();
+    uids.truncate(MAX_MESSAGES_PER_CHECK);
+
+    let mut check = EmailCheck::default();
+    for uid in uids {
+        // PEEK leaves the message unread until it's clipped
+        let fetches = session
+            .uid_fetch(uid.to_string(), "BODY.PEEK[]")
+            .map_err(|e| AppError::network(format!("Failed to fetch mail: {}", e)))?;
+        let Some(raw) = fetches.iter().next().and_then(|fetch| fetch.body()) else {
+            check.failed += 1;
+            continue;
+        };
+        match clip_message(app_handle, raw) {
+            Ok(clip_id) => check.clipped.push(clip_id),
+            // Empty mail or mail a rule skips won't get better on the next try
+            Err(AppError::Validation { message }) => {
+                info!("Not clipping email {}: {}", uid, message);
+                check.skipped += 1;
+            }
+            Err(e) => {
+                error!("Failed to clip email {}: {}", uid, e);
+                check.failed += 1;
+                continue;
+            }
+        }
+        session
+            .uid_store(uid.to_string(), "+FLAGS (\\Seen)")
+            .map_err(|e| AppError::network(format!("Failed to mark mail as read: {}", e)))?;
+    }
+    Ok(check)
+}
+
+fn check_inbox(app_handle: &AppHandle, inbox: &EmailInbox, password: &str) -> Result<EmailCheck, AppError> {
+    let tls = TlsConnector::new().map_err(|e| AppError::internal(format!("Failed to set up TLS: {}", e)))?;
+    let client = imap::connect((inbox.host.trim(), inbox.port()), inbox.host.trim(), &tls)
+        .map_err(|e| AppError::network(format!("Failed to connect to {}: {}", inbox.host, e)))?;
+    let mut session = client
+        .login(inbox.username.trim(), password)
+        .map_err(|(e, _)| AppError::auth(format!("IMAP login failed: {}", e)))?;
+    let check = clip_unread(app_handle, &mut session, inbox.folder());
+    if let Err(e) = session.logout() {
+        warn!("IMAP logout failed: {}", e);
+    }
+    check
+}
+
+/// Clip the unread messages in the configured inbox folder now
+pub async fn check_email(app_handle: &AppHandle) -> Result<EmailCheck, AppError> {
+    let inbox = app_handle
+        .state::<SettingsManager>()
+        .get()
+        .email_inbox
+        .ok_or_else(|| AppError::validation("Email clipping is not set up"))?;
+    let password = app_handle
+        .state::<SecretsManager>()
+        .get_secret(IMAP_PASSWORD_SECRET, "check_email")
+        .await
+        .map_err(|e| match e {
+            AppError::NotFound { .. } => AppError::auth(format!("No {} stored; add it in settings", IMAP_PASSWORD_SECRET)),
+            other => other,
+        })?;
+
+    if CHECKING.swap(true, Ordering::SeqCst) {
+        return Err(AppError::validation("Already checking email"));
+    }
+    let handle = app_handle.clone();
+    let check = tauri::async_runtime::spawn_blocking(move || check_inbox(&handle, &inbox, &password)).await;
+    CHECKING.store(false, Ordering::SeqCst);
+    let check = check.map_err(|e| AppError::internal(format!("Email check failed: {}", e)))??;
+    if !check.clipped.is_empty() {
+        info!("Clipped {} emails", check.clipped.len());
+    }
+    Ok(check)
+}
+
+/// Files attached to the email a clip came from
+pub fn list_attachments(conn: &Connection, media_dir: &Path, clip_id: i64) -> Result<Vec<ClipAttachment>, AppError> {
+    let dir = media_dir.join(ATTACHMENTS_DIR);
+    let mut stmt = conn
+        .prepare(
+            "SELECT file_name, stored_name, file_hash, size, media_type FROM clip_attachments
+             WHERE clip_id = ?1 ORDER BY id",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let attachments = stmt
+        .query_map(params![clip_id], |row| {
+            Ok(ClipAttachment {
+                clip_id,
+                file_name: row.get(0)?,
+                path: dir.join(row.get::<_, String>(1)?),
+                hash: row.get(2)?,
+                size: row.get::<_, i64>(3)? as u64,
+                media_type: row.get(4)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to read attachments: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read attachments: {}", e)))?;
+    Ok(attachments)
+}
+
+/// Check the inbox every `poll_minutes` while `email_inbox` is set and the app is online
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_check: Option<Instant> = None;
+        loop {
+            if let Some(inbox) = app_handle.state::<SettingsManager>().get().email_inbox {
+                let due = last_check.map_or(true, |at| at.elapsed() >= inbox.poll_interval());
+                if due && connectivity::is_online() {
+                    last_check = Some(Instant::now());
+                    if let Err(e) = check_email(&app_handle).await {
+                        error!("Scheduled email check failed: {}", e);
+                    }
+                }
+            }
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+        }
+    });
+}
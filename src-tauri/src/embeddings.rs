@@ -0,0 +1,226 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+use crate::clips::{self, ClipFilter, SqliteClip};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract::plain_text;
+use crate::notifications;
+use crate::providers::{embed_api, LlmError, ModelSelection, ProviderRegistry};
+use crate::secrets::SecretsManager;
+use crate::settings::SettingsManager;
+
+/// Text embedded per clip; longer content is cut off to stay under model input limits
+const MAX_EMBED_CHARS: usize = 8_000;
+/// Clips sent per embeddings request
+pub const BATCH_SIZE: usize = 16;
+
+#[derive(Debug, Serialize)]
+pub struct SemanticHit {
+    pub clip: SqliteClip,
+    /// Cosine similarity to the query, 1.0 being identical
+    pub score: f32,
+}
+
+pub fn embedding_model(app_handle: &AppHandle) -> Result<ModelSelection, AppError> {
+    app_handle
+        .state::<SettingsManager>()
+        .get()
+        .embedding_model
+        .ok_or_else(|| AppError::validation("No embedding model configured; choose one in settings"))
+}
+
+/// Embed `texts` with the configured embedding model
+async fn embed(app_handle: &AppHandle, selection: &ModelSelection, texts: &[String]) -> Result<Vec<Vec<f32>>, LlmError> {
+    let registry = ProviderRegistry::from_settings(&app_handle.state::<SettingsManager>().get());
+    let secrets_manager = app_handle.state::<SecretsManager>();
+    let vectors = embed_api(&secrets_manager, &registry, &selection.provider, &selection.model, texts, "embeddings")
+        .await
+        .inspect_err(|e| notifications::notify_llm_error(app_handle, &selection.provider, e))?;
+    Ok(vectors.into_iter().map(normalize).collect())
+}
+
+/// Scale to unit length so cosine similarity is a plain dot product
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+fn clip_text(clip: &SqliteClip) -> String {
+    let body = clip.content.as_deref().map(plain_text).unwrap_or_default();
+    let parts = [
+        Some(clip.title.as_str()),
+        clip.summary.as_deref(),
+        clip.description.as_deref(),
+        Some(body.as_str()),
+    ];
+    let text = parts.iter().flatten().filter(|p| !p.is_empty()).cloned().collect::<Vec<_>>().join("\n\n");
+    text.chars().take(MAX_EMBED_CHARS).collect()
+}
+
+/// Fingerprint of the text an embedding was computed from, to spot clips whose summary
+/// or content changed without an edit bumping `updated_at`
+fn text_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn store_embedding(
+    conn: &Connection,
+    clip: &SqliteClip,
+    model: &str,
+    text: &str,
+    vector: &[f32],
+) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO clip_embeddings (clip_id, model, dimensions, vector, clip_updated_at, text_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (clip_id) DO UPDATE SET
+            model = excluded.model, dimensions = excluded.dimensions, vector = excluded.vector,
+            clip_updated_at = excluded.clip_updated_at, text_hash = excluded.text_hash",
+        params![clip.id, model, vector.len() as i64, to_blob(vector), clip.updated_at, text_hash(text)],
+    )
+    .map_err(|e| AppError::database(format!("Failed to store embedding: {}", e)))?;
+    Ok(())
+}
+
+/// Clips with no embedding for `model`, or whose embedding predates their last edit.
+/// Private clips are never embedded.
+pub fn stale_clip_ids(conn: &Connection, model: &str, limit: u32) -> Result<Vec<i64>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT c.id FROM clips c
+             LEFT JOIN clip_embeddings e ON e.clip_id = c.id AND e.model = ?1
+             WHERE c.deleted_at IS NULL AND c.private = 0
+                AND (e.clip_id IS NULL OR e.clip_updated_at != COALESCE(c.updated_at, 0))
+             ORDER BY c.timestamp DESC, c.id DESC LIMIT ?2",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let ids = stmt
+        .query_map(params![model, limit], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to execute query: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+    Ok(ids)
+}
+
+/// Clips whose embedding for `model` is missing or was computed from text that has
+/// since changed. Embeddings stored before text hashes were kept fall back to
+/// comparing `updated_at`, like `stale_clip_ids`.
+pub fn outdated_clip_ids(conn: &Connection, model: &str) -> Result<Vec<i64>, AppError> {
+    let mut stmt = conn
+        .prepare("SELECT clip_id, text_hash, clip_updated_at FROM clip_embeddings WHERE model = ?1")
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let embedded: HashMap<i64, (Option<String>, i64)> = stmt
+        .query_map(params![model], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+        .map_err(|e| AppError::database(format!("Failed to read embeddings: {}", e)))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read embedding: {}", e)))?;
+
+    let mut ids = Vec::new();
+    let filter = ClipFilter {
+        private: Some(false),
+        ..Default::default()
+    };
+    clips::for_each_clip(conn, &filter, |clip| {
+        let outdated = match embedded.get(&clip.id) {
+            None => true,
+            Some((Some(hash), _)) => *hash != text_hash(&clip_text(&clip)),
+            Some((None, clip_updated_at)) => *clip_updated_at != clip.updated_at,
+        };
+        if outdated {
+            ids.push(clip.id);
+        }
+        Ok(())
+    })?;
+    Ok(ids)
+}
+
+/// Compute and store embeddings for the given clips, in batches of `BATCH_SIZE`.
+/// Private clips among them are skipped.
+pub async fn index_clips(app_handle: &AppHandle, ids: &[i64]) -> Result<usize, LlmError> {
+    let selection = embedding_model(app_handle)?;
+    let mut indexed = 0;
+
+    for batch in ids.chunks(BATCH_SIZE) {
+        let mut clips: Vec<SqliteClip> = {
+            let db = app_handle.state::<Database>();
+            let conn = db.conn()?;
+            batch
+                .iter()
+                .filter_map(|id| clips::get_clip(&conn, *id).transpose())
+                .collect::<Result<_, _>>()?
+        };
+        clips.retain(|clip| !clip.private);
+        if clips.is_empty() {
+            continue;
+        }
+
+        let texts: Vec<String> = clips.iter().map(clip_text).collect();
+        let vectors = embed(app_handle, &selection, &texts).await?;
+
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        for ((clip, text), vector) in clips.iter().zip(&texts).zip(&vectors) {
+            store_embedding(&conn, clip, &selection.model, text, vector)?;
+        }
+        indexed += clips.len();
+    }
+    Ok(indexed)
+}
+
+/// The `k` clips closest in meaning to `query`. Only clips embedded with the
+/// current model are searched; run `index_clip_embeddings` to cover the rest.
+pub async fn semantic_search(app_handle: &AppHandle, query: &str, k: usize) -> Result<Vec<SemanticHit>, LlmError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let selection = embedding_model(app_handle)?;
+    let query_vector = embed(app_handle, &selection, &[query.to_string()])
+        .await?
+        .pop()
+        .ok_or("No embedding returned for query")?;
+
+    let db = app_handle.state::<Database>();
+    let conn = db.conn()?;
+    let mut stmt = conn
+        .prepare("SELECT clip_id, vector FROM clip_embeddings WHERE model = ?1 AND dimensions = ?2")
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let mut scored: Vec<(i64, f32)> = stmt
+        .query_map(params![selection.model, query_vector.len() as i64], |row| {
+            let blob: Vec<u8> = row.get(1)?;
+            Ok((row.get(0)?, from_blob(&blob)))
+        })
+        .map_err(|e| AppError::database(format!("Failed to read embeddings: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read embedding: {}", e)))?
+        .into_iter()
+        .map(|(id, vector)| (id, vector.iter().zip(&query_vector).map(|(a, b)| a * b).sum()))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(k);
+
+    let mut hits = Vec::with_capacity(scored.len());
+    for (id, score) in scored {
+        if let Some(clip) = clips::get_clip(&conn, id)?.filter(|clip| clip.deleted_at.is_none()) {
+            hits.push(SemanticHit { clip, score });
+        }
+    }
+    Ok(hits)
+}
@@ -0,0 +1,417 @@
+use image::ImageOutputFormat;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::clips::{self, ClipFilter, SqliteClip};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract::plain_text;
+use crate::media;
+use crate::settings::SettingsManager;
+use crate::smtp::{self, MailAttachment};
+
+/// Books with more chapters than this get unwieldy on e-readers
+const MAX_CHAPTERS: usize = 500;
+/// Largest attachment Amazon's Send to Kindle accepts by email
+const MAX_KINDLE_BYTES: u64 = 50 * 1024 * 1024;
+/// Longest edge of the cover image
+const COVER_SIZE: u32 = 1600;
+const STYLESHEET: &str = "body { font-family: serif; line-height: 1.5; }
+h1 { font-size: 1.4em; margin-bottom: 0.2em; }
+p.byline { font-style: italic; margin-top: 0; }
+p.source { font-size: 0.8em; word-break: break-all; }
+";
+
+/// Result of `export_epub`
+#[derive(Debug, Serialize)]
+pub struct EpubExport {
+    pub path: PathBuf,
+    pub title: String,
+    pub chapters: u32,
+    /// Whether a clip's image was used as the cover
+    pub cover: bool,
+    /// Address the book was emailed to, if it was sent
+    pub sent_to: Option<String>,
+}
+
+/// One clip as a chapter of the book
+struct Chapter {
+    title: String,
+    author: Option<String>,
+    url: Option<String>,
+    paragraphs: Vec<String>,
+}
+
+impl Chapter {
+    /// Clips with no readable text are left out of the book
+    fn from_clip(clip: &SqliteClip) -> Option<Chapter> {
+        let text = clip
+            .content
+            .as_deref()
+            .map(plain_text)
+            .filter(|text| !text.trim().is_empty())
+            .or(clip.summary.clone())
+            .or(clip.description.clone())?;
+        let paragraphs: Vec<String> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        (!paragraphs.is_empty()).then(|| Chapter {
+            title: clip.title.clone(),
+            author: clip.author.clone(),
+            url: clip.url.clone(),
+            paragraphs,
+        })
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn xhtml_page(title: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<!DOCTYPE html>
+<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">
+<head><title>{}</title><link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/></head>
+<body>
+{}
+</body>
+</html>
+",
+        escape(title),
+        body
+    )
+}
+
+fn chapter_page(chapter: &Chapter) -> String {
+    let mut body = format!("<h1>{}</h1>\n", escape(&chapter.title));
+    if let Some(author) = &chapter.author {
+        body.push_str(&format!("<p class=\"byline\">{}</p>\n", escape(author)));
+    }
+    for paragraph in &chapter.paragraphs {
+        body.push_str(&format!("<p>{}</p>\n", escape(paragraph)));
+    }
+    if let Some(url) = &chapter.url {
+        body.push_str(&format!("<p class=\"source\"><a href=\"{0}\">{0}</a></p>\n", escape(url)));
+    }
+    xhtml_page(&chapter.title, &body)
+}
+
+/// Title page listing the chapters, which doubles as the EPUB 3 navigation document
+fn nav_page(title: &str, chapters: &[Chapter]) -> String {
+    let items: String = chapters
+        .iter()
+        .enumerate()
+        .map(|(i, chapter)| format!("<li><a href=\"chapter-{}.xhtml\">{}</a></li>\n", i + 1, escape(&chapter.title)))
+        .collect();
+    xhtml_page(
+        title,
+        &format!("<h1>{}</h1>\n<nav epub:type=\"toc\" id=\"toc\">\n<ol>\n{}</ol>\n</nav>", escape(title), items),
+    )
+}
+
+/// NCX table of contents, for older readers including Kindle conversion
+fn toc_ncx(identifier: &str, title: &str, chapters: &[Chapter]) -> String {
+    let points: String = chapters
+        .iter()
+        .enumerate()
+        .map(|(i, chapter)| {
+            format!(
+                "<navPoint id=\"chapter-{0}\" playOrder=\"{0}\"><navLabel><text>{1}</text></navLabel><content src=\"chapter-{0}.xhtml\"/></navPoint>\n",
+                i + 1,
+                escape(&chapter.title)
+            )
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<ncx xmlns=\"http://www.daisy.org/z3986/2005/ncx/\" version=\"2005-1\">
+<head><meta name=\"dtb:uid\" content=\"{}\"/></head>
+<docTitle><text>{}</text></docTitle>
+<navMap>
+{}</navMap>
+</ncx>
+",
+        escape(identifier),
+        escape(title),
+        points
+    )
+}
+
+fn package_opf(identifier: &str, title: &str, modified: &str, chapters: &[Chapter], cover: bool) -> String {
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>
+<item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>
+<item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>
+",
+    );
+    let mut spine = String::new();
+    if cover {
+        manifest.push_str(
+            "<item id=\"cover-image\" href=\"cover.jpg\" media-type=\"image/jpeg\" properties=\"cover-image\"/>
+<item id=\"cover\" href=\"cover.xhtml\" media-type=\"application/xhtml+xml\"/>
+",
+        );
+        spine.push_str("<itemref idref=\"cover\" linear=\"no\"/>\n");
+    }
+    spine.push_str("<itemref idref=\"nav\"/>\n");
+    for i in 1..=chapters.len() {
+        manifest.push_str(&format!(
+            "<item id=\"chapter-{0}\" href=\"chapter-{0}.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+            i
+        ));
+        spine.push_str(&format!("<itemref idref=\"chapter-{}\"/>\n", i));
+    }
+    let mut authors: Vec<&str> = chapters.iter().filter_map(|chapter| chapter.author.as_deref()).collect();
+    authors.sort_unstable();
+    authors.dedup();
+    let creator = match authors.as_slice() {
+        [author] => author.to_string(),
+        _ => "LOS".to_string(),
+    };
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">
+<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">
+<dc:identifier id=\"book-id\">{}</dc:identifier>
+<dc:title>{}</dc:title>
+<dc:creator>{}</dc:creator>
+<dc:language>en</dc:language>
+<meta property=\"dcterms:modified\">{}</meta>
+{}</metadata>
+<manifest>
+{}</manifest>
+<spine toc=\"ncx\">
+{}</spine>
+</package>
+",
+        escape(identifier),
+        escape(title),
+        escape(&creator),
+        modified,
+        if cover { "<meta name=\"cover\" content=\"cover-image\"/>\n" } else { "" },
+        manifest,
+        spine
+    )
+}
+
+/// Scale a clip's image down to cover size and re-encode it as JPEG, which every reader shows
+fn cover_jpeg(source: &Path) -> Result<Vec<u8>, AppError> {
+    let bytes = fs::read(source).map_err(|e| AppError::internal(format!("Failed to read cover image: {}", e)))?;
+    let image = image::load_from_memory(&bytes).map_err(|e| AppError::validation(format!("Failed to decode cover image: {}", e)))?;
+    let image = image::DynamicImage::ImageRgb8(image.thumbnail(COVER_SIZE, COVER_SIZE).to_rgb8());
+    let mut encoded = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut encoded), ImageOutputFormat::Jpeg(85))
+        .map_err(|e| AppError::internal(format!("Failed to encode cover image: {}", e)))?;
+    Ok(encoded)
+}
+
+fn write_epub(
+    dest: &Path,
+    identifier: &str,
+    title: &str,
+    modified: &str,
+    chapters: &[Chapter],
+    cover: Option<&[u8]>,
+) -> Result<(), AppError> {
+    let write_error = |e: &dyn std::fmt::Display| AppError::internal(format!("Failed to write {}: {}", dest.display(), e));
+    // Write to a temp file and rename so a failed export never leaves a broken book behind
+    let tmp = dest.with_extension("epub.part");
+    let file = File::create(&tmp).map_err(|e| write_error(&e))?;
+    let mut zip = ZipWriter::new(file);
+    let deflated = FileOptions::default();
+
+    // The mimetype entry must come first and be stored uncompressed
+    zip.start_file("mimetype", FileOptions::default().compression_method(CompressionMethod::Stored))
+        .map_err(|e| write_error(&e))?;
+    zip.write_all(b"application/epub+zip").map_err(|e| write_error(&e))?;
+
+    let mut entries: Vec<(String, Vec<u8>)> = vec![
+        (
+            "META-INF/container.xml".to_string(),
+            b"<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">
+<rootfiles><rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/></rootfiles>
+</container>
+"
+            .to_vec(),
+        ),
+        (
+            "OEBPS/content.opf".to_string(),
+            package_opf(identifier, title, modified, chapters, cover.is_some()).into_bytes(),
+        ),
+        ("OEBPS/nav.xhtml".to_string(), nav_page(title, chapters).into_bytes()),
+        ("OEBPS/toc.ncx".to_string(), toc_ncx(identifier, title, chapters).into_bytes()),
+        ("OEBPS/style.css".to_string(), STYLESHEET.as_bytes().to_vec()),
+    ];
+    if let Some(cover) = cover {
+        entries.push(("OEBPS/cover.jpg".to_string(), cover.to_vec()));
+        entries.push((
+            "OEBPS/cover.xhtml".to_string(),
+            xhtml_page(title, &format!("<img src=\"cover.jpg\" alt=\"{}\" style=\"max-width: 100%\"/>", escape(title)))
+                .into_bytes(),
+        ));
+    }
+    for (i, chapter) in chapters.iter().enumerate() {
+        entries.push((format!("OEBPS/chapter-{}.xhtml", i + 1), chapter_page(chapter).into_bytes()));
+    }
+    for (name, bytes) in entries {
+        zip.start_file(name, deflated).map_err(|e| write_error(&e))?;
+        zip.write_all(&bytes).map_err(|e| write_error(&e))?;
+    }
+    zip.finish().map_err(|e| write_error(&e))?;
+    fs::rename(&tmp, dest).map_err(|e| write_error(&e))?;
+    Ok(())
+}
+
+/// The clips to bundle, in the order given or newest first for a collection
+fn selected_clips(conn: &Connection, clip_ids: Option<&[i64]>, collection_id: Option<i64>) -> Result<Vec<SqliteClip>, AppError> {
+    match (clip_ids, collection_id) {
+        (Some(clip_ids), None) => clip_ids
+            .iter()
+            .map(|id| clips::get_clip(conn, *id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id))))
+            .collect(),
+        (None, Some(collection_id)) => {
+            let filter = ClipFilter {
+                collection_id: Some(collection_id),
+                ..Default::default()
+            };
+            let mut selected = Vec::new();
+            clips::for_each_clip(conn, &filter, |clip| {
+                selected.push(clip);
+                Ok(())
+            })?;
+            Ok(selected)
+        }
+        _ => Err(AppError::validation("Choose either clips or a collection to export")),
+    }
+}
+
+/// Bundle the chosen clips, or every clip in a collection, into one EPUB at `dest` with a
+/// chapter per clip, a table of contents and the first clip image as cover. With
+/// `send_to_kindle` the book is then emailed to `Settings::kindle_email` through
+/// `Settings::smtp_account`.
+pub async fn export_epub(
+    app_handle: &AppHandle,
+    clip_ids: Option<Vec<i64>>,
+    collection_id: Option<i64>,
+    dest: PathBuf,
+    title: Option<String>,
+    send_to_kindle: bool,
+) -> Result<EpubExport, AppError> {
+    let settings = app_handle.state::<SettingsManager>().get();
+    let kindle = if send_to_kindle {
+        let account = settings
+            .smtp_account
+            .clone()
+            .ok_or_else(|| AppError::validation("Set up an SMTP account to send to Kindle"))?;
+        let to = settings
+            .kindle_email
+            .clone()
+            .ok_or_else(|| AppError::validation("Enter your Send to Kindle address in settings"))?;
+        Some((account, to))
+    } else {
+        None
+    };
+
+    let (selected, title, modified) = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let selected = selected_clips(&conn, clip_ids.as_deref(), collection_id)?;
+        let title = match (title.filter(|t| !t.trim().is_empty()), collection_id) {
+            (Some(title), _) => title.trim().to_string(),
+            (None, Some(collection_id)) => conn
+                .query_row("SELECT name FROM collections WHERE id = ?1", params![collection_id], |row| row.get(0))
+                .optional()
+                .map_err(|e| AppError::database(format!("Failed to read collection: {}", e)))?
+                .ok_or_else(|| AppError::not_found(format!("Collection {} not found", collection_id)))?,
+            (None, None) => conn
+                .query_row("SELECT 'Clips ' || date('now', 'localtime')", [], |row| row.get(0))
+                .map_err(|e| AppError::database(format!("Failed to read the date: {}", e)))?,
+        };
+        let modified: String = conn
+            .query_row("SELECT strftime('%Y-%m-%dT%H:%M:%SZ', 'now')", [], |row| row.get(0))
+            .map_err(|e| AppError::database(format!("Failed to read the date: {}", e)))?;
+        (selected, title, modified)
+    };
+
+    let chapters: Vec<Chapter> = selected.iter().filter_map(Chapter::from_clip).collect();
+    if chapters.is_empty() {
+        return Err(AppError::validation("None of the chosen clips have text to put in a book"));
+    }
+    if chapters.len() > MAX_CHAPTERS {
+        return Err(AppError::validation(format!("A book can hold at most {} clips", MAX_CHAPTERS)));
+    }
+
+    // A missing or broken image only costs the cover
+    let mut cover_source = None;
+    if let Some(clip) = selected.iter().find(|clip| clip.image_url.is_some()) {
+        match media::get_clip_image(app_handle, clip.id).await {
+            Ok(image) => cover_source = Some(image.path),
+            Err(e) => warn!("No cover image from clip {}: {}", clip.id, e),
+        }
+    }
+
+    let hash = Sha256::digest(format!("{}\n{}", title, modified)).iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    let identifier = format!("urn:los:{}", &hash[..32]);
+    let chapter_count = chapters.len() as u32;
+    let (path, has_cover) = {
+        let title = title.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let cover = cover_source.and_then(|source| match cover_jpeg(&source) {
+                Ok(cover) => Some(cover),
+                Err(e) => {
+                    warn!("Skipping EPUB cover: {}", e);
+                    None
+                }
+            });
+            write_epub(&dest, &identifier, &title, &modified, &chapters, cover.as_deref()).map(|_| (dest, cover.is_some()))
+        })
+        .await
+        .map_err(|e| AppError::internal(format!("EPUB export failed: {}", e)))??
+    };
+
+    let mut sent_to = None;
+    if let Some((account, to)) = kindle {
+        let size = fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+        if size > MAX_KINDLE_BYTES {
+            return Err(AppError::validation("The book is larger than the 50 MB Send to Kindle accepts"));
+        }
+        let bytes = fs::read(&path).map_err(|e| AppError::internal(format!("Failed to read {}: {}", path.display(), e)))?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "clips.epub".to_string());
+        let attachment = MailAttachment {
+            file_name,
+            media_type: "application/epub+zip".to_string(),
+            bytes,
+        };
+        smtp::send_with_attachment(app_handle, &account, &to, &title, &title, attachment).await?;
+        sent_to = Some(to);
+    }
+
+    Ok(EpubExport {
+        path,
+        title,
+        chapters: chapter_count,
+        cover: has_cover,
+        sent_to,
+    })
+}
@@ -0,0 +1,229 @@
+use serde::ser::{Error as _, SerializeMap};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use tracing::error;
+
+use crate::providers::LlmError;
+use crate::redact;
+
+/// Error returned by every command. Serialized as `{ "code": "not_found", "message": ... }`
+/// so the frontend can branch on `code`; provider errors also carry the `LlmError`
+/// fields (`kind`, `retry_after_secs`, ...).
+#[derive(Debug)]
+pub enum AppError {
+    /// Reading or writing clips.db failed
+    Database { message: String },
+    /// A remote service (other than an LLM provider) couldn't be reached
+    Network { message: String },
+    /// Missing or rejected credentials
+    Auth { message: String },
+    NotFound { message: String },
+    /// The request itself is invalid; the message says what to fix
+    Validation { message: String },
+    /// An LLM provider call failed
+    Provider(LlmError),
+    /// A command ran past its timeout
+    Timeout { message: String },
+    /// The frontend cancelled the command with `cancel_request`
+    Cancelled { message: String },
+    /// The command needs the internet and the app is offline
+    Offline { message: String },
+    /// The app lock is engaged; `unlock_app` first
+    Locked { message: String },
+    /// Anything else: file system, serialization, bugs
+    Internal { message: String },
+}
+
+impl AppError {
+    pub fn database(message: impl Into<String>) -> Self {
+        AppError::Database {
+            message: message.into(),
+        }
+    }
+
+    pub fn network(message: impl Into<String>) -> Self {
+        AppError::Network {
+            message: message.into(),
+        }
+    }
+
+    pub fn auth(message: impl Into<String>) -> Self {
+        AppError::Auth {
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound {
+            message: message.into(),
+        }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        AppError::Validation {
+            message: message.into(),
+        }
+    }
+
+    pub fn timeout(message: impl Into<String>) -> Self {
+        AppError::Timeout {
+            message: message.into(),
+        }
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        AppError::Cancelled {
+            message: message.into(),
+        }
+    }
+
+    pub fn offline(message: impl Into<String>) -> Self {
+        AppError::Offline {
+            message: message.into(),
+        }
+    }
+
+    pub fn locked(message: impl Into<String>) -> Self {
+        AppError::Locked {
+            message: message.into(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::Internal {
+            message: message.into(),
+        }
+    }
+
+    /// Stable identifier the frontend matches on
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Database { .. } => "database",
+            AppError::Network { .. } => "network",
+            AppError::Auth { .. } => "auth",
+            AppError::NotFound { .. } => "not_found",
+            AppError::Validation { .. } => "validation",
+            AppError::Provider(_) => "provider",
+            AppError::Timeout { .. } => "timeout",
+            AppError::Cancelled { .. } => "cancelled",
+            AppError::Offline { .. } => "offline",
+            AppError::Locked { .. } => "locked",
+            AppError::Internal { .. } => "internal",
+        }
+    }
+
+    /// Text fit to show the user. Database and internal details mean nothing to
+    /// them (and can include file paths), so those get a generic message.
+    pub fn user_message(&self) -> String {
+        match self {
+            AppError::Database { .. } => "Something went wrong reading or saving your library".to_string(),
+            AppError::Internal { .. } => "Something went wrong; see the logs for details".to_string(),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Database { message }
+            | AppError::Network { message }
+            | AppError::Auth { message }
+            | AppError::NotFound { message }
+            | AppError::Validation { message }
+            | AppError::Timeout { message }
+            | AppError::Cancelled { message }
+            | AppError::Offline { message }
+            | AppError::Locked { message }
+            | AppError::Internal { message } => f.write_str(message),
+            AppError::Provider(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if matches!(self, AppError::Database { .. } | AppError::Internal { .. }) {
+            // The user only sees the generic message; keep the detail for diagnostics
+            error!("{} error: {}", self.code(), self);
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        // Provider bodies and wrapped errors can echo keys back; scrub them before the frontend sees them
+        map.serialize_entry("message", &redact::redact(&self.user_message()))?;
+        if let AppError::Provider(error) = self {
+            let error = serde_json::to_value(error).map_err(S::Error::custom)?;
+            map.serialize_entry("provider", &redact::redact_json(error))?;
+        }
+        map.end()
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal { message }
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::internal(message)
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::database(e.to_string())
+    }
+}
+
+impl From<r2d2::Error> for AppError {
+    fn from(e: r2d2::Error) -> Self {
+        AppError::database(format!("Failed to get database connection: {}", e))
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        AppError::network(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::internal(e.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        AppError::internal(e.to_string())
+    }
+}
+
+impl From<LlmError> for AppError {
+    fn from(error: LlmError) -> Self {
+        match error {
+            LlmError::Offline { message } => AppError::Offline { message },
+            error => AppError::Provider(error),
+        }
+    }
+}
+
+/// Lets LLM-driven features (summaries, tagging, ...) use `?` on database helpers
+impl From<AppError> for LlmError {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::Provider(error) => error,
+            AppError::Network { message } => LlmError::Network { message },
+            AppError::Auth { message } => LlmError::Auth { message },
+            AppError::Offline { message } => LlmError::Offline { message },
+            other => LlmError::Other {
+                message: other.to_string(),
+            },
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::clips::now_millis;
+
+/// Journaled events kept for `get_events_since`; older ones are dropped
+const JOURNAL_CAPACITY: usize = 1_000;
+/// Emitted after every journaled event so the frontend can track the latest sequence number
+const SEQ_EVENT: &str = "event-seq";
+
+/// A data-changing event as it was emitted to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    pub seq: u64,
+    pub event: String,
+    pub payload: Value,
+    pub emitted_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventsSince {
+    /// Journaled events after the requested sequence number, oldest first
+    pub events: Vec<JournalEntry>,
+    pub latest_seq: u64,
+    /// False when events after the requested one were already dropped from the journal
+    /// (or the app restarted since); the frontend has to refetch instead
+    pub complete: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct SeqPayload<'a> {
+    seq: u64,
+    event: &'a str,
+}
+
+/// Recent data-changing events, so a webview that reloaded can catch up on what it
+/// missed. Managed as Tauri state; kept in memory only.
+#[derive(Default)]
+pub struct EventJournal {
+    inner: Mutex<Journal>,
+}
+
+#[derive(Default)]
+struct Journal {
+    entries: VecDeque<JournalEntry>,
+    latest_seq: u64,
+}
+
+impl EventJournal {
+    fn record(&self, event: &str, payload: Value) -> u64 {
+        let mut journal = self.inner.lock().unwrap();
+        journal.latest_seq += 1;
+        let seq = journal.latest_seq;
+        if journal.entries.len() == JOURNAL_CAPACITY {
+            journal.entries.pop_front();
+        }
+        journal.entries.push_back(JournalEntry {
+            seq,
+            event: event.to_string(),
+            payload,
+            emitted_at: now_millis(),
+        });
+        seq
+    }
+
+    /// Forget every entry and start numbering again, so nothing journaled for one
+    /// profile is replayed in another. Frontends asking for older events get
+    /// `complete: false` and refetch.
+    pub fn clear(&self) {
+        *self.inner.lock().unwrap() = Journal::default();
+    }
+
+    pub fn events_since(&self, seq: u64) -> EventsSince {
+        let journal = self.inner.lock().unwrap();
+        let oldest_seq = journal.entries.front().map_or(journal.latest_seq + 1, |entry| entry.seq);
+        EventsSince {
+            events: journal.entries.iter().filter(|entry| entry.seq > seq).cloned().collect(),
+            latest_seq: journal.latest_seq,
+            complete: seq <= journal.latest_seq && seq + 1 >= oldest_seq,
+        }
+    }
+}
+
+/// Emit an event to the frontend and record it in the `EventJournal`, followed by an
+/// `event-seq` event carrying its sequence number. For events that change app data;
+/// progress updates and the like are emitted directly.
+pub fn emit<S: Serialize + ?Sized>(app_handle: &AppHandle, event: &str, payload: &S) -> Result<(), tauri::Error> {
+    let payload = serde_json::to_value(payload)?;
+    let seq = app_handle.state::<EventJournal>().record(event, payload.clone());
+    app_handle.emit(event, payload)?;
+    app_handle.emit(SEQ_EVENT, SeqPayload { seq, event })
+}
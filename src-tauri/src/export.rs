@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::warn;
+
+use crate::annotations::{self, Annotation};
+use crate::clips::{self, ClipFilter, SqliteClip};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract::plain_text;
+use crate::private::{self, PrivateVault};
+use crate::tags;
+
+/// Emit an `export-progress` event after this many clips
+const PROGRESS_INTERVAL: u32 = 200;
+/// Longest title fragment used in a Markdown file name
+const MAX_SLUG_LEN: usize = 60;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// A directory with one Markdown file per clip, metadata in YAML front matter
+    Markdown,
+    /// A single JSON array
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportProgress {
+    pub exported: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportSummary {
+    pub format: ExportFormat,
+    pub path: PathBuf,
+    pub exported: u32,
+}
+
+/// A clip as written to JSON, with its tags and annotations alongside the stored columns
+#[derive(Serialize)]
+struct ExportedClip<'a> {
+    #[serde(flatten)]
+    clip: &'a SqliteClip,
+    tags: &'a [String],
+    annotations: &'a [Annotation],
+}
+
+/// Write every clip matching `filter` to `dest` in the given format. Runs on a
+/// blocking thread; rows are streamed from the database straight to disk. Private
+/// clips are only exported while they're unlocked.
+pub async fn export_clips(
+    app_handle: &AppHandle,
+    format: ExportFormat,
+    filter: ClipFilter,
+    dest: PathBuf,
+) -> Result<ExportSummary, AppError> {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || write_export(&app_handle, format, &filter, dest))
+        .await
+        .map_err(|e| AppError::internal(format!("Export failed: {}", e)))?
+}
+
+fn write_export(
+    app_handle: &AppHandle,
+    format: ExportFormat,
+    filter: &ClipFilter,
+    dest: PathBuf,
+) -> Result<ExportSummary, AppError> {
+    let db = app_handle.state::<Database>();
+    let conn = db.conn()?;
+    let vault = app_handle.state::<PrivateVault>();
+    let mut filter = filter.clone();
+    if !vault.is_unlocked() {
+        filter.private = Some(false);
+    }
+    let total = clips::count_clips(&conn, &filter)?;
+
+    let mut exported = 0;
+    let report = |exported: u32| {
+        if exported % PROGRESS_INTERVAL == 0 || exported == total {
+            let progress = ExportProgress { exported, total };
+            if let Err(e) = app_handle.emit("export-progress", &progress) {
+                warn!("Failed to emit export progress: {}", e);
+            }
+        }
+    };
+
+    match format {
+        ExportFormat::Markdown => {
+            fs::create_dir_all(&dest).map_err(|e| AppError::internal(format!("Failed to create {}: {}", dest.display(), e)))?;
+            clips::for_each_clip(&conn, &filter, |mut clip| {
+                private::reveal(&vault, &conn, &mut clip)?;
+                let tags = tags::tags_for_clip(&conn, clip.id)?;
+                let annotations = annotations::list_annotations(&conn, clip.id)?;
+                write_markdown(&dest, &clip, &tags, &annotations)?;
+                exported += 1;
+                report(exported);
+                Ok(())
+            })?;
+        }
+        ExportFormat::Json => {
+            let mut out = create_file(&dest)?;
+            out.write_all(b"[").map_err(|e| write_error(&dest, e))?;
+            clips::for_each_clip(&conn, &filter, |mut clip| {
+                private::reveal(&vault, &conn, &mut clip)?;
+                let tags = tags::tags_for_clip(&conn, clip.id)?;
+                let annotations = annotations::list_annotations(&conn, clip.id)?;
+                if exported > 0 {
+                    out.write_all(b",").map_err(|e| write_error(&dest, e))?;
+                }
+                out.write_all(b"\n  ").map_err(|e| write_error(&dest, e))?;
+                let exported_clip = ExportedClip {
+                    clip: &clip,
+                    tags: &tags,
+                    annotations: &annotations,
+                };
+                serde_json::to_writer(&mut out, &exported_clip)
+                    .map_err(|e| AppError::internal(format!("Failed to serialize clip {}: {}", clip.id, e)))?;
+                exported += 1;
+                report(exported);
+                Ok(())
+            })?;
+            out.write_all(b"\n]\n").map_err(|e| write_error(&dest, e))?;
+            out.flush().map_err(|e| write_error(&dest, e))?;
+        }
+        ExportFormat::Csv => {
+            let mut out = csv::Writer::from_writer(create_file(&dest)?);
+            out.write_record([
+                "id", "type", "title", "url", "author", "domain", "description", "content", "summary", "category",
+                "tags", "annotations", "timestamp", "created_at",
+            ])
+            .map_err(|e| write_error(&dest, e))?;
+            clips::for_each_clip(&conn, &filter, |mut clip| {
+                private::reveal(&vault, &conn, &mut clip)?;
+                let tags = tags::tags_for_clip(&conn, clip.id)?;
+                let annotations = annotations::list_annotations(&conn, clip.id)?;
+                out.write_record([
+                    clip.id.to_string(),
+                    clip.r#type.clone(),
+                    clip.title.clone(),
+                    clip.url.clone().unwrap_or_default(),
+                    clip.author.clone().unwrap_or_default(),
+                    clip.domain.clone().unwrap_or_default(),
+                    clip.description.clone().unwrap_or_default(),
+                    clip.content.clone().unwrap_or_default(),
+                    clip.summary.clone().unwrap_or_default(),
+                    clip.category.clone().unwrap_or_default(),
+                    tags.join(", "),
+                    annotations.iter().map(annotation_text).collect::<Vec<_>>().join("\n\n"),
+                    clip.timestamp.to_string(),
+                    clip.created_at.clone(),
+                ])
+                .map_err(|e| write_error(&dest, e))?;
+                exported += 1;
+                report(exported);
+                Ok(())
+            })?;
+            out.flush().map_err(|e| write_error(&dest, e))?;
+        }
+    }
+
+    // An empty export still gets a final event so the UI can close its progress bar
+    if total == 0 {
+        report(0);
+    }
+
+    Ok(ExportSummary {
+        format,
+        path: dest,
+        exported,
+    })
+}
+
+fn create_file(path: &Path) -> Result<BufWriter<File>, AppError> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| AppError::internal(format!("Failed to create {}: {}", path.display(), e)))
+}
+
+fn write_error(path: &Path, e: impl std::fmt::Display) -> AppError {
+    AppError::internal(format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// An annotation as plain text: the quote in double quotes, then the note
+fn annotation_text(annotation: &Annotation) -> String {
+    match (&annotation.quote, &annotation.note) {
+        (Some(quote), Some(note)) => format!("\"{}\" {}", quote, note),
+        (Some(quote), None) => format!("\"{}\"", quote),
+        (None, Some(note)) => note.clone(),
+        (None, None) => String::new(),
+    }
+}
+
+/// Write `<id>-<title-slug>.md` into `dir`
+fn write_markdown(dir: &Path, clip: &SqliteClip, tags: &[String], annotations: &[Annotation]) -> Result<(), AppError> {
+    let path = dir.join(format!("{}-{}.md", clip.id, slug(&clip.title)));
+    fs::write(&path, markdown_note(clip, tags, annotations, "id")).map_err(|e| write_error(&path, e))
+}
+
+/// A clip as a Markdown note with YAML front-matter. `id_key` names the front-matter
+/// field holding the clip id.
+pub fn markdown_note(clip: &SqliteClip, tags: &[String], annotations: &[Annotation], id_key: &str) -> String {
+    // JSON strings are valid YAML double-quoted scalars, so serde_json handles the escaping
+    let quote = |value: &str| serde_json::to_string(value).unwrap_or_default();
+
+    let mut doc = String::from("---\n");
+    doc.push_str(&format!("{}: {}\n", id_key, clip.id));
+    doc.push_str(&format!("title: {}\n", quote(&clip.title)));
+    doc.push_str(&format!("type: {}\n", quote(&clip.r#type)));
+    let optional = [
+        ("url", &clip.url),
+        ("author", &clip.author),
+        ("domain", &clip.domain),
+        ("category", &clip.category),
+        ("image_url", &clip.image_url),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            doc.push_str(&format!("{}: {}\n", key, quote(value)));
+        }
+    }
+    doc.push_str(&format!("created_at: {}\n", quote(&clip.created_at)));
+    doc.push_str(&format!("timestamp: {}\n", clip.timestamp));
+    if !tags.is_empty() {
+        let tags: Vec<String> = tags.iter().map(|tag| quote(tag)).collect();
+        doc.push_str(&format!("tags: [{}]\n", tags.join(", ")));
+    }
+    doc.push_str("---\n\n");
+
+    doc.push_str(&format!("# {}\n\n", clip.title));
+    if let Some(description) = clip.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        doc.push_str(&format!("> {}\n\n", description.trim().replace('\n', "\n> ")));
+    }
+    if let Some(summary) = clip.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+        doc.push_str(&format!("## Summary\n\n{}\n\n", summary.trim()));
+    }
+    if let Some(content) = clip.content.as_deref() {
+        let text = plain_text(content);
+        if !text.trim().is_empty() {
+            doc.push_str(text.trim());
+            doc.push('\n');
+        }
+    }
+    if !annotations.is_empty() {
+        doc.push_str("\n## Highlights\n");
+        for annotation in annotations {
+            doc.push('\n');
+            if let Some(quote) = &annotation.quote {
+                doc.push_str(&format!("> {}\n", quote.trim().replace('\n', "\n> ")));
+            }
+            if let Some(note) = &annotation.note {
+                if annotation.quote.is_some() {
+                    doc.push('\n');
+                }
+                doc.push_str(&format!("{}\n", note.trim()));
+            }
+        }
+    }
+    doc
+}
+
+/// Lowercase ASCII words of `title` joined by dashes, safe for any file system
+fn slug(title: &str) -> String {
+    let mut slug = String::new();
+    for word in title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        if slug.len() + word.len() + 1 > MAX_SLUG_LEN {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+    if slug.is_empty() {
+        slug.push_str("clip");
+    }
+    slug
+}
@@ -0,0 +1,324 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::connectivity;
+use crate::errors::AppError;
+use crate::http;
+
+/// Paragraphs shorter than this are treated as boilerplate (captions, buttons, etc.)
+const MIN_PARAGRAPH_LEN: usize = 25;
+/// Class/id fragments that mark an element as page chrome rather than content
+const NEGATIVE_HINTS: &[&str] = &[
+    "comment", "footer", "footnote", "sidebar", "nav", "menu", "share", "social", "promo", "related", "sponsor",
+    "advert", "cookie", "banner", "header", "masthead", "widget", "subscribe",
+];
+const POSITIVE_HINTS: &[&str] = &["article", "content", "entry", "main", "post", "story", "body", "text"];
+
+/// Readable article content extracted from a web page
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExtractedArticle {
+    /// Final URL after redirects
+    pub url: String,
+    pub title: String,
+    pub byline: Option<String>,
+    pub site_name: Option<String>,
+    /// Short summary from page metadata, or the first paragraph
+    pub excerpt: Option<String>,
+    pub lead_image: Option<String>,
+    /// Main article text, paragraphs separated by blank lines
+    pub content: String,
+    pub word_count: usize,
+}
+
+/// Site and preview details from a page's OpenGraph, Twitter-card and `<link>` tags
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct PageMetadata {
+    pub site_name: Option<String>,
+    /// As the page states it, usually ISO 8601
+    pub published_at: Option<String>,
+    /// Absolute URL of the page's preview image
+    pub image: Option<String>,
+    /// Absolute URL of the site's icon, `/favicon.ico` when the page names none
+    pub favicon: String,
+}
+
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).expect("static selector is valid")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Content attribute of the first `<meta>` tag matching any of `keys`
+/// (checked against both `property` and `name`)
+fn meta_content(document: &Html, keys: &[&str]) -> Option<String> {
+    for key in keys {
+        let css = format!(r#"meta[property="{0}"], meta[name="{0}"]"#, key);
+        let value = Selector::parse(&css)
+            .ok()
+            .and_then(|sel| document.select(&sel).next())
+            .and_then(|el| el.value().attr("content"))
+            .map(collapse_whitespace)
+            .filter(|v| !v.is_empty());
+        if value.is_some() {
+            return value;
+        }
+    }
+    None
+}
+
+fn class_weight(element: &ElementRef) -> f64 {
+    let hints = format!(
+        "{} {}",
+        element.value().attr("class").unwrap_or_default(),
+        element.value().attr("id").unwrap_or_default()
+    )
+    .to_lowercase();
+
+    let mut weight = 0.0;
+    if NEGATIVE_HINTS.iter().any(|hint| hints.contains(hint)) {
+        weight -= 25.0;
+    }
+    if POSITIVE_HINTS.iter().any(|hint| hints.contains(hint)) {
+        weight += 25.0;
+    }
+    weight
+}
+
+/// Pick the element most likely to contain the article body by scoring the
+/// parents of substantial paragraphs, in the spirit of Mozilla's Readability
+fn find_content_root(document: &Html) -> Option<ElementRef<'_>> {
+    let mut scores = HashMap::new();
+
+    for paragraph in document.select(&selector("p, pre, td")) {
+        let text = collapse_whitespace(&paragraph.text().collect::<String>());
+        if text.len() < MIN_PARAGRAPH_LEN {
+            continue;
+        }
+
+        // One point per paragraph, one per comma, one per 100 chars (capped)
+        let score = 1.0 + text.matches(',').count() as f64 + (text.len() / 100).min(3) as f64;
+
+        let mut ancestors = paragraph.ancestors().filter_map(ElementRef::wrap);
+        if let Some(parent) = ancestors.next() {
+            *scores.entry(parent.id()).or_insert_with(|| class_weight(&parent)) += score;
+        }
+        if let Some(grandparent) = ancestors.next() {
+            *scores.entry(grandparent.id()).or_insert_with(|| class_weight(&grandparent)) += score / 2.0;
+        }
+    }
+
+    scores
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let element = ElementRef::wrap(document.tree.get(id)?)?;
+            // Penalize link-heavy candidates (navigation blocks, link lists)
+            let text_len = element.text().map(str::len).sum::<usize>().max(1);
+            let link_len: usize = element.select(&selector("a")).flat_map(|a| a.text()).map(str::len).sum();
+            let link_density = link_len as f64 / text_len as f64;
+            Some((element, score * (1.0 - link_density)))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(element, _)| element)
+}
+
+/// Flatten the content root into plain-text blocks, keeping headings, list
+/// items and preformatted text as separate paragraphs
+fn content_text(root: ElementRef) -> String {
+    let mut blocks = Vec::new();
+
+    for block in root.select(&selector("h1, h2, h3, h4, h5, h6, p, pre, li, blockquote")) {
+        // Nested blocks are already covered by their outermost block
+        let nested = block
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .take_while(|ancestor| ancestor.id() != root.id())
+            .any(|ancestor| matches!(ancestor.value().name(), "p" | "pre" | "li" | "blockquote"));
+        if nested || class_weight(&block) < 0.0 {
+            continue;
+        }
+
+        let text = if block.value().name() == "pre" {
+            block.text().collect::<String>().trim_end().to_string()
+        } else {
+            collapse_whitespace(&block.text().collect::<String>())
+        };
+        if text.is_empty() {
+            continue;
+        }
+
+        blocks.push(match block.value().name() {
+            "li" => format!("- {}", text),
+            _ => text,
+        });
+    }
+
+    blocks.join("\n\n")
+}
+
+/// Visible text of an HTML fragment, one line per block. Clip content may be HTML
+/// from the clipper; summaries, embeddings and hashing only want the text.
+pub fn plain_text(content: &str) -> String {
+    let fragment = Html::parse_fragment(content);
+    let text: Vec<&str> = fragment.root_element().text().collect();
+    text.join(" ")
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Readable text of an HTML document that isn't a web page, such as an HTML email:
+/// the main content block when one stands out, otherwise all visible text
+pub fn html_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    match find_content_root(&document) {
+        Some(root) => content_text(root),
+        None => plain_text(html),
+    }
+}
+
+/// Run readability-style extraction over an HTML document fetched from `url`
+pub fn extract_article(html: &str, url: &reqwest::Url) -> ExtractedArticle {
+    let document = Html::parse_document(html);
+
+    let title = meta_content(&document, &["og:title", "twitter:title"])
+        .or_else(|| {
+            document
+                .select(&selector("title"))
+                .next()
+                .map(|el| collapse_whitespace(&el.text().collect::<String>()))
+        })
+        .or_else(|| {
+            document
+                .select(&selector("h1"))
+                .next()
+                .map(|el| collapse_whitespace(&el.text().collect::<String>()))
+        })
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| url.to_string());
+
+    let byline = meta_content(&document, &["author", "article:author", "twitter:creator"]).or_else(|| {
+        document
+            .select(&selector(r#"[rel="author"], [itemprop="author"], .byline, .author"#))
+            .next()
+            .map(|el| collapse_whitespace(&el.text().collect::<String>()))
+            .filter(|b| !b.is_empty())
+    });
+
+    let lead_image = meta_content(&document, &["og:image", "twitter:image"])
+        .and_then(|src| url.join(&src).ok())
+        .map(|src| src.to_string());
+
+    let content = match find_content_root(&document) {
+        Some(root) => content_text(root),
+        None => document
+            .select(&selector("body"))
+            .next()
+            .map(|body| collapse_whitespace(&body.text().collect::<String>()))
+            .unwrap_or_default(),
+    };
+
+    let excerpt = meta_content(&document, &["og:description", "description", "twitter:description"]).or_else(|| {
+        content
+            .split("\n\n")
+            .find(|block| block.len() >= MIN_PARAGRAPH_LEN)
+            .map(|block| block.chars().take(300).collect())
+    });
+
+    ExtractedArticle {
+        url: url.to_string(),
+        title,
+        byline,
+        site_name: meta_content(&document, &["og:site_name", "application-name"]),
+        excerpt,
+        lead_image,
+        word_count: content.split_whitespace().count(),
+        content,
+    }
+}
+
+/// Read the site name, publication date, preview image and icon of an HTML document
+pub fn page_metadata(html: &str, url: &reqwest::Url) -> PageMetadata {
+    let document = Html::parse_document(html);
+    let absolute = |src: String| url.join(&src).ok().map(|src| src.to_string());
+
+    let favicon = [r#"link[rel~="icon"]"#, r#"link[rel="apple-touch-icon"]"#]
+        .iter()
+        .find_map(|css| {
+            document
+                .select(&selector(css))
+                .find_map(|el| el.value().attr("href"))
+                .and_then(|href| absolute(href.trim().to_string()))
+        })
+        .or_else(|| absolute("/favicon.ico".to_string()))
+        .unwrap_or_default();
+
+    PageMetadata {
+        site_name: meta_content(&document, &["og:site_name", "application-name", "twitter:site"]),
+        published_at: meta_content(
+            &document,
+            &["article:published_time", "og:published_time", "datePublished", "pubdate", "date", "dc.date"],
+        )
+        .or_else(|| {
+            document
+                .select(&selector("time[datetime]"))
+                .next()
+                .and_then(|el| el.value().attr("datetime"))
+                .map(|date| date.trim().to_string())
+                .filter(|date| !date.is_empty())
+        }),
+        image: meta_content(&document, &["og:image", "og:image:url", "twitter:image", "twitter:image:src"]).and_then(absolute),
+        favicon,
+    }
+}
+
+/// Download an HTML page, returning its final URL (after redirects) and body
+async fn fetch_html(url: &str) -> Result<(reqwest::Url, String), AppError> {
+    let client = http::client();
+
+    let response = client
+        .get(url)
+        .header("User-Agent", "Mozilla/5.0 (compatible; LOS/0.1; +https://github.com/mranderson01901234/LOS)")
+        .header("Accept", "text/html,application/xhtml+xml")
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to fetch URL: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("Failed to fetch URL: HTTP {}", response.status())));
+    }
+
+    let is_html = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.contains("html"))
+        .unwrap_or(true);
+    if !is_html {
+        return Err(AppError::validation("URL does not point to an HTML page"));
+    }
+
+    let final_url = response.url().clone();
+    let html = response
+        .text()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to read response body: {}", e)))?;
+    Ok((final_url, html))
+}
+
+/// Fetch a page and extract its readable article content
+pub async fn fetch_article(url: &str) -> Result<ExtractedArticle, AppError> {
+    connectivity::ensure_online()?;
+    let (final_url, html) = fetch_html(url).await?;
+    Ok(extract_article(&html, &final_url))
+}
+
+/// Fetch a page and read its site metadata
+pub async fn fetch_page_metadata(url: &str) -> Result<PageMetadata, AppError> {
+    let (final_url, html) = fetch_html(url).await?;
+    Ok(page_metadata(&html, &final_url))
+}
@@ -0,0 +1,453 @@
+use feed_rs::model::{Entry, Feed as ParsedFeed, Link};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{error, warn};
+
+use crate::clips::{now_millis, ClipData};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::extract::{self, plain_text};
+use crate::http;
+use crate::jobs::{Job, JobKind, JobQueue};
+use crate::redact;
+use crate::settings::SettingsManager;
+
+/// Feeds are polled this often unless `feed_refresh_minutes` says otherwise
+pub const DEFAULT_FEED_REFRESH_MINUTES: u32 = 60;
+/// How often the scheduler looks for feeds that are due
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// New items clipped per refresh; older unseen items are marked seen without a clip,
+/// so subscribing to a feed with a long history doesn't flood the library
+const MAX_NEW_ITEMS: usize = 20;
+/// Sent so publishers can tell feed readers apart from scrapers
+const FEED_USER_AGENT: &str = "Mozilla/5.0 (compatible; LOS/0.1; +https://github.com/mranderson01901234/LOS)";
+
+/// An RSS or Atom subscription
+#[derive(Debug, Serialize, Clone)]
+pub struct Feed {
+    pub id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    /// The site the feed belongs to
+    pub site_url: Option<String>,
+    /// Fetch each item's page for the full article instead of using the feed's excerpt
+    pub fetch_full_content: bool,
+    /// Milliseconds since the epoch
+    pub last_fetched_at: Option<i64>,
+    /// Why the last refresh failed, if it did
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    /// Clips created from this feed
+    pub clip_count: u32,
+}
+
+/// Result of one refresh; also the payload of the `feed-refreshed` event
+#[derive(Debug, Serialize, Clone)]
+pub struct FeedRefresh {
+    pub feed_id: i64,
+    pub new_clips: usize,
+    /// The server answered 304 Not Modified
+    pub not_modified: bool,
+}
+
+/// A feed response with the validators for the next conditional request
+struct FetchedFeed {
+    body: Vec<u8>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+const FEED_COLUMNS: &str = "f.id, f.url, f.title, f.site_url, f.fetch_full_content, f.last_fetched_at, f.last_error, f.created_at,
+     (SELECT COUNT(*) FROM feed_items i WHERE i.feed_id = f.id AND i.clip_id IS NOT NULL)";
+
+impl Feed {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Feed {
+            id: row.get(0)?,
+            url: row.get(1)?,
+            title: row.get(2)?,
+            site_url: row.get(3)?,
+            fetch_full_content: row.get(4)?,
+            last_fetched_at: row.get(5)?,
+            last_error: row.get(6)?,
+            created_at: row.get(7)?,
+            clip_count: row.get(8)?,
+        })
+    }
+}
+
+fn get_feed(conn: &Connection, id: i64) -> Result<Option<Feed>, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM feeds f WHERE f.id = ?1", FEED_COLUMNS),
+        params![id],
+        Feed::from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read feed: {}", e)))
+}
+
+pub fn list_feeds(conn: &Connection) -> Result<Vec<Feed>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM feeds f ORDER BY COALESCE(f.title, f.url) COLLATE NOCASE",
+            FEED_COLUMNS
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let feeds = stmt
+        .query_map([], Feed::from_row)
+        .map_err(|e| AppError::database(format!("Failed to list feeds: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read feed: {}", e)))?;
+    Ok(feeds)
+}
+
+/// Unsubscribe. Clips already created from the feed are kept.
+pub fn remove_feed(conn: &Connection, id: i64) -> Result<(), AppError> {
+    let deleted = conn
+        .execute("DELETE FROM feeds WHERE id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to remove feed: {}", e)))?;
+    if deleted == 0 {
+        return Err(AppError::not_found(format!("Feed {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Feeds not refreshed within `interval`
+fn due_feed_ids(conn: &Connection, interval: Duration) -> Result<Vec<i64>, AppError> {
+    let cutoff = now_millis() - interval.as_millis() as i64;
+    let mut stmt = conn
+        .prepare("SELECT id FROM feeds WHERE last_fetched_at IS NULL OR last_fetched_at <= ?1")
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let ids = stmt
+        .query_map(params![cutoff], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to list due feeds: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read feed: {}", e)))?;
+    Ok(ids)
+}
+
+/// The body of a feed request, or None when the server says it hasn't changed
+async fn fetch(url: &str, etag: Option<&str>, last_modified: Option<&str>) -> Result<Option<FetchedFeed>, AppError> {
+    let mut request = http::client()
+        .get(url)
+        .header("User-Agent", FEED_USER_AGENT)
+        .header("Accept", "application/rss+xml, application/atom+xml, application/xml;q=0.9, */*;q=0.8");
+    if let Some(etag) = etag {
+        request = request.header("If-None-Match", etag);
+    }
+    if let Some(last_modified) = last_modified {
+        request = request.header("If-Modified-Since", last_modified);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to fetch feed: {}", e)))?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("Failed to fetch feed: HTTP {}", response.status())));
+    }
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let (etag, last_modified) = (header(reqwest::header::ETAG), header(reqwest::header::LAST_MODIFIED));
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to read feed: {}", e)))?;
+    Ok(Some(FetchedFeed {
+        body: body.to_vec(),
+        etag,
+        last_modified,
+    }))
+}
+
+fn parse(body: &[u8]) -> Result<ParsedFeed, AppError> {
+    feed_rs::parser::parse(body).map_err(|e| AppError::validation(format!("Not a valid RSS or Atom feed: {}", e)))
+}
+
+/// The item's web page: its `alternate` link, or the first one
+fn page_link(links: &[Link]) -> Option<String> {
+    links
+        .iter()
+        .find(|link| matches!(link.rel.as_deref(), None | Some("alternate")))
+        .or_else(|| links.first())
+        .map(|link| link.href.clone())
+}
+
+/// Subscribe to a feed. It's fetched once to check that it parses and to learn its
+/// title; items are clipped by the refresh job queued afterwards.
+pub async fn add_feed(app_handle: &AppHandle, url: &str, fetch_full_content: bool) -> Result<Feed, AppError> {
+    let url = url.trim();
+    let parsed_url = reqwest::Url::parse(url).map_err(|e| AppError::validation(format!("Invalid feed URL '{}': {}", url, e)))?;
+    if !matches!(parsed_url.scheme(), "http" | "https") {
+        return Err(AppError::validation(format!("Unsupported feed URL scheme '{}'", parsed_url.scheme())));
+    }
+
+    let fetched = fetch(url, None, None)
+        .await?
+        .ok_or_else(|| AppError::network("Feed server answered 304 to an unconditional request"))?;
+    let parsed = parse(&fetched.body)?;
+
+    let feed = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let exists: bool = conn
+            .query_row("SELECT EXISTS (SELECT 1 FROM feeds WHERE url = ?1)", params![url], |row| row.get(0))
+            .map_err(|e| AppError::database(format!("Failed to check for existing feed: {}", e)))?;
+        if exists {
+            return Err(AppError::validation(format!("Already subscribed to {}", url)));
+        }
+        conn.execute(
+            "INSERT INTO feeds (url, title, site_url, fetch_full_content, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                url,
+                parsed.title.map(|title| title.content.trim().to_string()),
+                page_link(&parsed.links),
+                fetch_full_content,
+                now_millis()
+            ],
+        )
+        .map_err(|e| AppError::database(format!("Failed to add feed: {}", e)))?;
+        let id = conn.last_insert_rowid();
+        get_feed(&conn, id)?.ok_or_else(|| AppError::database(format!("Feed {} vanished after insert", id)))?
+    };
+
+    app_handle
+        .state::<JobQueue>()
+        .submit(app_handle, JobKind::RefreshFeed { feed_id: feed.id })?;
+    Ok(feed)
+}
+
+/// Queue a refresh of one feed, or of every feed
+pub fn queue_refresh(app_handle: &AppHandle, feed_id: Option<i64>) -> Result<Vec<Job>, AppError> {
+    let ids = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        match feed_id {
+            Some(id) => {
+                get_feed(&conn, id)?.ok_or_else(|| AppError::not_found(format!("Feed {} not found", id)))?;
+                vec![id]
+            }
+            None => list_feeds(&conn)?.into_iter().map(|feed| feed.id).collect(),
+        }
+    };
+    let queue = app_handle.state::<JobQueue>();
+    ids.into_iter()
+        .map(|feed_id| queue.submit(app_handle, JobKind::RefreshFeed { feed_id }))
+        .collect()
+}
+
+/// Build the clip for a feed item, fetching the full page when the feed asks for it
+async fn item_clip(entry: &Entry, url: String, fetch_full_content: bool) -> ClipData {
+    let summary = entry.summary.as_ref().map(|text| plain_text(&text.content));
+    let mut content = entry
+        .content
+        .as_ref()
+        .and_then(|content| content.body.as_deref())
+        .map(plain_text)
+        .or_else(|| summary.clone());
+    let mut image_url = None;
+
+    if fetch_full_content {
+        match extract::fetch_article(&url).await {
+            Ok(article) => {
+                if !article.content.trim().is_empty() {
+                    content = Some(article.content);
+                }
+                image_url = article.lead_image;
+            }
+            // The feed's own text is still worth keeping
+            Err(e) => warn!("Failed to fetch full article for {}: {}", url, e),
+        }
+    }
+
+    let timestamp = entry
+        .published
+        .or(entry.updated)
+        .map(|date| date.timestamp_millis())
+        .unwrap_or_else(now_millis);
+    ClipData {
+        r#type: "article".to_string(),
+        title: entry
+            .title
+            .as_ref()
+            .map(|title| title.content.trim().to_string())
+            .filter(|title| !title.is_empty())
+            .unwrap_or_else(|| url.clone()),
+        url: Some(url),
+        content: content.filter(|content| !content.trim().is_empty()),
+        image_url,
+        description: summary.filter(|summary| !summary.trim().is_empty()),
+        author: entry.authors.first().map(|author| author.name.clone()),
+        timestamp: timestamp.max(0) as u64,
+        original_url: None,
+        language: None,
+    }
+}
+
+fn record_error(app_handle: &AppHandle, feed_id: i64, error: &AppError) -> Result<(), AppError> {
+    let db = app_handle.state::<Database>();
+    db.conn()?
+        .execute(
+            "UPDATE feeds SET last_error = ?1, last_fetched_at = ?2 WHERE id = ?3",
+            params![redact::redact(&error.to_string()), now_millis(), feed_id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to update feed: {}", e)))?;
+    Ok(())
+}
+
+/// Fetch a feed and clip the items not seen before. Emits `feed-refreshed`.
+pub async fn refresh_feed(app_handle: &AppHandle, feed_id: i64) -> Result<FeedRefresh, AppError> {
+    let (url, fetch_full_content, etag, last_modified) = {
+        let db = app_handle.state::<Database>();
+        db.conn()?
+            .query_row(
+                "SELECT url, fetch_full_content, etag, last_modified FROM feeds WHERE id = ?1",
+                params![feed_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, bool>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|e| AppError::database(format!("Failed to read feed: {}", e)))?
+            .ok_or_else(|| AppError::not_found(format!("Feed {} not found", feed_id)))?
+    };
+
+    let fetched = match fetch(&url, etag.as_deref(), last_modified.as_deref()).await {
+        Ok(fetched) => fetched,
+        Err(e) => {
+            record_error(app_handle, feed_id, &e)?;
+            return Err(e);
+        }
+    };
+    let mut refresh = FeedRefresh {
+        feed_id,
+        new_clips: 0,
+        not_modified: fetched.is_none(),
+    };
+
+    if let Some(fetched) = fetched {
+        let parsed = match parse(&fetched.body) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                record_error(app_handle, feed_id, &e)?;
+                return Err(e);
+            }
+        };
+
+        let unseen: Vec<&Entry> = {
+            let db = app_handle.state::<Database>();
+            let conn = db.conn()?;
+            let mut seen = conn
+                .prepare("SELECT EXISTS (SELECT 1 FROM feed_items WHERE feed_id = ?1 AND guid = ?2)")
+                .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+            let mut unseen = Vec::new();
+            for entry in &parsed.entries {
+                let exists: bool = seen
+                    .query_row(params![feed_id, entry.id], |row| row.get(0))
+                    .map_err(|e| AppError::database(format!("Failed to check feed item: {}", e)))?;
+                if !exists {
+                    unseen.push(entry);
+                }
+            }
+            unseen
+        };
+
+        // Feeds list newest first; clip the newest few, oldest of those first
+        for (index, entry) in unseen.iter().enumerate().rev() {
+            let clip_id = match page_link(&entry.links) {
+                Some(link) if index < MAX_NEW_ITEMS => {
+                    let clip_data = item_clip(entry, link, fetch_full_content).await;
+                    match crate::ingest_clip(app_handle, clip_data) {
+                        Ok(inserted) => {
+                            if !inserted.merged {
+                                refresh.new_clips += 1;
+                            }
+                            Some(inserted.clip.id)
+                        }
+                        Err(e) => {
+                            warn!("Failed to clip feed item {}: {}", entry.id, e);
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+            let db = app_handle.state::<Database>();
+            db.conn()?
+                .execute(
+                    "INSERT OR IGNORE INTO feed_items (feed_id, guid, clip_id, seen_at) VALUES (?1, ?2, ?3, ?4)",
+                    params![feed_id, entry.id, clip_id, now_millis()],
+                )
+                .map_err(|e| AppError::database(format!("Failed to record feed item: {}", e)))?;
+        }
+
+        let db = app_handle.state::<Database>();
+        db.conn()?
+            .execute(
+                "UPDATE feeds SET etag = ?1, last_modified = ?2,
+                    title = COALESCE(title, ?3), site_url = COALESCE(site_url, ?4)
+                 WHERE id = ?5",
+                params![
+                    fetched.etag,
+                    fetched.last_modified,
+                    parsed.title.map(|title| title.content.trim().to_string()),
+                    page_link(&parsed.links),
+                    feed_id
+                ],
+            )
+            .map_err(|e| AppError::database(format!("Failed to update feed: {}", e)))?;
+    }
+
+    let db = app_handle.state::<Database>();
+    db.conn()?
+        .execute(
+            "UPDATE feeds SET last_fetched_at = ?1, last_error = NULL WHERE id = ?2",
+            params![now_millis(), feed_id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to update feed: {}", e)))?;
+    events::emit(app_handle, "feed-refreshed", &refresh)
+        .map_err(|e| AppError::internal(format!("Failed to emit feed event: {}", e)))?;
+    Ok(refresh)
+}
+
+/// Start the background task that queues a refresh for every feed not fetched
+/// within `feed_refresh_minutes`. Settings are re-read on every check.
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let interval = app_handle.state::<SettingsManager>().get().feed_refresh_interval();
+            let due = {
+                let db = app_handle.state::<Database>();
+                db.conn().and_then(|conn| due_feed_ids(&conn, interval))
+            };
+            match due {
+                Ok(ids) => {
+                    let queue = app_handle.state::<JobQueue>();
+                    for feed_id in ids {
+                        if let Err(e) = queue.submit(&app_handle, JobKind::RefreshFeed { feed_id }) {
+                            error!("Failed to queue refresh of feed {}: {}", feed_id, e);
+                        }
+                    }
+                }
+                Err(e) => error!("{}", e),
+            }
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,434 @@
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::clips::{self, now_millis};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract::plain_text;
+use crate::http;
+use crate::llm;
+use crate::providers::{LlmError, LlmMessage};
+
+/// Cards made when the caller doesn't ask for a number
+pub const DEFAULT_CARD_COUNT: u32 = 5;
+const MAX_CARD_COUNT: u32 = 20;
+/// Clip text beyond this isn't sent to the model
+const MAX_PROMPT_CHARS: usize = 12_000;
+const DEFAULT_DECK: &str = "LOS";
+const DEFAULT_ANKI_CONNECT_URL: &str = "http://127.0.0.1:8765";
+/// Anki note type written into `.apkg` files; a fixed id lets repeated imports share it
+const APKG_MODEL_ID: i64 = 1_700_000_000_001;
+
+/// A question/answer pair generated from a clip
+#[derive(Debug, Serialize, Clone)]
+pub struct Flashcard {
+    pub id: i64,
+    pub clip_id: i64,
+    pub question: String,
+    pub answer: String,
+    /// Anki note guid; stays the same across exports so re-importing updates the note
+    pub guid: String,
+    pub created_at: i64,
+    pub exported_at: Option<i64>,
+}
+
+/// Where `export_flashcards` sends cards
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FlashcardTarget {
+    /// An Anki package file to import by hand
+    Apkg { path: PathBuf },
+    /// A running Anki with the AnkiConnect add-on; defaults to `DEFAULT_ANKI_CONNECT_URL`
+    AnkiConnect { url: Option<String> },
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlashcardExportSummary {
+    pub exported: usize,
+    /// Cards Anki already had (AnkiConnect only)
+    pub skipped: usize,
+    pub path: Option<PathBuf>,
+}
+
+/// Shape we ask the model to reply with
+#[derive(Debug, Deserialize)]
+struct GeneratedCards {
+    #[serde(default)]
+    cards: Vec<GeneratedCard>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeneratedCard {
+    question: String,
+    answer: String,
+}
+
+const FLASHCARD_COLUMNS: &str = "id, clip_id, question, answer, guid, created_at, exported_at";
+
+fn flashcard_from_row(row: &rusqlite::Row) -> rusqlite::Result<Flashcard> {
+    Ok(Flashcard {
+        id: row.get(0)?,
+        clip_id: row.get(1)?,
+        question: row.get(2)?,
+        answer: row.get(3)?,
+        guid: row.get(4)?,
+        created_at: row.get(5)?,
+        exported_at: row.get(6)?,
+    })
+}
+
+fn query_flashcards(conn: &Connection, where_sql: &str, clip_ids: &[i64]) -> Result<Vec<Flashcard>, AppError> {
+    let placeholders = vec!["?"; clip_ids.len()].join(", ");
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM flashcards WHERE {} ({}) ORDER BY clip_id, id",
+            FLASHCARD_COLUMNS, where_sql, placeholders
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let cards = stmt
+        .query_map(rusqlite::params_from_iter(clip_ids), flashcard_from_row)
+        .map_err(|e| AppError::database(format!("Failed to list flashcards: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read flashcard: {}", e)))?;
+    Ok(cards)
+}
+
+pub fn list_flashcards(conn: &Connection, clip_id: i64) -> Result<Vec<Flashcard>, AppError> {
+    query_flashcards(conn, "clip_id IN", &[clip_id])
+}
+
+pub fn delete_flashcard(conn: &Connection, id: i64) -> Result<(), AppError> {
+    let deleted = conn
+        .execute("DELETE FROM flashcards WHERE id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to delete flashcard: {}", e)))?;
+    if deleted == 0 {
+        return Err(AppError::not_found(format!("Flashcard {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Ask the default model for `count` cards on a clip. A clip that already has cards
+/// keeps them and no model call is made unless `regenerate` is set; new cards are
+/// then added alongside the old ones, skipping repeated questions.
+pub async fn generate_flashcards(
+    app_handle: &AppHandle,
+    clip_id: i64,
+    count: u32,
+    regenerate: bool,
+) -> Result<Vec<Flashcard>, LlmError> {
+    let (clip, existing) = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let clip = clips::get_clip(&conn, clip_id)?.ok_or_else(|| format!("Clip {} not found", clip_id))?;
+        (clip, list_flashcards(&conn, clip_id)?)
+    };
+    if !existing.is_empty() && !regenerate {
+        return Ok(existing);
+    }
+
+    let text: String = clip
+        .content
+        .as_deref()
+        .map(plain_text)
+        .filter(|text| !text.trim().is_empty())
+        .or_else(|| clip.summary.clone())
+        .or_else(|| clip.description.clone())
+        .ok_or_else(|| format!("Clip {} has no text to make flashcards from", clip_id))?
+        .chars()
+        .take(MAX_PROMPT_CHARS)
+        .collect();
+    let count = count.clamp(1, MAX_CARD_COUNT);
+    let mut prompt = format!("Title: {}\n\n{}", clip.title, text);
+    if !existing.is_empty() {
+        let asked: Vec<&str> = existing.iter().map(|card| card.question.as_str()).collect();
+        prompt.push_str(&format!("\n\nAlready asked (don't repeat): {}", asked.join(" | ")));
+    }
+    let messages = vec![
+        LlmMessage {
+            role: "system".to_string(),
+            content: format!(
+                "You write Anki flashcards that help someone remember the key ideas of a text. Write {} cards. \
+                 Each question must make sense on its own and have one short, specific answer. \
+                 Reply with JSON only: {{\"cards\": [{{\"question\": \"...\", \"answer\": \"...\"}}]}}",
+                count
+            ),
+        },
+        LlmMessage {
+            role: "user".to_string(),
+            content: prompt,
+        },
+    ];
+    let response = llm::complete_with_default(app_handle, "flashcards", messages, Some(count * 150 + 100)).await?;
+    let generated = llm::extract_json::<GeneratedCards>(&response.content, "flashcards")?.cards;
+
+    let db = app_handle.state::<Database>();
+    let conn = db.conn()?;
+    let mut asked: Vec<String> = existing.iter().map(|card| card.question.trim().to_lowercase()).collect();
+    for card in generated.into_iter().take(count as usize) {
+        let (question, answer) = (card.question.trim(), card.answer.trim());
+        if question.is_empty() || answer.is_empty() || asked.contains(&question.to_lowercase()) {
+            continue;
+        }
+        asked.push(question.to_lowercase());
+        let guid: String = rand::thread_rng().sample_iter(&Alphanumeric).take(10).map(char::from).collect();
+        conn.execute(
+            "INSERT INTO flashcards (clip_id, question, answer, guid, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![clip_id, question, answer, guid, now_millis()],
+        )
+        .map_err(|e| AppError::database(format!("Failed to save flashcard: {}", e)))?;
+    }
+    Ok(list_flashcards(&conn, clip_id)?)
+}
+
+/// Plain text as an Anki field, which holds HTML
+fn field_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br>")
+}
+
+fn mark_exported(conn: &Connection, ids: &[i64]) -> Result<(), AppError> {
+    let now = now_millis();
+    for id in ids {
+        conn.execute("UPDATE flashcards SET exported_at = ?1 WHERE id = ?2", params![now, id])
+            .map_err(|e| AppError::database(format!("Failed to update flashcard: {}", e)))?;
+    }
+    Ok(())
+}
+
+async fn anki_connect(client: &reqwest::Client, url: &str, action: &str, params: Value) -> Result<Value, AppError> {
+    let reply: Value = client
+        .post(url)
+        .json(&json!({ "action": action, "version": 6, "params": params }))
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to reach AnkiConnect at {}; is Anki running? {}", url, e)))?
+        .json()
+        .await
+        .map_err(|e| AppError::network(format!("Invalid AnkiConnect response: {}", e)))?;
+    if let Some(error) = reply["error"].as_str() {
+        return Err(AppError::validation(format!("AnkiConnect {} failed: {}", action, error)));
+    }
+    Ok(reply["result"].clone())
+}
+
+async fn export_anki_connect(cards: &[Flashcard], url: &str, deck: &str) -> Result<(Vec<i64>, usize), AppError> {
+    let client = http::client();
+    anki_connect(&client, url, "createDeck", json!({ "deck": deck })).await?;
+    let notes: Vec<Value> = cards
+        .iter()
+        .map(|card| {
+            json!({
+                "deckName": deck,
+                "modelName": "Basic",
+                "fields": { "Front": field_html(&card.question), "Back": field_html(&card.answer) },
+                "tags": ["los", format!("los-clip-{}", card.clip_id)],
+                "options": { "allowDuplicate": false },
+            })
+        })
+        .collect();
+    // Cards can't be added one by one without a round trip each, so check which Anki
+    // would reject as duplicates first; addNotes fails as a whole otherwise
+    let addable = anki_connect(&client, url, "canAddNotes", json!({ "notes": notes })).await?;
+    let addable: Vec<bool> = addable
+        .as_array()
+        .map(|flags| flags.iter().map(|flag| flag.as_bool() == Some(true)).collect())
+        .unwrap_or_default();
+    let (new_notes, new_ids): (Vec<Value>, Vec<i64>) = notes
+        .into_iter()
+        .zip(cards)
+        .zip(addable.iter().chain(std::iter::repeat(&false)))
+        .filter(|(_, addable)| **addable)
+        .map(|((note, card), _)| (note, card.id))
+        .unzip();
+    if !new_notes.is_empty() {
+        anki_connect(&client, url, "addNotes", json!({ "notes": new_notes })).await?;
+    }
+    let skipped = cards.len() - new_ids.len();
+    Ok((new_ids, skipped))
+}
+
+/// Write an Anki 2.1 package: a zip holding a `collection.anki2` SQLite database
+/// with one deck, one two-field note type and the cards, plus an empty media map
+fn write_apkg(cards: &[Flashcard], dest: &Path, deck: &str) -> Result<(), AppError> {
+    let collection_path = std::env::temp_dir().join(format!("los-anki-{}.anki2", now_millis()));
+    let result = build_collection(&collection_path, cards, deck).and_then(|_| {
+        let collection =
+            fs::read(&collection_path).map_err(|e| AppError::internal(format!("Failed to read Anki collection: {}", e)))?;
+        let file =
+            File::create(dest).map_err(|e| AppError::internal(format!("Failed to create {}: {}", dest.display(), e)))?;
+        let write_error = |e: &dyn std::fmt::Display| AppError::internal(format!("Failed to write {}: {}", dest.display(), e));
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::FileOptions::default();
+        zip.start_file("collection.anki2", options).map_err(|e| write_error(&e))?;
+        zip.write_all(&collection).map_err(|e| write_error(&e))?;
+        zip.start_file("media", options).map_err(|e| write_error(&e))?;
+        zip.write_all(b"{}").map_err(|e| write_error(&e))?;
+        zip.finish().map_err(|e| write_error(&e))?;
+        Ok(())
+    });
+    let _ = fs::remove_file(&collection_path);
+    result
+}
+
+fn build_collection(path: &Path, cards: &[Flashcard], deck: &str) -> Result<(), AppError> {
+    let conn = Connection::open(path).map_err(|e| AppError::internal(format!("Failed to create Anki collection: {}", e)))?;
+    conn.execute_batch(
+        "CREATE TABLE col (id integer primary key, crt integer not null, mod integer not null, scm integer not null,
+            ver integer not null, dty integer not null, usn integer not null, ls integer not null, conf text not null,
+            models text not null, decks text not null, dconf text not null, tags text not null);
+        CREATE TABLE notes (id integer primary key, guid text not null, mid integer not null, mod integer not null,
+            usn integer not null, tags text not null, flds text not null, sfld integer not null, csum integer not null,
+            flags integer not null, data text not null);
+        CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null, ord integer not null,
+            mod integer not null, usn integer not null, type integer not null, queue integer not null, due integer not null,
+            ivl integer not null, factor integer not null, reps integer not null, lapses integer not null,
+            left integer not null, odue integer not null, odid integer not null, flags integer not null, data text not null);
+        CREATE TABLE revlog (id integer primary key, cid integer not null, usn integer not null, ease integer not null,
+            ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null,
+            type integer not null);
+        CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);",
+    )
+    .map_err(|e| AppError::internal(format!("Failed to create Anki collection: {}", e)))?;
+
+    let now_ms = now_millis();
+    let now = now_ms / 1000;
+    // Deck ids only need to be stable per name so re-imports land in the same deck
+    let deck_id = 1_000_000_000 + Sha1::digest(deck.as_bytes())[..4].iter().fold(0i64, |id, b| id * 256 + *b as i64);
+    let field = |name: &str, ord: u32| {
+        json!({ "name": name, "ord": ord, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": [] })
+    };
+    let mut models = serde_json::Map::new();
+    models.insert(
+        APKG_MODEL_ID.to_string(),
+        json!({
+            "id": APKG_MODEL_ID, "name": "LOS Basic", "type": 0, "mod": now, "usn": -1, "sortf": 0, "did": deck_id,
+            "tmpls": [{
+                "name": "Card 1", "ord": 0, "qfmt": "{{Front}}", "afmt": "{{FrontSide}}<hr id=answer>{{Back}}",
+                "did": null, "bqfmt": "", "bafmt": "",
+            }],
+            "flds": [field("Front", 0), field("Back", 1)],
+            "css": ".card { font-family: arial; font-size: 20px; text-align: center; color: black; background-color: white; }",
+            "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+            "latexPost": "\\end{document}",
+            "tags": [], "vers": [], "req": [[0, "any", [0]]],
+        }),
+    );
+    let deck_json = |id: i64, name: &str| {
+        json!({
+            "id": id, "name": name, "mod": now, "usn": -1, "desc": "", "dyn": 0, "conf": 1, "collapsed": false,
+            "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0],
+            "extendNew": 10, "extendRev": 50,
+        })
+    };
+    let mut decks = serde_json::Map::new();
+    decks.insert("1".to_string(), deck_json(1, "Default"));
+    decks.insert(deck_id.to_string(), deck_json(deck_id, deck));
+    let dconf = json!({
+        "1": {
+            "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60, "autoplay": true, "timer": 0,
+            "replayq": true, "dyn": false,
+            "new": { "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500, "order": 1, "perDay": 20, "bury": true },
+            "rev": { "perDay": 100, "ease4": 1.3, "fuzz": 0.05, "ivlFct": 1, "maxIvl": 36500, "bury": true },
+            "lapse": { "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0 },
+        }
+    });
+    let conf = json!({
+        "activeDecks": [1], "curDeck": 1, "newSpread": 0, "collapseTime": 1200, "timeLim": 0,
+        "estTimes": true, "dueCounts": true, "curModel": null, "nextPos": cards.len() + 1, "sortType": "noteFld",
+        "sortBackwards": false, "addToCur": true,
+    });
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?3, 11, 0, 0, 0, ?4, ?5, ?6, ?7, '{}')",
+        params![
+            now,
+            now_ms,
+            now_ms,
+            conf.to_string(),
+            Value::Object(models).to_string(),
+            Value::Object(decks).to_string(),
+            dconf.to_string()
+        ],
+    )
+    .map_err(|e| AppError::internal(format!("Failed to write Anki collection: {}", e)))?;
+
+    for (position, card) in cards.iter().enumerate() {
+        let note_id = now_ms + position as i64;
+        let digest = Sha1::digest(card.question.as_bytes());
+        let checksum = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+        let fields = format!("{}\x1f{}", field_html(&card.question), field_html(&card.answer));
+        conn.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            params![
+                note_id,
+                card.guid,
+                APKG_MODEL_ID,
+                now,
+                format!(" los los-clip-{} ", card.clip_id),
+                fields,
+                card.question,
+                checksum
+            ],
+        )
+        .map_err(|e| AppError::internal(format!("Failed to write Anki note: {}", e)))?;
+        conn.execute(
+            "INSERT INTO cards VALUES (?1, ?2, ?3, 0, ?4, -1, 0, 0, ?5, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            params![note_id, note_id, deck_id, now, position as i64 + 1],
+        )
+        .map_err(|e| AppError::internal(format!("Failed to write Anki card: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Send every card of `clip_ids` to Anki, into `deck` (default `DEFAULT_DECK`)
+pub async fn export_flashcards(
+    app_handle: &AppHandle,
+    clip_ids: Vec<i64>,
+    target: FlashcardTarget,
+    deck: Option<String>,
+) -> Result<FlashcardExportSummary, AppError> {
+    let deck = deck
+        .map(|deck| deck.trim().to_string())
+        .filter(|deck| !deck.is_empty())
+        .unwrap_or_else(|| DEFAULT_DECK.to_string());
+    let cards = {
+        let db = app_handle.state::<Database>();
+        query_flashcards(&db.conn()?, "clip_id IN", &clip_ids)?
+    };
+    if cards.is_empty() {
+        return Err(AppError::validation("These clips have no flashcards yet; generate some first"));
+    }
+
+    let (exported_ids, skipped, path) = match target {
+        FlashcardTarget::Apkg { path } => {
+            let export_cards = cards.clone();
+            let dest = path.clone();
+            tauri::async_runtime::spawn_blocking(move || write_apkg(&export_cards, &dest, &deck))
+                .await
+                .map_err(|e| AppError::internal(format!("Anki export failed: {}", e)))??;
+            (cards.iter().map(|card| card.id).collect::<Vec<_>>(), 0, Some(path))
+        }
+        FlashcardTarget::AnkiConnect { url } => {
+            let url = url.unwrap_or_else(|| DEFAULT_ANKI_CONNECT_URL.to_string());
+            let (ids, skipped) = export_anki_connect(&cards, &url, &deck).await?;
+            (ids, skipped, None)
+        }
+    };
+
+    let db = app_handle.state::<Database>();
+    mark_exported(&db.conn()?, &exported_ids)?;
+    Ok(FlashcardExportSummary {
+        exported: exported_ids.len(),
+        skipped,
+        path,
+    })
+}
@@ -0,0 +1,104 @@
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tracing::warn;
+
+use crate::clips::{now_millis, ClipData};
+use crate::errors::AppError;
+use crate::notifications::{notify, NotificationKind};
+use crate::settings::SettingsManager;
+
+/// Shortcut used until the user picks another one
+pub const DEFAULT_CAPTURE_SHORTCUT: &str = "CommandOrControl+Shift+L";
+/// Longest note title taken from the first line of the captured text
+const MAX_TITLE_CHARS: usize = 80;
+
+/// Register the capture shortcut from settings
+pub fn register_from_settings(app_handle: &AppHandle) -> Result<(), AppError> {
+    let shortcut = app_handle.state::<SettingsManager>().get().capture_shortcut().to_string();
+    register(app_handle, &shortcut)
+}
+
+/// Move the capture shortcut from `previous` to the one in settings, after settings
+/// were replaced wholesale (e.g. by switching profiles)
+pub fn reregister(app_handle: &AppHandle, previous: &str) -> Result<(), AppError> {
+    let current = app_handle.state::<SettingsManager>().get().capture_shortcut().to_string();
+    if current == previous {
+        return Ok(());
+    }
+    if let Err(e) = app_handle.global_shortcut().unregister(previous) {
+        warn!("Failed to unregister shortcut {}: {}", previous, e);
+    }
+    register(app_handle, &current)
+}
+
+/// Swap the capture shortcut for `shortcut` and save it. If the new binding can't
+/// be registered (invalid, or taken by another app) the old one stays active.
+pub fn set_capture_shortcut(app_handle: &AppHandle, shortcut: &str) -> Result<(), AppError> {
+    let settings = app_handle.state::<SettingsManager>();
+    let current = settings.get().capture_shortcut().to_string();
+    if shortcut == current {
+        return Ok(());
+    }
+
+    let global_shortcut = app_handle.global_shortcut();
+    if let Err(e) = global_shortcut.unregister(current.as_str()) {
+        warn!("Failed to unregister shortcut {}: {}", current, e);
+    }
+    if let Err(e) = register(app_handle, shortcut) {
+        register(app_handle, &current)?;
+        return Err(e);
+    }
+    settings.update(|s| s.capture_shortcut = Some(shortcut.to_string()))?;
+    Ok(())
+}
+
+fn register(app_handle: &AppHandle, shortcut: &str) -> Result<(), AppError> {
+    app_handle
+        .global_shortcut()
+        .on_shortcut(shortcut, |app_handle, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                capture_clipboard(app_handle);
+            }
+        })
+        .map_err(|e| AppError::validation(format!("Failed to register shortcut {}: {}", shortcut, e)))
+}
+
+/// Save the clipboard text as a note clip and confirm with a notification.
+/// Selected text is captured by copying it first; reading another app's
+/// selection directly isn't portable.
+pub(crate) fn capture_clipboard(app_handle: &AppHandle) {
+    let message = match app_handle.clipboard().read_text() {
+        Ok(text) if !text.trim().is_empty() => match crate::ingest_clip(app_handle, note_from_text(&text)) {
+            Ok(inserted) if inserted.merged => format!("Already saved: {}", inserted.clip.title),
+            Ok(inserted) => format!("Saved note: {}", inserted.clip.title),
+            Err(e) => format!("Couldn't save clip: {}", e),
+        },
+        Ok(_) => "The clipboard is empty".to_string(),
+        Err(e) => format!("Couldn't read the clipboard: {}", e),
+    };
+
+    notify(app_handle, NotificationKind::Capture, "LOS", &message);
+}
+
+/// A note clip titled with the first line of `text`
+pub(crate) fn note_from_text(text: &str) -> ClipData {
+    let first_line = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("Note");
+    let mut title: String = first_line.chars().take(MAX_TITLE_CHARS).collect();
+    if first_line.chars().count() > MAX_TITLE_CHARS {
+        title.push('…');
+    }
+
+    ClipData {
+        r#type: "note".to_string(),
+        title,
+        url: None,
+        content: Some(text.to_string()),
+        image_url: None,
+        description: None,
+        author: None,
+        timestamp: now_millis() as u64,
+        original_url: None,
+        language: None,
+    }
+}
@@ -0,0 +1,153 @@
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::RwLock;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::errors::AppError;
+
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 20;
+/// Generous enough for long LLM answers and large downloads
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 300;
+/// Local services (Ollama, SearxNG on localhost) skip the proxy unless `no_proxy` says otherwise
+const DEFAULT_NO_PROXY: &str = "localhost,127.0.0.1,::1";
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// How outgoing HTTP requests reach the network, set in `Settings::network`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// Proxy for every request, e.g. `http://proxy.corp:3128` or `socks5h://127.0.0.1:1080`;
+    /// credentials go in the URL as `user:password@`
+    pub proxy_url: Option<String>,
+    /// Comma-separated hosts and domains that bypass the proxy; defaults to `DEFAULT_NO_PROXY`
+    pub no_proxy: Option<String>,
+    /// PEM file of extra root certificates, for proxies that re-sign TLS traffic
+    pub ca_bundle_path: Option<String>,
+    /// Defaults to `DEFAULT_CONNECT_TIMEOUT_SECS`
+    pub connect_timeout_secs: Option<u64>,
+    /// Limit on a whole request, body included; defaults to `DEFAULT_REQUEST_TIMEOUT_SECS`
+    pub request_timeout_secs: Option<u64>,
+}
+
+impl NetworkSettings {
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout_secs.unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS))
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs.unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS))
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        self.proxy()?;
+        self.root_certificates()?;
+        if self.connect_timeout_secs == Some(0) || self.request_timeout_secs == Some(0) {
+            return Err(AppError::validation("Network timeouts must be at least one second"));
+        }
+        Ok(())
+    }
+
+    fn proxy(&self) -> Result<Option<Proxy>, AppError> {
+        let Some(url) = self.proxy_url.as_deref().map(str::trim).filter(|url| !url.is_empty()) else {
+            return Ok(None);
+        };
+        let parsed =
+            reqwest::Url::parse(url).map_err(|e| AppError::validation(format!("Invalid proxy URL '{}': {}", url, e)))?;
+        if !PROXY_SCHEMES.contains(&parsed.scheme()) {
+            return Err(AppError::validation(format!(
+                "Unsupported proxy scheme '{}'; use one of: {}",
+                parsed.scheme(),
+                PROXY_SCHEMES.join(", ")
+            )));
+        }
+        let proxy = Proxy::all(url).map_err(|e| AppError::validation(format!("Invalid proxy URL '{}': {}", url, e)))?;
+        let no_proxy = self.no_proxy.as_deref().unwrap_or(DEFAULT_NO_PROXY);
+        Ok(Some(proxy.no_proxy(NoProxy::from_string(no_proxy))))
+    }
+
+    /// Every certificate in the CA bundle
+    fn root_certificates(&self) -> Result<Vec<Certificate>, AppError> {
+        let Some(path) = self.ca_bundle_path.as_deref().map(str::trim).filter(|path| !path.is_empty()) else {
+            return Ok(Vec::new());
+        };
+        let pem = fs::read_to_string(path)
+            .map_err(|e| AppError::validation(format!("Failed to read CA bundle {}: {}", path, e)))?;
+        let mut certificates = Vec::new();
+        let mut rest = pem.as_str();
+        while let Some(start) = rest.find(PEM_BEGIN) {
+            let end = rest[start..]
+                .find(PEM_END)
+                .map(|end| start + end + PEM_END.len())
+                .ok_or_else(|| AppError::validation(format!("Unterminated certificate in CA bundle {}", path)))?;
+            let certificate = Certificate::from_pem(rest[start..end].as_bytes())
+                .map_err(|e| AppError::validation(format!("Invalid certificate in CA bundle {}: {}", path, e)))?;
+            certificates.push(certificate);
+            rest = &rest[end..];
+        }
+        if certificates.is_empty() {
+            return Err(AppError::validation(format!("No PEM certificates found in CA bundle {}", path)));
+        }
+        Ok(certificates)
+    }
+
+    /// `builder` with the proxy, extra root certificates and timeouts applied
+    fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, AppError> {
+        let mut builder = builder
+            .connect_timeout(self.connect_timeout())
+            .timeout(self.request_timeout());
+        if let Some(proxy) = self.proxy()? {
+            builder = builder.proxy(proxy);
+        }
+        for certificate in self.root_certificates()? {
+            builder = builder.add_root_certificate(certificate);
+        }
+        Ok(builder)
+    }
+}
+
+/// The settings the shared client was built from, and the client
+static NETWORK: RwLock<Option<(NetworkSettings, reqwest::Client)>> = RwLock::new(None);
+
+fn build(builder: ClientBuilder) -> Result<reqwest::Client, AppError> {
+    builder
+        .build()
+        .map_err(|e| AppError::internal(format!("Failed to build HTTP client: {}", e)))
+}
+
+/// Rebuild the shared client; called at startup and whenever the settings change. A
+/// client that can't be built is logged and the previous one kept.
+pub fn configure(settings: &NetworkSettings) {
+    if NETWORK.read().unwrap().as_ref().is_some_and(|(current, _)| current == settings) {
+        return;
+    }
+    match settings.apply(reqwest::Client::builder()).and_then(build) {
+        Ok(client) => {
+            if settings.proxy_url.is_some() {
+                info!("Sending HTTP requests through the configured proxy");
+            }
+            *NETWORK.write().unwrap() = Some((settings.clone(), client));
+        }
+        Err(e) => error!("Failed to apply network settings: {}", e),
+    }
+}
+
+/// The shared HTTP client. Clones share one connection pool, so call this freely
+/// rather than keeping a client around.
+pub fn client() -> reqwest::Client {
+    match NETWORK.read().unwrap().as_ref() {
+        Some((_, client)) => client.clone(),
+        // Only before `configure` has run
+        None => reqwest::Client::new(),
+    }
+}
+
+/// A builder with the configured proxy, certificates and timeouts, for the few clients
+/// that need more (no redirects, a shorter timeout)
+pub fn builder() -> Result<ClientBuilder, AppError> {
+    let settings = NETWORK.read().unwrap().as_ref().map(|(settings, _)| settings.clone()).unwrap_or_default();
+    settings.apply(reqwest::Client::builder())
+}
@@ -0,0 +1,209 @@
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::clips::{self, now_millis, ClipData};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::tags;
+
+/// Instapaper folders that are reading states rather than user folders
+const INSTAPAPER_BUILTIN_FOLDERS: &[&str] = &["Unread", "Archive"];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    /// Pocket export, either the legacy `ril_export.html` or the newer CSV
+    Pocket,
+    /// Instapaper CSV export
+    Instapaper,
+    /// Netscape bookmark file, as exported by every major browser
+    Bookmarks,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub imported: u32,
+    /// Entries whose URL is already saved (or repeated earlier in the file)
+    pub skipped: u32,
+    /// Entries without a usable http(s) URL
+    pub invalid: u32,
+}
+
+/// One saved link read from an export file
+struct ImportEntry {
+    url: String,
+    title: Option<String>,
+    description: Option<String>,
+    /// Milliseconds since the epoch
+    saved_at: Option<u64>,
+    tags: Vec<String>,
+}
+
+/// Read the export at `path` and add each new link as a `url` clip with its tags.
+/// Links already in the library are skipped. Imported clips aren't queued for
+/// enrichment, so a large import doesn't turn into thousands of LLM calls.
+pub async fn import_clips(app_handle: &AppHandle, source: ImportSource, path: PathBuf) -> Result<ImportSummary, AppError> {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let summary = import_file(&app_handle, source, &path)?;
+        events::emit(&app_handle, "clips-imported", &summary)
+            .map_err(|e| AppError::internal(format!("Failed to emit import event: {}", e)))?;
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| AppError::internal(format!("Import failed: {}", e)))?
+}
+
+fn import_file(app_handle: &AppHandle, source: ImportSource, path: &Path) -> Result<ImportSummary, AppError> {
+    let data = fs::read_to_string(path).map_err(|e| AppError::internal(format!("Failed to read {}: {}", path.display(), e)))?;
+    let entries = match source {
+        ImportSource::Pocket if data.trim_start().starts_with('<') => parse_html_links(&data),
+        ImportSource::Pocket => parse_pocket_csv(&data)?,
+        ImportSource::Instapaper => parse_instapaper_csv(&data)?,
+        ImportSource::Bookmarks => parse_html_links(&data),
+    };
+
+    let db = app_handle.state::<Database>();
+    let mut conn = db.conn()?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::database(format!("Failed to start import: {}", e)))?;
+
+    let mut summary = ImportSummary {
+        imported: 0,
+        skipped: 0,
+        invalid: 0,
+    };
+    for entry in entries {
+        if !is_web_url(&entry.url) {
+            summary.invalid += 1;
+            continue;
+        }
+        // Short links aren't resolved here; fetching each would make large imports crawl
+        let clip_data = clips::canonicalize(ClipData {
+            r#type: "url".to_string(),
+            title: entry
+                .title
+                .filter(|title| !title.trim().is_empty())
+                .unwrap_or_else(|| entry.url.clone()),
+            url: Some(entry.url),
+            content: None,
+            image_url: None,
+            description: entry.description.filter(|d| !d.trim().is_empty()),
+            author: None,
+            timestamp: entry.saved_at.unwrap_or_else(|| now_millis() as u64),
+            original_url: None,
+            language: None,
+        });
+        if clips::find_duplicate(&tx, &clip_data)?.is_some() {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let clip = clips::insert_clip(&tx, &clip_data)?;
+        for tag in entry.tags.iter().filter(|tag| tags::normalize_tag(tag).is_ok()) {
+            tags::add_tag_to_clip(&tx, clip.id, tag)?;
+        }
+        summary.imported += 1;
+    }
+
+    tx.commit().map_err(|e| AppError::database(format!("Failed to save import: {}", e)))?;
+    Ok(summary)
+}
+
+fn is_web_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Seconds-since-epoch attribute or column, as milliseconds
+fn parse_seconds(value: &str) -> Option<u64> {
+    value.trim().parse::<u64>().ok().filter(|secs| *secs > 0).map(|secs| secs * 1000)
+}
+
+/// Links in a Netscape bookmark file or Pocket HTML export: `<a href add_date|time_added tags>`
+fn parse_html_links(html: &str) -> Vec<ImportEntry> {
+    let document = Html::parse_document(html);
+    let links = Selector::parse("a[href]").expect("static selector is valid");
+
+    document
+        .select(&links)
+        .filter_map(|link| {
+            let attr = |name: &str| link.value().attr(name);
+            let title = link.text().collect::<String>();
+            Some(ImportEntry {
+                url: attr("href")?.trim().to_string(),
+                title: Some(title.trim().to_string()),
+                description: None,
+                saved_at: attr("time_added").or_else(|| attr("add_date")).and_then(parse_seconds),
+                tags: attr("tags").map(|tags| split_tags(tags, ',')).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn split_tags(tags: &str, separator: char) -> Vec<String> {
+    tags.split(separator)
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Rows of a CSV export keyed by lowercased header name
+fn csv_rows(data: &str) -> Result<Vec<HashMap<String, String>>, AppError> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| AppError::validation(format!("Failed to read CSV header: {}", e)))?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|e| AppError::validation(format!("Failed to read CSV row: {}", e)))?;
+            Ok(headers.iter().cloned().zip(record.iter().map(str::to_string)).collect())
+        })
+        .collect()
+}
+
+/// Pocket CSV: `title,url,time_added,tags,status`, tags separated by `|`
+fn parse_pocket_csv(data: &str) -> Result<Vec<ImportEntry>, AppError> {
+    Ok(csv_rows(data)?
+        .into_iter()
+        .map(|mut row| ImportEntry {
+            url: row.remove("url").unwrap_or_default().trim().to_string(),
+            title: row.remove("title"),
+            description: None,
+            saved_at: row.get("time_added").and_then(|t| parse_seconds(t)),
+            tags: row.get("tags").map(|tags| split_tags(tags, '|')).unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Instapaper CSV: `URL,Title,Selection,Folder,Timestamp`. User folders become tags.
+fn parse_instapaper_csv(data: &str) -> Result<Vec<ImportEntry>, AppError> {
+    Ok(csv_rows(data)?
+        .into_iter()
+        .map(|mut row| {
+            let folder = row.remove("folder").map(|f| f.trim().to_string()).unwrap_or_default();
+            ImportEntry {
+                url: row.remove("url").unwrap_or_default().trim().to_string(),
+                title: row.remove("title"),
+                description: row.remove("selection"),
+                saved_at: row.get("timestamp").and_then(|t| parse_seconds(t)),
+                tags: if folder.is_empty() || INSTAPAPER_BUILTIN_FOLDERS.contains(&folder.as_str()) {
+                    Vec::new()
+                } else {
+                    vec![folder]
+                },
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,100 @@
+use futures::future::{AbortHandle, Abortable, Aborted};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::errors::AppError;
+
+/// Timeouts for commands not listed in `Settings::command_timeouts`, in seconds
+const DEFAULT_TIMEOUTS: &[(&str, u64)] = &[
+    ("fetch_url_content", 30),
+    ("clip_search_result", 45),
+    ("search_brave", 30),
+    ("search_google", 30),
+    ("web_search", 30),
+    ("search_web", 45),
+    ("validate_secret", 20),
+    ("call_llm", 180),
+    ("ask_clips", 180),
+    ("call_llm_with_tools", 600),
+];
+/// For commands missing from `DEFAULT_TIMEOUTS`
+const FALLBACK_TIMEOUT_SECS: u64 = 120;
+
+/// How long `command` may run before it fails with a `timeout` error
+pub fn default_timeout(command: &str) -> Duration {
+    let secs = DEFAULT_TIMEOUTS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map_or(FALLBACK_TIMEOUT_SECS, |(_, secs)| *secs);
+    Duration::from_secs(secs)
+}
+
+/// Abort handles of running network commands that were given a request id, managed as
+/// Tauri state. `cancel_request` looks them up to drop the command's future.
+#[derive(Default)]
+pub struct RequestRegistry {
+    handles: Mutex<HashMap<String, AbortHandle>>,
+}
+
+/// Forgets a request's handle however its command ends, including when Tauri drops it
+struct Registration<'a> {
+    registry: &'a RequestRegistry,
+    request_id: Option<String>,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        if let Some(request_id) = &self.request_id {
+            self.registry.handles.lock().unwrap().remove(request_id);
+        }
+    }
+}
+
+impl RequestRegistry {
+    /// Run a command's `future`, failing with `timeout` once it takes longer than
+    /// `timeout` and with `cancelled` if `cancel` is called with `request_id`
+    pub async fn run<T, E, F>(
+        &self,
+        request_id: Option<String>,
+        command: &str,
+        timeout: Duration,
+        future: F,
+    ) -> Result<T, AppError>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Into<AppError>,
+    {
+        let (handle, abort_registration) = AbortHandle::new_pair();
+        if let Some(request_id) = &request_id {
+            let mut handles = self.handles.lock().unwrap();
+            if handles.contains_key(request_id) {
+                return Err(AppError::validation(format!("Request id '{}' is already in use", request_id)));
+            }
+            handles.insert(request_id.clone(), handle);
+        }
+        let _registration = Registration {
+            registry: self,
+            request_id,
+        };
+
+        match tokio::time::timeout(timeout, Abortable::new(future, abort_registration)).await {
+            Ok(Ok(result)) => result.map_err(Into::into),
+            Ok(Err(Aborted)) => Err(AppError::cancelled(format!("{} was cancelled", command))),
+            Err(_) => Err(AppError::timeout(format!("{} timed out after {}s", command, timeout.as_secs()))),
+        }
+    }
+
+    /// Abort the request started with `request_id`. Returns false if it already finished
+    /// or never existed.
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.handles.lock().unwrap().remove(request_id) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
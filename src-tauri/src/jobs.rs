@@ -0,0 +1,495 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{Notify, OwnedRwLockWriteGuard, RwLock};
+use tracing::{error, info, warn};
+
+use crate::archive;
+use crate::audio;
+use crate::autotag;
+use crate::clips::{self, now_millis};
+use crate::code;
+use crate::connectivity;
+use crate::db::Database;
+use crate::digest;
+use crate::embeddings;
+use crate::feeds;
+use crate::links;
+use crate::errors::AppError;
+use crate::maintenance;
+use crate::media;
+use crate::notifications::{self, NotificationKind};
+use crate::ocr;
+use crate::providers::LlmError;
+use crate::redact;
+use crate::research;
+use crate::site_metadata;
+use crate::summarize;
+use crate::video;
+use crate::wayback;
+
+/// Concurrent workers; kept low since most jobs are rate-limited API calls
+const WORKERS: usize = 2;
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+/// First job-level retry delay, doubled per attempt (requests already retry briefly on their own)
+const RETRY_BASE: Duration = Duration::from_secs(30);
+/// How often idle workers look for delayed jobs that have become due
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// A job that lost the network mid-run waits this long before it's tried again
+const OFFLINE_RETRY: Duration = Duration::from_secs(60);
+/// Kinds that only talk to the internet; while offline they stay queued instead of failing.
+/// LLM jobs still run, since `ProviderRegistry` can route them to Ollama.
+const INTERNET_JOBS: &[&str] = &[
+    "download_image",
+    "fetch_transcript",
+    "refresh_feed",
+    "research_topic",
+    "archive_page",
+    "resolve_url",
+    "check_links",
+    "save_to_wayback",
+    "fetch_site_metadata",
+];
+
+/// Work the queue knows how to run. Stored as JSON in `jobs.payload`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobKind {
+    SummarizeClip { clip_id: i64 },
+    AutoTagClip { clip_id: i64 },
+    EmbedClips { clip_ids: Vec<i64> },
+    DownloadImage { clip_id: i64 },
+    OcrClip { clip_id: i64 },
+    FetchTranscript { clip_id: i64 },
+    RefreshFeed { feed_id: i64 },
+    ResearchTopic { query: String, sources: u32 },
+    TranscribeAudio { clip_id: i64 },
+    ArchivePage { clip_id: i64 },
+    ResolveUrl { clip_id: i64 },
+    CheckLinks { clip_ids: Vec<i64> },
+    SaveToWayback { clip_id: i64 },
+    FetchSiteMetadata { clip_id: i64 },
+    DetectCodeLanguage { clip_id: i64 },
+    GenerateDigest,
+    IndexMaintenance,
+}
+
+impl JobKind {
+    fn name(&self) -> &'static str {
+        match self {
+            JobKind::SummarizeClip { .. } => "summarize_clip",
+            JobKind::AutoTagClip { .. } => "auto_tag_clip",
+            JobKind::EmbedClips { .. } => "embed_clips",
+            JobKind::DownloadImage { .. } => "download_image",
+            JobKind::OcrClip { .. } => "ocr_clip",
+            JobKind::FetchTranscript { .. } => "fetch_transcript",
+            JobKind::RefreshFeed { .. } => "refresh_feed",
+            JobKind::ResearchTopic { .. } => "research_topic",
+            JobKind::TranscribeAudio { .. } => "transcribe_audio",
+            JobKind::ArchivePage { .. } => "archive_page",
+            JobKind::ResolveUrl { .. } => "resolve_url",
+            JobKind::CheckLinks { .. } => "check_links",
+            JobKind::SaveToWayback { .. } => "save_to_wayback",
+            JobKind::FetchSiteMetadata { .. } => "fetch_site_metadata",
+            JobKind::DetectCodeLanguage { .. } => "detect_code_language",
+            JobKind::GenerateDigest => "generate_digest",
+            JobKind::IndexMaintenance => "index_maintenance",
+        }
+    }
+}
+
+/// Job statuses: `queued` -> `running` -> `done` | `failed` | `cancelled`.
+/// A failed attempt that can be retried goes back to `queued` with a later `run_after`.
+pub const JOB_STATUSES: &[&str] = &["queued", "running", "done", "failed", "cancelled"];
+
+/// A queued or finished job; also the payload of the `job-updated` event
+#[derive(Debug, Serialize, Clone)]
+pub struct Job {
+    pub id: i64,
+    pub kind: JobKind,
+    pub status: String,
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub last_error: Option<String>,
+    /// Milliseconds since the epoch before which the job won't start
+    pub run_after: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const JOB_COLUMNS: &str = "id, payload, status, attempts, max_attempts, last_error, run_after, created_at, updated_at";
+
+impl Job {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let payload: String = row.get(1)?;
+        let kind = serde_json::from_str(&payload)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e)))?;
+        Ok(Job {
+            id: row.get(0)?,
+            kind,
+            status: row.get(2)?,
+            attempts: row.get(3)?,
+            max_attempts: row.get(4)?,
+            last_error: row.get(5)?,
+            run_after: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }
+}
+
+fn get_job(conn: &Connection, id: i64) -> Result<Option<Job>, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
+        params![id],
+        Job::from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read job: {}", e)))
+}
+
+/// Queue `kind` unless an identical job is already waiting or running. Returns the job
+/// and whether it was newly created.
+pub fn enqueue(conn: &Connection, kind: &JobKind) -> Result<(Job, bool), AppError> {
+    let payload = serde_json::to_string(kind).map_err(|e| AppError::internal(format!("Failed to serialize job: {}", e)))?;
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM jobs WHERE payload = ?1 AND status IN ('queued', 'running')",
+            params![payload],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to check for duplicate job: {}", e)))?;
+    if let Some(id) = existing {
+        let job = get_job(conn, id)?.ok_or_else(|| AppError::not_found(format!("Job {} not found", id)))?;
+        return Ok((job, false));
+    }
+
+    let now = now_millis();
+    conn.execute(
+        "INSERT INTO jobs (kind, payload, status, attempts, max_attempts, run_after, created_at, updated_at)
+         VALUES (?1, ?2, 'queued', 0, ?3, ?4, ?4, ?4)",
+        params![kind.name(), payload, DEFAULT_MAX_ATTEMPTS, now],
+    )
+    .map_err(|e| AppError::database(format!("Failed to queue job: {}", e)))?;
+    let id = conn.last_insert_rowid();
+    let job = get_job(conn, id)?.ok_or_else(|| AppError::database(format!("Job {} vanished after insert", id)))?;
+    Ok((job, true))
+}
+
+/// Atomically take the oldest due job and mark it running. Offline, `INTERNET_JOBS`
+/// are left for later.
+fn claim_next(conn: &Connection, online: bool) -> Result<Option<Job>, AppError> {
+    let now = now_millis();
+    let internet_jobs = INTERNET_JOBS.iter().map(|kind| format!("'{}'", kind)).collect::<Vec<_>>().join(", ");
+    conn.query_row(
+        &format!(
+            "UPDATE jobs SET status = 'running', attempts = attempts + 1, updated_at = ?1
+             WHERE id = (
+                SELECT id FROM jobs WHERE status = 'queued' AND run_after <= ?1
+                AND (?2 OR kind NOT IN ({}))
+                ORDER BY run_after, id LIMIT 1
+             )
+             RETURNING {}",
+            internet_jobs, JOB_COLUMNS
+        ),
+        params![now, online],
+        Job::from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to claim job: {}", e)))
+}
+
+/// Record the outcome of a run. Only applies while the job is still `running`, so a
+/// cancellation that arrived mid-run is not overwritten.
+fn finish(conn: &Connection, job: &Job, outcome: Result<(), LlmError>) -> Result<Option<Job>, AppError> {
+    let now = now_millis();
+    match outcome {
+        Ok(()) => conn.execute(
+            "UPDATE jobs SET status = 'done', last_error = NULL, updated_at = ?1 WHERE id = ?2 AND status = 'running'",
+            params![now, job.id],
+        ),
+        // Lost the network mid-run: wait for it without using up an attempt
+        Err(e) if matches!(e, LlmError::Offline { .. }) || (e.is_retryable() && !connectivity::is_online()) => {
+            conn.execute(
+                "UPDATE jobs SET status = 'queued', attempts = attempts - 1, last_error = ?1, run_after = ?2,
+                 updated_at = ?3 WHERE id = ?4 AND status = 'running'",
+                params![redact::redact(&e.to_string()), now + OFFLINE_RETRY.as_millis() as i64, now, job.id],
+            )
+        }
+        Err(e) if e.is_retryable() && job.attempts < job.max_attempts => {
+            let delay = RETRY_BASE.saturating_mul(1u32 << job.attempts.saturating_sub(1).min(10));
+            conn.execute(
+                "UPDATE jobs SET status = 'queued', last_error = ?1, run_after = ?2, updated_at = ?3
+                 WHERE id = ?4 AND status = 'running'",
+                params![redact::redact(&e.to_string()), now + delay.as_millis() as i64, now, job.id],
+            )
+        }
+        // Poison: permanent errors and jobs out of attempts stay failed until retried by hand
+        Err(e) => conn.execute(
+            "UPDATE jobs SET status = 'failed', last_error = ?1, updated_at = ?2 WHERE id = ?3 AND status = 'running'",
+            params![redact::redact(&e.to_string()), now, job.id],
+        ),
+    }
+    .map_err(|e| AppError::database(format!("Failed to update job: {}", e)))?;
+    get_job(conn, job.id)
+}
+
+/// Jobs newest first, optionally only those with `status`
+pub fn list_jobs(conn: &Connection, status: Option<&str>, limit: u32) -> Result<Vec<Job>, AppError> {
+    if let Some(status) = status {
+        if !JOB_STATUSES.contains(&status) {
+            return Err(AppError::validation(format!("Invalid job status '{}'. Must be one of: {}", status, JOB_STATUSES.join(", "))));
+        }
+    }
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM jobs WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC LIMIT ?2",
+            JOB_COLUMNS
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let jobs = stmt
+        .query_map(params![status, limit], Job::from_row)
+        .map_err(|e| AppError::database(format!("Failed to list jobs: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read job: {}", e)))?;
+    Ok(jobs)
+}
+
+/// Cancel a queued or running job. A running job finishes its current step but its result is discarded.
+pub fn cancel_job(conn: &Connection, id: i64) -> Result<Job, AppError> {
+    let changed = conn
+        .execute(
+            "UPDATE jobs SET status = 'cancelled', updated_at = ?1 WHERE id = ?2 AND status IN ('queued', 'running')",
+            params![now_millis(), id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to cancel job: {}", e)))?;
+    let job = get_job(conn, id)?.ok_or_else(|| AppError::not_found(format!("Job {} not found", id)))?;
+    if changed == 0 {
+        return Err(AppError::validation(format!("Job {} is already {}", id, job.status)));
+    }
+    Ok(job)
+}
+
+/// Put a failed or cancelled job back in the queue with a fresh set of attempts
+pub fn retry_job(conn: &Connection, id: i64) -> Result<Job, AppError> {
+    let now = now_millis();
+    let changed = conn
+        .execute(
+            "UPDATE jobs SET status = 'queued', attempts = 0, run_after = ?1, updated_at = ?1
+             WHERE id = ?2 AND status IN ('failed', 'cancelled')",
+            params![now, id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to retry job: {}", e)))?;
+    let job = get_job(conn, id)?.ok_or_else(|| AppError::not_found(format!("Job {} not found", id)))?;
+    if changed == 0 {
+        return Err(AppError::validation(format!("Job {} is {} and can't be retried", id, job.status)));
+    }
+    Ok(job)
+}
+
+/// Jobs left `running` by a previous session never finished; queue them again
+fn requeue_interrupted(conn: &Connection) -> Result<usize, AppError> {
+    conn.execute(
+        "UPDATE jobs SET status = 'queued', updated_at = ?1 WHERE status = 'running'",
+        params![now_millis()],
+    )
+    .map_err(|e| AppError::database(format!("Failed to requeue interrupted jobs: {}", e)))
+}
+
+async fn execute(app_handle: &AppHandle, kind: &JobKind) -> Result<(), LlmError> {
+    match kind {
+        JobKind::SummarizeClip { clip_id } => summarize::summarize_clip(app_handle, *clip_id).await.map(|_| ()),
+        JobKind::AutoTagClip { clip_id } => autotag::auto_tag_clip(app_handle, *clip_id).await.map(|_| ()),
+        JobKind::EmbedClips { clip_ids } => embeddings::index_clips(app_handle, clip_ids).await.map(|_| ()),
+        // Download failures are usually transient (timeouts, flaky hosts), so let the queue retry them
+        JobKind::DownloadImage { clip_id } => media::store_clip_image(app_handle, *clip_id)
+            .await
+            .map(|_| ())
+            .map_err(|e| LlmError::Network { message: e.to_string() }),
+        // Only network errors (fetching the image) are retried; OCR itself fails the same way twice
+        JobKind::OcrClip { clip_id } => ocr::ocr_clip(app_handle, *clip_id).await.map(|_| ()).map_err(LlmError::from),
+        JobKind::FetchTranscript { clip_id } => video::fetch_transcript(app_handle, *clip_id)
+            .await
+            .map(|_| ())
+            .map_err(LlmError::from),
+        JobKind::RefreshFeed { feed_id } => feeds::refresh_feed(app_handle, *feed_id)
+            .await
+            .map(|_| ())
+            .map_err(LlmError::from),
+        JobKind::ResearchTopic { query, sources } => research::research_topic(app_handle, query, *sources).await.map(|_| ()),
+        JobKind::TranscribeAudio { clip_id } => audio::transcribe_clip(app_handle, *clip_id).await.map(|_| ()),
+        // Like image downloads, a failed page fetch is worth retrying
+        JobKind::ArchivePage { clip_id } => archive::archive_clip(app_handle, *clip_id)
+            .await
+            .map(|_| ())
+            .map_err(|e| LlmError::Network { message: e.to_string() }),
+        JobKind::ResolveUrl { clip_id } => links::resolve_clip_url(app_handle, *clip_id)
+            .await
+            .map(|_| ())
+            .map_err(LlmError::from),
+        JobKind::CheckLinks { clip_ids } => links::check_links(app_handle, clip_ids)
+            .await
+            .map(|_| ())
+            .map_err(LlmError::from),
+        // The save endpoint is rate-limited and often slow; let the queue retry
+        JobKind::SaveToWayback { clip_id } => wayback::save_to_wayback(app_handle, *clip_id)
+            .await
+            .map_err(|e| LlmError::Network { message: e.to_string() }),
+        JobKind::FetchSiteMetadata { clip_id } => site_metadata::fetch_site_metadata(app_handle, *clip_id)
+            .await
+            .map(|_| ())
+            .map_err(LlmError::from),
+        JobKind::DetectCodeLanguage { clip_id } => {
+            code::detect_language_with_llm(app_handle, *clip_id).await.map(|_| ())
+        }
+        JobKind::GenerateDigest => digest::generate_digest(app_handle).await.map(|_| ()),
+        JobKind::IndexMaintenance => maintenance::run_maintenance(app_handle).await.map(|_| ()),
+    }
+}
+
+/// Let the user know a summary or tag suggestions are ready to look at
+fn notify_finished(app_handle: &AppHandle, kind: &JobKind) {
+    let (clip_id, title) = match kind {
+        JobKind::SummarizeClip { clip_id } => (*clip_id, "Summary ready"),
+        JobKind::AutoTagClip { clip_id } => (*clip_id, "Tags suggested"),
+        JobKind::TranscribeAudio { clip_id } => (*clip_id, "Transcript ready"),
+        JobKind::EmbedClips { .. }
+        | JobKind::DownloadImage { .. }
+        | JobKind::OcrClip { .. }
+        | JobKind::FetchTranscript { .. }
+        | JobKind::RefreshFeed { .. }
+        | JobKind::ResearchTopic { .. }
+        | JobKind::ArchivePage { .. }
+        | JobKind::ResolveUrl { .. }
+        | JobKind::CheckLinks { .. }
+        | JobKind::SaveToWayback { .. }
+        | JobKind::FetchSiteMetadata { .. }
+        | JobKind::DetectCodeLanguage { .. }
+        | JobKind::GenerateDigest
+        | JobKind::IndexMaintenance => return,
+    };
+    let db = app_handle.state::<Database>();
+    let clip = db.conn().and_then(|conn| clips::get_clip(&conn, clip_id));
+    if let Ok(Some(clip)) = clip {
+        notifications::notify(app_handle, NotificationKind::Enrichment, title, &clip.title);
+    }
+}
+
+fn emit_job(app_handle: &AppHandle, job: &Job) {
+    if let Err(e) = redact::emit(app_handle, "job-updated", job) {
+        warn!("Failed to emit job event: {}", e);
+    }
+}
+
+/// Persistent background queue for enrichment work (summaries, tags, embeddings, images, OCR, transcripts, feeds)
+/// and research reports.
+/// Every status change is emitted as `job-updated`.
+pub struct JobQueue {
+    wake: Arc<Notify>,
+    /// Workers hold a read guard while they run a job; `pause` takes the write side
+    gate: Arc<RwLock<()>>,
+}
+
+impl JobQueue {
+    /// Requeue jobs interrupted by the last shutdown and start the worker pool
+    pub fn start(app_handle: AppHandle) -> Result<Self, AppError> {
+        {
+            let db = app_handle.state::<Database>();
+            let requeued = requeue_interrupted(&db.conn()?)?;
+            if requeued > 0 {
+                info!("Requeued {} interrupted job(s)", requeued);
+            }
+        }
+
+        let wake = Arc::new(Notify::new());
+        let gate = Arc::new(RwLock::new(()));
+        for _ in 0..WORKERS {
+            let app_handle = app_handle.clone();
+            let wake = wake.clone();
+            let gate = gate.clone();
+            tauri::async_runtime::spawn(async move { worker(app_handle, wake, gate).await });
+        }
+        Ok(Self { wake, gate })
+    }
+
+    /// Wait for running jobs to finish and keep workers from claiming new ones until
+    /// the returned guard is dropped
+    pub async fn pause(&self) -> OwnedRwLockWriteGuard<()> {
+        self.gate.clone().write_owned().await
+    }
+
+    /// Requeue jobs left running in a database that was just opened, such as another
+    /// profile's, and wake the workers for them
+    pub fn reload(&self, app_handle: &AppHandle) -> Result<(), AppError> {
+        let requeued = requeue_interrupted(&app_handle.state::<Database>().conn()?)?;
+        if requeued > 0 {
+            info!("Requeued {} interrupted job(s)", requeued);
+        }
+        self.wake.notify_waiters();
+        Ok(())
+    }
+
+    /// Queue a job and wake a worker. Duplicates of pending jobs are not added again.
+    pub fn submit(&self, app_handle: &AppHandle, kind: JobKind) -> Result<Job, AppError> {
+        let (job, created) = {
+            let db = app_handle.state::<Database>();
+            let conn = db.conn()?;
+            enqueue(&conn, &kind)?
+        };
+        if created {
+            emit_job(app_handle, &job);
+            self.wake.notify_one();
+        }
+        Ok(job)
+    }
+
+    /// Wake a worker after a job was requeued by hand
+    pub fn poke(&self) {
+        self.wake.notify_one();
+    }
+}
+
+async fn worker(app_handle: AppHandle, wake: Arc<Notify>, gate: Arc<RwLock<()>>) {
+    loop {
+        let running = gate.read().await;
+        let claimed = {
+            let db = app_handle.state::<Database>();
+            db.conn().and_then(|conn| claim_next(&conn, connectivity::is_online()))
+        };
+        let job = match claimed {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                drop(running);
+                let _ = tokio::time::timeout(POLL_INTERVAL, wake.notified()).await;
+                continue;
+            }
+            Err(e) => {
+                error!("Job worker error: {}", e);
+                drop(running);
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+        emit_job(&app_handle, &job);
+
+        let outcome = execute(&app_handle, &job.kind).await;
+        match &outcome {
+            Ok(()) => notify_finished(&app_handle, &job.kind),
+            Err(e) => error!("Job {} ({}) failed: {}", job.id, job.kind.name(), e),
+        }
+
+        let finished = {
+            let db = app_handle.state::<Database>();
+            db.conn().and_then(|conn| finish(&conn, &job, outcome))
+        };
+        match finished {
+            Ok(Some(job)) => emit_job(&app_handle, &job),
+            Ok(None) => {}
+            Err(e) => error!("Job worker error: {}", e),
+        }
+        drop(running);
+    }
+}
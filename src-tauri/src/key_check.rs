@@ -0,0 +1,99 @@
+use serde::Serialize;
+use std::time::Instant;
+
+use crate::connectivity;
+use crate::errors::AppError;
+use crate::providers::{KeyDetails, LlmError, ProviderRegistry};
+use crate::redact;
+use crate::search;
+use crate::secrets::SecretsManager;
+use crate::settings::Settings;
+
+/// Search keys aren't tied to an LLM provider, so they're matched by name
+const BRAVE_API_KEY: &str = "brave_api_key";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyStatus {
+    /// The service accepted the key
+    Valid,
+    /// The service rejected the key
+    Invalid,
+    /// The key works but the account is out of credit
+    QuotaExceeded,
+    /// The key works but is being throttled right now
+    RateLimited,
+    /// The service couldn't be reached or failed, so the key is unverified
+    Unreachable,
+    /// The check failed for another reason, given in `message`
+    Error,
+}
+
+impl KeyStatus {
+    fn from_error(error: &LlmError) -> Self {
+        match error {
+            LlmError::Auth { .. } => KeyStatus::Invalid,
+            LlmError::Quota { .. } => KeyStatus::QuotaExceeded,
+            LlmError::RateLimited { .. } => KeyStatus::RateLimited,
+            LlmError::Network { .. } | LlmError::Offline { .. } | LlmError::Server { .. } => KeyStatus::Unreachable,
+            LlmError::Api { .. } | LlmError::Other { .. } => KeyStatus::Error,
+        }
+    }
+}
+
+/// Outcome of probing a stored API key against its service
+#[derive(Debug, Serialize)]
+pub struct KeyValidation {
+    pub name: String,
+    /// Service the key was checked against, e.g. `OpenAI`
+    pub service: String,
+    pub status: KeyStatus,
+    /// Why the key was rejected or couldn't be checked
+    pub message: Option<String>,
+    /// Round trip of the probe request
+    pub latency_ms: u64,
+    /// Models, plan and rate limits; empty unless the key is valid
+    #[serde(flatten)]
+    pub details: KeyDetails,
+}
+
+/// Make a cheap authenticated request with the secret `name`: a model list for LLM
+/// providers, a one-result query for Brave. Rejections are reported in the result
+/// rather than as errors.
+pub async fn validate_secret(
+    secrets_manager: &SecretsManager,
+    settings: &Settings,
+    name: &str,
+) -> Result<KeyValidation, AppError> {
+    let registry = ProviderRegistry::from_settings(settings);
+    let provider = registry.for_secret(name);
+    let service = match provider {
+        Some(provider) => provider.name().to_string(),
+        None if name == BRAVE_API_KEY => "Brave Search".to_string(),
+        None => return Err(AppError::validation(format!("There is no check for the secret '{}'", name))),
+    };
+    if !provider.is_some_and(|provider| provider.is_local()) {
+        connectivity::ensure_online()?;
+    }
+    let api_key = secrets_manager.get_secret(name, "validate_secret").await?;
+
+    let started = Instant::now();
+    let checked = match provider {
+        Some(provider) => provider.check_key(Some(&api_key)).await,
+        None => search::check_brave_key(&api_key).await,
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let (status, message, details) = match checked {
+        Ok(details) => (KeyStatus::Valid, None, details),
+        Err(e) => (KeyStatus::from_error(&e), Some(redact::redact(&e.to_string())), KeyDetails::default()),
+    };
+    Ok(KeyValidation {
+        name: name.to_string(),
+        service,
+        status,
+        message,
+        latency_ms,
+        details,
+    })
+}
@@ -1,240 +1,2694 @@
-use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
-use tauri::{AppHandle, Emitter, State};
-use rusqlite::{Connection, Result as SqlResult};
+use tauri::{AppHandle, Emitter, Manager, State};
+use std::path::PathBuf;
+use tracing::{debug, error, warn};
 
+mod annotations;
+mod app_lock;
+mod archive;
+mod audio;
+mod ask;
+mod autotag;
+mod backup;
+mod bulk;
+mod clipboard;
+mod clipper_server;
+mod clips;
+mod code;
+mod collections;
+mod config;
+mod connectivity;
+mod conversations;
+mod db;
+mod digest;
+mod email;
+mod embeddings;
+mod epub;
+mod errors;
+mod events;
+mod export;
+mod extract;
+mod feeds;
+mod flashcards;
+mod hotkey;
+mod http;
+mod inflight;
+mod import;
+mod jobs;
+mod key_check;
+mod links;
+mod llm;
+mod logging;
+mod maintenance;
+mod markdown;
+mod media;
+mod merge;
+mod migrations;
+mod notifications;
+mod notion;
+mod ocr;
+mod pairing;
+mod pdf;
+mod private;
+mod profiles;
+mod prompts;
+mod providers;
+mod quarantine;
+mod redact;
+mod reminders;
+mod research;
+mod revisions;
+mod rules;
+mod sanitize;
+mod search;
+mod search_cache;
+mod secret_audit;
 mod secrets;
-use secrets::{SecretsManager, LlmMessage, call_llm_api};
+mod settings;
+mod share;
+mod site_metadata;
+mod smart_collections;
+mod sources;
+mod smtp;
+mod speech;
+mod stats;
+mod statuses;
+mod summarize;
+mod sync;
+mod tags;
+mod thumbnails;
+mod tools;
+mod trash;
+mod tray;
+mod usage;
+mod vault;
+mod video;
+mod vision;
+mod watcher;
+mod wayback;
+mod windows;
+use secret_audit::SecretAudit;
+use secrets::{SecretInfo, SecretsBackend, SecretsManager};
+use annotations::{Annotation, AnnotationSearchHit, AnnotationUpdate, NewAnnotation};
+use app_lock::{AppLock, AppLockStatus, APP_LOCK_SECRET};
+use archive::ClipArchive;
+use audio::{AudioTranscript, ClipAudio};
+use ask::AskAnswer;
+use autotag::ClipAutoTagged;
+use backup::BackupInfo;
+use bulk::{BulkClipChanges, BulkResult};
+use clipboard::{ClipboardAction, ClipboardMonitor, ClipboardMonitorSettings};
+use clipper_server::{ClipperEndpoint, ClipperServer};
+use clips::{
+    ClipData, ClipFilter, ClipInsert, ClipPage, ClipQuery, ClipSearchHit, ClipUpdate, DuplicateGroup, ReadingStats, SqliteClip,
+};
+use collections::Collection;
+use config::AppConfig;
+use connectivity::ConnectivityStatus;
+use conversations::{Conversation, ConversationDetail, ConversationSettings, Message};
+use db::{Database, DATABASE_KEY_SECRET};
+use email::{ClipAttachment, EmailCheck};
+use embeddings::SemanticHit;
+use epub::EpubExport;
+use errors::AppError;
+use events::{EventJournal, EventsSince};
+use export::{ExportFormat, ExportSummary};
+use extract::ExtractedArticle;
+use feeds::Feed;
+use flashcards::{Flashcard, FlashcardExportSummary, FlashcardTarget};
+use import::{ImportSource, ImportSummary};
+use inflight::RequestRegistry;
+use jobs::{Job, JobKind, JobQueue};
+use key_check::KeyValidation;
+use links::{BrokenLink, LinkCheck};
+use logging::{LogBuffer, LogEntry};
+use media::ClipImage;
+use merge::ClipMerge;
+use notifications::{NotificationKind, Notifier};
+use notion::NotionExportSummary;
+use ocr::ClipOcr;
+use pairing::{ClipperPairing, PairedClipper, PairingCode, PAIRING_KEY_SECRET};
+use pdf::ClipPdf;
+use private::PrivateVault;
+use profiles::{Profile, ProfileInfo, ProfileManager};
+use prompts::PromptTemplate;
+use quarantine::QuarantinedClip;
+use providers::ollama::{LocalModel, OllamaProvider};
+use providers::{
+    call_llm_api, CustomProviderConfig, LlmMessage, LlmRequest, ModelSelection, ProviderInfo, ProviderRegistry,
+    SafetySetting,
+};
+use reminders::Reminder;
+use revisions::ClipRevision;
+use rules::{NewRule, Rule, RuleTest};
+use search::{ClippedResults, MergedSearchResponse, SearchProvider, SearchResponse, SearchResult};
+use settings::{Settings, SettingsManager};
+use share::{RenderFormat, RenderedClip};
+use smart_collections::SmartCollection;
+use sources::{Source, SourceSettings};
+use speech::Speech;
+use stats::LibraryStats;
+use statuses::ClipStatus;
+use sync::{RemotePushReport, RemoteSyncStatus, SyncManager, SyncReport, SyncStatus, SYNC_PASSPHRASE_SECRET};
+use tags::{ClipTagsChanged, Tag};
+use thumbnails::ClipThumbnail;
+use tools::{Tool, ToolAnswer};
+use tray::Tray;
+use usage::{ModelPrice, UsagePeriod, UsageSummary};
+use vault::{VaultMirror, VaultMirrorSummary};
+use vision::ImageAttachment;
+use watcher::ClipWatcher;
+use wayback::WaybackRecovery;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct SearchResult {
-    title: String,
+// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+#[tauri::command]
+fn greet(name: &str) -> String {
+    format!("Hello, {}! You've been greeted from Rust!", name)
+}
+
+// Web search via the Brave Search API, using the stored `brave_api_key`
+#[tauri::command]
+async fn search_brave(
+    db: State<'_, Database>,
+    secrets_manager: State<'_, SecretsManager>,
+    settings: State<'_, SettingsManager>,
+    requests: State<'_, RequestRegistry>,
+    query: String,
+    num_results: u32,
+    request_id: Option<String>,
+) -> Result<SearchResponse, AppError> {
+    let settings = settings.get();
+    let api_key = search::search_api_key(&secrets_manager, "brave_api_key").await?;
+    let query_hash = search_cache::query_hash(&query, num_results, "");
+    let fetch = search::search_brave(&api_key, &query, num_results);
+    let search = search::cached(&db, &settings, SearchProvider::Brave, &query_hash, fetch);
+    requests.run(request_id, "search_brave", settings.command_timeout("search_brave"), search).await
+}
+
+// Web search via Google Programmable Search, using the stored `google_api_key`
+// and `google_search_engine_id`. Safe search defaults to on.
+#[tauri::command]
+async fn search_google(
+    db: State<'_, Database>,
+    secrets_manager: State<'_, SecretsManager>,
+    settings: State<'_, SettingsManager>,
+    requests: State<'_, RequestRegistry>,
+    query: String,
+    num_results: u32,
+    safe_search: Option<bool>,
+    request_id: Option<String>,
+) -> Result<SearchResponse, AppError> {
+    let settings = settings.get();
+    let api_key = search::search_api_key(&secrets_manager, "google_api_key").await?;
+    let engine_id = search::search_api_key(&secrets_manager, "google_search_engine_id").await?;
+    let safe_search = safe_search.unwrap_or(true);
+    // Safe search is part of the default key so `search_web` shares these entries
+    let query_hash = search_cache::query_hash(&query, num_results, if safe_search { "" } else { "unsafe" });
+    let fetch = search::search_google(&api_key, &engine_id, &query, num_results, safe_search);
+    let search = search::cached(&db, &settings, SearchProvider::Google, &query_hash, fetch);
+    requests.run(request_id, "search_google", settings.command_timeout("search_google"), search).await
+}
+
+// Web search with `provider`, or the default search provider from settings.
+// DuckDuckGo and SearxNG work without API keys.
+#[tauri::command]
+async fn web_search(
+    db: State<'_, Database>,
+    secrets_manager: State<'_, SecretsManager>,
+    settings: State<'_, SettingsManager>,
+    requests: State<'_, RequestRegistry>,
+    query: String,
+    num_results: u32,
+    provider: Option<SearchProvider>,
+    request_id: Option<String>,
+) -> Result<SearchResponse, AppError> {
+    let settings = settings.get();
+    let provider = provider.unwrap_or(settings.search_provider);
+    let search = search::search(&db, &secrets_manager, &settings, provider, &query, num_results);
+    requests.run(request_id, "web_search", settings.command_timeout("web_search"), search).await
+}
+
+// Search several providers at once (default: every configured one) and merge the
+// results into one ranking, each result listing the engines that returned it
+#[tauri::command]
+async fn search_web(
+    db: State<'_, Database>,
+    secrets_manager: State<'_, SecretsManager>,
+    settings: State<'_, SettingsManager>,
+    requests: State<'_, RequestRegistry>,
+    query: String,
+    providers: Option<Vec<SearchProvider>>,
+    num_results: u32,
+    request_id: Option<String>,
+) -> Result<MergedSearchResponse, AppError> {
+    let settings = settings.get();
+    let providers = match providers {
+        Some(providers) => providers,
+        None => search::configured_providers(&secrets_manager, &settings).await?,
+    };
+    let search = search::search_all(&db, &secrets_manager, &settings, &providers, &query, num_results);
+    requests.run(request_id, "search_web", settings.command_timeout("search_web"), search).await
+}
+
+// Drop every cached web search response; returns how many were removed
+#[tauri::command]
+async fn clear_search_cache(db: State<'_, Database>) -> Result<usize, AppError> {
+    search_cache::clear(&db.conn()?)
+}
+
+// Save a search result as an article clip, with the page's readable text as content
+#[tauri::command]
+async fn clip_search_result(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsManager>,
+    requests: State<'_, RequestRegistry>,
+    result: SearchResult,
+    request_id: Option<String>,
+) -> Result<ClipInsert, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let timeout = settings.get().command_timeout("clip_search_result");
+    let clip_data = requests.run(request_id, "clip_search_result", timeout, search::article_clip(&result)).await?;
+    ingest_clip(&app_handle, clip_data)
+}
+
+// Save a whole page of search results as article clips; pages that fail to load
+// are reported in `failures` instead of aborting the batch
+#[tauri::command]
+async fn clip_search_results(app_handle: AppHandle, results: Vec<SearchResult>) -> Result<ClippedResults, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    connectivity::ensure_online()?;
+    Ok(search::clip_results(&app_handle, &results).await)
+}
+
+// Fetch a web page and extract its readable article content
+#[tauri::command]
+async fn fetch_url_content(
+    settings: State<'_, SettingsManager>,
+    requests: State<'_, RequestRegistry>,
     url: String,
-    description: String,
-    snippet: Option<String>,
+    request_id: Option<String>,
+) -> Result<ExtractedArticle, AppError> {
+    let timeout = settings.get().command_timeout("fetch_url_content");
+    requests.run(request_id, "fetch_url_content", timeout, extract::fetch_article(&url)).await
+}
+
+// Stop a search, page fetch or LLM call started with `request_id`; it fails with a
+// `cancelled` error. Returns false if the request already finished.
+#[tauri::command]
+fn cancel_request(requests: State<'_, RequestRegistry>, request_id: String) -> bool {
+    requests.cancel(&request_id)
+}
+
+// Command to read all clips from SQLite database
+#[tauri::command]
+async fn get_all_clips(lock: State<'_, AppLock>, db: State<'_, Database>) -> Result<Vec<SqliteClip>, AppError> {
+    lock.check()?;
+    clips::get_all_clips(&db.conn()?)
+}
+
+// Paginated, filtered clip listing for the library view
+#[tauri::command]
+async fn query_clips(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    query: ClipQuery,
+) -> Result<ClipPage, AppError> {
+    lock.check()?;
+    clips::query_clips(&db.conn()?, &query)
+}
+
+// The full clip, including the fields `query_clips` can leave out, for the reader.
+// A clip that was merged away resolves to the clip it was merged into. A private
+// clip's content is only included while private clips are unlocked.
+#[tauri::command]
+async fn get_clip(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    vault: State<'_, PrivateVault>,
+    id: i64,
+) -> Result<SqliteClip, AppError> {
+    lock.check()?;
+    let conn = db.conn()?;
+    let id = merge::resolve_redirect(&conn, id)?;
+    let mut clip = clips::get_clip(&conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))?;
+    private::reveal(&vault, &conn, &mut clip)?;
+    Ok(clip)
+}
+
+// Unlock private clips for this session; the first unlock sets the passphrase
+#[tauri::command]
+async fn unlock_private_clips(app_handle: AppHandle, passphrase: String) -> Result<(), AppError> {
+    private::unlock(&app_handle, passphrase).await
+}
+
+#[tauri::command]
+async fn lock_private_clips(vault: State<'_, PrivateVault>) -> Result<(), AppError> {
+    vault.lock();
+    Ok(())
+}
+
+// Encrypt a clip's content, description and summary and keep it out of search,
+// embeddings and locked exports, or make it a normal clip again
+#[tauri::command]
+async fn set_clip_private(app_handle: AppHandle, id: i64, private: bool) -> Result<SqliteClip, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let clip = private::set_clip_private(&app_handle, id, private).await?;
+    events::emit(&app_handle, "clip-updated", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(clip)
+}
+
+// Full-text search for the library search box
+#[tauri::command]
+async fn search_clips(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<ClipSearchHit>, AppError> {
+    lock.check()?;
+    clips::search_clips(&db.conn()?, &query, limit.unwrap_or(50))
+}
+
+// Search code snippets, optionally in one language (`js`, `c++` and the like are
+// understood); an empty query lists the newest snippets
+#[tauri::command]
+async fn search_code_clips(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    query: String,
+    language: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<ClipSearchHit>, AppError> {
+    lock.check()?;
+    let language = match language.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+        Some(name) => Some(
+            code::normalize_language(name)
+                .ok_or_else(|| AppError::validation(format!("Unknown language '{}'", name)))?,
+        ),
+        None => None,
+    };
+    clips::search_code_clips(&db.conn()?, &query, language.as_deref(), limit.unwrap_or(50))
+}
+
+// Shared entry point for every new clip: the create_clip command, the browser
+// clipper (HTTP/WebSocket) and the drop folder fallback all end up here
+fn ingest_clip(app_handle: &AppHandle, clip_data: ClipData) -> Result<ClipInsert, AppError> {
+    debug!("Received clip: {:?}", clip_data);
+    let mut clip_data = sanitize::clip(clip_data)?;
+    // Clippers and feeds send article HTML; clips keep Markdown. Notes, code and other
+    // text stay exactly as sent.
+    if !sanitize::is_text_type(&clip_data.r#type) {
+        if let Some(markdown) = clip_data.content.as_deref().and_then(markdown::to_markdown) {
+            clip_data.content = Some(markdown);
+        }
+    }
+    let clip_data = code::classify(video::classify(clips::canonicalize(clip_data)));
+    let settings = app_handle.state::<SettingsManager>().get();
+    let duplicate_policy = settings.duplicate_policy;
+    // The clipper, the drop folder and the UI can all be saving at once
+    let (inserted, actions) = app_handle.state::<Database>().write(move |conn| {
+        let actions = rules::evaluate_stored(conn, &clip_data)?;
+        if let Some(domain) = &actions.muted_source {
+            return Err(AppError::validation(format!("Clip skipped: {} is muted", domain)));
+        }
+        if actions.skip {
+            return Err(AppError::validation(format!("Clip skipped by rule \"{}\"", actions.matched.join("\", \""))));
+        }
+        let mut inserted = clips::insert_or_merge(conn, &clip_data, duplicate_policy)?;
+        if inserted.merged {
+            return Ok((inserted, actions));
+        }
+
+        // Rule actions apply to new clips only; a failing action shouldn't lose the clip
+        for tag in &actions.tags {
+            if let Err(e) = tags::add_tag_to_clip(conn, inserted.clip.id, tag) {
+                error!("Failed to tag clip {} by rule: {}", inserted.clip.id, e);
+            }
+        }
+        if let Some(collection_id) = actions.collection_id {
+            match collections::move_clips(conn, &[inserted.clip.id], Some(collection_id)) {
+                Ok(_) => inserted.clip.collection_id = Some(collection_id),
+                Err(e) => error!("Failed to file clip {} by rule: {}", inserted.clip.id, e),
+            }
+        }
+        Ok((inserted, actions))
+    })?;
+
+    // A merged repeat is an existing clip that changed; it was enriched when first clipped
+    if inserted.merged {
+        events::emit(app_handle, "clip-updated", &inserted.clip)
+            .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+        return Ok(inserted);
+    }
+    let clip = &inserted.clip;
+
+    // Emit event to frontend
+    events::emit(app_handle, "new-clip", clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+
+    // Enrichment runs in the background job queue so ingestion never waits on an LLM
+    let mut enrichment = Vec::new();
+    if clip.url.as_deref().is_some_and(clips::is_short_link) {
+        enrichment.push(JobKind::ResolveUrl { clip_id: clip.id });
+    }
+    if matches!(clip.r#type.as_str(), "article" | "url") && clip.domain.is_some() {
+        enrichment.push(JobKind::FetchSiteMetadata { clip_id: clip.id });
+    }
+    if clip.r#type == "image" && clip.image_url.is_some() {
+        if settings.auto_ocr && ocr::is_available() {
+            // The OCR job downloads the image itself
+            if let Err(e) = ocr::queue_ocr(app_handle, clip.id) {
+                error!("Failed to queue OCR for clip {}: {}", clip.id, e);
+            }
+        } else {
+            enrichment.push(JobKind::DownloadImage { clip_id: clip.id });
+        }
+    }
+    if clip.r#type == "video" {
+        enrichment.push(JobKind::FetchTranscript { clip_id: clip.id });
+        if clip.image_url.is_some() {
+            enrichment.push(JobKind::DownloadImage { clip_id: clip.id });
+        }
+    }
+    if clip.r#type == "code" && clip.language.is_none() {
+        enrichment.push(JobKind::DetectCodeLanguage { clip_id: clip.id });
+    }
+    if settings.auto_tag {
+        enrichment.push(JobKind::AutoTagClip { clip_id: clip.id });
+    }
+    if settings.embedding_model.is_some() {
+        enrichment.push(JobKind::EmbedClips { clip_ids: vec![clip.id] });
+    }
+    if actions.summarize {
+        enrichment.push(JobKind::SummarizeClip { clip_id: clip.id });
+    }
+    if actions.archive && clip.url.is_some() {
+        enrichment.push(JobKind::ArchivePage { clip_id: clip.id });
+    }
+    if settings.save_to_wayback && clip.url.as_deref().is_some_and(|url| url.starts_with("http")) {
+        enrichment.push(JobKind::SaveToWayback { clip_id: clip.id });
+    }
+    let queue = app_handle.state::<JobQueue>();
+    for kind in enrichment {
+        if let Err(e) = queue.submit(app_handle, kind) {
+            error!("Failed to queue enrichment for clip {}: {}", clip.id, e);
+        }
+    }
+    Ok(inserted)
+}
+
+// Validate and store a clip. If it repeats an existing clip (same normalized URL or
+// content) it is merged or kept per the `duplicate_policy` setting, and
+// `duplicate_of` names the original.
+#[tauri::command]
+async fn create_clip(app_handle: AppHandle, clip_data: ClipData) -> Result<ClipInsert, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    ingest_clip(&app_handle, clip_data)
+}
+
+// Write the clips matching `filter` to `dest_path`: a directory of Markdown files,
+// a JSON array or a CSV file. Reports `export-progress` events while it runs.
+#[tauri::command]
+async fn export_clips(
+    app_handle: AppHandle,
+    format: ExportFormat,
+    filter: Option<ClipFilter>,
+    dest_path: PathBuf,
+) -> Result<ExportSummary, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    export::export_clips(&app_handle, format, filter.unwrap_or_default(), dest_path).await
+}
+
+// Bundle clips, or a whole collection, into one EPUB at `dest_path`; with
+// `send_to_kindle` it's also emailed to the Send to Kindle address
+#[tauri::command]
+async fn export_epub(
+    app_handle: AppHandle,
+    clip_ids: Option<Vec<i64>>,
+    collection_id: Option<i64>,
+    dest_path: PathBuf,
+    title: Option<String>,
+    send_to_kindle: Option<bool>,
+) -> Result<EpubExport, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    epub::export_epub(&app_handle, clip_ids, collection_id, dest_path, title, send_to_kindle.unwrap_or(false)).await
+}
+
+// Import saved links from a Pocket, Instapaper or browser bookmarks export,
+// skipping URLs already in the library. Emits `clips-imported` when done.
+#[tauri::command]
+async fn import_clips(app_handle: AppHandle, source: ImportSource, path: PathBuf) -> Result<ImportSummary, AppError> {
+    import::import_clips(&app_handle, source, path).await
+}
+
+// Groups of existing clips that share a normalized URL or content hash
+#[tauri::command]
+async fn find_duplicate_clips(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+) -> Result<Vec<DuplicateGroup>, AppError> {
+    lock.check()?;
+    clips::find_duplicate_clips(&db.conn()?)
+}
+
+// Connection details (port and URLs) for the browser clipper
+#[tauri::command]
+async fn get_clipper_endpoint(server: State<'_, ClipperServer>) -> Result<ClipperEndpoint, AppError> {
+    Ok(server.endpoint())
+}
+
+// Browser clippers allowed to submit clips
+#[tauri::command]
+async fn list_paired_clippers(db: State<'_, Database>) -> Result<Vec<PairedClipper>, AppError> {
+    pairing::list_paired_clippers(&db.conn()?)
+}
+
+// Clip payloads from paired clippers that failed validation, newest first
+#[tauri::command]
+async fn list_quarantine(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    limit: Option<usize>,
+) -> Result<Vec<QuarantinedClip>, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    quarantine::list(&db.conn()?, limit.unwrap_or(100))
+}
+
+// Show the code of a clipper's pairing request so the user can enter it in the clipper
+#[tauri::command]
+async fn approve_clipper_pairing(
+    lock: State<'_, AppLock>,
+    pairing: State<'_, ClipperPairing>,
+    pairing_id: String,
+) -> Result<PairingCode, AppError> {
+    lock.check()?;
+    pairing::approve_pairing(&pairing, &pairing_id)
+}
+
+#[tauri::command]
+async fn reject_clipper_pairing(pairing: State<'_, ClipperPairing>, pairing_id: String) -> Result<(), AppError> {
+    pairing::reject_pairing(&pairing, &pairing_id)
+}
+
+// Stop accepting clips from a paired clipper
+#[tauri::command]
+async fn revoke_clipper(app_handle: AppHandle, db: State<'_, Database>, id: String) -> Result<(), AppError> {
+    let revoked = id.clone();
+    db.write_async(move |conn| pairing::revoke_clipper(conn, &revoked)).await?;
+    events::emit(&app_handle, "clippers-changed", &id)
+        .map_err(|e| AppError::internal(format!("Failed to emit clipper event: {}", e)))
+}
+
+// Update selected fields of a clip. Pass the clip's last seen `updated_at` as
+// `expected_updated_at` to avoid overwriting a concurrent edit.
+#[tauri::command]
+async fn update_clip(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+    fields: ClipUpdate,
+) -> Result<SqliteClip, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let clip = db.write_async(move |conn| clips::update_clip(conn, id, fields)).await?;
+    events::emit(&app_handle, "clip-updated", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(clip)
+}
+
+// Rewrite a clip's HTML content as Markdown, keeping the HTML as a revision
+#[tauri::command]
+async fn convert_clip_to_markdown(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<SqliteClip, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let clip = db.write_async(move |conn| markdown::convert_clip(conn, id)).await?;
+    events::emit(&app_handle, "clip-updated", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(clip)
+}
+
+// Earlier titles and contents of a clip, newest first
+#[tauri::command]
+async fn list_revisions(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    clip_id: i64,
+) -> Result<Vec<ClipRevision>, AppError> {
+    lock.check()?;
+    revisions::list_revisions(&db.conn()?, clip_id)
+}
+
+// Bring back an earlier title and content; the version it replaces is kept as a revision
+#[tauri::command]
+async fn restore_revision(app_handle: AppHandle, db: State<'_, Database>, revision_id: i64) -> Result<SqliteClip, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let clip = db.write_async(move |conn| revisions::restore_revision(conn, revision_id)).await?;
+    events::emit(&app_handle, "clip-updated", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(clip)
+}
+
+#[tauri::command]
+async fn delete_clip(app_handle: AppHandle, db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.write_async(move |conn| clips::delete_clip(conn, id)).await?;
+    events::emit(&app_handle, "clip-deleted", &serde_json::json!({ "id": id }))
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))
+}
+
+// Apply the same tag, collection and read-state changes to many clips in one transaction.
+// Emits a single `clips-updated` event listing the clips instead of one event per clip.
+#[tauri::command]
+async fn bulk_update_clips(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    ids: Vec<i64>,
+    changes: BulkClipChanges,
+) -> Result<BulkResult, AppError> {
+    let result = db.write_async(move |conn| bulk::bulk_update_clips(conn, &ids, changes)).await?;
+    events::emit(&app_handle, "clips-updated", &result)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(result)
+}
+
+// Move many clips to the trash in one transaction, with a single `clips-deleted` event
+#[tauri::command]
+async fn bulk_delete_clips(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    ids: Vec<i64>,
+) -> Result<BulkResult, AppError> {
+    let result = db.write_async(move |conn| bulk::bulk_delete_clips(conn, &ids)).await?;
+    events::emit(&app_handle, "clips-deleted", &result)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(result)
+}
+
+// Fold near-duplicates into `primary_id`: their tags, annotations and attachments move
+// over, their text is kept as revisions, and they go to the trash redirecting to it
+#[tauri::command]
+async fn merge_clips(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    primary_id: i64,
+    duplicate_ids: Vec<i64>,
+) -> Result<ClipMerge, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let merged = db.write_async(move |conn| merge::merge_clips(conn, primary_id, &duplicate_ids)).await?;
+    events::emit(&app_handle, "clips-merged", &merged)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(merged)
+}
+
+// Deleted clips, most recently deleted first
+#[tauri::command]
+async fn list_trash(lock: State<'_, AppLock>, db: State<'_, Database>) -> Result<Vec<SqliteClip>, AppError> {
+    lock.check()?;
+    clips::list_trash(&db.conn()?)
+}
+
+#[tauri::command]
+async fn restore_clip(app_handle: AppHandle, db: State<'_, Database>, id: i64) -> Result<SqliteClip, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let clip = db.write_async(move |conn| clips::restore_clip(conn, id)).await?;
+    events::emit(&app_handle, "clip-restored", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(clip)
+}
+
+// Permanently delete everything in the trash; returns how many clips were purged
+#[tauri::command]
+async fn empty_trash(app_handle: AppHandle) -> Result<usize, AppError> {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || trash::empty_trash(&handle, None))
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to empty trash: {}", e)))?
+}
+
+// Tell the sidebar tag cloud (and any open clip) that a clip's tags changed
+fn emit_tags_changed(app_handle: &AppHandle, db: &Database, clip_id: i64) -> Result<(), AppError> {
+    let conn = db.conn()?;
+    let tags = tags::tags_for_clip(&conn, clip_id)?;
+    let suggested = tags::suggested_tags_for_clip(&conn, clip_id)?;
+    events::emit(app_handle, "tags-changed", &ClipTagsChanged { clip_id, tags, suggested })
+        .map_err(|e| AppError::internal(format!("Failed to emit tags event: {}", e)))
+}
+
+#[tauri::command]
+async fn add_tag_to_clip(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    clip_id: i64,
+    tag: String,
+) -> Result<(), AppError> {
+    db.write_async(move |conn| tags::add_tag_to_clip(conn, clip_id, &tag)).await?;
+    emit_tags_changed(&app_handle, &db, clip_id)
+}
+
+#[tauri::command]
+async fn remove_tag_from_clip(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    clip_id: i64,
+    tag: String,
+) -> Result<(), AppError> {
+    db.write_async(move |conn| tags::remove_tag_from_clip(conn, clip_id, &tag)).await?;
+    emit_tags_changed(&app_handle, &db, clip_id)
+}
+
+// Turn an auto-suggested tag into a regular tag. Rejecting one is `remove_tag_from_clip`.
+#[tauri::command]
+async fn accept_auto_tag(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    clip_id: i64,
+    tag: String,
+) -> Result<(), AppError> {
+    db.write_async(move |conn| tags::accept_auto_tag(conn, clip_id, &tag)).await?;
+    emit_tags_changed(&app_handle, &db, clip_id)
+}
+
+// Keep (accept = true) or discard the auto-suggested category of a clip
+#[tauri::command]
+async fn review_auto_category(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    clip_id: i64,
+    accept: bool,
+) -> Result<SqliteClip, AppError> {
+    lock.check()?;
+    db.write_async(move |conn| {
+        let clip =
+            clips::get_clip(conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+        if clip.category_source.as_deref() != Some("auto") {
+            return Err(AppError::validation(format!("Clip {} has no suggested category", clip_id)));
+        }
+        let category = if accept { clip.category.as_deref() } else { None };
+        clips::set_category(conn, clip_id, category, "user")?;
+        clips::get_clip(conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))
+    })
+    .await
+}
+
+// A clip's highlights and notes in reading order
+#[tauri::command]
+async fn list_annotations(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    clip_id: i64,
+) -> Result<Vec<Annotation>, AppError> {
+    lock.check()?;
+    annotations::list_annotations(&db.conn()?, clip_id)
+}
+
+// Highlight a passage (`start`/`end` offsets into the plain text plus its `quote`),
+// attach a note, or both
+#[tauri::command]
+async fn create_annotation(db: State<'_, Database>, clip_id: i64, annotation: NewAnnotation) -> Result<Annotation, AppError> {
+    db.write_async(move |conn| annotations::create_annotation(conn, clip_id, annotation)).await
+}
+
+#[tauri::command]
+async fn update_annotation(db: State<'_, Database>, id: i64, changes: AnnotationUpdate) -> Result<Annotation, AppError> {
+    db.write_async(move |conn| annotations::update_annotation(conn, id, changes)).await
+}
+
+#[tauri::command]
+async fn delete_annotation(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.write_async(move |conn| annotations::delete_annotation(conn, id)).await
+}
+
+// Full-text search over highlighted passages and notes
+#[tauri::command]
+async fn search_annotations(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<AnnotationSearchHit>, AppError> {
+    lock.check()?;
+    annotations::search_annotations(&db.conn()?, &query, limit.unwrap_or(50))
+}
+
+// Move a clip between the read-later states: unread, reading, archived
+#[tauri::command]
+async fn set_read_state(app_handle: AppHandle, db: State<'_, Database>, id: i64, state: String) -> Result<SqliteClip, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let clip = db.write_async(move |conn| clips::set_read_state(conn, id, &state)).await?;
+    events::emit(&app_handle, "clip-updated", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(clip)
+}
+
+// Save how far into a clip the reader got, from 0.0 to 1.0
+#[tauri::command]
+async fn set_progress(app_handle: AppHandle, db: State<'_, Database>, id: i64, progress: f64) -> Result<SqliteClip, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let clip = db.write_async(move |conn| clips::set_reading_progress(conn, id, progress)).await?;
+    events::emit(&app_handle, "clip-updated", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(clip)
+}
+
+// Read-later backlog size and clips finished per week over the last `weeks` (default 12)
+#[tauri::command]
+async fn get_reading_stats(db: State<'_, Database>, weeks: Option<u32>) -> Result<ReadingStats, AppError> {
+    clips::reading_stats(&db.conn()?, weeks.unwrap_or(12).min(520))
+}
+
+// Counts by type, tag and domain, clips saved per week over the last `weeks` (default 12),
+// words saved, reading backlog and disk usage, for the dashboard
+#[tauri::command]
+async fn get_library_stats(
+    config: State<'_, AppConfig>,
+    db: State<'_, Database>,
+    weeks: Option<u32>,
+) -> Result<LibraryStats, AppError> {
+    stats::library_stats(&db.conn()?, &config, weeks.unwrap_or(12).min(520))
+}
+
+// Run the auto-tagging pass on one clip now, regardless of the auto_tag setting
+#[tauri::command]
+async fn auto_tag_clip(app_handle: AppHandle, id: i64) -> Result<ClipAutoTagged, AppError> {
+    Ok(autotag::auto_tag_clip(&app_handle, id).await?)
+}
+
+// Turn automatic tag suggestions for newly ingested clips on or off
+#[tauri::command]
+async fn set_auto_tagging(settings: State<'_, SettingsManager>, enabled: bool) -> Result<(), AppError> {
+    settings.update(|s| s.auto_tag = enabled)?;
+    Ok(())
+}
+
+// Back up clips.db right away, outside the schedule
+#[tauri::command]
+async fn backup_now(app_handle: AppHandle) -> Result<BackupInfo, AppError> {
+    backup::backup_now(&app_handle).await
+}
+
+// Exchange clip changes with the sync backend right away, outside the schedule
+#[tauri::command]
+async fn sync_now(app_handle: AppHandle) -> Result<SyncReport, AppError> {
+    sync::sync_now(&app_handle).await
+}
+
+// Set the passphrase the sync changelog is encrypted with; every device needs the same one
+#[tauri::command]
+async fn set_sync_passphrase(
+    lock: State<'_, AppLock>,
+    secrets_manager: State<'_, SecretsManager>,
+    passphrase: String,
+) -> Result<(), AppError> {
+    lock.check()?;
+    if passphrase.is_empty() {
+        return Err(AppError::validation("Passphrase must not be empty"));
+    }
+    secrets_manager.store_secret(SYNC_PASSPHRASE_SECRET.to_string(), passphrase).await
+}
+
+#[tauri::command]
+async fn get_sync_status(app_handle: AppHandle) -> Result<SyncStatus, AppError> {
+    sync::get_sync_status(&app_handle)
+}
+
+// Push clips changed since the last push to the libsql database right away
+#[tauri::command]
+async fn push_to_remote_now(app_handle: AppHandle) -> Result<RemotePushReport, AppError> {
+    connectivity::ensure_online()?;
+    sync::push_to_remote(&app_handle).await
+}
+
+#[tauri::command]
+async fn get_remote_sync_status(app_handle: AppHandle) -> Result<RemoteSyncStatus, AppError> {
+    sync::get_remote_sync_status(&app_handle)
+}
+
+// Create or update a Notion database page for each clip; `database_id` may be a link
+#[tauri::command]
+async fn export_to_notion(app_handle: AppHandle, clip_ids: Vec<i64>, database_id: String) -> Result<NotionExportSummary, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    connectivity::ensure_online()?;
+    notion::export_to_notion(&app_handle, clip_ids, &database_id).await
+}
+
+// Check every clip's note in the Obsidian vault folder right away
+#[tauri::command]
+async fn mirror_obsidian_vault(app_handle: AppHandle) -> Result<VaultMirrorSummary, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    vault::mirror_now(&app_handle).await
+}
+
+// Backups in the app's backups dir, newest first
+#[tauri::command]
+async fn list_backups(app_handle: AppHandle) -> Result<Vec<BackupInfo>, AppError> {
+    backup::list_backups(&app_handle)
+}
+
+// Replace the database with a backup file. The current database is backed up first;
+// the frontend should reload its data on `database-restored`.
+#[tauri::command]
+async fn restore_backup(app_handle: AppHandle, path: PathBuf) -> Result<BackupInfo, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    backup::restore_backup(&app_handle, path).await
+}
+
+// Set how often scheduled backups run (None turns them off) and how many are kept
+#[tauri::command]
+async fn set_backup_schedule(
+    settings: State<'_, SettingsManager>,
+    interval_hours: Option<u32>,
+    keep: Option<usize>,
+) -> Result<(), AppError> {
+    settings.update(|s| {
+        s.backup_interval_hours = interval_hours;
+        s.backups_to_keep = keep;
+    })?;
+    Ok(())
+}
+
+// Encrypt clips.db at rest (SQLCipher) or turn encryption back off, converting the
+// file in place. Without a passphrase a random key is generated; either way the key
+// lives in the secrets store.
+#[tauri::command]
+async fn set_database_encryption(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    secrets: State<'_, SecretsManager>,
+    settings: State<'_, SettingsManager>,
+    profiles: State<'_, ProfileManager>,
+    enabled: bool,
+    passphrase: Option<String>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    if enabled == db.is_encrypted() {
+        return Ok(());
+    }
+    let key_secret = profiles.database_key_secret();
+    if enabled {
+        let key = passphrase
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| rand::random::<[u8; 32]>().iter().map(|b| format!("{:02x}", b)).collect());
+        // Save the key first so an interruption can never leave an encrypted file without it
+        secrets.store_secret(key_secret, key.clone()).await?;
+        db.convert(Some(key))?;
+        settings.update(|s| s.encrypt_database = true)?;
+    } else {
+        db.convert(None)?;
+        settings.update(|s| s.encrypt_database = false)?;
+        secrets.remove_secret(&key_secret).await?;
+    }
+    Ok(())
+}
+
+// Re-key the encrypted database with a new passphrase
+#[tauri::command]
+async fn change_database_passphrase(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    secrets: State<'_, SecretsManager>,
+    profiles: State<'_, ProfileManager>,
+    passphrase: String,
+) -> Result<(), AppError> {
+    lock.check()?;
+    if passphrase.is_empty() {
+        return Err(AppError::validation("Passphrase must not be empty"));
+    }
+    let key_secret = profiles.database_key_secret();
+    let old_key = secrets.get_secret(&key_secret, "change_database_passphrase").await?;
+    secrets.store_secret(key_secret.clone(), passphrase.clone()).await?;
+    if let Err(e) = db.rekey(passphrase) {
+        secrets.store_secret(key_secret, old_key).await?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+// Profiles (e.g. work and personal), each with its own database, media and settings
+#[tauri::command]
+async fn list_profiles(profiles: State<'_, ProfileManager>) -> Result<Vec<ProfileInfo>, AppError> {
+    Ok(profiles.list())
+}
+
+// Add an empty profile; with `shared_secrets` it uses the same API keys as the default profile
+#[tauri::command]
+async fn create_profile(
+    profiles: State<'_, ProfileManager>,
+    name: String,
+    shared_secrets: Option<bool>,
+) -> Result<Profile, AppError> {
+    profiles.create(&name, shared_secrets.unwrap_or(true))
+}
+
+// Make another profile active without restarting; emits profile-switched when done
+#[tauri::command]
+async fn switch_profile(app_handle: AppHandle, id: String) -> Result<Profile, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    profiles::switch_profile(&app_handle, &id).await
+}
+
+// Whether the app lock is on and engaged, for the lock screen
+#[tauri::command]
+async fn get_app_lock_status(app_handle: AppHandle) -> Result<AppLockStatus, AppError> {
+    Ok(app_lock::status(&app_handle))
+}
+
+// Lock the app after `idle_minutes` without activity, or turn the lock off with 0.
+// The passphrase is only needed the first time or to change it.
+#[tauri::command]
+async fn set_app_lock(app_handle: AppHandle, passphrase: Option<String>, idle_minutes: u32) -> Result<(), AppError> {
+    app_lock::configure(&app_handle, passphrase, idle_minutes).await
+}
+
+// Unlock with the passphrase, or without one through the OS (polkit) where available
+#[tauri::command]
+async fn unlock_app(app_handle: AppHandle, passphrase: Option<String>) -> Result<(), AppError> {
+    app_lock::unlock(&app_handle, passphrase).await
+}
+
+#[tauri::command]
+async fn lock_app(app_handle: AppHandle) -> Result<(), AppError> {
+    app_lock::lock_now(&app_handle)
+}
+
+// Keystrokes and clicks in the frontend, so reading one clip for a while doesn't lock the app
+#[tauri::command]
+async fn report_activity(lock: State<'_, AppLock>) -> Result<(), AppError> {
+    lock.touch();
+    Ok(())
+}
+
+// Global shortcut that saves the clipboard as a note clip
+#[tauri::command]
+async fn get_capture_shortcut(settings: State<'_, SettingsManager>) -> Result<String, AppError> {
+    Ok(settings.get().capture_shortcut().to_string())
+}
+
+// Rebind the capture shortcut, e.g. `CommandOrControl+Shift+L`
+#[tauri::command]
+async fn set_capture_shortcut(app_handle: AppHandle, shortcut: String) -> Result<(), AppError> {
+    hotkey::set_capture_shortcut(&app_handle, &shortcut)
+}
+
+// Turn the clipboard monitor on or off
+#[tauri::command]
+async fn set_clipboard_monitor(
+    app_handle: AppHandle,
+    monitor: State<'_, ClipboardMonitor>,
+    tray: State<'_, Tray>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    monitor.set_enabled(&app_handle, enabled)?;
+    tray.refresh(&app_handle);
+    Ok(())
+}
+
+// Pause or resume the passive capture paths (drop folder and clipboard monitor).
+// Shared by the tray menu; clips sent explicitly from the browser still arrive.
+fn pause_clipping(app_handle: &AppHandle, paused: bool) -> Result<(), AppError> {
+    let watcher = app_handle.state::<ClipWatcher>();
+    if paused {
+        watcher.shutdown();
+    } else {
+        watcher.resume()?;
+    }
+    app_handle.state::<ClipboardMonitor>().set_paused(paused);
+    app_handle.state::<Tray>().refresh(app_handle);
+    app_handle
+        .emit("clipping-paused", paused)
+        .map_err(|e| AppError::internal(format!("Failed to emit pause event: {}", e)))
+}
+
+#[tauri::command]
+async fn set_clipping_paused(app_handle: AppHandle, paused: bool) -> Result<(), AppError> {
+    pause_clipping(&app_handle, paused)
+}
+
+#[tauri::command]
+async fn is_clipping_paused(app_handle: AppHandle) -> Result<bool, AppError> {
+    Ok(tray::is_paused(&app_handle))
+}
+
+#[tauri::command]
+async fn get_clipboard_monitor(settings: State<'_, SettingsManager>) -> Result<ClipboardMonitorSettings, AppError> {
+    Ok(settings.get().clipboard_monitor)
+}
+
+// Configure what the clipboard monitor picks up and whether it saves clips directly
+// or asks first via `clipboard-clip-suggested`
+#[tauri::command]
+async fn set_clipboard_monitor_rules(
+    settings: State<'_, SettingsManager>,
+    action: ClipboardAction,
+    allowed_domains: Vec<String>,
+    min_text_length: usize,
+) -> Result<(), AppError> {
+    settings.update(|s| {
+        s.clipboard_monitor.action = action;
+        s.clipboard_monitor.allowed_domains = allowed_domains;
+        s.clipboard_monitor.min_text_length = min_text_length;
+    })?;
+    Ok(())
+}
+
+// Data-changing events emitted after `seq`, so a reloaded webview can catch up on what
+// it missed; when `complete` is false it has to refetch instead
+#[tauri::command]
+async fn get_events_since(
+    lock: State<'_, AppLock>,
+    journal: State<'_, EventJournal>,
+    seq: u64,
+) -> Result<EventsSince, AppError> {
+    lock.check()?;
+    Ok(journal.events_since(seq))
+}
+
+// Latest log entries at `level` (default `info`) or more severe, newest first,
+// for the diagnostics panel
+#[tauri::command]
+async fn get_recent_logs(
+    lock: State<'_, AppLock>,
+    logs: State<'_, LogBuffer>,
+    level: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>, AppError> {
+    lock.check()?;
+    let level = match level {
+        Some(level) => level.parse().map_err(|_| AppError::validation(format!("Unknown log level '{}'", level)))?,
+        None => tracing::Level::INFO,
+    };
+    Ok(logs.recent(level, limit.unwrap_or(200)))
+}
+
+#[tauri::command]
+async fn get_settings(settings: State<'_, SettingsManager>) -> Result<Settings, AppError> {
+    Ok(settings.get())
+}
+
+// Replace the settings after validating them; emits `settings-changed`. The secrets
+// backend and database encryption are kept as they are because changing them
+// means migrating data (`migrate_secrets`, `set_database_encryption`). A new
+// `data_dir` applies on the next launch.
+#[tauri::command]
+async fn update_settings(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsManager>,
+    monitor: State<'_, ClipboardMonitor>,
+    tray: State<'_, Tray>,
+    new_settings: Settings,
+) -> Result<Settings, AppError> {
+    new_settings.validate()?;
+    let current = settings.get();
+
+    if new_settings.capture_shortcut() != current.capture_shortcut() {
+        hotkey::set_capture_shortcut(&app_handle, new_settings.capture_shortcut())?;
+    }
+    if new_settings.clipboard_monitor.enabled != current.clipboard_monitor.enabled {
+        monitor.set_enabled(&app_handle, new_settings.clipboard_monitor.enabled)?;
+        tray.refresh(&app_handle);
+    }
+
+    settings.update(|s| {
+        let (secrets_backend, encrypt_database) = (s.secrets_backend, s.encrypt_database);
+        let app_lock_idle_minutes = s.app_lock_idle_minutes;
+        *s = new_settings;
+        s.secrets_backend = secrets_backend;
+        s.encrypt_database = encrypt_database;
+        s.app_lock_idle_minutes = app_lock_idle_minutes;
+    })
+}
+
+// Turn desktop notifications (new clips, finished summaries and tags, quota errors) on or off
+#[tauri::command]
+async fn set_notifications_enabled(settings: State<'_, SettingsManager>, enabled: bool) -> Result<(), AppError> {
+    settings.update(|s| s.disable_notifications = !enabled)?;
+    Ok(())
+}
+
+// Whether network features are available, and why not: offline mode or no connection
+#[tauri::command]
+fn get_connectivity() -> ConnectivityStatus {
+    connectivity::status()
+}
+
+// Turn offline mode on or off; while on, network features fail fast and LLM calls go to
+// the offline model
+#[tauri::command]
+async fn set_offline_mode(settings: State<'_, SettingsManager>, enabled: bool) -> Result<(), AppError> {
+    settings.update(|s| s.offline_mode = enabled)?;
+    Ok(())
+}
+
+// All tags with clip counts, for the sidebar tag cloud
+#[tauri::command]
+async fn list_tags(db: State<'_, Database>) -> Result<Vec<Tag>, AppError> {
+    tags::list_tags(&db.conn()?)
+}
+
+fn emit_collections_changed(app_handle: &AppHandle) -> Result<(), AppError> {
+    events::emit(app_handle, "collections-changed", &())
+        .map_err(|e| AppError::internal(format!("Failed to emit collections event: {}", e)))
+}
+
+#[tauri::command]
+async fn create_collection(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    name: String,
+    parent_id: Option<i64>,
+) -> Result<Collection, AppError> {
+    let collection = db.write_async(move |conn| collections::create_collection(conn, &name, parent_id)).await?;
+    emit_collections_changed(&app_handle)?;
+    Ok(collection)
+}
+
+#[tauri::command]
+async fn rename_collection(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+    name: String,
+) -> Result<Collection, AppError> {
+    let collection = db.write_async(move |conn| collections::rename_collection(conn, id, &name)).await?;
+    emit_collections_changed(&app_handle)?;
+    Ok(collection)
+}
+
+// Deletes the collection and its subcollections; their clips become unfiled
+#[tauri::command]
+async fn delete_collection(app_handle: AppHandle, db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.write_async(move |conn| collections::delete_collection(conn, id)).await?;
+    emit_collections_changed(&app_handle)
+}
+
+// Move clips into a collection (or out of all collections when collection_id is null)
+#[tauri::command]
+async fn move_clips_to_collection(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    clip_ids: Vec<i64>,
+    collection_id: Option<i64>,
+) -> Result<usize, AppError> {
+    let moved = db.write_async(move |conn| collections::move_clips(conn, &clip_ids, collection_id)).await?;
+    emit_collections_changed(&app_handle)?;
+    Ok(moved)
+}
+
+#[tauri::command]
+async fn list_collections(db: State<'_, Database>) -> Result<Vec<Collection>, AppError> {
+    collections::list_collections(&db.conn()?)
+}
+
+// Save a search as a live folder: type, tags, domain, search terms and dates, as in
+// `query_clips`. List its clips with `query_clips` and `smart_collection_id`.
+#[tauri::command]
+async fn create_smart_collection(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    name: String,
+    filter: ClipFilter,
+) -> Result<SmartCollection, AppError> {
+    let collection = db.write_async(move |conn| smart_collections::create_smart_collection(conn, &name, filter)).await?;
+    emit_collections_changed(&app_handle)?;
+    Ok(collection)
+}
+
+// Smart collections with how many clips match each right now
+#[tauri::command]
+async fn list_smart_collections(db: State<'_, Database>) -> Result<Vec<SmartCollection>, AppError> {
+    smart_collections::list_smart_collections(&db.conn()?)
+}
+
+#[tauri::command]
+async fn delete_smart_collection(app_handle: AppHandle, db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.write_async(move |conn| smart_collections::delete_smart_collection(conn, id)).await?;
+    emit_collections_changed(&app_handle)
+}
+
+fn emit_statuses_changed(app_handle: &AppHandle) -> Result<(), AppError> {
+    events::emit(app_handle, "statuses-changed", &())
+        .map_err(|e| AppError::internal(format!("Failed to emit statuses event: {}", e)))
+}
+
+// Board columns in order, with how many clips each holds
+#[tauri::command]
+async fn list_statuses(db: State<'_, Database>) -> Result<Vec<ClipStatus>, AppError> {
+    statuses::list_statuses(&db.conn()?)
+}
+
+// Add a status as the last board column
+#[tauri::command]
+async fn create_status(app_handle: AppHandle, db: State<'_, Database>, name: String) -> Result<ClipStatus, AppError> {
+    let status = db.write_async(move |conn| statuses::create_status(conn, &name)).await?;
+    emit_statuses_changed(&app_handle)?;
+    Ok(status)
+}
+
+#[tauri::command]
+async fn rename_status(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+    name: String,
+) -> Result<ClipStatus, AppError> {
+    let status = db.write_async(move |conn| statuses::rename_status(conn, id, &name)).await?;
+    emit_statuses_changed(&app_handle)?;
+    Ok(status)
+}
+
+// Deletes the status; its clips are kept without one
+#[tauri::command]
+async fn delete_status(app_handle: AppHandle, db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.write_async(move |conn| statuses::delete_status(conn, id)).await?;
+    emit_statuses_changed(&app_handle)
+}
+
+// Put the board columns in this order; `ids` must list every status
+#[tauri::command]
+async fn reorder_statuses(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    ids: Vec<i64>,
+) -> Result<Vec<ClipStatus>, AppError> {
+    let statuses = db.write_async(move |conn| statuses::reorder_statuses(conn, &ids)).await?;
+    emit_statuses_changed(&app_handle)?;
+    Ok(statuses)
+}
+
+// Move a clip to the bottom of a status column, or off the board when status_id is null
+#[tauri::command]
+async fn set_clip_status(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    clip_id: i64,
+    status_id: Option<i64>,
+) -> Result<SqliteClip, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let clip = db.write_async(move |conn| statuses::set_clip_status(conn, clip_id, status_id)).await?;
+    events::emit(&app_handle, "clip-updated", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    emit_statuses_changed(&app_handle)?;
+    Ok(clip)
+}
+
+// Drag a clip to `position` (0 is the top) within its status column
+#[tauri::command]
+async fn reorder_clip(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    clip_id: i64,
+    position: usize,
+) -> Result<SqliteClip, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let clip = db.write_async(move |conn| statuses::reorder_clip(conn, clip_id, position)).await?;
+    events::emit(&app_handle, "clip-updated", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(clip)
+}
+
+fn emit_reminders_changed(app_handle: &AppHandle) -> Result<(), AppError> {
+    events::emit(app_handle, "reminders-changed", &())
+        .map_err(|e| AppError::internal(format!("Failed to emit reminders event: {}", e)))
+}
+
+// Remind about a clip at `when` (milliseconds since the epoch); replaces the clip's open reminder
+#[tauri::command]
+async fn set_reminder(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    clip_id: i64,
+    when: i64,
+    note: Option<String>,
+) -> Result<Reminder, AppError> {
+    let reminder = db.write_async(move |conn| reminders::set_reminder(conn, clip_id, when, note.as_deref())).await?;
+    emit_reminders_changed(&app_handle)?;
+    Ok(reminder)
+}
+
+// Open reminders soonest first, plus completed ones when asked
+#[tauri::command]
+async fn list_reminders(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    include_completed: Option<bool>,
+) -> Result<Vec<Reminder>, AppError> {
+    lock.check()?;
+    reminders::list_reminders(&db.conn()?, include_completed.unwrap_or(false))
+}
+
+// Fire the reminder again at `until`, or in an hour
+#[tauri::command]
+async fn snooze_reminder(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+    until: Option<i64>,
+) -> Result<Reminder, AppError> {
+    let reminder = db.write_async(move |conn| reminders::snooze_reminder(conn, id, until)).await?;
+    emit_reminders_changed(&app_handle)?;
+    Ok(reminder)
+}
+
+#[tauri::command]
+async fn complete_reminder(app_handle: AppHandle, db: State<'_, Database>, id: i64) -> Result<Reminder, AppError> {
+    let reminder = db.write_async(move |conn| reminders::complete_reminder(conn, id)).await?;
+    emit_reminders_changed(&app_handle)?;
+    Ok(reminder)
+}
+
+#[tauri::command]
+async fn delete_reminder(app_handle: AppHandle, db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.write_async(move |conn| reminders::delete_reminder(conn, id)).await?;
+    emit_reminders_changed(&app_handle)
+}
+
+// Simple command to process clip data directly
+#[tauri::command]
+async fn process_clip_data(app_handle: tauri::AppHandle, clip_data: ClipData) -> Result<String, AppError> {
+    debug!("Processing clip: {:?}", clip_data);
+    
+    // Emit event to frontend
+    match events::emit(&app_handle, "new-clip", &clip_data) {
+        Ok(_) => Ok("Clip processed successfully".to_string()),
+        Err(e) => Err(AppError::internal(format!("Failed to emit clip event: {}", e)))
+    }
+}
+
+// Secrets the app manages itself through their own commands (the app lock, database
+// key, clipper pairing key and sync passphrase); the generic commands refuse them
+fn reserved_secret(name: &str) -> Result<(), AppError> {
+    let reserved = [APP_LOCK_SECRET, PAIRING_KEY_SECRET, SYNC_PASSPHRASE_SECRET].contains(&name)
+        || name == DATABASE_KEY_SECRET
+        || name.starts_with(&format!("{}:", DATABASE_KEY_SECRET));
+    if reserved {
+        return Err(AppError::validation(format!("Secret '{}' is managed by the app", name)));
+    }
+    Ok(())
+}
+
+// Secure API key management commands
+#[tauri::command]
+async fn store_secret(
+    lock: State<'_, AppLock>,
+    secrets_manager: State<'_, SecretsManager>,
+    name: String,
+    value: String,
+) -> Result<String, AppError> {
+    lock.check()?;
+    reserved_secret(&name)?;
+    secrets_manager.store_secret(name.clone(), value).await?;
+    Ok(format!("Secret '{}' stored securely", name))
+}
+
+#[tauri::command]
+async fn get_secret(
+    lock: State<'_, AppLock>,
+    secrets_manager: State<'_, SecretsManager>,
+    name: String,
+) -> Result<String, AppError> {
+    lock.check()?;
+    reserved_secret(&name)?;
+    secrets_manager.get_secret(&name, "get_secret").await
+}
+
+#[tauri::command]
+async fn has_secret(
+    secrets_manager: State<'_, SecretsManager>,
+    name: String,
+) -> Result<bool, AppError> {
+    secrets_manager.has_secret(&name).await
+}
+
+#[tauri::command]
+async fn list_secrets(
+    secrets_manager: State<'_, SecretsManager>,
+) -> Result<Vec<String>, AppError> {
+    secrets_manager.list_secrets().await
+}
+
+#[tauri::command]
+async fn remove_secret(
+    lock: State<'_, AppLock>,
+    secrets_manager: State<'_, SecretsManager>,
+    name: String,
+) -> Result<String, AppError> {
+    lock.check()?;
+    reserved_secret(&name)?;
+    secrets_manager.remove_secret(&name).await?;
+    Ok(format!("Secret '{}' removed", name))
+}
+
+// Names, providers and expiry dates of the stored secrets, without their values
+#[tauri::command]
+async fn list_secret_info(
+    lock: State<'_, AppLock>,
+    secrets_manager: State<'_, SecretsManager>,
+) -> Result<Vec<SecretInfo>, AppError> {
+    lock.check()?;
+    secrets_manager.list_secret_info().await
+}
+
+// Record when a secret expires (seconds since the epoch) and which provider it's for
+#[tauri::command]
+async fn set_secret_metadata(
+    lock: State<'_, AppLock>,
+    secrets_manager: State<'_, SecretsManager>,
+    name: String,
+    expires_at: Option<u64>,
+    provider: Option<String>,
+) -> Result<SecretInfo, AppError> {
+    lock.check()?;
+    reserved_secret(&name)?;
+    secrets_manager.set_secret_metadata(&name, expires_at, provider).await
+}
+
+// Swap in a new value for a secret, keeping the old one for `rollback_secret`
+#[tauri::command]
+async fn rotate_secret(
+    lock: State<'_, AppLock>,
+    secrets_manager: State<'_, SecretsManager>,
+    name: String,
+    new_value: String,
+    expires_at: Option<u64>,
+) -> Result<SecretInfo, AppError> {
+    lock.check()?;
+    reserved_secret(&name)?;
+    secrets_manager.rotate_secret(&name, new_value, expires_at).await
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct SearchResponse {
-    results: Vec<SearchResult>,
-    total_results: u32,
-    search_time: f64,
+// Restore the value a secret had before its last rotation
+#[tauri::command]
+async fn rollback_secret(
+    lock: State<'_, AppLock>,
+    secrets_manager: State<'_, SecretsManager>,
+    name: String,
+) -> Result<SecretInfo, AppError> {
+    lock.check()?;
+    reserved_secret(&name)?;
+    secrets_manager.rollback_secret(&name).await
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct ClipData {
-    r#type: String, // article, image, url, note
-    title: String,
-    url: Option<String>,
-    content: Option<String>,
-    image_url: Option<String>,
-    description: Option<String>,
-    author: Option<String>,
-    timestamp: u64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct SqliteClip {
-    id: i32,
-    r#type: String,
-    title: String,
-    url: Option<String>,
-    content: Option<String>,
-    image_url: Option<String>,
-    description: Option<String>,
-    author: Option<String>,
-    timestamp: i64,
-    created_at: String,
+// Which commands and features read a secret and when, newest reads first
+#[tauri::command]
+async fn get_secret_audit(
+    lock: State<'_, AppLock>,
+    secrets_manager: State<'_, SecretsManager>,
+    db: State<'_, Database>,
+    name: String,
+    limit: Option<u32>,
+) -> Result<SecretAudit, AppError> {
+    lock.check()?;
+    secret_audit::flush(&db, &secrets_manager).await?;
+    secret_audit::get_secret_audit(&db.conn()?, &name, limit.unwrap_or(100).min(1000))
 }
 
-// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+// Probe the service a stored key belongs to (LLM providers, Brave) so the user knows a
+// pasted key works. A rejected key is a normal result with `status: "invalid"`.
 #[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
+async fn validate_secret(
+    secrets_manager: State<'_, SecretsManager>,
+    settings: State<'_, SettingsManager>,
+    requests: State<'_, RequestRegistry>,
+    name: String,
+    request_id: Option<String>,
+) -> Result<KeyValidation, AppError> {
+    let settings = settings.get();
+    let check = key_check::validate_secret(&secrets_manager, &settings, &name);
+    requests.run(request_id, "validate_secret", settings.command_timeout("validate_secret"), check).await
 }
 
-// Placeholder implementations for search commands
-// These will be implemented when Rust toolchain is updated to 1.80+
 #[tauri::command]
-async fn search_brave(_query: String, _api_key: String, _num_results: u32) -> Result<SearchResponse, String> {
-    // TODO: Implement when Rust 1.80+ is available
-    Err("Brave Search requires Rust 1.80+. Please update your Rust toolchain.".to_string())
+async fn get_secrets_backend(
+    secrets_manager: State<'_, SecretsManager>,
+) -> Result<SecretsBackend, AppError> {
+    Ok(secrets_manager.backend().await)
 }
 
+// Moves every stored secret into the given backend and makes it the default
 #[tauri::command]
-async fn search_google(_query: String, _api_key: String, _num_results: u32) -> Result<SearchResponse, String> {
-    // TODO: Implement when Rust 1.80+ is available
-    Err("Google Search requires Rust 1.80+. Please update your Rust toolchain.".to_string())
+async fn migrate_secrets(
+    secrets_manager: State<'_, SecretsManager>,
+    settings: State<'_, SettingsManager>,
+    backend: SecretsBackend,
+) -> Result<String, AppError> {
+    let moved = secrets_manager.migrate(backend).await?;
+    settings.update(|s| s.secrets_backend = backend)?;
+    Ok(format!("Migrated {} secret(s)", moved))
 }
 
+// Start a new saved chat
 #[tauri::command]
-async fn fetch_url_content(_url: String) -> Result<String, String> {
-    // TODO: Implement when Rust 1.80+ is available
-    Err("URL content fetching requires Rust 1.80+. Please update your Rust toolchain.".to_string())
+async fn create_conversation(
+    db: State<'_, Database>,
+    title: Option<String>,
+    provider: Option<String>,
+    model: Option<String>,
+) -> Result<Conversation, AppError> {
+    db.write_async(move |conn| {
+        conversations::create_conversation(conn, title.as_deref(), provider.as_deref(), model.as_deref())
+    })
+    .await
 }
 
-// Command to read all clips from SQLite database
 #[tauri::command]
-async fn get_all_clips() -> Result<Vec<SqliteClip>, String> {
-    let db_path = "/home/daniel-parker/Desktop/LOSenviorment/los-app/clips.db";
-    
-    match Connection::open(db_path) {
-        Ok(conn) => {
-            let mut stmt = match conn.prepare("SELECT id, type, title, url, content, image_url, description, author, timestamp, created_at FROM clips ORDER BY timestamp DESC") {
-                Ok(stmt) => stmt,
-                Err(e) => return Err(format!("Failed to prepare statement: {}", e)),
-            };
-            
-            let clip_iter = match stmt.query_map([], |row| {
-                Ok(SqliteClip {
-                    id: row.get(0)?,
-                    r#type: row.get(1)?,
-                    title: row.get(2)?,
-                    url: row.get(3)?,
-                    content: row.get(4)?,
-                    image_url: row.get(5)?,
-                    description: row.get(6)?,
-                    author: row.get(7)?,
-                    timestamp: row.get(8)?,
-                    created_at: row.get(9)?,
-                })
-            }) {
-                Ok(iter) => iter,
-                Err(e) => return Err(format!("Failed to execute query: {}", e)),
-            };
-            
-            let mut clips = Vec::new();
-            for clip in clip_iter {
-                match clip {
-                    Ok(clip) => clips.push(clip),
-                    Err(e) => return Err(format!("Failed to read clip: {}", e)),
-                }
-            }
-            
-            Ok(clips)
-        },
-        Err(e) => Err(format!("Failed to open database: {}", e)),
-    }
+async fn append_message(
+    db: State<'_, Database>,
+    conversation_id: i64,
+    role: String,
+    content: String,
+) -> Result<Message, AppError> {
+    db.write_async(move |conn| conversations::append_message(conn, conversation_id, &role, &content, None)).await
 }
 
-// Simple command to process clip data directly
 #[tauri::command]
-async fn process_clip_data(app_handle: tauri::AppHandle, clip_data: ClipData) -> Result<String, String> {
-    println!("Processing clip: {:?}", clip_data);
-    
-    // Emit event to frontend
-    match app_handle.emit("new-clip", clip_data.clone()) {
-        Ok(_) => Ok("Clip processed successfully".to_string()),
-        Err(e) => Err(format!("Failed to emit clip event: {}", e))
+async fn list_conversations(lock: State<'_, AppLock>, db: State<'_, Database>) -> Result<Vec<Conversation>, AppError> {
+    lock.check()?;
+    conversations::list_conversations(&db.conn()?)
+}
+
+#[tauri::command]
+async fn get_conversation(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<Option<ConversationDetail>, AppError> {
+    lock.check()?;
+    conversations::get_conversation(&db.conn()?, id)
+}
+
+// Change a conversation's system prompt, model, temperature or max_tokens; the next
+// `call_llm` with its id uses them
+#[tauri::command]
+async fn update_conversation_settings(
+    settings_manager: State<'_, SettingsManager>,
+    db: State<'_, Database>,
+    id: i64,
+    settings: ConversationSettings,
+) -> Result<Conversation, AppError> {
+    if let Some(provider) = &settings.provider {
+        ProviderRegistry::from_settings(&settings_manager.get()).get(provider)?;
     }
+    db.write_async(move |conn| conversations::update_conversation_settings(conn, id, &settings)).await
 }
 
-// Secure API key management commands
+// Secure LLM API call command. With a `conversation_id`, `messages` are only the new
+// turn: they're sent after the saved history, and both they and the reply are appended.
+// The conversation's stored system prompt and model settings apply to any argument
+// left out. `images` go with the last new message, for vision-capable models; they
+// aren't saved with the conversation.
+// Errors are classified (rate_limited, auth, quota, network, ...) so the UI can react.
 #[tauri::command]
-async fn store_secret(
+async fn call_llm(
+    app_handle: AppHandle,
     secrets_manager: State<'_, SecretsManager>,
-    name: String,
-    value: String,
-) -> Result<String, String> {
-    secrets_manager.store_secret(name.clone(), value).await?;
-    Ok(format!("Secret '{}' stored securely", name))
+    settings: State<'_, SettingsManager>,
+    db: State<'_, Database>,
+    requests: State<'_, RequestRegistry>,
+    conversation_id: Option<i64>,
+    provider: Option<String>,
+    model: Option<String>,
+    messages: Vec<LlmMessage>,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+    safety_settings: Option<Vec<SafetySetting>>,
+    images: Option<Vec<ImageAttachment>>,
+    request_id: Option<String>,
+) -> Result<providers::LlmResponse, AppError> {
+    let images = vision::prepare_images(&app_handle, images.unwrap_or_default()).await?;
+    let settings = settings.get();
+    let registry = ProviderRegistry::from_settings(&settings);
+    let (full_messages, stored) = match conversation_id {
+        Some(id) => {
+            app_handle.state::<AppLock>().check()?;
+            let conn = db.conn()?;
+            let mut history = conversations::history(&conn, id)?;
+            history.extend(messages.iter().cloned());
+            (history, conversations::get_conversation_row(&conn, id)?)
+        }
+        None => (messages.clone(), None),
+    };
+    let provider = provider
+        .or_else(|| stored.as_ref().and_then(|c| c.provider.clone()))
+        .ok_or_else(|| AppError::validation("No provider given for this call"))?;
+    let model = model
+        .or_else(|| stored.as_ref().and_then(|c| c.model.clone()))
+        .ok_or_else(|| AppError::validation("No model given for this call"))?;
+    let max_tokens = max_tokens.or_else(|| stored.as_ref().and_then(|c| c.max_tokens));
+    let temperature = temperature.or_else(|| stored.as_ref().and_then(|c| c.temperature));
+    let price = usage::price_for(&provider, &model, &settings.model_prices);
+    let model_name = model.clone();
+    let request = LlmRequest {
+        model,
+        messages: full_messages,
+        max_tokens,
+        temperature,
+        safety_settings,
+        images,
+    };
+    let call = async {
+        call_llm_api(&secrets_manager, &registry, &provider, request, "call_llm")
+            .await
+            .inspect_err(|e| notifications::notify_llm_error(&app_handle, &provider, e))
+    };
+    let response = requests.run(request_id, "call_llm", settings.command_timeout("call_llm"), call).await?;
+
+    // The turn is stored whole or not at all
+    let (llm_usage, reply) = (response.usage.clone(), response.content.clone());
+    db.write_async(move |conn| {
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+        if let Some(llm_usage) = &llm_usage {
+            usage::record_usage(&tx, &provider, &model_name, llm_usage, price)?;
+        }
+        if let Some(id) = conversation_id {
+            for message in &messages {
+                conversations::append_message(&tx, id, &message.role, &message.content, None)?;
+            }
+            conversations::append_message(&tx, id, "assistant", &reply, llm_usage.as_ref())?;
+        }
+        tx.commit()
+            .map_err(|e| AppError::database(format!("Failed to commit conversation turn: {}", e)))
+    })
+    .await?;
+
+    Ok(response)
 }
 
+// Like `call_llm`, but the model may call tools (web search, clip search, page fetch)
+// before answering. `tools` defaults to all of them; each call is emitted as
+// `llm-tool-call`. Supported by Anthropic and OpenAI-compatible providers.
 #[tauri::command]
-async fn get_secret(
-    secrets_manager: State<'_, SecretsManager>,
+async fn call_llm_with_tools(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsManager>,
+    requests: State<'_, RequestRegistry>,
+    provider: String,
+    model: String,
+    messages: Vec<LlmMessage>,
+    tools: Option<Vec<Tool>>,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+    request_id: Option<String>,
+) -> Result<ToolAnswer, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let tools = tools.unwrap_or_else(|| Tool::ALL.to_vec());
+    let timeout = settings.get().command_timeout("call_llm_with_tools");
+    let call = tools::call_with_tools(&app_handle, &provider, &model, messages, &tools, max_tokens, temperature);
+    requests.run(request_id, "call_llm_with_tools", timeout, call).await
+}
+
+// Prompt templates, sorted by name; each lists the `{{variables}}` it uses
+#[tauri::command]
+async fn list_prompts(db: State<'_, Database>) -> Result<Vec<PromptTemplate>, AppError> {
+    prompts::list_prompts(&db.conn()?)
+}
+
+#[tauri::command]
+async fn create_prompt(
+    db: State<'_, Database>,
     name: String,
-) -> Result<String, String> {
-    secrets_manager.get_secret(&name).await
+    template: String,
+    description: Option<String>,
+) -> Result<PromptTemplate, AppError> {
+    db.write_async(move |conn| prompts::create_prompt(conn, &name, &template, description.as_deref())).await
 }
 
 #[tauri::command]
-async fn has_secret(
-    secrets_manager: State<'_, SecretsManager>,
+async fn update_prompt(
+    db: State<'_, Database>,
+    id: i64,
     name: String,
-) -> Result<bool, String> {
-    Ok(secrets_manager.has_secret(&name).await)
+    template: String,
+    description: Option<String>,
+) -> Result<PromptTemplate, AppError> {
+    db.write_async(move |conn| prompts::update_prompt(conn, id, &name, &template, description.as_deref())).await
 }
 
 #[tauri::command]
-async fn list_secrets(
-    secrets_manager: State<'_, SecretsManager>,
-) -> Result<Vec<String>, String> {
-    Ok(secrets_manager.list_secrets().await)
+async fn delete_prompt(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.write_async(move |conn| prompts::delete_prompt(conn, id)).await
 }
 
+// Render a template against a clip or some text and send it through `call_llm`.
+// Uses the default model unless both `provider` and `model` are given.
 #[tauri::command]
-async fn remove_secret(
+async fn run_prompt(
+    app_handle: AppHandle,
     secrets_manager: State<'_, SecretsManager>,
-    name: String,
-) -> Result<String, String> {
-    secrets_manager.remove_secret(&name).await?;
-    Ok(format!("Secret '{}' removed", name))
+    settings: State<'_, SettingsManager>,
+    db: State<'_, Database>,
+    requests: State<'_, RequestRegistry>,
+    template_id: i64,
+    clip_id: Option<i64>,
+    text: Option<String>,
+    variables: Option<std::collections::HashMap<String, String>>,
+    provider: Option<String>,
+    model: Option<String>,
+    request_id: Option<String>,
+) -> Result<providers::LlmResponse, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let prompt = prompts::render_prompt(&db.conn()?, template_id, clip_id, text, variables.unwrap_or_default())?;
+    let selection = match (provider, model) {
+        (Some(provider), Some(model)) => ModelSelection { provider, model },
+        _ => settings
+            .get()
+            .default_model
+            .ok_or_else(|| AppError::validation("No default model configured; choose one in settings"))?,
+    };
+    let messages = vec![LlmMessage {
+        role: "user".to_string(),
+        content: prompt,
+    }];
+    call_llm(
+        app_handle,
+        secrets_manager,
+        settings,
+        db,
+        requests,
+        None,
+        Some(selection.provider),
+        Some(selection.model),
+        messages,
+        None,
+        None,
+        None,
+        None,
+        request_id,
+    )
+    .await
 }
 
-// Secure LLM API call command
+// Ingestion rules, evaluated in creation order against every new clip
 #[tauri::command]
-async fn call_llm(
-    secrets_manager: State<'_, SecretsManager>,
-    model: String,
-    messages: Vec<LlmMessage>,
-    max_tokens: Option<u32>,
-    temperature: Option<f32>,
-) -> Result<secrets::LlmResponse, String> {
-    call_llm_api(&secrets_manager, model, messages, max_tokens, temperature).await
+async fn list_rules(db: State<'_, Database>) -> Result<Vec<Rule>, AppError> {
+    rules::list_rules(&db.conn()?)
+}
+
+#[tauri::command]
+async fn create_rule(db: State<'_, Database>, rule: NewRule) -> Result<Rule, AppError> {
+    db.write_async(move |conn| rules::create_rule(conn, rule)).await
+}
+
+#[tauri::command]
+async fn update_rule(db: State<'_, Database>, id: i64, rule: NewRule) -> Result<Rule, AppError> {
+    db.write_async(move |conn| rules::update_rule(conn, id, rule)).await
+}
+
+#[tauri::command]
+async fn delete_rule(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.write_async(move |conn| rules::delete_rule(conn, id)).await
+}
+
+// Dry run: what `rule` would do to `sample_clip`, without saving either
+#[tauri::command]
+async fn test_rule(rule: NewRule, sample_clip: ClipData) -> Result<RuleTest, AppError> {
+    rules::test_rule(rule, &video::classify(sample_clip))
+}
+
+// Domains clips came from with clip counts and when each was last clipped, plus their
+// mute, default tag and default collection settings
+#[tauri::command]
+async fn list_sources(db: State<'_, Database>) -> Result<Vec<Source>, AppError> {
+    sources::list_sources(&db.conn()?)
+}
+
+// Set what happens to new clips from `domain`; the rules engine applies it at ingestion
+#[tauri::command]
+async fn update_source(db: State<'_, Database>, domain: String, settings: SourceSettings) -> Result<Source, AppError> {
+    db.write_async(move |conn| sources::update_source(conn, &domain, settings)).await
+}
+
+// Choose the model used for summaries and other background LLM work
+#[tauri::command]
+async fn set_default_model(settings: State<'_, SettingsManager>, selection: Option<ModelSelection>) -> Result<(), AppError> {
+    if let Some(selection) = &selection {
+        ProviderRegistry::from_settings(&settings.get()).get(&selection.provider)?;
+    }
+    settings.update(|s| s.default_model = selection)?;
+    Ok(())
+}
+
+// Q/A cards for a clip from the default model. Existing cards are returned as they
+// are unless `regenerate` asks for more.
+#[tauri::command]
+async fn generate_flashcards(
+    app_handle: AppHandle,
+    clip_id: i64,
+    n: Option<u32>,
+    regenerate: Option<bool>,
+) -> Result<Vec<Flashcard>, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let count = n.unwrap_or(flashcards::DEFAULT_CARD_COUNT);
+    Ok(flashcards::generate_flashcards(&app_handle, clip_id, count, regenerate.unwrap_or(false)).await?)
+}
+
+#[tauri::command]
+async fn list_flashcards(
+    lock: State<'_, AppLock>,
+    db: State<'_, Database>,
+    clip_id: i64,
+) -> Result<Vec<Flashcard>, AppError> {
+    lock.check()?;
+    flashcards::list_flashcards(&db.conn()?, clip_id)
+}
+
+#[tauri::command]
+async fn delete_flashcard(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.write_async(move |conn| flashcards::delete_flashcard(conn, id)).await
+}
+
+// Send the clips' cards to Anki, as an .apkg file or through AnkiConnect
+#[tauri::command]
+async fn export_flashcards(
+    app_handle: AppHandle,
+    clip_ids: Vec<i64>,
+    target: FlashcardTarget,
+    deck: Option<String>,
+) -> Result<FlashcardExportSummary, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    flashcards::export_flashcards(&app_handle, clip_ids, target, deck).await
+}
+
+// Summarize a clip with the default model; also emits `clip-summarized`
+#[tauri::command]
+async fn summarize_clip(app_handle: AppHandle, id: i64) -> Result<String, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    Ok(summarize::summarize_clip(&app_handle, id).await?)
+}
+
+// Queue summary jobs for clips that don't have a summary yet; returns the jobs
+#[tauri::command]
+async fn summarize_missing_clips(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    queue: State<'_, JobQueue>,
+    limit: Option<u32>,
+) -> Result<Vec<Job>, AppError> {
+    let ids = clips::unsummarized_clip_ids(&db.conn()?, limit.unwrap_or(100))?;
+    ids.into_iter()
+        .map(|clip_id| queue.submit(&app_handle, JobKind::SummarizeClip { clip_id }))
+        .collect()
+}
+
+// Choose the embeddings model used for semantic search. Changing it means clips
+// need re-indexing with `index_clip_embeddings`.
+#[tauri::command]
+async fn set_embedding_model(settings: State<'_, SettingsManager>, selection: Option<ModelSelection>) -> Result<(), AppError> {
+    if let Some(selection) = &selection {
+        ProviderRegistry::from_settings(&settings.get()).get(&selection.provider)?;
+    }
+    settings.update(|s| s.embedding_model = selection)?;
+    Ok(())
+}
+
+// Queue embedding jobs for clips that are new or changed since they were last indexed
+#[tauri::command]
+async fn index_clip_embeddings(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    queue: State<'_, JobQueue>,
+    limit: Option<u32>,
+) -> Result<Vec<Job>, AppError> {
+    let model = embeddings::embedding_model(&app_handle)?.model;
+    let ids = embeddings::stale_clip_ids(&db.conn()?, &model, limit.unwrap_or(1000))?;
+    ids.chunks(embeddings::BATCH_SIZE)
+        .map(|batch| queue.submit(&app_handle, JobKind::EmbedClips { clip_ids: batch.to_vec() }))
+        .collect()
+}
+
+// Save a self-contained HTML snapshot of the clip's page (styles and images inlined)
+#[tauri::command]
+async fn archive_clip(app_handle: AppHandle, id: i64) -> Result<ClipArchive, AppError> {
+    connectivity::ensure_online()?;
+    archive::archive_clip(&app_handle, id).await
+}
+
+// Clips whose links were found dead by the periodic link checker
+#[tauri::command]
+async fn find_broken_links(db: State<'_, Database>) -> Result<Vec<BrokenLink>, AppError> {
+    links::find_broken_links(&db.conn()?)
+}
+
+// Check a clip's link right away instead of waiting for the scheduler
+#[tauri::command]
+async fn check_clip_link(app_handle: AppHandle, id: i64) -> Result<LinkCheck, AppError> {
+    connectivity::ensure_online()?;
+    links::check_clip_link(&app_handle, id).await
+}
+
+// Restore a dead page's content from the Wayback Machine snapshot closest to when it was clipped
+#[tauri::command]
+async fn recover_from_wayback(app_handle: AppHandle, clip_id: i64) -> Result<WaybackRecovery, AppError> {
+    connectivity::ensure_online()?;
+    wayback::recover_from_wayback(&app_handle, clip_id).await
+}
+
+// Typeset a card of the clip's title, a quote and its source as a PDF or PNG at
+// `dest_path`; the quote defaults to the first highlight
+#[tauri::command]
+async fn render_clip(
+    app_handle: AppHandle,
+    id: i64,
+    format: RenderFormat,
+    dest_path: PathBuf,
+    quote: Option<String>,
+) -> Result<RenderedClip, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    share::render_clip(&app_handle, id, format, dest_path, quote).await
+}
+
+// Show a clip in its own reader window
+#[tauri::command]
+async fn open_reader_window(app_handle: AppHandle, id: i64) -> Result<(), AppError> {
+    windows::open_reader_window(&app_handle, id)
+}
+
+// Show the small always-on-top quick-capture window
+#[tauri::command]
+async fn open_capture_window(app_handle: AppHandle) -> Result<(), AppError> {
+    windows::open_capture_window(&app_handle)
+}
+
+// Cached icon of the clip's site, if one was found when the clip was saved
+#[tauri::command]
+async fn get_clip_favicon(app_handle: AppHandle, id: i64) -> Result<Option<PathBuf>, AppError> {
+    site_metadata::get_clip_favicon(&app_handle, id)
+}
+
+// Local copy of a clip's image, downloaded again if it's missing
+#[tauri::command]
+async fn get_clip_image(app_handle: AppHandle, id: i64) -> Result<ClipImage, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    media::get_clip_image(&app_handle, id).await
+}
+
+// Small WebP version of a clip's image for grid views; `size` is the longest edge in pixels
+#[tauri::command]
+async fn get_clip_thumbnail(app_handle: AppHandle, id: i64, size: Option<u32>) -> Result<ClipThumbnail, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    thumbnails::get_clip_thumbnail(&app_handle, id, size).await
+}
+
+// Queue OCR for an image clip; progress is reported through `job-updated` and `get_clip_ocr`
+#[tauri::command]
+async fn ocr_clip(app_handle: AppHandle, id: i64) -> Result<Job, AppError> {
+    ocr::queue_ocr(&app_handle, id)
+}
+
+// OCR status and recognized text of an image clip, or null if OCR never ran on it
+#[tauri::command]
+async fn get_clip_ocr(lock: State<'_, AppLock>, db: State<'_, Database>, id: i64) -> Result<Option<ClipOcr>, AppError> {
+    lock.check()?;
+    ocr::get_clip_ocr(&db.conn()?, id)
+}
+
+// Fetch a video clip's captions again, e.g. after the author added some
+#[tauri::command]
+async fn fetch_video_transcript(app_handle: AppHandle, id: i64) -> Result<Job, AppError> {
+    video::queue_transcript(&app_handle, id)
+}
+
+// RSS/Atom subscriptions, alphabetically
+#[tauri::command]
+async fn list_feeds(db: State<'_, Database>) -> Result<Vec<Feed>, AppError> {
+    feeds::list_feeds(&db.conn()?)
+}
+
+// Subscribe to an RSS or Atom feed. New items become article clips; with
+// `fetch_full_content` each item's page is fetched for the whole article.
+#[tauri::command]
+async fn add_feed(app_handle: AppHandle, url: String, fetch_full_content: Option<bool>) -> Result<Feed, AppError> {
+    connectivity::ensure_online()?;
+    feeds::add_feed(&app_handle, &url, fetch_full_content.unwrap_or(false)).await
+}
+
+// Unsubscribe from a feed; its clips stay in the library
+#[tauri::command]
+async fn remove_feed(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.write_async(move |conn| feeds::remove_feed(conn, id)).await
+}
+
+// Refresh one feed now, or all of them without an id. Runs in the job queue;
+// each refresh emits `feed-refreshed`.
+#[tauri::command]
+async fn refresh_feeds(app_handle: AppHandle, id: Option<i64>) -> Result<Vec<Job>, AppError> {
+    feeds::queue_refresh(&app_handle, id)
+}
+
+// Clip a PDF from a file path or URL; its text becomes the clip's content
+#[tauri::command]
+async fn clip_pdf(app_handle: AppHandle, source: String) -> Result<ClipInsert, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    pdf::clip_pdf(&app_handle, &source).await
+}
+
+// Local path, page count and document metadata of a PDF clip
+#[tauri::command]
+async fn get_clip_pdf(app_handle: AppHandle, id: i64) -> Result<ClipPdf, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    pdf::get_clip_pdf(&app_handle, id)
+}
+
+// Clip an audio file from a local path or http(s) URL; transcription is queued and
+// fills in the clip's content when done
+#[tauri::command]
+async fn clip_audio(app_handle: AppHandle, source: String) -> Result<ClipInsert, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    audio::clip_audio(&app_handle, &source).await
+}
+
+// Local path and type of an audio clip's file, for playback
+#[tauri::command]
+async fn get_clip_audio(app_handle: AppHandle, id: i64) -> Result<ClipAudio, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    audio::get_clip_audio(&app_handle, id)
+}
+
+// Transcribe an audio clip again now, e.g. after switching transcription engines
+#[tauri::command]
+async fn transcribe_audio(app_handle: AppHandle, id: i64) -> Result<AudioTranscript, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    Ok(audio::transcribe_clip(&app_handle, id).await?)
+}
+
+// Read a clip or some text aloud. Returns a playable audio file from the media dir,
+// generated once per text and voice and cached after that.
+#[tauri::command]
+async fn synthesize_speech(
+    app_handle: AppHandle,
+    clip_id: Option<i64>,
+    text: Option<String>,
+    voice: Option<String>,
+) -> Result<Speech, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    Ok(speech::synthesize_speech(&app_handle, clip_id, text, voice).await?)
+}
+
+// Queue a research job: search the web, save and summarize the top pages, and write a
+// report note citing them. Progress is emitted as `research-progress`.
+#[tauri::command]
+async fn research_topic(
+    app_handle: AppHandle,
+    queue: State<'_, JobQueue>,
+    query: String,
+    sources: Option<u32>,
+) -> Result<Job, AppError> {
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Err(AppError::validation("Research query must not be empty"));
+    }
+    let sources = sources.unwrap_or(research::DEFAULT_SOURCE_COUNT);
+    queue.submit(&app_handle, JobKind::ResearchTopic { query, sources })
+}
+
+// Queue today's digest now instead of waiting for `digest_hour`
+#[tauri::command]
+async fn generate_digest(app_handle: AppHandle, queue: State<'_, JobQueue>) -> Result<Job, AppError> {
+    queue.submit(&app_handle, JobKind::GenerateDigest)
+}
+
+// Queue index maintenance now instead of waiting for `maintenance_interval_hours`
+#[tauri::command]
+async fn run_maintenance_now(app_handle: AppHandle, queue: State<'_, JobQueue>) -> Result<Job, AppError> {
+    queue.submit(&app_handle, JobKind::IndexMaintenance)
+}
+
+// Clip the unread messages in the email inbox folder now instead of waiting for the next poll
+#[tauri::command]
+async fn check_email_now(app_handle: AppHandle) -> Result<EmailCheck, AppError> {
+    connectivity::ensure_online()?;
+    email::check_email(&app_handle).await
+}
+
+// Files that came attached to an emailed clip
+#[tauri::command]
+async fn get_clip_attachments(
+    lock: State<'_, AppLock>,
+    config: State<'_, AppConfig>,
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<Vec<ClipAttachment>, AppError> {
+    lock.check()?;
+    email::list_attachments(&db.conn()?, &config.media_dir(), id)
+}
+
+// Background jobs, newest first; filter by status (queued, running, done, failed, cancelled)
+#[tauri::command]
+async fn list_jobs(db: State<'_, Database>, status: Option<String>, limit: Option<u32>) -> Result<Vec<Job>, AppError> {
+    jobs::list_jobs(&db.conn()?, status.as_deref(), limit.unwrap_or(100).min(1000))
+}
+
+#[tauri::command]
+async fn cancel_job(app_handle: AppHandle, db: State<'_, Database>, id: i64) -> Result<Job, AppError> {
+    let job = db.write_async(move |conn| jobs::cancel_job(conn, id)).await?;
+    redact::emit(&app_handle, "job-updated", &job)
+        .map_err(|e| AppError::internal(format!("Failed to emit job event: {}", e)))?;
+    Ok(job)
+}
+
+// Requeue a failed or cancelled job
+#[tauri::command]
+async fn retry_job(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    queue: State<'_, JobQueue>,
+    id: i64,
+) -> Result<Job, AppError> {
+    let job = db.write_async(move |conn| jobs::retry_job(conn, id)).await?;
+    queue.poke();
+    redact::emit(&app_handle, "job-updated", &job)
+        .map_err(|e| AppError::internal(format!("Failed to emit job event: {}", e)))?;
+    Ok(job)
+}
+
+// Find clips by meaning: the `k` nearest neighbours of the query with cosine scores
+#[tauri::command]
+async fn semantic_search_clips(app_handle: AppHandle, query: String, k: Option<usize>) -> Result<Vec<SemanticHit>, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    Ok(embeddings::semantic_search(&app_handle, &query, k.unwrap_or(10).min(100)).await?)
+}
+
+// Answer a question from the most relevant clips, with the clip ids it cites
+#[tauri::command]
+async fn ask_clips(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsManager>,
+    requests: State<'_, RequestRegistry>,
+    question: String,
+    k: Option<usize>,
+    request_id: Option<String>,
+) -> Result<AskAnswer, AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let timeout = settings.get().command_timeout("ask_clips");
+    let ask = ask::ask_clips(&app_handle, &question, k.unwrap_or(6).clamp(1, 20));
+    requests.run(request_id, "ask_clips", timeout, ask).await
+}
+
+// Token and cost totals for LLM calls over the last `days` days (default 30)
+#[tauri::command]
+async fn get_usage_summary(db: State<'_, Database>, period: UsagePeriod, days: Option<u32>) -> Result<UsageSummary, AppError> {
+    usage::usage_summary(&db.conn()?, period, days.unwrap_or(30))
+}
+
+// Override model prices (USD per million tokens), keyed by model-name prefix
+#[tauri::command]
+async fn set_model_prices(
+    settings: State<'_, SettingsManager>,
+    prices: std::collections::HashMap<String, ModelPrice>,
+) -> Result<(), AppError> {
+    if prices.values().any(|p| p.input_per_million < 0.0 || p.output_per_million < 0.0) {
+        return Err(AppError::validation("Prices must not be negative"));
+    }
+    settings.update(|s| s.model_prices = prices)?;
+    Ok(())
+}
+
+// Providers `call_llm` can route to, with the secret each one reads its API key from
+#[tauri::command]
+async fn list_llm_providers(settings: State<'_, SettingsManager>) -> Result<Vec<ProviderInfo>, AppError> {
+    Ok(ProviderRegistry::from_settings(&settings.get()).list())
+}
+
+// Replace the user's custom OpenAI-compatible endpoints
+#[tauri::command]
+async fn set_custom_llm_providers(
+    settings: State<'_, SettingsManager>,
+    providers: Vec<CustomProviderConfig>,
+) -> Result<(), AppError> {
+    let builtin = ProviderRegistry::from_settings(&settings::Settings::default());
+    let mut seen = std::collections::HashSet::new();
+    for provider in &providers {
+        if provider.id.trim().is_empty() {
+            return Err(AppError::validation("Provider id must not be empty"));
+        }
+        if builtin.get(&provider.id).is_ok() || !seen.insert(provider.id.as_str()) {
+            return Err(AppError::validation(format!("Provider id '{}' is already in use", provider.id)));
+        }
+        reqwest::Url::parse(&provider.base_url)
+            .map_err(|e| AppError::validation(format!("Invalid base URL for '{}': {}", provider.id, e)))?;
+    }
+    settings.update(|s| s.custom_providers = providers)?;
+    Ok(())
+}
+
+// Point local model calls at a different Ollama server; None restores the default
+#[tauri::command]
+async fn set_ollama_url(settings: State<'_, SettingsManager>, url: Option<String>) -> Result<(), AppError> {
+    let url = url.map(|url| url.trim().trim_end_matches('/').to_string()).filter(|url| !url.is_empty());
+    if let Some(url) = &url {
+        reqwest::Url::parse(url).map_err(|e| AppError::validation(format!("Invalid Ollama URL: {}", e)))?;
+    }
+    settings.update(|s| s.ollama_url = url)?;
+    Ok(())
+}
+
+// Models available from the local Ollama server, named for use with `call_llm`
+#[tauri::command]
+async fn list_local_models(settings: State<'_, SettingsManager>) -> Result<Vec<LocalModel>, AppError> {
+    let ollama = OllamaProvider::new(settings.get().ollama_url());
+    ollama.list_models().await
 }
 
 pub fn main() {
     tauri::Builder::default()
-        .manage(SecretsManager::new())
+        // Must come first so a second launch (e.g. from a los:// link) hands over before doing anything
+        .plugin(tauri_plugin_single_instance::init(|app_handle, args, _cwd| {
+            if !windows::has_deep_link(&args) {
+                tray::show_main_window(app_handle);
+            }
+        }))
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             greet, 
             search_brave, 
             search_google, 
+            web_search,
+            search_web,
+            clear_search_cache,
+            clip_search_result,
+            clip_search_results,
             fetch_url_content,
+            cancel_request,
             process_clip_data,
+            get_clipper_endpoint,
+            list_paired_clippers,
+            approve_clipper_pairing,
+            reject_clipper_pairing,
+            revoke_clipper,
+            list_quarantine,
             get_all_clips,
+            query_clips,
+            get_clip,
+            unlock_private_clips,
+            lock_private_clips,
+            set_clip_private,
+            search_clips,
+            search_code_clips,
+            create_clip,
+            update_clip,
+            convert_clip_to_markdown,
+            delete_clip,
+            bulk_update_clips,
+            bulk_delete_clips,
+            merge_clips,
+            list_trash,
+            restore_clip,
+            empty_trash,
+            add_tag_to_clip,
+            remove_tag_from_clip,
+            list_tags,
+            accept_auto_tag,
+            review_auto_category,
+            auto_tag_clip,
+            set_auto_tagging,
+            create_collection,
+            rename_collection,
+            delete_collection,
+            move_clips_to_collection,
+            list_collections,
+            create_smart_collection,
+            list_smart_collections,
+            delete_smart_collection,
+            list_statuses,
+            create_status,
+            rename_status,
+            delete_status,
+            reorder_statuses,
+            set_clip_status,
+            reorder_clip,
+            set_reminder,
+            list_reminders,
+            snooze_reminder,
+            complete_reminder,
+            delete_reminder,
             store_secret,
             get_secret,
             has_secret,
             list_secrets,
             remove_secret,
-            call_llm
+            list_secret_info,
+            set_secret_metadata,
+            rotate_secret,
+            rollback_secret,
+            get_secret_audit,
+            validate_secret,
+            get_secrets_backend,
+            migrate_secrets,
+            create_conversation,
+            append_message,
+            list_conversations,
+            get_conversation,
+            update_conversation_settings,
+            call_llm,
+            call_llm_with_tools,
+            list_prompts,
+            create_prompt,
+            update_prompt,
+            delete_prompt,
+            run_prompt,
+            list_rules,
+            create_rule,
+            update_rule,
+            delete_rule,
+            test_rule,
+            list_sources,
+            update_source,
+            get_usage_summary,
+            set_default_model,
+            summarize_clip,
+            generate_flashcards,
+            list_flashcards,
+            delete_flashcard,
+            export_flashcards,
+            summarize_missing_clips,
+            set_embedding_model,
+            index_clip_embeddings,
+            semantic_search_clips,
+            get_clip_image,
+            get_clip_thumbnail,
+            render_clip,
+            open_reader_window,
+            open_capture_window,
+            get_clip_favicon,
+            ocr_clip,
+            get_clip_ocr,
+            fetch_video_transcript,
+            list_revisions,
+            restore_revision,
+            list_annotations,
+            create_annotation,
+            update_annotation,
+            delete_annotation,
+            search_annotations,
+            set_read_state,
+            set_progress,
+            get_reading_stats,
+            get_library_stats,
+            list_feeds,
+            add_feed,
+            remove_feed,
+            refresh_feeds,
+            clip_pdf,
+            get_clip_pdf,
+            clip_audio,
+            get_clip_audio,
+            transcribe_audio,
+            synthesize_speech,
+            archive_clip,
+            find_broken_links,
+            check_clip_link,
+            recover_from_wayback,
+            find_duplicate_clips,
+            export_clips,
+            export_epub,
+            import_clips,
+            backup_now,
+            sync_now,
+            set_sync_passphrase,
+            get_sync_status,
+            push_to_remote_now,
+            get_remote_sync_status,
+            mirror_obsidian_vault,
+            export_to_notion,
+            list_backups,
+            restore_backup,
+            set_backup_schedule,
+            set_database_encryption,
+            change_database_passphrase,
+            list_profiles,
+            create_profile,
+            switch_profile,
+            get_app_lock_status,
+            set_app_lock,
+            unlock_app,
+            lock_app,
+            report_activity,
+            get_capture_shortcut,
+            set_capture_shortcut,
+            set_clipboard_monitor,
+            get_clipboard_monitor,
+            set_clipboard_monitor_rules,
+            set_clipping_paused,
+            is_clipping_paused,
+            set_notifications_enabled,
+            get_connectivity,
+            set_offline_mode,
+            get_settings,
+            get_recent_logs,
+            get_events_since,
+            update_settings,
+            research_topic,
+            generate_digest,
+            run_maintenance_now,
+            check_email_now,
+            get_clip_attachments,
+            list_jobs,
+            cancel_job,
+            retry_job,
+            ask_clips,
+            set_model_prices,
+            list_llm_providers,
+            set_custom_llm_providers,
+            set_ollama_url,
+            list_local_models
         ])
         .setup(|app| {
-            let app_handle = app.handle().clone();
-            // Start file watcher in a separate thread
-            std::thread::spawn(move || {
-                println!("LOS Clipper server starting (file-based communication)");
+            let profiles = ProfileManager::load(app.handle())?;
+            let profile = profiles.active();
+            let settings = SettingsManager::load(profiles.settings_path(&profile));
+            let config = AppConfig::new(profiles.data_dir(&profile, &settings.get()));
+            config.ensure_dirs()?;
+            app.manage(logging::init(&config.logs_dir())?);
+            http::configure(&settings.get().network);
+            connectivity::apply_settings(app.handle(), &settings.get());
+            app.manage(Notifier::default());
+            app.manage(RequestRegistry::default());
+            app.manage(EventJournal::default());
+            app.manage(PrivateVault::default());
+            app.manage(AppLock::new(settings.get().app_lock_idle()));
+            app.manage(SecretsManager::new(profiles.secrets_location(&profile), settings.get().secrets_backend));
+            let encrypt_database = settings.get().encrypt_database;
+            app.manage(settings);
+            let key = if encrypt_database {
+                let secrets = app.state::<SecretsManager>();
+                let key_secret = profiles.database_key_secret();
+                Some(tauri::async_runtime::block_on(secrets.get_secret(&key_secret, "open_database"))?)
+            } else {
+                None
+            };
+            app.manage(Database::open(&config.clips_db_path(), key)?);
+            let pairing = ClipperPairing::default();
+            tauri::async_runtime::block_on(pairing.load_key(&app.state::<SecretsManager>()))?;
+            app.manage(pairing);
+            app.manage(config.clone());
+            app.manage(profiles);
+            app.manage(JobQueue::start(app.handle().clone())?);
+            app.manage(SyncManager::default());
+            app.manage(VaultMirror::default());
+            backup::start_scheduler(app.handle().clone());
+            feeds::start_scheduler(app.handle().clone());
+            links::start_scheduler(app.handle().clone());
+            digest::start_scheduler(app.handle().clone());
+            maintenance::start_scheduler(app.handle().clone());
+            secrets::start_expiry_check(app.handle().clone());
+            secret_audit::start_flush(app.handle().clone());
+            email::start_scheduler(app.handle().clone());
+            trash::start_purge_scheduler(app.handle().clone());
+            reminders::start_scheduler(app.handle().clone());
+            sync::start_scheduler(app.handle().clone());
+            sync::start_push_scheduler(app.handle().clone());
+            vault::start_mirror(app.handle().clone());
+            app_lock::start_idle_check(app.handle().clone());
+            connectivity::start_monitor(app.handle().clone());
+            app.manage(ClipboardMonitor::start(app.handle().clone()));
+            // Another app may own the shortcut; capture is then just unavailable until it's changed
+            if let Err(e) = hotkey::register_from_settings(app.handle()) {
+                error!("{}", e);
+            }
 
-                let clips_dir = Path::new("/home/daniel-parker/Desktop/LOSenviorment/los-app/clips");
-                if !clips_dir.exists() {
-                    fs::create_dir_all(clips_dir).unwrap();
+            let app_handle = app.handle().clone();
+            let server = ClipperServer::start(app.handle().clone(), move |clip_data| {
+                let inserted = ingest_clip(&app_handle, clip_data)?;
+                if !inserted.merged {
+                    notifications::notify(&app_handle, NotificationKind::ClipReceived, "Clip saved", &inserted.clip.title);
                 }
+                Ok(inserted.clip.id)
+            })?;
+            app.manage(server);
 
-                loop {
-                    if let Ok(entries) = fs::read_dir(clips_dir) {
-                        for entry in entries.flatten() {
-                            if let Some(extension) = entry.path().extension() {
-                                if extension == "json" {
-                                    if let Ok(content) = fs::read_to_string(&entry.path()) {
-                                        if let Ok(clip_data) = serde_json::from_str::<ClipData>(&content) {
-                                            println!("Received clip from file: {:?}", clip_data);
-                                            // Emit event to frontend
-                                            app_handle.emit("new-clip", clip_data.clone()).unwrap();
-                                            let _ = fs::remove_file(&entry.path());
-                                        }
-                                    }
-                                }
-                            }
+            // Drop folder kept as a fallback for clippers that can't reach the server
+            let app_handle = app.handle().clone();
+            let debounce = app.state::<SettingsManager>().get().watcher_debounce();
+            let watcher = ClipWatcher::start(config.clips_dir(), debounce, move |file| {
+                let signed = match file {
+                    Ok(signed) => signed,
+                    Err(unreadable) => {
+                        warn!("Quarantined unreadable clip file: {}", unreadable.reason);
+                        let quarantined = quarantine::add_unreadable_and_notify(
+                            &app_handle,
+                            "drop_folder",
+                            &unreadable.content,
+                            &unreadable.reason,
+                        );
+                        if let Err(e) = quarantined {
+                            error!("{}", e);
+                        }
+                        return;
+                    }
+                };
+                // Files can wait in the folder a while, so only the signature is checked
+                let verified = pairing::verify(&app_handle, &signed, false)
+                    .and_then(|clip_data| sanitize::check(&clip_data).map(|_| clip_data));
+                let clip_data = match verified {
+                    Ok(clip_data) => clip_data,
+                    Err(e @ AppError::Auth { .. }) => {
+                        warn!("Rejected clip file from clipper {}: {}", signed.client_id, e);
+                        return;
+                    }
+                    // Nobody is waiting for an answer, so keep it for the user to look at
+                    Err(e) => {
+                        warn!("Quarantined clip file from clipper {}: {}", signed.client_id, e);
+                        let reason = e.to_string();
+                        if let Err(e) = quarantine::add_and_notify(&app_handle, "drop_folder", &signed, &reason) {
+                            error!("{}", e);
                         }
+                        return;
                     }
-                    std::thread::sleep(std::time::Duration::from_millis(500));
+                };
+                if let Err(e) = ingest_clip(&app_handle, clip_data) {
+                    error!("{}", e);
+                }
+            })?;
+            app.manage(watcher);
+            app.manage(Tray::build(app.handle())?);
+            windows::setup_deep_links(app.handle());
+
+            // Push live settings to running subsystems and the frontend
+            let app_handle = app.handle().clone();
+            app.state::<SettingsManager>().subscribe(move |settings| {
+                if let Some(watcher) = app_handle.try_state::<ClipWatcher>() {
+                    watcher.set_debounce(settings.watcher_debounce());
+                }
+                app_handle.state::<AppLock>().set_idle_timeout(settings.app_lock_idle());
+                http::configure(&settings.network);
+                connectivity::apply_settings(&app_handle, settings);
+                if let Err(e) = app_handle.emit("settings-changed", settings) {
+                    warn!("Failed to emit settings event: {}", e);
                 }
             });
+
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                app_handle.state::<ClipperServer>().shutdown();
+                app_handle.state::<ClipWatcher>().shutdown();
+            }
+        });
 }
\ No newline at end of file
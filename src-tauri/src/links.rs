@@ -0,0 +1,293 @@
+use reqwest::{redirect, StatusCode, Url};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{error, warn};
+
+use crate::clips::{self, now_millis, SqliteClip};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::http;
+use crate::jobs::{JobKind, JobQueue};
+use crate::redact;
+use crate::settings::SettingsManager;
+
+/// Redirects followed before giving up; shorteners rarely chain more than two or three
+const MAX_REDIRECTS: usize = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Re-check each link this often unless the `link_check_interval_days` setting says otherwise
+pub const DEFAULT_LINK_CHECK_INTERVAL_DAYS: u32 = 30;
+/// A link that keeps failing for other reasons is dead after this many checks in a row
+const DEAD_AFTER_FAILURES: u32 = 3;
+/// Links checked per `check_links` job
+const CHECK_BATCH_SIZE: u32 = 50;
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+fn client() -> Result<reqwest::Client, AppError> {
+    http::builder()?
+        .redirect(redirect::Policy::none())
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::internal(format!("Failed to build HTTP client: {}", e)))
+}
+
+/// Follow `url`'s redirects one hop at a time, at most `MAX_REDIRECTS` of them, and
+/// return where they end. Uses HEAD, falling back to GET for servers that refuse it.
+pub async fn resolve_redirects(url: &str) -> Result<String, AppError> {
+    let client = client()?;
+    let mut current = Url::parse(url).map_err(|e| AppError::validation(format!("Invalid URL: {}", e)))?;
+    for _ in 0..=MAX_REDIRECTS {
+        let mut response = client
+            .head(current.clone())
+            .send()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to fetch {}: {}", current, e)))?;
+        if matches!(response.status(), StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED) {
+            response = client
+                .get(current.clone())
+                .send()
+                .await
+                .map_err(|e| AppError::network(format!("Failed to fetch {}: {}", current, e)))?;
+        }
+        if !response.status().is_redirection() {
+            return Ok(current.to_string());
+        }
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| AppError::network(format!("{} redirected without a location", current)))?;
+        current = current
+            .join(location)
+            .map_err(|e| AppError::network(format!("Invalid redirect from {}: {}", current, e)))?;
+    }
+    Err(AppError::network(format!("More than {} redirects from {}", MAX_REDIRECTS, url)))
+}
+
+/// Replace a short link clip's URL with the canonical URL of the page it leads to.
+/// Clips that aren't short links are returned unchanged.
+pub async fn resolve_clip_url(app_handle: &AppHandle, clip_id: i64) -> Result<SqliteClip, AppError> {
+    let clip = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?
+    };
+    let Some(url) = clip.url.clone().filter(|url| clips::is_short_link(url)) else {
+        return Ok(clip);
+    };
+
+    let resolved = resolve_redirects(&url).await?;
+    let resolved = clips::strip_tracking_params(&resolved).unwrap_or(resolved);
+    if resolved == url {
+        return Ok(clip);
+    }
+    let db = app_handle.state::<Database>();
+    let clip = clips::set_resolved_url(&db.conn()?, clip_id, &resolved)?;
+    events::emit(app_handle, "clip-updated", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(clip)
+}
+
+/// The latest health check of a clip's URL
+#[derive(Debug, Serialize, Clone)]
+pub struct LinkCheck {
+    pub clip_id: i64,
+    /// HTTP status of the final response; None when the request itself failed
+    pub status: Option<u16>,
+    /// Why the request failed, for timeouts, DNS and TLS errors
+    pub error: Option<String>,
+    /// Milliseconds since the epoch
+    pub checked_at: i64,
+    /// Failed checks in a row, reset by a successful one
+    pub failures: u32,
+    pub dead: bool,
+}
+
+/// A clip whose link was found dead, for `find_broken_links`
+#[derive(Debug, Serialize)]
+pub struct BrokenLink {
+    pub clip_id: i64,
+    pub title: String,
+    pub url: String,
+    /// Whether an offline snapshot of the page exists
+    pub archived: bool,
+    pub check: LinkCheck,
+}
+
+fn link_check_from_row(row: &rusqlite::Row) -> rusqlite::Result<LinkCheck> {
+    Ok(LinkCheck {
+        clip_id: row.get(0)?,
+        status: row.get(1)?,
+        error: row.get(2)?,
+        checked_at: row.get(3)?,
+        failures: row.get(4)?,
+        dead: row.get(5)?,
+    })
+}
+
+pub fn get_link_check(conn: &Connection, clip_id: i64) -> Result<Option<LinkCheck>, AppError> {
+    conn.query_row(
+        "SELECT clip_id, status, error, checked_at, failures, dead FROM link_checks WHERE clip_id = ?1",
+        params![clip_id],
+        link_check_from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read link check: {}", e)))
+}
+
+/// Clips with dead links, most recently checked first
+pub fn find_broken_links(conn: &Connection) -> Result<Vec<BrokenLink>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT l.clip_id, l.status, l.error, l.checked_at, l.failures, l.dead, c.title, c.url, c.archive_path
+             FROM link_checks l JOIN clips c ON c.id = l.clip_id
+             WHERE l.dead = 1 AND c.deleted_at IS NULL AND c.url IS NOT NULL
+             ORDER BY l.checked_at DESC",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let links = stmt
+        .query_map([], |row| {
+            Ok(BrokenLink {
+                clip_id: row.get(0)?,
+                title: row.get(6)?,
+                url: row.get(7)?,
+                archived: row.get::<_, Option<String>>(8)?.is_some(),
+                check: link_check_from_row(row)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to list broken links: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read broken link: {}", e)))?;
+    Ok(links)
+}
+
+/// Web clips never checked, or last checked before `interval` ago, oldest first
+fn due_clip_ids(conn: &Connection, interval: Duration, limit: u32) -> Result<Vec<i64>, AppError> {
+    let cutoff = now_millis() - interval.as_millis() as i64;
+    let mut stmt = conn
+        .prepare(
+            "SELECT c.id FROM clips c LEFT JOIN link_checks l ON l.clip_id = c.id
+             WHERE c.deleted_at IS NULL AND (c.url LIKE 'http://%' OR c.url LIKE 'https://%')
+               AND (l.checked_at IS NULL OR l.checked_at <= ?1)
+             ORDER BY l.checked_at IS NOT NULL, l.checked_at, c.id
+             LIMIT ?2",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let ids = stmt
+        .query_map(params![cutoff, limit], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to list links to check: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+    Ok(ids)
+}
+
+/// Status of `url`, following redirects. Servers that refuse HEAD get a GET instead.
+async fn probe(url: &str) -> Result<StatusCode, AppError> {
+    let client = http::builder()?
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::internal(format!("Failed to build HTTP client: {}", e)))?;
+    let send = |request: reqwest::RequestBuilder| async move {
+        request.send().await.map(|response| response.status()).map_err(|e| AppError::network(e.to_string()))
+    };
+    let status = send(client.head(url)).await?;
+    if matches!(
+        status,
+        StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED | StatusCode::FORBIDDEN
+    ) {
+        return send(client.get(url)).await;
+    }
+    Ok(status)
+}
+
+/// Check a clip's URL now and record the result. A 404 or 410 marks the link dead at
+/// once; other failures only after `DEAD_AFTER_FAILURES` checks in a row.
+pub async fn check_clip_link(app_handle: &AppHandle, clip_id: i64) -> Result<LinkCheck, AppError> {
+    let (clip, previous) = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let clip = clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+        (clip, get_link_check(&conn, clip_id)?)
+    };
+    let url = clip
+        .url
+        .clone()
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .ok_or_else(|| AppError::validation(format!("Clip {} has no web link to check", clip_id)))?;
+
+    let (status, error) = match probe(&url).await {
+        Ok(status) => (Some(status), None),
+        Err(e) => (None, Some(redact::redact(&e.to_string()))),
+    };
+    let ok = status.is_some_and(|status| status.is_success());
+    let gone = status.is_some_and(|status| matches!(status, StatusCode::NOT_FOUND | StatusCode::GONE));
+    let failures = if ok { 0 } else { previous.map(|check| check.failures).unwrap_or(0) + 1 };
+    let check = LinkCheck {
+        clip_id,
+        status: status.map(|status| status.as_u16()),
+        error,
+        checked_at: now_millis(),
+        failures,
+        dead: gone || failures >= DEAD_AFTER_FAILURES,
+    };
+
+    let db = app_handle.state::<Database>();
+    db.conn()?
+        .execute(
+            "INSERT INTO link_checks (clip_id, status, error, checked_at, failures, dead)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(clip_id) DO UPDATE SET status = ?2, error = ?3, checked_at = ?4, failures = ?5, dead = ?6",
+            params![check.clip_id, check.status, check.error, check.checked_at, check.failures, check.dead],
+        )
+        .map_err(|e| AppError::database(format!("Failed to record link check: {}", e)))?;
+
+    // A live page without a snapshot may not stay live; keep a copy while we still can
+    if ok && clip.archive_path.is_none() && app_handle.state::<SettingsManager>().get().archive_checked_links {
+        if let Err(e) = app_handle.state::<JobQueue>().submit(app_handle, JobKind::ArchivePage { clip_id }) {
+            error!("Failed to queue archive of clip {}: {}", clip_id, e);
+        }
+    }
+    Ok(check)
+}
+
+/// Check each clip's link in turn. Failures are recorded per link, so the batch
+/// itself only fails on database errors.
+pub async fn check_links(app_handle: &AppHandle, clip_ids: &[i64]) -> Result<usize, AppError> {
+    let mut checked = 0;
+    for clip_id in clip_ids {
+        match check_clip_link(app_handle, *clip_id).await {
+            Ok(_) => checked += 1,
+            Err(e @ (AppError::Database { .. } | AppError::Internal { .. })) => return Err(e),
+            // Deleted clips and edited URLs are fine to skip
+            Err(e) => warn!("Skipping link check of clip {}: {}", clip_id, e),
+        }
+    }
+    Ok(checked)
+}
+
+/// Queue a batch of links that are due for a check every hour, while checks are on
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Some(interval) = app_handle.state::<SettingsManager>().get().link_check_interval() {
+                let due = {
+                    let db = app_handle.state::<Database>();
+                    db.conn().and_then(|conn| due_clip_ids(&conn, interval, CHECK_BATCH_SIZE))
+                };
+                match due {
+                    Ok(clip_ids) if !clip_ids.is_empty() => {
+                        let queue = app_handle.state::<JobQueue>();
+                        if let Err(e) = queue.submit(&app_handle, JobKind::CheckLinks { clip_ids }) {
+                            error!("Failed to queue link checks: {}", e);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("{}", e),
+                }
+            }
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,58 @@
+use serde::de::DeserializeOwned;
+use tauri::{AppHandle, Manager};
+
+use crate::db::Database;
+use crate::notifications;
+use crate::providers::{call_llm_api, LlmError, LlmMessage, LlmRequest, LlmResponse, ProviderRegistry};
+use crate::secrets::SecretsManager;
+use crate::settings::SettingsManager;
+use crate::usage;
+
+/// Run a prompt against the default model from the settings and record its usage.
+/// Used by background features that have no model picker of their own; `caller`
+/// names the feature in the secret access audit.
+pub async fn complete_with_default(
+    app_handle: &AppHandle,
+    caller: &str,
+    messages: Vec<LlmMessage>,
+    max_tokens: Option<u32>,
+) -> Result<LlmResponse, LlmError> {
+    let settings = app_handle.state::<SettingsManager>().get();
+    let selection = settings
+        .default_model
+        .clone()
+        .ok_or("No default model configured; choose one in settings")?;
+    let registry = ProviderRegistry::from_settings(&settings);
+
+    let request = LlmRequest {
+        model: selection.model.clone(),
+        messages,
+        max_tokens,
+        temperature: Some(settings.temperature()),
+        safety_settings: None,
+        images: Vec::new(),
+    };
+    let secrets_manager = app_handle.state::<SecretsManager>();
+    let response = call_llm_api(&secrets_manager, &registry, &selection.provider, request, caller)
+        .await
+        .inspect_err(|e| notifications::notify_llm_error(app_handle, &selection.provider, e))?;
+
+    if let Some(llm_usage) = &response.usage {
+        let price = usage::price_for(&selection.provider, &selection.model, &settings.model_prices);
+        let db = app_handle.state::<Database>();
+        usage::record_usage(&db.conn()?, &selection.provider, &selection.model, llm_usage, price)?;
+    }
+
+    Ok(response)
+}
+
+/// Pull the JSON object out of a reply that may wrap it in prose or code fences.
+/// `what` names the expected reply in the error, e.g. `digest`.
+pub fn extract_json<T: DeserializeOwned>(reply: &str, what: &str) -> Result<T, String> {
+    let start = reply.find('{').ok_or("No JSON object in model reply")?;
+    let end = reply.rfind('}').ok_or("No JSON object in model reply")?;
+    if end < start {
+        return Err("No JSON object in model reply".to_string());
+    }
+    serde_json::from_str(&reply[start..=end]).map_err(|e| format!("Invalid {}: {}", what, e))
+}
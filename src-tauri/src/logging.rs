@@ -0,0 +1,146 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::rolling::{Builder as RollingBuilder, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::clips::now_millis;
+use crate::errors::AppError;
+use crate::redact;
+
+/// Log entries kept in memory for the diagnostics panel
+const BUFFER_CAPACITY: usize = 2000;
+/// Daily log files kept on disk
+const MAX_LOG_FILES: usize = 7;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// Milliseconds since the epoch
+    pub timestamp: i64,
+    pub level: String,
+    /// Module that logged it, e.g. `los_app_lib::jobs`
+    pub target: String,
+    pub message: String,
+    #[serde(skip)]
+    severity: Level,
+}
+
+/// The most recent log entries, managed as Tauri state
+#[derive(Clone, Default)]
+pub struct LogBuffer {
+    entries: Arc<Mutex<VecDeque<LogEntry>>>,
+}
+
+impl LogBuffer {
+    /// Up to `limit` entries at `level` or more severe, newest first
+    pub fn recent(&self, level: Level, limit: usize) -> Vec<LogEntry> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            // tracing orders levels by verbosity: ERROR < WARN < ... < TRACE
+            .filter(|entry| entry.severity <= level)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn push(&self, entry: LogEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == BUFFER_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+}
+
+/// Install the global subscriber: stdout, daily-rotated files in `logs_dir`, and
+/// the in-memory buffer returned for `get_recent_logs`
+pub fn init(logs_dir: &Path) -> Result<LogBuffer, AppError> {
+    let file_appender = RollingBuilder::new()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("los")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(logs_dir)
+        .map_err(|e| AppError::internal(format!("Failed to open log file in {}: {}", logs_dir.display(), e)))?;
+
+    let buffer = LogBuffer::default();
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(tracing_subscriber::fmt::layer().with_writer(RedactingWriter(io::stdout)))
+        .with(tracing_subscriber::fmt::layer().with_ansi(false).with_writer(RedactingWriter(file_appender)))
+        .with(BufferLayer(buffer.clone()))
+        .try_init()
+        .map_err(|e| AppError::internal(format!("Failed to initialize logging: {}", e)))?;
+    Ok(buffer)
+}
+
+/// Wraps a log writer so secrets are scrubbed from each formatted line before it's written
+struct RedactingWriter<M>(M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingWriter<M> {
+    type Writer = Redacting<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        Redacting(self.0.make_writer())
+    }
+}
+
+struct Redacting<W>(W);
+
+impl<W: io::Write> io::Write for Redacting<W> {
+    // The fmt layer writes each event in one call, so a key is never split across writes
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let line = String::from_utf8_lossy(buf);
+        self.0.write_all(redact::redact(&line).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+struct BufferLayer(LogBuffer);
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        self.0.push(LogEntry {
+            timestamp: now_millis(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: redact::redact(&visitor.message),
+            severity: *metadata.level(),
+        });
+    }
+}
+
+/// Renders an event as its message followed by any structured `key=value` fields
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.message, " {}={:?}", field.name(), value);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tracing::{error, info};
+
+use crate::clips::now_millis;
+use crate::db::Database;
+use crate::embeddings;
+use crate::errors::AppError;
+use crate::jobs::{JobKind, JobQueue};
+use crate::providers::LlmError;
+use crate::settings::SettingsManager;
+
+/// Hours between maintenance runs when the user hasn't chosen an interval
+pub const DEFAULT_MAINTENANCE_INTERVAL_HOURS: u32 = 24;
+/// VACUUM rewrites the whole file, so it runs far less often than the rest
+const VACUUM_INTERVAL: Duration = Duration::from_secs(7 * 86_400);
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Clips re-embedded per run; the rest wait for the next one
+const MAX_REEMBED_PER_RUN: usize = 1_000;
+/// Pause between embedding batches so a large backlog doesn't hit provider rate limits
+const BATCH_PAUSE: Duration = Duration::from_secs(2);
+
+const MAINTENANCE_TASK: &str = "maintenance";
+const VACUUM_TASK: &str = "vacuum";
+
+/// What a maintenance run did
+#[derive(Debug, Serialize)]
+pub struct MaintenanceReport {
+    /// Clips whose embeddings were recomputed
+    pub reembedded: usize,
+    /// Outdated clips left for the next run
+    pub reembed_remaining: usize,
+    pub vacuumed: bool,
+}
+
+fn last_run(conn: &Connection, task: &str) -> Result<Option<i64>, AppError> {
+    conn.query_row("SELECT ran_at FROM maintenance_runs WHERE task = ?1", params![task], |row| row.get(0))
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read maintenance runs: {}", e)))
+}
+
+fn record_run(conn: &Connection, task: &str) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO maintenance_runs (task, ran_at) VALUES (?1, ?2)
+         ON CONFLICT (task) DO UPDATE SET ran_at = excluded.ran_at",
+        params![task, now_millis()],
+    )
+    .map_err(|e| AppError::database(format!("Failed to record maintenance run: {}", e)))?;
+    Ok(())
+}
+
+/// Merge the full-text indexes' segments, then VACUUM if it's been `VACUUM_INTERVAL`
+/// since the last one. Returns whether it vacuumed.
+fn optimize_database(db: &Database) -> Result<bool, AppError> {
+    let conn = db.conn()?;
+    conn.execute_batch(
+        "INSERT INTO clips_fts(clips_fts) VALUES ('optimize');
+         INSERT INTO annotations_fts(annotations_fts) VALUES ('optimize');",
+    )
+    .map_err(|e| AppError::database(format!("Failed to optimize search index: {}", e)))?;
+
+    let vacuum_due = last_run(&conn, VACUUM_TASK)?
+        .map_or(true, |ran_at| now_millis() - ran_at >= VACUUM_INTERVAL.as_millis() as i64);
+    if vacuum_due {
+        conn.execute_batch("VACUUM;")
+            .map_err(|e| AppError::database(format!("Failed to vacuum database: {}", e)))?;
+        record_run(&conn, VACUUM_TASK)?;
+    }
+    Ok(vacuum_due)
+}
+
+/// Re-embed clips whose text changed since they were embedded, in batches with a pause
+/// between them, then optimize the search indexes and VACUUM on schedule. A rate limit
+/// error ends the run; the job queue retries it later and finished batches are kept.
+pub async fn run_maintenance(app_handle: &AppHandle) -> Result<MaintenanceReport, LlmError> {
+    let mut reembedded = 0;
+    let mut reembed_remaining = 0;
+    if let Some(selection) = app_handle.state::<SettingsManager>().get().embedding_model {
+        let outdated = {
+            let db = app_handle.state::<Database>();
+            embeddings::outdated_clip_ids(&db.conn()?, &selection.model)?
+        };
+        let batch = &outdated[..outdated.len().min(MAX_REEMBED_PER_RUN)];
+        reembed_remaining = outdated.len() - batch.len();
+        for (i, chunk) in batch.chunks(embeddings::BATCH_SIZE).enumerate() {
+            if i > 0 {
+                tokio::time::sleep(BATCH_PAUSE).await;
+            }
+            reembedded += embeddings::index_clips(app_handle, chunk).await?;
+        }
+    }
+
+    let handle = app_handle.clone();
+    let vacuumed = tauri::async_runtime::spawn_blocking(move || {
+        let db = handle.state::<Database>();
+        let vacuumed = optimize_database(&db)?;
+        record_run(&db.conn()?, MAINTENANCE_TASK)?;
+        Ok::<_, AppError>(vacuumed)
+    })
+    .await
+    .map_err(|e| AppError::internal(format!("Maintenance failed: {}", e)))??;
+
+    info!(
+        "Maintenance re-embedded {} clips ({} left){}",
+        reembedded,
+        reembed_remaining,
+        if vacuumed { " and vacuumed the database" } else { "" }
+    );
+    Ok(MaintenanceReport {
+        reembedded,
+        reembed_remaining,
+        vacuumed,
+    })
+}
+
+/// Queue a maintenance job whenever the last run is older than
+/// `maintenance_interval_hours`. A run that keeps failing is queued once per interval.
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut queued_at: Option<Instant> = None;
+        loop {
+            if let Some(interval) = app_handle.state::<SettingsManager>().get().maintenance_interval() {
+                let due = {
+                    let db = app_handle.state::<Database>();
+                    db.conn().and_then(|conn| last_run(&conn, MAINTENANCE_TASK))
+                }
+                .map(|ran_at| ran_at.map_or(true, |ran_at| now_millis() - ran_at >= interval.as_millis() as i64));
+                match due {
+                    Ok(true) if queued_at.map_or(true, |at| at.elapsed() >= interval) => {
+                        let queue = app_handle.state::<JobQueue>();
+                        match queue.submit(&app_handle, JobKind::IndexMaintenance) {
+                            Ok(_) => queued_at = Some(Instant::now()),
+                            Err(e) => error!("Failed to queue maintenance: {}", e),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("{}", e),
+                }
+            }
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+        }
+    });
+}
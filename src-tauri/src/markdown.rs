@@ -0,0 +1,431 @@
+use rusqlite::Connection;
+use scraper::{ElementRef, Html, Node};
+
+use crate::clips::{self, ClipUpdate, SqliteClip};
+use crate::errors::AppError;
+use crate::sanitize::{contains_html, is_text_type};
+
+/// Elements whose text isn't part of the content
+const SKIPPED: &[&str] = &["script", "style", "noscript", "template", "head", "button", "form", "svg"];
+/// Link schemes kept in Markdown; links with any other scheme keep only their text
+const LINK_SCHEMES: &[&str] = &["http", "https", "mailto"];
+/// Elements that only group other blocks
+const CONTAINERS: &[&str] = &[
+    "p", "div", "section", "article", "main", "header", "footer", "aside", "nav", "figure", "figcaption", "details",
+    "summary", "dl", "dd", "dt", "address",
+];
+
+/// Markdown for `content` if it contains HTML; None when it's already text or Markdown
+pub fn to_markdown(content: &str) -> Option<String> {
+    contains_html(content).then(|| html_to_markdown(content))
+}
+
+/// Convert an HTML fragment to Markdown, keeping headings, emphasis, links, images,
+/// lists, quotes, tables and code blocks. Anything else becomes plain paragraphs, and
+/// links other than http, https and mailto keep only their text.
+pub fn html_to_markdown(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    tidy(&children(fragment.root_element()))
+}
+
+/// Rewrite an existing clip's HTML content as Markdown. The HTML is kept as a revision.
+pub fn convert_clip(conn: &Connection, id: i64) -> Result<SqliteClip, AppError> {
+    let clip = clips::get_clip(conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))?;
+    if clip.private {
+        return Err(AppError::validation(format!("Clip {} is private; make it a normal clip to convert it", id)));
+    }
+    if is_text_type(&clip.r#type) {
+        let message = format!("Clip {} is a {} clip; its content is kept as written", id, clip.r#type);
+        return Err(AppError::validation(message));
+    }
+    let Some(markdown) = clip.content.as_deref().and_then(to_markdown) else {
+        return Ok(clip);
+    };
+    let changes = ClipUpdate {
+        content: Some(Some(markdown)),
+        expected_updated_at: Some(clip.updated_at),
+        ..Default::default()
+    };
+    clips::update_clip(conn, id, changes)
+}
+
+fn children(element: ElementRef) -> String {
+    let mut out = String::new();
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => {
+                let text = collapse_whitespace(text);
+                // Leading spaces would read as indentation
+                if out.is_empty() || out.ends_with('\n') {
+                    out.push_str(&escape(text.trim_start(), true));
+                } else {
+                    out.push_str(&escape(&text, false));
+                }
+            }
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    out.push_str(&convert(child));
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn convert(element: ElementRef) -> String {
+    let name = element.value().name();
+    match name {
+        _ if SKIPPED.contains(&name) => String::new(),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse().unwrap_or(1);
+            let text = single_line(&children(element));
+            if text.is_empty() {
+                String::new()
+            } else {
+                block(&format!("{} {}", "#".repeat(level), text))
+            }
+        }
+        "br" => "\n".to_string(),
+        "hr" => block("---"),
+        "strong" | "b" => emphasis(&children(element), "**"),
+        "em" | "i" => emphasis(&children(element), "*"),
+        "del" | "s" | "strike" => emphasis(&children(element), "~~"),
+        "code" => inline_code(&element.text().collect::<String>()),
+        "pre" => code_block(element),
+        "a" => link(element),
+        "img" => image(element),
+        "ul" => list(element, None),
+        "ol" => list(element, Some(element.value().attr("start").and_then(|s| s.parse().ok()).unwrap_or(1))),
+        "blockquote" => {
+            let quoted = tidy(&children(element));
+            let lines: Vec<String> = quoted
+                .lines()
+                .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                .collect();
+            block(&lines.join("\n"))
+        }
+        "table" => table(element),
+        _ if CONTAINERS.contains(&name) => block(&children(element)),
+        _ => children(element),
+    }
+}
+
+fn block(markdown: &str) -> String {
+    let markdown = markdown.trim_matches('\n').trim_end();
+    if markdown.trim().is_empty() {
+        String::new()
+    } else {
+        format!("\n\n{}\n\n", markdown)
+    }
+}
+
+/// Markers go around the text itself, so `<b> bold</b>` keeps its space outside them
+fn emphasis(text: &str, marker: &str) -> String {
+    let inner = text.trim();
+    if inner.is_empty() {
+        return text.to_string();
+    }
+    let leading = if text.starts_with(char::is_whitespace) { " " } else { "" };
+    let trailing = if text.ends_with(char::is_whitespace) { " " } else { "" };
+    format!("{}{}{}{}{}", leading, marker, inner, marker, trailing)
+}
+
+fn inline_code(code: &str) -> String {
+    if code.is_empty() {
+        String::new()
+    } else if code.contains('`') {
+        format!("`` {} ``", code)
+    } else {
+        format!("`{}`", code)
+    }
+}
+
+/// Fenced, with the language from a `language-*` or `lang-*` class on the `pre` or its `code`
+fn code_block(element: ElementRef) -> String {
+    let code = element.text().collect::<String>();
+    let language = std::iter::once(element)
+        .chain(element.children().filter_map(ElementRef::wrap).filter(|c| c.value().name() == "code"))
+        .flat_map(|e| e.value().classes())
+        .find_map(|class| class.strip_prefix("language-").or_else(|| class.strip_prefix("lang-")))
+        .unwrap_or_default();
+    let fence = if code.contains("```") { "~~~~" } else { "```" };
+    format!("\n\n{}{}\n{}\n{}\n\n", fence, language, code.trim_matches('\n'), fence)
+}
+
+fn link(element: ElementRef) -> String {
+    let text = single_line(&children(element));
+    match element.value().attr("href").map(str::trim) {
+        Some(href) if !href.is_empty() && !href.starts_with('#') && allowed_scheme(href, LINK_SCHEMES) => {
+            let text = if text.is_empty() { escape(href, false) } else { text };
+            format!("[{}]({})", text, destination(href))
+        }
+        _ => text,
+    }
+}
+
+fn image(element: ElementRef) -> String {
+    match element.value().attr("src").map(str::trim) {
+        Some(src) if !src.is_empty() && (allowed_scheme(src, &["http", "https"]) || is_data_image(src)) => {
+            let alt = escape(&single_line(element.value().attr("alt").unwrap_or_default()), false);
+            format!("![{}]({})", alt, destination(src))
+        }
+        _ => String::new(),
+    }
+}
+
+/// Link destinations with spaces or parentheses need angle brackets, so any in the
+/// URL itself are percent-encoded
+fn destination(url: &str) -> String {
+    let url = url.replace('<', "%3C").replace('>', "%3E");
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url)
+    } else {
+        url
+    }
+}
+
+/// Relative URLs have no scheme and are kept; browsers ignore whitespace and control
+/// characters in a scheme, so `java\tscript:` counts as `javascript:`
+fn allowed_scheme(url: &str, schemes: &[&str]) -> bool {
+    let Some((scheme, _)) = url.split_once(':') else {
+        return true;
+    };
+    if scheme.contains(['/', '?', '#']) {
+        return true;
+    }
+    let scheme: String = scheme.chars().filter(|c| !c.is_whitespace() && !c.is_control()).collect();
+    schemes.iter().any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+}
+
+fn is_data_image(url: &str) -> bool {
+    url.get(..11).is_some_and(|prefix| prefix.eq_ignore_ascii_case("data:image/"))
+}
+
+/// Text as it reads, not as markup. Text nodes arrive entity-decoded, so `&lt;img&gt;`
+/// in the page would otherwise become a live tag. Characters are only escaped where
+/// they would start markup, so `snake_case`, `C#` and `A & B` come through as written.
+/// `line_start` is set for text that may begin a line, where `#`, `>` and list markers
+/// count too.
+fn escape(text: &str, line_start: bool) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let marker = if line_start { block_marker(&chars) } else { None };
+    let spaced = |c: Option<char>| c.map_or(true, char::is_whitespace);
+    let mut out = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1).copied();
+        let escaped = match c {
+            _ if marker == Some(i) => true,
+            // A tag, comment or autolink
+            '<' if next.is_some_and(|n| n.is_ascii_alphabetic() || matches!(n, '/' | '!' | '?')) => {
+                out.push_str("&lt;");
+                continue;
+            }
+            '&' if is_entity(&chars[i + 1..]) => {
+                out.push_str("&amp;");
+                continue;
+            }
+            '\\' => next.is_some_and(|n| n.is_ascii_punctuation()),
+            '`' | '[' => true,
+            // Emphasis and strikethrough need a non-space on one side
+            '*' | '~' => !(spaced(prev) && spaced(next)),
+            // Inside a word, as in snake_case, `_` is never emphasis
+            '_' => !(prev.is_some_and(char::is_alphanumeric) && next.is_some_and(char::is_alphanumeric)),
+            _ => false,
+        };
+        if escaped {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Index of the character that would make a line starting with `chars` a heading,
+/// quote, list item or rule
+fn block_marker(chars: &[char]) -> Option<usize> {
+    let first = *chars.first()?;
+    let space_at = |i: usize| chars.get(i).map_or(true, |c| c.is_whitespace());
+    let rule = || chars.iter().all(|&c| c == first || c == ' ');
+    match first {
+        '>' => Some(0),
+        '#' => {
+            let hashes = chars.iter().take_while(|&&c| c == '#').count();
+            (hashes <= 6 && space_at(hashes)).then_some(0)
+        }
+        '-' | '+' | '*' if space_at(1) || rule() => Some(0),
+        '_' | '=' if rule() => Some(0),
+        '0'..='9' => {
+            let digits = chars.iter().take_while(|c| c.is_ascii_digit()).count();
+            (digits <= 9 && matches!(chars.get(digits), Some('.' | ')')) && space_at(digits + 1)).then_some(digits)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the text after a `&` would be read as a character reference, like `amp;` or `#39;`
+fn is_entity(rest: &[char]) -> bool {
+    let name = rest.strip_prefix(['#'].as_slice()).unwrap_or(rest);
+    let len = name.iter().take_while(|c| c.is_ascii_alphanumeric()).count();
+    len > 0 && name.get(len) == Some(&';')
+}
+
+/// Items are kept tight; nested lists and blocks are indented under their item
+fn list(element: ElementRef, start: Option<u32>) -> String {
+    let items: Vec<String> = element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(|child| child.value().name() == "li")
+        .enumerate()
+        .map(|(i, item)| {
+            let marker = match start {
+                Some(start) => format!("{}. ", start + i as u32),
+                None => "- ".to_string(),
+            };
+            let body = tidy(&children(item));
+            let indent = " ".repeat(marker.len());
+            let mut lines = body.lines().filter(|line| !line.is_empty());
+            let mut markdown = format!("{}{}", marker, lines.next().unwrap_or_default());
+            for line in lines {
+                markdown.push('\n');
+                markdown.push_str(&indent);
+                markdown.push_str(line);
+            }
+            markdown
+        })
+        .collect();
+    block(&items.join("\n"))
+}
+
+/// A pipe table with the first row as its header
+fn table(element: ElementRef) -> String {
+    let rows: Vec<Vec<String>> = element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|e| e.value().name() == "tr")
+        .map(|row| {
+            row.children()
+                .filter_map(ElementRef::wrap)
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                .map(|cell| single_line(&children(cell)).replace('|', "\\|"))
+                .collect()
+        })
+        .filter(|cells: &Vec<String>| !cells.is_empty())
+        .collect();
+    let Some(columns) = rows.iter().map(Vec::len).max() else {
+        return String::new();
+    };
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    for (i, mut cells) in rows.into_iter().enumerate() {
+        cells.resize(columns, String::new());
+        lines.push(format!("| {} |", cells.join(" | ")));
+        if i == 0 {
+            lines.push(format!("|{}", " --- |".repeat(columns)));
+        }
+    }
+    block(&lines.join("\n"))
+}
+
+/// Runs of whitespace become one space, keeping whether the text started or ended with any
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+        } else {
+            if space {
+                out.push(' ');
+            }
+            space = false;
+            out.push(c);
+        }
+    }
+    if space {
+        out.push(' ');
+    }
+    out
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Trim line ends and collapse blank lines, leaving fenced code as it is
+fn tidy(markdown: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut fence: Option<&str> = None;
+    for line in markdown.lines() {
+        let marker = ["```", "~~~~"].into_iter().find(|m| line.trim_start().starts_with(m));
+        match (fence, marker) {
+            (Some(open), Some(close)) if open == close && line.trim() == close => fence = None,
+            (Some(_), _) => {
+                lines.push(line);
+                continue;
+            }
+            (None, Some(open)) => fence = Some(open),
+            (None, None) => {}
+        }
+        let line = line.trim_end();
+        if line.is_empty() && matches!(lines.last(), None | Some(&"")) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prose_comes_through_as_written() {
+        for (html, markdown) in [
+            ("<p>snake_case</p>", "snake_case"),
+            ("<p>C# and F#</p>", "C# and F#"),
+            ("<p>A &amp; B</p>", "A & B"),
+            ("<p>Hi!</p>", "Hi!"),
+            ("<p>2 * 3 = 6, 1 &lt; 2</p>", "2 * 3 = 6, 1 < 2"),
+            ("<p>#hashtag and 2024.</p>", "#hashtag and 2024."),
+            ("<p>It's 5 o'clock - time to go</p>", "It's 5 o'clock - time to go"),
+        ] {
+            assert_eq!(html_to_markdown(html), markdown, "for {}", html);
+        }
+    }
+
+    #[test]
+    fn markup_in_text_stays_text() {
+        for (html, markdown) in [
+            ("<p>&lt;img src=x onerror=alert(1)&gt;</p>", "&lt;img src=x onerror=alert(1)>"),
+            ("<p>&amp;lt;b&amp;gt;</p>", "&amp;lt;b&amp;gt;"),
+            ("<p># Not a heading</p>", "\\# Not a heading"),
+            ("<p>&gt; Not a quote</p>", "\\> Not a quote"),
+            ("<p>1. Not a list</p>", "1\\. Not a list"),
+            ("<p>*not emphasis* and _not_ either</p>", "\\*not emphasis\\* and \\_not\\_ either"),
+            ("<p>[not](javascript:alert(1)) a link</p>", "\\[not](javascript:alert(1)) a link"),
+            ("<p>`not code`</p>", "\\`not code\\`"),
+        ] {
+            assert_eq!(html_to_markdown(html), markdown, "for {}", html);
+        }
+    }
+
+    #[test]
+    fn unsafe_links_keep_only_their_text() {
+        for (html, markdown) in [
+            ("<a href=\"https://example.com\">site</a>", "[site](https://example.com)"),
+            ("<a href=\"mailto:a@example.com\">mail</a>", "[mail](mailto:a@example.com)"),
+            ("<a href=\"javascript:alert(1)\">click</a>", "click"),
+            ("<a href=\"java&#9;script:alert(1)\">click</a>", "click"),
+            ("<a href=\"data:text/html,hi\">click</a>", "click"),
+            ("<img src=\"javascript:alert(1)\" alt=\"x\">", ""),
+        ] {
+            assert_eq!(html_to_markdown(html), markdown, "for {}", html);
+        }
+    }
+}
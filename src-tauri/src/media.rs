@@ -0,0 +1,139 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::clips;
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::http;
+
+/// Refuse to store anything larger than this
+const MAX_IMAGE_BYTES: usize = 25 * 1024 * 1024;
+
+/// A clip's image as stored on disk
+#[derive(Debug, Serialize)]
+pub struct ClipImage {
+    pub clip_id: i64,
+    /// Absolute path of the local copy, for `convertFileSrc` in the UI
+    pub path: PathBuf,
+    /// Hex SHA-256 of the image bytes
+    pub hash: String,
+}
+
+/// File extension for an image MIME type, falling back to the URL's extension
+pub(crate) fn extension_for(content_type: Option<&str>, url: &reqwest::Url) -> String {
+    let from_mime = content_type.and_then(|mime| match mime.split(';').next().unwrap_or("").trim() {
+        "image/jpeg" => Some("jpg"),
+        "image/png" => Some("png"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/svg+xml" => Some("svg"),
+        "image/avif" => Some("avif"),
+        "image/bmp" => Some("bmp"),
+        "image/x-icon" | "image/vnd.microsoft.icon" => Some("ico"),
+        _ => None,
+    });
+    let from_url = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_lowercase())
+        .filter(|ext| ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric()));
+    from_mime
+        .map(str::to_string)
+        .or(from_url)
+        .unwrap_or_else(|| "img".to_string())
+}
+
+/// Download `url` into `media_dir`. Files are named by content hash, so the same image
+/// clipped twice is stored once. Returns the file name and hash.
+async fn download_image(url: &str, media_dir: &Path) -> Result<(String, String), AppError> {
+    let url = reqwest::Url::parse(url).map_err(|e| AppError::validation(format!("Invalid image URL: {}", e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::validation(format!("Unsupported image URL scheme '{}'", url.scheme())));
+    }
+
+    let response = http::client()
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to download image: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("Failed to download image: HTTP {}", response.status())));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    if let Some(mime) = &content_type {
+        if !mime.starts_with("image/") {
+            return Err(AppError::validation(format!("URL did not return an image ({})", mime)));
+        }
+    }
+    if response.content_length().map(|len| len as usize > MAX_IMAGE_BYTES).unwrap_or(false) {
+        return Err(AppError::validation("Image is too large to store"));
+    }
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to download image: {}", e)))?;
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(AppError::validation("Image is too large to store"));
+    }
+
+    let hash: String = Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    let file_name = format!("{}.{}", hash, extension_for(content_type.as_deref(), &url));
+    let path = media_dir.join(&file_name);
+    if !path.exists() {
+        // Write to a temp file and rename so a crash never leaves a truncated image behind
+        let tmp = media_dir.join(format!(".{}.part", file_name));
+        let mut file = fs::File::create(&tmp).map_err(|e| AppError::internal(format!("Failed to write image: {}", e)))?;
+        file.write_all(&bytes).map_err(|e| AppError::internal(format!("Failed to write image: {}", e)))?;
+        fs::rename(&tmp, &path).map_err(|e| AppError::internal(format!("Failed to write image: {}", e)))?;
+    }
+    Ok((file_name, hash))
+}
+
+/// Download the clip's `image_url` into the media dir and record it on the clip
+pub async fn store_clip_image(app_handle: &AppHandle, clip_id: i64) -> Result<ClipImage, AppError> {
+    let clip = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?
+    };
+    let image_url = clip.image_url.ok_or_else(|| AppError::validation(format!("Clip {} has no image", clip_id)))?;
+
+    let media_dir = app_handle.state::<AppConfig>().media_dir();
+    let (file_name, hash) = download_image(&image_url, &media_dir).await?;
+
+    let db = app_handle.state::<Database>();
+    clips::set_image_file(&db.conn()?, clip_id, Some((file_name.as_str(), hash.as_str())))?;
+    Ok(ClipImage {
+        clip_id,
+        path: media_dir.join(file_name),
+        hash,
+    })
+}
+
+/// The clip's local image, downloading it again if it was never stored or has gone missing
+pub async fn get_clip_image(app_handle: &AppHandle, clip_id: i64) -> Result<ClipImage, AppError> {
+    let clip = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?
+    };
+
+    if let (Some(file_name), Some(hash)) = (clip.image_path, clip.image_hash) {
+        let path = app_handle.state::<AppConfig>().media_dir().join(&file_name);
+        if path.is_file() {
+            return Ok(ClipImage { clip_id, path, hash });
+        }
+    }
+    store_clip_image(app_handle, clip_id).await
+}
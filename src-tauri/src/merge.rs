@@ -0,0 +1,128 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::clips::{self, now_millis, SqliteClip};
+use crate::errors::AppError;
+use crate::revisions;
+
+/// Result of `merge_clips`, also the payload of the `clips-merged` event
+#[derive(Debug, Serialize)]
+pub struct ClipMerge {
+    /// The primary clip with everything merged in
+    pub clip: SqliteClip,
+    /// Duplicates now in the trash, redirecting to `clip`
+    pub merged_ids: Vec<i64>,
+}
+
+fn live_clip(conn: &Connection, id: i64) -> Result<SqliteClip, AppError> {
+    clips::get_clip(conn, id)?
+        .filter(|clip| clip.deleted_at.is_none())
+        .ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))
+}
+
+/// Fold `duplicate_ids` into `primary_id` in one transaction. The primary gains their
+/// tags, annotations and attachments, and each duplicate's title and content is kept
+/// as a revision of it; empty fields of the primary are filled from the duplicates.
+/// The duplicates go to the trash with a redirect, so links to them open the primary.
+/// Private clips can't take part, since their text would end up in plaintext columns.
+pub fn merge_clips(conn: &mut Connection, primary_id: i64, duplicate_ids: &[i64]) -> Result<ClipMerge, AppError> {
+    let mut seen = HashSet::new();
+    let duplicate_ids: Vec<i64> = duplicate_ids.iter().copied().filter(|id| seen.insert(*id)).collect();
+    if duplicate_ids.is_empty() {
+        return Err(AppError::validation("Choose at least one clip to merge"));
+    }
+    if duplicate_ids.contains(&primary_id) {
+        return Err(AppError::validation("A clip can't be merged into itself"));
+    }
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+    let primary = live_clip(&tx, primary_id)?;
+    let duplicates = duplicate_ids.iter().map(|&id| live_clip(&tx, id)).collect::<Result<Vec<_>, _>>()?;
+    if let Some(clip) = std::iter::once(&primary).chain(&duplicates).find(|clip| clip.private) {
+        return Err(AppError::validation(format!("Clip {} is private; make it a normal clip to merge it", clip.id)));
+    }
+    let now = now_millis();
+
+    for duplicate in &duplicates {
+        let duplicate_id = duplicate.id;
+
+        // A user tag on either side stays a user tag
+        tx.execute(
+            "INSERT INTO clip_tags (clip_id, tag_id, source)
+             SELECT ?1, tag_id, source FROM clip_tags WHERE clip_id = ?2
+             ON CONFLICT (clip_id, tag_id) DO UPDATE SET source = 'user' WHERE excluded.source = 'user'",
+            params![primary_id, duplicate_id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to merge tags: {}", e)))?;
+        tx.execute(
+            "UPDATE annotations SET clip_id = ?1 WHERE clip_id = ?2",
+            params![primary_id, duplicate_id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to merge annotations: {}", e)))?;
+        // Files the primary already has stay with the tombstone
+        tx.execute(
+            "UPDATE OR IGNORE clip_attachments SET clip_id = ?1 WHERE clip_id = ?2",
+            params![primary_id, duplicate_id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to merge attachments: {}", e)))?;
+
+        if duplicate.title != primary.title || duplicate.content != primary.content {
+            revisions::record_for(&tx, primary_id, duplicate)?;
+        }
+        tx.execute(
+            "UPDATE clips SET description = COALESCE(description, ?2), author = COALESCE(author, ?3),
+                summary = COALESCE(summary, ?4), collection_id = COALESCE(collection_id, ?5),
+                times_clipped = times_clipped + ?6
+             WHERE id = ?1",
+            params![
+                primary_id,
+                &duplicate.description,
+                &duplicate.author,
+                &duplicate.summary,
+                duplicate.collection_id,
+                duplicate.times_clipped,
+            ],
+        )
+        .map_err(|e| AppError::database(format!("Failed to merge clip: {}", e)))?;
+
+        tx.execute("UPDATE clips SET deleted_at = ?1 WHERE id = ?2", params![now, duplicate_id])
+            .map_err(|e| AppError::database(format!("Failed to tombstone clip: {}", e)))?;
+        // Links that led to the duplicate, directly or through an earlier merge, now lead here
+        tx.execute(
+            "UPDATE clip_redirects SET to_id = ?1 WHERE to_id = ?2",
+            params![primary_id, duplicate_id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to update redirects: {}", e)))?;
+        tx.execute(
+            "INSERT OR REPLACE INTO clip_redirects (from_id, to_id, merged_at) VALUES (?1, ?2, ?3)",
+            params![duplicate_id, primary_id, now],
+        )
+        .map_err(|e| AppError::database(format!("Failed to record redirect: {}", e)))?;
+    }
+
+    // Keep the concurrency token strictly increasing, as `update_clip` does
+    tx.execute(
+        "UPDATE clips SET updated_at = ?1 WHERE id = ?2",
+        params![now.max(primary.updated_at + 1), primary_id],
+    )
+    .map_err(|e| AppError::database(format!("Failed to merge clip: {}", e)))?;
+    let clip = live_clip(&tx, primary_id)?;
+    tx.commit()
+        .map_err(|e| AppError::database(format!("Failed to commit merge: {}", e)))?;
+    Ok(ClipMerge {
+        clip,
+        merged_ids: duplicate_ids,
+    })
+}
+
+/// The clip `id` was merged into, or `id` itself if it wasn't
+pub fn resolve_redirect(conn: &Connection, id: i64) -> Result<i64, AppError> {
+    let target = conn
+        .query_row("SELECT to_id FROM clip_redirects WHERE from_id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read redirect: {}", e)))?;
+    Ok(target.unwrap_or(id))
+}
@@ -0,0 +1,983 @@
+use rusqlite::Connection;
+use tracing::info;
+
+use crate::clips;
+use crate::errors::AppError;
+
+type Migration = fn(&Connection) -> Result<(), AppError>;
+
+/// Schema migrations in the order they were introduced. `PRAGMA user_version`
+/// records how many have been applied, so only append to this list.
+pub const MIGRATIONS: &[(&str, Migration)] = &[
+    ("create clips table", create_clips),
+    ("add clips.updated_at", add_updated_at),
+    ("add clips.domain", add_domain),
+    ("create clips_fts search index", create_fts),
+    ("create tags tables", create_tags),
+    ("create collections", create_collections),
+    ("create conversations and messages", create_conversations),
+    ("create usage table", create_usage),
+    ("add clips.summary", add_summary),
+    ("add auto-tagging columns", add_auto_tagging),
+    ("create clip_embeddings table", create_embeddings),
+    ("create jobs table", create_jobs),
+    ("add local image columns", add_image_file),
+    ("add page archive columns", add_archive),
+    ("add duplicate detection columns", add_duplicate_detection),
+    ("create search_cache table", create_search_cache),
+    ("create clip_documents table", create_documents),
+    ("create clip_ocr table", create_ocr),
+    ("create feeds tables", create_feeds),
+    ("add reading state columns", add_reading_state),
+    ("create annotations table and search index", create_annotations),
+    ("create clip_revisions table", create_revisions),
+    ("add clips.deleted_at", add_deleted_at),
+    ("create sync tables", create_sync),
+    ("create vault mirror tables", create_vault),
+    ("create notion_pages table", create_notion_pages),
+    ("create flashcards table", create_flashcards),
+    ("create prompts table", create_prompts),
+    ("add conversation model settings", add_conversation_settings),
+    ("create clip_audio table", create_clip_audio),
+    ("create rules table", create_rules),
+    ("add clip original_url", add_original_url),
+    ("create link_checks table", create_link_checks),
+    ("add clip site metadata", add_site_metadata),
+    ("create clip_attachments table", create_clip_attachments),
+    ("create secret_access_log table", create_secret_access_log),
+    ("create clip_redirects table", create_clip_redirects),
+    ("create smart_collections table", create_smart_collections),
+    ("create sources table", create_sources),
+    ("add embedding text hashes and maintenance_runs table", create_maintenance),
+    ("create libsql_pending table", create_libsql_pending),
+    ("add private clips and private_vault table", create_private_clips),
+    ("create paired_clippers table", create_paired_clippers),
+    ("create quarantined_clips table", create_quarantined_clips),
+    ("add language column to clips", add_clip_language),
+    ("create clip_statuses and clip status columns", create_clip_statuses),
+    ("create reminders table", create_reminders),
+    ("clear content hashes of private clips", clear_private_content_hashes),
+];
+
+/// Apply every migration newer than the database's `user_version`, each in its own transaction
+pub fn run(conn: &mut Connection) -> Result<(), AppError> {
+    let current: usize = conn
+        .pragma_query_value(None, "user_version", |row| row.get::<_, i64>(0))
+        .map_err(|e| AppError::database(format!("Failed to read schema version: {}", e)))? as usize;
+
+    for (index, (name, migrate)) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::database(format!("Failed to start migration {}: {}", version, e)))?;
+        migrate(&tx).map_err(|e| AppError::database(format!("Migration {} ({}) failed: {}", version, name, e)))?;
+        tx.pragma_update(None, "user_version", version as i64)
+            .map_err(|e| AppError::database(format!("Failed to record migration {}: {}", version, e)))?;
+        tx.commit()
+            .map_err(|e| AppError::database(format!("Failed to commit migration {}: {}", version, e)))?;
+        info!("Applied database migration {}: {}", version, name);
+    }
+    Ok(())
+}
+
+/// Matches the table the old Node clip processor created, so existing clips.db files carry over
+fn create_clips(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS clips (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            type TEXT NOT NULL,
+            title TEXT NOT NULL,
+            url TEXT,
+            content TEXT,
+            image_url TEXT,
+            description TEXT,
+            author TEXT,
+            timestamp INTEGER NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE INDEX IF NOT EXISTS idx_clips_timestamp ON clips(timestamp DESC, id DESC);",
+    )
+    .map_err(AppError::from)
+}
+
+fn add_updated_at(conn: &Connection) -> Result<(), AppError> {
+    if add_column_if_missing(conn, "clips", "updated_at", "INTEGER")? {
+        conn.execute(
+            "UPDATE clips SET updated_at = CAST(strftime('%s', created_at) AS INTEGER) * 1000",
+            [],
+        )
+        .map_err(|e| AppError::database(format!("Failed to backfill updated_at: {}", e)))?;
+    }
+    Ok(())
+}
+
+fn add_domain(conn: &Connection) -> Result<(), AppError> {
+    if add_column_if_missing(conn, "clips", "domain", "TEXT")? {
+        backfill_domains(conn)?;
+    }
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_clips_domain ON clips(domain)", [])
+        .map_err(|e| AppError::database(format!("Failed to create domain index: {}", e)))?;
+    Ok(())
+}
+
+fn create_tags(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE clip_tags (
+            clip_id INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (clip_id, tag_id)
+        );
+        CREATE INDEX idx_clip_tags_tag ON clip_tags(tag_id);",
+    )
+    .map_err(AppError::from)
+}
+
+fn create_collections(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE collections (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            parent_id INTEGER REFERENCES collections(id) ON DELETE CASCADE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE INDEX idx_collections_parent ON collections(parent_id);
+        ALTER TABLE clips ADD COLUMN collection_id INTEGER REFERENCES collections(id) ON DELETE SET NULL;
+        CREATE INDEX idx_clips_collection ON clips(collection_id);",
+    )
+    .map_err(AppError::from)
+}
+
+fn create_conversations(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE conversations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            provider TEXT,
+            model TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE INDEX idx_conversations_updated ON conversations(updated_at DESC);
+        CREATE TABLE messages (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            conversation_id INTEGER NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+            role TEXT NOT NULL,
+            content TEXT NOT NULL,
+            input_tokens INTEGER,
+            output_tokens INTEGER,
+            created_at INTEGER NOT NULL
+        );
+        CREATE INDEX idx_messages_conversation ON messages(conversation_id, id);",
+    )
+    .map_err(AppError::from)
+}
+
+fn create_usage(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE usage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            input_tokens INTEGER NOT NULL,
+            output_tokens INTEGER NOT NULL,
+            cost REAL,
+            created_at INTEGER NOT NULL
+        );
+        CREATE INDEX idx_usage_created ON usage(created_at);",
+    )
+    .map_err(AppError::from)
+}
+
+fn add_summary(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "summary", "TEXT")?;
+    Ok(())
+}
+
+fn add_auto_tagging(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clip_tags", "source", "TEXT NOT NULL DEFAULT 'user'")?;
+    add_column_if_missing(conn, "clips", "category", "TEXT")?;
+    add_column_if_missing(conn, "clips", "category_source", "TEXT")?;
+    Ok(())
+}
+
+/// Vectors are little-endian f32, normalized to unit length
+fn create_embeddings(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE clip_embeddings (
+            clip_id INTEGER PRIMARY KEY REFERENCES clips(id) ON DELETE CASCADE,
+            model TEXT NOT NULL,
+            dimensions INTEGER NOT NULL,
+            vector BLOB NOT NULL,
+            clip_updated_at INTEGER NOT NULL
+        );
+        CREATE INDEX idx_clip_embeddings_model ON clip_embeddings(model);",
+    )
+    .map_err(AppError::from)
+}
+
+fn create_jobs(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            max_attempts INTEGER NOT NULL,
+            last_error TEXT,
+            run_after INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE INDEX idx_jobs_status ON jobs(status, run_after);",
+    )
+    .map_err(AppError::from)
+}
+
+fn add_image_file(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "image_path", "TEXT")?;
+    add_column_if_missing(conn, "clips", "image_hash", "TEXT")?;
+    Ok(())
+}
+
+fn add_archive(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "archive_path", "TEXT")?;
+    add_column_if_missing(conn, "clips", "archived_at", "INTEGER")?;
+    Ok(())
+}
+
+/// Add `normalized_url`, `content_hash` and `times_clipped` to `clips` so repeat
+/// clips of the same page can be found and merged
+fn add_duplicate_detection(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "normalized_url", "TEXT")?;
+    add_column_if_missing(conn, "clips", "content_hash", "TEXT")?;
+    add_column_if_missing(conn, "clips", "times_clipped", "INTEGER NOT NULL DEFAULT 1")?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_clips_normalized_url ON clips(normalized_url);
+         CREATE INDEX IF NOT EXISTS idx_clips_content_hash ON clips(content_hash);",
+    )
+    .map_err(|e| AppError::database(format!("Failed to create duplicate indexes: {}", e)))?;
+    backfill_duplicate_keys(conn)
+}
+
+fn create_search_cache(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE search_cache (
+            provider TEXT NOT NULL,
+            query_hash TEXT NOT NULL,
+            response TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (provider, query_hash)
+        );
+        CREATE INDEX idx_search_cache_created ON search_cache(created_at);",
+    )
+    .map_err(AppError::from)
+}
+
+/// Files stored for document clips (PDFs). `metadata` is the document info
+/// dictionary as JSON.
+fn create_documents(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE clip_documents (
+            clip_id INTEGER PRIMARY KEY REFERENCES clips(id) ON DELETE CASCADE,
+            file_name TEXT NOT NULL,
+            file_hash TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            page_count INTEGER NOT NULL,
+            metadata TEXT NOT NULL
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+fn create_ocr(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE clip_ocr (
+            clip_id INTEGER PRIMARY KEY REFERENCES clips(id) ON DELETE CASCADE,
+            status TEXT NOT NULL,
+            text TEXT,
+            error TEXT,
+            updated_at INTEGER NOT NULL
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+/// `feed_items` remembers every item seen so a refresh only clips new ones; `clip_id`
+/// is null for items skipped without a clip
+fn create_feeds(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE feeds (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL UNIQUE,
+            title TEXT,
+            site_url TEXT,
+            fetch_full_content INTEGER NOT NULL DEFAULT 0,
+            etag TEXT,
+            last_modified TEXT,
+            last_fetched_at INTEGER,
+            last_error TEXT,
+            created_at INTEGER NOT NULL
+        );
+        CREATE TABLE feed_items (
+            feed_id INTEGER NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+            guid TEXT NOT NULL,
+            clip_id INTEGER REFERENCES clips(id) ON DELETE SET NULL,
+            seen_at INTEGER NOT NULL,
+            PRIMARY KEY (feed_id, guid)
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+fn add_reading_state(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "read_state", "TEXT NOT NULL DEFAULT 'unread'")?;
+    add_column_if_missing(conn, "clips", "reading_progress", "REAL NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "clips", "finished_at", "INTEGER")?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_clips_read_state ON clips(read_state)", [])
+        .map_err(|e| AppError::database(format!("Failed to create read state index: {}", e)))?;
+    Ok(())
+}
+
+/// Offsets index the clip's plain text. `annotations_fts` mirrors quote and note
+/// through triggers, like `clips_fts`.
+fn create_annotations(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE annotations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            clip_id INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+            start_offset INTEGER,
+            end_offset INTEGER,
+            quote TEXT,
+            note TEXT,
+            color TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE INDEX idx_annotations_clip ON annotations(clip_id);
+
+        CREATE VIRTUAL TABLE annotations_fts USING fts5(
+            quote, note,
+            content = 'annotations', content_rowid = 'id',
+            tokenize = 'porter unicode61'
+        );
+
+        CREATE TRIGGER annotations_fts_insert AFTER INSERT ON annotations BEGIN
+            INSERT INTO annotations_fts(rowid, quote, note) VALUES (new.id, new.quote, new.note);
+        END;
+
+        CREATE TRIGGER annotations_fts_delete AFTER DELETE ON annotations BEGIN
+            INSERT INTO annotations_fts(annotations_fts, rowid, quote, note)
+            VALUES ('delete', old.id, old.quote, old.note);
+        END;
+
+        CREATE TRIGGER annotations_fts_update AFTER UPDATE OF quote, note ON annotations BEGIN
+            INSERT INTO annotations_fts(annotations_fts, rowid, quote, note)
+            VALUES ('delete', old.id, old.quote, old.note);
+            INSERT INTO annotations_fts(rowid, quote, note) VALUES (new.id, new.quote, new.note);
+        END;",
+    )
+    .map_err(|e| AppError::database(format!("Failed to create annotations: {}", e)))
+}
+
+fn create_revisions(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE clip_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            clip_id INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+            title TEXT NOT NULL,
+            content TEXT,
+            clip_updated_at INTEGER NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+        CREATE INDEX idx_clip_revisions_clip ON clip_revisions(clip_id, id);",
+    )
+    .map_err(AppError::from)
+}
+
+fn add_deleted_at(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "deleted_at", "INTEGER")?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_clips_deleted_at ON clips(deleted_at)", [])
+        .map_err(|e| AppError::database(format!("Failed to create deleted_at index: {}", e)))?;
+    Ok(())
+}
+
+/// Every clip gets a random `sync_id` shared across devices. Triggers add a clip to
+/// `sync_pending` whenever a synced field or its tags change; `version` lets a push
+/// clear only the entries it actually sent.
+fn create_sync(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "sync_id", "TEXT")?;
+    conn.execute_batch(
+        "UPDATE clips SET sync_id = lower(hex(randomblob(16))) WHERE sync_id IS NULL;
+        CREATE UNIQUE INDEX idx_clips_sync_id ON clips(sync_id);
+        CREATE TABLE sync_state (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        CREATE TABLE sync_cursors (
+            device_id TEXT PRIMARY KEY,
+            seq INTEGER NOT NULL
+        );
+        CREATE TABLE sync_pending (
+            clip_id INTEGER PRIMARY KEY,
+            version INTEGER NOT NULL
+        );
+        INSERT INTO sync_pending (clip_id, version) SELECT id, 1 FROM clips;
+        CREATE TRIGGER clips_sync_insert AFTER INSERT ON clips BEGIN
+            UPDATE clips SET sync_id = lower(hex(randomblob(16))) WHERE id = new.id AND sync_id IS NULL;
+            INSERT OR REPLACE INTO sync_pending (clip_id, version)
+            VALUES (new.id, COALESCE((SELECT version FROM sync_pending WHERE clip_id = new.id), 0) + 1);
+        END;
+        CREATE TRIGGER clips_sync_update AFTER UPDATE OF type, title, url, content, image_url, description, author,
+            timestamp, updated_at, summary, category, read_state, reading_progress, finished_at, deleted_at ON clips BEGIN
+            INSERT OR REPLACE INTO sync_pending (clip_id, version)
+            VALUES (new.id, COALESCE((SELECT version FROM sync_pending WHERE clip_id = new.id), 0) + 1);
+        END;
+        CREATE TRIGGER clip_tags_sync_insert AFTER INSERT ON clip_tags BEGIN
+            INSERT OR REPLACE INTO sync_pending (clip_id, version)
+            VALUES (new.clip_id, COALESCE((SELECT version FROM sync_pending WHERE clip_id = new.clip_id), 0) + 1);
+        END;
+        CREATE TRIGGER clip_tags_sync_update AFTER UPDATE ON clip_tags BEGIN
+            INSERT OR REPLACE INTO sync_pending (clip_id, version)
+            VALUES (new.clip_id, COALESCE((SELECT version FROM sync_pending WHERE clip_id = new.clip_id), 0) + 1);
+        END;
+        CREATE TRIGGER clip_tags_sync_delete AFTER DELETE ON clip_tags BEGIN
+            INSERT OR REPLACE INTO sync_pending (clip_id, version)
+            VALUES (old.clip_id, COALESCE((SELECT version FROM sync_pending WHERE clip_id = old.clip_id), 0) + 1);
+        END;",
+    )
+    .map_err(AppError::from)
+}
+
+/// `vault_pending` collects clips whose Obsidian note needs rewriting; deletes are
+/// recorded too so purged clips' notes get removed. `vault_notes` remembers the file
+/// each clip was last written to.
+fn create_vault(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE vault_notes (
+            clip_id INTEGER PRIMARY KEY,
+            file_name TEXT NOT NULL
+        );
+        CREATE INDEX idx_vault_notes_file ON vault_notes(file_name);
+        CREATE TABLE vault_pending (
+            clip_id INTEGER PRIMARY KEY
+        );
+        CREATE TRIGGER clips_vault_insert AFTER INSERT ON clips BEGIN
+            INSERT OR IGNORE INTO vault_pending (clip_id) VALUES (new.id);
+        END;
+        CREATE TRIGGER clips_vault_update AFTER UPDATE ON clips BEGIN
+            INSERT OR IGNORE INTO vault_pending (clip_id) VALUES (new.id);
+        END;
+        CREATE TRIGGER clips_vault_delete AFTER DELETE ON clips BEGIN
+            INSERT OR IGNORE INTO vault_pending (clip_id) VALUES (old.id);
+        END;
+        CREATE TRIGGER clip_tags_vault_insert AFTER INSERT ON clip_tags BEGIN
+            INSERT OR IGNORE INTO vault_pending (clip_id) VALUES (new.clip_id);
+        END;
+        CREATE TRIGGER clip_tags_vault_delete AFTER DELETE ON clip_tags BEGIN
+            INSERT OR IGNORE INTO vault_pending (clip_id) VALUES (old.clip_id);
+        END;
+        CREATE TRIGGER annotations_vault_insert AFTER INSERT ON annotations BEGIN
+            INSERT OR IGNORE INTO vault_pending (clip_id) VALUES (new.clip_id);
+        END;
+        CREATE TRIGGER annotations_vault_update AFTER UPDATE ON annotations BEGIN
+            INSERT OR IGNORE INTO vault_pending (clip_id) VALUES (new.clip_id);
+        END;
+        CREATE TRIGGER annotations_vault_delete AFTER DELETE ON annotations BEGIN
+            INSERT OR IGNORE INTO vault_pending (clip_id) VALUES (old.clip_id);
+        END;",
+    )
+    .map_err(AppError::from)
+}
+
+/// The Notion page each clip was exported to, per target database
+fn create_notion_pages(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE notion_pages (
+            clip_id INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+            database_id TEXT NOT NULL,
+            page_id TEXT NOT NULL,
+            exported_at INTEGER NOT NULL,
+            PRIMARY KEY (clip_id, database_id)
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+fn create_flashcards(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE flashcards (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            clip_id INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+            question TEXT NOT NULL,
+            answer TEXT NOT NULL,
+            guid TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            exported_at INTEGER
+        );
+        CREATE INDEX idx_flashcards_clip ON flashcards(clip_id);",
+    )
+    .map_err(AppError::from)
+}
+
+/// Prompt templates, seeded with a few to start from
+fn create_prompts(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE prompts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            template TEXT NOT NULL,
+            description TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        INSERT INTO prompts (name, template, description, created_at, updated_at)
+        VALUES
+            ('Summarize',
+             'Summarize the following in a few sentences.\n\nTitle: {{title}}\n\n{{text}}',
+             'A short summary of the clip',
+             CAST(strftime('%s', 'now') AS INTEGER) * 1000,
+             CAST(strftime('%s', 'now') AS INTEGER) * 1000),
+            ('Explain like I''m five',
+             'Explain the following so a five-year-old could understand it.\n\n{{text}}',
+             'A plain-language explanation',
+             CAST(strftime('%s', 'now') AS INTEGER) * 1000,
+             CAST(strftime('%s', 'now') AS INTEGER) * 1000),
+            ('Extract action items',
+             'List the action items in the following as a bulleted list. Reply \"None\" if there are none.\n\n{{text}}',
+             'Tasks and next steps mentioned in the clip',
+             CAST(strftime('%s', 'now') AS INTEGER) * 1000,
+             CAST(strftime('%s', 'now') AS INTEGER) * 1000);",
+    )
+    .map_err(AppError::from)
+}
+
+/// Per-conversation system prompt and sampling settings
+fn add_conversation_settings(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "conversations", "system_prompt", "TEXT")?;
+    add_column_if_missing(conn, "conversations", "temperature", "REAL")?;
+    add_column_if_missing(conn, "conversations", "max_tokens", "INTEGER")?;
+    Ok(())
+}
+
+fn create_clip_audio(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE clip_audio (
+            clip_id INTEGER PRIMARY KEY REFERENCES clips(id) ON DELETE CASCADE,
+            file_name TEXT NOT NULL,
+            file_hash TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            media_type TEXT NOT NULL,
+            transcribed_at INTEGER
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+/// Conditions and actions are JSON objects (see `rules::RuleConditions` and `rules::RuleActions`)
+fn create_rules(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            conditions TEXT NOT NULL,
+            actions TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+/// The URL as clipped, when `url` holds its canonical form
+fn add_original_url(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "original_url", "TEXT")?;
+    Ok(())
+}
+
+fn create_link_checks(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE link_checks (
+            clip_id INTEGER PRIMARY KEY REFERENCES clips(id) ON DELETE CASCADE,
+            status INTEGER,
+            error TEXT,
+            checked_at INTEGER NOT NULL,
+            failures INTEGER NOT NULL DEFAULT 0,
+            dead INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX idx_link_checks_checked_at ON link_checks(checked_at);",
+    )
+    .map_err(AppError::from)
+}
+
+/// OpenGraph details and favicon for rich previews in the clip list
+fn add_site_metadata(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "site_name", "TEXT")?;
+    add_column_if_missing(conn, "clips", "published_at", "TEXT")?;
+    add_column_if_missing(conn, "clips", "preview_image_url", "TEXT")?;
+    add_column_if_missing(conn, "clips", "favicon_path", "TEXT")?;
+    Ok(())
+}
+
+/// Files attached to emailed clips, stored in the media dir by content hash
+fn create_clip_attachments(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE clip_attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            clip_id INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+            file_name TEXT NOT NULL,
+            stored_name TEXT NOT NULL,
+            file_hash TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            media_type TEXT NOT NULL,
+            UNIQUE (clip_id, file_hash)
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+/// Reads of stored secrets by command or feature; values are never logged
+fn create_secret_access_log(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE secret_access_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            caller TEXT NOT NULL,
+            accessed_at INTEGER NOT NULL
+        );
+        CREATE INDEX idx_secret_access_log_name ON secret_access_log(name, accessed_at);",
+    )
+    .map_err(AppError::from)
+}
+
+/// Where clips merged into another now live. `from_id` has no foreign key so the
+/// redirect outlasts the tombstone being purged from the trash.
+fn create_clip_redirects(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE clip_redirects (
+            from_id INTEGER PRIMARY KEY,
+            to_id INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+            merged_at INTEGER NOT NULL
+        );
+        CREATE INDEX idx_clip_redirects_to ON clip_redirects(to_id);",
+    )
+    .map_err(AppError::from)
+}
+
+/// Saved searches; `filter` is a JSON `ClipFilter`
+fn create_smart_collections(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE smart_collections (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            filter TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+/// Per-domain ingestion settings; `default_tags` is a JSON array
+fn create_sources(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE sources (
+            domain TEXT PRIMARY KEY,
+            muted INTEGER NOT NULL DEFAULT 0,
+            default_tags TEXT NOT NULL DEFAULT '[]',
+            default_collection_id INTEGER REFERENCES collections(id) ON DELETE SET NULL,
+            updated_at INTEGER NOT NULL
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+/// `text_hash` spots embeddings computed from outdated text; `maintenance_runs` records
+/// when each maintenance task last ran
+fn create_maintenance(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clip_embeddings", "text_hash", "TEXT")?;
+    conn.execute_batch(
+        "CREATE TABLE maintenance_runs (
+            task TEXT PRIMARY KEY,
+            ran_at INTEGER NOT NULL
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+/// `libsql_pending` collects clips to push to the libsql database, like `sync_pending`.
+/// Purged clips keep their `sync_id` so the remote copy can be removed too; the upserts
+/// leave it alone so the cascaded tag deletes don't clear it.
+fn create_libsql_pending(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE libsql_pending (
+            clip_id INTEGER PRIMARY KEY,
+            version INTEGER NOT NULL,
+            sync_id TEXT
+        );
+        INSERT INTO libsql_pending (clip_id, version) SELECT id, 1 FROM clips;
+        CREATE TRIGGER clips_libsql_insert AFTER INSERT ON clips BEGIN
+            INSERT INTO libsql_pending (clip_id, version) VALUES (new.id, 1)
+            ON CONFLICT (clip_id) DO UPDATE SET version = version + 1;
+        END;
+        CREATE TRIGGER clips_libsql_update AFTER UPDATE OF type, title, url, content, image_url, description, author,
+            timestamp, updated_at, summary, category, read_state, reading_progress, finished_at, deleted_at ON clips BEGIN
+            INSERT INTO libsql_pending (clip_id, version) VALUES (new.id, 1)
+            ON CONFLICT (clip_id) DO UPDATE SET version = version + 1;
+        END;
+        CREATE TRIGGER clips_libsql_delete AFTER DELETE ON clips BEGIN
+            INSERT INTO libsql_pending (clip_id, version, sync_id) VALUES (old.id, 1, old.sync_id)
+            ON CONFLICT (clip_id) DO UPDATE SET version = version + 1, sync_id = excluded.sync_id;
+        END;
+        CREATE TRIGGER clip_tags_libsql_insert AFTER INSERT ON clip_tags BEGIN
+            INSERT INTO libsql_pending (clip_id, version) VALUES (new.clip_id, 1)
+            ON CONFLICT (clip_id) DO UPDATE SET version = version + 1;
+        END;
+        CREATE TRIGGER clip_tags_libsql_update AFTER UPDATE ON clip_tags BEGIN
+            INSERT INTO libsql_pending (clip_id, version) VALUES (new.clip_id, 1)
+            ON CONFLICT (clip_id) DO UPDATE SET version = version + 1;
+        END;
+        CREATE TRIGGER clip_tags_libsql_delete AFTER DELETE ON clip_tags BEGIN
+            INSERT INTO libsql_pending (clip_id, version) VALUES (old.clip_id, 1)
+            ON CONFLICT (clip_id) DO UPDATE SET version = version + 1;
+        END;",
+    )
+    .map_err(AppError::from)
+}
+
+/// Private clips keep content, description and summary encrypted in `encrypted_body`
+/// and are left out of `clips_fts`, so the triggers only index clips that aren't private.
+/// `private_vault` holds a value encrypted with the passphrase, to check it on unlock.
+fn create_private_clips(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "private", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "clips", "encrypted_body", "TEXT")?;
+    conn.execute_batch(
+        "CREATE TABLE private_vault (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            verifier TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+
+        DROP TRIGGER IF EXISTS clips_fts_insert;
+        DROP TRIGGER IF EXISTS clips_fts_delete;
+        DROP TRIGGER IF EXISTS clips_fts_update;
+
+        CREATE TRIGGER clips_fts_insert AFTER INSERT ON clips WHEN new.private = 0 BEGIN
+            INSERT INTO clips_fts(rowid, title, content, description)
+            VALUES (new.id, new.title, new.content, new.description);
+        END;
+
+        CREATE TRIGGER clips_fts_delete AFTER DELETE ON clips WHEN old.private = 0 BEGIN
+            INSERT INTO clips_fts(clips_fts, rowid, title, content, description)
+            VALUES ('delete', old.id, old.title, old.content, old.description);
+        END;
+
+        CREATE TRIGGER clips_fts_update AFTER UPDATE OF title, content, description, private ON clips BEGIN
+            INSERT INTO clips_fts(clips_fts, rowid, title, content, description)
+            SELECT 'delete', old.id, old.title, old.content, old.description WHERE old.private = 0;
+            INSERT INTO clips_fts(rowid, title, content, description)
+            SELECT new.id, new.title, new.content, new.description WHERE new.private = 0;
+        END;",
+    )
+    .map_err(AppError::from)
+}
+
+fn create_paired_clippers(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE paired_clippers (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            last_seen_at INTEGER,
+            revoked_at INTEGER
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+fn create_quarantined_clips(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE quarantined_clips (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source TEXT NOT NULL,
+            client_id TEXT,
+            payload TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            received_at INTEGER NOT NULL
+        );",
+    )
+    .map_err(AppError::from)
+}
+
+fn add_clip_language(conn: &Connection) -> Result<(), AppError> {
+    add_column_if_missing(conn, "clips", "language", "TEXT")?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_clips_language ON clips(language)", [])
+        .map_err(|e| AppError::database(format!("Failed to create language index: {}", e)))?;
+    Ok(())
+}
+
+/// Board columns for clips, seeded with a starting workflow. `status_position` orders
+/// the clips within their status.
+fn create_clip_statuses(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE clip_statuses (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            position INTEGER NOT NULL
+        );
+        INSERT INTO clip_statuses (name, position)
+        VALUES ('Inbox', 0), ('To Read', 1), ('Reference', 2), ('Done', 3);",
+    )
+    .map_err(AppError::from)?;
+    add_column_if_missing(conn, "clips", "status_id", "INTEGER REFERENCES clip_statuses(id) ON DELETE SET NULL")?;
+    add_column_if_missing(conn, "clips", "status_position", "INTEGER")?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_clips_status ON clips(status_id, status_position)", [])
+        .map_err(|e| AppError::database(format!("Failed to create status index: {}", e)))?;
+    Ok(())
+}
+
+/// At most one open reminder per clip; `fired_at` records that the current `due_at`
+/// has been announced
+fn create_reminders(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE reminders (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            clip_id INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+            due_at INTEGER NOT NULL,
+            note TEXT,
+            created_at INTEGER NOT NULL,
+            fired_at INTEGER,
+            snooze_count INTEGER NOT NULL DEFAULT 0,
+            completed_at INTEGER
+        );
+        CREATE UNIQUE INDEX idx_reminders_open_clip ON reminders(clip_id) WHERE completed_at IS NULL;
+        CREATE INDEX idx_reminders_due ON reminders(due_at) WHERE completed_at IS NULL;",
+    )
+    .map_err(AppError::from)
+}
+
+/// A sealed clip's content hash would let a guessed plaintext be checked against it;
+/// making the clip public again recomputes it
+fn clear_private_content_hashes(conn: &Connection) -> Result<(), AppError> {
+    conn.execute("UPDATE clips SET content_hash = NULL WHERE private = 1", [])
+        .map(|_| ())
+        .map_err(AppError::from)
+}
+
+/// Add `column` to `table` unless it already exists. Returns whether it was added.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<bool, AppError> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_info({})", table))
+        .map_err(|e| AppError::database(format!("Failed to inspect {}: {}", table, e)))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| AppError::database(format!("Failed to inspect {}: {}", table, e)))?
+        .filter_map(Result::ok)
+        .any(|name| name == column);
+
+    if exists {
+        return Ok(false);
+    }
+    conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl), [])
+        .map_err(|e| AppError::database(format!("Failed to add {}.{}: {}", table, column, e)))?;
+    Ok(true)
+}
+
+/// Populate `clips.domain` for rows inserted before the column existed
+fn backfill_domains(conn: &Connection) -> Result<(), AppError> {
+    let mut stmt = conn
+        .prepare("SELECT id, url FROM clips WHERE domain IS NULL AND url IS NOT NULL")
+        .map_err(|e| AppError::database(format!("Failed to read clip urls: {}", e)))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| AppError::database(format!("Failed to read clip urls: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read clip urls: {}", e)))?;
+
+    for (id, url) in rows {
+        conn.execute(
+            "UPDATE clips SET domain = ?1 WHERE id = ?2",
+            rusqlite::params![clips::domain_of(&url), id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to backfill domain: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Compute `normalized_url` and `content_hash` for rows inserted before the columns existed
+fn backfill_duplicate_keys(conn: &Connection) -> Result<(), AppError> {
+    let mut stmt = conn
+        .prepare("SELECT id, url, content FROM clips WHERE normalized_url IS NULL AND content_hash IS NULL")
+        .map_err(|e| AppError::database(format!("Failed to read clips: {}", e)))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, Option<String>>(2)?))
+        })
+        .map_err(|e| AppError::database(format!("Failed to read clips: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read clips: {}", e)))?;
+
+    for (id, url, content) in rows {
+        conn.execute(
+            "UPDATE clips SET normalized_url = ?1, content_hash = ?2 WHERE id = ?3",
+            rusqlite::params![
+                url.as_deref().and_then(clips::normalize_url),
+                clips::content_hash(content.as_deref()),
+                id
+            ],
+        )
+        .map_err(|e| AppError::database(format!("Failed to backfill duplicate keys: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Create the `clips_fts` full-text index over title, content and description,
+/// kept in sync with `clips` by triggers
+fn create_fts(conn: &Connection) -> Result<(), AppError> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'clips_fts')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::database(format!("Failed to inspect search index: {}", e)))?;
+
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS clips_fts USING fts5(
+            title, content, description,
+            content = 'clips', content_rowid = 'id',
+            tokenize = 'porter unicode61'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS clips_fts_insert AFTER INSERT ON clips BEGIN
+            INSERT INTO clips_fts(rowid, title, content, description)
+            VALUES (new.id, new.title, new.content, new.description);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS clips_fts_delete AFTER DELETE ON clips BEGIN
+            INSERT INTO clips_fts(clips_fts, rowid, title, content, description)
+            VALUES ('delete', old.id, old.title, old.content, old.description);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS clips_fts_update AFTER UPDATE OF title, content, description ON clips BEGIN
+            INSERT INTO clips_fts(clips_fts, rowid, title, content, description)
+            VALUES ('delete', old.id, old.title, old.content, old.description);
+            INSERT INTO clips_fts(rowid, title, content, description)
+            VALUES (new.id, new.title, new.content, new.description);
+        END;",
+    )
+    .map_err(|e| AppError::database(format!("Failed to create search index: {}", e)))?;
+
+    // Index clips that were stored before the search index existed
+    if !exists {
+        conn.execute("INSERT INTO clips_fts(clips_fts) VALUES ('rebuild')", [])
+            .map_err(|e| AppError::database(format!("Failed to build search index: {}", e)))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tracing::warn;
+
+use crate::providers::LlmError;
+use crate::redact;
+use crate::settings::SettingsManager;
+
+/// Sources of desktop notifications; each is throttled separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationKind {
+    /// Confirmation of a capture the user just triggered (shortcut, tray)
+    Capture,
+    /// A clip arrived from the browser extension
+    ClipReceived,
+    /// A background summary or tag suggestion finished
+    Enrichment,
+    /// An LLM provider refused a call for lack of credit
+    Quota,
+    /// The daily digest was written
+    Digest,
+    /// Stored API keys are about to expire
+    SecretExpiry,
+    /// A reminder set on a clip came due
+    Reminder,
+}
+
+impl NotificationKind {
+    /// Minimum time between two notifications of this kind. Ones arriving sooner
+    /// are counted and mentioned in the next notification instead.
+    fn min_interval(self) -> Duration {
+        match self {
+            NotificationKind::Capture | NotificationKind::Digest | NotificationKind::Reminder => Duration::ZERO,
+            NotificationKind::ClipReceived | NotificationKind::Enrichment => Duration::from_secs(15),
+            NotificationKind::Quota => Duration::from_secs(10 * 60),
+            NotificationKind::SecretExpiry => Duration::from_secs(24 * 3600),
+        }
+    }
+}
+
+struct Throttle {
+    last_shown: Instant,
+    suppressed: u32,
+}
+
+/// Shared throttling for every subsystem that notifies, managed as Tauri state
+#[derive(Default)]
+pub struct Notifier {
+    throttles: Mutex<HashMap<NotificationKind, Throttle>>,
+}
+
+impl Notifier {
+    /// Show a notification unless notifications are disabled or one of the same kind
+    /// was shown too recently
+    pub fn notify(&self, app_handle: &AppHandle, kind: NotificationKind, title: &str, body: &str) {
+        if app_handle.state::<SettingsManager>().get().disable_notifications {
+            return;
+        }
+
+        let suppressed = {
+            let mut throttles = self.throttles.lock().unwrap();
+            match throttles.get_mut(&kind) {
+                Some(throttle) if throttle.last_shown.elapsed() < kind.min_interval() => {
+                    throttle.suppressed += 1;
+                    return;
+                }
+                Some(throttle) => {
+                    throttle.last_shown = Instant::now();
+                    std::mem::take(&mut throttle.suppressed)
+                }
+                None => {
+                    throttles.insert(
+                        kind,
+                        Throttle {
+                            last_shown: Instant::now(),
+                            suppressed: 0,
+                        },
+                    );
+                    0
+                }
+            }
+        };
+
+        // Bodies often carry error text, which can quote a key
+        let body = redact::redact(body);
+        let body = match suppressed {
+            0 => body,
+            n => format!("{} (and {} more)", body, n),
+        };
+        if let Err(e) = app_handle.notification().builder().title(title).body(body).show() {
+            warn!("Failed to show notification: {}", e);
+        }
+    }
+}
+
+/// Shorthand for `Notifier::notify` on the managed notifier
+pub fn notify(app_handle: &AppHandle, kind: NotificationKind, title: &str, body: &str) {
+    app_handle.state::<Notifier>().notify(app_handle, kind, title, body);
+}
+
+/// Tell the user when a provider stops serving requests because of quota or billing;
+/// other LLM errors are reported where they happen
+pub fn notify_llm_error(app_handle: &AppHandle, provider: &str, error: &LlmError) {
+    if let LlmError::Quota { message } = error {
+        notify(
+            app_handle,
+            NotificationKind::Quota,
+            &format!("{} quota exceeded", provider),
+            message,
+        );
+    }
+}
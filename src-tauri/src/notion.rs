@@ -0,0 +1,381 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::warn;
+
+use crate::annotations::{self, Annotation};
+use crate::clips::{self, now_millis, SqliteClip};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract::plain_text;
+use crate::http;
+use crate::providers::{send_json, LlmError};
+use crate::redact;
+use crate::secrets::SecretsManager;
+use crate::tags;
+
+/// Secret holding the Notion integration token
+pub const NOTION_TOKEN_SECRET: &str = "notion_token";
+const NOTION_API: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+/// Notion allows about three requests a second per integration
+const REQUEST_SPACING: Duration = Duration::from_millis(350);
+/// Longest text Notion accepts in one rich text object
+const MAX_TEXT_LEN: usize = 2000;
+/// Blocks per create or append request
+const MAX_BLOCKS_PER_REQUEST: usize = 100;
+/// Longer clips are cut off with a note
+const MAX_BLOCKS_PER_PAGE: usize = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotionExportProgress {
+    pub exported: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotionExportFailure {
+    pub clip_id: i64,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct NotionExportSummary {
+    pub created: u32,
+    pub updated: u32,
+    pub failures: Vec<NotionExportFailure>,
+}
+
+/// Names of the target database's properties that clips are written to
+struct DatabaseSchema {
+    title: String,
+    url: Option<String>,
+    tags: Option<String>,
+}
+
+/// Notion API client that spaces its requests to stay under the rate limit;
+/// `send_json` waits out any 429 that gets through anyway
+struct NotionClient {
+    client: reqwest::Client,
+    token: String,
+    last_request: Option<Instant>,
+}
+
+fn notion_error(error: LlmError) -> AppError {
+    match error {
+        LlmError::Auth { message } => AppError::auth(format!("Notion rejected the token: {}", message)),
+        LlmError::Api { status: 404, message } => AppError::not_found(message),
+        LlmError::Api { message, .. } | LlmError::Other { message } | LlmError::Quota { message } => {
+            AppError::validation(message)
+        }
+        LlmError::RateLimited { message, .. } | LlmError::Network { message } | LlmError::Server { message, .. } => {
+            AppError::network(message)
+        }
+        LlmError::Offline { message } => AppError::offline(message),
+    }
+}
+
+impl NotionClient {
+    async fn request(&mut self, method: reqwest::Method, path: &str, body: Option<Value>) -> Result<Value, AppError> {
+        if let Some(wait) = self.last_request.and_then(|last| REQUEST_SPACING.checked_sub(last.elapsed())) {
+            tokio::time::sleep(wait).await;
+        }
+        self.last_request = Some(Instant::now());
+
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", NOTION_API, path))
+            .bearer_auth(&self.token)
+            .header("Notion-Version", NOTION_VERSION);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        send_json(request).await.map_err(notion_error)
+    }
+
+    async fn schema(&mut self, database_id: &str) -> Result<DatabaseSchema, AppError> {
+        let database = self
+            .request(reqwest::Method::GET, &format!("/databases/{}", database_id), None)
+            .await
+            .map_err(|e| match e {
+                AppError::NotFound { .. } => AppError::not_found(
+                    "Notion database not found; check the id and share the database with the integration",
+                ),
+                other => other,
+            })?;
+        let properties: Vec<(String, String)> = database["properties"]
+            .as_object()
+            .map(|properties| {
+                properties
+                    .iter()
+                    .map(|(name, property)| (name.clone(), property["type"].as_str().unwrap_or_default().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        // Prefer properties named for what they hold, then any of the right type
+        let find = |kind: &str, preferred: &str| {
+            properties
+                .iter()
+                .filter(|(_, property_kind)| property_kind == kind)
+                .max_by_key(|(name, _)| name.eq_ignore_ascii_case(preferred))
+                .map(|(name, _)| name.clone())
+        };
+        Ok(DatabaseSchema {
+            title: find("title", "name").ok_or_else(|| AppError::validation("The Notion database has no title property"))?,
+            url: find("url", "url"),
+            tags: find("multi_select", "tags"),
+        })
+    }
+
+    /// Remove a page's blocks so a re-export replaces its content
+    async fn clear_page(&mut self, page_id: &str) -> Result<(), AppError> {
+        loop {
+            let children = self
+                .request(
+                    reqwest::Method::GET,
+                    &format!("/blocks/{}/children?page_size=100", page_id),
+                    None,
+                )
+                .await?;
+            let ids: Vec<String> = children["results"]
+                .as_array()
+                .map(|blocks| blocks.iter().filter_map(|block| block["id"].as_str().map(str::to_string)).collect())
+                .unwrap_or_default();
+            if ids.is_empty() {
+                return Ok(());
+            }
+            for id in ids {
+                self.request(reqwest::Method::DELETE, &format!("/blocks/{}", id), None).await?;
+            }
+        }
+    }
+
+    async fn append(&mut self, page_id: &str, blocks: &[Value]) -> Result<(), AppError> {
+        for chunk in blocks.chunks(MAX_BLOCKS_PER_REQUEST) {
+            self.request(
+                reqwest::Method::PATCH,
+                &format!("/blocks/{}/children", page_id),
+                Some(json!({ "children": chunk })),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// The 32-hex-digit id in a database id or a link to the database
+fn parse_database_id(input: &str) -> Result<String, AppError> {
+    let path = input.split(['?', '#']).next().unwrap_or_default();
+    let last_segment = path.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+    let hex: String = last_segment.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+    if hex.len() < 32 {
+        return Err(AppError::validation(format!("'{}' isn't a Notion database id or link", input)));
+    }
+    Ok(hex[hex.len() - 32..].to_lowercase())
+}
+
+/// `text` in pieces of at most `MAX_TEXT_LEN` characters
+fn chunk_text(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.chunks(MAX_TEXT_LEN).map(|chunk| chunk.iter().collect()).collect()
+}
+
+fn text_block(kind: &str, text: &str) -> Value {
+    let mut block = json!({ "object": "block", "type": kind });
+    block[kind] = json!({ "rich_text": [{ "type": "text", "text": { "content": text } }] });
+    block
+}
+
+/// One block of `kind` per `MAX_TEXT_LEN` characters of `text`
+fn push_text(blocks: &mut Vec<Value>, kind: &str, text: &str) {
+    for chunk in chunk_text(text.trim()) {
+        blocks.push(text_block(kind, &chunk));
+    }
+}
+
+/// Description, summary, content paragraphs and highlights as Notion blocks
+fn page_blocks(clip: &SqliteClip, annotations: &[Annotation]) -> Vec<Value> {
+    let mut blocks = Vec::new();
+
+    if let Some(description) = clip.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        push_text(&mut blocks, "quote", description);
+    }
+    if let Some(summary) = clip.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+        blocks.push(text_block("heading_2", "Summary"));
+        push_text(&mut blocks, "paragraph", summary);
+    }
+    if let Some(content) = clip.content.as_deref() {
+        let text = plain_text(content);
+        for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+            push_text(&mut blocks, "paragraph", paragraph);
+        }
+    }
+    if !annotations.is_empty() {
+        blocks.push(text_block("heading_2", "Highlights"));
+        for annotation in annotations {
+            if let Some(quote) = &annotation.quote {
+                push_text(&mut blocks, "quote", quote);
+            }
+            if let Some(note) = &annotation.note {
+                push_text(&mut blocks, "paragraph", note);
+            }
+        }
+    }
+
+    if blocks.len() > MAX_BLOCKS_PER_PAGE {
+        blocks.truncate(MAX_BLOCKS_PER_PAGE - 1);
+        blocks.push(text_block("paragraph", "… (truncated; the full clip is in LOS)"));
+    }
+    blocks
+}
+
+fn page_properties(schema: &DatabaseSchema, clip: &SqliteClip, tags: &[String]) -> Value {
+    let title: String = clip.title.chars().take(MAX_TEXT_LEN).collect();
+    let mut properties = json!({});
+    properties[&schema.title] = json!({ "title": [{ "type": "text", "text": { "content": title } }] });
+    if let Some(url_property) = &schema.url {
+        properties[url_property] = json!({ "url": clip.url });
+    }
+    if let Some(tags_property) = &schema.tags {
+        // Select option names can't contain commas
+        let options: Vec<Value> = tags.iter().map(|tag| json!({ "name": tag.replace(',', " ") })).collect();
+        properties[tags_property] = json!({ "multi_select": options });
+    }
+    properties
+}
+
+fn mapped_page(conn: &Connection, clip_id: i64, database_id: &str) -> Result<Option<String>, AppError> {
+    conn.query_row(
+        "SELECT page_id FROM notion_pages WHERE clip_id = ?1 AND database_id = ?2",
+        params![clip_id, database_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read Notion pages: {}", e)))
+}
+
+/// Create or update the Notion page for one clip. Returns whether a page was created.
+async fn export_clip(
+    app_handle: &AppHandle,
+    notion: &mut NotionClient,
+    schema: &DatabaseSchema,
+    database_id: &str,
+    clip_id: i64,
+) -> Result<bool, AppError> {
+    let (clip, tags, annotations, page_id) = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let clip = clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+        let tags = tags::tags_for_clip(&conn, clip_id)?;
+        let annotations = annotations::list_annotations(&conn, clip_id)?;
+        (clip, tags, annotations, mapped_page(&conn, clip_id, database_id)?)
+    };
+    let properties = page_properties(schema, &clip, &tags);
+    let blocks = page_blocks(&clip, &annotations);
+
+    // A page deleted in Notion since the last export is recreated
+    let existing = match page_id {
+        Some(page_id) => {
+            match notion
+                .request(
+                    reqwest::Method::PATCH,
+                    &format!("/pages/{}", page_id),
+                    Some(json!({ "properties": properties })),
+                )
+                .await
+            {
+                Ok(page) if page["archived"].as_bool() != Some(true) => Some(page_id),
+                Ok(_) | Err(AppError::NotFound { .. }) => None,
+                Err(e) => return Err(e),
+            }
+        }
+        None => None,
+    };
+
+    let (page_id, created) = match existing {
+        Some(page_id) => {
+            notion.clear_page(&page_id).await?;
+            notion.append(&page_id, &blocks).await?;
+            (page_id, false)
+        }
+        None => {
+            let (first, rest) = blocks.split_at(blocks.len().min(MAX_BLOCKS_PER_REQUEST));
+            let page = notion
+                .request(
+                    reqwest::Method::POST,
+                    "/pages",
+                    Some(json!({
+                        "parent": { "database_id": database_id },
+                        "properties": properties,
+                        "children": first,
+                    })),
+                )
+                .await?;
+            let page_id = page["id"]
+                .as_str()
+                .ok_or_else(|| AppError::network("Notion returned a page without an id"))?
+                .to_string();
+            notion.append(&page_id, rest).await?;
+            (page_id, true)
+        }
+    };
+
+    let db = app_handle.state::<Database>();
+    db.conn()?
+        .execute(
+            "INSERT OR REPLACE INTO notion_pages (clip_id, database_id, page_id, exported_at) VALUES (?1, ?2, ?3, ?4)",
+            params![clip_id, database_id, page_id, now_millis()],
+        )
+        .map_err(|e| AppError::database(format!("Failed to save Notion page: {}", e)))?;
+    Ok(created)
+}
+
+/// Export clips as pages of a Notion database: title, URL and tags go into the
+/// database's properties and the text into the page. Clips exported to the same
+/// database before have their page updated. Emits `notion-export-progress` after
+/// each clip; a failed clip is reported and the rest carry on, except on auth errors.
+pub async fn export_to_notion(
+    app_handle: &AppHandle,
+    clip_ids: Vec<i64>,
+    database_id: &str,
+) -> Result<NotionExportSummary, AppError> {
+    let database_id = parse_database_id(database_id)?;
+    let token = app_handle
+        .state::<SecretsManager>()
+        .get_secret(NOTION_TOKEN_SECRET, "export_to_notion")
+        .await
+        .map_err(|e| match e {
+            AppError::NotFound { .. } => AppError::auth("No Notion token stored; add it in settings"),
+            other => other,
+        })?;
+    let mut notion = NotionClient {
+        client: http::client(),
+        token,
+        last_request: None,
+    };
+    let schema = notion.schema(&database_id).await?;
+
+    let total = clip_ids.len() as u32;
+    let mut summary = NotionExportSummary::default();
+    for (done, clip_id) in clip_ids.into_iter().enumerate() {
+        match export_clip(app_handle, &mut notion, &schema, &database_id, clip_id).await {
+            Ok(true) => summary.created += 1,
+            Ok(false) => summary.updated += 1,
+            Err(e @ AppError::Auth { .. }) => return Err(e),
+            Err(e) => summary.failures.push(NotionExportFailure {
+                clip_id,
+                error: redact::redact(&e.to_string()),
+            }),
+        }
+        let progress = NotionExportProgress {
+            exported: done as u32 + 1,
+            total,
+        };
+        if let Err(e) = app_handle.emit("notion-export-progress", &progress) {
+            warn!("Failed to emit Notion export progress: {}", e);
+        }
+    }
+    Ok(summary)
+}
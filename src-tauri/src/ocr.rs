@@ -0,0 +1,168 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use crate::clips::{self, now_millis, ClipUpdate};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::jobs::{Job, JobKind, JobQueue};
+use crate::media;
+use crate::settings::SettingsManager;
+
+/// Tesseract languages used when none are configured
+pub const DEFAULT_OCR_LANGUAGES: &str = "eng";
+
+/// OCR state of an image clip: `queued` -> `running` -> `done` | `failed`
+#[derive(Debug, Serialize)]
+pub struct ClipOcr {
+    pub clip_id: i64,
+    pub status: String,
+    /// Recognized text, once done
+    pub text: Option<String>,
+    pub error: Option<String>,
+    pub updated_at: i64,
+}
+
+/// Whether this build was compiled with the `ocr` feature (tesseract via leptess)
+pub fn is_available() -> bool {
+    cfg!(feature = "ocr")
+}
+
+fn unavailable() -> AppError {
+    AppError::validation("OCR isn't available in this build; it needs the `ocr` feature")
+}
+
+#[cfg(feature = "ocr")]
+fn recognize(path: &Path, languages: &str) -> Result<String, AppError> {
+    let mut tess = leptess::LepTess::new(None, languages)
+        .map_err(|e| AppError::internal(format!("Failed to start tesseract with languages '{}': {}", languages, e)))?;
+    tess.set_image(path)
+        .map_err(|e| AppError::validation(format!("Failed to load image for OCR: {}", e)))?;
+    tess.get_utf8_text()
+        .map_err(|e| AppError::internal(format!("OCR produced invalid text: {}", e)))
+}
+
+#[cfg(not(feature = "ocr"))]
+fn recognize(_path: &Path, _languages: &str) -> Result<String, AppError> {
+    Err(unavailable())
+}
+
+/// Trim each line and collapse runs of blank lines left by the page layout
+fn tidy(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() && matches!(lines.last(), None | Some(&"")) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+fn set_status(conn: &Connection, clip_id: i64, status: &str, text: Option<&str>, error: Option<&str>) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO clip_ocr (clip_id, status, text, error, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (clip_id) DO UPDATE SET
+            status = excluded.status, text = COALESCE(excluded.text, text),
+            error = excluded.error, updated_at = excluded.updated_at",
+        params![clip_id, status, text, error, now_millis()],
+    )
+    .map_err(|e| AppError::database(format!("Failed to record OCR status: {}", e)))?;
+    Ok(())
+}
+
+pub fn get_clip_ocr(conn: &Connection, clip_id: i64) -> Result<Option<ClipOcr>, AppError> {
+    conn.query_row(
+        "SELECT clip_id, status, text, error, updated_at FROM clip_ocr WHERE clip_id = ?1",
+        params![clip_id],
+        |row| {
+            Ok(ClipOcr {
+                clip_id: row.get(0)?,
+                status: row.get(1)?,
+                text: row.get(2)?,
+                error: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read OCR status: {}", e)))
+}
+
+/// Queue OCR for an image clip. The job downloads the image if it isn't stored yet.
+pub fn queue_ocr(app_handle: &AppHandle, clip_id: i64) -> Result<Job, AppError> {
+    if !is_available() {
+        return Err(unavailable());
+    }
+    {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let clip = clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+        if clip.r#type != "image" {
+            return Err(AppError::validation(format!("Clip {} is not an image", clip_id)));
+        }
+        set_status(&conn, clip_id, "queued", None, None)?;
+    }
+    app_handle.state::<JobQueue>().submit(app_handle, JobKind::OcrClip { clip_id })
+}
+
+async fn recognize_clip(app_handle: &AppHandle, clip_id: i64) -> Result<String, AppError> {
+    let image = media::get_clip_image(app_handle, clip_id).await?;
+    let settings = app_handle.state::<SettingsManager>().get();
+    let languages = settings.ocr_languages().to_string();
+    let text = tauri::async_runtime::spawn_blocking(move || recognize(&image.path, &languages))
+        .await
+        .map_err(|e| AppError::internal(format!("OCR failed: {}", e)))??;
+    Ok(tidy(&text))
+}
+
+/// Recognize the text in an image clip and store it. The text becomes the clip's
+/// `content` (and so reaches full-text search and embeddings) unless the clip has
+/// content of its own.
+pub async fn ocr_clip(app_handle: &AppHandle, clip_id: i64) -> Result<ClipOcr, AppError> {
+    let previous = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let previous = get_clip_ocr(&conn, clip_id)?.and_then(|ocr| ocr.text);
+        set_status(&conn, clip_id, "running", None, None)?;
+        previous
+    };
+
+    let text = match recognize_clip(app_handle, clip_id).await {
+        Ok(text) => text,
+        Err(e) => {
+            let db = app_handle.state::<Database>();
+            set_status(&db.conn()?, clip_id, "failed", None, Some(&e.to_string()))?;
+            return Err(e);
+        }
+    };
+
+    let db = app_handle.state::<Database>();
+    let conn = db.conn()?;
+    let clip = clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+    let content = clip.content.as_deref().map(str::trim).unwrap_or_default();
+    // Replace content only if it's empty or came from an earlier OCR run
+    if !text.is_empty() && (content.is_empty() || Some(content) == previous.as_deref().map(str::trim)) {
+        let clip = clips::update_clip(
+            &conn,
+            clip_id,
+            ClipUpdate {
+                content: Some(Some(text.clone())),
+                ..Default::default()
+            },
+        )?;
+        events::emit(app_handle, "clip-updated", &clip)
+            .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+
+        if app_handle.state::<SettingsManager>().get().embedding_model.is_some() {
+            app_handle
+                .state::<JobQueue>()
+                .submit(app_handle, JobKind::EmbedClips { clip_ids: vec![clip_id] })?;
+        }
+    }
+
+    set_status(&conn, clip_id, "done", Some(&text), None)?;
+    get_clip_ocr(&conn, clip_id)?.ok_or_else(|| AppError::database(format!("OCR status for clip {} vanished", clip_id)))
+}
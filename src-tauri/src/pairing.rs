@@ -0,0 +1,376 @@
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::clips::{now_millis, ClipData};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::notifications::{notify, NotificationKind};
+use crate::secrets::SecretsManager;
+
+/// Secret every clipper token is derived from; replacing it unpairs every clipper
+pub const PAIRING_KEY_SECRET: &str = "clipper_pairing_key";
+/// How long a pairing request waits for the user, and then how long its code stays valid
+const PAIRING_CODE_TTL_MS: i64 = 2 * 60 * 1000;
+/// Wrong codes allowed per pairing request before it's dropped
+const MAX_CODE_ATTEMPTS: u32 = 5;
+/// Wrong codes allowed across all pairing requests within `FAILED_CONFIRM_WINDOW_MS`
+const MAX_FAILED_CONFIRMS: u32 = 10;
+const FAILED_CONFIRM_WINDOW_MS: i64 = 10 * 60 * 1000;
+/// How long pairing is refused after too many wrong codes
+const PAIRING_LOCKOUT_MS: i64 = 15 * 60 * 1000;
+/// Pairing requests waiting for their code at once; more are refused
+const MAX_PENDING: usize = 10;
+/// Signed submissions over the network must be this fresh, which limits replays
+const MAX_CLOCK_SKEW_MS: i64 = 5 * 60 * 1000;
+
+/// A clip as a paired clipper submits it, over HTTP, WebSocket or the drop folder
+#[derive(Debug, Deserialize)]
+pub struct SignedClip {
+    pub client_id: String,
+    /// Milliseconds since the epoch
+    pub timestamp: i64,
+    /// The ClipData JSON exactly as signed
+    pub payload: String,
+    /// Hex HMAC-SHA256 of `{timestamp}.{payload}`, keyed with the clipper's token
+    pub signature: String,
+}
+
+/// `POST /pair` from a clipper that wants to be paired
+#[derive(Debug, Deserialize)]
+pub struct PairingRequest {
+    /// Shown next to the code, e.g. `Firefox on laptop`
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PairingStarted {
+    pub pairing_id: String,
+    pub expires_at: i64,
+}
+
+/// Emitted as `clipper-pairing-requested` so the app can ask the user to approve it
+#[derive(Debug, Serialize, Clone)]
+struct PairingPrompt<'a> {
+    pairing_id: &'a str,
+    name: &'a str,
+    expires_at: i64,
+}
+
+/// The code of an approved pairing request, shown in the app for the user to enter in the clipper
+#[derive(Debug, Serialize, Clone)]
+pub struct PairingCode {
+    pub pairing_id: String,
+    pub name: String,
+    pub code: String,
+    pub expires_at: i64,
+}
+
+/// `POST /pair/confirm` with the code the user read off the app
+#[derive(Debug, Deserialize)]
+pub struct PairingConfirm {
+    pub pairing_id: String,
+    pub code: String,
+}
+
+/// What a clipper gets back once paired; it signs every submission with `token`
+#[derive(Debug, Serialize)]
+pub struct PairedCredentials {
+    pub client_id: String,
+    pub token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PairedClipper {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+    pub last_seen_at: Option<i64>,
+}
+
+struct PendingPairing {
+    name: String,
+    code: String,
+    expires_at: i64,
+    attempts: u32,
+    /// The code only works once the user has approved the request in the app
+    approved: bool,
+}
+
+/// Wrong codes entered across every pairing request, so guessing can't be spread
+/// over many requests
+#[derive(Default)]
+struct FailedConfirms {
+    window_start: i64,
+    count: u32,
+    locked_until: i64,
+}
+
+/// Pairing requests waiting for approval or their code and the key clipper tokens
+/// derive from, managed as Tauri state. The key is loaded by `load_key` so verifying
+/// a clip never waits on the secrets store.
+#[derive(Default)]
+pub struct ClipperPairing {
+    key: Mutex<Option<Vec<u8>>>,
+    pending: Mutex<HashMap<String, PendingPairing>>,
+    failures: Mutex<FailedConfirms>,
+}
+
+impl ClipperPairing {
+    /// Read the pairing key from the active profile's secrets, creating it on first run
+    pub async fn load_key(&self, secrets: &SecretsManager) -> Result<(), AppError> {
+        let key = if secrets.has_secret(PAIRING_KEY_SECRET).await? {
+            secrets.get_secret(PAIRING_KEY_SECRET, "clipper_pairing").await?
+        } else {
+            let key = hex(&rand::random::<[u8; 32]>());
+            secrets.store_secret(PAIRING_KEY_SECRET.to_string(), key.clone()).await?;
+            key
+        };
+        *self.key.lock().unwrap() = Some(key.into_bytes());
+        self.pending.lock().unwrap().clear();
+        Ok(())
+    }
+
+    fn token(&self, client_id: &str) -> Result<String, AppError> {
+        let key = self.key.lock().unwrap();
+        let key = key.as_ref().ok_or_else(|| AppError::internal("The clipper pairing key isn't loaded"))?;
+        Ok(hex(&hmac_sha256(key, format!("clipper:{}", client_id).as_bytes())))
+    }
+
+    fn check_lockout(&self, now: i64) -> Result<(), AppError> {
+        if self.failures.lock().unwrap().locked_until > now {
+            return Err(AppError::auth("Too many wrong pairing codes; try again later"));
+        }
+        Ok(())
+    }
+
+    /// Count a wrong code; too many within the window refuses all pairing for a while
+    /// and drops every pending request
+    fn record_failure(&self, now: i64) {
+        let mut failures = self.failures.lock().unwrap();
+        if now - failures.window_start > FAILED_CONFIRM_WINDOW_MS {
+            failures.window_start = now;
+            failures.count = 0;
+        }
+        failures.count += 1;
+        if failures.count >= MAX_FAILED_CONFIRMS {
+            failures.locked_until = now + PAIRING_LOCKOUT_MS;
+            failures.count = 0;
+            self.pending.lock().unwrap().clear();
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Constant-time comparison so codes and signatures can't be guessed byte by byte
+fn matches(expected: &str, candidate: &str) -> bool {
+    let (expected, candidate) = (expected.as_bytes(), candidate.as_bytes());
+    expected.len() == candidate.len() && expected.iter().zip(candidate).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Start pairing a clipper: ask the user in the app to approve it. Approving shows
+/// the code the user then types into the clipper.
+pub fn request_pairing(app_handle: &AppHandle, request: PairingRequest) -> Result<PairingStarted, AppError> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err(AppError::validation("Clipper name must not be empty"));
+    }
+    let pairing = app_handle.state::<ClipperPairing>();
+    let now = now_millis();
+    pairing.check_lockout(now)?;
+    let pairing_id = hex(&rand::random::<[u8; 16]>());
+    let code = format!("{:06}", rand::thread_rng().gen_range(0..1_000_000));
+    let expires_at = now + PAIRING_CODE_TTL_MS;
+    {
+        let mut pending = pairing.pending.lock().unwrap();
+        pending.retain(|_, p| p.expires_at > now);
+        if pending.len() >= MAX_PENDING {
+            return Err(AppError::validation("Too many pairing requests; try again in a few minutes"));
+        }
+        pending.insert(
+            pairing_id.clone(),
+            PendingPairing {
+                name: name.to_string(),
+                code: code.clone(),
+                expires_at,
+                attempts: 0,
+                approved: false,
+            },
+        );
+    }
+
+    let prompt = PairingPrompt {
+        pairing_id: &pairing_id,
+        name,
+        expires_at,
+    };
+    app_handle
+        .emit("clipper-pairing-requested", &prompt)
+        .map_err(|e| AppError::internal(format!("Failed to emit pairing event: {}", e)))?;
+    let body = format!("{} wants to pair; approve it in the app", name);
+    notify(app_handle, NotificationKind::Capture, "Pair clipper", &body);
+    Ok(PairingStarted { pairing_id, expires_at })
+}
+
+/// Let a pairing request's code be used and return it for the app to show. The
+/// clipper has `PAIRING_CODE_TTL_MS` from now to confirm with it.
+pub fn approve_pairing(pairing: &ClipperPairing, pairing_id: &str) -> Result<PairingCode, AppError> {
+    let now = now_millis();
+    pairing.check_lockout(now)?;
+    let mut pending = pairing.pending.lock().unwrap();
+    let entry = pending
+        .get_mut(pairing_id)
+        .filter(|p| p.expires_at > now)
+        .ok_or_else(|| AppError::not_found("Pairing request expired; start pairing again"))?;
+    entry.approved = true;
+    entry.expires_at = now + PAIRING_CODE_TTL_MS;
+    Ok(PairingCode {
+        pairing_id: pairing_id.to_string(),
+        name: entry.name.clone(),
+        code: entry.code.clone(),
+        expires_at: entry.expires_at,
+    })
+}
+
+/// Turn down a pairing request so its code can never be used
+pub fn reject_pairing(pairing: &ClipperPairing, pairing_id: &str) -> Result<(), AppError> {
+    pairing
+        .pending
+        .lock()
+        .unwrap()
+        .remove(pairing_id)
+        .map(|_| ())
+        .ok_or_else(|| AppError::not_found(format!("Pairing request {} not found", pairing_id)))
+}
+
+/// Finish pairing with the code the user entered in the clipper
+pub fn confirm_pairing(app_handle: &AppHandle, confirm: PairingConfirm) -> Result<PairedCredentials, AppError> {
+    let pairing = app_handle.state::<ClipperPairing>();
+    let now = now_millis();
+    pairing.check_lockout(now)?;
+    let name = {
+        let mut pending = pairing.pending.lock().unwrap();
+        let entry = pending
+            .get_mut(&confirm.pairing_id)
+            .filter(|p| p.expires_at > now)
+            .ok_or_else(|| AppError::auth("Pairing request expired; start pairing again"))?;
+        if !entry.approved {
+            return Err(AppError::auth("Pairing request hasn't been approved in the app yet"));
+        }
+        if !matches(&entry.code, confirm.code.trim()) {
+            entry.attempts += 1;
+            if entry.attempts >= MAX_CODE_ATTEMPTS {
+                pending.remove(&confirm.pairing_id);
+            }
+            drop(pending);
+            pairing.record_failure(now);
+            return Err(AppError::auth("Wrong pairing code"));
+        }
+        pending.remove(&confirm.pairing_id).map(|p| p.name).unwrap_or_default()
+    };
+
+    let client_id = hex(&rand::random::<[u8; 16]>());
+    let token = pairing.token(&client_id)?;
+    let clipper = PairedClipper {
+        id: client_id.clone(),
+        name,
+        created_at: now_millis(),
+        last_seen_at: None,
+    };
+    let (id, name, created_at) = (clipper.id.clone(), clipper.name.clone(), clipper.created_at);
+    app_handle.state::<Database>().write(move |conn| {
+        conn.execute(
+            "INSERT INTO paired_clippers (id, name, created_at) VALUES (?1, ?2, ?3)",
+            params![id, name, created_at],
+        )
+        .map_err(|e| AppError::database(format!("Failed to save paired clipper: {}", e)))
+    })?;
+    events::emit(app_handle, "clippers-changed", &clipper)
+        .map_err(|e| AppError::internal(format!("Failed to emit clipper event: {}", e)))?;
+    Ok(PairedCredentials { client_id, token })
+}
+
+/// Check that a submission comes from a paired clipper and return the clip in it.
+/// Drop-folder files can sit for a while before the app sees them, so only network
+/// submissions are held to `MAX_CLOCK_SKEW_MS`.
+pub fn verify(app_handle: &AppHandle, signed: &SignedClip, check_freshness: bool) -> Result<ClipData, AppError> {
+    let db = app_handle.state::<Database>();
+    let known: bool = db
+        .conn()?
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM paired_clippers WHERE id = ?1 AND revoked_at IS NULL)",
+            params![signed.client_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::database(format!("Failed to read paired clippers: {}", e)))?;
+    if !known {
+        return Err(AppError::auth("Unknown or revoked clipper; pair it again"));
+    }
+
+    let token = app_handle.state::<ClipperPairing>().token(&signed.client_id)?;
+    let expected = hex(&hmac_sha256(token.as_bytes(), format!("{}.{}", signed.timestamp, signed.payload).as_bytes()));
+    if !matches(&expected, &signed.signature.to_ascii_lowercase()) {
+        return Err(AppError::auth("Invalid clip signature"));
+    }
+    if check_freshness && (now_millis() - signed.timestamp).abs() > MAX_CLOCK_SKEW_MS {
+        return Err(AppError::auth("Clip submission expired; check the clock"));
+    }
+
+    let (client_id, seen_at) = (signed.client_id.clone(), now_millis());
+    db.write(move |conn| {
+        conn.execute("UPDATE paired_clippers SET last_seen_at = ?1 WHERE id = ?2", params![seen_at, client_id])
+            .map_err(|e| AppError::database(format!("Failed to update paired clipper: {}", e)))
+    })?;
+    serde_json::from_str(&signed.payload).map_err(|e| AppError::validation(format!("Invalid clip payload: {}", e)))
+}
+
+pub fn list_paired_clippers(conn: &Connection) -> Result<Vec<PairedClipper>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, created_at, last_seen_at FROM paired_clippers
+             WHERE revoked_at IS NULL ORDER BY created_at",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let clippers = stmt
+        .query_map([], |row| {
+            Ok(PairedClipper {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                created_at: row.get(2)?,
+                last_seen_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to read paired clippers: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read paired clipper: {}", e)))?;
+    Ok(clippers)
+}
+
+/// Stop accepting clips from a clipper. It has to be paired again to send more.
+pub fn revoke_clipper(conn: &Connection, id: &str) -> Result<(), AppError> {
+    let revoked = conn
+        .execute(
+            "UPDATE paired_clippers SET revoked_at = ?1 WHERE id = ?2 AND revoked_at IS NULL",
+            params![now_millis(), id],
+        )
+        .map_err(|e| AppError::database(format!("Failed to revoke clipper: {}", e)))?;
+    if revoked == 0 {
+        return Err(AppError::not_found(format!("Clipper {} not found", id)));
+    }
+    Ok(())
+}
@@ -0,0 +1,283 @@
+use lopdf::{Dictionary, Document, Object};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+use crate::clips::{now_millis, ClipData, ClipInsert};
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::http;
+
+/// Refuse to store anything larger than this
+const MAX_PDF_BYTES: usize = 100 * 1024 * 1024;
+
+/// Fields of the PDF document info dictionary
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PdfMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creator: Option<String>,
+    pub producer: Option<String>,
+    /// Raw PDF date string, e.g. `D:20240131120000Z`
+    pub created: Option<String>,
+    pub modified: Option<String>,
+}
+
+/// A PDF clip's file as stored on disk
+#[derive(Debug, Serialize)]
+pub struct ClipPdf {
+    pub clip_id: i64,
+    /// Absolute path of the local copy
+    pub path: PathBuf,
+    /// Hex SHA-256 of the file
+    pub hash: String,
+    pub size: u64,
+    pub page_count: u32,
+    pub metadata: PdfMetadata,
+}
+
+/// Text and metadata pulled out of a PDF
+struct ParsedPdf {
+    text: String,
+    page_count: u32,
+    metadata: PdfMetadata,
+}
+
+/// Read the PDF from an http(s) URL or a local path. Returns the bytes, the URL
+/// (for remote files) and the file name to fall back on for the title.
+async fn load(source: &str) -> Result<(Vec<u8>, Option<String>, Option<String>), AppError> {
+    let source = source.trim();
+    if let Ok(url) = reqwest::Url::parse(source) {
+        if matches!(url.scheme(), "http" | "https") {
+            let bytes = download(&url).await?;
+            let name = url
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .filter(|name| !name.is_empty())
+                .map(str::to_string);
+            return Ok((bytes, Some(url.to_string()), name));
+        }
+    }
+
+    let path = PathBuf::from(source);
+    let size = fs::metadata(&path)
+        .map_err(|e| AppError::validation(format!("Failed to read {}: {}", path.display(), e)))?
+        .len();
+    if size as usize > MAX_PDF_BYTES {
+        return Err(AppError::validation("PDF is too large to store"));
+    }
+    let bytes = fs::read(&path).map_err(|e| AppError::internal(format!("Failed to read {}: {}", path.display(), e)))?;
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned());
+    Ok((bytes, None, name))
+}
+
+async fn download(url: &reqwest::Url) -> Result<Vec<u8>, AppError> {
+    let response = http::client()
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to download PDF: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("Failed to download PDF: HTTP {}", response.status())));
+    }
+    if response.content_length().map(|len| len as usize > MAX_PDF_BYTES).unwrap_or(false) {
+        return Err(AppError::validation("PDF is too large to store"));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to download PDF: {}", e)))?;
+    if bytes.len() > MAX_PDF_BYTES {
+        return Err(AppError::validation("PDF is too large to store"));
+    }
+    Ok(bytes.to_vec())
+}
+
+/// Decode a PDF text string: UTF-16BE with a byte order mark, otherwise
+/// PDFDocEncoding, which matches Latin-1 for printable characters
+fn decode_text(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+fn info_dictionary(doc: &Document) -> Option<&Dictionary> {
+    match doc.trailer.get(b"Info").ok()? {
+        Object::Reference(id) => doc.get_dictionary(*id).ok(),
+        Object::Dictionary(dict) => Some(dict),
+        _ => None,
+    }
+}
+
+fn read_metadata(doc: &Document) -> PdfMetadata {
+    let Some(info) = info_dictionary(doc) else {
+        return PdfMetadata::default();
+    };
+    let field = |key: &[u8]| {
+        let value = match info.get(key).ok()? {
+            Object::Reference(id) => doc.get_object(*id).ok()?,
+            value => value,
+        };
+        match value {
+            Object::String(bytes, _) => Some(decode_text(bytes).trim().to_string()).filter(|s| !s.is_empty()),
+            _ => None,
+        }
+    };
+    PdfMetadata {
+        title: field(b"Title"),
+        author: field(b"Author"),
+        subject: field(b"Subject"),
+        keywords: field(b"Keywords"),
+        creator: field(b"Creator"),
+        producer: field(b"Producer"),
+        created: field(b"CreationDate"),
+        modified: field(b"ModDate"),
+    }
+}
+
+/// Page count, metadata and the text of every page. Pages whose text can't be
+/// extracted (scans, unusual font encodings) are skipped rather than failing the clip.
+fn parse(bytes: &[u8]) -> Result<ParsedPdf, AppError> {
+    let doc = Document::load_mem(bytes).map_err(|e| AppError::validation(format!("Failed to read PDF: {}", e)))?;
+    if doc.is_encrypted() {
+        return Err(AppError::validation("PDF is password protected"));
+    }
+
+    let pages: Vec<u32> = doc.get_pages().keys().copied().collect();
+    let mut text = String::new();
+    for page in &pages {
+        match doc.extract_text(&[*page]) {
+            Ok(page_text) if !page_text.trim().is_empty() => {
+                if !text.is_empty() {
+                    text.push_str("\n\n");
+                }
+                text.push_str(page_text.trim());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to extract text from PDF page {}: {}", page, e),
+        }
+    }
+
+    Ok(ParsedPdf {
+        text,
+        page_count: pages.len() as u32,
+        metadata: read_metadata(&doc),
+    })
+}
+
+/// Write the PDF into `media_dir`, named by content hash. Returns the file name and hash.
+fn store_file(bytes: &[u8], media_dir: &Path) -> Result<(String, String), AppError> {
+    let hash: String = Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    let file_name = format!("{}.pdf", hash);
+    let path = media_dir.join(&file_name);
+    if !path.exists() {
+        let tmp = media_dir.join(format!(".{}.part", file_name));
+        let mut file = fs::File::create(&tmp).map_err(|e| AppError::internal(format!("Failed to write PDF: {}", e)))?;
+        file.write_all(bytes).map_err(|e| AppError::internal(format!("Failed to write PDF: {}", e)))?;
+        fs::rename(&tmp, &path).map_err(|e| AppError::internal(format!("Failed to write PDF: {}", e)))?;
+    }
+    Ok((file_name, hash))
+}
+
+fn save_document(conn: &Connection, clip_id: i64, file_name: &str, hash: &str, size: u64, parsed: &ParsedPdf) -> Result<(), AppError> {
+    let metadata = serde_json::to_string(&parsed.metadata)
+        .map_err(|e| AppError::internal(format!("Failed to serialize PDF metadata: {}", e)))?;
+    conn.execute(
+        "INSERT INTO clip_documents (clip_id, file_name, file_hash, size, page_count, metadata)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (clip_id) DO UPDATE SET
+            file_name = excluded.file_name, file_hash = excluded.file_hash, size = excluded.size,
+            page_count = excluded.page_count, metadata = excluded.metadata",
+        params![clip_id, file_name, hash, size as i64, parsed.page_count, metadata],
+    )
+    .map_err(|e| AppError::database(format!("Failed to store PDF details: {}", e)))?;
+    Ok(())
+}
+
+/// Clip a PDF from a file path or http(s) URL: keep a copy in the media dir, put its
+/// text in the clip's content so it's searchable and summarizable, and record the
+/// page count and document metadata.
+pub async fn clip_pdf(app_handle: &AppHandle, source: &str) -> Result<ClipInsert, AppError> {
+    let (bytes, url, file_name) = load(source).await?;
+    if !bytes.starts_with(b"%PDF-") {
+        return Err(AppError::validation("File is not a PDF"));
+    }
+
+    let media_dir = app_handle.state::<AppConfig>().media_dir();
+    let (bytes, parsed, stored_name, hash) = tauri::async_runtime::spawn_blocking(move || {
+        let parsed = parse(&bytes)?;
+        let (stored_name, hash) = store_file(&bytes, &media_dir)?;
+        Ok::<_, AppError>((bytes, parsed, stored_name, hash))
+    })
+    .await
+    .map_err(|e| AppError::internal(format!("PDF extraction failed: {}", e)))??;
+
+    let title = parsed
+        .metadata
+        .title
+        .clone()
+        .or(file_name)
+        .unwrap_or_else(|| "Untitled PDF".to_string());
+    let clip_data = ClipData {
+        r#type: "pdf".to_string(),
+        title,
+        url,
+        content: Some(parsed.text.clone()).filter(|text| !text.is_empty()),
+        image_url: None,
+        description: parsed.metadata.subject.clone(),
+        author: parsed.metadata.author.clone(),
+        timestamp: now_millis() as u64,
+        original_url: None,
+        language: None,
+    };
+    let inserted = crate::ingest_clip(app_handle, clip_data)?;
+
+    let db = app_handle.state::<Database>();
+    save_document(&db.conn()?, inserted.clip.id, &stored_name, &hash, bytes.len() as u64, &parsed)?;
+    Ok(inserted)
+}
+
+/// The stored file and metadata of a PDF clip
+pub fn get_clip_pdf(app_handle: &AppHandle, clip_id: i64) -> Result<ClipPdf, AppError> {
+    let db = app_handle.state::<Database>();
+    let conn = db.conn()?;
+    let (file_name, hash, size, page_count, metadata) = conn
+        .query_row(
+            "SELECT file_name, file_hash, size, page_count, metadata FROM clip_documents WHERE clip_id = ?1",
+            params![clip_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, u32>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read PDF details: {}", e)))?
+        .ok_or_else(|| AppError::not_found(format!("Clip {} has no stored PDF", clip_id)))?;
+
+    Ok(ClipPdf {
+        clip_id,
+        path: app_handle.state::<AppConfig>().media_dir().join(file_name),
+        hash,
+        size: size as u64,
+        page_count,
+        metadata: serde_json::from_str(&metadata).unwrap_or_default(),
+    })
+}
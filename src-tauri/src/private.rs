@@ -0,0 +1,403 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+use crate::annotations::{self, Annotation};
+use crate::clips::{self, now_millis, SqliteClip};
+use crate::db::Database;
+use crate::errors::AppError;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Encrypted into `private_vault` to check the passphrase on unlock
+const VERIFIER: &[u8] = b"los-private-clips";
+
+/// What a private clip keeps in `encrypted_body` instead of the plain columns
+#[derive(Serialize, Deserialize)]
+struct PrivateBody {
+    content: Option<String>,
+    description: Option<String>,
+    summary: Option<String>,
+    /// Highlights and notes, put back in `annotations` when the clip is made normal again
+    #[serde(default)]
+    annotations: Vec<PrivateAnnotation>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PrivateAnnotation {
+    start: Option<i64>,
+    end: Option<i64>,
+    quote: Option<String>,
+    note: Option<String>,
+    color: String,
+    created_at: i64,
+    updated_at: i64,
+}
+
+impl From<Annotation> for PrivateAnnotation {
+    fn from(annotation: Annotation) -> Self {
+        PrivateAnnotation {
+            start: annotation.start,
+            end: annotation.end,
+            quote: annotation.quote,
+            note: annotation.note,
+            color: annotation.color,
+            created_at: annotation.created_at,
+            updated_at: annotation.updated_at,
+        }
+    }
+}
+
+/// Keys for private clips while they're unlocked, managed as Tauri state. Nothing
+/// is written to disk; `lock` or a restart locks them again.
+#[derive(Default)]
+pub struct PrivateVault {
+    session: Mutex<Option<Session>>,
+}
+
+struct Session {
+    passphrase: String,
+    /// This database's salt; clips made private here are encrypted with its key
+    salt: Vec<u8>,
+    /// Derived keys by salt. Clips synced from another device carry that device's salt.
+    keys: HashMap<Vec<u8>, Key<Aes256Gcm>>,
+}
+
+impl PrivateVault {
+    pub fn is_unlocked(&self) -> bool {
+        self.session.lock().unwrap().is_some()
+    }
+
+    pub fn lock(&self) {
+        *self.session.lock().unwrap() = None;
+    }
+
+    fn encrypt(&self, body: &PrivateBody) -> Result<String, AppError> {
+        let session = self.session.lock().unwrap();
+        let session = session.as_ref().ok_or_else(locked)?;
+        let plaintext = serde_json::to_vec(body)
+            .map_err(|e| AppError::internal(format!("Failed to serialize private clip: {}", e)))?;
+        seal(&session.keys[&session.salt], &session.salt, &plaintext)
+    }
+
+    fn decrypt(&self, sealed: &str) -> Result<PrivateBody, AppError> {
+        let (salt, data) = split(sealed)?;
+        let mut session = self.session.lock().unwrap();
+        let session = session.as_mut().ok_or_else(locked)?;
+        let key = match session.keys.get(&salt) {
+            Some(key) => *key,
+            None => {
+                let key = derive_key(&session.passphrase, &salt)?;
+                session.keys.insert(salt, key);
+                key
+            }
+        };
+        let plaintext = open(&key, &data)?;
+        serde_json::from_slice(&plaintext).map_err(|e| AppError::internal(format!("Unreadable private clip: {}", e)))
+    }
+}
+
+fn locked() -> AppError {
+    AppError::auth("Private clips are locked; unlock them first")
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key<Aes256Gcm>, AppError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| AppError::internal(format!("Failed to derive private clip key: {}", e)))?;
+    Ok(*Key::<Aes256Gcm>::from_slice(&key))
+}
+
+/// Base64 of salt, nonce and ciphertext, so any device with the passphrase can open it
+fn seal(key: &Key<Aes256Gcm>, salt: &[u8], plaintext: &[u8]) -> Result<String, AppError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|e| AppError::internal(format!("Failed to encrypt private clip: {}", e)))?;
+    Ok(BASE64.encode([salt, nonce.as_slice(), ciphertext.as_slice()].concat()))
+}
+
+/// The salt and the rest of a value made by `seal`
+fn split(sealed: &str) -> Result<(Vec<u8>, Vec<u8>), AppError> {
+    let mut data = BASE64
+        .decode(sealed)
+        .map_err(|e| AppError::validation(format!("Corrupt private clip data: {}", e)))?;
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err(AppError::validation("Private clip data is truncated"));
+    }
+    let rest = data.split_off(SALT_LEN);
+    Ok((data, rest))
+}
+
+fn open(key: &Key<Aes256Gcm>, data: &[u8]) -> Result<Vec<u8>, AppError> {
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    Aes256Gcm::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| AppError::auth("Failed to decrypt private clip (wrong passphrase or corrupted data)"))
+}
+
+/// Unlock private clips until `lock` or a restart. The first unlock on a database sets
+/// its passphrase; later ones must match it.
+pub async fn unlock(app_handle: &AppHandle, passphrase: String) -> Result<(), AppError> {
+    if passphrase.is_empty() {
+        return Err(AppError::validation("Passphrase must not be empty"));
+    }
+    let verifier: Option<String> = app_handle
+        .state::<Database>()
+        .conn()?
+        .query_row("SELECT verifier FROM private_vault WHERE id = 1", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read private vault: {}", e)))?;
+    let salt = match &verifier {
+        Some(verifier) => split(verifier)?.0,
+        None => {
+            let mut salt = vec![0u8; SALT_LEN];
+            rand::thread_rng().fill_bytes(&mut salt);
+            salt
+        }
+    };
+
+    // Argon2 is deliberately slow; keep it off the async workers
+    let (kdf_passphrase, kdf_salt) = (passphrase.clone(), salt.clone());
+    let key = tauri::async_runtime::spawn_blocking(move || derive_key(&kdf_passphrase, &kdf_salt))
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to derive private clip key: {}", e)))??;
+
+    match verifier {
+        Some(verifier) => {
+            let (_, data) = split(&verifier)?;
+            if open(&key, &data).ok().as_deref() != Some(VERIFIER) {
+                return Err(AppError::auth("Wrong passphrase for private clips"));
+            }
+        }
+        None => {
+            let verifier = seal(&key, &salt, VERIFIER)?;
+            app_handle.state::<Database>().write_async(move |conn| {
+                conn.execute(
+                    "INSERT INTO private_vault (id, verifier, created_at) VALUES (1, ?1, ?2)",
+                    params![verifier, now_millis()],
+                )
+                .map_err(|e| AppError::database(format!("Failed to set up private clips: {}", e)))
+            })
+            .await?;
+        }
+    }
+
+    *app_handle.state::<PrivateVault>().session.lock().unwrap() = Some(Session {
+        passphrase,
+        keys: HashMap::from([(salt.clone(), key)]),
+        salt,
+    });
+    seal_annotations(app_handle).await
+}
+
+/// Move annotations still stored in plaintext on private clips, made private before
+/// annotations were sealed with them, into their encrypted bodies
+async fn seal_annotations(app_handle: &AppHandle) -> Result<(), AppError> {
+    let vault = app_handle.state::<PrivateVault>();
+    let db = app_handle.state::<Database>();
+    let leftovers: Vec<(i64, Option<String>)> = {
+        let conn = db.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, encrypted_body FROM clips
+                 WHERE private = 1 AND id IN (SELECT clip_id FROM annotations)",
+            )
+            .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+        let leftovers = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| AppError::database(format!("Failed to read private clips: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::database(format!("Failed to read private clip: {}", e)))?;
+        leftovers
+    };
+
+    for (id, sealed) in leftovers {
+        let mut body = match sealed.as_deref().map(|sealed| vault.decrypt(sealed)).transpose() {
+            Ok(body) => body.unwrap_or(PrivateBody {
+                content: None,
+                description: None,
+                summary: None,
+                annotations: Vec::new(),
+            }),
+            // Sealed with another device's passphrase; that device can fold them in
+            Err(e) => {
+                warn!("Couldn't decrypt private clip {}: {}", id, e);
+                continue;
+            }
+        };
+        let clip_annotations = annotations::list_annotations(&db.conn()?, id)?;
+        let annotation_ids: Vec<i64> = clip_annotations.iter().map(|annotation| annotation.id).collect();
+        body.annotations.extend(clip_annotations.into_iter().map(PrivateAnnotation::from));
+        let sealed = vault.encrypt(&body)?;
+        db.write_async(move |conn| {
+            let tx = conn
+                .transaction()
+                .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+            tx.execute("UPDATE clips SET encrypted_body = ?1 WHERE id = ?2", params![sealed, id])
+                .map_err(|e| AppError::database(format!("Failed to seal annotations: {}", e)))?;
+            for annotation_id in &annotation_ids {
+                tx.execute("DELETE FROM annotations WHERE id = ?1", params![annotation_id])
+                    .map_err(|e| AppError::database(format!("Failed to remove annotation: {}", e)))?;
+            }
+            tx.commit()
+                .map_err(|e| AppError::database(format!("Failed to commit transaction: {}", e)))
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+/// Fill in a private clip's content, description and summary if private clips are
+/// unlocked; otherwise they stay `null`
+pub fn reveal(vault: &PrivateVault, conn: &Connection, clip: &mut SqliteClip) -> Result<(), AppError> {
+    if !clip.private || !vault.is_unlocked() {
+        return Ok(());
+    }
+    let sealed: Option<String> = conn
+        .query_row("SELECT encrypted_body FROM clips WHERE id = ?1", params![clip.id], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+    let Some(sealed) = sealed else {
+        return Ok(());
+    };
+    match vault.decrypt(&sealed) {
+        Ok(body) => {
+            clip.content = body.content;
+            clip.description = body.description;
+            clip.summary = body.summary;
+        }
+        // Most likely synced from a device that uses another passphrase
+        Err(e) => warn!("Couldn't decrypt private clip {}: {}", clip.id, e),
+    }
+    Ok(())
+}
+
+/// Make a clip private, encrypting its content, description, summary and annotations and
+/// dropping its embedding, revisions and content hash, or turn it back into a normal clip.
+/// Either way private clips must be unlocked. Returns the clip with its content revealed if
+/// it's now a normal clip; a private one comes back with those fields `null`, as `get_clip`
+/// would show it while locked, since the result is also broadcast as `clip-updated`.
+pub async fn set_clip_private(app_handle: &AppHandle, id: i64, private: bool) -> Result<SqliteClip, AppError> {
+    let vault = app_handle.state::<PrivateVault>();
+    let db = app_handle.state::<Database>();
+    let (mut clip, clip_annotations) = {
+        let conn = db.conn()?;
+        let mut clip =
+            clips::get_clip(&conn, id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", id)))?;
+        reveal(&vault, &conn, &mut clip)?;
+        (clip, annotations::list_annotations(&conn, id)?)
+    };
+    if !vault.is_unlocked() {
+        return Err(locked());
+    }
+    if clip.private == private {
+        if private {
+            conceal(&mut clip);
+        }
+        return Ok(clip);
+    }
+
+    let updated_at = now_millis().max(clip.updated_at + 1);
+    if private {
+        let sealed = vault.encrypt(&PrivateBody {
+            content: clip.content.clone(),
+            description: clip.description.clone(),
+            summary: clip.summary.clone(),
+            annotations: clip_annotations.into_iter().map(PrivateAnnotation::from).collect(),
+        })?;
+        db.write_async(move |conn| {
+            let tx = conn
+                .transaction()
+                .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+            tx.execute(
+                "UPDATE clips SET private = 1, encrypted_body = ?1, content = NULL, description = NULL,
+                    summary = NULL, content_hash = NULL, updated_at = ?2
+                 WHERE id = ?3",
+                params![sealed, updated_at, id],
+            )
+            .map_err(|e| AppError::database(format!("Failed to make clip private: {}", e)))?;
+            tx.execute("DELETE FROM clip_embeddings WHERE clip_id = ?1", params![id])
+                .map_err(|e| AppError::database(format!("Failed to remove embedding: {}", e)))?;
+            tx.execute("DELETE FROM clip_revisions WHERE clip_id = ?1", params![id])
+                .map_err(|e| AppError::database(format!("Failed to remove revisions: {}", e)))?;
+            // Their quotes and notes leave `annotations_fts` through its trigger
+            tx.execute("DELETE FROM annotations WHERE clip_id = ?1", params![id])
+                .map_err(|e| AppError::database(format!("Failed to remove annotations: {}", e)))?;
+            tx.commit()
+                .map_err(|e| AppError::database(format!("Failed to commit transaction: {}", e)))
+        })
+        .await?;
+    } else {
+        // `reveal` leaves the fields empty if the clip couldn't be decrypted
+        let sealed: Option<String> = db
+            .conn()?
+            .query_row("SELECT encrypted_body FROM clips WHERE id = ?1", params![id], |row| row.get(0))
+            .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+        let body = match sealed {
+            Some(sealed) => vault.decrypt(&sealed)?,
+            None => PrivateBody {
+                content: None,
+                description: None,
+                summary: None,
+                annotations: Vec::new(),
+            },
+        };
+        let hash = clips::content_hash(body.content.as_deref());
+        db.write_async(move |conn| {
+            let tx = conn
+                .transaction()
+                .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+            tx.execute(
+                "UPDATE clips SET private = 0, encrypted_body = NULL, content = ?1, description = ?2,
+                    summary = ?3, content_hash = ?4, updated_at = ?5
+                 WHERE id = ?6",
+                params![body.content, body.description, body.summary, hash, updated_at, id],
+            )
+            .map_err(|e| AppError::database(format!("Failed to make clip public: {}", e)))?;
+            for annotation in &body.annotations {
+                tx.execute(
+                    "INSERT INTO annotations (clip_id, start_offset, end_offset, quote, note, color, created_at,
+                        updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        id,
+                        annotation.start,
+                        annotation.end,
+                        annotation.quote,
+                        annotation.note,
+                        annotation.color,
+                        annotation.created_at,
+                        annotation.updated_at,
+                    ],
+                )
+                .map_err(|e| AppError::database(format!("Failed to restore annotation: {}", e)))?;
+            }
+            tx.commit()
+                .map_err(|e| AppError::database(format!("Failed to commit transaction: {}", e)))
+        })
+        .await?;
+    }
+
+    clip.private = private;
+    clip.updated_at = updated_at;
+    if private {
+        conceal(&mut clip);
+    }
+    Ok(clip)
+}
+
+/// Clear the fields a private clip only keeps encrypted
+fn conceal(clip: &mut SqliteClip) {
+    clip.content = None;
+    clip.description = None;
+    clip.summary = None;
+}
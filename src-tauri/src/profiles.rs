@@ -0,0 +1,343 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tracing::error;
+
+use crate::clipboard::ClipboardMonitor;
+use crate::clips::now_millis;
+use crate::config::AppConfig;
+use crate::db::{Database, DATABASE_KEY_SECRET};
+use crate::errors::AppError;
+use crate::app_lock;
+use crate::events::{self, EventJournal};
+use crate::hotkey;
+use crate::jobs::JobQueue;
+use crate::pairing::ClipperPairing;
+use crate::private::PrivateVault;
+use crate::secrets::{SecretsLocation, SecretsManager};
+use crate::settings::{Settings, SettingsManager};
+use crate::sync::SyncManager;
+use crate::tray::Tray;
+use crate::watcher::ClipWatcher;
+
+/// The profile that keeps using the locations LOS had before profiles existed
+pub const DEFAULT_PROFILE_ID: &str = "default";
+const PROFILES_FILE: &str = "profiles.json";
+const MAX_ID_LEN: usize = 40;
+
+/// An isolated set of clips, media and settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    /// Use the secrets shared by all such profiles instead of a set of its own
+    pub shared_secrets: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileInfo {
+    #[serde(flatten)]
+    pub profile: Profile,
+    pub active: bool,
+    pub data_dir: PathBuf,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ProfilesFile {
+    active: Option<String>,
+    profiles: Vec<Profile>,
+}
+
+/// Known profiles and which one is active, persisted as JSON in the app config dir.
+/// Managed as Tauri state.
+pub struct ProfileManager {
+    config_dir: PathBuf,
+    app_data_dir: PathBuf,
+    state: Mutex<ProfilesFile>,
+    /// Held for the whole of `switch_profile`
+    switching: tokio::sync::Mutex<()>,
+}
+
+impl ProfileManager {
+    /// Read the profile list, falling back to just the default profile
+    pub fn load(app_handle: &AppHandle) -> Result<Self, AppError> {
+        let config_dir = app_handle
+            .path()
+            .app_config_dir()
+            .map_err(|e| AppError::internal(format!("Failed to resolve app config directory: {}", e)))?;
+        let app_data_dir = app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|e| AppError::internal(format!("Failed to resolve app data directory: {}", e)))?;
+
+        let mut state: ProfilesFile = fs::read_to_string(config_dir.join(PROFILES_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        if !state.profiles.iter().any(|p| p.id == DEFAULT_PROFILE_ID) {
+            state.profiles.insert(
+                0,
+                Profile {
+                    id: DEFAULT_PROFILE_ID.to_string(),
+                    name: "Default".to_string(),
+                    shared_secrets: true,
+                    created_at: now_millis(),
+                },
+            );
+        }
+        if !state.profiles.iter().any(|p| Some(&p.id) == state.active.as_ref()) {
+            state.active = Some(DEFAULT_PROFILE_ID.to_string());
+        }
+
+        Ok(Self {
+            config_dir,
+            app_data_dir,
+            state: Mutex::new(state),
+            switching: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub fn active(&self) -> Profile {
+        let state = self.state.lock().unwrap();
+        state
+            .profiles
+            .iter()
+            .find(|p| Some(&p.id) == state.active.as_ref())
+            .cloned()
+            .expect("the active profile is always known")
+    }
+
+    pub fn get(&self, id: &str) -> Result<Profile, AppError> {
+        let state = self.state.lock().unwrap();
+        state
+            .profiles
+            .iter()
+            .find(|p| p.id == id)
+            .cloned()
+            .ok_or_else(|| AppError::not_found(format!("Profile '{}' not found", id)))
+    }
+
+    /// Every profile, in the order they were created
+    pub fn list(&self) -> Vec<ProfileInfo> {
+        let active = self.active();
+        let profiles = self.state.lock().unwrap().profiles.clone();
+        profiles
+            .into_iter()
+            .map(|profile| ProfileInfo {
+                active: profile.id == active.id,
+                data_dir: self.data_dir(&profile, &SettingsManager::read(&self.settings_path(&profile))),
+                profile,
+            })
+            .collect()
+    }
+
+    /// Add a profile named `name`. It starts out with default settings and an empty database.
+    pub fn create(&self, name: &str, shared_secrets: bool) -> Result<Profile, AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::validation("Profile name must not be empty"));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let base = profile_id(name);
+        let mut id = base.clone();
+        let mut n = 2;
+        while state.profiles.iter().any(|p| p.id == id) {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+        let profile = Profile {
+            id,
+            name: name.to_string(),
+            shared_secrets,
+            created_at: now_millis(),
+        };
+        state.profiles.push(profile.clone());
+        if let Err(e) = self.save(&state) {
+            state.profiles.pop();
+            return Err(e);
+        }
+        Ok(profile)
+    }
+
+    fn set_active(&self, id: &str) -> Result<(), AppError> {
+        let mut state = self.state.lock().unwrap();
+        let previous = state.active.replace(id.to_string());
+        if let Err(e) = self.save(&state) {
+            state.active = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn save(&self, state: &ProfilesFile) -> Result<(), AppError> {
+        fs::create_dir_all(&self.config_dir)
+            .map_err(|e| AppError::internal(format!("Failed to create config directory: {}", e)))?;
+        let json =
+            serde_json::to_string_pretty(state).map_err(|e| AppError::internal(format!("Failed to serialize profiles: {}", e)))?;
+        fs::write(self.config_dir.join(PROFILES_FILE), json)
+            .map_err(|e| AppError::internal(format!("Failed to write profiles: {}", e)))
+    }
+
+    /// The default profile keeps `settings.json` where it always was; others get a folder
+    pub fn settings_path(&self, profile: &Profile) -> PathBuf {
+        if profile.id == DEFAULT_PROFILE_ID {
+            self.config_dir.join("settings.json")
+        } else {
+            self.config_dir.join("profiles").join(&profile.id).join("settings.json")
+        }
+    }
+
+    /// Where `profile` keeps its database, clips and media, given its settings
+    pub fn data_dir(&self, profile: &Profile, settings: &Settings) -> PathBuf {
+        match &settings.data_dir {
+            Some(dir) => dir.clone(),
+            None if profile.id == DEFAULT_PROFILE_ID => self.app_data_dir.clone(),
+            None => self.app_data_dir.join("profiles").join(&profile.id),
+        }
+    }
+
+    /// The shared secrets live with the default profile's data; a profile's own set
+    /// lives with its data and under its own keychain entry names
+    pub fn secrets_location(&self, profile: &Profile) -> SecretsLocation {
+        if profile.shared_secrets || profile.id == DEFAULT_PROFILE_ID {
+            let default = self.get(DEFAULT_PROFILE_ID).expect("the default profile is always known");
+            let settings = SettingsManager::read(&self.settings_path(&default));
+            SecretsLocation {
+                file_path: AppConfig::new(self.data_dir(&default, &settings)).secrets_path(),
+                keychain_scope: None,
+            }
+        } else {
+            let settings = SettingsManager::read(&self.settings_path(profile));
+            SecretsLocation {
+                file_path: AppConfig::new(self.data_dir(profile, &settings)).secrets_path(),
+                keychain_scope: Some(profile.id.clone()),
+            }
+        }
+    }
+
+    /// Name of the secret holding the database key of the active profile. Profiles
+    /// sharing secrets each need their own name for it.
+    pub fn database_key_secret(&self) -> String {
+        database_key_secret(&self.active())
+    }
+}
+
+fn database_key_secret(profile: &Profile) -> String {
+    if profile.shared_secrets && profile.id != DEFAULT_PROFILE_ID {
+        format!("{}:{}", DATABASE_KEY_SECRET, profile.id)
+    } else {
+        DATABASE_KEY_SECRET.to_string()
+    }
+}
+
+/// Lowercase ASCII words of `name` joined by dashes, usable as a folder name
+fn profile_id(name: &str) -> String {
+    let mut id = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()) {
+        if id.len() + word.len() + 1 > MAX_ID_LEN {
+            break;
+        }
+        if !id.is_empty() {
+            id.push('-');
+        }
+        id.push_str(&word.to_ascii_lowercase());
+    }
+    if id.is_empty() {
+        id.push_str("profile");
+    }
+    id
+}
+
+/// Make `id` the active profile without restarting: jobs, syncs and the drop folder
+/// watcher are paused, the profile's secrets, database and settings swapped in, and
+/// everything resumed against them. Logs keep going to the folder they started in.
+/// On error the previous profile stays active.
+pub async fn switch_profile(app_handle: &AppHandle, id: &str) -> Result<Profile, AppError> {
+    let profiles = app_handle.state::<ProfileManager>();
+    let _switching = profiles.switching.lock().await;
+    let profile = profiles.get(id)?;
+    if profile.id == profiles.active().id {
+        return Ok(profile);
+    }
+
+    let settings_path = profiles.settings_path(&profile);
+    let profile_settings = SettingsManager::read(&settings_path);
+    let data_dir = profiles.data_dir(&profile, &profile_settings);
+    let profile_config = AppConfig::new(data_dir.clone());
+    profile_config.ensure_dirs()?;
+
+    let jobs = app_handle.state::<JobQueue>();
+    let sync = app_handle.state::<SyncManager>();
+    let watcher = app_handle.state::<ClipWatcher>();
+    let secrets = app_handle.state::<SecretsManager>();
+    let db = app_handle.state::<Database>();
+    let jobs_paused = jobs.pause().await;
+    let sync_paused = sync.pause().await;
+    let watching = watcher.is_running();
+    watcher.shutdown();
+
+    let previous_backend = secrets.backend().await;
+    let previous_location = secrets
+        .switch_location(profiles.secrets_location(&profile), profile_settings.secrets_backend)
+        .await;
+    let key = if profile_settings.encrypt_database {
+        secrets.get_secret(&database_key_secret(&profile), "open_database").await.map(Some)
+    } else {
+        Ok(None)
+    };
+    if let Err(e) = key.and_then(|key| db.reopen(&profile_config.clips_db_path(), key)) {
+        secrets.switch_location(previous_location, previous_backend).await;
+        if watching {
+            watcher.resume()?;
+        }
+        return Err(e);
+    }
+
+    app_handle.state::<AppConfig>().set_data_dir(data_dir);
+    // Clipper tokens derive from a key in the profile's secrets; its database lists the paired ones
+    if let Err(e) = app_handle.state::<ClipperPairing>().load_key(&secrets).await {
+        error!("{}", e);
+    }
+    // The other database has its own private clip passphrase
+    app_handle.state::<PrivateVault>().lock();
+    // Events of the previous profile carry its clips
+    app_handle.state::<EventJournal>().clear();
+    watcher.set_clips_dir(profile_config.clips_dir())?;
+    if watching {
+        if let Err(e) = watcher.resume() {
+            error!("{}", e);
+        }
+    }
+
+    let settings = app_handle.state::<SettingsManager>();
+    let previous_settings = settings.get();
+    let current = settings.reload(settings_path);
+    // After the reload, so it's the new profile's lock setting that applies
+    if let Err(e) = app_lock::lock_now(app_handle) {
+        error!("{}", e);
+    }
+    profiles.set_active(&profile.id)?;
+    jobs.reload(app_handle)?;
+    drop(sync_paused);
+    drop(jobs_paused);
+
+    // Another app may own the profile's shortcut; capture is then unavailable until it's changed
+    if let Err(e) = hotkey::reregister(app_handle, previous_settings.capture_shortcut()) {
+        error!("{}", e);
+    }
+    if current.clipboard_monitor.enabled != previous_settings.clipboard_monitor.enabled {
+        app_handle
+            .state::<ClipboardMonitor>()
+            .set_enabled(app_handle, current.clipboard_monitor.enabled)?;
+    }
+    app_handle.state::<Tray>().refresh(app_handle);
+
+    events::emit(app_handle, "profile-switched", &profile)
+        .map_err(|e| AppError::internal(format!("Failed to emit profile-switched event: {}", e)))?;
+    Ok(profile)
+}
@@ -0,0 +1,229 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::clips::{self, now_millis};
+use crate::errors::AppError;
+use crate::extract::plain_text;
+
+const MAX_NAME_LEN: usize = 128;
+/// Clip text beyond this isn't substituted into a prompt
+const MAX_CONTENT_CHARS: usize = 12_000;
+
+/// A reusable prompt with `{{variable}}` placeholders
+#[derive(Debug, Serialize, Clone)]
+pub struct PromptTemplate {
+    pub id: i64,
+    pub name: String,
+    pub template: String,
+    pub description: Option<String>,
+    /// Placeholder names in the template, in order of first use
+    pub variables: Vec<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const PROMPT_COLUMNS: &str = "id, name, template, description, created_at, updated_at";
+
+fn prompt_from_row(row: &rusqlite::Row) -> rusqlite::Result<PromptTemplate> {
+    let template: String = row.get(2)?;
+    Ok(PromptTemplate {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        variables: template_variables(&template),
+        template,
+        description: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+/// Each `{{name}}` placeholder with its byte range in `template`
+fn placeholders(template: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = template[offset..].find("{{") {
+        let start = offset + start;
+        let Some(len) = template[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        let name = template[start + 2..end - 2].trim();
+        if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            found.push((start..end, name));
+        }
+        offset = end;
+    }
+    found
+}
+
+/// Placeholder names used in `template`, without duplicates
+pub fn template_variables(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (_, name) in placeholders(template) {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Substitute `vars` into the template's placeholders. Every placeholder needs a value.
+pub fn render(template: &str, vars: &HashMap<String, String>) -> Result<String, AppError> {
+    let missing: Vec<String> = template_variables(template)
+        .into_iter()
+        .filter(|name| !vars.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::validation(format!("No value for {}", missing.join(", "))));
+    }
+
+    let mut rendered = String::with_capacity(template.len());
+    let mut last = 0;
+    for (range, name) in placeholders(template) {
+        rendered.push_str(&template[last..range.start]);
+        rendered.push_str(&vars[name]);
+        last = range.end;
+    }
+    rendered.push_str(&template[last..]);
+    Ok(rendered)
+}
+
+fn normalize_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::validation("Prompt name must not be empty"));
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::validation(format!("Prompt name must be at most {} characters", MAX_NAME_LEN)));
+    }
+    Ok(name.to_string())
+}
+
+fn check_template(template: &str) -> Result<(), AppError> {
+    if template.trim().is_empty() {
+        return Err(AppError::validation("Prompt template must not be empty"));
+    }
+    Ok(())
+}
+
+/// Map a unique-name violation to a validation error
+fn write_error(e: rusqlite::Error, name: &str) -> AppError {
+    match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            AppError::validation(format!("A prompt named \"{}\" already exists", name))
+        }
+        e => AppError::database(format!("Failed to save prompt: {}", e)),
+    }
+}
+
+pub fn get_prompt(conn: &Connection, id: i64) -> Result<Option<PromptTemplate>, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM prompts WHERE id = ?1", PROMPT_COLUMNS),
+        params![id],
+        prompt_from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read prompt: {}", e)))
+}
+
+pub fn list_prompts(conn: &Connection) -> Result<Vec<PromptTemplate>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM prompts ORDER BY name COLLATE NOCASE", PROMPT_COLUMNS))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let prompts = stmt
+        .query_map([], prompt_from_row)
+        .map_err(|e| AppError::database(format!("Failed to list prompts: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to list prompts: {}", e)))?;
+    Ok(prompts)
+}
+
+pub fn create_prompt(
+    conn: &Connection,
+    name: &str,
+    template: &str,
+    description: Option<&str>,
+) -> Result<PromptTemplate, AppError> {
+    let name = normalize_name(name)?;
+    check_template(template)?;
+    let now = now_millis();
+    conn.execute(
+        "INSERT INTO prompts (name, template, description, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+        params![name, template, description, now],
+    )
+    .map_err(|e| write_error(e, &name))?;
+
+    let id = conn.last_insert_rowid();
+    get_prompt(conn, id)?.ok_or_else(|| AppError::database(format!("Prompt {} vanished after insert", id)))
+}
+
+pub fn update_prompt(
+    conn: &Connection,
+    id: i64,
+    name: &str,
+    template: &str,
+    description: Option<&str>,
+) -> Result<PromptTemplate, AppError> {
+    let name = normalize_name(name)?;
+    check_template(template)?;
+    let changed = conn
+        .execute(
+            "UPDATE prompts SET name = ?1, template = ?2, description = ?3, updated_at = ?4 WHERE id = ?5",
+            params![name, template, description, now_millis(), id],
+        )
+        .map_err(|e| write_error(e, &name))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Prompt {} not found", id)));
+    }
+    get_prompt(conn, id)?.ok_or_else(|| AppError::not_found(format!("Prompt {} not found", id)))
+}
+
+pub fn delete_prompt(conn: &Connection, id: i64) -> Result<(), AppError> {
+    let deleted = conn
+        .execute("DELETE FROM prompts WHERE id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to delete prompt: {}", e)))?;
+    if deleted == 0 {
+        return Err(AppError::not_found(format!("Prompt {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Render a stored template against a clip or a piece of text. A clip provides
+/// `title`, `url`, `summary` and `content`; plain text provides `content`. Both are
+/// also available as `text`, and `extra` adds or overrides variables.
+pub fn render_prompt(
+    conn: &Connection,
+    template_id: i64,
+    clip_id: Option<i64>,
+    text: Option<String>,
+    extra: HashMap<String, String>,
+) -> Result<String, AppError> {
+    let prompt = get_prompt(conn, template_id)?
+        .ok_or_else(|| AppError::not_found(format!("Prompt {} not found", template_id)))?;
+
+    let mut vars = HashMap::new();
+    match (clip_id, text) {
+        (Some(clip_id), None) => {
+            let clip = clips::get_clip(conn, clip_id)?
+                .filter(|clip| clip.deleted_at.is_none())
+                .ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+            let content: String = plain_text(clip.content.as_deref().unwrap_or_default())
+                .chars()
+                .take(MAX_CONTENT_CHARS)
+                .collect();
+            vars.insert("title".to_string(), clip.title);
+            vars.insert("url".to_string(), clip.url.unwrap_or_default());
+            vars.insert("summary".to_string(), clip.summary.unwrap_or_default());
+            vars.insert("text".to_string(), content.clone());
+            vars.insert("content".to_string(), content);
+        }
+        (None, Some(text)) => {
+            vars.insert("text".to_string(), text.clone());
+            vars.insert("content".to_string(), text);
+        }
+        _ => return Err(AppError::validation("Give either a clip or some text to run the prompt on")),
+    }
+    vars.extend(extra);
+    render(&prompt.template, &vars)
+}
@@ -0,0 +1,201 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::{
+    key_limit, last_user_message, send_json, send_json_once, KeyDetails, LlmError, LlmProvider, LlmRequest, LlmResponse,
+    LlmUsage, ToolCall, ToolDefinition, ToolRound, ToolStep,
+};
+use crate::http;
+
+/// Anthropic Claude Messages API
+pub struct AnthropicProvider;
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn id(&self) -> &str {
+        "anthropic"
+    }
+
+    fn name(&self) -> &str {
+        "Anthropic"
+    }
+
+    fn api_key_name(&self) -> Option<&str> {
+        Some("anthropic_api_key")
+    }
+
+    async fn complete(&self, api_key: Option<&str>, request: LlmRequest) -> Result<LlmResponse, LlmError> {
+        let response_json = send_request(api_key, base_request(&request)).await?;
+
+        let content = response_json["content"][0]["text"]
+            .as_str()
+            .ok_or("No content in response")?
+            .to_string();
+
+        Ok(LlmResponse {
+            content,
+            usage: usage_from(&response_json),
+        })
+    }
+
+    async fn complete_with_tools(
+        &self,
+        api_key: Option<&str>,
+        request: LlmRequest,
+        tools: &[ToolDefinition],
+        rounds: &[ToolRound],
+    ) -> Result<ToolStep, LlmError> {
+        let mut anthropic_request = base_request(&request);
+        // Each round is the assistant's tool_use blocks followed by a user turn with the results
+        let messages = anthropic_request["messages"].as_array_mut().ok_or("Malformed request")?;
+        for round in rounds {
+            let mut blocks: Vec<Value> = round.text.iter().map(|text| json!({ "type": "text", "text": text })).collect();
+            blocks.extend(round.calls.iter().map(|call| {
+                json!({ "type": "tool_use", "id": call.id, "name": call.name, "input": call.arguments })
+            }));
+            messages.push(json!({ "role": "assistant", "content": blocks }));
+            let results: Vec<Value> = round
+                .calls
+                .iter()
+                .zip(&round.results)
+                .map(|(call, result)| json!({ "type": "tool_result", "tool_use_id": call.id, "content": result }))
+                .collect();
+            messages.push(json!({ "role": "user", "content": results }));
+        }
+        anthropic_request["tools"] = tools
+            .iter()
+            .map(|tool| json!({ "name": tool.name, "description": tool.description, "input_schema": tool.parameters }))
+            .collect();
+
+        let response_json = send_request(api_key, anthropic_request).await?;
+        let blocks = response_json["content"].as_array().ok_or("No content in response")?;
+        let text: Vec<&str> = blocks
+            .iter()
+            .filter(|block| block["type"] == "text")
+            .filter_map(|block| block["text"].as_str())
+            .collect();
+        let text = text.join("");
+        let calls: Vec<ToolCall> = blocks
+            .iter()
+            .filter(|block| block["type"] == "tool_use")
+            .map(|block| ToolCall {
+                id: block["id"].as_str().unwrap_or_default().to_string(),
+                name: block["name"].as_str().unwrap_or_default().to_string(),
+                arguments: block["input"].clone(),
+            })
+            .collect();
+        let usage = usage_from(&response_json);
+
+        if calls.is_empty() {
+            return Ok(ToolStep::Answer(LlmResponse { content: text, usage }));
+        }
+        Ok(ToolStep::Calls {
+            text: Some(text).filter(|text| !text.is_empty()),
+            calls,
+            usage,
+        })
+    }
+
+    async fn check_key(&self, api_key: Option<&str>) -> Result<KeyDetails, LlmError> {
+        list_models(api_key).await
+    }
+}
+
+/// Messages API body for `request`, without tools
+fn base_request(request: &LlmRequest) -> Value {
+    // Anthropic takes system prompts as a top-level field rather than a message role
+    let (system, messages): (Vec<_>, Vec<_>) = request.messages.iter().partition(|m| m.role == "system");
+    let mut anthropic_request = json!({
+        "model": request.model,
+        "max_tokens": request.max_tokens.unwrap_or(1000),
+        "messages": messages
+    });
+    if !system.is_empty() {
+        let system: Vec<&str> = system.iter().map(|m| m.content.as_str()).collect();
+        anthropic_request["system"] = json!(system.join("\n\n"));
+    }
+    if let Some(temperature) = request.temperature {
+        anthropic_request["temperature"] = json!(temperature);
+    }
+    if !request.images.is_empty() {
+        if let Some(message) = last_user_message(&mut anthropic_request["messages"]) {
+            let mut blocks: Vec<Value> = request
+                .images
+                .iter()
+                .map(|image| {
+                    json!({
+                        "type": "image",
+                        "source": { "type": "base64", "media_type": image.media_type, "data": image.data }
+                    })
+                })
+                .collect();
+            blocks.push(json!({ "type": "text", "text": message["content"] }));
+            message["content"] = json!(blocks);
+        }
+    }
+    anthropic_request
+}
+
+/// Rate limit windows Anthropic reports in `anthropic-ratelimit-<window>-*` headers
+const RATE_LIMIT_WINDOWS: &[&str] = &["requests", "tokens", "input-tokens", "output-tokens"];
+
+/// List the models the key can use; the page size is the API's maximum
+async fn list_models(api_key: Option<&str>) -> Result<KeyDetails, LlmError> {
+    let api_key = api_key.ok_or_else(|| LlmError::Auth {
+        message: "Missing Anthropic API key".to_string(),
+    })?;
+
+    let (headers, response_json) = send_json_once(
+        http::client()
+            .get("https://api.anthropic.com/v1/models")
+            .query(&[("limit", "1000")])
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01"),
+    )
+    .await?;
+    let limits = RATE_LIMIT_WINDOWS
+        .iter()
+        .filter_map(|window| {
+            key_limit(
+                &headers,
+                window,
+                &format!("anthropic-ratelimit-{}-limit", window),
+                &format!("anthropic-ratelimit-{}-remaining", window),
+                &format!("anthropic-ratelimit-{}-reset", window),
+            )
+        })
+        .collect();
+    Ok(KeyDetails {
+        models: response_json["data"].as_array().map(Vec::len),
+        plan: None,
+        limits,
+    })
+}
+
+async fn send_request(api_key: Option<&str>, body: Value) -> Result<Value, LlmError> {
+    let api_key = api_key.ok_or_else(|| LlmError::Auth {
+        message: "Missing Anthropic API key".to_string(),
+    })?;
+
+    send_json(
+        http::client()
+            .post("https://api.anthropic.com/v1/messages")
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("Content-Type", "application/json")
+            .json(&body),
+    )
+    .await
+}
+
+fn usage_from(response_json: &Value) -> Option<LlmUsage> {
+    response_json.get("usage").map(|usage_obj| {
+        let input_tokens = usage_obj["input_tokens"].as_u64().unwrap_or(0) as u32;
+        let output_tokens = usage_obj["output_tokens"].as_u64().unwrap_or(0) as u32;
+        LlmUsage {
+            input_tokens,
+            output_tokens,
+            total_tokens: input_tokens + output_tokens,
+        }
+    })
+}
@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+
+use super::{
+    last_user_message, send_json, send_json_once, KeyDetails, LlmError, LlmProvider, LlmRequest, LlmResponse, LlmUsage,
+};
+use crate::http;
+
+/// Google Gemini `generateContent` API
+pub struct GeminiProvider;
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    fn id(&self) -> &str {
+        "gemini"
+    }
+
+    fn name(&self) -> &str {
+        "Google Gemini"
+    }
+
+    fn api_key_name(&self) -> Option<&str> {
+        Some("gemini_api_key")
+    }
+
+    async fn complete(&self, api_key: Option<&str>, request: LlmRequest) -> Result<LlmResponse, LlmError> {
+        let api_key = api_key.ok_or_else(|| LlmError::Auth {
+            message: "Missing Gemini API key".to_string(),
+        })?;
+
+        // Gemini takes system prompts separately and calls the assistant role "model"
+        let mut system_parts = Vec::new();
+        let mut contents = Vec::new();
+        for message in &request.messages {
+            match message.role.as_str() {
+                "system" => system_parts.push(serde_json::json!({ "text": message.content })),
+                role => contents.push(serde_json::json!({
+                    "role": if role == "assistant" { "model" } else { "user" },
+                    "parts": [{ "text": message.content }]
+                })),
+            }
+        }
+
+        let mut contents = serde_json::Value::Array(contents);
+        if let Some(message) = last_user_message(&mut contents) {
+            if let Some(parts) = message["parts"].as_array_mut() {
+                parts.extend(request.images.iter().map(|image| {
+                    serde_json::json!({ "inline_data": { "mime_type": image.media_type, "data": image.data } })
+                }));
+            }
+        }
+
+        let mut gemini_request = serde_json::json!({
+            "contents": contents,
+            "generationConfig": {
+                "maxOutputTokens": request.max_tokens,
+                "temperature": request.temperature
+            }
+        });
+        if !system_parts.is_empty() {
+            gemini_request["systemInstruction"] = serde_json::json!({ "parts": system_parts });
+        }
+        if let Some(safety_settings) = &request.safety_settings {
+            gemini_request["safetySettings"] = serde_json::json!(safety_settings);
+        }
+
+        let response_json = send_json(
+            http::client()
+                .post(format!(
+                    "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
+                    request.model
+                ))
+                .header("x-goog-api-key", api_key)
+                .header("Content-Type", "application/json")
+                .json(&gemini_request),
+        )
+        .await?;
+
+        if let Some(reason) = response_json["promptFeedback"]["blockReason"].as_str() {
+            return Err(format!("Prompt blocked by Gemini: {}", reason).into());
+        }
+
+        let parts = response_json["candidates"][0]["content"]["parts"]
+            .as_array()
+            .ok_or("No content in response")?;
+        let content: String = parts.iter().filter_map(|part| part["text"].as_str()).collect();
+
+        let usage = response_json.get("usageMetadata").map(|usage_obj| LlmUsage {
+            input_tokens: usage_obj["promptTokenCount"].as_u64().unwrap_or(0) as u32,
+            output_tokens: usage_obj["candidatesTokenCount"].as_u64().unwrap_or(0) as u32,
+            total_tokens: usage_obj["totalTokenCount"].as_u64().unwrap_or(0) as u32,
+        });
+
+        Ok(LlmResponse { content, usage })
+    }
+
+    async fn check_key(&self, api_key: Option<&str>) -> Result<KeyDetails, LlmError> {
+        let api_key = api_key.ok_or_else(|| LlmError::Auth {
+            message: "Missing Gemini API key".to_string(),
+        })?;
+
+        let (_, response_json) = send_json_once(
+            http::client()
+                .get("https://generativelanguage.googleapis.com/v1beta/models")
+                .query(&[("pageSize", "1000")])
+                .header("x-goog-api-key", api_key),
+        )
+        .await?;
+        Ok(KeyDetails {
+            models: response_json["models"].as_array().map(Vec::len),
+            ..KeyDetails::default()
+        })
+    }
+}
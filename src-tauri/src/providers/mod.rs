@@ -0,0 +1,403 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::connectivity;
+use crate::errors::AppError;
+use crate::secrets::SecretsManager;
+use crate::settings::Settings;
+
+pub mod anthropic;
+pub mod gemini;
+pub mod ollama;
+pub mod openai;
+mod retry;
+
+use anthropic::AnthropicProvider;
+use gemini::GeminiProvider;
+use ollama::OllamaProvider;
+use openai::OpenAiCompatibleProvider;
+pub use retry::LlmError;
+pub(crate) use retry::{send_bytes, send_json, send_json_once};
+
+/// LLM API request structure
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LlmRequest {
+    pub model: String,
+    pub messages: Vec<LlmMessage>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    /// Passed through to providers that support content filtering (Gemini); ignored by others
+    #[serde(default)]
+    pub safety_settings: Option<Vec<SafetySetting>>,
+    /// Sent with the last user message; only vision-capable models accept them
+    #[serde(default)]
+    pub images: Vec<EncodedImage>,
+}
+
+/// An image ready to upload to a vision model
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EncodedImage {
+    /// e.g. `image/jpeg`
+    pub media_type: String,
+    /// Base64 of the image bytes, without a `data:` prefix
+    pub data: String,
+}
+
+impl EncodedImage {
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data)
+    }
+}
+
+/// A Gemini safety threshold, e.g. `HARM_CATEGORY_HARASSMENT` / `BLOCK_ONLY_HIGH`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SafetySetting {
+    pub category: String,
+    pub threshold: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LlmMessage {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LlmResponse {
+    pub content: String,
+    pub usage: Option<LlmUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LlmUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// A function the model may call, with a JSON Schema describing its arguments
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+/// A tool call requested by the model
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToolCall {
+    /// Provider-assigned id that the result must be sent back with
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+/// One earlier round of tool use: the calls the model made and what each returned
+#[derive(Debug, Clone)]
+pub struct ToolRound {
+    /// Text the model sent alongside its calls
+    pub text: Option<String>,
+    pub calls: Vec<ToolCall>,
+    /// Results in the same order as `calls`
+    pub results: Vec<String>,
+}
+
+/// What a completion with tools available came back with
+#[derive(Debug)]
+pub enum ToolStep {
+    /// A final answer
+    Answer(LlmResponse),
+    /// The model wants these tools run before it answers
+    Calls {
+        text: Option<String>,
+        calls: Vec<ToolCall>,
+        usage: Option<LlmUsage>,
+    },
+}
+
+/// One rate limit window reported with a key check
+#[derive(Debug, Serialize, Clone)]
+pub struct KeyLimit {
+    /// What is limited, e.g. `requests` or `tokens`
+    pub name: String,
+    pub limit: Option<f64>,
+    pub remaining: Option<f64>,
+    /// When the window resets, in the provider's own format (`20s`, an RFC 3339 time)
+    pub reset: Option<String>,
+}
+
+/// What a key check learned about an accepted key
+#[derive(Debug, Serialize, Default)]
+pub struct KeyDetails {
+    /// Models the key can use, where the provider lists them
+    pub models: Option<usize>,
+    /// Plan or tier, where the provider reports one
+    pub plan: Option<String>,
+    pub limits: Vec<KeyLimit>,
+}
+
+/// The `limit`, `remaining` and `reset` headers of one rate limit window, if any were sent
+pub(crate) fn key_limit(
+    headers: &reqwest::header::HeaderMap,
+    name: &str,
+    limit: &str,
+    remaining: &str,
+    reset: &str,
+) -> Option<KeyLimit> {
+    let text = |header: &str| headers.get(header)?.to_str().ok().map(|value| value.trim().to_string());
+    let number = |header: &str| text(header)?.parse().ok();
+    let window = KeyLimit {
+        name: name.to_string(),
+        limit: number(limit),
+        remaining: number(remaining),
+        reset: text(reset),
+    };
+    (window.limit.is_some() || window.remaining.is_some()).then_some(window)
+}
+
+/// A backend that can answer chat completion requests
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Stable id used to select the provider, e.g. `anthropic`
+    fn id(&self) -> &str;
+
+    /// Human-readable name for the UI
+    fn name(&self) -> &str;
+
+    /// Secret holding this provider's API key, or None if it doesn't need one
+    fn api_key_name(&self) -> Option<&str>;
+
+    /// Runs on this machine or the local network, so it keeps working offline
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    async fn complete(&self, api_key: Option<&str>, request: LlmRequest) -> Result<LlmResponse, LlmError>;
+
+    /// Continue `request` with `tools` available. `rounds` are earlier calls and their
+    /// results, sent after `request.messages`. Providers without tool use keep the default.
+    async fn complete_with_tools(
+        &self,
+        _api_key: Option<&str>,
+        _request: LlmRequest,
+        _tools: &[ToolDefinition],
+        _rounds: &[ToolRound],
+    ) -> Result<ToolStep, LlmError> {
+        Err(format!("{} does not support tool calling", self.name()).into())
+    }
+
+    /// Embedding vectors for `inputs`, in order. Providers without an embeddings API keep the default.
+    async fn embed(&self, _api_key: Option<&str>, _model: &str, _inputs: &[String]) -> Result<Vec<Vec<f32>>, LlmError> {
+        Err(format!("{} does not support embeddings", self.name()).into())
+    }
+
+    /// Make a cheap authenticated request, usually listing models, to confirm `api_key`
+    /// works. Providers that can't check keys keep the default.
+    async fn check_key(&self, _api_key: Option<&str>) -> Result<KeyDetails, LlmError> {
+        Err(format!("{} does not support key checks", self.name()).into())
+    }
+}
+
+/// A provider and one of its models, e.g. the default model used by background features
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelSelection {
+    pub provider: String,
+    pub model: String,
+}
+
+/// What the UI needs to offer a provider in its model picker
+#[derive(Debug, Serialize)]
+pub struct ProviderInfo {
+    pub id: String,
+    pub name: String,
+    pub api_key_name: Option<String>,
+}
+
+/// OpenAI-compatible endpoint configured by the user (LM Studio, vLLM, Groq, ...)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomProviderConfig {
+    pub id: String,
+    pub name: String,
+    pub base_url: String,
+    /// Secret holding the API key; None for endpoints that don't need one
+    pub api_key_name: Option<String>,
+}
+
+/// All providers available to `call_llm`, keyed by id
+pub struct ProviderRegistry {
+    providers: HashMap<String, Box<dyn LlmProvider>>,
+    /// Ollama model that completions go to while offline (`Settings::offline_model`)
+    offline_model: Option<String>,
+}
+
+impl ProviderRegistry {
+    /// Built-in providers plus any custom endpoints from the settings
+    pub fn from_settings(settings: &Settings) -> Self {
+        let mut registry = Self {
+            providers: HashMap::new(),
+            offline_model: settings.offline_model.clone(),
+        };
+
+        registry.register(Box::new(AnthropicProvider));
+        registry.register(Box::new(OpenAiCompatibleProvider::openai()));
+        registry.register(Box::new(OpenAiCompatibleProvider::openrouter()));
+        registry.register(Box::new(GeminiProvider));
+        registry.register(Box::new(OllamaProvider::new(settings.ollama_url())));
+        for custom in &settings.custom_providers {
+            registry.register(Box::new(OpenAiCompatibleProvider::custom(custom)));
+        }
+
+        registry
+    }
+
+    fn register(&mut self, provider: Box<dyn LlmProvider>) {
+        self.providers.insert(provider.id().to_string(), provider);
+    }
+
+    pub fn get(&self, id: &str) -> Result<&dyn LlmProvider, AppError> {
+        self.providers
+            .get(id)
+            .map(|provider| provider.as_ref())
+            .ok_or_else(|| AppError::validation(format!("Unknown LLM provider '{}'", id)))
+    }
+
+    /// `provider_id`, unless the app is offline and it isn't local
+    fn reachable(&self, provider_id: &str) -> Result<&dyn LlmProvider, LlmError> {
+        let provider = self.get(provider_id)?;
+        if provider.is_local() || connectivity::is_online() {
+            return Ok(provider);
+        }
+        Err(LlmError::Offline {
+            message: format!("{} can't be reached while offline", provider.name()),
+        })
+    }
+
+    /// The provider a completion for `provider_id` goes to. Offline, calls to remote
+    /// providers are sent to Ollama with `offline_model` instead, if one is set.
+    fn route(&self, provider_id: &str, request: &mut LlmRequest) -> Result<(&dyn LlmProvider, bool), LlmError> {
+        match (self.reachable(provider_id), &self.offline_model) {
+            (Err(LlmError::Offline { .. }), Some(model)) => {
+                request.model = model.clone();
+                Ok((self.get("ollama")?, true))
+            }
+            (provider, _) => provider.map(|provider| (provider, false)),
+        }
+    }
+
+    /// The provider whose API key is kept in the secret `name`
+    pub fn for_secret(&self, name: &str) -> Option<&dyn LlmProvider> {
+        self.providers
+            .values()
+            .map(|provider| provider.as_ref())
+            .find(|provider| provider.api_key_name() == Some(name))
+    }
+
+    pub fn list(&self) -> Vec<ProviderInfo> {
+        let mut providers: Vec<ProviderInfo> = self
+            .providers
+            .values()
+            .map(|provider| ProviderInfo {
+                id: provider.id().to_string(),
+                name: provider.name().to_string(),
+                api_key_name: provider.api_key_name().map(str::to_string),
+            })
+            .collect();
+        providers.sort_by(|a, b| a.name.cmp(&b.name));
+        providers
+    }
+}
+
+/// Call LLM API securely from backend. `caller` names the feature making the call
+/// in the secret access audit.
+pub async fn call_llm_api(
+    secrets_manager: &SecretsManager,
+    registry: &ProviderRegistry,
+    provider_id: &str,
+    mut request: LlmRequest,
+    caller: &str,
+) -> Result<LlmResponse, LlmError> {
+    let (provider, rerouted) = registry.route(provider_id, &mut request)?;
+    let api_key = api_key_for(secrets_manager, provider, caller).await?;
+    let mut response = provider.complete(api_key.as_deref(), request).await?;
+    // Callers price usage for the model they asked for, so a rerouted call isn't counted
+    if rerouted {
+        response.usage = None;
+    }
+    Ok(response)
+}
+
+/// Call LLM API with tools available; see `LlmProvider::complete_with_tools`
+pub async fn call_llm_with_tools_api(
+    secrets_manager: &SecretsManager,
+    registry: &ProviderRegistry,
+    provider_id: &str,
+    mut request: LlmRequest,
+    tools: &[ToolDefinition],
+    rounds: &[ToolRound],
+    caller: &str,
+) -> Result<ToolStep, LlmError> {
+    let (provider, rerouted) = registry.route(provider_id, &mut request)?;
+    let api_key = api_key_for(secrets_manager, provider, caller).await?;
+    let step = provider.complete_with_tools(api_key.as_deref(), request, tools, rounds).await?;
+    Ok(match step {
+        ToolStep::Answer(mut response) if rerouted => {
+            response.usage = None;
+            ToolStep::Answer(response)
+        }
+        ToolStep::Calls { text, calls, .. } if rerouted => ToolStep::Calls { text, calls, usage: None },
+        step => step,
+    })
+}
+
+/// Embed `inputs` with `model` from the given provider
+pub async fn embed_api(
+    secrets_manager: &SecretsManager,
+    registry: &ProviderRegistry,
+    provider_id: &str,
+    model: &str,
+    inputs: &[String],
+    caller: &str,
+) -> Result<Vec<Vec<f32>>, LlmError> {
+    // Vectors from another model wouldn't match the index, so embeddings are never rerouted
+    let provider = registry.reachable(provider_id)?;
+    let api_key = api_key_for(secrets_manager, provider, caller).await?;
+    let vectors = provider.embed(api_key.as_deref(), model, inputs).await?;
+    if vectors.len() != inputs.len() {
+        return Err(format!("Expected {} embeddings, got {}", inputs.len(), vectors.len()).into());
+    }
+    Ok(vectors)
+}
+
+/// Get the provider's API key securely; a missing key is reported as an auth error
+async fn api_key_for(
+    secrets_manager: &SecretsManager,
+    provider: &dyn LlmProvider,
+    caller: &str,
+) -> Result<Option<String>, LlmError> {
+    match provider.api_key_name() {
+        Some(name) => secrets_manager
+            .get_secret(name, caller)
+            .await
+            .map(Some)
+            .map_err(|e| LlmError::Auth { message: e.to_string() }),
+        None => Ok(None),
+    }
+}
+
+/// The last `user` message in a provider request body's message list, which carries
+/// the request's images
+pub(crate) fn last_user_message(messages: &mut serde_json::Value) -> Option<&mut serde_json::Value> {
+    messages.as_array_mut()?.iter_mut().rev().find(|message| message["role"] == "user")
+}
+
+/// Read an array of float arrays (one embedding per entry) out of a response
+pub(crate) fn parse_vectors<'a>(items: impl Iterator<Item = &'a serde_json::Value>) -> Result<Vec<Vec<f32>>, LlmError> {
+    items
+        .map(|item| {
+            item.as_array()
+                .map(|values| values.iter().map(|v| v.as_f64().unwrap_or(0.0) as f32).collect())
+                .ok_or_else(|| LlmError::from("Malformed embedding in response"))
+        })
+        .collect()
+}
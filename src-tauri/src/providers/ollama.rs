@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{last_user_message, parse_vectors, send_json, LlmError, LlmProvider, LlmRequest, LlmResponse, LlmUsage};
+use crate::errors::AppError;
+use crate::http;
+
+/// Default address of a locally running Ollama server
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// A model installed in the local Ollama server
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LocalModel {
+    pub name: String,
+    pub size: u64,
+    pub modified_at: Option<String>,
+}
+
+/// Local models served by Ollama; needs no API key
+pub struct OllamaProvider {
+    base_url: String,
+}
+
+impl OllamaProvider {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// List the models installed in the Ollama server (`GET /api/tags`)
+    pub async fn list_models(&self) -> Result<Vec<LocalModel>, AppError> {
+        let response_json = send_json(http::client().get(format!("{}/api/tags", self.base_url)))
+            .await
+            .map_err(|e| AppError::network(format!("Failed to reach Ollama at {}: {}", self.base_url, e)))?;
+
+        let models = response_json["models"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|model| {
+                        Some(LocalModel {
+                            name: model["name"].as_str()?.to_string(),
+                            size: model["size"].as_u64().unwrap_or(0),
+                            modified_at: model["modified_at"].as_str().map(str::to_string),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(models)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn id(&self) -> &str {
+        "ollama"
+    }
+
+    fn name(&self) -> &str {
+        "Ollama (local)"
+    }
+
+    fn api_key_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    async fn complete(&self, _api_key: Option<&str>, request: LlmRequest) -> Result<LlmResponse, LlmError> {
+        // Ollama rejects null options, so only send the ones that are set
+        let mut options = serde_json::Map::new();
+        if let Some(max_tokens) = request.max_tokens {
+            options.insert("num_predict".to_string(), max_tokens.into());
+        }
+        if let Some(temperature) = request.temperature {
+            options.insert("temperature".to_string(), temperature.into());
+        }
+
+        let mut ollama_request = serde_json::json!({
+            "model": request.model,
+            "messages": request.messages,
+            "stream": false,
+            "options": options
+        });
+        if !request.images.is_empty() {
+            if let Some(message) = last_user_message(&mut ollama_request["messages"]) {
+                let images: Vec<&str> = request.images.iter().map(|image| image.data.as_str()).collect();
+                message["images"] = serde_json::json!(images);
+            }
+        }
+
+        let response_json = send_json(
+            http::client()
+                .post(format!("{}/api/chat", self.base_url))
+                .json(&ollama_request),
+        )
+        .await?;
+
+        let content = response_json["message"]["content"]
+            .as_str()
+            .ok_or("No content in response")?
+            .to_string();
+
+        let input_tokens = response_json["prompt_eval_count"].as_u64().unwrap_or(0) as u32;
+        let output_tokens = response_json["eval_count"].as_u64().unwrap_or(0) as u32;
+        let usage = Some(LlmUsage {
+            input_tokens,
+            output_tokens,
+            total_tokens: input_tokens + output_tokens,
+        });
+
+        Ok(LlmResponse { content, usage })
+    }
+
+    async fn embed(&self, _api_key: Option<&str>, model: &str, inputs: &[String]) -> Result<Vec<Vec<f32>>, LlmError> {
+        let response_json = send_json(
+            http::client()
+                .post(format!("{}/api/embed", self.base_url))
+                .json(&serde_json::json!({ "model": model, "input": inputs })),
+        )
+        .await?;
+
+        let embeddings = response_json["embeddings"].as_array().ok_or("No embeddings in response")?;
+        parse_vectors(embeddings.iter())
+    }
+}
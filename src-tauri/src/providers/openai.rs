@@ -0,0 +1,266 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::{
+    key_limit, last_user_message, parse_vectors, send_json, send_json_once, CustomProviderConfig, KeyDetails, KeyLimit,
+    LlmError, LlmProvider, LlmRequest, LlmResponse, LlmUsage, ToolCall, ToolDefinition, ToolRound, ToolStep,
+};
+use crate::connectivity;
+use crate::http;
+
+/// Any endpoint speaking the OpenAI chat completions API: OpenAI itself,
+/// OpenRouter, and user-configured custom base URLs
+pub struct OpenAiCompatibleProvider {
+    id: String,
+    name: String,
+    base_url: String,
+    api_key_name: Option<String>,
+}
+
+impl OpenAiCompatibleProvider {
+    pub fn openai() -> Self {
+        Self {
+            id: "openai".to_string(),
+            name: "OpenAI".to_string(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key_name: Some("openai_api_key".to_string()),
+        }
+    }
+
+    pub fn openrouter() -> Self {
+        Self {
+            id: "openrouter".to_string(),
+            name: "OpenRouter".to_string(),
+            base_url: "https://openrouter.ai/api/v1".to_string(),
+            api_key_name: Some("openrouter_api_key".to_string()),
+        }
+    }
+
+    pub fn custom(config: &CustomProviderConfig) -> Self {
+        Self {
+            id: config.id.clone(),
+            name: config.name.clone(),
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            api_key_name: config.api_key_name.clone(),
+        }
+    }
+
+    async fn send_request(&self, api_key: Option<&str>, body: Value) -> Result<Value, LlmError> {
+        let mut http_request = http::client()
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Content-Type", "application/json")
+            .json(&body);
+        if let Some(api_key) = api_key {
+            http_request = http_request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        send_json(http_request).await
+    }
+
+    fn authorized_get(&self, api_key: Option<&str>, path: &str) -> reqwest::RequestBuilder {
+        let http_request = http::client().get(format!("{}/{}", self.base_url, path));
+        match api_key {
+            Some(api_key) => http_request.header("Authorization", format!("Bearer {}", api_key)),
+            None => http_request,
+        }
+    }
+
+    /// OpenRouter's model list is public, so only its key endpoint proves a key works.
+    /// It also reports the key's credit limit and whether it's on the free tier.
+    async fn check_openrouter_key(&self, api_key: Option<&str>, details: &mut KeyDetails) -> Result<(), LlmError> {
+        let (_, response_json) = send_json_once(self.authorized_get(api_key, "key")).await?;
+        let data = &response_json["data"];
+        details.plan = data["is_free_tier"]
+            .as_bool()
+            .map(|free| if free { "Free tier" } else { "Paid" }.to_string());
+        // A null limit means the key can spend the whole account balance
+        if data["limit"].is_number() || data["limit_remaining"].is_number() {
+            details.limits.push(KeyLimit {
+                name: "credits".to_string(),
+                limit: data["limit"].as_f64(),
+                remaining: data["limit_remaining"].as_f64(),
+                reset: None,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn api_key_name(&self) -> Option<&str> {
+        self.api_key_name.as_deref()
+    }
+
+    fn is_local(&self) -> bool {
+        reqwest::Url::parse(&self.base_url)
+            .ok()
+            .and_then(|url| url.host_str().map(connectivity::is_local_host))
+            .unwrap_or(false)
+    }
+
+    async fn complete(&self, api_key: Option<&str>, request: LlmRequest) -> Result<LlmResponse, LlmError> {
+        let response_json = self.send_request(api_key, base_request(&request)).await?;
+
+        let content = response_json["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or("No content in response")?
+            .to_string();
+
+        Ok(LlmResponse {
+            content,
+            usage: usage_from(&response_json),
+        })
+    }
+
+    async fn complete_with_tools(
+        &self,
+        api_key: Option<&str>,
+        request: LlmRequest,
+        tools: &[ToolDefinition],
+        rounds: &[ToolRound],
+    ) -> Result<ToolStep, LlmError> {
+        let mut openai_request = base_request(&request);
+        // Each round is the assistant's tool_calls followed by one `tool` message per result
+        let messages = openai_request["messages"].as_array_mut().ok_or("Malformed request")?;
+        for round in rounds {
+            let tool_calls: Vec<Value> = round
+                .calls
+                .iter()
+                .map(|call| {
+                    json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": { "name": call.name, "arguments": call.arguments.to_string() }
+                    })
+                })
+                .collect();
+            messages.push(json!({ "role": "assistant", "content": round.text, "tool_calls": tool_calls }));
+            for (call, result) in round.calls.iter().zip(&round.results) {
+                messages.push(json!({ "role": "tool", "tool_call_id": call.id, "content": result }));
+            }
+        }
+        openai_request["tools"] = tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "function": { "name": tool.name, "description": tool.description, "parameters": tool.parameters }
+                })
+            })
+            .collect();
+
+        let response_json = self.send_request(api_key, openai_request).await?;
+        let message = &response_json["choices"][0]["message"];
+        let text = message["content"].as_str().map(str::to_string);
+        let usage = usage_from(&response_json);
+        let calls: Vec<ToolCall> = message["tool_calls"]
+            .as_array()
+            .map(|calls| {
+                calls
+                    .iter()
+                    .map(|call| {
+                        // Arguments arrive as a JSON string; pass malformed ones on as text
+                        let arguments = call["function"]["arguments"].as_str().unwrap_or("{}");
+                        ToolCall {
+                            id: call["id"].as_str().unwrap_or_default().to_string(),
+                            name: call["function"]["name"].as_str().unwrap_or_default().to_string(),
+                            arguments: serde_json::from_str(arguments).unwrap_or_else(|_| json!(arguments)),
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if calls.is_empty() {
+            let content = text.ok_or("No content in response")?;
+            return Ok(ToolStep::Answer(LlmResponse { content, usage }));
+        }
+        Ok(ToolStep::Calls {
+            text: text.filter(|text| !text.is_empty()),
+            calls,
+            usage,
+        })
+    }
+
+    async fn embed(&self, api_key: Option<&str>, model: &str, inputs: &[String]) -> Result<Vec<Vec<f32>>, LlmError> {
+        let mut http_request = http::client()
+            .post(format!("{}/embeddings", self.base_url))
+            .json(&serde_json::json!({ "model": model, "input": inputs }));
+        if let Some(api_key) = api_key {
+            http_request = http_request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        let response_json = send_json(http_request).await?;
+        let mut data = response_json["data"]
+            .as_array()
+            .ok_or("No embeddings in response")?
+            .clone();
+        // Entries carry their input index; don't rely on the order they come back in
+        data.sort_by_key(|item| item["index"].as_u64().unwrap_or(0));
+        parse_vectors(data.iter().map(|item| &item["embedding"]))
+    }
+
+    async fn check_key(&self, api_key: Option<&str>) -> Result<KeyDetails, LlmError> {
+        let (headers, response_json) = send_json_once(self.authorized_get(api_key, "models")).await?;
+        let limits = ["requests", "tokens"]
+            .iter()
+            .filter_map(|window| {
+                key_limit(
+                    &headers,
+                    window,
+                    &format!("x-ratelimit-limit-{}", window),
+                    &format!("x-ratelimit-remaining-{}", window),
+                    &format!("x-ratelimit-reset-{}", window),
+                )
+            })
+            .collect();
+        let mut details = KeyDetails {
+            models: response_json["data"].as_array().map(Vec::len),
+            plan: None,
+            limits,
+        };
+        if self.id == "openrouter" {
+            self.check_openrouter_key(api_key, &mut details).await?;
+        }
+        Ok(details)
+    }
+}
+
+/// Chat completions body for `request`, without tools
+fn base_request(request: &LlmRequest) -> Value {
+    let mut openai_request = json!({
+        "model": request.model,
+        "messages": request.messages,
+        "max_tokens": request.max_tokens,
+        "temperature": request.temperature
+    });
+    if !request.images.is_empty() {
+        if let Some(message) = last_user_message(&mut openai_request["messages"]) {
+            let mut parts = vec![json!({ "type": "text", "text": message["content"] })];
+            parts.extend(
+                request
+                    .images
+                    .iter()
+                    .map(|image| json!({ "type": "image_url", "image_url": { "url": image.data_url() } })),
+            );
+            message["content"] = json!(parts);
+        }
+    }
+    openai_request
+}
+
+fn usage_from(response_json: &Value) -> Option<LlmUsage> {
+    response_json.get("usage").map(|usage_obj| LlmUsage {
+        input_tokens: usage_obj["prompt_tokens"].as_u64().unwrap_or(0) as u32,
+        output_tokens: usage_obj["completion_tokens"].as_u64().unwrap_or(0) as u32,
+        total_tokens: usage_obj["total_tokens"].as_u64().unwrap_or(0) as u32,
+    })
+}
@@ -0,0 +1,189 @@
+use rand::Rng;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::redact;
+
+/// Attempts per request, including the first
+const MAX_ATTEMPTS: u32 = 4;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+/// A `Retry-After` longer than this is reported to the UI instead of waited out
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Classified LLM failure, serialized as `{ "kind": "rate_limited", "message": ..., ... }`
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LlmError {
+    /// Too many requests; try again after `retry_after_secs` if known
+    RateLimited { message: String, retry_after_secs: Option<u64> },
+    /// Missing or rejected API key
+    Auth { message: String },
+    /// Account is out of credit or over its spending limit
+    Quota { message: String },
+    /// Could not reach the provider
+    Network { message: String },
+    /// The app is offline and the provider isn't on this machine
+    Offline { message: String },
+    /// Provider-side failure (5xx, overloaded)
+    Server { status: u16, message: String },
+    /// Request rejected for any other reason
+    Api { status: u16, message: String },
+    Other { message: String },
+}
+
+impl LlmError {
+    /// Transient failures worth trying again later
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            LlmError::RateLimited { .. } | LlmError::Network { .. } | LlmError::Server { .. }
+        )
+    }
+
+    /// Build an error from a non-success response
+    fn from_response(status: StatusCode, retry_after: Option<Duration>, body: String) -> Self {
+        let lowered = body.to_lowercase();
+        let mentions_quota = ["quota", "credit", "billing", "insufficient"]
+            .iter()
+            .any(|word| lowered.contains(word));
+        // Some providers echo the request, headers and key included, in their error body
+        let message = format!("API error: {}", redact::redact(&body));
+
+        match status.as_u16() {
+            401 | 403 => LlmError::Auth { message },
+            402 => LlmError::Quota { message },
+            429 if mentions_quota => LlmError::Quota { message },
+            429 => LlmError::RateLimited {
+                message,
+                retry_after_secs: retry_after.map(|d| d.as_secs()),
+            },
+            // 529 is Anthropic's "overloaded"
+            408 | 500..=599 => LlmError::Server {
+                status: status.as_u16(),
+                message,
+            },
+            status => LlmError::Api { status, message },
+        }
+    }
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LlmError::RateLimited { message, .. }
+            | LlmError::Auth { message }
+            | LlmError::Quota { message }
+            | LlmError::Network { message }
+            | LlmError::Offline { message }
+            | LlmError::Server { message, .. }
+            | LlmError::Api { message, .. }
+            | LlmError::Other { message } => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for LlmError {
+    fn from(message: String) -> Self {
+        LlmError::Other { message }
+    }
+}
+
+impl From<&str> for LlmError {
+    fn from(message: &str) -> Self {
+        LlmError::Other {
+            message: message.to_string(),
+        }
+    }
+}
+
+/// `Retry-After` in its delay-seconds form; HTTP dates fall back to backoff
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Exponential backoff with jitter: a random delay between half and all of `BASE_DELAY * 2^attempt`
+fn backoff(attempt: u32) -> Duration {
+    let ceiling = BASE_DELAY.saturating_mul(1 << attempt).min(MAX_DELAY);
+    ceiling.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+async fn send_once(request: reqwest::RequestBuilder) -> Result<reqwest::Response, (LlmError, Option<Duration>)> {
+    let response = request.send().await.map_err(|e| {
+        let error = LlmError::Network {
+            message: format!("Failed to send request: {}", e),
+        };
+        (error, None)
+    })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let wait = retry_after(response.headers());
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err((LlmError::from_response(status, wait, error_text), wait));
+    }
+    Ok(response)
+}
+
+/// Send a request and parse the JSON body. Rate limits, 5xx and network errors are
+/// retried with backoff (honoring `Retry-After`); everything else fails immediately.
+pub(crate) async fn send_json(request: reqwest::RequestBuilder) -> Result<serde_json::Value, LlmError> {
+    send_with_retry(request).await?.json().await.map_err(|e| LlmError::Other {
+        message: format!("Failed to parse response: {}", e),
+    })
+}
+
+/// A single attempt at a JSON request, keeping the response headers. For quick probes
+/// such as key checks, where retrying would only hide a slow or failing provider.
+pub(crate) async fn send_json_once(
+    request: reqwest::RequestBuilder,
+) -> Result<(reqwest::header::HeaderMap, serde_json::Value), LlmError> {
+    let response = send_once(request).await.map_err(|(error, _)| error)?;
+    let headers = response.headers().clone();
+    let body = response.json().await.map_err(|e| LlmError::Other {
+        message: format!("Failed to parse response: {}", e),
+    })?;
+    Ok((headers, body))
+}
+
+/// Like `send_json`, for endpoints that answer with a binary body (e.g. generated audio)
+pub(crate) async fn send_bytes(request: reqwest::RequestBuilder) -> Result<Vec<u8>, LlmError> {
+    let bytes = send_with_retry(request).await?.bytes().await.map_err(|e| LlmError::Network {
+        message: format!("Failed to read response: {}", e),
+    })?;
+    Ok(bytes.to_vec())
+}
+
+async fn send_with_retry(request: reqwest::RequestBuilder) -> Result<reqwest::Response, LlmError> {
+    let mut attempt = 1;
+    loop {
+        // Bodies that can't be cloned (streams) get a single attempt
+        let Some(this_try) = request.try_clone() else {
+            return send_once(request).await.map_err(|(error, _)| error);
+        };
+
+        let (error, wait) = match send_once(this_try).await {
+            Ok(response) => return Ok(response),
+            Err(failure) => failure,
+        };
+        if !error.is_retryable() || attempt >= MAX_ATTEMPTS {
+            return Err(error);
+        }
+
+        let delay = match wait {
+            Some(wait) if wait > MAX_RETRY_AFTER => return Err(error),
+            Some(wait) => wait,
+            None => backoff(attempt - 1),
+        };
+        warn!("LLM request failed ({}), retrying in {:?} (attempt {}/{})", error, delay, attempt + 1, MAX_ATTEMPTS);
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
@@ -0,0 +1,118 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::clips::now_millis;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::pairing::SignedClip;
+
+/// Entries kept; older ones are dropped as new ones arrive
+const MAX_ENTRIES: i64 = 500;
+/// Longer payloads are cut short; the reason usually says what was wrong anyway
+const MAX_STORED_PAYLOAD: usize = 64 * 1024;
+
+/// A clip payload that was signed by a paired clipper but failed validation, or a
+/// drop-folder file that wasn't a signed clip at all (with no `client_id`)
+#[derive(Debug, Serialize)]
+pub struct QuarantinedClip {
+    pub id: i64,
+    /// Where it came from, e.g. `drop_folder`
+    pub source: String,
+    pub client_id: Option<String>,
+    /// The payload as received, possibly truncated
+    pub payload: String,
+    pub reason: String,
+    pub received_at: i64,
+}
+
+/// Keep a rejected payload so the user can see what a clipper sent and why it was refused
+fn add(
+    conn: &Connection,
+    source: &str,
+    client_id: Option<&str>,
+    payload: &str,
+    reason: &str,
+) -> Result<QuarantinedClip, AppError> {
+    let mut end = payload.len().min(MAX_STORED_PAYLOAD);
+    while !payload.is_char_boundary(end) {
+        end -= 1;
+    }
+    let received_at = now_millis();
+    conn.execute(
+        "INSERT INTO quarantined_clips (source, client_id, payload, reason, received_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![source, client_id, &payload[..end], reason, received_at],
+    )
+    .map_err(|e| AppError::database(format!("Failed to quarantine clip: {}", e)))?;
+    let id = conn.last_insert_rowid();
+    conn.execute(
+        "DELETE FROM quarantined_clips WHERE id <= (SELECT MAX(id) FROM quarantined_clips) - ?1",
+        params![MAX_ENTRIES],
+    )
+    .map_err(|e| AppError::database(format!("Failed to prune quarantine: {}", e)))?;
+
+    Ok(QuarantinedClip {
+        id,
+        source: source.to_string(),
+        client_id: client_id.map(str::to_string),
+        payload: payload[..end].to_string(),
+        reason: reason.to_string(),
+        received_at,
+    })
+}
+
+/// Quarantine a signed submission and emit `clip-quarantined` with the entry
+pub fn add_and_notify(app_handle: &AppHandle, source: &str, signed: &SignedClip, reason: &str) -> Result<(), AppError> {
+    record_and_notify(app_handle, source, Some(signed.client_id.clone()), signed.payload.clone(), reason)
+}
+
+/// Quarantine a file that couldn't be read as a signed clip, keeping its raw content
+pub fn add_unreadable_and_notify(
+    app_handle: &AppHandle,
+    source: &str,
+    content: &str,
+    reason: &str,
+) -> Result<(), AppError> {
+    record_and_notify(app_handle, source, None, content.to_string(), reason)
+}
+
+fn record_and_notify(
+    app_handle: &AppHandle,
+    source: &str,
+    client_id: Option<String>,
+    payload: String,
+    reason: &str,
+) -> Result<(), AppError> {
+    let (source, reason) = (source.to_string(), reason.to_string());
+    let entry = app_handle
+        .state::<Database>()
+        .write(move |conn| add(conn, &source, client_id.as_deref(), &payload, &reason))?;
+    events::emit(app_handle, "clip-quarantined", &entry)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip-quarantined event: {}", e)))
+}
+
+/// Quarantined payloads, newest first
+pub fn list(conn: &Connection, limit: usize) -> Result<Vec<QuarantinedClip>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, source, client_id, payload, reason, received_at FROM quarantined_clips
+             ORDER BY id DESC LIMIT ?1",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let entries = stmt
+        .query_map(params![limit as i64], |row| {
+            Ok(QuarantinedClip {
+                id: row.get(0)?,
+                source: row.get(1)?,
+                client_id: row.get(2)?,
+                payload: row.get(3)?,
+                reason: row.get(4)?,
+                received_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to read quarantine: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read quarantined clip: {}", e)))?;
+    Ok(entries)
+}
@@ -0,0 +1,122 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter};
+
+/// What a scrubbed secret is replaced with
+const REDACTED: &str = "[redacted]";
+/// Stored values shorter than this (ids, flags) would scrub ordinary words
+const MIN_SECRET_LEN: usize = 8;
+/// Characters a key must have after its prefix or label to count as one
+const MIN_KEY_CHARS: usize = 16;
+/// Prefixes of well-known API keys: OpenAI and Anthropic (`sk-ant-`), Google, xAI,
+/// Groq, Hugging Face, Perplexity and GitHub
+const KEY_PREFIXES: &[&str] = &["sk-", "AIza", "xai-", "gsk_", "hf_", "pplx-", "ghp_", "github_pat_"];
+/// Labels that introduce a credential in headers and URLs; matched ignoring case
+const KEY_LABELS: &[&str] = &["Bearer ", "x-api-key: ", "x-goog-api-key: ", "api_key=", "key=", "token="];
+
+/// Values from the secrets store, longest first so a secret containing another is
+/// scrubbed whole
+static KNOWN_SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Replace the set of stored secret values to scrub; called whenever the store changes
+pub fn set_known_secrets<'a>(values: impl IntoIterator<Item = &'a str>) {
+    let mut known: Vec<String> = values
+        .into_iter()
+        .map(str::trim)
+        .filter(|value| value.len() >= MIN_SECRET_LEN)
+        .map(str::to_string)
+        .collect();
+    known.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    known.dedup();
+    if let Ok(mut secrets) = KNOWN_SECRETS.write() {
+        *secrets = known;
+    }
+}
+
+fn is_key_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-' || c == b'_'
+}
+
+/// Length of the key-like run at the start of `bytes`; dots are allowed for JWTs
+fn key_run(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|&&c| is_key_char(c) || c == b'.').count()
+}
+
+/// A key starting at `start`: how many bytes of label to keep, and where the key ends
+fn key_at(bytes: &[u8], start: usize) -> Option<(usize, usize)> {
+    let rest = &bytes[start..];
+    for prefix in KEY_PREFIXES {
+        if rest.starts_with(prefix.as_bytes()) {
+            let end = start + key_run(rest);
+            if end - start >= prefix.len() + MIN_KEY_CHARS {
+                return Some((0, end));
+            }
+        }
+    }
+    for label in KEY_LABELS {
+        if rest.len() >= label.len() && rest[..label.len()].eq_ignore_ascii_case(label.as_bytes()) {
+            let key_start = start + label.len();
+            let end = key_start + key_run(&bytes[key_start..]);
+            if end - key_start >= MIN_KEY_CHARS {
+                return Some((label.len(), end));
+            }
+        }
+    }
+    None
+}
+
+/// Scrub anything shaped like an API key. Prefixes and labels are ASCII, so every
+/// cut falls on a character boundary.
+fn redact_patterns(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut redacted = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if i == 0 || !is_key_char(bytes[i - 1]) {
+            if let Some((keep, end)) = key_at(bytes, i) {
+                redacted.push_str(&text[copied..i + keep]);
+                redacted.push_str(REDACTED);
+                copied = end;
+                i = end;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    redacted.push_str(&text[copied..]);
+    redacted
+}
+
+/// `text` with stored secret values and common API key patterns replaced by `[redacted]`
+pub fn redact(text: &str) -> String {
+    let mut text = text.to_string();
+    if let Ok(secrets) = KNOWN_SECRETS.read() {
+        for secret in secrets.iter() {
+            if text.contains(secret.as_str()) {
+                text = text.replace(secret.as_str(), REDACTED);
+            }
+        }
+    }
+    redact_patterns(&text)
+}
+
+/// Redact every string in a JSON value
+pub fn redact_json(value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(redact(&text)),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact_json).collect()),
+        Value::Object(fields) => {
+            Value::Object(fields.into_iter().map(|(key, value)| (key, redact_json(value))).collect())
+        }
+        other => other,
+    }
+}
+
+/// Emit an event to the frontend with every string in `payload` redacted. For events
+/// that can carry error text or provider output.
+pub fn emit<S: Serialize>(app_handle: &AppHandle, event: &str, payload: &S) -> Result<(), tauri::Error> {
+    let payload = serde_json::to_value(payload)?;
+    app_handle.emit(event, redact_json(payload))
+}
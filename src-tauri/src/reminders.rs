@@ -0,0 +1,225 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::error;
+
+use crate::clips::now_millis;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::notifications::{notify, NotificationKind};
+
+/// How often the scheduler looks for reminders that came due
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How long a snooze lasts when no time is given
+const DEFAULT_SNOOZE_MS: i64 = 60 * 60 * 1000;
+const MAX_NOTE_LEN: usize = 1_000;
+
+const REMINDER_COLUMNS: &str =
+    "r.id, r.clip_id, c.title, r.due_at, r.note, r.created_at, r.fired_at, r.snooze_count, r.completed_at";
+
+/// A follow-up on a clip. A clip has at most one open (not completed) reminder.
+#[derive(Debug, Serialize, Clone)]
+pub struct Reminder {
+    pub id: i64,
+    pub clip_id: i64,
+    pub clip_title: String,
+    /// Milliseconds since the epoch
+    pub due_at: i64,
+    pub note: Option<String>,
+    pub created_at: i64,
+    /// When `reminder-due` went out for the current `due_at`; snoozing clears it
+    pub fired_at: Option<i64>,
+    pub snooze_count: u32,
+    pub completed_at: Option<i64>,
+}
+
+impl Reminder {
+    /// Map a row selected with `REMINDER_COLUMNS`
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Reminder {
+            id: row.get(0)?,
+            clip_id: row.get(1)?,
+            clip_title: row.get(2)?,
+            due_at: row.get(3)?,
+            note: row.get(4)?,
+            created_at: row.get(5)?,
+            fired_at: row.get(6)?,
+            snooze_count: row.get(7)?,
+            completed_at: row.get(8)?,
+        })
+    }
+}
+
+fn get_reminder(conn: &Connection, id: i64) -> Result<Option<Reminder>, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM reminders r JOIN clips c ON c.id = r.clip_id WHERE r.id = ?1", REMINDER_COLUMNS),
+        params![id],
+        Reminder::from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read reminder: {}", e)))
+}
+
+fn open_reminder(conn: &Connection, id: i64) -> Result<Reminder, AppError> {
+    let reminder = get_reminder(conn, id)?.ok_or_else(|| AppError::not_found(format!("Reminder {} not found", id)))?;
+    if reminder.completed_at.is_some() {
+        return Err(AppError::validation(format!("Reminder {} is already completed", id)));
+    }
+    Ok(reminder)
+}
+
+/// Remind about a clip at `due_at` (milliseconds since the epoch). A clip's open
+/// reminder is moved to the new time rather than a second one added.
+pub fn set_reminder(conn: &Connection, clip_id: i64, due_at: i64, note: Option<&str>) -> Result<Reminder, AppError> {
+    if due_at <= 0 {
+        return Err(AppError::validation("Reminder time must be a timestamp in milliseconds"));
+    }
+    let note = note.map(str::trim).filter(|note| !note.is_empty());
+    if note.is_some_and(|note| note.chars().count() > MAX_NOTE_LEN) {
+        return Err(AppError::validation(format!("Reminder note must be at most {} characters", MAX_NOTE_LEN)));
+    }
+    let live: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM clips WHERE id = ?1 AND deleted_at IS NULL)",
+            params![clip_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+    if !live {
+        return Err(AppError::not_found(format!("Clip {} not found", clip_id)));
+    }
+
+    conn.execute(
+        "INSERT INTO reminders (clip_id, due_at, note, created_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (clip_id) WHERE completed_at IS NULL
+         DO UPDATE SET due_at = excluded.due_at, note = excluded.note, fired_at = NULL",
+        params![clip_id, due_at, note, now_millis()],
+    )
+    .map_err(|e| AppError::database(format!("Failed to set reminder: {}", e)))?;
+    let id: i64 = conn
+        .query_row(
+            "SELECT id FROM reminders WHERE clip_id = ?1 AND completed_at IS NULL",
+            params![clip_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::database(format!("Failed to read reminder: {}", e)))?;
+    get_reminder(conn, id)?.ok_or_else(|| AppError::database(format!("Reminder {} vanished after insert", id)))
+}
+
+/// Push an open reminder back to `until`, an hour from now if not given. It fires again then.
+pub fn snooze_reminder(conn: &Connection, id: i64, until: Option<i64>) -> Result<Reminder, AppError> {
+    open_reminder(conn, id)?;
+    let now = now_millis();
+    let until = until.unwrap_or(now + DEFAULT_SNOOZE_MS);
+    if until <= now {
+        return Err(AppError::validation("Snooze until a time in the future"));
+    }
+    conn.execute(
+        "UPDATE reminders SET due_at = ?1, fired_at = NULL, snooze_count = snooze_count + 1 WHERE id = ?2",
+        params![until, id],
+    )
+    .map_err(|e| AppError::database(format!("Failed to snooze reminder: {}", e)))?;
+    get_reminder(conn, id)?.ok_or_else(|| AppError::not_found(format!("Reminder {} not found", id)))
+}
+
+/// Mark a reminder done; it stays listed among completed reminders
+pub fn complete_reminder(conn: &Connection, id: i64) -> Result<Reminder, AppError> {
+    open_reminder(conn, id)?;
+    conn.execute("UPDATE reminders SET completed_at = ?1 WHERE id = ?2", params![now_millis(), id])
+        .map_err(|e| AppError::database(format!("Failed to complete reminder: {}", e)))?;
+    get_reminder(conn, id)?.ok_or_else(|| AppError::not_found(format!("Reminder {} not found", id)))
+}
+
+pub fn delete_reminder(conn: &Connection, id: i64) -> Result<(), AppError> {
+    let deleted = conn
+        .execute("DELETE FROM reminders WHERE id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to delete reminder: {}", e)))?;
+    if deleted == 0 {
+        return Err(AppError::not_found(format!("Reminder {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Open reminders soonest first, then completed ones most recent first if asked for.
+/// Reminders on clips in the trash are left out.
+pub fn list_reminders(conn: &Connection, include_completed: bool) -> Result<Vec<Reminder>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM reminders r JOIN clips c ON c.id = r.clip_id
+             WHERE c.deleted_at IS NULL AND (?1 OR r.completed_at IS NULL)
+             ORDER BY r.completed_at IS NOT NULL, r.completed_at DESC, r.due_at",
+            REMINDER_COLUMNS
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let reminders = stmt
+        .query_map(params![include_completed], Reminder::from_row)
+        .map_err(|e| AppError::database(format!("Failed to list reminders: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read reminder: {}", e)))?;
+    Ok(reminders)
+}
+
+/// Open reminders due by `now` that haven't fired yet, marked as fired so each goes out once
+fn take_due(conn: &mut Connection, now: i64) -> Result<Vec<Reminder>, AppError> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+    let due = {
+        let mut stmt = tx
+            .prepare(&format!(
+                "SELECT {} FROM reminders r JOIN clips c ON c.id = r.clip_id
+                 WHERE r.completed_at IS NULL AND r.fired_at IS NULL AND r.due_at <= ?1 AND c.deleted_at IS NULL
+                 ORDER BY r.due_at",
+                REMINDER_COLUMNS
+            ))
+            .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+        let due = stmt
+            .query_map(params![now], Reminder::from_row)
+            .map_err(|e| AppError::database(format!("Failed to read due reminders: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::database(format!("Failed to read reminder: {}", e)))?;
+        due
+    };
+    for reminder in &due {
+        tx.execute("UPDATE reminders SET fired_at = ?1 WHERE id = ?2", params![now, reminder.id])
+            .map_err(|e| AppError::database(format!("Failed to mark reminder fired: {}", e)))?;
+    }
+    tx.commit()
+        .map_err(|e| AppError::database(format!("Failed to commit fired reminders: {}", e)))?;
+    Ok(due
+        .into_iter()
+        .map(|reminder| Reminder {
+            fired_at: Some(now),
+            ..reminder
+        })
+        .collect())
+}
+
+/// Start the background task that fires due reminders as desktop notifications and
+/// `reminder-due` events. Reminders that came due while the app was closed fire on the
+/// first check.
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let due = app_handle.state::<Database>().write_async(|conn| take_due(conn, now_millis())).await;
+            match due {
+                Ok(due) => {
+                    for reminder in due {
+                        let body = match &reminder.note {
+                            Some(note) => format!("{}: {}", reminder.clip_title, note),
+                            None => reminder.clip_title.clone(),
+                        };
+                        notify(&app_handle, NotificationKind::Reminder, "Reminder", &body);
+                        if let Err(e) = events::emit(&app_handle, "reminder-due", &reminder) {
+                            error!("Failed to emit reminder-due event: {}", e);
+                        }
+                    }
+                }
+                Err(e) => error!("Failed to check reminders: {}", e),
+            }
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,204 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::warn;
+
+use crate::clips::{self, now_millis, ClipData};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::llm;
+use crate::notifications::{self, NotificationKind};
+use crate::providers::{LlmError, LlmMessage};
+use crate::search;
+use crate::secrets::SecretsManager;
+use crate::settings::SettingsManager;
+use crate::summarize;
+
+/// Sources used when the caller doesn't ask for a number
+pub const DEFAULT_SOURCE_COUNT: u32 = 5;
+const MAX_SOURCE_COUNT: u32 = 10;
+const REPORT_MAX_TOKENS: u32 = 1_500;
+
+/// Payload of the `research-progress` event
+#[derive(Debug, Serialize, Clone)]
+pub struct ResearchProgress {
+    pub query: String,
+    /// `searching`, `fetching`, `summarizing`, `synthesizing` or `done`
+    pub step: &'static str,
+    /// 1-based source being worked on, for the per-source steps
+    pub current: Option<usize>,
+    pub total: Option<usize>,
+    /// The source's URL while fetching
+    pub url: Option<String>,
+    /// Set once the report is saved
+    pub report_clip_id: Option<i64>,
+}
+
+/// A page that made it into the report
+struct Source {
+    clip_id: i64,
+    title: String,
+    url: Option<String>,
+    summary: String,
+}
+
+fn emit_progress(app_handle: &AppHandle, progress: ResearchProgress) {
+    if let Err(e) = app_handle.emit("research-progress", progress) {
+        warn!("Failed to emit research progress: {}", e);
+    }
+}
+
+fn progress(query: &str, step: &'static str) -> ResearchProgress {
+    ResearchProgress {
+        query: query.to_string(),
+        step,
+        current: None,
+        total: None,
+        url: None,
+        report_clip_id: None,
+    }
+}
+
+/// Save a fetched page as a clip, reusing the existing clip when the page is already
+/// in the library (e.g. when a failed research job is retried)
+fn save_source(app_handle: &AppHandle, clip_data: ClipData) -> Result<i64, AppError> {
+    let existing = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::find_duplicate(&conn, &clip_data)?
+    };
+    match existing {
+        Some(id) => Ok(id),
+        None => Ok(crate::ingest_clip(app_handle, clip_data)?.clip.id),
+    }
+}
+
+/// Search the web for `query`, save the top `source_count` pages as clips, summarize
+/// each, and write a report with numbered citations as a new note clip. Pages that
+/// fail to load are skipped. Emits `research-progress` per step and returns the
+/// report clip's id.
+pub async fn research_topic(app_handle: &AppHandle, query: &str, source_count: u32) -> Result<i64, LlmError> {
+    let source_count = source_count.clamp(1, MAX_SOURCE_COUNT);
+
+    emit_progress(app_handle, progress(query, "searching"));
+    let results = {
+        let settings = app_handle.state::<SettingsManager>().get();
+        let secrets_manager = app_handle.state::<SecretsManager>();
+        let db = app_handle.state::<Database>();
+        let providers = search::configured_providers(&secrets_manager, &settings).await?;
+        search::search_all(&db, &secrets_manager, &settings, &providers, query, source_count).await?.results
+    };
+    if results.is_empty() {
+        return Err(format!("No search results for \"{}\"", query).into());
+    }
+
+    let total = results.len();
+    let mut source_ids = Vec::with_capacity(total);
+    for (index, result) in results.iter().enumerate() {
+        emit_progress(
+            app_handle,
+            ResearchProgress {
+                current: Some(index + 1),
+                total: Some(total),
+                url: Some(result.url.clone()),
+                ..progress(query, "fetching")
+            },
+        );
+        match search::article_clip(result).await.and_then(|clip_data| save_source(app_handle, clip_data)) {
+            Ok(clip_id) => source_ids.push(clip_id),
+            Err(e) => warn!("Skipping research source {}: {}", result.url, e),
+        }
+    }
+    if source_ids.is_empty() {
+        return Err(format!("None of the pages found for \"{}\" could be fetched", query).into());
+    }
+
+    let mut sources = Vec::with_capacity(source_ids.len());
+    for (index, clip_id) in source_ids.iter().enumerate() {
+        emit_progress(
+            app_handle,
+            ResearchProgress {
+                current: Some(index + 1),
+                total: Some(source_ids.len()),
+                ..progress(query, "summarizing")
+            },
+        );
+        let clip = {
+            let db = app_handle.state::<Database>();
+            let conn = db.conn()?;
+            clips::get_clip(&conn, *clip_id)?.ok_or_else(|| format!("Clip {} not found", clip_id))?
+        };
+        let summary = match clip.summary {
+            Some(summary) => summary,
+            None => summarize::summarize_clip(app_handle, *clip_id).await?,
+        };
+        sources.push(Source {
+            clip_id: *clip_id,
+            title: clip.title,
+            url: clip.url,
+            summary,
+        });
+    }
+
+    emit_progress(app_handle, progress(query, "synthesizing"));
+    let numbered: Vec<String> = sources
+        .iter()
+        .enumerate()
+        .map(|(index, source)| {
+            format!(
+                "[{}] {} ({})\n{}",
+                index + 1,
+                source.title,
+                source.url.as_deref().unwrap_or("no URL"),
+                source.summary
+            )
+        })
+        .collect();
+    let messages = vec![
+        LlmMessage {
+            role: "system".to_string(),
+            content: "You write research reports from source summaries. Use Markdown headings and \
+                      paragraphs. Cite sources inline by their number, like [1] or [2][3], and only \
+                      state what the sources support. Don't add a list of sources; it is appended \
+                      for you."
+                .to_string(),
+        },
+        LlmMessage {
+            role: "user".to_string(),
+            content: format!("Topic: {}\n\nSources:\n\n{}", query, numbered.join("\n\n")),
+        },
+    ];
+    let report = llm::complete_with_default(app_handle, "research", messages, Some(REPORT_MAX_TOKENS)).await?;
+
+    let references: Vec<String> = sources
+        .iter()
+        .enumerate()
+        .map(|(index, source)| match &source.url {
+            Some(url) => format!("{}. [{}]({}) (clip {})", index + 1, source.title, url, source.clip_id),
+            None => format!("{}. {} (clip {})", index + 1, source.title, source.clip_id),
+        })
+        .collect();
+    let title = format!("Research: {}", query);
+    let report_clip = ClipData {
+        r#type: "note".to_string(),
+        title: title.clone(),
+        url: None,
+        content: Some(format!("{}\n\n## Sources\n\n{}", report.content.trim(), references.join("\n"))),
+        image_url: None,
+        description: Some(format!("Research report from {} sources", sources.len())),
+        author: None,
+        timestamp: now_millis() as u64,
+        original_url: None,
+        language: None,
+    };
+    let report_clip_id = crate::ingest_clip(app_handle, report_clip)?.clip.id;
+
+    emit_progress(
+        app_handle,
+        ResearchProgress {
+            report_clip_id: Some(report_clip_id),
+            ..progress(query, "done")
+        },
+    );
+    notifications::notify(app_handle, NotificationKind::Enrichment, "Research report ready", &title);
+    Ok(report_clip_id)
+}
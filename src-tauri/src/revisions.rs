@@ -0,0 +1,101 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+
+use crate::clips::{self, now_millis, ClipUpdate, SqliteClip};
+use crate::errors::AppError;
+
+/// Older revisions beyond this many per clip are dropped
+const MAX_REVISIONS_PER_CLIP: i64 = 50;
+
+/// The title and content a clip had before an edit
+#[derive(Debug, Serialize)]
+pub struct ClipRevision {
+    pub id: i64,
+    pub clip_id: i64,
+    pub title: String,
+    pub content: Option<String>,
+    /// The clip's `updated_at` while this version was current
+    pub clip_updated_at: i64,
+    /// When the version was replaced, in milliseconds since the epoch
+    pub created_at: i64,
+}
+
+const REVISION_COLUMNS: &str = "id, clip_id, title, content, clip_updated_at, created_at";
+
+impl ClipRevision {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(ClipRevision {
+            id: row.get(0)?,
+            clip_id: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            clip_updated_at: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }
+}
+
+/// Save the version of `clip` that an edit is about to replace, pruning the oldest
+/// revisions past `MAX_REVISIONS_PER_CLIP`
+pub fn record(conn: &Connection, clip: &SqliteClip) -> Result<(), AppError> {
+    record_for(conn, clip.id, clip)
+}
+
+/// Save `version`'s title and content as a revision of the clip `clip_id`, such as a
+/// duplicate's text when it's merged into the clip
+pub fn record_for(conn: &Connection, clip_id: i64, version: &SqliteClip) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO clip_revisions (clip_id, title, content, clip_updated_at, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![clip_id, version.title, version.content, version.updated_at, now_millis()],
+    )
+    .map_err(|e| AppError::database(format!("Failed to save revision: {}", e)))?;
+    conn.execute(
+        "DELETE FROM clip_revisions WHERE clip_id = ?1 AND id NOT IN (
+            SELECT id FROM clip_revisions WHERE clip_id = ?1 ORDER BY id DESC LIMIT ?2
+         )",
+        params![clip_id, MAX_REVISIONS_PER_CLIP],
+    )
+    .map_err(|e| AppError::database(format!("Failed to prune revisions: {}", e)))?;
+    Ok(())
+}
+
+/// A clip's earlier versions, newest first
+pub fn list_revisions(conn: &Connection, clip_id: i64) -> Result<Vec<ClipRevision>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM clip_revisions WHERE clip_id = ?1 ORDER BY id DESC",
+            REVISION_COLUMNS
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let revisions = stmt
+        .query_map(params![clip_id], ClipRevision::from_row)
+        .map_err(|e| AppError::database(format!("Failed to list revisions: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read revision: {}", e)))?;
+    Ok(revisions)
+}
+
+/// Put a revision's title and content back. This is an edit like any other, so the
+/// version being replaced becomes a revision itself and the restore can be undone.
+pub fn restore_revision(conn: &Connection, revision_id: i64) -> Result<SqliteClip, AppError> {
+    let revision = conn
+        .query_row(
+            &format!("SELECT {} FROM clip_revisions WHERE id = ?1", REVISION_COLUMNS),
+            params![revision_id],
+            ClipRevision::from_row,
+        )
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read revision: {}", e)))?
+        .ok_or_else(|| AppError::not_found(format!("Revision {} not found", revision_id)))?;
+
+    clips::update_clip(
+        conn,
+        revision.clip_id,
+        ClipUpdate {
+            title: Some(revision.title),
+            content: Some(revision.content),
+            ..Default::default()
+        },
+    )
+}
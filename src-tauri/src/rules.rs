@@ -0,0 +1,334 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::clips::{self, now_millis, ClipData, CLIP_TYPES};
+use crate::errors::AppError;
+use crate::extract::plain_text;
+use crate::sources;
+use crate::tags;
+
+const MAX_NAME_LEN: usize = 128;
+
+/// What a clip must look like for a rule to apply. Every non-empty list has to match
+/// (any one entry of it); empty lists are ignored.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RuleConditions {
+    /// Hosts like `github.com`; subdomains match too
+    pub domains: Vec<String>,
+    /// Clip types from `CLIP_TYPES`
+    pub types: Vec<String>,
+    /// Case-insensitive words looked for in the title, URL, description and content
+    pub keywords: Vec<String>,
+}
+
+/// What happens to a clip a rule matches
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RuleActions {
+    pub tags: Vec<String>,
+    pub collection_id: Option<i64>,
+    /// Queue a summary right away
+    pub summarize: bool,
+    /// Don't store the clip at all
+    pub skip: bool,
+    /// Save an offline snapshot of the page
+    pub archive: bool,
+}
+
+/// An ingestion rule, evaluated against every new clip in creation order
+#[derive(Debug, Serialize, Clone)]
+pub struct Rule {
+    pub id: i64,
+    pub name: String,
+    pub enabled: bool,
+    pub conditions: RuleConditions,
+    pub actions: RuleActions,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Fields of a rule to create, or to replace an existing rule's with
+#[derive(Debug, Deserialize)]
+pub struct NewRule {
+    pub name: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub conditions: RuleConditions,
+    #[serde(default)]
+    pub actions: RuleActions,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// The combined actions of every rule matching a clip
+#[derive(Debug, Serialize, Default)]
+pub struct RuleOutcome {
+    /// Names of the matching rules, in evaluation order
+    pub matched: Vec<String>,
+    pub skip: bool,
+    pub tags: Vec<String>,
+    /// From the first matching rule that names a collection
+    pub collection_id: Option<i64>,
+    pub summarize: bool,
+    pub archive: bool,
+    /// Domain of the clip when it's a muted source; the clip is dropped like `skip`
+    pub muted_source: Option<String>,
+}
+
+/// Result of the dry-run `test_rule` command
+#[derive(Debug, Serialize)]
+pub struct RuleTest {
+    pub matches: bool,
+    /// What the rule would do to the sample clip, if it matches
+    pub outcome: RuleOutcome,
+}
+
+const RULE_COLUMNS: &str = "id, name, enabled, conditions, actions, created_at, updated_at";
+
+fn rule_from_row(row: &rusqlite::Row) -> rusqlite::Result<Rule> {
+    let conditions: String = row.get(3)?;
+    let actions: String = row.get(4)?;
+    Ok(Rule {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        enabled: row.get(2)?,
+        // A rule that no longer parses matches nothing rather than breaking ingestion
+        conditions: serde_json::from_str(&conditions).unwrap_or_default(),
+        actions: serde_json::from_str(&actions).unwrap_or_default(),
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// Trim the rule's lists, drop empty entries and reject anything that can't match
+fn normalize(rule: NewRule) -> Result<NewRule, AppError> {
+    let name = rule.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::validation("Rule name must not be empty"));
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::validation(format!("Rule name must be at most {} characters", MAX_NAME_LEN)));
+    }
+
+    let clean = |values: Vec<String>| -> Vec<String> {
+        values
+            .into_iter()
+            .map(|value| value.trim().to_lowercase())
+            .filter(|value| !value.is_empty())
+            .collect()
+    };
+    let domains = clean(rule.conditions.domains)
+        .into_iter()
+        .map(|domain| domain.strip_prefix("www.").map(str::to_string).unwrap_or(domain))
+        .collect();
+    let types = clean(rule.conditions.types);
+    if let Some(unknown) = types.iter().find(|t| !CLIP_TYPES.contains(&t.as_str())) {
+        return Err(AppError::validation(format!("Unknown clip type \"{}\"", unknown)));
+    }
+    let conditions = RuleConditions {
+        domains,
+        types,
+        keywords: clean(rule.conditions.keywords),
+    };
+    // A rule without conditions would apply to every clip, which is never what a skip rule means
+    if conditions.domains.is_empty() && conditions.types.is_empty() && conditions.keywords.is_empty() {
+        return Err(AppError::validation("A rule needs at least one domain, type or keyword"));
+    }
+
+    let tags = rule
+        .actions
+        .tags
+        .iter()
+        .map(|tag| tags::normalize_tag(tag))
+        .collect::<Result<Vec<_>, _>>()?;
+    let actions = RuleActions { tags, ..rule.actions };
+    if actions.skip && (!actions.tags.is_empty() || actions.collection_id.is_some() || actions.summarize || actions.archive) {
+        return Err(AppError::validation("A rule that skips clips can't have other actions"));
+    }
+    if !actions.skip && actions.tags.is_empty() && actions.collection_id.is_none() && !actions.summarize && !actions.archive {
+        return Err(AppError::validation("A rule needs at least one action"));
+    }
+
+    Ok(NewRule {
+        name,
+        enabled: rule.enabled,
+        conditions,
+        actions,
+    })
+}
+
+/// Map a unique-name violation to a validation error
+fn write_error(e: rusqlite::Error, name: &str) -> AppError {
+    match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            AppError::validation(format!("A rule named \"{}\" already exists", name))
+        }
+        e => AppError::database(format!("Failed to save rule: {}", e)),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string(value).map_err(|e| AppError::internal(format!("Failed to serialize rule: {}", e)))
+}
+
+pub fn get_rule(conn: &Connection, id: i64) -> Result<Option<Rule>, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM rules WHERE id = ?1", RULE_COLUMNS),
+        params![id],
+        rule_from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read rule: {}", e)))
+}
+
+pub fn list_rules(conn: &Connection) -> Result<Vec<Rule>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM rules ORDER BY id", RULE_COLUMNS))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let rules = stmt
+        .query_map([], rule_from_row)
+        .map_err(|e| AppError::database(format!("Failed to list rules: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to list rules: {}", e)))?;
+    Ok(rules)
+}
+
+pub fn create_rule(conn: &Connection, rule: NewRule) -> Result<Rule, AppError> {
+    let rule = normalize(rule)?;
+    conn.execute(
+        "INSERT INTO rules (name, enabled, conditions, actions, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+        params![rule.name, rule.enabled, to_json(&rule.conditions)?, to_json(&rule.actions)?, now_millis()],
+    )
+    .map_err(|e| write_error(e, &rule.name))?;
+
+    let id = conn.last_insert_rowid();
+    get_rule(conn, id)?.ok_or_else(|| AppError::database(format!("Rule {} vanished after insert", id)))
+}
+
+pub fn update_rule(conn: &Connection, id: i64, rule: NewRule) -> Result<Rule, AppError> {
+    let rule = normalize(rule)?;
+    let changed = conn
+        .execute(
+            "UPDATE rules SET name = ?1, enabled = ?2, conditions = ?3, actions = ?4, updated_at = ?5 WHERE id = ?6",
+            params![rule.name, rule.enabled, to_json(&rule.conditions)?, to_json(&rule.actions)?, now_millis(), id],
+        )
+        .map_err(|e| write_error(e, &rule.name))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Rule {} not found", id)));
+    }
+    get_rule(conn, id)?.ok_or_else(|| AppError::not_found(format!("Rule {} not found", id)))
+}
+
+pub fn delete_rule(conn: &Connection, id: i64) -> Result<(), AppError> {
+    let deleted = conn
+        .execute("DELETE FROM rules WHERE id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to delete rule: {}", e)))?;
+    if deleted == 0 {
+        return Err(AppError::not_found(format!("Rule {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Whether `clip` meets every condition of `conditions`
+fn matches(conditions: &RuleConditions, clip: &ClipData) -> bool {
+    if !conditions.domains.is_empty() {
+        let Some(domain) = clip.url.as_deref().and_then(clips::domain_of) else {
+            return false;
+        };
+        let on_domain = |rule_domain: &String| {
+            domain == *rule_domain || domain.strip_suffix(rule_domain.as_str()).is_some_and(|sub| sub.ends_with('.'))
+        };
+        if !conditions.domains.iter().any(on_domain) {
+            return false;
+        }
+    }
+    if !conditions.types.is_empty() && !conditions.types.contains(&clip.r#type) {
+        return false;
+    }
+    if !conditions.keywords.is_empty() {
+        let text = [
+            Some(clip.title.clone()),
+            clip.url.clone(),
+            clip.description.clone(),
+            clip.content.as_deref().map(plain_text),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_lowercase();
+        if !conditions.keywords.iter().any(|keyword| text.contains(keyword.as_str())) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Combine the actions of the enabled rules in `rules` that match `clip`
+pub fn evaluate(rules: &[Rule], clip: &ClipData) -> RuleOutcome {
+    let mut outcome = RuleOutcome::default();
+    for rule in rules.iter().filter(|rule| rule.enabled && matches(&rule.conditions, clip)) {
+        outcome.matched.push(rule.name.clone());
+        let actions = &rule.actions;
+        outcome.skip |= actions.skip;
+        outcome.summarize |= actions.summarize;
+        outcome.archive |= actions.archive;
+        for tag in &actions.tags {
+            if !outcome.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                outcome.tags.push(tag.clone());
+            }
+        }
+        if outcome.collection_id.is_none() {
+            outcome.collection_id = actions.collection_id;
+        }
+    }
+    outcome
+}
+
+/// Evaluate the stored rules against a clip about to be ingested, then the settings of
+/// the source it came from. Rules go first, so a rule's collection wins over the source's.
+pub fn evaluate_stored(conn: &Connection, clip: &ClipData) -> Result<RuleOutcome, AppError> {
+    let mut outcome = evaluate(&list_rules(conn)?, clip);
+    let Some(domain) = clip.url.as_deref().and_then(clips::domain_of) else {
+        return Ok(outcome);
+    };
+    let source = sources::source_settings(conn, &domain)?;
+    if source.muted {
+        outcome.muted_source = Some(domain);
+        return Ok(outcome);
+    }
+    for tag in source.default_tags {
+        if !outcome.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            outcome.tags.push(tag);
+        }
+    }
+    if outcome.collection_id.is_none() {
+        outcome.collection_id = source.default_collection_id;
+    }
+    Ok(outcome)
+}
+
+/// Check what an unsaved rule would do to `sample_clip` without storing anything
+pub fn test_rule(rule: NewRule, sample_clip: &ClipData) -> Result<RuleTest, AppError> {
+    let rule = normalize(rule)?;
+    let rule = Rule {
+        id: 0,
+        name: rule.name,
+        // Disabled rules are tested as if enabled; the point is to see what they'd do
+        enabled: true,
+        conditions: rule.conditions,
+        actions: rule.actions,
+        created_at: 0,
+        updated_at: 0,
+    };
+    let outcome = evaluate(std::slice::from_ref(&rule), sample_clip);
+    Ok(RuleTest {
+        matches: !outcome.matched.is_empty(),
+        outcome,
+    })
+}
@@ -0,0 +1,140 @@
+use crate::clips::{self, ClipData};
+use crate::errors::AppError;
+
+const MAX_TITLE_LEN: usize = 1_000;
+const MAX_URL_LEN: usize = 8_192;
+const MAX_AUTHOR_LEN: usize = 500;
+const MAX_DESCRIPTION_LEN: usize = 20_000;
+/// Long enough for a book-length article or PDF text
+const MAX_CONTENT_LEN: usize = 5_000_000;
+/// Inline images from the clipper; larger ones should be sent as a link
+const MAX_DATA_URL_LEN: usize = 2_000_000;
+/// Clip types whose content is written or extracted as text or Markdown, never HTML
+const TEXT_TYPES: &[&str] = &["note", "code", "pdf", "audio", "digest"];
+
+/// Reject clips that are malformed, oversized or point at URLs the app shouldn't open.
+/// Cheap, so callers that need to tell these apart from other failures can run it first.
+pub fn check(clip: &ClipData) -> Result<(), AppError> {
+    clips::validate(clip)?;
+    check_len("title", &clip.title, MAX_TITLE_LEN)?;
+    check_len("author", clip.author.as_deref().unwrap_or_default(), MAX_AUTHOR_LEN)?;
+    check_len("description", clip.description.as_deref().unwrap_or_default(), MAX_DESCRIPTION_LEN)?;
+    check_len("content", clip.content.as_deref().unwrap_or_default(), MAX_CONTENT_LEN)?;
+    for (field, url) in [("url", &clip.url), ("original_url", &clip.original_url)] {
+        if let Some(url) = url {
+            check_url(field, url, false)?;
+        }
+    }
+    if let Some(image_url) = &clip.image_url {
+        check_url("image_url", image_url, true)?;
+    }
+    Ok(())
+}
+
+/// `check` the clip, then strip scripts, event handlers and unsafe links from HTML in its
+/// content and description, and control characters from its title and author. Notes,
+/// code and other text clips keep their content byte for byte, so `Vec<String>` in a
+/// note isn't taken for a tag.
+pub fn clip(mut clip: ClipData) -> Result<ClipData, AppError> {
+    check(&clip)?;
+    clip.title = strip_controls(&clip.title);
+    clip.author = clip.author.as_deref().map(strip_controls);
+    if !is_text_type(&clip.r#type) {
+        clip.content = clip.content.as_deref().map(clean_html);
+    }
+    clip.description = clip.description.as_deref().map(clean_html);
+    Ok(clip)
+}
+
+fn check_len(field: &str, value: &str, max: usize) -> Result<(), AppError> {
+    if value.len() > max {
+        let message = format!("Clip {} is too long ({} bytes, at most {})", field, value.len(), max);
+        return Err(AppError::validation(message));
+    }
+    Ok(())
+}
+
+/// Only web URLs, plus inline images for `image_url`; `javascript:`, `file:` and the
+/// like are refused
+fn check_url(field: &str, url: &str, allow_data_image: bool) -> Result<(), AppError> {
+    let url = url.trim();
+    if allow_data_image && url.get(..11).is_some_and(|scheme| scheme.eq_ignore_ascii_case("data:image/")) {
+        return check_len(field, url, MAX_DATA_URL_LEN);
+    }
+    check_len(field, url, MAX_URL_LEN)?;
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| AppError::validation(format!("Clip {} is not a valid URL: {}", field, e)))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(()),
+        scheme => Err(AppError::validation(format!("Clip {} uses the unsupported scheme '{}:'", field, scheme))),
+    }
+}
+
+/// Whether a clip of this type holds text or Markdown rather than HTML
+pub fn is_text_type(clip_type: &str) -> bool {
+    TEXT_TYPES.contains(&clip_type)
+}
+
+/// Whether `value` has anything that looks like a tag, as opposed to a bare `<`
+pub fn contains_html(value: &str) -> bool {
+    value
+        .as_bytes()
+        .windows(2)
+        .any(|w| w[0] == b'<' && (w[1].is_ascii_alphabetic() || w[1] == b'/' || w[1] == b'!'))
+}
+
+/// Plain text and Markdown are left alone; only values that contain tags are cleaned,
+/// since cleaning escapes a bare `<` or `&`
+fn clean_html(value: &str) -> String {
+    if contains_html(value) {
+        ammonia::clean(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Line breaks and tabs become spaces; other control characters are dropped
+fn strip_controls(value: &str) -> String {
+    value
+        .chars()
+        .filter_map(|c| match c {
+            '\n' | '\r' | '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip_of(r#type: &str, content: &str) -> ClipData {
+        ClipData {
+            r#type: r#type.to_string(),
+            title: "Sample".to_string(),
+            content: Some(content.to_string()),
+            url: None,
+            image_url: None,
+            description: None,
+            author: None,
+            timestamp: 1_700_000_000_000,
+            original_url: None,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn notes_keep_angle_brackets() {
+        for content in ["Vec<String>", "a<b>c", "Use <T: Clone> here\nand & there"] {
+            let cleaned = clip(clip_of("note", content)).unwrap();
+            assert_eq!(cleaned.content.as_deref(), Some(content));
+        }
+    }
+
+    #[test]
+    fn articles_are_still_cleaned() {
+        let cleaned = clip(clip_of("article", "<p>Hi<script>alert(1)</script></p>")).unwrap();
+        assert_eq!(cleaned.content.as_deref(), Some("<p>Hi</p>"));
+    }
+}
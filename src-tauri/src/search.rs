@@ -0,0 +1,804 @@
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use futures::future::join_all;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::time::Instant;
+use tauri::AppHandle;
+use tracing::warn;
+
+use crate::clips::{normalize_url, now_millis, ClipData, ClipInsert};
+use crate::connectivity;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract;
+use crate::http;
+use crate::providers::{send_json_once, KeyDetails, KeyLimit, LlmError};
+use crate::search_cache;
+use crate::secrets::SecretsManager;
+use crate::settings::Settings;
+
+const BRAVE_SEARCH_URL: &str = "https://api.search.brave.com/res/v1/web/search";
+/// Brave returns at most this many results per request
+const BRAVE_PAGE_SIZE: u32 = 20;
+/// Brave rejects offsets (page indexes) above this
+const BRAVE_MAX_OFFSET: u32 = 9;
+
+/// Web search backends. Brave and Google need API keys; DuckDuckGo and SearxNG don't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchProvider {
+    Brave,
+    Google,
+    #[default]
+    DuckDuckGo,
+    /// A self-hosted or public SearxNG instance set in `Settings::searxng_url`
+    Searxng,
+}
+
+impl SearchProvider {
+    /// Same as the serialized name
+    pub fn id(self) -> &'static str {
+        match self {
+            SearchProvider::Brave => "brave",
+            SearchProvider::Google => "google",
+            SearchProvider::DuckDuckGo => "duckduckgo",
+            SearchProvider::Searxng => "searxng",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub description: String,
+    pub snippet: Option<String>,
+    /// Engines that returned this result, best-ranked first
+    #[serde(default)]
+    pub sources: Vec<SearchProvider>,
+}
+
+/// Provider quota information reported alongside results
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RateLimit {
+    pub limit: Option<u32>,
+    pub remaining: Option<u32>,
+    /// Seconds until the current window resets
+    pub reset_seconds: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+    pub total_results: u32,
+    pub search_time: f64,
+    pub rate_limit: Option<RateLimit>,
+    /// Served from the local search cache rather than the provider
+    #[serde(default)]
+    pub cache_hit: bool,
+    /// When a cached response was originally fetched, in milliseconds since the epoch
+    #[serde(default)]
+    pub cached_at: Option<i64>,
+}
+
+/// Brave sends comma-separated values per window (per-second, per-month);
+/// the first one is the window that trips first
+fn first_header_value(headers: &reqwest::header::HeaderMap, name: &str) -> Option<u32> {
+    headers
+        .get(name)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn brave_rate_limit(headers: &reqwest::header::HeaderMap) -> RateLimit {
+    RateLimit {
+        limit: first_header_value(headers, "x-ratelimit-limit"),
+        remaining: first_header_value(headers, "x-ratelimit-remaining"),
+        reset_seconds: first_header_value(headers, "x-ratelimit-reset"),
+    }
+}
+
+/// Check a Brave key with a one-result query. Every response carries the plan's
+/// per-second and per-month allowances as comma-separated header values.
+pub async fn check_brave_key(api_key: &str) -> Result<KeyDetails, LlmError> {
+    let (headers, _) = send_json_once(
+        http::client()
+            .get(BRAVE_SEARCH_URL)
+            .header("X-Subscription-Token", api_key)
+            .header("Accept", "application/json")
+            .query(&[("q", "brave"), ("count", "1")]),
+    )
+    .await
+    .map_err(|e| match e {
+        // Brave answers a bad token with 422 rather than 401
+        LlmError::Api { status: 422, message } if message.contains("SUBSCRIPTION_TOKEN_INVALID") => {
+            LlmError::Auth { message }
+        }
+        other => other,
+    })?;
+    let values = |name: &str| -> Vec<f64> {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.split(',').filter_map(|n| n.trim().parse().ok()).collect())
+            .unwrap_or_default()
+    };
+    let (limit, remaining, reset) = (
+        values("x-ratelimit-limit"),
+        values("x-ratelimit-remaining"),
+        values("x-ratelimit-reset"),
+    );
+    let limits = ["requests per second", "requests per month"]
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i < limit.len())
+        .map(|(i, name)| KeyLimit {
+            name: name.to_string(),
+            limit: limit.get(i).copied(),
+            remaining: remaining.get(i).copied(),
+            reset: reset.get(i).map(|seconds| format!("{}s", seconds)),
+        })
+        .collect();
+    Ok(KeyDetails {
+        limits,
+        ..KeyDetails::default()
+    })
+}
+
+/// Query the Brave Search web endpoint, paging until `num_results` are collected
+pub async fn search_brave(api_key: &str, query: &str, num_results: u32) -> Result<SearchResponse, AppError> {
+    let client = http::client();
+    let started = Instant::now();
+    let mut results = Vec::new();
+    let mut rate_limit = None;
+
+    // Brave's offset counts pages of `count`, so the page size must stay fixed
+    let count = num_results.clamp(1, BRAVE_PAGE_SIZE);
+    let mut offset = 0;
+    while (results.len() as u32) < num_results && offset <= BRAVE_MAX_OFFSET {
+        let response = client
+            .get(BRAVE_SEARCH_URL)
+            .header("X-Subscription-Token", api_key)
+            .header("Accept", "application/json")
+            .query(&[
+                ("q", query.to_string()),
+                ("count", count.to_string()),
+                ("offset", offset.to_string()),
+            ])
+            .send()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to send request: {}", e)))?;
+
+        let limits = brave_rate_limit(response.headers());
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(AppError::network(match limits.reset_seconds {
+                Some(seconds) => format!("Brave Search rate limit reached, retry in {}s", seconds),
+                None => "Brave Search rate limit reached".to_string(),
+            }));
+        }
+        rate_limit = Some(limits);
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::network(format!("Brave Search API error: {}", error_text)));
+        }
+
+        let response_json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to parse response: {}", e)))?;
+
+        let page = response_json["web"]["results"].as_array().cloned().unwrap_or_default();
+        let page_len = page.len() as u32;
+        for item in page {
+            let url = match item["url"].as_str() {
+                Some(url) => url.to_string(),
+                None => continue,
+            };
+            let snippet = item["extra_snippets"]
+                .as_array()
+                .map(|snippets| {
+                    snippets
+                        .iter()
+                        .filter_map(|s| s.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" … ")
+                })
+                .filter(|s| !s.is_empty());
+            results.push(SearchResult {
+                title: item["title"].as_str().unwrap_or_default().to_string(),
+                url,
+                description: item["description"].as_str().unwrap_or_default().to_string(),
+                snippet,
+                sources: vec![SearchProvider::Brave],
+            });
+        }
+
+        // A short page or no "more results" flag means we've exhausted the query
+        let more_available = response_json["query"]["more_results_available"].as_bool().unwrap_or(false);
+        if page_len < count || !more_available {
+            break;
+        }
+        offset += 1;
+    }
+
+    results.truncate(num_results as usize);
+    Ok(SearchResponse {
+        total_results: results.len() as u32,
+        results,
+        search_time: started.elapsed().as_secs_f64(),
+        rate_limit,
+        cache_hit: false,
+        cached_at: None,
+    })
+}
+
+/// Google returns at most this many results per request
+const GOOGLE_PAGE_SIZE: u32 = 10;
+/// Google serves no results past the 100th
+const GOOGLE_MAX_RESULTS: u32 = 100;
+
+/// Query the Google Programmable Search JSON API, paging until `num_results`
+/// are collected. `engine_id` is the Programmable Search Engine `cx` value.
+pub async fn search_google(
+    api_key: &str,
+    engine_id: &str,
+    query: &str,
+    num_results: u32,
+    safe_search: bool,
+) -> Result<SearchResponse, AppError> {
+    let client = http::client();
+    let num_results = num_results.min(GOOGLE_MAX_RESULTS);
+    let mut results = Vec::new();
+    let mut total_results = 0;
+    let mut search_time = 0.0;
+
+    // `start` is the 1-based index of the first result on the page
+    let mut start = 1;
+    while (results.len() as u32) < num_results && start <= GOOGLE_MAX_RESULTS {
+        let num = (num_results - results.len() as u32).min(GOOGLE_PAGE_SIZE);
+        let response = client
+            .get("https://www.googleapis.com/customsearch/v1")
+            .query(&[
+                ("key", api_key.to_string()),
+                ("cx", engine_id.to_string()),
+                ("q", query.to_string()),
+                ("num", num.to_string()),
+                ("start", start.to_string()),
+                ("safe", if safe_search { "active" } else { "off" }.to_string()),
+            ])
+            .send()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to send request: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_json: serde_json::Value = response.json().await.unwrap_or_default();
+            let message = error_json["error"]["message"].as_str().unwrap_or("Unknown error");
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(AppError::network(format!("Google Search quota exceeded: {}", message)));
+            }
+            return Err(AppError::network(format!("Google Search API error: {}", message)));
+        }
+
+        let response_json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to parse response: {}", e)))?;
+
+        let info = &response_json["searchInformation"];
+        search_time += info["searchTime"].as_f64().unwrap_or(0.0);
+        if start == 1 {
+            // Google reports the total as a string, e.g. "12300000"
+            total_results = info["totalResults"]
+                .as_str()
+                .and_then(|total| total.parse::<u64>().ok())
+                .map(|total| total.min(u32::MAX as u64) as u32)
+                .unwrap_or(0);
+        }
+
+        let page = response_json["items"].as_array().cloned().unwrap_or_default();
+        let page_len = page.len() as u32;
+        for item in page {
+            let url = match item["link"].as_str() {
+                Some(url) => url.to_string(),
+                None => continue,
+            };
+            let snippet = item["pagemap"]["metatags"][0]["og:description"]
+                .as_str()
+                .map(|s| s.to_string());
+            results.push(SearchResult {
+                title: item["title"].as_str().unwrap_or_default().to_string(),
+                url,
+                description: item["snippet"].as_str().unwrap_or_default().to_string(),
+                snippet,
+                sources: vec![SearchProvider::Google],
+            });
+        }
+
+        if page_len < num || response_json["queries"]["nextPage"].is_null() {
+            break;
+        }
+        start += page_len;
+    }
+
+    Ok(SearchResponse {
+        total_results,
+        results,
+        search_time,
+        rate_limit: None,
+        cache_hit: false,
+        cached_at: None,
+    })
+}
+
+/// DuckDuckGo's HTML endpoint, which needs no API key
+const DUCKDUCKGO_URL: &str = "https://html.duckduckgo.com/html/";
+/// Result pages fetched from DuckDuckGo at most; it throttles clients that page quickly
+const DUCKDUCKGO_MAX_PAGES: u32 = 3;
+/// DuckDuckGo serves an empty page to clients without a browser-like user agent
+pub const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+
+/// Search DuckDuckGo by scraping its HTML results page. There's no rate-limit
+/// information; a blocked request surfaces as a network error.
+pub async fn search_duckduckgo(query: &str, num_results: u32) -> Result<SearchResponse, AppError> {
+    let client = http::client();
+    let started = Instant::now();
+    let mut results: Vec<SearchResult> = Vec::new();
+    let mut seen = HashSet::new();
+
+    let mut page = 0;
+    while (results.len() as u32) < num_results && page < DUCKDUCKGO_MAX_PAGES {
+        let offset = results.len();
+        let response = client
+            .get(DUCKDUCKGO_URL)
+            .header("User-Agent", BROWSER_USER_AGENT)
+            .header("Accept", "text/html")
+            .query(&[("q", query.to_string()), ("s", offset.to_string()), ("dc", (offset + 1).to_string())])
+            .send()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to send request: {}", e)))?;
+
+        // DuckDuckGo answers suspected bots with 202 and a challenge page
+        if response.status() != reqwest::StatusCode::OK {
+            return Err(AppError::network(format!(
+                "DuckDuckGo rejected the request (HTTP {}); try again later",
+                response.status()
+            )));
+        }
+        let html = response
+            .text()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to read response: {}", e)))?;
+
+        let page_results = parse_duckduckgo_html(&html)?;
+        let before = results.len();
+        results.extend(page_results.into_iter().filter(|result| seen.insert(result.url.clone())));
+        if results.len() == before {
+            break;
+        }
+        page += 1;
+    }
+
+    results.truncate(num_results as usize);
+    Ok(SearchResponse {
+        total_results: results.len() as u32,
+        results,
+        search_time: started.elapsed().as_secs_f64(),
+        rate_limit: None,
+        cache_hit: false,
+        cached_at: None,
+    })
+}
+
+fn parse_duckduckgo_html(html: &str) -> Result<Vec<SearchResult>, AppError> {
+    let document = Html::parse_document(html);
+    let result_selector = Selector::parse("div.result").expect("static selector is valid");
+    let link_selector = Selector::parse("a.result__a").expect("static selector is valid");
+    let snippet_selector = Selector::parse(".result__snippet").expect("static selector is valid");
+    let challenge_selector = Selector::parse(".anomaly-modal, #challenge-form").expect("static selector is valid");
+
+    if document.select(&challenge_selector).next().is_some() {
+        return Err(AppError::network("DuckDuckGo is asking for a captcha; try again later"));
+    }
+
+    let results = document
+        .select(&result_selector)
+        // Sponsored results
+        .filter(|result| !result.value().classes().any(|class| class == "result--ad"))
+        .filter_map(|result| {
+            let link = result.select(&link_selector).next()?;
+            let url = duckduckgo_target(link.value().attr("href")?)?;
+            let description = result
+                .select(&snippet_selector)
+                .next()
+                .map(|snippet| snippet.text().collect::<String>().trim().to_string())
+                .unwrap_or_default();
+            Some(SearchResult {
+                title: link.text().collect::<String>().trim().to_string(),
+                url,
+                description,
+                snippet: None,
+                sources: vec![SearchProvider::DuckDuckGo],
+            })
+        })
+        .collect();
+    Ok(results)
+}
+
+/// Result links go through DuckDuckGo's redirector (`//duckduckgo.com/l/?uddg=<url>`);
+/// pull out the destination
+fn duckduckgo_target(href: &str) -> Option<String> {
+    let absolute = if href.starts_with("//") {
+        format!("https:{}", href)
+    } else {
+        href.to_string()
+    };
+    let url = reqwest::Url::parse(&absolute).ok()?;
+    if url.path() == "/l/" {
+        return url
+            .query_pairs()
+            .find(|(key, _)| key == "uddg")
+            .map(|(_, target)| target.into_owned());
+    }
+    matches!(url.scheme(), "http" | "https").then_some(absolute)
+}
+
+/// Result pages fetched from a SearxNG instance at most
+const SEARXNG_MAX_PAGES: u32 = 5;
+
+/// Query a SearxNG instance's JSON API. The instance must have the `json` format
+/// enabled under `search.formats` in its settings.yml.
+pub async fn search_searxng(base_url: &str, query: &str, num_results: u32) -> Result<SearchResponse, AppError> {
+    let client = http::client();
+    let endpoint = format!("{}/search", base_url.trim_end_matches('/'));
+    let started = Instant::now();
+    let mut results: Vec<SearchResult> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut total_results = 0;
+
+    // `pageno` is 1-based
+    let mut page = 1;
+    while (results.len() as u32) < num_results && page <= SEARXNG_MAX_PAGES {
+        let response = client
+            .get(&endpoint)
+            .header("Accept", "application/json")
+            .query(&[("q", query.to_string()), ("format", "json".to_string()), ("pageno", page.to_string())])
+            .send()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to reach SearxNG at {}: {}", base_url, e)))?;
+
+        if response.status() == reqwest::StatusCode::FORBIDDEN {
+            return Err(AppError::network(format!(
+                "SearxNG at {} refused JSON results; enable the json format in its settings",
+                base_url
+            )));
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::network(format!("SearxNG error: {}", error_text)));
+        }
+
+        let response_json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to parse response: {}", e)))?;
+        if page == 1 {
+            total_results = response_json["number_of_results"].as_u64().unwrap_or(0).min(u32::MAX as u64) as u32;
+        }
+
+        let before = results.len();
+        for item in response_json["results"].as_array().cloned().unwrap_or_default() {
+            let url = match item["url"].as_str() {
+                Some(url) => url.to_string(),
+                None => continue,
+            };
+            if !seen.insert(url.clone()) {
+                continue;
+            }
+            results.push(SearchResult {
+                title: item["title"].as_str().unwrap_or_default().to_string(),
+                url,
+                description: item["content"].as_str().unwrap_or_default().to_string(),
+                snippet: None,
+                sources: vec![SearchProvider::Searxng],
+            });
+        }
+        if results.len() == before {
+            break;
+        }
+        page += 1;
+    }
+
+    results.truncate(num_results as usize);
+    Ok(SearchResponse {
+        // Many engines don't report a total, leaving SearxNG's estimate at 0
+        total_results: total_results.max(results.len() as u32),
+        results,
+        search_time: started.elapsed().as_secs_f64(),
+        rate_limit: None,
+        cache_hit: false,
+        cached_at: None,
+    })
+}
+
+/// A search API key from the secrets store; a missing key is reported as an auth error
+pub async fn search_api_key(secrets_manager: &SecretsManager, name: &str) -> Result<String, AppError> {
+    secrets_manager.get_secret(name, "web_search").await.map_err(|e| match e {
+        AppError::NotFound { .. } => AppError::auth(format!("No {} stored; add it in settings", name)),
+        other => other,
+    })
+}
+
+/// Answer from the search cache when the same query was run within the cache TTL;
+/// otherwise run `fetch` and cache what it returns. `query_hash` comes from
+/// `search_cache::query_hash`.
+pub async fn cached<F>(
+    db: &Database,
+    settings: &Settings,
+    provider: SearchProvider,
+    query_hash: &str,
+    fetch: F,
+) -> Result<SearchResponse, AppError>
+where
+    F: Future<Output = Result<SearchResponse, AppError>>,
+{
+    let ttl = settings.search_cache_ttl();
+    if ttl.is_zero() {
+        connectivity::ensure_online()?;
+        return fetch.await;
+    }
+    if let Some(hit) = search_cache::lookup(&db.conn()?, provider, query_hash, ttl)? {
+        return Ok(hit);
+    }
+
+    // Offline, cached results are all there is
+    connectivity::ensure_online()?;
+    let response = fetch.await?;
+    // A cache failure shouldn't cost the user their results
+    if let Err(e) = search_cache::store(&db.conn()?, provider, query_hash, &response, ttl) {
+        warn!("Failed to cache {} results: {}", provider.id(), e);
+    }
+    Ok(response)
+}
+
+/// Search with `provider`, looking up whatever keys or instance URL it needs.
+/// Google runs with safe search on. Responses are cached (see `cached`).
+pub async fn search(
+    db: &Database,
+    secrets_manager: &SecretsManager,
+    settings: &Settings,
+    provider: SearchProvider,
+    query: &str,
+    num_results: u32,
+) -> Result<SearchResponse, AppError> {
+    let query_hash = search_cache::query_hash(query, num_results, "");
+    cached(db, settings, provider, &query_hash, fetch(secrets_manager, settings, provider, query, num_results)).await
+}
+
+async fn fetch(
+    secrets_manager: &SecretsManager,
+    settings: &Settings,
+    provider: SearchProvider,
+    query: &str,
+    num_results: u32,
+) -> Result<SearchResponse, AppError> {
+    match provider {
+        SearchProvider::Brave => {
+            let api_key = search_api_key(secrets_manager, "brave_api_key").await?;
+            search_brave(&api_key, query, num_results).await
+        }
+        SearchProvider::Google => {
+            let api_key = search_api_key(secrets_manager, "google_api_key").await?;
+            let engine_id = search_api_key(secrets_manager, "google_search_engine_id").await?;
+            search_google(&api_key, &engine_id, query, num_results, true).await
+        }
+        SearchProvider::DuckDuckGo => search_duckduckgo(query, num_results).await,
+        SearchProvider::Searxng => {
+            let base_url = settings
+                .searxng_url
+                .as_deref()
+                .ok_or_else(|| AppError::validation("Set a SearxNG instance URL in settings first"))?;
+            search_searxng(base_url, query, num_results).await
+        }
+    }
+}
+
+/// Providers usable right now: those with their API keys stored or, for SearxNG,
+/// an instance URL set. DuckDuckGo is always available.
+pub async fn configured_providers(
+    secrets_manager: &SecretsManager,
+    settings: &Settings,
+) -> Result<Vec<SearchProvider>, AppError> {
+    let mut providers = Vec::new();
+    if secrets_manager.has_secret("brave_api_key").await? {
+        providers.push(SearchProvider::Brave);
+    }
+    if secrets_manager.has_secret("google_api_key").await? && secrets_manager.has_secret("google_search_engine_id").await? {
+        providers.push(SearchProvider::Google);
+    }
+    providers.push(SearchProvider::DuckDuckGo);
+    if settings.searxng_url.is_some() {
+        providers.push(SearchProvider::Searxng);
+    }
+    Ok(providers)
+}
+
+/// Reciprocal rank fusion constant; larger values flatten the advantage of top ranks
+const RRF_K: f64 = 60.0;
+
+/// A provider that failed during a multi-provider search
+#[derive(Debug, Serialize)]
+pub struct ProviderFailure {
+    pub provider: SearchProvider,
+    pub error: AppError,
+}
+
+/// Results from several providers merged into a single ranking
+#[derive(Debug, Serialize)]
+pub struct MergedSearchResponse {
+    pub results: Vec<SearchResult>,
+    /// Largest total any provider reported
+    pub total_results: u32,
+    /// Wall-clock time for the whole fan-out, in seconds
+    pub search_time: f64,
+    /// Providers whose results are included
+    pub providers: Vec<SearchProvider>,
+    pub failures: Vec<ProviderFailure>,
+}
+
+/// Query every provider in `providers` concurrently and merge the results. Pages
+/// found by several engines (compared by normalized URL) appear once, ranked by
+/// reciprocal rank fusion so agreement between engines pushes a result up. Fails
+/// only if every provider fails.
+pub async fn search_all(
+    db: &Database,
+    secrets_manager: &SecretsManager,
+    settings: &Settings,
+    providers: &[SearchProvider],
+    query: &str,
+    num_results: u32,
+) -> Result<MergedSearchResponse, AppError> {
+    if providers.is_empty() {
+        return Err(AppError::validation("Choose at least one search provider"));
+    }
+    let started = Instant::now();
+    let responses = join_all(providers.iter().map(|provider| async move {
+        (*provider, search(db, secrets_manager, settings, *provider, query, num_results).await)
+    }))
+    .await;
+
+    let mut answered = Vec::new();
+    let mut failures = Vec::new();
+    for (provider, response) in responses {
+        match response {
+            Ok(response) => answered.push((provider, response)),
+            Err(error) => failures.push(ProviderFailure { provider, error }),
+        }
+    }
+    if answered.is_empty() {
+        // Every provider failed; report the first reason
+        return Err(failures.remove(0).error);
+    }
+
+    let total_results = answered.iter().map(|(_, response)| response.total_results).max().unwrap_or(0);
+    let providers = answered.iter().map(|(provider, _)| *provider).collect();
+    let mut results = merge_rankings(answered.into_iter().map(|(_, response)| response.results).collect());
+    results.truncate(num_results as usize);
+
+    Ok(MergedSearchResponse {
+        results,
+        total_results,
+        search_time: started.elapsed().as_secs_f64(),
+        providers,
+        failures,
+    })
+}
+
+/// Combine ranked lists into one, deduplicated by normalized URL and ordered by
+/// the sum of `1 / (RRF_K + rank)` over the lists a result appears in
+fn merge_rankings(rankings: Vec<Vec<SearchResult>>) -> Vec<SearchResult> {
+    let mut merged: Vec<(f64, SearchResult)> = Vec::new();
+    let mut index_by_key: HashMap<String, usize> = HashMap::new();
+
+    // Visit rank by rank across lists so ties keep the engines interleaved
+    let depth = rankings.iter().map(Vec::len).max().unwrap_or(0);
+    let mut rankings: Vec<_> = rankings.into_iter().map(Vec::into_iter).collect();
+    for rank in 0..depth {
+        for ranking in rankings.iter_mut() {
+            let Some(result) = ranking.next() else {
+                continue;
+            };
+            let score = 1.0 / (RRF_K + rank as f64 + 1.0);
+            let key = normalize_url(&result.url).unwrap_or_else(|| result.url.clone());
+            match index_by_key.get(&key) {
+                Some(&index) => {
+                    let (total, existing) = &mut merged[index];
+                    *total += score;
+                    for source in result.sources {
+                        if !existing.sources.contains(&source) {
+                            existing.sources.push(source);
+                        }
+                    }
+                    if result.description.len() > existing.description.len() {
+                        existing.description = result.description;
+                    }
+                    if existing.snippet.is_none() {
+                        existing.snippet = result.snippet;
+                    }
+                }
+                None => {
+                    index_by_key.insert(key, merged.len());
+                    merged.push((score, result));
+                }
+            }
+        }
+    }
+
+    // Stable sort, so equal scores keep their interleaved order
+    merged.sort_by(|a, b| b.0.total_cmp(&a.0));
+    merged.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Fetch a search result's page through the readability extractor and turn it into
+/// an article clip. The search snippet stands in when the page has no excerpt.
+pub async fn article_clip(result: &SearchResult) -> Result<ClipData, AppError> {
+    let article = extract::fetch_article(&result.url).await?;
+    let title = if article.title.trim().is_empty() { result.title.clone() } else { article.title };
+    let description = article
+        .excerpt
+        .or_else(|| Some(result.description.clone()))
+        .filter(|description| !description.trim().is_empty());
+    Ok(ClipData {
+        r#type: "article".to_string(),
+        title,
+        url: Some(article.url),
+        content: Some(article.content).filter(|content| !content.trim().is_empty()),
+        image_url: article.lead_image,
+        description,
+        author: article.byline,
+        timestamp: now_millis() as u64,
+        original_url: None,
+        language: None,
+    })
+}
+
+/// A search result that couldn't be saved
+#[derive(Debug, Serialize)]
+pub struct ClipFailure {
+    pub url: String,
+    pub error: AppError,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClippedResults {
+    pub clips: Vec<ClipInsert>,
+    pub failures: Vec<ClipFailure>,
+}
+
+/// Save each result as an article clip, fetching the pages concurrently. One page
+/// failing to load doesn't stop the rest.
+pub async fn clip_results(app_handle: &AppHandle, results: &[SearchResult]) -> ClippedResults {
+    let fetched = join_all(results.iter().map(article_clip)).await;
+
+    let mut clipped = ClippedResults {
+        clips: Vec::new(),
+        failures: Vec::new(),
+    };
+    for (result, clip_data) in results.iter().zip(fetched) {
+        match clip_data.and_then(|clip_data| crate::ingest_clip(app_handle, clip_data)) {
+            Ok(inserted) => clipped.clips.push(inserted),
+            Err(error) => clipped.failures.push(ClipFailure {
+                url: result.url.clone(),
+                error,
+            }),
+        }
+    }
+    clipped
+}
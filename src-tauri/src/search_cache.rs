@@ -0,0 +1,79 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+use crate::clips::now_millis;
+use crate::errors::AppError;
+use crate::search::{SearchProvider, SearchResponse};
+
+/// How long cached search responses are served before the provider is asked again
+pub const DEFAULT_SEARCH_CACHE_TTL_HOURS: u32 = 24;
+
+/// Cache key for a query. Case and spacing don't change results, so they don't
+/// change the key; `options` covers anything else that does (e.g. safe search).
+pub fn query_hash(query: &str, num_results: u32, options: &str) -> String {
+    let query = query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let key = format!("{}\n{}\n{}", query, num_results, options);
+    Sha256::digest(key.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The cached response for this query if it's younger than `ttl`, marked as a cache hit
+pub fn lookup(
+    conn: &Connection,
+    provider: SearchProvider,
+    query_hash: &str,
+    ttl: Duration,
+) -> Result<Option<SearchResponse>, AppError> {
+    let cached: Option<(String, i64)> = conn
+        .query_row(
+            "SELECT response, created_at FROM search_cache
+             WHERE provider = ?1 AND query_hash = ?2 AND created_at >= ?3",
+            params![provider.id(), query_hash, now_millis() - ttl.as_millis() as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read search cache: {}", e)))?;
+
+    let Some((json, created_at)) = cached else {
+        return Ok(None);
+    };
+    // An entry written by an older version may not parse; treat it as a miss
+    let Ok(mut response) = serde_json::from_str::<SearchResponse>(&json) else {
+        return Ok(None);
+    };
+    response.cache_hit = true;
+    response.cached_at = Some(created_at);
+    Ok(Some(response))
+}
+
+/// Cache a fresh response, dropping entries that have outlived `ttl`
+pub fn store(
+    conn: &Connection,
+    provider: SearchProvider,
+    query_hash: &str,
+    response: &SearchResponse,
+    ttl: Duration,
+) -> Result<(), AppError> {
+    let json = serde_json::to_string(response)
+        .map_err(|e| AppError::internal(format!("Failed to serialize search response: {}", e)))?;
+    let now = now_millis();
+    conn.execute(
+        "INSERT INTO search_cache (provider, query_hash, response, created_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (provider, query_hash) DO UPDATE SET
+            response = excluded.response, created_at = excluded.created_at",
+        params![provider.id(), query_hash, json, now],
+    )
+    .map_err(|e| AppError::database(format!("Failed to write search cache: {}", e)))?;
+    conn.execute(
+        "DELETE FROM search_cache WHERE created_at < ?1",
+        params![now - ttl.as_millis() as i64],
+    )
+    .map_err(|e| AppError::database(format!("Failed to prune search cache: {}", e)))?;
+    Ok(())
+}
+
+/// Remove every cached response; returns how many there were
+pub fn clear(conn: &Connection) -> Result<usize, AppError> {
+    conn.execute("DELETE FROM search_cache", [])
+        .map_err(|e| AppError::database(format!("Failed to clear search cache: {}", e)))
+}
@@ -0,0 +1,123 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::error;
+
+use crate::clips::now_millis;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::secrets::{SecretAccess, SecretsManager};
+
+/// Accesses older than this are pruned when new ones are written
+const RETENTION_MILLIS: i64 = 90 * 24 * 3_600_000;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often one command or feature read a secret
+#[derive(Debug, Serialize)]
+pub struct CallerCount {
+    pub caller: String,
+    pub count: u32,
+    pub last_accessed_at: i64,
+}
+
+/// Who read a secret, kept for `RETENTION_MILLIS`
+#[derive(Debug, Serialize)]
+pub struct SecretAudit {
+    pub name: String,
+    pub total: u32,
+    /// Busiest caller first
+    pub by_caller: Vec<CallerCount>,
+    /// Newest first
+    pub recent: Vec<SecretAccess>,
+}
+
+/// Write the accesses the secrets manager has recorded since the last flush and
+/// prune old ones, on the database writer. Returns the number written.
+pub async fn flush(db: &Database, secrets_manager: &SecretsManager) -> Result<usize, AppError> {
+    let accesses = secrets_manager.take_accesses();
+    if accesses.is_empty() {
+        return Ok(0);
+    }
+    db.write_async(move |conn| record(conn, &accesses)).await
+}
+
+fn record(conn: &mut Connection, accesses: &[SecretAccess]) -> Result<usize, AppError> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+    for access in accesses {
+        tx.execute(
+            "INSERT INTO secret_access_log (name, caller, accessed_at) VALUES (?1, ?2, ?3)",
+            params![access.name, access.caller, access.accessed_at],
+        )
+        .map_err(|e| AppError::database(format!("Failed to record secret access: {}", e)))?;
+    }
+    tx.execute(
+        "DELETE FROM secret_access_log WHERE accessed_at < ?1",
+        params![now_millis() - RETENTION_MILLIS],
+    )
+    .map_err(|e| AppError::database(format!("Failed to prune secret access log: {}", e)))?;
+    tx.commit()
+        .map_err(|e| AppError::database(format!("Failed to record secret accesses: {}", e)))?;
+    Ok(accesses.len())
+}
+
+/// Which commands and features read the secret `name`, with the latest `limit` reads
+pub fn get_secret_audit(conn: &Connection, name: &str, limit: u32) -> Result<SecretAudit, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT caller, COUNT(*), MAX(accessed_at) FROM secret_access_log WHERE name = ?1
+             GROUP BY caller ORDER BY COUNT(*) DESC, caller",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let by_caller = stmt
+        .query_map(params![name], |row| {
+            Ok(CallerCount {
+                caller: row.get(0)?,
+                count: row.get(1)?,
+                last_accessed_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to read secret access log: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read secret access log: {}", e)))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT caller, accessed_at FROM secret_access_log WHERE name = ?1
+             ORDER BY accessed_at DESC, id DESC LIMIT ?2",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let recent = stmt
+        .query_map(params![name, limit], |row| {
+            Ok(SecretAccess {
+                name: name.to_string(),
+                caller: row.get(0)?,
+                accessed_at: row.get(1)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to read secret access log: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read secret access log: {}", e)))?;
+
+    Ok(SecretAudit {
+        name: name.to_string(),
+        total: by_caller.iter().map(|caller| caller.count).sum(),
+        by_caller,
+        recent,
+    })
+}
+
+/// Write recorded secret accesses to the database every `FLUSH_INTERVAL`
+pub fn start_flush(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            let flushed = flush(&app_handle.state::<Database>(), &app_handle.state::<SecretsManager>()).await;
+            if let Err(e) = flushed {
+                error!("{}", e);
+            }
+        }
+    });
+}
@@ -1,7 +1,33 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tauri::State;
-use tokio::sync::Mutex;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{error, warn};
+
+use crate::errors::AppError;
+use crate::notifications::{self, NotificationKind};
+use crate::redact;
+
+/// Keyring service name used for all LOS entries
+const KEYRING_SERVICE: &str = "los-app";
+/// Keyring entry holding the AES-256 key for the on-disk secrets file
+const MASTER_KEY_ENTRY: &str = "secrets-master-key";
+/// Keyring entry listing secret names when the keychain backend is active
+const SECRETS_INDEX_ENTRY: &str = "secrets-index";
+const NONCE_LEN: usize = 12;
+/// Secrets expiring within this many days are reported by the expiry check
+const EXPIRY_WARNING_DAYS: u64 = 14;
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+/// Accesses held in memory until `secret_audit` writes them out; the oldest are
+/// dropped beyond this
+const MAX_PENDING_ACCESSES: usize = 10_000;
 
 /// Secure storage for API keys and sensitive data
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -9,232 +35,570 @@ pub struct SecretData {
     pub value: String,
     pub created_at: u64,
     pub last_accessed: Option<u64>,
+    /// When the key stops working, in seconds since the epoch, if the provider says
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    /// Provider or service the key belongs to, e.g. `openai`
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// The value replaced by the last `rotate_secret`, kept so the rotation can be undone
+    #[serde(default)]
+    pub previous_value: Option<String>,
+    #[serde(default)]
+    pub rotated_at: Option<u64>,
+}
+
+/// What the UI may know about a secret: everything but its values
+#[derive(Debug, Serialize, Clone)]
+pub struct SecretInfo {
+    pub name: String,
+    pub created_at: u64,
+    pub last_accessed: Option<u64>,
+    pub expires_at: Option<u64>,
+    pub provider: Option<String>,
+    pub rotated_at: Option<u64>,
+    /// Whether `rollback_secret` has a previous value to restore
+    pub can_roll_back: bool,
+}
+
+impl SecretInfo {
+    fn new(name: &str, data: &SecretData) -> Self {
+        SecretInfo {
+            name: name.to_string(),
+            created_at: data.created_at,
+            last_accessed: data.last_accessed,
+            expires_at: data.expires_at,
+            provider: data.provider.clone(),
+            rotated_at: data.rotated_at,
+            can_roll_back: data.previous_value.is_some(),
+        }
+    }
+}
+
+/// One read of a secret's value
+#[derive(Debug, Serialize, Clone)]
+pub struct SecretAccess {
+    pub name: String,
+    /// Command or feature that read it, e.g. `summarize`
+    pub caller: String,
+    /// Milliseconds since the epoch
+    pub accessed_at: i64,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Encrypted file backing the secrets manager.
+///
+/// The file holds a 12-byte AES-GCM nonce followed by the ciphertext of the
+/// JSON-serialized secrets map. The AES key itself lives in the OS keyring.
+struct EncryptedStore {
+    path: PathBuf,
+}
+
+impl EncryptedStore {
+    /// Fetch the master key from the OS keyring, generating it on first use
+    fn master_key(&self) -> Result<Key<Aes256Gcm>, AppError> {
+        let entry = keyring::Entry::new(KEYRING_SERVICE, MASTER_KEY_ENTRY)
+            .map_err(|e| AppError::internal(format!("Failed to open keyring entry: {}", e)))?;
+
+        match entry.get_password() {
+            Ok(encoded) => {
+                let bytes = BASE64
+                    .decode(encoded)
+                    .map_err(|e| AppError::internal(format!("Corrupt master key in keyring: {}", e)))?;
+                if bytes.len() != 32 {
+                    return Err(AppError::internal("Corrupt master key in keyring: wrong length"));
+                }
+                Ok(*Key::<Aes256Gcm>::from_slice(&bytes))
+            }
+            Err(keyring::Error::NoEntry) => {
+                let key = Aes256Gcm::generate_key(OsRng);
+                entry
+                    .set_password(&BASE64.encode(key))
+                    .map_err(|e| AppError::internal(format!("Failed to store master key in keyring: {}", e)))?;
+                Ok(key)
+            }
+            Err(e) => Err(AppError::internal(format!("Failed to read master key from keyring: {}", e))),
+        }
+    }
+
+    /// Read and decrypt the secrets file. A missing file is an empty store.
+    fn load(&self) -> Result<HashMap<String, SecretData>, AppError> {
+        if !self.path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let data = fs::read(&self.path).map_err(|e| AppError::internal(format!("Failed to read secrets file: {}", e)))?;
+        if data.len() < NONCE_LEN {
+            return Err(AppError::internal("Secrets file is truncated"));
+        }
+
+        let cipher = Aes256Gcm::new(&self.master_key()?);
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| AppError::auth("Failed to decrypt secrets file (wrong key or corrupted data)"))?;
+
+        serde_json::from_slice(&plaintext).map_err(|e| AppError::internal(format!("Failed to parse secrets file: {}", e)))
+    }
+
+    /// Encrypt and atomically replace the secrets file
+    fn save(&self, secrets: &HashMap<String, SecretData>) -> Result<(), AppError> {
+        let plaintext = serde_json::to_vec(secrets).map_err(|e| AppError::internal(format!("Failed to serialize secrets: {}", e)))?;
+
+        let cipher = Aes256Gcm::new(&self.master_key()?);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_ref())
+            .map_err(|_| AppError::internal("Failed to encrypt secrets"))?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::internal(format!("Failed to create secrets directory: {}", e)))?;
+        }
+
+        // Write to a sibling temp file and rename over the original so a crash
+        // mid-write never leaves a half-written secrets file behind
+        let tmp_path = self.path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp_path).map_err(|e| AppError::internal(format!("Failed to write secrets file: {}", e)))?;
+        file.write_all(&nonce)
+            .and_then(|_| file.write_all(&ciphertext))
+            .and_then(|_| file.sync_all())
+            .map_err(|e| AppError::internal(format!("Failed to write secrets file: {}", e)))?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| AppError::internal(format!("Failed to replace secrets file: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Keychain-backed storage: one keyring entry per secret plus an index entry,
+/// since keyrings cannot enumerate their own entries
+struct KeychainStore {
+    /// Prefix of this store's entry names, keeping a profile's secrets apart
+    scope: Option<String>,
+}
+
+impl KeychainStore {
+    fn entry(&self, user: &str) -> Result<keyring::Entry, AppError> {
+        let user = match &self.scope {
+            Some(scope) => format!("{}:{}", scope, user),
+            None => user.to_string(),
+        };
+        keyring::Entry::new(KEYRING_SERVICE, &user).map_err(|e| AppError::internal(format!("Failed to open keyring entry: {}", e)))
+    }
+
+    fn secret_entry(&self, name: &str) -> Result<keyring::Entry, AppError> {
+        self.entry(&format!("secret:{}", name))
+    }
+
+    fn read_index(&self) -> Result<Vec<String>, AppError> {
+        match self.entry(SECRETS_INDEX_ENTRY)?.get_password() {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| AppError::internal(format!("Corrupt secrets index in keyring: {}", e))),
+            Err(keyring::Error::NoEntry) => Ok(Vec::new()),
+            Err(e) => Err(AppError::internal(format!("Failed to read secrets index from keyring: {}", e))),
+        }
+    }
+
+    fn load(&self) -> Result<HashMap<String, SecretData>, AppError> {
+        let mut secrets = HashMap::new();
+        for name in self.read_index()? {
+            match self.secret_entry(&name)?.get_password() {
+                Ok(json) => {
+                    let data = serde_json::from_str(&json)
+                        .map_err(|e| AppError::internal(format!("Corrupt keyring entry for '{}': {}", name, e)))?;
+                    secrets.insert(name, data);
+                }
+                // Entry was removed outside the app; drop it from the index on next save
+                Err(keyring::Error::NoEntry) => {}
+                Err(e) => return Err(AppError::internal(format!("Failed to read '{}' from keyring: {}", name, e))),
+            }
+        }
+        Ok(secrets)
+    }
+
+    fn save(&self, secrets: &HashMap<String, SecretData>) -> Result<(), AppError> {
+        for (name, data) in secrets {
+            let json = serde_json::to_string(data).map_err(|e| AppError::internal(format!("Failed to serialize secret: {}", e)))?;
+            self.secret_entry(name)?
+                .set_password(&json)
+                .map_err(|e| AppError::internal(format!("Failed to write '{}' to keyring: {}", name, e)))?;
+        }
+
+        for stale in self.read_index()?.iter().filter(|name| !secrets.contains_key(*name)) {
+            match self.secret_entry(stale)?.delete_password() {
+                Ok(()) | Err(keyring::Error::NoEntry) => {}
+                Err(e) => return Err(AppError::internal(format!("Failed to remove '{}' from keyring: {}", stale, e))),
+            }
+        }
+
+        let mut names: Vec<&String> = secrets.keys().collect();
+        names.sort();
+        let index = serde_json::to_string(&names).map_err(|e| AppError::internal(format!("Failed to serialize secrets index: {}", e)))?;
+        self.entry(SECRETS_INDEX_ENTRY)?
+            .set_password(&index)
+            .map_err(|e| AppError::internal(format!("Failed to write secrets index to keyring: {}", e)))
+    }
+
+    fn clear(&self) -> Result<(), AppError> {
+        self.save(&HashMap::new())?;
+        match self.entry(SECRETS_INDEX_ENTRY)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(AppError::internal(format!("Failed to remove secrets index from keyring: {}", e))),
+        }
+    }
+}
+
+/// Where secret values are persisted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretsBackend {
+    /// AES-GCM encrypted file in the app data dir
+    #[default]
+    EncryptedFile,
+    /// GNOME Keyring / macOS Keychain / Windows Credential Manager
+    Keychain,
+}
+
+/// Where a set of secrets is kept, whichever backend is active
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecretsLocation {
+    /// The encrypted file
+    pub file_path: PathBuf,
+    /// Prefix of keychain entry names; None for the shared set
+    pub keychain_scope: Option<String>,
+}
+
+enum SecretStore {
+    EncryptedFile(EncryptedStore),
+    Keychain(KeychainStore),
+}
+
+impl SecretStore {
+    fn new(backend: SecretsBackend, location: &SecretsLocation) -> Self {
+        match backend {
+            SecretsBackend::EncryptedFile => SecretStore::EncryptedFile(EncryptedStore {
+                path: location.file_path.clone(),
+            }),
+            SecretsBackend::Keychain => SecretStore::Keychain(KeychainStore {
+                scope: location.keychain_scope.clone(),
+            }),
+        }
+    }
+
+    fn backend(&self) -> SecretsBackend {
+        match self {
+            SecretStore::EncryptedFile(_) => SecretsBackend::EncryptedFile,
+            SecretStore::Keychain(_) => SecretsBackend::Keychain,
+        }
+    }
+
+    fn load(&self) -> Result<HashMap<String, SecretData>, AppError> {
+        match self {
+            SecretStore::EncryptedFile(store) => store.load(),
+            SecretStore::Keychain(store) => store.load(),
+        }
+    }
+
+    fn save(&self, secrets: &HashMap<String, SecretData>) -> Result<(), AppError> {
+        match self {
+            SecretStore::EncryptedFile(store) => store.save(secrets),
+            SecretStore::Keychain(store) => store.save(secrets),
+        }
+    }
+
+    /// Remove everything this store has persisted
+    fn clear(&self) -> Result<(), AppError> {
+        match self {
+            SecretStore::EncryptedFile(store) => {
+                if store.path.exists() {
+                    fs::remove_file(&store.path).map_err(|e| AppError::internal(format!("Failed to remove secrets file: {}", e)))?;
+                }
+                Ok(())
+            }
+            SecretStore::Keychain(store) => store.clear(),
+        }
+    }
+}
+
+struct SecretsInner {
+    store: SecretStore,
+    /// Kept so we can migrate between backends
+    location: SecretsLocation,
+    /// Lazily loaded from `store` on first access
+    secrets: Option<HashMap<String, SecretData>>,
+}
+
+impl SecretsInner {
+    fn secrets_mut(&mut self) -> &mut HashMap<String, SecretData> {
+        self.secrets.get_or_insert_with(HashMap::new)
+    }
+
+    fn persist(&self) -> Result<(), AppError> {
+        match &self.secrets {
+            Some(secrets) => {
+                share_with_redactor(secrets);
+                self.store.save(secrets)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Let `redact` scrub current and previous secret values from errors, logs and events
+fn share_with_redactor(secrets: &HashMap<String, SecretData>) {
+    redact::set_known_secrets(
+        secrets
+            .values()
+            .flat_map(|data| std::iter::once(data.value.as_str()).chain(data.previous_value.as_deref())),
+    );
 }
 
 /// Secure secrets manager
 pub struct SecretsManager {
-    secrets: Mutex<HashMap<String, SecretData>>,
+    inner: Mutex<SecretsInner>,
+    /// Reads not yet written to the audit log. Kept apart from `inner` so recording
+    /// never waits on the secrets lock.
+    accesses: std::sync::Mutex<Vec<SecretAccess>>,
 }
 
 impl SecretsManager {
-    pub fn new() -> Self {
+    pub fn new(location: SecretsLocation, backend: SecretsBackend) -> Self {
         Self {
-            secrets: Mutex::new(HashMap::new()),
+            inner: Mutex::new(SecretsInner {
+                store: SecretStore::new(backend, &location),
+                location,
+                secrets: None,
+            }),
+            accesses: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Use the secrets at `location` from now on, e.g. another profile's. They're
+    /// loaded on next access. Returns the location used until now.
+    pub async fn switch_location(&self, location: SecretsLocation, backend: SecretsBackend) -> SecretsLocation {
+        let mut inner = self.inner.lock().await;
+        inner.store = SecretStore::new(backend, &location);
+        inner.secrets = None;
+        std::mem::replace(&mut inner.location, location)
+    }
+
+    /// Lock the manager, loading secrets from the backend on first use
+    async fn lock_loaded(&self) -> Result<MutexGuard<'_, SecretsInner>, AppError> {
+        let mut inner = self.inner.lock().await;
+        if inner.secrets.is_none() {
+            let loaded = inner.store.load()?;
+            share_with_redactor(&loaded);
+            inner.secrets = Some(loaded);
         }
+        Ok(inner)
     }
 
-    /// Store a secret securely
-    pub async fn store_secret(&self, name: String, value: String) -> Result<(), String> {
-        let mut secrets = self.secrets.lock().await;
+    /// Backend secrets are currently persisted to
+    pub async fn backend(&self) -> SecretsBackend {
+        self.inner.lock().await.store.backend()
+    }
+
+    /// Move all secrets into `target` and remove them from the current backend.
+    /// Returns the number of secrets moved.
+    pub async fn migrate(&self, target: SecretsBackend) -> Result<usize, AppError> {
+        let mut inner = self.lock_loaded().await?;
+        if inner.store.backend() == target {
+            return Ok(0);
+        }
+
+        let new_store = SecretStore::new(target, &inner.location);
+        let count = inner.secrets_mut().len();
+        new_store.save(inner.secrets_mut())?;
+
+        // Only clear the old backend once the new one holds a full copy
+        let old_store = std::mem::replace(&mut inner.store, new_store);
+        old_store.clear()?;
+
+        Ok(count)
+    }
+
+    /// Store a secret securely. Replacing a secret keeps its provider but drops its
+    /// expiry and any value kept for rollback.
+    pub async fn store_secret(&self, name: String, value: String) -> Result<(), AppError> {
+        let mut inner = self.lock_loaded().await?;
+        let provider = inner.secrets_mut().get(&name).and_then(|existing| existing.provider.clone());
         let secret_data = SecretData {
             value,
-            created_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            created_at: now_secs(),
             last_accessed: None,
+            expires_at: None,
+            provider,
+            previous_value: None,
+            rotated_at: None,
         };
-        secrets.insert(name, secret_data);
-        Ok(())
+        inner.secrets_mut().insert(name, secret_data);
+        inner.persist()
+    }
+
+    /// Replace a secret with a new value, keeping the old one for `rollback_secret`
+    pub async fn rotate_secret(&self, name: &str, new_value: String, expires_at: Option<u64>) -> Result<SecretInfo, AppError> {
+        let mut inner = self.lock_loaded().await?;
+        let secret_data = inner
+            .secrets_mut()
+            .get_mut(name)
+            .ok_or_else(|| AppError::not_found(format!("Secret '{}' not found", name)))?;
+        if secret_data.value == new_value {
+            return Err(AppError::validation("The new value is the same as the current one"));
+        }
+        secret_data.previous_value = Some(std::mem::replace(&mut secret_data.value, new_value));
+        secret_data.rotated_at = Some(now_secs());
+        secret_data.expires_at = expires_at;
+        let info = SecretInfo::new(name, secret_data);
+        inner.persist()?;
+        Ok(info)
+    }
+
+    /// Go back to the value a secret had before its last rotation. The rotated-out
+    /// value is kept in turn, so rolling back twice restores the rotation.
+    pub async fn rollback_secret(&self, name: &str) -> Result<SecretInfo, AppError> {
+        let mut inner = self.lock_loaded().await?;
+        let secret_data = inner
+            .secrets_mut()
+            .get_mut(name)
+            .ok_or_else(|| AppError::not_found(format!("Secret '{}' not found", name)))?;
+        let previous = secret_data
+            .previous_value
+            .take()
+            .ok_or_else(|| AppError::validation(format!("Secret '{}' has no previous value", name)))?;
+        secret_data.previous_value = Some(std::mem::replace(&mut secret_data.value, previous));
+        secret_data.rotated_at = Some(now_secs());
+        // The old key's expiry isn't known any more
+        secret_data.expires_at = None;
+        let info = SecretInfo::new(name, secret_data);
+        inner.persist()?;
+        Ok(info)
+    }
+
+    /// Record when a secret expires and which provider it belongs to
+    pub async fn set_secret_metadata(
+        &self,
+        name: &str,
+        expires_at: Option<u64>,
+        provider: Option<String>,
+    ) -> Result<SecretInfo, AppError> {
+        let mut inner = self.lock_loaded().await?;
+        let secret_data = inner
+            .secrets_mut()
+            .get_mut(name)
+            .ok_or_else(|| AppError::not_found(format!("Secret '{}' not found", name)))?;
+        secret_data.expires_at = expires_at;
+        secret_data.provider = provider.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+        let info = SecretInfo::new(name, secret_data);
+        inner.persist()?;
+        Ok(info)
     }
 
-    /// Retrieve a secret securely
-    pub async fn get_secret(&self, name: &str) -> Result<String, String> {
-        let mut secrets = self.secrets.lock().await;
-        if let Some(secret_data) = secrets.get_mut(name) {
-            secret_data.last_accessed = Some(
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-            );
-            Ok(secret_data.value.clone())
+    /// Names, dates and providers of every secret, sorted by name
+    pub async fn list_secret_info(&self) -> Result<Vec<SecretInfo>, AppError> {
+        let mut inner = self.lock_loaded().await?;
+        let mut infos: Vec<SecretInfo> = inner.secrets_mut().iter().map(|(name, data)| SecretInfo::new(name, data)).collect();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(infos)
+    }
+
+    /// Secrets that expire before `before` (seconds since the epoch), soonest first.
+    /// Already expired ones are included.
+    pub async fn expiring_secrets(&self, before: u64) -> Result<Vec<SecretInfo>, AppError> {
+        let mut infos: Vec<SecretInfo> = self
+            .list_secret_info()
+            .await?
+            .into_iter()
+            .filter(|info| info.expires_at.is_some_and(|at| at < before))
+            .collect();
+        infos.sort_by_key(|info| info.expires_at);
+        Ok(infos)
+    }
+
+    /// Retrieve a secret securely. `caller` names the command or feature reading it
+    /// for the access audit.
+    pub async fn get_secret(&self, name: &str, caller: &str) -> Result<String, AppError> {
+        let mut inner = self.lock_loaded().await?;
+        if let Some(secret_data) = inner.secrets_mut().get_mut(name) {
+            // Access time is kept in memory only and persisted with the next write
+            secret_data.last_accessed = Some(now_secs());
+            let value = secret_data.value.clone();
+            drop(inner);
+            self.record_access(name, caller);
+            Ok(value)
         } else {
-            Err(format!("Secret '{}' not found", name))
+            Err(AppError::not_found(format!("Secret '{}' not found", name)))
+        }
+    }
+
+    fn record_access(&self, name: &str, caller: &str) {
+        let Ok(mut accesses) = self.accesses.lock() else {
+            return;
+        };
+        if accesses.len() >= MAX_PENDING_ACCESSES {
+            accesses.remove(0);
         }
+        accesses.push(SecretAccess {
+            name: name.to_string(),
+            caller: caller.to_string(),
+            accessed_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64,
+        });
+    }
+
+    /// Reads recorded since the last call, oldest first
+    pub fn take_accesses(&self) -> Vec<SecretAccess> {
+        self.accesses.lock().map(|mut accesses| std::mem::take(&mut *accesses)).unwrap_or_default()
     }
 
     /// Check if a secret exists
-    pub async fn has_secret(&self, name: &str) -> bool {
-        let secrets = self.secrets.lock().await;
-        secrets.contains_key(name)
+    pub async fn has_secret(&self, name: &str) -> Result<bool, AppError> {
+        let mut inner = self.lock_loaded().await?;
+        Ok(inner.secrets_mut().contains_key(name))
     }
 
     /// List all secret names (without values)
-    pub async fn list_secrets(&self) -> Vec<String> {
-        let secrets = self.secrets.lock().await;
-        secrets.keys().cloned().collect()
+    pub async fn list_secrets(&self) -> Result<Vec<String>, AppError> {
+        let mut inner = self.lock_loaded().await?;
+        Ok(inner.secrets_mut().keys().cloned().collect())
     }
 
     /// Remove a secret
-    pub async fn remove_secret(&self, name: &str) -> Result<(), String> {
-        let mut secrets = self.secrets.lock().await;
-        if secrets.remove(name).is_some() {
-            Ok(())
+    pub async fn remove_secret(&self, name: &str) -> Result<(), AppError> {
+        let mut inner = self.lock_loaded().await?;
+        if inner.secrets_mut().remove(name).is_some() {
+            inner.persist()
         } else {
-            Err(format!("Secret '{}' not found", name))
-        }
-    }
-}
-
-/// LLM API request structure
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LlmRequest {
-    pub model: String,
-    pub messages: Vec<LlmMessage>,
-    pub max_tokens: Option<u32>,
-    pub temperature: Option<f32>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LlmMessage {
-    pub role: String,
-    pub content: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LlmResponse {
-    pub content: String,
-    pub usage: Option<LlmUsage>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LlmUsage {
-    pub input_tokens: u32,
-    pub output_tokens: u32,
-    pub total_tokens: u32,
-}
-
-/// Call LLM API securely from backend
-pub async fn call_llm_api(
-    secrets_manager: &SecretsManager,
-    model: String,
-    messages: Vec<LlmMessage>,
-    max_tokens: Option<u32>,
-    temperature: Option<f32>,
-) -> Result<LlmResponse, String> {
-    // Determine which API key to use based on model
-    let api_key_name = if model.contains("claude") || model.contains("anthropic") {
-        "anthropic_api_key"
-    } else if model.contains("gpt") || model.contains("openai") {
-        "openai_api_key"
-    } else {
-        return Err("Unsupported model type".to_string());
-    };
-
-    // Get API key securely
-    let api_key = secrets_manager.get_secret(api_key_name).await?;
-
-    // Prepare request
-    let request = LlmRequest {
-        model: model.clone(),
-        messages,
-        max_tokens,
-        temperature,
-    };
-
-    // Make API call based on model type
-    if model.contains("claude") || model.contains("anthropic") {
-        call_anthropic_api(&api_key, request).await
-    } else if model.contains("gpt") || model.contains("openai") {
-        call_openai_api(&api_key, request).await
-    } else {
-        Err("Unsupported model type".to_string())
-    }
-}
-
-/// Call Anthropic Claude API
-async fn call_anthropic_api(api_key: &str, request: LlmRequest) -> Result<LlmResponse, String> {
-    let client = reqwest::Client::new();
-    
-    let anthropic_request = serde_json::json!({
-        "model": request.model,
-        "max_tokens": request.max_tokens.unwrap_or(1000),
-        "messages": request.messages
-    });
+            Err(AppError::not_found(format!("Secret '{}' not found", name)))
+        }
+    }
+}
 
-    let response = client
-        .post("https://api.anthropic.com/v1/messages")
-        .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header("Content-Type", "application/json")
-        .json(&anthropic_request)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("API error: {}", error_text));
-    }
-
-    let response_json: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    let content = response_json["content"][0]["text"]
-        .as_str()
-        .ok_or("No content in response")?
-        .to_string();
-
-    let usage = if let Some(usage_obj) = response_json.get("usage") {
-        Some(LlmUsage {
-            input_tokens: usage_obj["input_tokens"].as_u64().unwrap_or(0) as u32,
-            output_tokens: usage_obj["output_tokens"].as_u64().unwrap_or(0) as u32,
-            total_tokens: usage_obj["total_tokens"].as_u64().unwrap_or(0) as u32,
-        })
-    } else {
-        None
-    };
-
-    Ok(LlmResponse { content, usage })
-}
-
-/// Call OpenAI API
-async fn call_openai_api(api_key: &str, request: LlmRequest) -> Result<LlmResponse, String> {
-    let client = reqwest::Client::new();
-    
-    let openai_request = serde_json::json!({
-        "model": request.model,
-        "messages": request.messages,
-        "max_tokens": request.max_tokens,
-        "temperature": request.temperature
+/// Look for secrets that expire within `EXPIRY_WARNING_DAYS` every few hours; emits
+/// `secrets-expiring` with them and reminds the user with a notification
+pub fn start_expiry_check(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let before = now_secs() + EXPIRY_WARNING_DAYS * 86_400;
+            match app_handle.state::<SecretsManager>().expiring_secrets(before).await {
+                Ok(expiring) if !expiring.is_empty() => {
+                    if let Err(e) = app_handle.emit("secrets-expiring", &expiring) {
+                        warn!("Failed to emit secrets event: {}", e);
+                    }
+                    let now = now_secs();
+                    let lines: Vec<String> = expiring
+                        .iter()
+                        .map(|info| match info.expires_at {
+                            Some(at) if at <= now => format!("{} has expired", info.name),
+                            Some(at) => format!("{} expires in {} days", info.name, (at - now).div_ceil(86_400)),
+                            None => info.name.clone(),
+                        })
+                        .collect();
+                    notifications::notify(&app_handle, NotificationKind::SecretExpiry, "Rotate your API keys", &lines.join("\n"));
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed to check secret expiry: {}", e),
+            }
+            tokio::time::sleep(EXPIRY_CHECK_INTERVAL).await;
+        }
     });
-
-    let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&openai_request)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("API error: {}", error_text));
-    }
-
-    let response_json: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    let content = response_json["choices"][0]["message"]["content"]
-        .as_str()
-        .ok_or("No content in response")?
-        .to_string();
-
-    let usage = if let Some(usage_obj) = response_json.get("usage") {
-        Some(LlmUsage {
-            input_tokens: usage_obj["prompt_tokens"].as_u64().unwrap_or(0) as u32,
-            output_tokens: usage_obj["completion_tokens"].as_u64().unwrap_or(0) as u32,
-            total_tokens: usage_obj["total_tokens"].as_u64().unwrap_or(0) as u32,
-        })
-    } else {
-        None
-    };
-
-    Ok(LlmResponse { content, usage })
 }
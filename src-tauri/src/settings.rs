@@ -0,0 +1,392 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri_plugin_global_shortcut::Shortcut;
+
+use crate::audio::TranscriptionEngine;
+use crate::backup::DEFAULT_BACKUPS_TO_KEEP;
+use crate::clipboard::ClipboardMonitorSettings;
+use crate::clips::DuplicatePolicy;
+use crate::email::EmailInbox;
+use crate::errors::AppError;
+use crate::feeds::DEFAULT_FEED_REFRESH_MINUTES;
+use crate::hotkey::DEFAULT_CAPTURE_SHORTCUT;
+use crate::http::NetworkSettings;
+use crate::inflight;
+use crate::links::DEFAULT_LINK_CHECK_INTERVAL_DAYS;
+use crate::maintenance::DEFAULT_MAINTENANCE_INTERVAL_HOURS;
+use crate::ocr::DEFAULT_OCR_LANGUAGES;
+use crate::providers::ollama::DEFAULT_OLLAMA_URL;
+use crate::providers::{CustomProviderConfig, ModelSelection};
+use crate::search::SearchProvider;
+use crate::search_cache::DEFAULT_SEARCH_CACHE_TTL_HOURS;
+use crate::secrets::SecretsBackend;
+use crate::smtp::{self, SmtpAccount};
+use crate::speech::SpeechEngine;
+use crate::sync::{LibsqlRemote, SyncBackend, DEFAULT_LIBSQL_PUSH_INTERVAL_MINUTES};
+use crate::trash::DEFAULT_TRASH_RETENTION_DAYS;
+use crate::usage::ModelPrice;
+use crate::watcher::DEFAULT_DEBOUNCE;
+
+/// Sampling temperature for summaries, tagging and other background prompts
+pub const DEFAULT_TEMPERATURE: f32 = 0.2;
+/// Provider ids registered by `ProviderRegistry` itself
+const BUILTIN_PROVIDERS: &[&str] = &["anthropic", "openai", "openrouter", "gemini", "ollama"];
+
+/// User-configurable application settings
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub secrets_backend: SecretsBackend,
+    /// Overrides the platform app data dir (or a profile's folder in it) for the database, clips and secrets
+    pub data_dir: Option<PathBuf>,
+    /// Base URL of the local Ollama server; defaults to `DEFAULT_OLLAMA_URL`
+    pub ollama_url: Option<String>,
+    /// Extra OpenAI-compatible endpoints offered alongside the built-in LLM providers
+    pub custom_providers: Vec<CustomProviderConfig>,
+    /// Per-model price overrides keyed by model-name prefix, used for cost tracking
+    pub model_prices: HashMap<String, ModelPrice>,
+    /// Model used for summaries and other background LLM work
+    pub default_model: Option<ModelSelection>,
+    /// Embeddings model for semantic search (OpenAI-compatible or Ollama)
+    pub embedding_model: Option<ModelSelection>,
+    /// Queue new clips for LLM tag and category suggestions
+    pub auto_tag: bool,
+    /// Queue new image clips for OCR; only takes effect in builds with the `ocr` feature
+    pub auto_ocr: bool,
+    /// Tesseract language codes joined by `+`, e.g. `eng+deu`; defaults to `DEFAULT_OCR_LANGUAGES`
+    pub ocr_languages: Option<String>,
+    /// What to do with a clip of a page or text that's already saved
+    pub duplicate_policy: DuplicatePolicy,
+    /// Back up clips.db this often; None turns scheduled backups off
+    pub backup_interval_hours: Option<u32>,
+    /// Number of backups kept; defaults to `DEFAULT_BACKUPS_TO_KEEP`
+    pub backups_to_keep: Option<usize>,
+    /// clips.db is SQLCipher-encrypted with the key in the secrets store
+    pub encrypt_database: bool,
+    /// Lock the app after this many minutes without activity; None or 0 turns the lock off.
+    /// Set with `set_app_lock`, which also stores the passphrase.
+    pub app_lock_idle_minutes: Option<u32>,
+    /// Global shortcut that saves the clipboard as a note; defaults to `DEFAULT_CAPTURE_SHORTCUT`
+    pub capture_shortcut: Option<String>,
+    /// Watch the clipboard for URLs and text worth clipping
+    pub clipboard_monitor: ClipboardMonitorSettings,
+    /// Turn off desktop notifications
+    pub disable_notifications: bool,
+    /// Sampling temperature for background LLM work; defaults to `DEFAULT_TEMPERATURE`
+    pub temperature: Option<f32>,
+    /// How long a drop-folder file must be quiet before it's read; defaults to `DEFAULT_DEBOUNCE`
+    pub watcher_debounce_ms: Option<u64>,
+    /// Web search backend used when a search doesn't name one
+    pub search_provider: SearchProvider,
+    /// Base URL of the SearxNG instance for `SearchProvider::Searxng`
+    pub searxng_url: Option<String>,
+    /// How long web search results are cached; defaults to `DEFAULT_SEARCH_CACHE_TTL_HOURS`, 0 turns caching off
+    pub search_cache_ttl_hours: Option<u32>,
+    /// How often feed subscriptions are polled; defaults to `DEFAULT_FEED_REFRESH_MINUTES`
+    pub feed_refresh_minutes: Option<u32>,
+    /// Days deleted clips stay in the trash; defaults to `DEFAULT_TRASH_RETENTION_DAYS`, 0 keeps them until emptied
+    pub trash_retention_days: Option<u32>,
+    /// Where clips are synced with other devices; None turns sync off. The changelog is
+    /// encrypted with the `SYNC_PASSPHRASE_SECRET` secret.
+    pub sync_backend: Option<SyncBackend>,
+    /// Sync in the background this often; None syncs only on demand
+    pub sync_interval_minutes: Option<u32>,
+    /// Hosted libsql/Turso database that gets a copy of every clip; None turns it off.
+    /// The auth token is the `LIBSQL_AUTH_TOKEN_SECRET` secret.
+    pub libsql_remote: Option<LibsqlRemote>,
+    /// Push to `libsql_remote` this often; defaults to `DEFAULT_LIBSQL_PUSH_INTERVAL_MINUTES`, 0 pushes only on demand
+    pub libsql_push_interval_minutes: Option<u32>,
+    /// Keep a Markdown note per clip in this folder, e.g. inside an Obsidian vault; None turns the mirror off
+    pub obsidian_vault_dir: Option<PathBuf>,
+    /// Speech-to-text backend for audio clips
+    pub transcription_engine: TranscriptionEngine,
+    /// Text-to-speech backend for reading clips aloud
+    pub speech_engine: SpeechEngine,
+    /// Re-check saved links this many days apart; defaults to `DEFAULT_LINK_CHECK_INTERVAL_DAYS`, 0 turns checks off
+    pub link_check_interval_days: Option<u32>,
+    /// Re-embed changed clips and optimize the database this many hours apart; defaults to
+    /// `DEFAULT_MAINTENANCE_INTERVAL_HOURS`, 0 turns scheduled maintenance off
+    pub maintenance_interval_hours: Option<u32>,
+    /// Archive pages that pass a link check and have no snapshot yet
+    pub archive_checked_links: bool,
+    /// Ask the Wayback Machine to capture each newly clipped page
+    pub save_to_wayback: bool,
+    /// Write a digest of the day's clips once a day from this local hour (0-23); None turns it off
+    pub digest_hour: Option<u8>,
+    /// Show a desktop notification when the digest is ready
+    pub digest_notification: bool,
+    /// Mailbox folder whose unread messages become clips; None turns email clipping off
+    pub email_inbox: Option<EmailInbox>,
+    /// Account outgoing mail such as Send to Kindle goes through
+    pub smtp_account: Option<SmtpAccount>,
+    /// Send to Kindle address that `export_epub` mails books to
+    pub kindle_email: Option<String>,
+    /// Proxy, extra CA certificates and timeouts for every outgoing HTTP request
+    pub network: NetworkSettings,
+    /// Seconds a network command may run, keyed by command name (e.g. `call_llm`);
+    /// commands not listed use `inflight::default_timeout`
+    pub command_timeouts: HashMap<String, u64>,
+    /// Work offline: network features fail fast and LLM calls go to `offline_model`
+    pub offline_mode: bool,
+    /// Ollama model used for completions while offline; None fails them instead
+    pub offline_model: Option<String>,
+}
+
+impl Settings {
+    pub fn ollama_url(&self) -> &str {
+        self.ollama_url.as_deref().unwrap_or(DEFAULT_OLLAMA_URL)
+    }
+
+    pub fn capture_shortcut(&self) -> &str {
+        self.capture_shortcut.as_deref().unwrap_or(DEFAULT_CAPTURE_SHORTCUT)
+    }
+
+    pub fn backups_to_keep(&self) -> usize {
+        self.backups_to_keep.unwrap_or(DEFAULT_BACKUPS_TO_KEEP)
+    }
+
+    pub fn temperature(&self) -> f32 {
+        self.temperature.unwrap_or(DEFAULT_TEMPERATURE)
+    }
+
+    pub fn watcher_debounce(&self) -> Duration {
+        self.watcher_debounce_ms.map(Duration::from_millis).unwrap_or(DEFAULT_DEBOUNCE)
+    }
+
+    pub fn ocr_languages(&self) -> &str {
+        self.ocr_languages.as_deref().unwrap_or(DEFAULT_OCR_LANGUAGES)
+    }
+
+    pub fn search_cache_ttl(&self) -> Duration {
+        let hours = self.search_cache_ttl_hours.unwrap_or(DEFAULT_SEARCH_CACHE_TTL_HOURS);
+        Duration::from_secs(hours as u64 * 3600)
+    }
+
+    pub fn feed_refresh_interval(&self) -> Duration {
+        let minutes = self.feed_refresh_minutes.unwrap_or(DEFAULT_FEED_REFRESH_MINUTES);
+        Duration::from_secs(minutes as u64 * 60)
+    }
+
+    /// How long deleted clips are kept before being purged, or `None` to keep them
+    pub fn trash_retention(&self) -> Option<Duration> {
+        let days = self.trash_retention_days.unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+        (days > 0).then(|| Duration::from_secs(days as u64 * 86_400))
+    }
+
+    /// How often each link is re-checked, or `None` when checks are off
+    pub fn link_check_interval(&self) -> Option<Duration> {
+        let days = self.link_check_interval_days.unwrap_or(DEFAULT_LINK_CHECK_INTERVAL_DAYS);
+        (days > 0).then(|| Duration::from_secs(days as u64 * 86_400))
+    }
+
+    /// How long the app may sit idle before it locks, or `None` when the lock is off
+    pub fn app_lock_idle(&self) -> Option<Duration> {
+        let minutes = self.app_lock_idle_minutes.unwrap_or(0);
+        (minutes > 0).then(|| Duration::from_secs(minutes as u64 * 60))
+    }
+
+    /// How often maintenance runs, or `None` when it's off
+    pub fn maintenance_interval(&self) -> Option<Duration> {
+        let hours = self.maintenance_interval_hours.unwrap_or(DEFAULT_MAINTENANCE_INTERVAL_HOURS);
+        (hours > 0).then(|| Duration::from_secs(hours as u64 * 3600))
+    }
+
+    pub fn command_timeout(&self, command: &str) -> Duration {
+        match self.command_timeouts.get(command) {
+            Some(secs) => Duration::from_secs(*secs),
+            None => inflight::default_timeout(command),
+        }
+    }
+
+    pub fn sync_interval(&self) -> Option<Duration> {
+        self.sync_interval_minutes.map(|minutes| Duration::from_secs(minutes as u64 * 60))
+    }
+
+    /// How often to push to `libsql_remote`, or `None` when pushes are on demand only
+    pub fn libsql_push_interval(&self) -> Option<Duration> {
+        let minutes = self.libsql_push_interval_minutes.unwrap_or(DEFAULT_LIBSQL_PUSH_INTERVAL_MINUTES);
+        (minutes > 0).then(|| Duration::from_secs(minutes as u64 * 60))
+    }
+
+    /// Reject values the rest of the app can't work with
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let Some(url) = &self.ollama_url {
+            reqwest::Url::parse(url).map_err(|e| AppError::validation(format!("Invalid Ollama URL '{}': {}", url, e)))?;
+        }
+        if let Some(url) = &self.searxng_url {
+            reqwest::Url::parse(url).map_err(|e| AppError::validation(format!("Invalid SearxNG URL '{}': {}", url, e)))?;
+        }
+
+        let mut provider_ids = HashSet::new();
+        for provider in &self.custom_providers {
+            if provider.id.trim().is_empty() {
+                return Err(AppError::validation("Custom providers need an id"));
+            }
+            if BUILTIN_PROVIDERS.contains(&provider.id.as_str()) || !provider_ids.insert(provider.id.as_str()) {
+                return Err(AppError::validation(format!("Provider id '{}' is already in use", provider.id)));
+            }
+            reqwest::Url::parse(&provider.base_url)
+                .map_err(|e| AppError::validation(format!("Invalid base URL for provider '{}': {}", provider.id, e)))?;
+        }
+        for selection in [&self.default_model, &self.embedding_model].into_iter().flatten() {
+            if !BUILTIN_PROVIDERS.contains(&selection.provider.as_str()) && !provider_ids.contains(selection.provider.as_str()) {
+                return Err(AppError::validation(format!("Unknown LLM provider '{}'", selection.provider)));
+            }
+            if selection.model.trim().is_empty() {
+                return Err(AppError::validation(format!("Choose a model for provider '{}'", selection.provider)));
+            }
+        }
+
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(AppError::validation("Temperature must be between 0 and 2"));
+            }
+        }
+        if let Some(debounce_ms) = self.watcher_debounce_ms {
+            if !(10..=10_000).contains(&debounce_ms) {
+                return Err(AppError::validation("Watcher debounce must be between 10 and 10000 ms"));
+            }
+        }
+        if let Some(languages) = &self.ocr_languages {
+            let valid = languages
+                .split('+')
+                .all(|code| !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+            if !valid {
+                return Err(AppError::validation(format!("Invalid OCR languages '{}'; use codes like eng+deu", languages)));
+            }
+        }
+        if self.backup_interval_hours == Some(0) {
+            return Err(AppError::validation("Backup interval must be at least one hour"));
+        }
+        if self.feed_refresh_minutes.is_some_and(|minutes| minutes < 5) {
+            return Err(AppError::validation("Feeds can be refreshed at most every 5 minutes"));
+        }
+        if let Some(backend) = &self.sync_backend {
+            backend.validate()?;
+        }
+        if let Some(remote) = &self.libsql_remote {
+            remote.validate()?;
+        }
+        self.transcription_engine.validate()?;
+        self.speech_engine.validate()?;
+        if self.obsidian_vault_dir.as_ref().is_some_and(|dir| !dir.is_absolute()) {
+            return Err(AppError::validation("The Obsidian vault folder must be an absolute path"));
+        }
+        if let Some(inbox) = &self.email_inbox {
+            inbox.validate()?;
+        }
+        if let Some(account) = &self.smtp_account {
+            account.validate()?;
+        }
+        if let Some(address) = &self.kindle_email {
+            smtp::parse_mailbox(address)?;
+        }
+        self.network.validate()?;
+        if let Some(command) = self.command_timeouts.iter().find(|(_, secs)| **secs == 0).map(|(command, _)| command) {
+            return Err(AppError::validation(format!("The timeout for {} must be at least one second", command)));
+        }
+        if self.offline_model.as_ref().is_some_and(|model| model.trim().is_empty()) {
+            return Err(AppError::validation("Choose an Ollama model for offline mode"));
+        }
+        if self.digest_hour.is_some_and(|hour| hour > 23) {
+            return Err(AppError::validation("Digest hour must be between 0 and 23"));
+        }
+        if self.sync_interval_minutes == Some(0) {
+            return Err(AppError::validation("Sync interval must be at least one minute"));
+        }
+        if self.backups_to_keep == Some(0) {
+            return Err(AppError::validation("Keep at least one backup"));
+        }
+        if let Some(shortcut) = &self.capture_shortcut {
+            shortcut
+                .parse::<Shortcut>()
+                .map_err(|e| AppError::validation(format!("Invalid shortcut '{}': {}", shortcut, e)))?;
+        }
+        Ok(())
+    }
+}
+
+type SettingsListener = Box<dyn Fn(&Settings) + Send + Sync>;
+
+/// Settings persisted as JSON in the app config dir, one file per profile
+pub struct SettingsManager {
+    path: Mutex<PathBuf>,
+    settings: Mutex<Settings>,
+    listeners: Mutex<Vec<SettingsListener>>,
+}
+
+impl SettingsManager {
+    /// Load settings from `path`, falling back to defaults if the file is missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        Self {
+            settings: Mutex::new(Self::read(&path)),
+            path: Mutex::new(path),
+            listeners: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The settings stored at `path`, or defaults if the file is missing or unreadable
+    pub fn read(path: &Path) -> Settings {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Switch to the settings stored at `path`, e.g. another profile's, and notify
+    /// subscribers as for an update
+    pub fn reload(&self, path: PathBuf) -> Settings {
+        let settings = Self::read(&path);
+        {
+            let mut current = self.settings.lock().unwrap();
+            *self.path.lock().unwrap() = path;
+            *current = settings.clone();
+        }
+        for listener in self.listeners.lock().unwrap().iter() {
+            listener(&settings);
+        }
+        settings
+    }
+
+    /// Call `listener` with the new settings after every successful update
+    pub fn subscribe<F: Fn(&Settings) + Send + Sync + 'static>(&self, listener: F) {
+        self.listeners.lock().unwrap().push(Box::new(listener));
+    }
+
+    /// Snapshot of the current settings
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Apply a change, validate it and write the result to disk. An invalid change
+    /// leaves the current settings untouched.
+    pub fn update<F: FnOnce(&mut Settings)>(&self, change: F) -> Result<Settings, AppError> {
+        let updated = {
+            let mut settings = self.settings.lock().unwrap();
+            let mut updated = settings.clone();
+            change(&mut updated);
+            updated.validate()?;
+
+            let path = self.path.lock().unwrap();
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| AppError::internal(format!("Failed to create config directory: {}", e)))?;
+            }
+            let json =
+                serde_json::to_string_pretty(&updated).map_err(|e| AppError::internal(format!("Failed to serialize settings: {}", e)))?;
+            fs::write(&*path, json).map_err(|e| AppError::internal(format!("Failed to write settings: {}", e)))?;
+
+            *settings = updated.clone();
+            updated
+        };
+
+        // Outside the settings lock so listeners can read settings themselves
+        for listener in self.listeners.lock().unwrap().iter() {
+            listener(&updated);
+        }
+        Ok(updated)
+    }
+}
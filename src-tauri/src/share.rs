@@ -0,0 +1,417 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+use resvg::{tiny_skia, usvg};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::annotations;
+use crate::clips::{self, SqliteClip};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract::plain_text;
+
+/// Card width in PDF points; PNGs are rendered at `PNG_SCALE` pixels per point
+const CARD_WIDTH: f32 = 600.0;
+const MARGIN: f32 = 48.0;
+const PNG_SCALE: f32 = 2.0;
+/// Longer quotes are cut at a word boundary
+const MAX_QUOTE_CHARS: usize = 400;
+const MAX_TITLE_LINES: usize = 4;
+const BACKGROUND: (u8, u8, u8) = (250, 247, 240);
+const ACCENT: (u8, u8, u8) = (196, 120, 60);
+const INK: (u8, u8, u8) = (34, 34, 34);
+const MUTED: (u8, u8, u8) = (110, 110, 110);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderFormat {
+    Pdf,
+    Png,
+}
+
+/// Result of `render_clip`
+#[derive(Debug, Serialize)]
+pub struct RenderedClip {
+    pub clip_id: i64,
+    pub format: RenderFormat,
+    pub path: PathBuf,
+    /// In points for PDFs and pixels for PNGs
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Copy)]
+enum TextStyle {
+    Title,
+    Quote,
+    Source,
+}
+
+impl TextStyle {
+    fn size(self) -> f32 {
+        match self {
+            TextStyle::Title => 24.0,
+            TextStyle::Quote => 17.0,
+            TextStyle::Source => 11.0,
+        }
+    }
+
+    fn line_height(self) -> f32 {
+        self.size() * 1.35
+    }
+
+    /// Average glyph width as a share of the font size, for wrapping without font metrics
+    fn char_width(self) -> f32 {
+        match self {
+            TextStyle::Title => 0.55,
+            TextStyle::Quote => 0.47,
+            TextStyle::Source => 0.52,
+        }
+    }
+
+    fn color(self) -> (u8, u8, u8) {
+        match self {
+            TextStyle::Title | TextStyle::Quote => INK,
+            TextStyle::Source => MUTED,
+        }
+    }
+}
+
+/// A line of text placed on the card; `baseline` is measured down from the top
+struct PlacedLine {
+    text: String,
+    style: TextStyle,
+    x: f32,
+    baseline: f32,
+}
+
+/// Everything drawn on the card, in points
+struct CardLayout {
+    height: f32,
+    lines: Vec<PlacedLine>,
+    /// Top and bottom of the bar beside the quote
+    quote_bar: Option<(f32, f32)>,
+}
+
+/// Break `text` into lines of at most `max_chars` characters, at spaces where possible
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+        while word.chars().count() > max_chars {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let rest: String = word.chars().skip(max_chars).collect();
+            lines.push(word.chars().take(max_chars).collect());
+            word = rest;
+        }
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Cut `text` to `max_chars` at a word boundary, marking the cut with an ellipsis
+fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    format!("{}…", cut.trim_end_matches(|c: char| c.is_ascii_punctuation()))
+}
+
+/// The user's first highlight, or else the summary, description or opening of the text
+fn default_quote(app_handle: &AppHandle, clip: &SqliteClip) -> Result<Option<String>, AppError> {
+    let db = app_handle.state::<Database>();
+    let highlight = annotations::list_annotations(&db.conn()?, clip.id)?
+        .into_iter()
+        .find_map(|annotation| annotation.quote);
+    Ok(highlight
+        .or(clip.summary.clone())
+        .or(clip.description.clone())
+        .or(clip.content.as_deref().map(plain_text))
+        .filter(|quote| !quote.trim().is_empty()))
+}
+
+fn layout(title: &str, quote: Option<&str>, source: &[String]) -> CardLayout {
+    let inner = CARD_WIDTH - 2.0 * MARGIN;
+    let max_chars = |style: TextStyle, width: f32| (width / (style.size() * style.char_width())) as usize;
+    let mut lines = Vec::new();
+    let mut y = MARGIN;
+    let mut place = |text: String, style: TextStyle, x: f32, y: &mut f32| {
+        *y += style.line_height();
+        lines.push(PlacedLine {
+            text,
+            style,
+            x,
+            // The baseline sits about a quarter of the line height above the line's bottom
+            baseline: *y - style.line_height() * 0.28,
+        });
+    };
+
+    let mut title_lines = wrap(title, max_chars(TextStyle::Title, inner));
+    if title_lines.len() > MAX_TITLE_LINES {
+        title_lines.truncate(MAX_TITLE_LINES);
+        let last = title_lines.pop().unwrap_or_default();
+        title_lines.push(truncate(&last, last.chars().count().saturating_sub(1)));
+    }
+    for line in title_lines {
+        place(line, TextStyle::Title, MARGIN, &mut y);
+    }
+
+    let mut quote_bar = None;
+    if let Some(quote) = quote {
+        y += 20.0;
+        let top = y;
+        let indent = 18.0;
+        let text = format!("“{}”", truncate(quote, MAX_QUOTE_CHARS));
+        for line in wrap(&text, max_chars(TextStyle::Quote, inner - indent)) {
+            place(line, TextStyle::Quote, MARGIN + indent, &mut y);
+        }
+        quote_bar = Some((top + 4.0, y));
+    }
+
+    y += 24.0;
+    for part in source {
+        for line in wrap(part, max_chars(TextStyle::Source, inner)) {
+            place(line, TextStyle::Source, MARGIN, &mut y);
+        }
+    }
+
+    CardLayout {
+        height: (y + MARGIN).ceil(),
+        lines,
+        quote_bar,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn svg_color((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn card_svg(card: &CardLayout) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"{2}\"/>\n",
+        CARD_WIDTH,
+        card.height,
+        svg_color(BACKGROUND)
+    );
+    if let Some((top, bottom)) = card.quote_bar {
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"4\" height=\"{}\" fill=\"{}\"/>\n",
+            MARGIN,
+            top,
+            bottom - top,
+            svg_color(ACCENT)
+        ));
+    }
+    for line in &card.lines {
+        let (family, weight, style) = match line.style {
+            TextStyle::Title => ("Georgia, 'DejaVu Serif', serif", "bold", "normal"),
+            TextStyle::Quote => ("Georgia, 'DejaVu Serif', serif", "normal", "italic"),
+            TextStyle::Source => ("Helvetica, Arial, 'DejaVu Sans', sans-serif", "normal", "normal"),
+        };
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" font-weight=\"{}\" font-style=\"{}\" fill=\"{}\">{}</text>\n",
+            line.x,
+            line.baseline,
+            family,
+            line.style.size(),
+            weight,
+            style,
+            svg_color(line.style.color()),
+            escape(&line.text)
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn write_png(card: &CardLayout, dest: &Path) -> Result<(u32, u32), AppError> {
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_str(&card_svg(card), &options)
+        .map_err(|e| AppError::internal(format!("Failed to lay out clip card: {}", e)))?;
+    let width = (CARD_WIDTH * PNG_SCALE) as u32;
+    let height = (card.height * PNG_SCALE) as u32;
+    let mut pixmap =
+        tiny_skia::Pixmap::new(width, height).ok_or_else(|| AppError::internal("Clip card is too large to render"))?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(PNG_SCALE, PNG_SCALE), &mut pixmap.as_mut());
+    let png = pixmap
+        .encode_png()
+        .map_err(|e| AppError::internal(format!("Failed to encode clip card: {}", e)))?;
+    fs::write(dest, png).map_err(|e| AppError::internal(format!("Failed to write {}: {}", dest.display(), e)))?;
+    Ok((width, height))
+}
+
+/// Encode text for the standard PDF fonts, which use WinAnsi. Characters outside it
+/// become `?`.
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '…' => 0x85,
+            _ => b'?',
+        })
+        .collect()
+}
+
+fn fill_color((r, g, b): (u8, u8, u8)) -> Operation {
+    Operation::new(
+        "rg",
+        vec![(r as f32 / 255.0).into(), (g as f32 / 255.0).into(), (b as f32 / 255.0).into()],
+    )
+}
+
+fn write_pdf(card: &CardLayout, dest: &Path) -> Result<(u32, u32), AppError> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font = |doc: &mut Document, name: &str| {
+        doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => name,
+            "Encoding" => "WinAnsiEncoding",
+        })
+    };
+    let fonts = dictionary! {
+        "Title" => font(&mut doc, "Times-Bold"),
+        "Quote" => font(&mut doc, "Times-Italic"),
+        "Source" => font(&mut doc, "Helvetica"),
+    };
+    let resources_id = doc.add_object(dictionary! { "Font" => fonts });
+
+    // PDF coordinates run up from the bottom of the page
+    let height = card.height;
+    let mut operations = vec![
+        fill_color(BACKGROUND),
+        Operation::new("re", vec![0.into(), 0.into(), CARD_WIDTH.into(), height.into()]),
+        Operation::new("f", vec![]),
+    ];
+    if let Some((top, bottom)) = card.quote_bar {
+        operations.push(fill_color(ACCENT));
+        operations.push(Operation::new(
+            "re",
+            vec![MARGIN.into(), (height - bottom).into(), 4.into(), (bottom - top).into()],
+        ));
+        operations.push(Operation::new("f", vec![]));
+    }
+    for line in &card.lines {
+        let font = match line.style {
+            TextStyle::Title => "Title",
+            TextStyle::Quote => "Quote",
+            TextStyle::Source => "Source",
+        };
+        operations.extend([
+            fill_color(line.style.color()),
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![font.into(), line.style.size().into()]),
+            Operation::new("Td", vec![line.x.into(), (height - line.baseline).into()]),
+            Operation::new("Tj", vec![Object::string_literal(win_ansi(&line.text))]),
+            Operation::new("ET", vec![]),
+        ]);
+    }
+    let content = Content { operations }
+        .encode()
+        .map_err(|e| AppError::internal(format!("Failed to typeset clip card: {}", e)))?;
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Contents" => content_id,
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), CARD_WIDTH.into(), height.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.compress();
+    doc.save(dest)
+        .map_err(|e| AppError::internal(format!("Failed to write {}: {}", dest.display(), e)))?;
+    Ok((CARD_WIDTH as u32, height as u32))
+}
+
+/// Typeset a shareable card of the clip: its title, a quote and where it came from.
+/// The quote defaults to the first highlight, then the summary or description.
+pub async fn render_clip(
+    app_handle: &AppHandle,
+    clip_id: i64,
+    format: RenderFormat,
+    dest: PathBuf,
+    quote: Option<String>,
+) -> Result<RenderedClip, AppError> {
+    let clip = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?
+    };
+    let quote = match quote.filter(|quote| !quote.trim().is_empty()) {
+        Some(quote) => Some(quote),
+        None => default_quote(app_handle, &clip)?,
+    };
+    let byline: Vec<&str> = [clip.author.as_deref(), clip.site_name.as_deref().or(clip.domain.as_deref())]
+        .into_iter()
+        .flatten()
+        .collect();
+    let mut source = Vec::new();
+    if !byline.is_empty() {
+        source.push(byline.join(" — "));
+    }
+    if let Some(url) = &clip.url {
+        source.push(url.clone());
+    }
+    let card = layout(&clip.title, quote.as_deref(), &source);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let (width, height) = match format {
+            RenderFormat::Pdf => write_pdf(&card, &dest)?,
+            RenderFormat::Png => write_png(&card, &dest)?,
+        };
+        Ok(RenderedClip {
+            clip_id,
+            format,
+            path: dest,
+            width,
+            height,
+        })
+    })
+    .await
+    .map_err(|e| AppError::internal(format!("Rendering clip failed: {}", e)))?
+}
@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+use crate::clips::{self, SqliteClip};
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::extract;
+use crate::http;
+use crate::media;
+
+/// Icons are tiny; anything bigger is not what we asked for
+const MAX_FAVICON_BYTES: usize = 512 * 1024;
+
+/// Download a site's icon into `dir` as `<domain>.<ext>` and return the file name
+async fn download_favicon(url: &str, domain: &str, dir: &Path) -> Result<String, AppError> {
+    let url = reqwest::Url::parse(url).map_err(|e| AppError::validation(format!("Invalid favicon URL: {}", e)))?;
+    let response = http::client()
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to download favicon: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("Failed to download favicon: HTTP {}", response.status())));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    if content_type.as_deref().is_some_and(|mime| !mime.starts_with("image/")) {
+        return Err(AppError::validation("Favicon URL did not return an image"));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to download favicon: {}", e)))?;
+    if bytes.is_empty() || bytes.len() > MAX_FAVICON_BYTES {
+        return Err(AppError::validation("Favicon is empty or too large"));
+    }
+
+    let file_name = format!("{}.{}", domain, media::extension_for(content_type.as_deref(), &url));
+    let tmp = dir.join(format!(".{}.part", file_name));
+    fs::write(&tmp, &bytes).map_err(|e| AppError::internal(format!("Failed to write favicon: {}", e)))?;
+    fs::rename(&tmp, dir.join(&file_name)).map_err(|e| AppError::internal(format!("Failed to write favicon: {}", e)))?;
+    Ok(file_name)
+}
+
+/// Fetch the clip's page for its site name, publication date and preview image, and
+/// cache the site's favicon (once per domain). A missing icon doesn't fail the job.
+pub async fn fetch_site_metadata(app_handle: &AppHandle, clip_id: i64) -> Result<SqliteClip, AppError> {
+    let clip = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?
+    };
+    let (Some(url), Some(domain)) = (clip.url.clone(), clip.domain.clone()) else {
+        return Err(AppError::validation(format!("Clip {} has no web page", clip_id)));
+    };
+
+    let metadata = extract::fetch_page_metadata(&url).await?;
+
+    let dir = app_handle.state::<AppConfig>().favicons_dir();
+    let cached = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::cached_favicon(&conn, &domain)?.filter(|file_name| dir.join(file_name).is_file())
+    };
+    let favicon = match cached {
+        Some(file_name) => Some(file_name),
+        None => match download_favicon(&metadata.favicon, &domain, &dir).await {
+            Ok(file_name) => Some(file_name),
+            Err(e) => {
+                warn!("No favicon for {}: {}", domain, e);
+                None
+            }
+        },
+    };
+
+    let db = app_handle.state::<Database>();
+    let conn = db.conn()?;
+    clips::set_site_metadata(&conn, clip_id, &metadata, favicon.as_deref())?;
+    let clip = clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+    events::emit(app_handle, "clip-updated", &clip)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    Ok(clip)
+}
+
+/// Absolute path of the clip's cached favicon, if there is one
+pub fn get_clip_favicon(app_handle: &AppHandle, clip_id: i64) -> Result<Option<PathBuf>, AppError> {
+    let db = app_handle.state::<Database>();
+    let clip = clips::get_clip(&db.conn()?, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+    let dir = app_handle.state::<AppConfig>().favicons_dir();
+    Ok(clip.favicon_path.map(|file_name| dir.join(file_name)).filter(|path| path.is_file()))
+}
@@ -0,0 +1,133 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+
+use crate::clips::{self, now_millis, ClipFilter, READ_STATES};
+use crate::errors::AppError;
+
+const MAX_NAME_LEN: usize = 128;
+
+/// A saved search that behaves like a live folder: its clips are whatever matches
+/// `filter` right now. Pass the id as `smart_collection_id` to `query_clips`.
+#[derive(Debug, Serialize)]
+pub struct SmartCollection {
+    pub id: i64,
+    pub name: String,
+    pub filter: ClipFilter,
+    /// Clips currently matching; only filled in by `list_smart_collections`
+    pub clip_count: u32,
+    pub created_at: i64,
+}
+
+const SMART_COLLECTION_COLUMNS: &str = "id, name, filter, created_at";
+
+impl SmartCollection {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let filter: String = row.get(2)?;
+        Ok(SmartCollection {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            filter: serde_json::from_str(&filter).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+            })?,
+            clip_count: 0,
+            created_at: row.get(3)?,
+        })
+    }
+}
+
+fn normalize_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::validation("Smart collection name must not be empty"));
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::validation(format!(
+            "Smart collection name must be at most {} characters",
+            MAX_NAME_LEN
+        )));
+    }
+    Ok(name.to_string())
+}
+
+fn validate_filter(filter: &ClipFilter) -> Result<(), AppError> {
+    if let Some(text) = &filter.text {
+        if clips::fts_query(text).is_none() {
+            return Err(AppError::validation("Search terms must not be empty"));
+        }
+    }
+    for state in filter.read_states.iter().flatten() {
+        if !READ_STATES.contains(&state.as_str()) {
+            return Err(AppError::validation(format!(
+                "Invalid read state '{}'. Must be one of: {}",
+                state,
+                READ_STATES.join(", ")
+            )));
+        }
+    }
+    if let (Some(since), Some(until)) = (filter.since, filter.until) {
+        if since >= until {
+            return Err(AppError::validation("The date range must end after it starts"));
+        }
+    }
+    if filter.within_days == Some(0) {
+        return Err(AppError::validation("A relative date range must cover at least one day"));
+    }
+    Ok(())
+}
+
+pub fn get_smart_collection(conn: &Connection, id: i64) -> Result<SmartCollection, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM smart_collections WHERE id = ?1", SMART_COLLECTION_COLUMNS),
+        params![id],
+        SmartCollection::from_row,
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read smart collection: {}", e)))?
+    .ok_or_else(|| AppError::not_found(format!("Smart collection {} not found", id)))
+}
+
+pub fn create_smart_collection(conn: &Connection, name: &str, filter: ClipFilter) -> Result<SmartCollection, AppError> {
+    let name = normalize_name(name)?;
+    validate_filter(&filter)?;
+    let filter_json =
+        serde_json::to_string(&filter).map_err(|e| AppError::internal(format!("Failed to save filter: {}", e)))?;
+    conn.execute(
+        "INSERT INTO smart_collections (name, filter, created_at) VALUES (?1, ?2, ?3)",
+        params![name, filter_json, now_millis()],
+    )
+    .map_err(|e| AppError::database(format!("Failed to create smart collection: {}", e)))?;
+
+    let mut collection = get_smart_collection(conn, conn.last_insert_rowid())?;
+    collection.clip_count = clips::count_clips(conn, &collection.filter)?;
+    Ok(collection)
+}
+
+/// Every smart collection with its current clip count, alphabetically
+pub fn list_smart_collections(conn: &Connection) -> Result<Vec<SmartCollection>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM smart_collections ORDER BY name COLLATE NOCASE",
+            SMART_COLLECTION_COLUMNS
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let mut collections = stmt
+        .query_map([], SmartCollection::from_row)
+        .map_err(|e| AppError::database(format!("Failed to list smart collections: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read smart collection: {}", e)))?;
+    for collection in &mut collections {
+        collection.clip_count = clips::count_clips(conn, &collection.filter)?;
+    }
+    Ok(collections)
+}
+
+/// Delete a smart collection; its clips are untouched
+pub fn delete_smart_collection(conn: &Connection, id: i64) -> Result<(), AppError> {
+    let deleted = conn
+        .execute("DELETE FROM smart_collections WHERE id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to delete smart collection: {}", e)))?;
+    if deleted == 0 {
+        return Err(AppError::not_found(format!("Smart collection {} not found", id)));
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::errors::AppError;
+use crate::secrets::SecretsManager;
+
+/// Secret holding the SMTP password, or an app password for Gmail and similar
+pub const SMTP_PASSWORD_SECRET: &str = "smtp_password";
+/// Submission with STARTTLS; port 465 uses TLS from the start instead
+const DEFAULT_SMTP_PORT: u16 = 587;
+const IMPLICIT_TLS_PORT: u16 = 465;
+
+/// Account outgoing mail is sent through, set in `Settings::smtp_account`. The password
+/// is the `SMTP_PASSWORD_SECRET` secret.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmtpAccount {
+    pub host: String,
+    /// Defaults to `DEFAULT_SMTP_PORT`
+    pub port: Option<u16>,
+    pub username: String,
+    /// Sender address, e.g. the one approved in Amazon's Send to Kindle settings
+    pub from: String,
+}
+
+impl SmtpAccount {
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(DEFAULT_SMTP_PORT)
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.host.trim().is_empty() {
+            return Err(AppError::validation("Enter the SMTP server of the mail account"));
+        }
+        if self.username.trim().is_empty() {
+            return Err(AppError::validation("Enter the user name of the mail account"));
+        }
+        parse_mailbox(&self.from)?;
+        Ok(())
+    }
+}
+
+pub fn parse_mailbox(address: &str) -> Result<Mailbox, AppError> {
+    address
+        .trim()
+        .parse()
+        .map_err(|e| AppError::validation(format!("Invalid email address '{}': {}", address, e)))
+}
+
+/// A file to attach to an outgoing message
+pub struct MailAttachment {
+    pub file_name: String,
+    pub media_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Send a message with one attachment through `account`
+pub async fn send_with_attachment(
+    app_handle: &AppHandle,
+    account: &SmtpAccount,
+    to: &str,
+    subject: &str,
+    text: &str,
+    attachment: MailAttachment,
+) -> Result<(), AppError> {
+    let password = app_handle
+        .state::<SecretsManager>()
+        .get_secret(SMTP_PASSWORD_SECRET, "send_email")
+        .await
+        .map_err(|e| match e {
+            AppError::NotFound { .. } => AppError::auth(format!("No {} stored; add it in settings", SMTP_PASSWORD_SECRET)),
+            other => other,
+        })?;
+
+    let content_type = ContentType::parse(&attachment.media_type)
+        .map_err(|e| AppError::validation(format!("Invalid attachment type '{}': {}", attachment.media_type, e)))?;
+    let message = Message::builder()
+        .from(parse_mailbox(&account.from)?)
+        .to(parse_mailbox(to)?)
+        .subject(subject)
+        .multipart(
+            MultiPart::mixed()
+                .singlepart(SinglePart::plain(text.to_string()))
+                .singlepart(Attachment::new(attachment.file_name).body(attachment.bytes, content_type)),
+        )
+        .map_err(|e| AppError::internal(format!("Failed to build email: {}", e)))?;
+
+    let host = account.host.trim().to_string();
+    let builder = if account.port() == IMPLICIT_TLS_PORT {
+        SmtpTransport::relay(&host)
+    } else {
+        SmtpTransport::starttls_relay(&host)
+    }
+    .map_err(|e| AppError::network(format!("Failed to set up SMTP for {}: {}", host, e)))?;
+    let mailer = builder
+        .port(account.port())
+        .credentials(Credentials::new(account.username.trim().to_string(), password))
+        .build();
+
+    // lettre's SMTP transport is blocking
+    tauri::async_runtime::spawn_blocking(move || mailer.send(&message))
+        .await
+        .map_err(|e| AppError::internal(format!("Sending email failed: {}", e)))?
+        .map_err(|e| AppError::network(format!("Failed to send email via {}: {}", host, e)))?;
+    Ok(())
+}
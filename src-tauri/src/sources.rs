@@ -0,0 +1,146 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::clips::now_millis;
+use crate::errors::AppError;
+use crate::tags;
+
+/// What happens to new clips from a domain, applied by the rules engine at ingestion
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct SourceSettings {
+    /// Drop new clips from this domain
+    pub muted: bool,
+    /// Tags every new clip from this domain gets
+    pub default_tags: Vec<String>,
+    /// Collection new clips are filed in, unless a rule names another
+    pub default_collection_id: Option<i64>,
+}
+
+/// A domain clips came from, for the sources view
+#[derive(Debug, Serialize)]
+pub struct Source {
+    pub domain: String,
+    /// Clips from this domain, not counting the trash
+    pub clip_count: u32,
+    /// `timestamp` of the newest clip from this domain
+    pub last_clipped_at: Option<i64>,
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+}
+
+fn settings_from_row(row: &Row, offset: usize) -> rusqlite::Result<SourceSettings> {
+    let default_tags: Option<String> = row.get(offset + 1)?;
+    Ok(SourceSettings {
+        muted: row.get::<_, Option<bool>>(offset)?.unwrap_or(false),
+        default_tags: default_tags.and_then(|tags| serde_json::from_str(&tags).ok()).unwrap_or_default(),
+        default_collection_id: row.get(offset + 2)?,
+    })
+}
+
+/// Lowercase and without `www.`, the way `clips::domain_of` stores it
+fn normalize_domain(domain: &str) -> Result<String, AppError> {
+    let domain = domain.trim().to_lowercase();
+    let domain = domain.strip_prefix("www.").unwrap_or(&domain);
+    if domain.is_empty() || domain.contains(['/', ' ']) {
+        return Err(AppError::validation(format!("Invalid domain '{}'", domain)));
+    }
+    Ok(domain.to_string())
+}
+
+/// Every domain with clips or settings, most clipped first
+pub fn list_sources(conn: &Connection) -> Result<Vec<Source>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "WITH counts AS (
+                SELECT domain, COUNT(*) AS clip_count, MAX(timestamp) AS last_clipped_at
+                FROM clips WHERE deleted_at IS NULL AND domain IS NOT NULL GROUP BY domain
+            )
+            SELECT d.domain, COALESCE(c.clip_count, 0), c.last_clipped_at,
+                   s.muted, s.default_tags, s.default_collection_id
+            FROM (SELECT domain FROM counts UNION SELECT domain FROM sources) d
+            LEFT JOIN counts c ON c.domain = d.domain
+            LEFT JOIN sources s ON s.domain = d.domain
+            ORDER BY COALESCE(c.clip_count, 0) DESC, d.domain",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let sources = stmt
+        .query_map([], |row| {
+            Ok(Source {
+                domain: row.get(0)?,
+                clip_count: row.get(1)?,
+                last_clipped_at: row.get(2)?,
+                settings: settings_from_row(row, 3)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to list sources: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read source: {}", e)))?;
+    Ok(sources)
+}
+
+/// Settings for `domain` exactly (subdomains are sources of their own); defaults if none are saved
+pub fn source_settings(conn: &Connection, domain: &str) -> Result<SourceSettings, AppError> {
+    conn.query_row(
+        "SELECT muted, default_tags, default_collection_id FROM sources WHERE domain = ?1",
+        params![domain],
+        |row| settings_from_row(row, 0),
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read source: {}", e)))
+    .map(Option::unwrap_or_default)
+}
+
+/// Replace the settings of `domain`. Back at the defaults, the domain is forgotten.
+pub fn update_source(conn: &Connection, domain: &str, settings: SourceSettings) -> Result<Source, AppError> {
+    let domain = normalize_domain(domain)?;
+    let default_tags = settings
+        .default_tags
+        .iter()
+        .map(|tag| tags::normalize_tag(tag))
+        .collect::<Result<Vec<_>, _>>()?;
+    let settings = SourceSettings {
+        default_tags,
+        ..settings
+    };
+    if let Some(collection_id) = settings.default_collection_id {
+        let exists: bool = conn
+            .query_row("SELECT EXISTS(SELECT 1 FROM collections WHERE id = ?1)", params![collection_id], |row| {
+                row.get(0)
+            })
+            .map_err(|e| AppError::database(format!("Failed to read collection: {}", e)))?;
+        if !exists {
+            return Err(AppError::not_found(format!("Collection {} not found", collection_id)));
+        }
+    }
+
+    if settings == SourceSettings::default() {
+        conn.execute("DELETE FROM sources WHERE domain = ?1", params![domain])
+            .map_err(|e| AppError::database(format!("Failed to save source: {}", e)))?;
+    } else {
+        let default_tags = serde_json::to_string(&settings.default_tags)
+            .map_err(|e| AppError::internal(format!("Failed to serialize source: {}", e)))?;
+        conn.execute(
+            "INSERT INTO sources (domain, muted, default_tags, default_collection_id, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (domain) DO UPDATE SET muted = excluded.muted, default_tags = excluded.default_tags,
+                default_collection_id = excluded.default_collection_id, updated_at = excluded.updated_at",
+            params![domain, settings.muted, default_tags, settings.default_collection_id, now_millis()],
+        )
+        .map_err(|e| AppError::database(format!("Failed to save source: {}", e)))?;
+    }
+
+    let (clip_count, last_clipped_at) = conn
+        .query_row(
+            "SELECT COUNT(*), MAX(timestamp) FROM clips WHERE deleted_at IS NULL AND domain = ?1",
+            params![domain],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| AppError::database(format!("Failed to count clips: {}", e)))?;
+    Ok(Source {
+        domain,
+        clip_count,
+        last_clipped_at,
+        settings,
+    })
+}
@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tauri::{AppHandle, Manager};
+
+use crate::clips;
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract::plain_text;
+use crate::http;
+use crate::providers::{send_bytes, LlmError};
+use crate::secrets::SecretsManager;
+use crate::settings::SettingsManager;
+
+pub const DEFAULT_VOICE: &str = "alloy";
+const OPENAI_TTS_MODEL: &str = "tts-1";
+const OPENAI_API_KEY_SECRET: &str = "openai_api_key";
+/// The OpenAI speech endpoint takes at most 4096 characters per request
+const OPENAI_CHUNK_CHARS: usize = 4_000;
+/// Longer texts are cut; an hour of speech is roughly this much
+const MAX_SPEECH_CHARS: usize = 60_000;
+/// Subfolder of the media dir holding generated audio
+const SPEECH_DIR: &str = "speech";
+
+/// Text-to-speech backend for reading clips aloud
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpeechEngine {
+    /// OpenAI's `tts-1` API, with the `openai_api_key` secret
+    #[default]
+    OpenAi,
+    /// A local Piper binary and `.onnx` voice model; the `voice` argument is ignored
+    Piper { binary: PathBuf, model: PathBuf },
+}
+
+impl SpeechEngine {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let SpeechEngine::Piper { binary, model } = self {
+            if !binary.is_absolute() || !model.is_absolute() {
+                return Err(AppError::validation("The Piper binary and voice model must be absolute paths"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Distinguishes cached files made by different engines and voices
+    fn cache_key(&self, voice: &str) -> String {
+        match self {
+            SpeechEngine::OpenAi => format!("openai:{}:{}", OPENAI_TTS_MODEL, voice),
+            SpeechEngine::Piper { model, .. } => format!("piper:{}", model.display()),
+        }
+    }
+
+    /// File extension and MIME type of the audio the engine produces
+    fn format(&self) -> (&'static str, &'static str) {
+        match self {
+            SpeechEngine::OpenAi => ("mp3", "audio/mpeg"),
+            SpeechEngine::Piper { .. } => ("wav", "audio/wav"),
+        }
+    }
+}
+
+/// Generated speech ready to play
+#[derive(Debug, Serialize)]
+pub struct Speech {
+    /// Absolute path of the audio file, for `convertFileSrc` in the UI
+    pub path: PathBuf,
+    pub media_type: String,
+    /// The file was generated earlier for the same text and voice
+    pub cached: bool,
+}
+
+/// Split `text` into pieces of at most `max_chars`, preferring sentence ends
+fn chunk_sentences(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for sentence in text.split_inclusive(['.', '!', '?', '\n']) {
+        if current.chars().count() + sentence.chars().count() > max_chars && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+        // A single sentence longer than a chunk is hard-split
+        let mut sentence: Vec<char> = sentence.chars().collect();
+        while sentence.len() > max_chars {
+            let rest = sentence.split_off(max_chars);
+            chunks.push(sentence.into_iter().collect());
+            sentence = rest;
+        }
+        current.extend(sentence);
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+async fn synthesize_with_openai(app_handle: &AppHandle, text: &str, voice: &str) -> Result<Vec<u8>, LlmError> {
+    let api_key = app_handle
+        .state::<SecretsManager>()
+        .get_secret(OPENAI_API_KEY_SECRET, "speech")
+        .await
+        .map_err(|e| LlmError::Auth { message: e.to_string() })?;
+    let client = http::client();
+    // MP3 frames can simply be concatenated, so long texts are spoken chunk by chunk
+    let mut audio = Vec::new();
+    for chunk in chunk_sentences(text, OPENAI_CHUNK_CHARS) {
+        let bytes = send_bytes(
+            client
+                .post("https://api.openai.com/v1/audio/speech")
+                .header("Authorization", format!("Bearer {}", api_key))
+                .json(&serde_json::json!({
+                    "model": OPENAI_TTS_MODEL,
+                    "input": chunk,
+                    "voice": voice,
+                    "response_format": "mp3"
+                })),
+        )
+        .await?;
+        audio.extend_from_slice(&bytes);
+    }
+    Ok(audio)
+}
+
+/// Pipe `text` into Piper and have it write a WAV file to `output`
+fn synthesize_with_piper(binary: &Path, model: &Path, text: &str, output: &Path) -> Result<(), AppError> {
+    let mut child = Command::new(binary)
+        .arg("--model")
+        .arg(model)
+        .arg("--output_file")
+        .arg(output)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::internal(format!("Failed to run {}: {}", binary.display(), e)))?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| AppError::internal("Piper has no stdin"))?
+        .write_all(text.as_bytes())
+        .map_err(|e| AppError::internal(format!("Failed to send text to Piper: {}", e)))?;
+    let result = child
+        .wait_with_output()
+        .map_err(|e| AppError::internal(format!("Piper failed: {}", e)))?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        return Err(AppError::internal(format!("Piper failed: {}", stderr.trim())));
+    }
+    Ok(())
+}
+
+/// The text to read for a clip: its title, then its content (or description)
+fn clip_text(app_handle: &AppHandle, clip_id: i64) -> Result<String, AppError> {
+    let db = app_handle.state::<Database>();
+    let clip = clips::get_clip(&db.conn()?, clip_id)?
+        .filter(|clip| clip.deleted_at.is_none())
+        .ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+    let body = plain_text(clip.content.as_deref().or(clip.description.as_deref()).unwrap_or_default());
+    if body.trim().is_empty() {
+        return Err(AppError::validation(format!("Clip {} has no text to read", clip_id)));
+    }
+    Ok(format!("{}.\n\n{}", clip.title.trim_end_matches('.'), body))
+}
+
+/// Speak a clip or a piece of text with the configured engine. Audio is cached in the
+/// media dir by text and voice, so asking again returns the same file at once.
+pub async fn synthesize_speech(
+    app_handle: &AppHandle,
+    clip_id: Option<i64>,
+    text: Option<String>,
+    voice: Option<String>,
+) -> Result<Speech, LlmError> {
+    let text = match (clip_id, text) {
+        (Some(clip_id), None) => clip_text(app_handle, clip_id)?,
+        (None, Some(text)) if !text.trim().is_empty() => text,
+        (None, Some(_)) => return Err(AppError::validation("Text to read must not be empty").into()),
+        _ => return Err(AppError::validation("Give either a clip or some text to read").into()),
+    };
+    let text: String = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text: String = text.chars().take(MAX_SPEECH_CHARS).collect();
+    let voice = voice.unwrap_or_else(|| DEFAULT_VOICE.to_string());
+
+    let engine = app_handle.state::<SettingsManager>().get().speech_engine;
+    let dir = app_handle.state::<AppConfig>().media_dir().join(SPEECH_DIR);
+    fs::create_dir_all(&dir).map_err(|e| AppError::internal(format!("Failed to create speech folder: {}", e)))?;
+    let hash: String = Sha256::digest(format!("{}\n{}", engine.cache_key(&voice), text).as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let (extension, media_type) = engine.format();
+    let path = dir.join(format!("{}.{}", hash, extension));
+    let media_type = media_type.to_string();
+    if path.is_file() {
+        return Ok(Speech {
+            path,
+            media_type,
+            cached: true,
+        });
+    }
+
+    // Write to a temp file and rename so an interrupted run never leaves a partial file cached
+    let tmp = dir.join(format!(".{}.part", hash));
+    match engine {
+        SpeechEngine::OpenAi => {
+            let audio = synthesize_with_openai(app_handle, &text, &voice).await?;
+            fs::write(&tmp, audio).map_err(|e| AppError::internal(format!("Failed to write speech: {}", e)))?;
+        }
+        SpeechEngine::Piper { binary, model } => {
+            let tmp = tmp.clone();
+            tauri::async_runtime::spawn_blocking(move || synthesize_with_piper(&binary, &model, &text, &tmp))
+                .await
+                .map_err(|e| format!("Speech synthesis failed: {}", e))??;
+        }
+    }
+    fs::rename(&tmp, &path).map_err(|e| AppError::internal(format!("Failed to write speech: {}", e)))?;
+    Ok(Speech {
+        path,
+        media_type,
+        cached: false,
+    })
+}
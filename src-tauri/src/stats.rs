@@ -0,0 +1,162 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::clips::{now_millis, week_start, WeeklyCount, CHARS_PER_WORD, WEEK_MILLIS, WORDS_PER_MINUTE};
+use crate::config::AppConfig;
+use crate::errors::AppError;
+
+/// Tags and domains listed in the dashboard; the rest only count towards the totals
+const TOP_LIMIT: u32 = 25;
+
+/// Clips grouped by type, tag or domain
+#[derive(Debug, Serialize)]
+pub struct NamedCount {
+    pub name: String,
+    pub count: u32,
+}
+
+/// Bytes on disk used by each kind of stored data
+#[derive(Debug, Serialize, Default)]
+pub struct StorageUsage {
+    pub database_bytes: u64,
+    /// Images, audio, generated speech and thumbnails
+    pub media_bytes: u64,
+    pub archive_bytes: u64,
+    pub favicon_bytes: u64,
+}
+
+/// Everything the library dashboard shows
+#[derive(Debug, Serialize)]
+pub struct LibraryStats {
+    pub total_clips: u32,
+    pub by_type: Vec<NamedCount>,
+    /// The most used tags, most clips first
+    pub by_tag: Vec<NamedCount>,
+    /// The most clipped sites, most clips first
+    pub by_domain: Vec<NamedCount>,
+    /// Clips saved per week, oldest week first
+    pub clips_per_week: Vec<WeeklyCount>,
+    /// Estimated from the length of the clips' text
+    pub total_words: u64,
+    /// Words in unread and in-progress clips
+    pub backlog_words: u64,
+    pub backlog_minutes: u32,
+    pub storage: StorageUsage,
+}
+
+fn named_counts(conn: &Connection, sql: &str, limit: u32) -> Result<Vec<NamedCount>, AppError> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let counts = stmt
+        .query_map(params![limit], |row| {
+            Ok(NamedCount {
+                name: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to count clips: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to count clips: {}", e)))?;
+    Ok(counts)
+}
+
+/// Total size of the files under `dir`, including subfolders. Missing dirs count as empty.
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Library-wide counts, clips saved in each of the last `weeks` weeks, the reading
+/// backlog and disk usage. Trashed clips aren't counted.
+pub fn library_stats(conn: &Connection, config: &AppConfig, weeks: u32) -> Result<LibraryStats, AppError> {
+    let (total_clips, total_chars, backlog_chars): (u32, i64, i64) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(LENGTH(content)), 0),
+                    COALESCE(SUM(CASE WHEN read_state != 'archived' THEN LENGTH(content) END), 0)
+             FROM clips WHERE deleted_at IS NULL",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| AppError::database(format!("Failed to count clips: {}", e)))?;
+
+    let by_type = named_counts(
+        conn,
+        "SELECT type, COUNT(*) FROM clips WHERE deleted_at IS NULL GROUP BY type ORDER BY COUNT(*) DESC LIMIT ?1",
+        TOP_LIMIT,
+    )?;
+    // Pending auto-tag suggestions aren't the user's tags yet
+    let by_tag = named_counts(
+        conn,
+        "SELECT t.name, COUNT(*) FROM clip_tags ct
+         JOIN tags t ON t.id = ct.tag_id JOIN clips c ON c.id = ct.clip_id
+         WHERE ct.source = 'user' AND c.deleted_at IS NULL
+         GROUP BY t.id ORDER BY COUNT(*) DESC, t.name LIMIT ?1",
+        TOP_LIMIT,
+    )?;
+    let by_domain = named_counts(
+        conn,
+        "SELECT domain, COUNT(*) FROM clips WHERE domain IS NOT NULL AND deleted_at IS NULL
+         GROUP BY domain ORDER BY COUNT(*) DESC, domain LIMIT ?1",
+        TOP_LIMIT,
+    )?;
+
+    let weeks = weeks.max(1) as i64;
+    let first_week = week_start(now_millis()) - (weeks - 1) * WEEK_MILLIS;
+    let mut clips_per_week: Vec<WeeklyCount> = (0..weeks)
+        .map(|i| WeeklyCount {
+            week_start: first_week + i * WEEK_MILLIS,
+            count: 0,
+        })
+        .collect();
+    // Same bucketing as `week_start`: days since the epoch, back to the Monday
+    let mut stmt = conn
+        .prepare(
+            "SELECT (timestamp / 86400000 - (timestamp / 86400000 + 3) % 7) * 86400000 AS week, COUNT(*)
+             FROM clips WHERE timestamp >= ?1 AND deleted_at IS NULL GROUP BY week",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let rows = stmt
+        .query_map(params![first_week], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, u32>(1)?)))
+        .map_err(|e| AppError::database(format!("Failed to count clips per week: {}", e)))?;
+    for row in rows {
+        let (week, count) = row.map_err(|e| AppError::database(format!("Failed to count clips per week: {}", e)))?;
+        if let Some(bucket) = clips_per_week.get_mut(((week - first_week) / WEEK_MILLIS) as usize) {
+            bucket.count = count;
+        }
+    }
+
+    let backlog_words = (backlog_chars as f64 / CHARS_PER_WORD) as u64;
+    let database_bytes = [config.clips_db_path(), config.clips_db_path().with_extension("db-wal")]
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    Ok(LibraryStats {
+        total_clips,
+        by_type,
+        by_tag,
+        by_domain,
+        clips_per_week,
+        total_words: (total_chars as f64 / CHARS_PER_WORD) as u64,
+        backlog_words,
+        backlog_minutes: (backlog_words as f64 / WORDS_PER_MINUTE).ceil() as u32,
+        storage: StorageUsage {
+            database_bytes,
+            media_bytes: dir_size(&config.media_dir()),
+            archive_bytes: dir_size(&config.archives_dir()),
+            favicon_bytes: dir_size(&config.favicons_dir()),
+        },
+    })
+}
@@ -0,0 +1,207 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::clips::{self, SqliteClip};
+use crate::errors::AppError;
+
+const MAX_NAME_LEN: usize = 64;
+
+/// A column of the clip board, e.g. `To Read`; statuses are shown in `position` order
+#[derive(Debug, Serialize, Clone)]
+pub struct ClipStatus {
+    pub id: i64,
+    pub name: String,
+    pub position: i64,
+    /// Clips with this status, not counting the trash
+    pub clip_count: u32,
+}
+
+fn normalize_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::validation("Status name must not be empty"));
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::validation(format!("Status name must be at most {} characters", MAX_NAME_LEN)));
+    }
+    Ok(name.to_string())
+}
+
+fn get_status(conn: &Connection, id: i64) -> Result<Option<ClipStatus>, AppError> {
+    conn.query_row(
+        "SELECT s.id, s.name, s.position,
+                (SELECT COUNT(*) FROM clips WHERE status_id = s.id AND deleted_at IS NULL)
+         FROM clip_statuses s WHERE s.id = ?1",
+        params![id],
+        |row| {
+            Ok(ClipStatus {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                position: row.get(2)?,
+                clip_count: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| AppError::database(format!("Failed to read status: {}", e)))
+}
+
+fn name_taken(conn: &Connection, name: &str, except_id: Option<i64>) -> Result<bool, AppError> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM clip_statuses WHERE name = ?1 AND id IS NOT ?2)",
+        params![name, except_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| AppError::database(format!("Failed to read statuses: {}", e)))
+}
+
+/// Every status with its clip count, in board order
+pub fn list_statuses(conn: &Connection) -> Result<Vec<ClipStatus>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.name, s.position,
+                    (SELECT COUNT(*) FROM clips WHERE status_id = s.id AND deleted_at IS NULL)
+             FROM clip_statuses s ORDER BY s.position, s.id",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let statuses = stmt
+        .query_map([], |row| {
+            Ok(ClipStatus {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                position: row.get(2)?,
+                clip_count: row.get(3)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to list statuses: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read status: {}", e)))?;
+    Ok(statuses)
+}
+
+/// Add a status as the last column of the board
+pub fn create_status(conn: &Connection, name: &str) -> Result<ClipStatus, AppError> {
+    let name = normalize_name(name)?;
+    if name_taken(conn, &name, None)? {
+        return Err(AppError::validation(format!("A status named '{}' already exists", name)));
+    }
+    conn.execute(
+        "INSERT INTO clip_statuses (name, position)
+         VALUES (?1, (SELECT COALESCE(MAX(position) + 1, 0) FROM clip_statuses))",
+        params![name],
+    )
+    .map_err(|e| AppError::database(format!("Failed to create status: {}", e)))?;
+
+    let id = conn.last_insert_rowid();
+    get_status(conn, id)?.ok_or_else(|| AppError::database(format!("Status {} vanished after insert", id)))
+}
+
+pub fn rename_status(conn: &Connection, id: i64, name: &str) -> Result<ClipStatus, AppError> {
+    let name = normalize_name(name)?;
+    if name_taken(conn, &name, Some(id))? {
+        return Err(AppError::validation(format!("A status named '{}' already exists", name)));
+    }
+    let changed = conn
+        .execute("UPDATE clip_statuses SET name = ?1 WHERE id = ?2", params![name, id])
+        .map_err(|e| AppError::database(format!("Failed to rename status: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Status {} not found", id)));
+    }
+    get_status(conn, id)?.ok_or_else(|| AppError::not_found(format!("Status {} not found", id)))
+}
+
+/// Delete a status. Its clips are kept but no longer have a status.
+pub fn delete_status(conn: &Connection, id: i64) -> Result<(), AppError> {
+    conn.execute("UPDATE clips SET status_position = NULL WHERE status_id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to clear clip statuses: {}", e)))?;
+    let deleted = conn
+        .execute("DELETE FROM clip_statuses WHERE id = ?1", params![id])
+        .map_err(|e| AppError::database(format!("Failed to delete status: {}", e)))?;
+    if deleted == 0 {
+        return Err(AppError::not_found(format!("Status {} not found", id)));
+    }
+    Ok(())
+}
+
+/// Put the board's columns in the order of `ids`, which must name every status once
+pub fn reorder_statuses(conn: &mut Connection, ids: &[i64]) -> Result<Vec<ClipStatus>, AppError> {
+    let mut current: Vec<i64> = list_statuses(conn)?.into_iter().map(|status| status.id).collect();
+    let mut requested = ids.to_vec();
+    current.sort_unstable();
+    requested.sort_unstable();
+    if current != requested {
+        return Err(AppError::validation("Reordering must list every status exactly once"));
+    }
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+    for (position, id) in ids.iter().enumerate() {
+        tx.execute("UPDATE clip_statuses SET position = ?1 WHERE id = ?2", params![position as i64, id])
+            .map_err(|e| AppError::database(format!("Failed to reorder statuses: {}", e)))?;
+    }
+    tx.commit()
+        .map_err(|e| AppError::database(format!("Failed to commit status order: {}", e)))?;
+    list_statuses(conn)
+}
+
+/// Give a clip a status, placing it at the bottom of that column, or clear it with None.
+/// Setting the status it already has leaves it where it is. Like read states, this
+/// doesn't bump `updated_at`.
+pub fn set_clip_status(conn: &Connection, clip_id: i64, status_id: Option<i64>) -> Result<SqliteClip, AppError> {
+    let clip =
+        clips::get_clip(conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+    if clip.status_id == status_id {
+        return Ok(clip);
+    }
+    if let Some(status_id) = status_id {
+        if get_status(conn, status_id)?.is_none() {
+            return Err(AppError::not_found(format!("Status {} not found", status_id)));
+        }
+    }
+    conn.execute(
+        "UPDATE clips SET status_id = ?1,
+            status_position = CASE WHEN ?1 IS NULL THEN NULL
+                ELSE (SELECT COALESCE(MAX(status_position) + 1, 0) FROM clips WHERE status_id = ?1) END
+         WHERE id = ?2",
+        params![status_id, clip_id],
+    )
+    .map_err(|e| AppError::database(format!("Failed to set clip status: {}", e)))?;
+    clips::get_clip(conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))
+}
+
+/// Move a clip to `position` (0 is the top) within its status column, shifting the others.
+/// Positions past the end put it last.
+pub fn reorder_clip(conn: &mut Connection, clip_id: i64, position: usize) -> Result<SqliteClip, AppError> {
+    let clip =
+        clips::get_clip(conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+    let status_id = clip
+        .status_id
+        .ok_or_else(|| AppError::validation(format!("Clip {} has no status; set one before ordering it", clip_id)))?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+    let mut column: Vec<i64> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id FROM clips WHERE status_id = ?1 AND deleted_at IS NULL AND id != ?2
+                 ORDER BY status_position IS NULL, status_position, id",
+            )
+            .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+        let ids = stmt
+            .query_map(params![status_id, clip_id], |row| row.get(0))
+            .map_err(|e| AppError::database(format!("Failed to read status column: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::database(format!("Failed to read status column: {}", e)))?;
+        ids
+    };
+    column.insert(position.min(column.len()), clip_id);
+    for (position, id) in column.iter().enumerate() {
+        tx.execute("UPDATE clips SET status_position = ?1 WHERE id = ?2", params![position as i64, id])
+            .map_err(|e| AppError::database(format!("Failed to reorder clips: {}", e)))?;
+    }
+    tx.commit()
+        .map_err(|e| AppError::database(format!("Failed to commit clip order: {}", e)))?;
+    clips::get_clip(conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))
+}
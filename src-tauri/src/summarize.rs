@@ -0,0 +1,115 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::clips;
+use crate::db::Database;
+use crate::events;
+use crate::extract::plain_text;
+use crate::llm;
+use crate::providers::{LlmError, LlmMessage};
+
+/// Longest piece of an article sent in one request (~3k tokens)
+const CHUNK_CHARS: usize = 12_000;
+const SUMMARY_MAX_TOKENS: u32 = 400;
+
+/// Payload of the `clip-summarized` event
+#[derive(Debug, Serialize, Clone)]
+pub struct ClipSummarized {
+    pub clip_id: i64,
+    pub summary: String,
+}
+
+/// Split on line boundaries into pieces of at most `max_chars`, hard-splitting overlong lines
+fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for line in text.lines() {
+        let mut line: Vec<char> = line.chars().collect();
+        while line.len() > max_chars {
+            let rest = line.split_off(max_chars);
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+            }
+            chunks.push(line.into_iter().collect());
+            line = rest;
+        }
+
+        if current.chars().count() + line.len() + 1 > max_chars && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.extend(line);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+async fn ask(app_handle: &AppHandle, system: &str, prompt: String) -> Result<String, LlmError> {
+    let messages = vec![
+        LlmMessage {
+            role: "system".to_string(),
+            content: system.to_string(),
+        },
+        LlmMessage {
+            role: "user".to_string(),
+            content: prompt,
+        },
+    ];
+    let response = llm::complete_with_default(app_handle, "summarize", messages, Some(SUMMARY_MAX_TOKENS)).await?;
+    Ok(response.content.trim().to_string())
+}
+
+/// Summarize a clip with the default model, store the summary and emit `clip-summarized`.
+/// Long articles are summarized chunk by chunk and the partial summaries then combined.
+pub async fn summarize_clip(app_handle: &AppHandle, id: i64) -> Result<String, LlmError> {
+    let clip = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::get_clip(&conn, id)?.ok_or_else(|| format!("Clip {} not found", id))?
+    };
+
+    let source = clip.content.as_deref().or(clip.description.as_deref()).unwrap_or_default();
+    let text = plain_text(source);
+    if text.is_empty() {
+        return Err(format!("Clip {} has no content to summarize", id).into());
+    }
+
+    let system = "You summarize saved web clips. Reply with the summary only, in 3-5 sentences.";
+    let chunks = chunk_text(&text, CHUNK_CHARS);
+    let summary = if chunks.len() == 1 {
+        ask(app_handle, system, format!("Title: {}\n\n{}", clip.title, text)).await?
+    } else {
+        let mut partials = Vec::with_capacity(chunks.len());
+        for (index, chunk) in chunks.iter().enumerate() {
+            let prompt = format!("Title: {}\nPart {} of {}:\n\n{}", clip.title, index + 1, chunks.len(), chunk);
+            partials.push(ask(app_handle, system, prompt).await?);
+        }
+        let prompt = format!(
+            "Title: {}\n\nThese are summaries of consecutive parts of one article. Combine them into a single summary.\n\n{}",
+            clip.title,
+            partials.join("\n\n")
+        );
+        ask(app_handle, system, prompt).await?
+    };
+
+    {
+        let db = app_handle.state::<Database>();
+        clips::set_summary(&db.conn()?, id, &summary)?;
+    }
+    events::emit(
+        app_handle,
+        "clip-summarized",
+        &ClipSummarized {
+            clip_id: id,
+            summary: summary.clone(),
+        },
+    )
+    .map_err(|e| format!("Failed to emit summary event: {}", e))?;
+
+    Ok(summary)
+}
@@ -0,0 +1,414 @@
+use reqwest::{StatusCode, Url};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info};
+
+use super::{get_state, load_change, set_state, ClipChange, SyncManager};
+use crate::clips::now_millis;
+use crate::connectivity;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::http;
+use crate::redact;
+use crate::secrets::SecretsManager;
+use crate::settings::SettingsManager;
+
+/// Secret holding the libsql/Turso database auth token
+pub const LIBSQL_AUTH_TOKEN_SECRET: &str = "libsql_auth_token";
+/// Push to the libsql database this often unless `libsql_push_interval_minutes` says otherwise
+pub const DEFAULT_LIBSQL_PUSH_INTERVAL_MINUTES: u32 = 15;
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Clips per pipeline request
+const MAX_CHANGES_PER_PUSH: usize = 200;
+
+const CREATE_REMOTE_TABLE: &str = "CREATE TABLE IF NOT EXISTS clips (
+    sync_id TEXT PRIMARY KEY,
+    type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    url TEXT,
+    content TEXT,
+    image_url TEXT,
+    description TEXT,
+    author TEXT,
+    timestamp INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    summary TEXT,
+    category TEXT,
+    read_state TEXT NOT NULL,
+    reading_progress REAL NOT NULL,
+    finished_at INTEGER,
+    deleted_at INTEGER,
+    tags TEXT NOT NULL
+)";
+
+/// Another device pushing to the same database may hold a newer copy; the newer `updated_at` wins
+const UPSERT_REMOTE_CLIP: &str = "INSERT INTO clips (sync_id, type, title, url, content, image_url, description,
+    author, timestamp, created_at, updated_at, summary, category, read_state, reading_progress, finished_at,
+    deleted_at, tags)
+VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+ON CONFLICT (sync_id) DO UPDATE SET
+    type = excluded.type, title = excluded.title, url = excluded.url, content = excluded.content,
+    image_url = excluded.image_url, description = excluded.description, author = excluded.author,
+    timestamp = excluded.timestamp, updated_at = excluded.updated_at, summary = excluded.summary,
+    category = excluded.category, read_state = excluded.read_state, reading_progress = excluded.reading_progress,
+    finished_at = excluded.finished_at, deleted_at = excluded.deleted_at, tags = excluded.tags
+WHERE excluded.updated_at >= clips.updated_at";
+
+/// A hosted libsql database (e.g. on Turso) that gets a copy of every clip, set in
+/// `Settings::libsql_remote`. The local database stays the one the app reads and
+/// writes; changes are pushed in the background. The auth token is the
+/// `LIBSQL_AUTH_TOKEN_SECRET` secret.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibsqlRemote {
+    /// `libsql://`, `https://` or, for a local sqld, `http://` URL of the database
+    pub url: String,
+}
+
+impl LibsqlRemote {
+    pub fn validate(&self) -> Result<(), AppError> {
+        self.pipeline_url().map(|_| ())
+    }
+
+    /// The Hrana-over-HTTP endpoint; `libsql://` URLs are served over HTTPS
+    fn pipeline_url(&self) -> Result<Url, AppError> {
+        let url = self.url.trim().trim_end_matches('/');
+        let url = match url.strip_prefix("libsql://") {
+            Some(rest) => format!("https://{}", rest),
+            None => url.to_string(),
+        };
+        let parsed = Url::parse(&format!("{}/v2/pipeline", url))
+            .map_err(|e| AppError::validation(format!("Invalid libsql URL '{}': {}", self.url, e)))?;
+        if !matches!(parsed.scheme(), "https" | "http") {
+            return Err(AppError::validation("The libsql URL must start with libsql://, https:// or http://"));
+        }
+        Ok(parsed)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemotePushReport {
+    /// Clips created or updated in the remote database
+    pub pushed: usize,
+    /// Clips purged here and removed from the remote database
+    pub removed: usize,
+    pub finished_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemoteSyncStatus {
+    /// URL of the configured database, or None when the remote copy is off
+    pub url: Option<String>,
+    pub running: bool,
+    pub last_push_at: Option<i64>,
+    pub last_error: Option<String>,
+    /// Clips changed locally since the last push
+    pub pending_changes: u32,
+}
+
+/// A Hrana statement argument
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Value {
+    Null,
+    /// Hrana sends integers as strings so they survive JSON's doubles
+    Integer { value: String },
+    Float { value: f64 },
+    Text { value: String },
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer { value: value.to_string() }
+    }
+}
+
+impl From<Option<i64>> for Value {
+    fn from(value: Option<i64>) -> Self {
+        value.map_or(Value::Null, Value::from)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float { value }
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text { value }
+    }
+}
+
+impl From<Option<String>> for Value {
+    fn from(value: Option<String>) -> Self {
+        value.map_or(Value::Null, Value::from)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Statement {
+    sql: &'static str,
+    args: Vec<Value>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum PipelineRequest {
+    Execute { stmt: Statement },
+    Close,
+}
+
+#[derive(Debug, Deserialize)]
+struct PipelineResponse {
+    results: Vec<PipelineResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum PipelineResult {
+    Ok {},
+    Error { error: PipelineError },
+}
+
+#[derive(Debug, Deserialize)]
+struct PipelineError {
+    message: String,
+}
+
+fn upsert(change: ClipChange) -> Result<Statement, AppError> {
+    let tags = serde_json::to_string(&change.tags)
+        .map_err(|e| AppError::internal(format!("Failed to serialize tags: {}", e)))?;
+    Ok(Statement {
+        sql: UPSERT_REMOTE_CLIP,
+        args: vec![
+            change.sync_id.into(),
+            change.r#type.into(),
+            change.title.into(),
+            change.url.into(),
+            change.content.into(),
+            change.image_url.into(),
+            change.description.into(),
+            change.author.into(),
+            change.timestamp.into(),
+            change.created_at.into(),
+            change.updated_at.into(),
+            change.summary.into(),
+            change.category.into(),
+            change.read_state.into(),
+            change.reading_progress.into(),
+            change.finished_at.into(),
+            change.deleted_at.into(),
+            tags.into(),
+        ],
+    })
+}
+
+/// Run `statements` in order in one pipeline request. Every statement is idempotent,
+/// so a push that fails halfway is simply repeated.
+async fn execute(client: &reqwest::Client, url: &Url, token: &str, statements: Vec<Statement>) -> Result<(), AppError> {
+    let requests: Vec<PipelineRequest> = statements
+        .into_iter()
+        .map(|stmt| PipelineRequest::Execute { stmt })
+        .chain(std::iter::once(PipelineRequest::Close))
+        .collect();
+    let response = client
+        .post(url.clone())
+        .bearer_auth(token)
+        .json(&serde_json::json!({ "requests": requests }))
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to reach libsql database: {}", e)))?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = format!("libsql database returned HTTP {}", status);
+        return Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => AppError::auth(message),
+            _ => AppError::network(message),
+        });
+    }
+    let body: PipelineResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::network(format!("Unreadable libsql response: {}", e)))?;
+    match body.results.into_iter().find_map(|result| match result {
+        PipelineResult::Error { error } => Some(error),
+        PipelineResult::Ok {} => None,
+    }) {
+        Some(error) => Err(AppError::database(format!("libsql database rejected the push: {}", error.message))),
+        None => Ok(()),
+    }
+}
+
+/// Pending clips, oldest id first: `(clip_id, version, sync_id of a purged clip)`
+fn pending(conn: &Connection) -> Result<Vec<(i64, i64, Option<String>)>, AppError> {
+    let mut stmt = conn
+        .prepare("SELECT clip_id, version, sync_id FROM libsql_pending ORDER BY clip_id LIMIT ?1")
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let rows = stmt
+        .query_map(params![MAX_CHANGES_PER_PUSH as i64], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| AppError::database(format!("Failed to read pending changes: {}", e)))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read pending changes: {}", e)))?;
+    Ok(rows)
+}
+
+async fn run(app_handle: &AppHandle) -> Result<RemotePushReport, AppError> {
+    let remote = app_handle
+        .state::<SettingsManager>()
+        .get()
+        .libsql_remote
+        .ok_or_else(|| AppError::validation("No libsql database is set up; add one in settings"))?;
+    let url = remote.pipeline_url()?;
+    let token = app_handle
+        .state::<SecretsManager>()
+        .get_secret(LIBSQL_AUTH_TOKEN_SECRET, "libsql_push")
+        .await
+        .map_err(|e| match e {
+            AppError::NotFound { .. } => {
+                AppError::auth(format!("No {} stored; add it in settings", LIBSQL_AUTH_TOKEN_SECRET))
+            }
+            other => other,
+        })?;
+    let client = http::client();
+
+    let (mut pushed, mut removed) = (0, 0);
+    let mut statements = vec![Statement {
+        sql: CREATE_REMOTE_TABLE,
+        args: Vec::new(),
+    }];
+    loop {
+        let versions = {
+            let db = app_handle.state::<Database>();
+            let conn = db.conn()?;
+            let versions = pending(&conn)?;
+            for (clip_id, _, sync_id) in &versions {
+                match (load_change(&conn, *clip_id)?, sync_id) {
+                    (Some(change), _) => {
+                        statements.push(upsert(change)?);
+                        pushed += 1;
+                    }
+                    (None, Some(sync_id)) => {
+                        statements.push(Statement {
+                            sql: "DELETE FROM clips WHERE sync_id = ?",
+                            args: vec![sync_id.clone().into()],
+                        });
+                        removed += 1;
+                    }
+                    (None, None) => {}
+                }
+            }
+            versions
+        };
+        if versions.is_empty() && statements.is_empty() {
+            break;
+        }
+        execute(&client, &url, &token, std::mem::take(&mut statements)).await?;
+        if versions.is_empty() {
+            break;
+        }
+
+        // Only clear entries that weren't edited again while uploading
+        let db = app_handle.state::<Database>();
+        let mut conn = db.conn()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+        for (clip_id, version, _) in &versions {
+            tx.execute(
+                "DELETE FROM libsql_pending WHERE clip_id = ?1 AND version = ?2",
+                params![clip_id, version],
+            )
+            .map_err(|e| AppError::database(format!("Failed to update pending changes: {}", e)))?;
+        }
+        tx.commit()
+            .map_err(|e| AppError::database(format!("Failed to commit push progress: {}", e)))?;
+    }
+
+    Ok(RemotePushReport {
+        pushed,
+        removed,
+        finished_at: now_millis(),
+    })
+}
+
+/// Push clips changed since the last push to the libsql database. Only one push runs
+/// at a time. Emits `remote-push-finished` with the report.
+pub async fn push_to_remote(app_handle: &AppHandle) -> Result<RemotePushReport, AppError> {
+    let manager = app_handle.state::<SyncManager>();
+    let _guard = manager.remote_lock.lock().await;
+
+    let started_at = now_millis();
+    let result = run(app_handle).await;
+    {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        set_state(&conn, "remote_last_attempt_at", Some(&started_at.to_string()))?;
+        match &result {
+            Ok(report) => {
+                set_state(&conn, "remote_last_push_at", Some(&report.finished_at.to_string()))?;
+                set_state(&conn, "remote_last_error", None)?;
+            }
+            Err(e) => set_state(&conn, "remote_last_error", Some(&redact::redact(&e.to_string())))?,
+        }
+    }
+    let report = result?;
+    app_handle
+        .emit("remote-push-finished", &report)
+        .map_err(|e| AppError::internal(format!("Failed to emit remote push event: {}", e)))?;
+    Ok(report)
+}
+
+pub fn get_remote_sync_status(app_handle: &AppHandle) -> Result<RemoteSyncStatus, AppError> {
+    let settings = app_handle.state::<SettingsManager>().get();
+    let running = app_handle.state::<SyncManager>().remote_lock.try_lock().is_err();
+    let db = app_handle.state::<Database>();
+    let conn = db.conn()?;
+    let pending_changes = conn
+        .query_row("SELECT COUNT(*) FROM libsql_pending", [], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to count pending changes: {}", e)))?;
+    Ok(RemoteSyncStatus {
+        url: settings.libsql_remote.map(|remote| remote.url),
+        running,
+        last_push_at: get_state(&conn, "remote_last_push_at")?.and_then(|at| at.parse().ok()),
+        last_error: get_state(&conn, "remote_last_error")?,
+        pending_changes,
+    })
+}
+
+/// Start the background task that pushes to the libsql database every
+/// `libsql_push_interval_minutes` while one is configured. Pushes are skipped while
+/// offline, and a failed push waits a full interval before the next try.
+pub fn start_push_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let settings = app_handle.state::<SettingsManager>().get();
+            if let (Some(_), Some(interval)) = (&settings.libsql_remote, settings.libsql_push_interval()) {
+                if connectivity::is_online() {
+                    let last_attempt_at = {
+                        let db = app_handle.state::<Database>();
+                        db.conn().and_then(|conn| get_state(&conn, "remote_last_attempt_at"))
+                    };
+                    match last_attempt_at {
+                        Ok(last_attempt_at) => {
+                            let last_attempt_at = last_attempt_at.and_then(|at| at.parse::<i64>().ok()).unwrap_or(0);
+                            if now_millis() - last_attempt_at >= interval.as_millis() as i64 {
+                                match push_to_remote(&app_handle).await {
+                                    Ok(report) => {
+                                        info!("Pushed to libsql: {} updated, {} removed", report.pushed, report.removed)
+                                    }
+                                    Err(e) => error!("Scheduled libsql push failed: {}", e),
+                                }
+                            }
+                        }
+                        Err(e) => error!("{}", e),
+                    }
+                }
+            }
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,645 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{error, info};
+
+use crate::clips::{self, now_millis};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::redact;
+use crate::revisions;
+use crate::secrets::SecretsManager;
+use crate::settings::SettingsManager;
+use crate::tags;
+
+mod libsql;
+mod remote;
+
+pub use libsql::{
+    get_remote_sync_status, push_to_remote, start_push_scheduler, LibsqlRemote, RemotePushReport, RemoteSyncStatus,
+    DEFAULT_LIBSQL_PUSH_INTERVAL_MINUTES,
+};
+use remote::Remote;
+pub use remote::SyncBackend;
+
+/// Secret holding the passphrase the changelog is encrypted with. Every device
+/// syncing to the same backend needs the same passphrase.
+pub const SYNC_PASSPHRASE_SECRET: &str = "sync_passphrase";
+/// How often the scheduler checks whether a sync is due
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const MANIFEST_PATH: &str = "los-sync.json";
+const CHANGES_DIR: &str = "changes";
+const CHANGE_EXTENSION: &str = ".enc";
+const FORMAT_VERSION: u32 = 1;
+const KDF_ROUNDS: u32 = 600_000;
+const NONCE_LEN: usize = 12;
+/// Decrypting this from the manifest proves the passphrase is right
+const KEY_CHECK: &[u8] = b"los-sync";
+/// Clips per changelog file
+const MAX_CHANGES_PER_BATCH: usize = 500;
+
+/// Unencrypted file at the root of the backend
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    /// Base64 PBKDF2 salt for the passphrase
+    salt: String,
+    /// Base64 nonce and ciphertext of `KEY_CHECK`
+    check: String,
+}
+
+/// The synced state of one clip. Images, archives, collections and annotations
+/// stay on the device that made them.
+#[derive(Debug, Serialize, Deserialize)]
+struct ClipChange {
+    sync_id: String,
+    r#type: String,
+    title: String,
+    url: Option<String>,
+    content: Option<String>,
+    image_url: Option<String>,
+    description: Option<String>,
+    author: Option<String>,
+    timestamp: i64,
+    created_at: String,
+    updated_at: i64,
+    summary: Option<String>,
+    category: Option<String>,
+    read_state: String,
+    reading_progress: f64,
+    finished_at: Option<i64>,
+    deleted_at: Option<i64>,
+    /// User tags; pending auto-tag suggestions aren't synced
+    tags: Vec<String>,
+    #[serde(default)]
+    private: bool,
+    /// A private clip's content, still encrypted with its passphrase
+    #[serde(default)]
+    encrypted_body: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+}
+
+/// One changelog file, `changes/{device_id}-{seq}.enc`. Files are written once and
+/// never modified, so each device only reads what it hasn't seen.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChangeBatch {
+    device_id: String,
+    seq: i64,
+    created_at: i64,
+    changes: Vec<ClipChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncReport {
+    /// Clips created or updated from other devices' changes
+    pub pulled: usize,
+    /// Clips sent to the backend
+    pub pushed: usize,
+    /// Incoming changes dropped because the local clip was newer
+    pub conflicts: usize,
+    pub finished_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncStatus {
+    /// Kind of the configured backend, or None when sync is off
+    pub backend: Option<String>,
+    pub device_id: Option<String>,
+    pub running: bool,
+    pub last_sync_at: Option<i64>,
+    pub last_error: Option<String>,
+    /// Clips changed locally since the last push
+    pub pending_changes: u32,
+}
+
+/// Serializes syncs, and separately pushes to the libsql database, managed as Tauri state
+#[derive(Default)]
+pub struct SyncManager {
+    lock: Mutex<()>,
+    remote_lock: Mutex<()>,
+}
+
+impl SyncManager {
+    /// Wait for running syncs and pushes to finish and hold off new ones until the
+    /// guards are dropped
+    pub async fn pause(&self) -> (MutexGuard<'_, ()>, MutexGuard<'_, ()>) {
+        (self.lock.lock().await, self.remote_lock.lock().await)
+    }
+}
+
+fn get_state(conn: &Connection, key: &str) -> Result<Option<String>, AppError> {
+    conn.query_row("SELECT value FROM sync_state WHERE key = ?1", params![key], |row| row.get(0))
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read sync state: {}", e)))
+}
+
+fn set_state(conn: &Connection, key: &str, value: Option<&str>) -> Result<(), AppError> {
+    match value {
+        Some(value) => conn.execute(
+            "INSERT OR REPLACE INTO sync_state (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        ),
+        None => conn.execute("DELETE FROM sync_state WHERE key = ?1", params![key]),
+    }
+    .map_err(|e| AppError::database(format!("Failed to save sync state: {}", e)))?;
+    Ok(())
+}
+
+/// This device's id in the changelog, created on first use
+fn device_id(conn: &Connection) -> Result<String, AppError> {
+    if let Some(id) = get_state(conn, "device_id")? {
+        return Ok(id);
+    }
+    let mut bytes = [0u8; 8];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let id: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    set_state(conn, "device_id", Some(&id))?;
+    Ok(id)
+}
+
+pub fn get_sync_status(app_handle: &AppHandle) -> Result<SyncStatus, AppError> {
+    let settings = app_handle.state::<SettingsManager>().get();
+    let running = app_handle.state::<SyncManager>().lock.try_lock().is_err();
+    let db = app_handle.state::<Database>();
+    let conn = db.conn()?;
+    let pending_changes = conn
+        .query_row("SELECT COUNT(*) FROM sync_pending", [], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to count pending changes: {}", e)))?;
+    Ok(SyncStatus {
+        backend: settings.sync_backend.as_ref().map(|backend| backend.id().to_string()),
+        device_id: get_state(&conn, "device_id")?,
+        running,
+        last_sync_at: get_state(&conn, "last_sync_at")?.and_then(|at| at.parse().ok()),
+        last_error: get_state(&conn, "last_error")?,
+        pending_changes,
+    })
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Key<Aes256Gcm> {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    *Key::<Aes256Gcm>::from_slice(&key)
+}
+
+fn encrypt(cipher: &Aes256Gcm, plaintext: &[u8]) -> Result<Vec<u8>, AppError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|e| AppError::internal(format!("Failed to encrypt changes: {}", e)))?;
+    Ok([nonce.as_slice(), ciphertext.as_slice()].concat())
+}
+
+fn decrypt(cipher: &Aes256Gcm, data: &[u8]) -> Result<Vec<u8>, AppError> {
+    if data.len() < NONCE_LEN {
+        return Err(AppError::validation("Sync file is truncated"));
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| AppError::auth("Failed to decrypt synced changes (wrong passphrase or corrupted data)"))
+}
+
+/// The cipher for the backend's changelog. The first device to sync writes the
+/// manifest; later ones check their passphrase against it.
+async fn open_cipher(remote: &Remote, passphrase: String) -> Result<Aes256Gcm, AppError> {
+    let manifest = match remote.get(MANIFEST_PATH).await? {
+        Some(bytes) => Some(
+            serde_json::from_slice::<Manifest>(&bytes)
+                .map_err(|e| AppError::validation(format!("Unreadable sync manifest: {}", e)))?,
+        ),
+        None => None,
+    };
+    if let Some(manifest) = &manifest {
+        if manifest.version > FORMAT_VERSION {
+            return Err(AppError::validation(
+                "The sync backend was written by a newer version of LOS; update to keep syncing",
+            ));
+        }
+    }
+
+    let salt = match &manifest {
+        Some(manifest) => BASE64
+            .decode(&manifest.salt)
+            .map_err(|e| AppError::validation(format!("Corrupt sync manifest salt: {}", e)))?,
+        None => {
+            let mut salt = vec![0u8; 16];
+            rand::thread_rng().fill_bytes(&mut salt);
+            salt
+        }
+    };
+    // PBKDF2 is deliberately slow; keep it off the async workers
+    let kdf_salt = salt.clone();
+    let key = tauri::async_runtime::spawn_blocking(move || derive_key(&passphrase, &kdf_salt))
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to derive sync key: {}", e)))?;
+    let cipher = Aes256Gcm::new(&key);
+
+    match manifest {
+        Some(manifest) => {
+            let check = BASE64
+                .decode(&manifest.check)
+                .map_err(|e| AppError::validation(format!("Corrupt sync manifest: {}", e)))?;
+            if decrypt(&cipher, &check).ok().as_deref() != Some(KEY_CHECK) {
+                return Err(AppError::auth("The sync passphrase doesn't match the one used by your other devices"));
+            }
+        }
+        None => {
+            let manifest = Manifest {
+                version: FORMAT_VERSION,
+                salt: BASE64.encode(&salt),
+                check: BASE64.encode(encrypt(&cipher, KEY_CHECK)?),
+            };
+            let json = serde_json::to_vec_pretty(&manifest)
+                .map_err(|e| AppError::internal(format!("Failed to serialize sync manifest: {}", e)))?;
+            remote.put(MANIFEST_PATH, json).await?;
+        }
+    }
+    Ok(cipher)
+}
+
+/// `{device_id}-{seq}.enc` to its parts
+fn parse_change_name(name: &str) -> Option<(String, i64)> {
+    let (device_id, seq) = name.strip_suffix(CHANGE_EXTENSION)?.rsplit_once('-')?;
+    Some((device_id.to_string(), seq.parse().ok()?))
+}
+
+fn load_change(conn: &Connection, clip_id: i64) -> Result<Option<ClipChange>, AppError> {
+    let Some(clip) = clips::get_clip(conn, clip_id)? else {
+        return Ok(None);
+    };
+    let (sync_id, encrypted_body): (String, Option<String>) = conn
+        .query_row("SELECT sync_id, encrypted_body FROM clips WHERE id = ?1", params![clip_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.name FROM clip_tags ct JOIN tags t ON t.id = ct.tag_id
+             WHERE ct.clip_id = ?1 AND ct.source = 'user' ORDER BY t.name",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let tags = stmt
+        .query_map(params![clip_id], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to read tags: {}", e)))?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read tags: {}", e)))?;
+
+    Ok(Some(ClipChange {
+        sync_id,
+        r#type: clip.r#type,
+        title: clip.title,
+        url: clip.url,
+        content: clip.content,
+        image_url: clip.image_url,
+        description: clip.description,
+        author: clip.author,
+        timestamp: clip.timestamp,
+        created_at: clip.created_at,
+        updated_at: clip.updated_at,
+        summary: clip.summary,
+        category: clip.category,
+        read_state: clip.read_state,
+        reading_progress: clip.reading_progress,
+        finished_at: clip.finished_at,
+        deleted_at: clip.deleted_at,
+        tags,
+        private: clip.private,
+        encrypted_body,
+        language: clip.language,
+    }))
+}
+
+/// Apply another device's change. The newer `updated_at` wins; on a tie the incoming
+/// change wins unless the local clip has unpushed edits. Returns whether it was applied.
+fn apply_change(tx: &Transaction, change: &ClipChange) -> Result<bool, AppError> {
+    let local: Option<(i64, i64, bool)> = tx
+        .query_row(
+            "SELECT id, updated_at, EXISTS(SELECT 1 FROM sync_pending WHERE clip_id = clips.id)
+             FROM clips WHERE sync_id = ?1",
+            params![change.sync_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+
+    let url = change.url.as_deref();
+    let clip_id = match local {
+        Some((_, updated_at, pending))
+            if updated_at > change.updated_at || (updated_at == change.updated_at && pending) =>
+        {
+            return Ok(false);
+        }
+        Some((id, _, pending)) => {
+            // Keep local edits that lose to a newer remote edit as a revision
+            if pending {
+                if let Some(existing) = clips::get_clip(tx, id)? {
+                    if existing.title != change.title || existing.content != change.content {
+                        revisions::record(tx, &existing)?;
+                    }
+                }
+            }
+            tx.execute(
+                "UPDATE clips SET type = ?1, title = ?2, url = ?3, content = ?4, image_url = ?5, description = ?6,
+                    author = ?7, timestamp = ?8, updated_at = ?9, summary = ?10, category = ?11, read_state = ?12,
+                    reading_progress = ?13, finished_at = ?14, deleted_at = ?15, domain = ?16, normalized_url = ?17,
+                    content_hash = ?18, private = ?20, encrypted_body = ?21, language = ?22
+                 WHERE id = ?19",
+                params![
+                    change.r#type,
+                    change.title,
+                    change.url,
+                    change.content,
+                    change.image_url,
+                    change.description,
+                    change.author,
+                    change.timestamp,
+                    change.updated_at,
+                    change.summary,
+                    change.category,
+                    change.read_state,
+                    change.reading_progress,
+                    change.finished_at,
+                    change.deleted_at,
+                    url.and_then(clips::domain_of),
+                    url.and_then(clips::normalize_url),
+                    clips::content_hash(change.content.as_deref()),
+                    id,
+                    change.private,
+                    change.encrypted_body,
+                    change.language,
+                ],
+            )
+            .map_err(|e| AppError::database(format!("Failed to update synced clip: {}", e)))?;
+            id
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO clips (sync_id, type, title, url, content, image_url, description, author, timestamp,
+                    created_at, updated_at, summary, category, read_state, reading_progress, finished_at, deleted_at,
+                    domain, normalized_url, content_hash, private, encrypted_body, language)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
+                    ?21, ?22, ?23)",
+                params![
+                    change.sync_id,
+                    change.r#type,
+                    change.title,
+                    change.url,
+                    change.content,
+                    change.image_url,
+                    change.description,
+                    change.author,
+                    change.timestamp,
+                    change.created_at,
+                    change.updated_at,
+                    change.summary,
+                    change.category,
+                    change.read_state,
+                    change.reading_progress,
+                    change.finished_at,
+                    change.deleted_at,
+                    url.and_then(clips::domain_of),
+                    url.and_then(clips::normalize_url),
+                    clips::content_hash(change.content.as_deref()),
+                    change.private,
+                    change.encrypted_body,
+                    change.language,
+                ],
+            )
+            .map_err(|e| AppError::database(format!("Failed to insert synced clip: {}", e)))?;
+            tx.last_insert_rowid()
+        }
+    };
+
+    tx.execute("DELETE FROM clip_tags WHERE clip_id = ?1 AND source = 'user'", params![clip_id])
+        .map_err(|e| AppError::database(format!("Failed to update synced tags: {}", e)))?;
+    for tag in &change.tags {
+        tags::add_tag_to_clip(tx, clip_id, tag)?;
+    }
+    tx.execute("DELETE FROM tags WHERE NOT EXISTS (SELECT 1 FROM clip_tags WHERE tag_id = tags.id)", [])
+        .map_err(|e| AppError::database(format!("Failed to clean up tags: {}", e)))?;
+
+    // The triggers just marked the clip as changed here; it isn't, so don't echo it back
+    tx.execute("DELETE FROM sync_pending WHERE clip_id = ?1", params![clip_id])
+        .map_err(|e| AppError::database(format!("Failed to update pending changes: {}", e)))?;
+    Ok(true)
+}
+
+/// Read other devices' changelog files this device hasn't applied yet
+async fn pull(app_handle: &AppHandle, remote: &Remote, cipher: &Aes256Gcm, device_id: &str) -> Result<(usize, usize), AppError> {
+    let cursors: HashMap<String, i64> = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let mut stmt = conn
+            .prepare("SELECT device_id, seq FROM sync_cursors")
+            .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+        let cursors = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| AppError::database(format!("Failed to read sync cursors: {}", e)))?
+            .collect::<Result<_, _>>()
+            .map_err(|e| AppError::database(format!("Failed to read sync cursors: {}", e)))?;
+        cursors
+    };
+
+    let mut unseen: Vec<(String, i64, String)> = remote
+        .list(CHANGES_DIR)
+        .await?
+        .into_iter()
+        .filter_map(|name| parse_change_name(&name).map(|(device, seq)| (device, seq, name)))
+        .filter(|(device, seq, _)| device != device_id && *seq > cursors.get(device).copied().unwrap_or(0))
+        .collect();
+    unseen.sort();
+
+    let (mut pulled, mut conflicts) = (0, 0);
+    for (device, seq, name) in unseen {
+        let path = format!("{}/{}", CHANGES_DIR, name);
+        let Some(data) = remote.get(&path).await? else {
+            continue;
+        };
+        let batch: ChangeBatch = serde_json::from_slice(&decrypt(cipher, &data)?)
+            .map_err(|e| AppError::validation(format!("Unreadable sync file {}: {}", name, e)))?;
+
+        let db = app_handle.state::<Database>();
+        let mut conn = db.conn()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+        for change in &batch.changes {
+            if apply_change(&tx, change)? {
+                pulled += 1;
+            } else {
+                conflicts += 1;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO sync_cursors (device_id, seq) VALUES (?1, ?2)",
+            params![device, seq],
+        )
+        .map_err(|e| AppError::database(format!("Failed to save sync cursor: {}", e)))?;
+        tx.commit()
+            .map_err(|e| AppError::database(format!("Failed to commit synced changes: {}", e)))?;
+    }
+    Ok((pulled, conflicts))
+}
+
+/// Write clips changed here since the last push as new changelog files
+async fn push(app_handle: &AppHandle, remote: &Remote, cipher: &Aes256Gcm, device_id: &str) -> Result<usize, AppError> {
+    let mut pushed = 0;
+    loop {
+        let (batch, versions) = {
+            let db = app_handle.state::<Database>();
+            let conn = db.conn()?;
+            let mut stmt = conn
+                .prepare("SELECT clip_id, version FROM sync_pending ORDER BY clip_id LIMIT ?1")
+                .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+            let versions: Vec<(i64, i64)> = stmt
+                .query_map(params![MAX_CHANGES_PER_BATCH as i64], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| AppError::database(format!("Failed to read pending changes: {}", e)))?
+                .collect::<Result<_, _>>()
+                .map_err(|e| AppError::database(format!("Failed to read pending changes: {}", e)))?;
+            if versions.is_empty() {
+                break;
+            }
+            let mut changes = Vec::new();
+            // Clips purged from the trash since they changed have nothing left to send
+            for (clip_id, _) in &versions {
+                changes.extend(load_change(&conn, *clip_id)?);
+            }
+            let seq = get_state(&conn, "next_seq")?.and_then(|seq| seq.parse().ok()).unwrap_or(1);
+            let batch = ChangeBatch {
+                device_id: device_id.to_string(),
+                seq,
+                created_at: now_millis(),
+                changes,
+            };
+            (batch, versions)
+        };
+
+        if !batch.changes.is_empty() {
+            let json =
+                serde_json::to_vec(&batch).map_err(|e| AppError::internal(format!("Failed to serialize changes: {}", e)))?;
+            let name = format!("{}/{}-{:010}{}", CHANGES_DIR, device_id, batch.seq, CHANGE_EXTENSION);
+            remote.put(&name, encrypt(cipher, &json)?).await?;
+            pushed += batch.changes.len();
+        }
+
+        // Only clear entries that weren't edited again while uploading
+        let db = app_handle.state::<Database>();
+        let mut conn = db.conn()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::database(format!("Failed to start transaction: {}", e)))?;
+        for (clip_id, version) in &versions {
+            tx.execute(
+                "DELETE FROM sync_pending WHERE clip_id = ?1 AND version = ?2",
+                params![clip_id, version],
+            )
+            .map_err(|e| AppError::database(format!("Failed to update pending changes: {}", e)))?;
+        }
+        if !batch.changes.is_empty() {
+            set_state(&tx, "next_seq", Some(&(batch.seq + 1).to_string()))?;
+        }
+        tx.commit()
+            .map_err(|e| AppError::database(format!("Failed to commit sync progress: {}", e)))?;
+    }
+    Ok(pushed)
+}
+
+async fn run(app_handle: &AppHandle) -> Result<SyncReport, AppError> {
+    let backend = app_handle
+        .state::<SettingsManager>()
+        .get()
+        .sync_backend
+        .ok_or_else(|| AppError::validation("Sync isn't set up; choose a sync backend in settings"))?;
+    let secrets_manager = app_handle.state::<SecretsManager>();
+    let passphrase = secrets_manager.get_secret(SYNC_PASSPHRASE_SECRET, "sync").await.map_err(|e| match e {
+        AppError::NotFound { .. } => AppError::auth("Set a sync passphrase before syncing"),
+        other => other,
+    })?;
+    let remote = Remote::connect(&backend, &secrets_manager).await?;
+    let cipher = open_cipher(&remote, passphrase).await?;
+
+    let device_id = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        device_id(&conn)?
+    };
+    // Pull first so a local edit that lost to a newer remote one isn't pushed
+    let (pulled, conflicts) = pull(app_handle, &remote, &cipher, &device_id).await?;
+    let pushed = push(app_handle, &remote, &cipher, &device_id).await?;
+
+    Ok(SyncReport {
+        pulled,
+        pushed,
+        conflicts,
+        finished_at: now_millis(),
+    })
+}
+
+/// Exchange changes with the configured backend. Only one sync runs at a time; a
+/// second call waits for the first to finish. Emits `sync-finished` with the report.
+pub async fn sync_now(app_handle: &AppHandle) -> Result<SyncReport, AppError> {
+    let manager = app_handle.state::<SyncManager>();
+    let _guard = manager.lock.lock().await;
+
+    let started_at = now_millis();
+    let result = run(app_handle).await;
+    {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        set_state(&conn, "last_attempt_at", Some(&started_at.to_string()))?;
+        match &result {
+            Ok(report) => {
+                set_state(&conn, "last_sync_at", Some(&report.finished_at.to_string()))?;
+                set_state(&conn, "last_error", None)?;
+            }
+            Err(e) => set_state(&conn, "last_error", Some(&redact::redact(&e.to_string())))?,
+        }
+    }
+    let report = result?;
+    app_handle
+        .emit("sync-finished", &report)
+        .map_err(|e| AppError::internal(format!("Failed to emit sync event: {}", e)))?;
+    Ok(report)
+}
+
+/// Start the background task that syncs every `sync_interval_minutes` while a
+/// backend is configured. A failed sync waits a full interval before the next try.
+/// Settings are re-read on every check.
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let settings = app_handle.state::<SettingsManager>().get();
+            if let (Some(_), Some(interval)) = (&settings.sync_backend, settings.sync_interval()) {
+                let last_attempt_at = {
+                    let db = app_handle.state::<Database>();
+                    db.conn().and_then(|conn| get_state(&conn, "last_attempt_at"))
+                };
+                match last_attempt_at {
+                    Ok(last_attempt_at) => {
+                        let last_attempt_at = last_attempt_at.and_then(|at| at.parse::<i64>().ok()).unwrap_or(0);
+                        if now_millis() - last_attempt_at >= interval.as_millis() as i64 {
+                            match sync_now(&app_handle).await {
+                                Ok(report) => info!(
+                                    "Synced: {} pulled, {} pushed, {} conflicts",
+                                    report.pulled, report.pushed, report.conflicts
+                                ),
+                                Err(e) => error!("Scheduled sync failed: {}", e),
+                            }
+                        }
+                    }
+                    Err(e) => error!("{}", e),
+                }
+            }
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,438 @@
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::AppError;
+use crate::http;
+use crate::secrets::SecretsManager;
+
+/// Secret holding the WebDAV password
+pub const WEBDAV_PASSWORD_SECRET: &str = "sync_webdav_password";
+/// Secret holding the S3 secret access key
+pub const S3_SECRET_KEY_SECRET: &str = "sync_s3_secret_key";
+
+/// Where synced changes are stored, set in `Settings::sync_backend`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SyncBackend {
+    /// A folder kept in sync by another tool, e.g. inside Dropbox or iCloud Drive
+    Folder { path: PathBuf },
+    /// A WebDAV collection; the password is the `WEBDAV_PASSWORD_SECRET` secret
+    Webdav { url: String, username: String },
+    /// A bucket on S3 or an S3-compatible store, addressed path-style; the secret
+    /// key is the `S3_SECRET_KEY_SECRET` secret
+    S3 {
+        endpoint: String,
+        region: String,
+        bucket: String,
+        access_key_id: String,
+    },
+}
+
+impl SyncBackend {
+    /// Same as the serialized kind
+    pub fn id(&self) -> &'static str {
+        match self {
+            SyncBackend::Folder { .. } => "folder",
+            SyncBackend::Webdav { .. } => "webdav",
+            SyncBackend::S3 { .. } => "s3",
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        match self {
+            SyncBackend::Folder { path } => {
+                if !path.is_absolute() {
+                    return Err(AppError::validation("The sync folder must be an absolute path"));
+                }
+            }
+            SyncBackend::Webdav { url, .. } => {
+                Url::parse(url).map_err(|e| AppError::validation(format!("Invalid WebDAV URL '{}': {}", url, e)))?;
+            }
+            SyncBackend::S3 {
+                endpoint,
+                region,
+                bucket,
+                access_key_id,
+            } => {
+                Url::parse(endpoint).map_err(|e| AppError::validation(format!("Invalid S3 endpoint '{}': {}", endpoint, e)))?;
+                if region.trim().is_empty() || bucket.trim().is_empty() || access_key_id.trim().is_empty() {
+                    return Err(AppError::validation("S3 sync needs a region, bucket and access key id"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A connected sync backend. Paths are relative and `/`-separated, e.g. `changes/x.enc`.
+pub enum Remote {
+    Folder(PathBuf),
+    Webdav {
+        client: reqwest::Client,
+        base: Url,
+        username: String,
+        password: String,
+    },
+    S3 {
+        client: reqwest::Client,
+        base: Url,
+        region: String,
+        access_key_id: String,
+        secret_key: String,
+    },
+}
+
+/// A URL that `join` treats as a directory
+fn directory_url(url: &str) -> Result<Url, AppError> {
+    let url = if url.ends_with('/') { url.to_string() } else { format!("{}/", url) };
+    Url::parse(&url).map_err(|e| AppError::validation(format!("Invalid sync URL '{}': {}", url, e)))
+}
+
+impl Remote {
+    pub async fn connect(backend: &SyncBackend, secrets_manager: &SecretsManager) -> Result<Self, AppError> {
+        let secret = |name: &'static str| async move {
+            secrets_manager.get_secret(name, "sync").await.map_err(|e| match e {
+                AppError::NotFound { .. } => AppError::auth(format!("No {} stored; add it in settings", name)),
+                other => other,
+            })
+        };
+        Ok(match backend {
+            SyncBackend::Folder { path } => Remote::Folder(path.clone()),
+            SyncBackend::Webdav { url, username } => Remote::Webdav {
+                client: http::client(),
+                base: directory_url(url)?,
+                username: username.clone(),
+                password: secret(WEBDAV_PASSWORD_SECRET).await?,
+            },
+            SyncBackend::S3 {
+                endpoint,
+                region,
+                bucket,
+                access_key_id,
+            } => Remote::S3 {
+                client: http::client(),
+                base: directory_url(endpoint)?
+                    .join(&format!("{}/", bucket.trim()))
+                    .map_err(|e| AppError::validation(format!("Invalid S3 bucket '{}': {}", bucket, e)))?,
+                region: region.trim().to_string(),
+                access_key_id: access_key_id.trim().to_string(),
+                secret_key: secret(S3_SECRET_KEY_SECRET).await?,
+            },
+        })
+    }
+
+    /// The file at `path`, or None if it doesn't exist
+    pub async fn get(&self, path: &str) -> Result<Option<Vec<u8>>, AppError> {
+        match self {
+            Remote::Folder(root) => match fs::read(root.join(path)) {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(AppError::internal(format!("Failed to read {} from sync folder: {}", path, e))),
+            },
+            _ => {
+                let response = self.send(Method::GET, path, &[], Vec::new()).await?;
+                if response.status() == StatusCode::NOT_FOUND {
+                    return Ok(None);
+                }
+                let response = check(response, path).await?;
+                let bytes = response
+                    .bytes()
+                    .await
+                    .map_err(|e| AppError::network(format!("Failed to download {}: {}", path, e)))?;
+                Ok(Some(bytes.to_vec()))
+            }
+        }
+    }
+
+    /// Write the file at `path`, creating parent folders as needed
+    pub async fn put(&self, path: &str, bytes: Vec<u8>) -> Result<(), AppError> {
+        match self {
+            Remote::Folder(root) => {
+                let target = root.join(path);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| AppError::internal(format!("Failed to create sync folder: {}", e)))?;
+                }
+                // Write then rename so a file syncing tool never uploads half a file
+                let tmp = target.with_extension("part");
+                fs::write(&tmp, bytes).map_err(|e| AppError::internal(format!("Failed to write {}: {}", path, e)))?;
+                fs::rename(&tmp, &target).map_err(|e| AppError::internal(format!("Failed to write {}: {}", path, e)))
+            }
+            Remote::Webdav { .. } => {
+                let response = self.send(Method::PUT, path, &[], bytes.clone()).await?;
+                // 409 means the parent collection doesn't exist yet
+                let response = if response.status() == StatusCode::CONFLICT {
+                    if let Some((parent, _)) = path.rsplit_once('/') {
+                        let mkcol = Method::from_bytes(b"MKCOL").expect("MKCOL is a valid method");
+                        self.send(mkcol, &format!("{}/", parent), &[], Vec::new()).await?;
+                    }
+                    self.send(Method::PUT, path, &[], bytes).await?
+                } else {
+                    response
+                };
+                check(response, path).await.map(|_| ())
+            }
+            Remote::S3 { .. } => {
+                let response = self.send(Method::PUT, path, &[], bytes).await?;
+                check(response, path).await.map(|_| ())
+            }
+        }
+    }
+
+    /// Names of the files directly inside `dir`; a missing folder is empty
+    pub async fn list(&self, dir: &str) -> Result<Vec<String>, AppError> {
+        match self {
+            Remote::Folder(root) => {
+                let entries = match fs::read_dir(root.join(dir)) {
+                    Ok(entries) => entries,
+                    Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+                    Err(e) => return Err(AppError::internal(format!("Failed to list sync folder: {}", e))),
+                };
+                Ok(entries
+                    .filter_map(Result::ok)
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .collect())
+            }
+            Remote::Webdav { .. } => {
+                let propfind = Method::from_bytes(b"PROPFIND").expect("PROPFIND is a valid method");
+                let response = self.send(propfind, &format!("{}/", dir), &[("Depth", "1")], Vec::new()).await?;
+                if response.status() == StatusCode::NOT_FOUND {
+                    return Ok(Vec::new());
+                }
+                let body = text(check(response, dir).await?, dir).await?;
+                Ok(xml_values(&body, "href")
+                    .iter()
+                    .filter(|href| !href.ends_with('/'))
+                    .filter_map(|href| href.rsplit('/').next())
+                    .map(str::to_string)
+                    .collect())
+            }
+            Remote::S3 { .. } => {
+                let prefix = format!("{}/", dir);
+                let mut names = Vec::new();
+                let mut token: Option<String> = None;
+                loop {
+                    let mut query = vec![("list-type", "2"), ("prefix", prefix.as_str())];
+                    if let Some(token) = &token {
+                        query.push(("continuation-token", token.as_str()));
+                    }
+                    let response = self.send_query(Method::GET, "", &query).await?;
+                    let body = text(check(response, dir).await?, dir).await?;
+                    names.extend(
+                        xml_values(&body, "Key")
+                            .iter()
+                            .filter_map(|key| key.strip_prefix(&prefix))
+                            .filter(|name| !name.contains('/'))
+                            .map(str::to_string),
+                    );
+                    token = xml_values(&body, "NextContinuationToken").into_iter().next();
+                    if token.is_none() {
+                        break;
+                    }
+                }
+                Ok(names)
+            }
+        }
+    }
+
+    async fn send(&self, method: Method, path: &str, headers: &[(&str, &str)], body: Vec<u8>) -> Result<reqwest::Response, AppError> {
+        let request = match self {
+            Remote::Folder(_) => unreachable!("folder backends don't make requests"),
+            Remote::Webdav {
+                client,
+                base,
+                username,
+                password,
+            } => {
+                let url = base.join(path).map_err(|e| AppError::internal(format!("Invalid sync path '{}': {}", path, e)))?;
+                client.request(method, url).basic_auth(username, Some(password))
+            }
+            Remote::S3 { client, base, .. } => {
+                let url = base.join(path).map_err(|e| AppError::internal(format!("Invalid sync path '{}': {}", path, e)))?;
+                let signed = self.sign(method.as_str(), &url, &body);
+                signed.into_iter().fold(client.request(method, url), |request, (name, value)| request.header(name, value))
+            }
+        };
+        headers
+            .iter()
+            .fold(request, |request, (name, value)| request.header(*name, *value))
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to reach sync backend: {}", e)))
+    }
+
+    /// An S3 request with a query string, e.g. ListObjectsV2
+    async fn send_query(&self, method: Method, path: &str, query: &[(&str, &str)]) -> Result<reqwest::Response, AppError> {
+        let Remote::S3 { client, base, .. } = self else {
+            unreachable!("only S3 requests carry a query");
+        };
+        let mut url = base.join(path).map_err(|e| AppError::internal(format!("Invalid sync path '{}': {}", path, e)))?;
+        url.query_pairs_mut().extend_pairs(query);
+        let signed = self.sign(method.as_str(), &url, &[]);
+        signed
+            .into_iter()
+            .fold(client.request(method, url), |request, (name, value)| request.header(name, value))
+            .send()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to reach sync backend: {}", e)))
+    }
+
+    /// AWS Signature Version 4 headers for an S3 request
+    fn sign(&self, method: &str, url: &Url, body: &[u8]) -> Vec<(&'static str, String)> {
+        let Remote::S3 {
+            region,
+            access_key_id,
+            secret_key,
+            ..
+        } = self
+        else {
+            return Vec::new();
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let (date, timestamp) = amz_date(now);
+        let payload_hash = hex(&Sha256::digest(body));
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let mut query: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(key, value)| (aws_encode(&key), aws_encode(&value)))
+            .collect();
+        query.sort();
+        let query = query.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join("&");
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method,
+            url.path(),
+            query,
+            host,
+            payload_hash,
+            timestamp,
+            signed_headers,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = [date.as_str(), region.as_str(), "s3", "aws4_request"]
+            .iter()
+            .fold(format!("AWS4{}", secret_key).into_bytes(), |key, part| hmac_sha256(&key, part.as_bytes()));
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        vec![
+            ("x-amz-date", timestamp),
+            ("x-amz-content-sha256", payload_hash),
+            (
+                "Authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    access_key_id, scope, signed_headers, signature
+                ),
+            ),
+        ]
+    }
+}
+
+async fn check(response: reqwest::Response, path: &str) -> Result<reqwest::Response, AppError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let message = format!("Sync backend returned HTTP {} for {}", status, path);
+    Err(match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => AppError::auth(message),
+        _ => AppError::network(message),
+    })
+}
+
+async fn text(response: reqwest::Response, path: &str) -> Result<String, AppError> {
+    response
+        .text()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to read listing of {}: {}", path, e)))
+}
+
+/// Text of every `<tag>` element, whatever its namespace prefix. Enough for
+/// WebDAV multistatus and S3 listings without an XML parser.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let name = rest[..end].split_whitespace().next().unwrap_or_default();
+        rest = &rest[end + 1..];
+        if name.rsplit(':').next() == Some(tag) {
+            if let Some(close) = rest.find("</") {
+                let value = &rest[..close];
+                values.push(
+                    value
+                        .replace("&lt;", "<")
+                        .replace("&gt;", ">")
+                        .replace("&quot;", "\"")
+                        .replace("&apos;", "'")
+                        .replace("&amp;", "&"),
+                );
+            }
+        }
+    }
+    values
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters, as SigV4 requires
+fn aws_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// `YYYYMMDD` and `YYYYMMDDTHHMMSSZ` for a Unix time in seconds
+fn amz_date(secs: u64) -> (String, String) {
+    // Days since the epoch to a civil date (Howard Hinnant's algorithm)
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let seconds = secs % 86_400;
+    let timestamp = format!("{}T{:02}{:02}{:02}Z", date, seconds / 3600, seconds / 60 % 60, seconds % 60);
+    (date, timestamp)
+}
@@ -0,0 +1,193 @@
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::errors::AppError;
+
+const MAX_TAG_LEN: usize = 64;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    /// Number of clips carrying this tag
+    pub clip_count: u32,
+}
+
+/// Payload of the `tags-changed` event
+#[derive(Debug, Serialize, Clone)]
+pub struct ClipTagsChanged {
+    pub clip_id: i64,
+    pub tags: Vec<String>,
+    /// Subset of `tags` proposed by auto-tagging and not yet accepted
+    pub suggested: Vec<String>,
+}
+
+/// Trim and collapse whitespace; tags are matched case-insensitively
+pub fn normalize_tag(name: &str) -> Result<String, AppError> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        return Err(AppError::validation("Tag name must not be empty"));
+    }
+    if name.chars().count() > MAX_TAG_LEN {
+        return Err(AppError::validation(format!("Tag name must be at most {} characters", MAX_TAG_LEN)));
+    }
+    Ok(name)
+}
+
+/// Id of the tag named `name`, creating it if needed
+pub fn ensure_tag(conn: &Connection, name: &str) -> Result<i64, AppError> {
+    let name = normalize_tag(name)?;
+    conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![name])
+        .map_err(|e| AppError::database(format!("Failed to create tag: {}", e)))?;
+    conn.query_row("SELECT id FROM tags WHERE name = ?1", params![name], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to read tag: {}", e)))
+}
+
+/// Tag a clip. A user tag replaces a pending auto-tag of the same name.
+pub fn add_tag_to_clip(conn: &Connection, clip_id: i64, name: &str) -> Result<(), AppError> {
+    insert_clip_tag(conn, clip_id, name, "user")
+}
+
+/// Attach an LLM-proposed tag for the user to accept or reject. Never downgrades a user tag.
+pub fn add_auto_tag(conn: &Connection, clip_id: i64, name: &str) -> Result<(), AppError> {
+    insert_clip_tag(conn, clip_id, name, "auto")
+}
+
+fn insert_clip_tag(conn: &Connection, clip_id: i64, name: &str, source: &str) -> Result<(), AppError> {
+    let clip_exists: bool = conn
+        .query_row("SELECT EXISTS(SELECT 1 FROM clips WHERE id = ?1)", params![clip_id], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to read clip: {}", e)))?;
+    if !clip_exists {
+        return Err(AppError::not_found(format!("Clip {} not found", clip_id)));
+    }
+
+    let tag_id = ensure_tag(conn, name)?;
+    conn.execute(
+        "INSERT INTO clip_tags (clip_id, tag_id, source) VALUES (?1, ?2, ?3)
+         ON CONFLICT (clip_id, tag_id) DO UPDATE SET source = 'user' WHERE excluded.source = 'user'",
+        params![clip_id, tag_id, source],
+    )
+    .map_err(|e| AppError::database(format!("Failed to tag clip: {}", e)))?;
+    Ok(())
+}
+
+/// Keep an auto-tag, turning it into a regular user tag
+pub fn accept_auto_tag(conn: &Connection, clip_id: i64, name: &str) -> Result<(), AppError> {
+    let name = normalize_tag(name)?;
+    let changed = conn
+        .execute(
+            "UPDATE clip_tags SET source = 'user'
+             WHERE clip_id = ?1 AND source = 'auto' AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+            params![clip_id, name],
+        )
+        .map_err(|e| AppError::database(format!("Failed to accept tag: {}", e)))?;
+    if changed == 0 {
+        return Err(AppError::not_found(format!("Clip {} has no suggested tag '{}'", clip_id, name)));
+    }
+    Ok(())
+}
+
+/// Untag a clip; tags left with no clips are deleted so the tag cloud stays clean
+pub fn remove_tag_from_clip(conn: &Connection, clip_id: i64, name: &str) -> Result<(), AppError> {
+    let name = normalize_tag(name)?;
+    let tag_id: Option<i64> = conn
+        .query_row("SELECT id FROM tags WHERE name = ?1", params![name], |row| row.get(0))
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read tag: {}", e)))?;
+    let tag_id = match tag_id {
+        Some(id) => id,
+        None => return Err(AppError::not_found(format!("Tag '{}' not found", name))),
+    };
+
+    conn.execute(
+        "DELETE FROM clip_tags WHERE clip_id = ?1 AND tag_id = ?2",
+        params![clip_id, tag_id],
+    )
+    .map_err(|e| AppError::database(format!("Failed to untag clip: {}", e)))?;
+    conn.execute(
+        "DELETE FROM tags WHERE id = ?1 AND NOT EXISTS (SELECT 1 FROM clip_tags WHERE tag_id = ?1)",
+        params![tag_id],
+    )
+    .map_err(|e| AppError::database(format!("Failed to clean up tag: {}", e)))?;
+    Ok(())
+}
+
+/// Untag several clips at once. Unlike `remove_tag_from_clip`, a tag that doesn't
+/// exist is nothing to remove rather than an error.
+pub fn remove_tag_from_clips(conn: &Connection, clip_ids: &[i64], name: &str) -> Result<(), AppError> {
+    let name = normalize_tag(name)?;
+    let tag_id: Option<i64> = conn
+        .query_row("SELECT id FROM tags WHERE name = ?1", params![name], |row| row.get(0))
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read tag: {}", e)))?;
+    let Some(tag_id) = tag_id else {
+        return Ok(());
+    };
+    if clip_ids.is_empty() {
+        return Ok(());
+    }
+
+    let sql = format!(
+        "DELETE FROM clip_tags WHERE tag_id = ? AND clip_id IN ({})",
+        vec!["?"; clip_ids.len()].join(", ")
+    );
+    conn.execute(&sql, params_from_iter(std::iter::once(tag_id).chain(clip_ids.iter().copied())))
+        .map_err(|e| AppError::database(format!("Failed to untag clips: {}", e)))?;
+    conn.execute(
+        "DELETE FROM tags WHERE id = ?1 AND NOT EXISTS (SELECT 1 FROM clip_tags WHERE tag_id = ?1)",
+        params![tag_id],
+    )
+    .map_err(|e| AppError::database(format!("Failed to clean up tag: {}", e)))?;
+    Ok(())
+}
+
+/// All tags with their clip counts, alphabetically
+pub fn list_tags(conn: &Connection) -> Result<Vec<Tag>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, COUNT(c.id)
+             FROM tags t
+             LEFT JOIN clip_tags ct ON ct.tag_id = t.id
+             LEFT JOIN clips c ON c.id = ct.clip_id AND c.deleted_at IS NULL
+             GROUP BY t.id
+             ORDER BY t.name COLLATE NOCASE",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let tags = stmt
+        .query_map([], |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                clip_count: row.get(2)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to list tags: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read tag: {}", e)))?;
+    Ok(tags)
+}
+
+pub fn tags_for_clip(conn: &Connection, clip_id: i64) -> Result<Vec<String>, AppError> {
+    clip_tag_names(conn, clip_id, false)
+}
+
+/// Auto-tags on the clip that the user hasn't accepted yet
+pub fn suggested_tags_for_clip(conn: &Connection, clip_id: i64) -> Result<Vec<String>, AppError> {
+    clip_tag_names(conn, clip_id, true)
+}
+
+fn clip_tag_names(conn: &Connection, clip_id: i64, only_suggested: bool) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.name FROM clip_tags ct JOIN tags t ON t.id = ct.tag_id
+             WHERE ct.clip_id = ?1 AND (?2 = 0 OR ct.source = 'auto')
+             ORDER BY t.name COLLATE NOCASE",
+        )
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let names = stmt
+        .query_map(params![clip_id, only_suggested], |row| row.get(0))
+        .map_err(|e| AppError::database(format!("Failed to read clip tags: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read clip tags: {}", e)))?;
+    Ok(names)
+}
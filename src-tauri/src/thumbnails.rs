@@ -0,0 +1,84 @@
+use image::{GenericImageView, ImageOutputFormat};
+use serde::Serialize;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::config::AppConfig;
+use crate::errors::AppError;
+use crate::media;
+
+/// Longest edge of a thumbnail when the caller doesn't ask for a size
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 320;
+/// Requested sizes are rounded up to one of these so the cache stays small
+const THUMBNAIL_SIZES: &[u32] = &[160, 320, 640];
+/// Subfolder of the media dir holding generated thumbnails
+const THUMBNAILS_DIR: &str = "thumbnails";
+
+/// A cached thumbnail of a clip's image
+#[derive(Debug, Serialize)]
+pub struct ClipThumbnail {
+    pub clip_id: i64,
+    /// Absolute path of the WebP file, for `convertFileSrc` in the UI
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Scale the image at `source` to fit in `size` x `size` and write it to `dest` as WebP.
+/// Smaller images are only re-encoded, never scaled up.
+fn generate(source: &Path, dest: &Path, size: u32) -> Result<(u32, u32), AppError> {
+    let bytes = fs::read(source).map_err(|e| AppError::internal(format!("Failed to read image: {}", e)))?;
+    let image = image::load_from_memory(&bytes).map_err(|e| AppError::validation(format!("Failed to decode image: {}", e)))?;
+    let (width, height) = image.dimensions();
+    let image = if width.max(height) > size { image.thumbnail(size, size) } else { image };
+    // The WebP encoder takes 8-bit RGB(A) only
+    let image = if image.color().has_alpha() {
+        image::DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        image::DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+
+    let mut encoded = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut encoded), ImageOutputFormat::WebP)
+        .map_err(|e| AppError::internal(format!("Failed to encode thumbnail: {}", e)))?;
+    // Write to a temp file and rename so a half-written thumbnail is never served
+    let tmp = dest.with_extension("part");
+    fs::write(&tmp, encoded).map_err(|e| AppError::internal(format!("Failed to write thumbnail: {}", e)))?;
+    fs::rename(&tmp, dest).map_err(|e| AppError::internal(format!("Failed to write thumbnail: {}", e)))?;
+    Ok(image.dimensions())
+}
+
+/// A thumbnail of the clip's image no larger than `size` pixels on its longest edge,
+/// generated on first request and cached by image hash and size. The full image is
+/// downloaded first if it isn't stored yet.
+pub async fn get_clip_thumbnail(app_handle: &AppHandle, clip_id: i64, size: Option<u32>) -> Result<ClipThumbnail, AppError> {
+    let requested = size.unwrap_or(DEFAULT_THUMBNAIL_SIZE);
+    let size = THUMBNAIL_SIZES
+        .iter()
+        .copied()
+        .find(|size| *size >= requested)
+        .unwrap_or(THUMBNAIL_SIZES[THUMBNAIL_SIZES.len() - 1]);
+
+    let image = media::get_clip_image(app_handle, clip_id).await?;
+    let dir = app_handle.state::<AppConfig>().media_dir().join(THUMBNAILS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| AppError::internal(format!("Failed to create thumbnails folder: {}", e)))?;
+    let path = dir.join(format!("{}-{}.webp", image.hash, size));
+
+    let (width, height) = if path.is_file() {
+        image::image_dimensions(&path).map_err(|e| AppError::internal(format!("Failed to read thumbnail: {}", e)))?
+    } else {
+        let dest = path.clone();
+        tauri::async_runtime::spawn_blocking(move || generate(&image.path, &dest, size))
+            .await
+            .map_err(|e| AppError::internal(format!("Thumbnail generation failed: {}", e)))??
+    };
+    Ok(ClipThumbnail {
+        clip_id,
+        path,
+        width,
+        height,
+    })
+}
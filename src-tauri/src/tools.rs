@@ -0,0 +1,276 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+use crate::clips;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::extract;
+use crate::notifications;
+use crate::providers::{
+    call_llm_with_tools_api, LlmError, LlmMessage, LlmRequest, LlmUsage, ProviderRegistry, ToolDefinition, ToolRound,
+    ToolStep,
+};
+use crate::redact;
+use crate::search;
+use crate::secrets::SecretsManager;
+use crate::settings::SettingsManager;
+use crate::usage;
+
+/// Rounds of tool calls allowed before giving up on a final answer
+const MAX_TOOL_ROUNDS: usize = 8;
+/// Tool output beyond this is cut before it goes back to the model
+const MAX_RESULT_CHARS: usize = 8_000;
+const DEFAULT_SEARCH_RESULTS: u64 = 5;
+const MAX_SEARCH_RESULTS: u64 = 20;
+
+/// Functions the model can call during `call_with_tools`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tool {
+    SearchWeb,
+    SearchClips,
+    FetchUrlContent,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 3] = [Tool::SearchWeb, Tool::SearchClips, Tool::FetchUrlContent];
+
+    /// Same as the serialized name; also the function name the model sees
+    pub fn id(self) -> &'static str {
+        match self {
+            Tool::SearchWeb => "search_web",
+            Tool::SearchClips => "search_clips",
+            Tool::FetchUrlContent => "fetch_url_content",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Tool> {
+        Tool::ALL.into_iter().find(|tool| tool.id() == id)
+    }
+
+    pub fn definition(self) -> ToolDefinition {
+        let (description, parameters) = match self {
+            Tool::SearchWeb => (
+                "Search the web. Returns titles, URLs and descriptions of matching pages.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "Search terms" },
+                        "num_results": { "type": "integer", "description": "How many results to return (default 5)" }
+                    },
+                    "required": ["query"]
+                }),
+            ),
+            Tool::SearchClips => (
+                "Full-text search over the user's saved clips. Returns clip ids, titles, URLs and matching snippets.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "Words to look for" },
+                        "limit": { "type": "integer", "description": "How many clips to return (default 5)" }
+                    },
+                    "required": ["query"]
+                }),
+            ),
+            Tool::FetchUrlContent => (
+                "Download a web page and return its title and readable text.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "url": { "type": "string", "description": "Absolute http(s) URL" }
+                    },
+                    "required": ["url"]
+                }),
+            ),
+        };
+        ToolDefinition {
+            name: self.id().to_string(),
+            description: description.to_string(),
+            parameters,
+        }
+    }
+
+    /// Run the tool and return its output as JSON text for the model
+    async fn execute(self, app_handle: &AppHandle, arguments: &Value) -> Result<String, AppError> {
+        let output = match self {
+            Tool::SearchWeb => {
+                let query = string_argument(arguments, "query")?;
+                let num_results = count_argument(arguments, "num_results");
+                let settings = app_handle.state::<SettingsManager>().get();
+                let secrets_manager = app_handle.state::<SecretsManager>();
+                let db = app_handle.state::<Database>();
+                let providers = search::configured_providers(&secrets_manager, &settings).await?;
+                let response = search::search_all(&db, &secrets_manager, &settings, &providers, query, num_results).await?;
+                let results: Vec<Value> = response
+                    .results
+                    .iter()
+                    .map(|result| json!({ "title": result.title, "url": result.url, "description": result.description }))
+                    .collect();
+                json!(results)
+            }
+            Tool::SearchClips => {
+                let query = string_argument(arguments, "query")?;
+                let limit = count_argument(arguments, "limit");
+                let db = app_handle.state::<Database>();
+                let hits = clips::search_clips(&db.conn()?, query, limit)?;
+                let results: Vec<Value> = hits
+                    .iter()
+                    .map(|hit| json!({ "id": hit.clip.id, "title": hit.clip.title, "url": hit.clip.url, "snippet": hit.snippet }))
+                    .collect();
+                json!(results)
+            }
+            Tool::FetchUrlContent => {
+                let url = string_argument(arguments, "url")?;
+                let article = extract::fetch_article(url).await?;
+                json!({ "url": article.url, "title": article.title, "content": article.content })
+            }
+        };
+        Ok(truncate(output.to_string()))
+    }
+}
+
+fn string_argument<'a>(arguments: &'a Value, name: &str) -> Result<&'a str, AppError> {
+    arguments[name]
+        .as_str()
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| AppError::validation(format!("Missing \"{}\" argument", name)))
+}
+
+fn count_argument(arguments: &Value, name: &str) -> u32 {
+    arguments[name]
+        .as_u64()
+        .unwrap_or(DEFAULT_SEARCH_RESULTS)
+        .clamp(1, MAX_SEARCH_RESULTS) as u32
+}
+
+fn truncate(mut text: String) -> String {
+    if let Some((cut, _)) = text.char_indices().nth(MAX_RESULT_CHARS) {
+        text.truncate(cut);
+        text.push_str("… [truncated]");
+    }
+    text
+}
+
+/// A tool call made while answering, reported to the UI
+#[derive(Debug, Serialize, Clone)]
+pub struct ToolCallRecord {
+    pub name: String,
+    pub arguments: Value,
+    pub result: String,
+}
+
+/// Final answer from `call_with_tools` and the calls that led to it
+#[derive(Debug, Serialize)]
+pub struct ToolAnswer {
+    pub content: String,
+    /// Summed over every round
+    pub usage: Option<LlmUsage>,
+    pub tool_calls: Vec<ToolCallRecord>,
+}
+
+fn add_usage(total: &mut Option<LlmUsage>, usage: Option<LlmUsage>) {
+    if let Some(usage) = usage {
+        let total = total.get_or_insert(LlmUsage {
+            input_tokens: 0,
+            output_tokens: 0,
+            total_tokens: 0,
+        });
+        total.input_tokens += usage.input_tokens;
+        total.output_tokens += usage.output_tokens;
+        total.total_tokens += usage.total_tokens;
+    }
+}
+
+/// Ask the model with `tools` available, running whatever it calls and sending the
+/// results back until it answers. Each call is emitted as `llm-tool-call`; a failing
+/// tool is reported to the model as an error rather than ending the run.
+pub async fn call_with_tools(
+    app_handle: &AppHandle,
+    provider: &str,
+    model: &str,
+    messages: Vec<LlmMessage>,
+    tools: &[Tool],
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+) -> Result<ToolAnswer, LlmError> {
+    let settings = app_handle.state::<SettingsManager>().get();
+    let registry = ProviderRegistry::from_settings(&settings);
+    let price = usage::price_for(provider, model, &settings.model_prices);
+    let secrets_manager = app_handle.state::<SecretsManager>();
+    let definitions: Vec<ToolDefinition> = tools.iter().map(|tool| tool.definition()).collect();
+
+    let mut rounds: Vec<ToolRound> = Vec::new();
+    let mut records = Vec::new();
+    let mut total_usage = None;
+    loop {
+        let request = LlmRequest {
+            model: model.to_string(),
+            messages: messages.clone(),
+            max_tokens,
+            temperature,
+            safety_settings: None,
+            images: Vec::new(),
+        };
+        let step = call_llm_with_tools_api(
+            &secrets_manager,
+            &registry,
+            provider,
+            request,
+            &definitions,
+            &rounds,
+            "call_llm_with_tools",
+        )
+        .await
+        .inspect_err(|e| notifications::notify_llm_error(app_handle, provider, e))?;
+
+        let (text, calls, step_usage) = match step {
+            ToolStep::Answer(response) => {
+                if let Some(llm_usage) = &response.usage {
+                    let db = app_handle.state::<Database>();
+                    usage::record_usage(&db.conn()?, provider, model, llm_usage, price)?;
+                }
+                add_usage(&mut total_usage, response.usage);
+                return Ok(ToolAnswer {
+                    content: response.content,
+                    usage: total_usage,
+                    tool_calls: records,
+                });
+            }
+            ToolStep::Calls { text, calls, usage } => (text, calls, usage),
+        };
+        if let Some(llm_usage) = &step_usage {
+            let db = app_handle.state::<Database>();
+            usage::record_usage(&db.conn()?, provider, model, llm_usage, price)?;
+        }
+        add_usage(&mut total_usage, step_usage);
+        if rounds.len() >= MAX_TOOL_ROUNDS {
+            return Err(format!("No answer after {} rounds of tool calls", MAX_TOOL_ROUNDS).into());
+        }
+
+        let mut results = Vec::with_capacity(calls.len());
+        for call in &calls {
+            // Only tools offered for this call may run
+            let result = match Tool::from_id(&call.name).filter(|tool| tools.contains(tool)) {
+                Some(tool) => tool.execute(app_handle, &call.arguments).await,
+                None => Err(AppError::validation(format!("Unknown tool \"{}\"", call.name))),
+            };
+            let result = result.unwrap_or_else(|e| {
+                warn!("Tool {} failed: {}", call.name, e);
+                json!({ "error": e.to_string() }).to_string()
+            });
+            let record = ToolCallRecord {
+                name: call.name.clone(),
+                arguments: call.arguments.clone(),
+                result: result.clone(),
+            };
+            if let Err(e) = redact::emit(app_handle, "llm-tool-call", &record) {
+                warn!("Failed to emit tool call event: {}", e);
+            }
+            records.push(record);
+            results.push(result);
+        }
+        rounds.push(ToolRound { text, calls, results });
+    }
+}
@@ -0,0 +1,53 @@
+use std::fs;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{error, info};
+
+use crate::clips::{self, now_millis};
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::settings::SettingsManager;
+
+/// Days a deleted clip stays in the trash when the user hasn't chosen a window
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+/// How often the scheduler purges clips past the retention window
+const PURGE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Permanently delete clips in the trash, only those deleted before `before` (milliseconds
+/// since the epoch) if given. Tags, embeddings, annotations and revisions go with them
+/// through their foreign keys; archive snapshots are removed here. Returns the number of
+/// clips purged.
+pub fn empty_trash(app_handle: &AppHandle, before: Option<i64>) -> Result<usize, AppError> {
+    let archives = {
+        let db = app_handle.state::<Database>();
+        clips::purge_trash(&db.conn()?, before)?
+    };
+    let purged = archives.len();
+    // Media files are shared by content hash, so only the per-clip archives are removed
+    let archives_dir = app_handle.state::<AppConfig>().archives_dir();
+    for file_name in archives.into_iter().flatten() {
+        if let Err(e) = fs::remove_file(archives_dir.join(&file_name)) {
+            error!("Failed to remove archive {}: {}", file_name, e);
+        }
+    }
+    Ok(purged)
+}
+
+/// Start the background task that purges clips deleted more than `trash_retention_days`
+/// ago. Settings are re-read on every check; a window of 0 keeps the trash until it's
+/// emptied by hand.
+pub fn start_purge_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Some(retention) = app_handle.state::<SettingsManager>().get().trash_retention() {
+                match empty_trash(&app_handle, Some(now_millis() - retention.as_millis() as i64)) {
+                    Ok(0) => {}
+                    Ok(purged) => info!("Purged {} clips from the trash", purged),
+                    Err(e) => error!("Failed to purge trash: {}", e),
+                }
+            }
+            tokio::time::sleep(PURGE_CHECK_INTERVAL).await;
+        }
+    });
+}
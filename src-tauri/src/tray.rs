@@ -0,0 +1,85 @@
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{TrayIcon, TrayIconBuilder};
+use tauri::{AppHandle, Manager, Wry};
+use tracing::{error, warn};
+
+use crate::clipboard::ClipboardMonitor;
+use crate::errors::AppError;
+use crate::watcher::ClipWatcher;
+
+/// The tray icon and the menu item whose label follows the paused state, managed as Tauri state
+pub struct Tray {
+    icon: TrayIcon<Wry>,
+    pause_item: MenuItem<Wry>,
+}
+
+impl Tray {
+    /// Build the tray icon. Menu actions go through the same functions as the
+    /// matching commands so they behave exactly like the UI.
+    pub fn build(app_handle: &AppHandle) -> Result<Self, AppError> {
+        let menu_error = |e: tauri::Error| AppError::internal(format!("Failed to build tray menu: {}", e));
+        let new_note = MenuItem::with_id(app_handle, "new_note", "New note clip", true, None::<&str>).map_err(menu_error)?;
+        let pause_item =
+            MenuItem::with_id(app_handle, "pause", "Pause clipping", true, None::<&str>).map_err(menu_error)?;
+        let open = MenuItem::with_id(app_handle, "open", "Open LOS", true, None::<&str>).map_err(menu_error)?;
+        let quit = MenuItem::with_id(app_handle, "quit", "Quit", true, None::<&str>).map_err(menu_error)?;
+        let separator = PredefinedMenuItem::separator(app_handle).map_err(menu_error)?;
+        let menu = Menu::with_items(app_handle, &[&new_note, &pause_item, &separator, &open, &quit]).map_err(menu_error)?;
+
+        let mut builder = TrayIconBuilder::with_id("main")
+            .menu(&menu)
+            .tooltip("LOS")
+            .on_menu_event(|app_handle, event| match event.id().as_ref() {
+                // Same as the capture shortcut: the clipboard becomes a note clip
+                "new_note" => crate::hotkey::capture_clipboard(app_handle),
+                "pause" => {
+                    if let Err(e) = crate::pause_clipping(app_handle, !is_paused(app_handle)) {
+                        error!("{}", e);
+                    }
+                }
+                "open" => show_main_window(app_handle),
+                "quit" => app_handle.exit(0),
+                _ => {}
+            });
+        if let Some(icon) = app_handle.default_window_icon() {
+            builder = builder.icon(icon.clone());
+        }
+        let icon = builder
+            .build(app_handle)
+            .map_err(|e| AppError::internal(format!("Failed to create tray icon: {}", e)))?;
+
+        let tray = Self { icon, pause_item };
+        tray.refresh(app_handle);
+        Ok(tray)
+    }
+
+    /// Update the pause label and tooltip from the watchers' current state
+    pub fn refresh(&self, app_handle: &AppHandle) {
+        let paused = is_paused(app_handle);
+        let clipboard = app_handle.state::<ClipboardMonitor>().is_enabled();
+        let (label, tooltip) = match (paused, clipboard) {
+            (true, _) => ("Resume clipping", "LOS: clipping paused"),
+            (false, true) => ("Pause clipping", "LOS: watching drop folder and clipboard"),
+            (false, false) => ("Pause clipping", "LOS: watching drop folder"),
+        };
+        if let Err(e) = self.pause_item.set_text(label) {
+            warn!("Failed to update tray menu: {}", e);
+        }
+        if let Err(e) = self.icon.set_tooltip(Some(tooltip)) {
+            warn!("Failed to update tray tooltip: {}", e);
+        }
+    }
+}
+
+/// Clipping is paused when the drop folder watcher is stopped
+pub fn is_paused(app_handle: &AppHandle) -> bool {
+    !app_handle.state::<ClipWatcher>().is_running()
+}
+
+pub(crate) fn show_main_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
@@ -0,0 +1,150 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::clips::now_millis;
+use crate::errors::AppError;
+use crate::providers::LlmUsage;
+
+/// USD per million tokens
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// Built-in prices, matched by model-name prefix. User overrides in the settings win.
+const DEFAULT_PRICES: &[(&str, f64, f64)] = &[
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-opus", 15.0, 75.0),
+    ("claude-opus-4", 15.0, 75.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gemini-1.5-flash", 0.075, 0.3),
+    ("gemini-1.5-pro", 1.25, 5.0),
+    ("gemini-2.0-flash", 0.1, 0.4),
+];
+
+/// Price for `model`, preferring the longest matching prefix. Local models are free;
+/// unknown models return None so their calls show up as unpriced rather than $0.
+pub fn price_for(provider: &str, model: &str, overrides: &HashMap<String, ModelPrice>) -> Option<ModelPrice> {
+    if provider == "ollama" {
+        return Some(ModelPrice {
+            input_per_million: 0.0,
+            output_per_million: 0.0,
+        });
+    }
+
+    let user = overrides
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, price)| *price);
+    user.or_else(|| {
+        DEFAULT_PRICES
+            .iter()
+            .filter(|(prefix, _, _)| model.starts_with(prefix))
+            .max_by_key(|(prefix, _, _)| prefix.len())
+            .map(|(_, input, output)| ModelPrice {
+                input_per_million: *input,
+                output_per_million: *output,
+            })
+    })
+}
+
+/// Record one LLM call. Returns the computed cost, if the model has a price.
+pub fn record_usage(
+    conn: &Connection,
+    provider: &str,
+    model: &str,
+    usage: &LlmUsage,
+    price: Option<ModelPrice>,
+) -> Result<Option<f64>, AppError> {
+    let cost = price.map(|price| {
+        (usage.input_tokens as f64 * price.input_per_million + usage.output_tokens as f64 * price.output_per_million)
+            / 1_000_000.0
+    });
+    conn.execute(
+        "INSERT INTO usage (provider, model, input_tokens, output_tokens, cost, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![provider, model, usage.input_tokens, usage.output_tokens, cost, now_millis()],
+    )
+    .map_err(|e| AppError::database(format!("Failed to record usage: {}", e)))?;
+    Ok(cost)
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum UsagePeriod {
+    Daily,
+    Weekly,
+}
+
+/// Totals for one day or week (local time)
+#[derive(Debug, Serialize)]
+pub struct UsageBucket {
+    /// First day of the bucket, `YYYY-MM-DD`
+    pub start: String,
+    pub calls: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost: f64,
+    /// Calls to models with no known price, not included in `cost`
+    pub unpriced_calls: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageSummary {
+    pub buckets: Vec<UsageBucket>,
+    pub total_calls: u32,
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
+    pub total_cost: f64,
+}
+
+/// Usage over the last `days` days, grouped by day or by week (weeks start on Monday)
+pub fn usage_summary(conn: &Connection, period: UsagePeriod, days: u32) -> Result<UsageSummary, AppError> {
+    let bucket = match period {
+        UsagePeriod::Daily => "date(created_at / 1000, 'unixepoch', 'localtime')",
+        UsagePeriod::Weekly => "date(created_at / 1000, 'unixepoch', 'localtime', 'weekday 0', '-6 days')",
+    };
+    let since = now_millis() - days as i64 * 24 * 60 * 60 * 1000;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} AS bucket, COUNT(*), SUM(input_tokens), SUM(output_tokens),
+                    COALESCE(SUM(cost), 0), SUM(cost IS NULL)
+             FROM usage WHERE created_at >= ?1
+             GROUP BY bucket ORDER BY bucket",
+            bucket
+        ))
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let buckets = stmt
+        .query_map(params![since], |row| {
+            Ok(UsageBucket {
+                start: row.get(0)?,
+                calls: row.get(1)?,
+                input_tokens: row.get::<_, i64>(2)? as u64,
+                output_tokens: row.get::<_, i64>(3)? as u64,
+                cost: row.get(4)?,
+                unpriced_calls: row.get(5)?,
+            })
+        })
+        .map_err(|e| AppError::database(format!("Failed to summarize usage: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read usage: {}", e)))?;
+
+    Ok(UsageSummary {
+        total_calls: buckets.iter().map(|b| b.calls).sum(),
+        total_input_tokens: buckets.iter().map(|b| b.input_tokens).sum(),
+        total_output_tokens: buckets.iter().map(|b| b.output_tokens).sum(),
+        total_cost: buckets.iter().map(|b| b.cost).sum(),
+        buckets,
+    })
+}
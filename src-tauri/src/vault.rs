@@ -0,0 +1,258 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{error, info};
+
+use crate::annotations;
+use crate::clips;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::export::markdown_note;
+use crate::settings::SettingsManager;
+use crate::tags;
+
+/// How often the mirror writes notes for changed clips
+const MIRROR_INTERVAL: Duration = Duration::from_secs(15);
+/// Front-matter field holding the clip id; it identifies a note after the user renames it
+const ID_KEY: &str = "los_id";
+/// Longest title used in a note's file name
+const MAX_FILE_NAME_LEN: usize = 100;
+
+/// What a mirror pass changed in the vault folder
+#[derive(Debug, Serialize, Default)]
+pub struct VaultMirrorSummary {
+    pub written: usize,
+    pub renamed: usize,
+    pub removed: usize,
+}
+
+/// Serializes mirror passes and remembers the folder last mirrored to, managed as
+/// Tauri state
+#[derive(Default)]
+pub struct VaultMirror {
+    mirrored_dir: Mutex<Option<PathBuf>>,
+}
+
+/// `title` as a note file name: characters Obsidian or the file system reject become
+/// spaces
+fn note_file_name(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| if c.is_control() || "\\/:*?\"<>|#^[]".contains(c) { ' ' } else { c })
+        .collect();
+    let mut name = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.chars().count() > MAX_FILE_NAME_LEN {
+        name = name.chars().take(MAX_FILE_NAME_LEN).collect::<String>().trim_end().to_string();
+    }
+    // A leading dot would hide the note
+    let name = name.trim_start_matches('.').to_string();
+    if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name
+    }
+}
+
+/// The clip id in a note's front-matter, if it's one of ours
+fn note_clip_id(path: &Path) -> Option<i64> {
+    let text = fs::read_to_string(path).ok()?;
+    let front_matter = text.strip_prefix("---\n")?.split("\n---").next()?;
+    front_matter
+        .lines()
+        .find_map(|line| line.strip_prefix(ID_KEY)?.strip_prefix(':'))
+        .and_then(|id| id.trim().parse().ok())
+}
+
+/// Clip id to file name for every note in `dir` with our front-matter
+fn scan_notes(dir: &Path) -> Result<HashMap<i64, String>, AppError> {
+    let entries = fs::read_dir(dir).map_err(|e| AppError::internal(format!("Failed to read vault folder: {}", e)))?;
+    let mut notes = HashMap::new();
+    for entry in entries.filter_map(Result::ok) {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if name.ends_with(".md") {
+            if let Some(clip_id) = note_clip_id(&entry.path()) {
+                notes.insert(clip_id, name);
+            }
+        }
+    }
+    Ok(notes)
+}
+
+/// `base.md`, or `base (id).md` when another clip's note already has that name
+fn available_name(conn: &Connection, dir: &Path, clip_id: i64, base: &str, current: Option<&str>) -> Result<String, AppError> {
+    let name = format!("{}.md", base);
+    let owner: Option<i64> = conn
+        .query_row("SELECT clip_id FROM vault_notes WHERE file_name = ?1", params![name], |row| row.get(0))
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read vault notes: {}", e)))?;
+    let taken = match owner {
+        Some(owner) => owner != clip_id,
+        None => current != Some(name.as_str()) && dir.join(&name).exists(),
+    };
+    Ok(if taken { format!("{} ({}).md", base, clip_id) } else { name })
+}
+
+/// Bring one clip's note up to date: write it, rename it after a title change, or
+/// remove it once the clip is deleted. `index` is filled by a folder scan the first
+/// time a note isn't where it was last written.
+fn mirror_clip(
+    conn: &Connection,
+    dir: &Path,
+    clip_id: i64,
+    index: &mut Option<HashMap<i64, String>>,
+    summary: &mut VaultMirrorSummary,
+) -> Result<(), AppError> {
+    let recorded: Option<String> = conn
+        .query_row("SELECT file_name FROM vault_notes WHERE clip_id = ?1", params![clip_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| AppError::database(format!("Failed to read vault notes: {}", e)))?;
+    let current = match recorded.as_ref().filter(|name| dir.join(name).is_file()) {
+        Some(name) => Some(name.clone()),
+        None => {
+            if index.is_none() {
+                *index = Some(scan_notes(dir)?);
+            }
+            index.as_ref().and_then(|notes| notes.get(&clip_id).cloned())
+        }
+    };
+
+    let Some(clip) = clips::get_clip(conn, clip_id)?.filter(|clip| clip.deleted_at.is_none()) else {
+        if let Some(name) = current {
+            fs::remove_file(dir.join(&name)).map_err(|e| AppError::internal(format!("Failed to remove {}: {}", name, e)))?;
+            summary.removed += 1;
+        }
+        conn.execute("DELETE FROM vault_notes WHERE clip_id = ?1", params![clip_id])
+            .map_err(|e| AppError::database(format!("Failed to update vault notes: {}", e)))?;
+        return Ok(());
+    };
+
+    // Obsidian tags can't contain spaces
+    let tags: Vec<String> = tags::tags_for_clip(conn, clip_id)?
+        .iter()
+        .map(|tag| tag.split_whitespace().collect::<Vec<_>>().join("-"))
+        .collect();
+    let annotations = annotations::list_annotations(conn, clip_id)?;
+    let note = markdown_note(&clip, &tags, &annotations, ID_KEY);
+
+    let name = match current {
+        // Renamed in the vault since it was last written; the user's name sticks
+        Some(name) if recorded.as_deref() != Some(name.as_str()) => name,
+        Some(name) => {
+            let wanted = available_name(conn, dir, clip_id, &note_file_name(&clip.title), Some(&name))?;
+            if wanted != name {
+                fs::rename(dir.join(&name), dir.join(&wanted))
+                    .map_err(|e| AppError::internal(format!("Failed to rename {}: {}", name, e)))?;
+                summary.renamed += 1;
+            }
+            wanted
+        }
+        None => available_name(conn, dir, clip_id, &note_file_name(&clip.title), None)?,
+    };
+
+    let path = dir.join(&name);
+    if fs::read_to_string(&path).ok().as_deref() != Some(note.as_str()) {
+        fs::write(&path, note).map_err(|e| AppError::internal(format!("Failed to write {}: {}", name, e)))?;
+        summary.written += 1;
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO vault_notes (clip_id, file_name) VALUES (?1, ?2)",
+        params![clip_id, name],
+    )
+    .map_err(|e| AppError::database(format!("Failed to update vault notes: {}", e)))?;
+    Ok(())
+}
+
+/// Write notes for every clip changed since the last pass. When the vault folder
+/// changes (or on the first pass after startup) every clip is checked, so notes
+/// edited or deleted in the vault are put back.
+fn mirror_pass(app_handle: &AppHandle, dir: &Path) -> Result<VaultMirrorSummary, AppError> {
+    let mirror = app_handle.state::<VaultMirror>();
+    let mut mirrored_dir = mirror.mirrored_dir.lock().unwrap();
+    let db = app_handle.state::<Database>();
+    let conn = db.conn()?;
+
+    fs::create_dir_all(dir).map_err(|e| AppError::internal(format!("Failed to create vault folder: {}", e)))?;
+    if mirrored_dir.as_deref() != Some(dir) {
+        if mirrored_dir.is_some() {
+            conn.execute("DELETE FROM vault_notes", [])
+                .map_err(|e| AppError::database(format!("Failed to reset vault notes: {}", e)))?;
+        }
+        conn.execute("INSERT OR IGNORE INTO vault_pending (clip_id) SELECT id FROM clips", [])
+            .map_err(|e| AppError::database(format!("Failed to queue vault notes: {}", e)))?;
+        *mirrored_dir = Some(dir.to_path_buf());
+    }
+
+    let mut summary = VaultMirrorSummary::default();
+    let mut index = None;
+    let mut stmt = conn
+        .prepare("SELECT clip_id FROM vault_pending ORDER BY clip_id")
+        .map_err(|e| AppError::database(format!("Failed to prepare statement: {}", e)))?;
+    let pending = stmt
+        .query_map([], |row| row.get::<_, i64>(0))
+        .map_err(|e| AppError::database(format!("Failed to read vault queue: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::database(format!("Failed to read vault queue: {}", e)))?;
+    for clip_id in pending {
+        // Dequeue first: an edit made while the note is written queues it again
+        conn.execute("DELETE FROM vault_pending WHERE clip_id = ?1", params![clip_id])
+            .map_err(|e| AppError::database(format!("Failed to update vault queue: {}", e)))?;
+        if let Err(e) = mirror_clip(&conn, dir, clip_id, &mut index, &mut summary) {
+            conn.execute("INSERT OR IGNORE INTO vault_pending (clip_id) VALUES (?1)", params![clip_id])
+                .map_err(|e| AppError::database(format!("Failed to update vault queue: {}", e)))?;
+            return Err(e);
+        }
+    }
+    Ok(summary)
+}
+
+/// Check every clip's note against the vault now, outside the schedule
+pub async fn mirror_now(app_handle: &AppHandle) -> Result<VaultMirrorSummary, AppError> {
+    let dir = app_handle
+        .state::<SettingsManager>()
+        .get()
+        .obsidian_vault_dir
+        .ok_or_else(|| AppError::validation("Choose an Obsidian vault folder in settings first"))?;
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        {
+            let db = app_handle.state::<Database>();
+            db.conn()?
+                .execute("INSERT OR IGNORE INTO vault_pending (clip_id) SELECT id FROM clips", [])
+                .map_err(|e| AppError::database(format!("Failed to queue vault notes: {}", e)))?;
+        }
+        mirror_pass(&app_handle, &dir)
+    })
+    .await
+    .map_err(|e| AppError::internal(format!("Vault mirror failed: {}", e)))?
+}
+
+/// Start the background task that keeps the vault folder in step with the clips
+/// while `obsidian_vault_dir` is set. Settings are re-read on every pass.
+pub fn start_mirror(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Some(dir) = app_handle.state::<SettingsManager>().get().obsidian_vault_dir {
+                let handle = app_handle.clone();
+                let result = tauri::async_runtime::spawn_blocking(move || mirror_pass(&handle, &dir))
+                    .await
+                    .map_err(|e| AppError::internal(format!("Vault mirror failed: {}", e)));
+                match result.and_then(|result| result) {
+                    Ok(summary) if summary.written + summary.renamed + summary.removed > 0 => info!(
+                        "Vault mirror: {} written, {} renamed, {} removed",
+                        summary.written, summary.renamed, summary.removed
+                    ),
+                    Ok(_) => {}
+                    Err(e) => error!("Vault mirror failed: {}", e),
+                }
+            }
+            tokio::time::sleep(MIRROR_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,236 @@
+use scraper::{Html, Selector};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::clips::{self, ClipData, ClipUpdate};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::http;
+use crate::jobs::{Job, JobKind, JobQueue};
+use crate::search::BROWSER_USER_AGENT;
+use crate::settings::SettingsManager;
+
+/// Transcript lines are grouped into paragraphs covering about this many seconds
+const PARAGRAPH_SECONDS: f64 = 60.0;
+
+/// Captions fetched for a video clip
+#[derive(Debug, Serialize)]
+pub struct VideoTranscript {
+    pub clip_id: i64,
+    pub video_id: String,
+    /// Caption track language, e.g. `en`
+    pub language: String,
+    /// YouTube's speech recognition captions rather than ones uploaded by the author
+    pub auto_generated: bool,
+    pub word_count: usize,
+}
+
+/// The video id of a YouTube watch, short, shorts or embed link
+pub fn youtube_video_id(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?.to_lowercase();
+    let host = host.strip_prefix("www.").or_else(|| host.strip_prefix("m.")).unwrap_or(&host);
+    let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+    let id = match host {
+        "youtu.be" => segments.next().map(str::to_string),
+        "youtube.com" | "music.youtube.com" | "youtube-nocookie.com" => match segments.next() {
+            Some("watch") => url.query_pairs().find(|(key, _)| key == "v").map(|(_, v)| v.into_owned()),
+            Some("shorts" | "embed" | "live" | "v") => segments.next().map(str::to_string),
+            _ => None,
+        },
+        _ => None,
+    }?;
+    // Ids are 11 characters of base64url
+    let valid = id.len() == 11 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(id)
+}
+
+/// Turn a clip of a YouTube link into a `video` clip with the video's thumbnail.
+/// The transcript is fetched afterwards by a `FetchTranscript` job.
+pub fn classify(mut clip_data: ClipData) -> ClipData {
+    if !matches!(clip_data.r#type.as_str(), "url" | "article" | "video") {
+        return clip_data;
+    }
+    let Some(video_id) = clip_data.url.as_deref().and_then(youtube_video_id) else {
+        return clip_data;
+    };
+    clip_data.r#type = "video".to_string();
+    if clip_data.image_url.is_none() {
+        clip_data.image_url = Some(format!("https://i.ytimg.com/vi/{}/hqdefault.jpg", video_id));
+    }
+    clip_data
+}
+
+/// Queue a transcript fetch for a video clip
+pub fn queue_transcript(app_handle: &AppHandle, clip_id: i64) -> Result<Job, AppError> {
+    {
+        let db = app_handle.state::<Database>();
+        let clip = clips::get_clip(&db.conn()?, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+        if clip.r#type != "video" {
+            return Err(AppError::validation(format!("Clip {} is not a video", clip_id)));
+        }
+    }
+    app_handle.state::<JobQueue>().submit(app_handle, JobKind::FetchTranscript { clip_id })
+}
+
+/// The `ytInitialPlayerResponse` object embedded in a watch page
+fn player_response(html: &str) -> Option<Value> {
+    let start = html.find("ytInitialPlayerResponse = ")? + "ytInitialPlayerResponse = ".len();
+    serde_json::Deserializer::from_str(&html[start..]).into_iter::<Value>().next()?.ok()
+}
+
+/// Author-provided English captions first, then any English, then whatever the video has
+fn pick_track(tracks: &[Value]) -> Option<&Value> {
+    let is_english = |track: &&Value| track["languageCode"].as_str().is_some_and(|code| code.starts_with("en"));
+    let is_manual = |track: &&Value| track["kind"].as_str() != Some("asr");
+    tracks
+        .iter()
+        .find(|track| is_english(track) && is_manual(track))
+        .or_else(|| tracks.iter().find(is_english))
+        .or_else(|| tracks.iter().find(is_manual))
+        .or_else(|| tracks.first())
+}
+
+/// Caption text is HTML-escaped twice; the parser undoes one level
+fn unescape(text: &str) -> String {
+    text.replace("&#39;", "'")
+        .replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn timestamp(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Timed-text XML (`<transcript><text start dur>...</text>`) as paragraphs of about
+/// `PARAGRAPH_SECONDS`, each starting with its timestamp
+fn parse_transcript(xml: &str) -> String {
+    let document = Html::parse_fragment(xml);
+    let lines = Selector::parse("text").expect("static selector is valid");
+
+    let mut paragraphs: Vec<(f64, Vec<String>)> = Vec::new();
+    for line in document.select(&lines) {
+        let start = line.value().attr("start").and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0);
+        let text = unescape(&line.text().collect::<String>());
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            continue;
+        }
+        match paragraphs.last_mut() {
+            Some((paragraph_start, words)) if start - *paragraph_start < PARAGRAPH_SECONDS => words.push(text),
+            _ => paragraphs.push((start, vec![text])),
+        }
+    }
+    paragraphs
+        .into_iter()
+        .map(|(start, lines)| format!("[{}] {}", timestamp(start), lines.join(" ")))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+async fn get_text(client: &reqwest::Client, url: &str) -> Result<String, AppError> {
+    let response = client
+        .get(url)
+        .header("User-Agent", BROWSER_USER_AGENT)
+        .header("Accept-Language", "en")
+        // Skip the EU cookie consent interstitial
+        .header("Cookie", "CONSENT=YES+1")
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to reach YouTube: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("YouTube returned HTTP {}", response.status())));
+    }
+    response
+        .text()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to read YouTube response: {}", e)))
+}
+
+/// Fetch the captions of a video clip and save them as its content. The title,
+/// author and description are filled in from the video where the clip lacks them.
+pub async fn fetch_transcript(app_handle: &AppHandle, clip_id: i64) -> Result<VideoTranscript, AppError> {
+    let clip = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?
+    };
+    let video_id = clip
+        .url
+        .as_deref()
+        .and_then(youtube_video_id)
+        .ok_or_else(|| AppError::validation(format!("Clip {} is not a YouTube video", clip_id)))?;
+
+    let client = http::client();
+    let page = get_text(&client, &format!("https://www.youtube.com/watch?v={}&hl=en", video_id)).await?;
+    let player = player_response(&page).ok_or_else(|| AppError::network("YouTube returned a page without video details"))?;
+    if let Some(reason) = player["playabilityStatus"]["reason"].as_str() {
+        if player["playabilityStatus"]["status"].as_str() != Some("OK") {
+            return Err(AppError::validation(format!("Video unavailable: {}", reason)));
+        }
+    }
+
+    let tracks = player["captions"]["playerCaptionsTracklistRenderer"]["captionTracks"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let track = pick_track(&tracks).ok_or_else(|| AppError::not_found("This video has no captions"))?;
+    let track_url = track["baseUrl"]
+        .as_str()
+        .ok_or_else(|| AppError::network("Caption track has no URL"))?;
+    let transcript = parse_transcript(&get_text(&client, track_url).await?);
+    if transcript.is_empty() {
+        return Err(AppError::not_found("The video's captions are empty"));
+    }
+
+    let details = &player["videoDetails"];
+    let present = |value: &Option<String>| value.as_deref().is_some_and(|v| !v.trim().is_empty());
+    let title_is_placeholder = clip.title.trim().is_empty() || Some(clip.title.trim()) == clip.url.as_deref();
+    let changes = ClipUpdate {
+        title: details["title"].as_str().filter(|_| title_is_placeholder).map(str::to_string),
+        content: Some(Some(transcript.clone())),
+        author: details["author"]
+            .as_str()
+            .filter(|_| !present(&clip.author))
+            .map(|author| Some(author.to_string())),
+        description: details["shortDescription"]
+            .as_str()
+            .filter(|description| !description.trim().is_empty() && !present(&clip.description))
+            .map(|description| Some(description.to_string())),
+        ..Default::default()
+    };
+
+    let updated = {
+        let db = app_handle.state::<Database>();
+        clips::update_clip(&db.conn()?, clip_id, changes)?
+    };
+    events::emit(app_handle, "clip-updated", &updated)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+
+    // Tags and embeddings queued at ingest only saw the title; redo them with the transcript
+    let settings = app_handle.state::<SettingsManager>().get();
+    let queue = app_handle.state::<JobQueue>();
+    if settings.auto_tag {
+        queue.submit(app_handle, JobKind::AutoTagClip { clip_id })?;
+    }
+    if settings.embedding_model.is_some() {
+        queue.submit(app_handle, JobKind::EmbedClips { clip_ids: vec![clip_id] })?;
+    }
+
+    Ok(VideoTranscript {
+        clip_id,
+        video_id,
+        language: track["languageCode"].as_str().unwrap_or_default().to_string(),
+        auto_generated: track["kind"].as_str() == Some("asr"),
+        word_count: transcript.split_whitespace().count(),
+    })
+}
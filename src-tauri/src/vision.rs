@@ -0,0 +1,126 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageFormat, ImageOutputFormat};
+use serde::Deserialize;
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::config::AppConfig;
+use crate::errors::AppError;
+use crate::media;
+use crate::providers::EncodedImage;
+
+/// Images per request; providers cap this too (Anthropic allows 20, some models 1)
+const MAX_IMAGES: usize = 5;
+/// Longest edge sent to the model. Larger images are scaled down: providers resize
+/// them anyway and the upload would only cost time and tokens.
+const MAX_DIMENSION: u32 = 1568;
+/// Re-encode anything larger than this even if its dimensions are fine
+const MAX_UPLOAD_BYTES: usize = 3 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 85;
+
+/// An image attached to a `call_llm` request
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ImageAttachment {
+    /// Raw base64 or a `data:` URL, e.g. a pasted screenshot
+    Base64 { data: String },
+    /// A file inside the app's media folder
+    Path { path: PathBuf },
+    /// An image clip's stored image, downloaded first if needed
+    Clip { clip_id: i64 },
+}
+
+/// The image bytes an attachment refers to
+async fn load(app_handle: &AppHandle, attachment: ImageAttachment) -> Result<Vec<u8>, AppError> {
+    match attachment {
+        ImageAttachment::Base64 { data } => {
+            let data = match data.strip_prefix("data:") {
+                Some(url) => url.split_once(',').map(|(_, data)| data).unwrap_or_default(),
+                None => data.as_str(),
+            };
+            BASE64
+                .decode(data.trim())
+                .map_err(|e| AppError::validation(format!("Invalid base64 image: {}", e)))
+        }
+        ImageAttachment::Path { path } => {
+            let media_dir = app_handle.state::<AppConfig>().media_dir();
+            // Only the media folder is readable this way, not arbitrary files
+            let path = fs::canonicalize(&path).map_err(|e| AppError::not_found(format!("Image not found: {}", e)))?;
+            let media_dir = fs::canonicalize(&media_dir).map_err(|e| AppError::internal(format!("Media folder missing: {}", e)))?;
+            if !path.starts_with(&media_dir) {
+                return Err(AppError::validation("Images must come from the media folder"));
+            }
+            fs::read(&path).map_err(|e| AppError::internal(format!("Failed to read image: {}", e)))
+        }
+        ImageAttachment::Clip { clip_id } => {
+            let image = media::get_clip_image(app_handle, clip_id).await?;
+            fs::read(&image.path).map_err(|e| AppError::internal(format!("Failed to read image: {}", e)))
+        }
+    }
+}
+
+/// Scale `bytes` down to `MAX_DIMENSION` if needed and base64-encode it. Images that
+/// are already small enough in a format every provider accepts are sent unchanged.
+fn encode(bytes: Vec<u8>) -> Result<EncodedImage, AppError> {
+    let format = image::guess_format(&bytes).map_err(|_| AppError::validation("Unrecognized image format"))?;
+    let passthrough = match format {
+        ImageFormat::Png => Some("image/png"),
+        ImageFormat::Jpeg => Some("image/jpeg"),
+        ImageFormat::Gif => Some("image/gif"),
+        ImageFormat::WebP => Some("image/webp"),
+        _ => None,
+    };
+
+    let decoded = image::load_from_memory(&bytes).map_err(|e| AppError::validation(format!("Failed to decode image: {}", e)))?;
+    let (width, height) = decoded.dimensions();
+    let small = width.max(height) <= MAX_DIMENSION && bytes.len() <= MAX_UPLOAD_BYTES;
+    if let (true, Some(media_type)) = (small, passthrough) {
+        return Ok(EncodedImage {
+            media_type: media_type.to_string(),
+            data: BASE64.encode(&bytes),
+        });
+    }
+
+    let resized = if width.max(height) > MAX_DIMENSION {
+        decoded.resize(MAX_DIMENSION, MAX_DIMENSION, FilterType::Lanczos3)
+    } else {
+        decoded
+    };
+    // Screenshots with transparency stay PNG; everything else becomes a JPEG
+    let (output, media_type, image) = if resized.color().has_alpha() {
+        (ImageOutputFormat::Png, "image/png", resized)
+    } else {
+        (
+            ImageOutputFormat::Jpeg(JPEG_QUALITY),
+            "image/jpeg",
+            DynamicImage::ImageRgb8(resized.to_rgb8()),
+        )
+    };
+    let mut encoded = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut encoded), output)
+        .map_err(|e| AppError::internal(format!("Failed to encode image: {}", e)))?;
+    Ok(EncodedImage {
+        media_type: media_type.to_string(),
+        data: BASE64.encode(&encoded),
+    })
+}
+
+/// Load, downscale and encode attachments for a vision model request
+pub async fn prepare_images(app_handle: &AppHandle, attachments: Vec<ImageAttachment>) -> Result<Vec<EncodedImage>, AppError> {
+    if attachments.len() > MAX_IMAGES {
+        return Err(AppError::validation(format!("At most {} images can be attached", MAX_IMAGES)));
+    }
+    let mut images = Vec::with_capacity(attachments.len());
+    for attachment in attachments {
+        let bytes = load(app_handle, attachment).await?;
+        let image = tauri::async_runtime::spawn_blocking(move || encode(bytes))
+            .await
+            .map_err(|e| AppError::internal(format!("Image encoding failed: {}", e)))??;
+        images.push(image);
+    }
+    Ok(images)
+}
@@ -0,0 +1,211 @@
+use notify::event::{AccessKind, AccessMode, EventKind, ModifyKind, RenameMode};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+use crate::errors::AppError;
+use crate::pairing::SignedClip;
+
+/// How long a file must go without new events before we read it, unless configured
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(150);
+/// A truncated file changed more recently than this is taken to be still being written
+const INCOMPLETE_GRACE: Duration = Duration::from_secs(60);
+
+type EventResult = notify::Result<notify::Event>;
+type ClipHandler = Arc<dyn Fn(Result<SignedClip, UnreadableClipFile>) + Send + Sync>;
+
+/// A clip file in the drop folder that isn't a signed clip; it's removed once handled
+pub struct UnreadableClipFile {
+    pub content: String,
+    pub reason: String,
+}
+
+/// Watches the clips drop folder for signed clip files written by the browser extension
+pub struct ClipWatcher {
+    /// Changed by `set_clips_dir` when another profile is switched to
+    clips_dir: Mutex<PathBuf>,
+    on_clip: ClipHandler,
+    /// Milliseconds, read on every loop so a settings change applies immediately
+    debounce_ms: Arc<AtomicU64>,
+    running: Mutex<Option<(RecommendedWatcher, JoinHandle<()>)>>,
+}
+
+impl ClipWatcher {
+    /// Start watching `clips_dir`, calling `on_clip` for every complete clip file, or
+    /// with the file's content if it isn't a signed clip. Files already in the folder
+    /// are picked up immediately.
+    pub fn start<F>(clips_dir: PathBuf, debounce: Duration, on_clip: F) -> Result<Self, AppError>
+    where
+        F: Fn(Result<SignedClip, UnreadableClipFile>) + Send + Sync + 'static,
+    {
+        let watcher = Self {
+            clips_dir: Mutex::new(clips_dir),
+            on_clip: Arc::new(on_clip),
+            debounce_ms: Arc::new(AtomicU64::new(debounce.as_millis() as u64)),
+            running: Mutex::new(None),
+        };
+        watcher.resume()?;
+        Ok(watcher)
+    }
+
+    /// Start watching again after `shutdown`. Files dropped in the meantime are
+    /// picked up, so pausing delays clips rather than losing them.
+    pub fn resume(&self) -> Result<(), AppError> {
+        let mut running = self.running.lock().unwrap();
+        if running.is_some() {
+            return Ok(());
+        }
+
+        let clips_dir = self.clips_dir.lock().unwrap().clone();
+        let on_clip = self.on_clip.clone();
+        let debounce_ms = self.debounce_ms.clone();
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |res: EventResult| {
+            let _ = tx.send(res);
+        })
+        .map_err(|e| AppError::internal(format!("Failed to create file watcher: {}", e)))?;
+        watcher
+            .watch(&clips_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| AppError::internal(format!("Failed to watch {}: {}", clips_dir.display(), e)))?;
+
+        let handle = std::thread::spawn(move || run(&clips_dir, rx, &debounce_ms, &*on_clip));
+
+        *running = Some((watcher, handle));
+        Ok(())
+    }
+
+    /// Watch `clips_dir` instead, restarting the watcher if it's running
+    pub fn set_clips_dir(&self, clips_dir: PathBuf) -> Result<(), AppError> {
+        let was_running = self.is_running();
+        self.shutdown();
+        *self.clips_dir.lock().unwrap() = clips_dir;
+        if was_running {
+            self.resume()?;
+        }
+        Ok(())
+    }
+
+    pub fn set_debounce(&self, debounce: Duration) {
+        self.debounce_ms.store(debounce.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.lock().unwrap().is_some()
+    }
+
+    /// Stop watching and wait for in-flight files to finish processing
+    pub fn shutdown(&self) {
+        if let Some((watcher, handle)) = self.running.lock().unwrap().take() {
+            // Dropping the watcher closes the event channel, which ends the worker loop
+            drop(watcher);
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Events that can mean a clip file is (now) complete
+fn is_relevant(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_)
+            | EventKind::Modify(ModifyKind::Data(_))
+            | EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Both | RenameMode::Any))
+            | EventKind::Access(AccessKind::Close(AccessMode::Write))
+    )
+}
+
+/// Only `*.json` files count; writers use a temp name (`.foo.json`, `foo.json.part`)
+/// and rename into place when done
+fn is_clip_file(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with('.'))
+        .unwrap_or(true);
+    !hidden && path.extension().map(|ext| ext == "json").unwrap_or(false)
+}
+
+fn run<F: Fn(Result<SignedClip, UnreadableClipFile>)>(
+    clips_dir: &Path,
+    events: mpsc::Receiver<EventResult>,
+    debounce_ms: &AtomicU64,
+    on_clip: F,
+) {
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+
+    // Pick up anything dropped while the app wasn't running
+    if let Ok(entries) = fs::read_dir(clips_dir) {
+        for entry in entries.flatten() {
+            pending.insert(entry.path(), Instant::now());
+        }
+    }
+
+    loop {
+        let debounce = Duration::from_millis(debounce_ms.load(Ordering::Relaxed));
+        match events.recv_timeout(debounce) {
+            Ok(Ok(event)) => {
+                if is_relevant(&event.kind) {
+                    for path in event.paths {
+                        pending.insert(path, Instant::now());
+                    }
+                }
+            }
+            Ok(Err(e)) => error!("File watcher error: {}", e),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        let ready: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, last_event)| last_event.elapsed() >= debounce)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in ready {
+            pending.remove(&path);
+            process_file(&path, &on_clip);
+        }
+    }
+}
+
+fn process_file<F: Fn(Result<SignedClip, UnreadableClipFile>)>(path: &Path, on_clip: &F) {
+    if !is_clip_file(path) || !path.is_file() {
+        return;
+    }
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            error!("Failed to read clip file {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    match serde_json::from_str::<SignedClip>(&content) {
+        Ok(signed) => on_clip(Ok(signed)),
+        // Most likely still being written in place; the next modify event retries it
+        Err(e) if e.is_eof() && recently_modified(path) => {
+            warn!("Skipping incomplete clip file {}: {}", path.display(), e);
+            return;
+        }
+        // Left in place it would come up again on every scan
+        Err(e) => on_clip(Err(UnreadableClipFile {
+            content,
+            reason: format!("Not a signed clip file: {}", e),
+        })),
+    }
+    let _ = fs::remove_file(path);
+}
+
+fn recently_modified(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < INCOMPLETE_GRACE)
+}
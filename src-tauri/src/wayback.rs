@@ -0,0 +1,153 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+use crate::clips::{self, ClipUpdate, SqliteClip};
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::events;
+use crate::extract;
+use crate::http;
+use crate::jobs::{JobKind, JobQueue};
+use crate::settings::SettingsManager;
+
+const AVAILABILITY_API: &str = "https://archive.org/wayback/available";
+const SAVE_ENDPOINT: &str = "https://web.archive.org/save/";
+/// Saving makes the Internet Archive crawl the page first, which can take a while
+const SAVE_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Deserialize)]
+struct Availability {
+    archived_snapshots: ArchivedSnapshots,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArchivedSnapshots {
+    closest: Option<Snapshot>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Snapshot {
+    available: bool,
+    url: String,
+    /// `yyyyMMddhhmmss`
+    timestamp: String,
+    status: String,
+}
+
+/// Result of `recover_from_wayback`
+#[derive(Debug, Serialize)]
+pub struct WaybackRecovery {
+    pub clip: SqliteClip,
+    /// The snapshot on web.archive.org the content came from
+    pub snapshot_url: String,
+    /// When the snapshot was taken, as `yyyyMMddhhmmss`
+    pub snapshot_timestamp: String,
+}
+
+/// The snapshot of `url` closest to `timestamp` (`yyyyMMddhhmmss`), if the page was archived
+async fn closest_snapshot(url: &str, timestamp: &str) -> Result<Option<Snapshot>, AppError> {
+    let response = http::client()
+        .get(AVAILABILITY_API)
+        .query(&[("url", url), ("timestamp", timestamp)])
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to reach the Wayback Machine: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("Wayback Machine lookup failed: HTTP {}", response.status())));
+    }
+    let availability: Availability = response
+        .json()
+        .await
+        .map_err(|e| AppError::network(format!("Invalid Wayback Machine response: {}", e)))?;
+    Ok(availability
+        .archived_snapshots
+        .closest
+        .filter(|snapshot| snapshot.available && snapshot.status.starts_with('2')))
+}
+
+/// Replace a clip's content with the text of the Wayback Machine snapshot taken
+/// closest to when it was clipped, for pages that have since gone away
+pub async fn recover_from_wayback(app_handle: &AppHandle, clip_id: i64) -> Result<WaybackRecovery, AppError> {
+    let (clip, timestamp) = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        let clip = clips::get_clip(&conn, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?;
+        let timestamp: String = conn
+            .query_row(
+                "SELECT strftime('%Y%m%d%H%M%S', ?1 / 1000, 'unixepoch')",
+                params![clip.timestamp],
+                |row| row.get(0),
+            )
+            .map_err(|e| AppError::database(format!("Failed to format clip time: {}", e)))?;
+        (clip, timestamp)
+    };
+    let url = clip
+        .url
+        .clone()
+        .ok_or_else(|| AppError::validation(format!("Clip {} has no URL to recover", clip_id)))?;
+
+    let snapshot = closest_snapshot(&url, &timestamp)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("The Wayback Machine has no copy of {}", url)))?;
+    // The `id_` flag serves the page as captured, without the archive's toolbar and rewritten links
+    let raw_url = format!("https://web.archive.org/web/{}id_/{}", snapshot.timestamp, url);
+    let article = extract::fetch_article(&raw_url).await?;
+    if article.content.trim().is_empty() {
+        return Err(AppError::not_found(format!("The Wayback Machine copy of {} has no readable text", url)));
+    }
+
+    let updated = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::update_clip(
+            &conn,
+            clip_id,
+            ClipUpdate {
+                content: Some(Some(article.content)),
+                description: clip.description.is_none().then_some(article.excerpt),
+                ..Default::default()
+            },
+        )?
+    };
+    events::emit(app_handle, "clip-updated", &updated)
+        .map_err(|e| AppError::internal(format!("Failed to emit clip event: {}", e)))?;
+    if app_handle.state::<SettingsManager>().get().embedding_model.is_some() {
+        app_handle
+            .state::<JobQueue>()
+            .submit(app_handle, JobKind::EmbedClips { clip_ids: vec![clip_id] })?;
+    }
+
+    Ok(WaybackRecovery {
+        clip: updated,
+        snapshot_url: snapshot.url,
+        snapshot_timestamp: snapshot.timestamp,
+    })
+}
+
+/// Ask the Wayback Machine to capture the clip's page now
+pub async fn save_to_wayback(app_handle: &AppHandle, clip_id: i64) -> Result<(), AppError> {
+    let url = {
+        let db = app_handle.state::<Database>();
+        let conn = db.conn()?;
+        clips::get_clip(&conn, clip_id)?
+            .ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?
+            .url
+            .ok_or_else(|| AppError::validation(format!("Clip {} has no URL to archive", clip_id)))?
+    };
+    let response = http::builder()?
+        .timeout(SAVE_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::internal(format!("Failed to build HTTP client: {}", e)))?
+        .get(format!("{}{}", SAVE_ENDPOINT, url))
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("Failed to reach the Wayback Machine: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("Wayback Machine save failed: HTTP {}", response.status())));
+    }
+    info!("Saved {} to the Wayback Machine at {}", url, response.url());
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tracing::{error, warn};
+
+use crate::app_lock::AppLock;
+use crate::clips;
+use crate::db::Database;
+use crate::errors::AppError;
+use crate::merge;
+
+/// URL scheme registered with the OS; `los://clip/<id>` opens that clip in a reader window
+pub const DEEP_LINK_SCHEME: &str = "los";
+const CAPTURE_WINDOW: &str = "capture";
+
+/// The clip id of a `los://clip/<id>` link
+fn parse_clip_link(link: &str) -> Option<i64> {
+    let url = reqwest::Url::parse(link).ok()?;
+    if url.scheme() != DEEP_LINK_SCHEME || url.host_str() != Some("clip") {
+        return None;
+    }
+    url.path().trim_matches('/').parse().ok()
+}
+
+fn focus(window: &WebviewWindow) {
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+}
+
+/// Show the clip in its own reader window, focusing the existing one if it's open. A
+/// clip that was merged away opens the clip it was merged into.
+pub fn open_reader_window(app_handle: &AppHandle, clip_id: i64) -> Result<(), AppError> {
+    app_handle.state::<AppLock>().check()?;
+    let clip_id = merge::resolve_redirect(&app_handle.state::<Database>().conn()?, clip_id)?;
+    let label = format!("reader-{}", clip_id);
+    if let Some(window) = app_handle.get_webview_window(&label) {
+        focus(&window);
+        return Ok(());
+    }
+    let clip = {
+        let db = app_handle.state::<Database>();
+        clips::get_clip(&db.conn()?, clip_id)?.ok_or_else(|| AppError::not_found(format!("Clip {} not found", clip_id)))?
+    };
+    // The frontend picks the view from the URL fragment
+    WebviewWindowBuilder::new(app_handle, &label, WebviewUrl::App(format!("index.html#reader/{}", clip_id).into()))
+        .title(&clip.title)
+        .inner_size(760.0, 900.0)
+        .min_inner_size(400.0, 300.0)
+        .build()
+        .map_err(|e| AppError::internal(format!("Failed to open reader window: {}", e)))?;
+    Ok(())
+}
+
+/// Show the small always-on-top window for jotting down a note or pasting a link
+pub fn open_capture_window(app_handle: &AppHandle) -> Result<(), AppError> {
+    if let Some(window) = app_handle.get_webview_window(CAPTURE_WINDOW) {
+        focus(&window);
+        return Ok(());
+    }
+    WebviewWindowBuilder::new(app_handle, CAPTURE_WINDOW, WebviewUrl::App("index.html#capture".into()))
+        .title("Quick capture")
+        .inner_size(420.0, 240.0)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .build()
+        .map_err(|e| AppError::internal(format!("Failed to open capture window: {}", e)))?;
+    Ok(())
+}
+
+fn handle_deep_links<'a>(app_handle: &AppHandle, links: impl IntoIterator<Item = &'a str>) {
+    for link in links {
+        match parse_clip_link(link) {
+            Some(clip_id) => {
+                if let Err(e) = open_reader_window(app_handle, clip_id) {
+                    error!("Failed to open {}: {}", link, e);
+                }
+            }
+            None => warn!("Ignoring unknown link {}", link),
+        }
+    }
+}
+
+/// Open clips for deep links, both the one the app was launched with and any that
+/// arrive while it runs. Links opened while another instance runs are forwarded to
+/// it by the single-instance plugin.
+pub fn setup_deep_links(app_handle: &AppHandle) {
+    // Linux and Windows dev builds register the scheme at runtime; installers do it otherwise
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(e) = app_handle.deep_link().register_all() {
+        warn!("Failed to register the {}:// scheme: {}", DEEP_LINK_SCHEME, e);
+    }
+
+    let handle = app_handle.clone();
+    app_handle.deep_link().on_open_url(move |event| {
+        let urls = event.urls();
+        handle_deep_links(&handle, urls.iter().map(|url| url.as_str()));
+    });
+    match app_handle.deep_link().get_current() {
+        Ok(Some(urls)) => handle_deep_links(app_handle, urls.iter().map(|url| url.as_str())),
+        Ok(None) => {}
+        Err(e) => warn!("Failed to read the launch link: {}", e),
+    }
+}
+
+/// Whether a second launch's arguments carry a deep link, which `setup_deep_links` handles
+pub fn has_deep_link(args: &[String]) -> bool {
+    let prefix = format!("{}://", DEEP_LINK_SCHEME);
+    args.iter().any(|arg| arg.starts_with(&prefix))
+}